@@ -0,0 +1,32 @@
+import {performance} from 'node:perf_hooks';
+import {randomBytes} from 'node:crypto';
+
+import {base64Encode, base64Decode, hexEncode, hexDecode} from '../index.js';
+
+const SIZE = 16 * 1024 * 1024;
+const ROUNDS = 10;
+
+function bench(name, fn)
+{
+    fn();
+    const start = performance.now();
+    for (let i = 0; i < ROUNDS; i++)
+    {
+        fn();
+    }
+    const ms = (performance.now() - start) / ROUNDS;
+    console.log(`${name.padEnd(28)} ${ms.toFixed(2)} ms/op`);
+}
+
+const data = randomBytes(SIZE);
+const b64 = data.toString('base64');
+const hex = data.toString('hex');
+
+bench('Buffer base64 encode', () => data.toString('base64'));
+bench('native base64Encode', () => base64Encode(data));
+bench('Buffer base64 decode', () => Buffer.from(b64, 'base64'));
+bench('native base64Decode', () => base64Decode(b64));
+bench('Buffer hex encode', () => data.toString('hex'));
+bench('native hexEncode', () => hexEncode(data));
+bench('Buffer hex decode', () => Buffer.from(hex, 'hex'));
+bench('native hexDecode', () => hexDecode(hex));
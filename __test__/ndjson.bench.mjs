@@ -0,0 +1,57 @@
+import {performance} from 'node:perf_hooks';
+import {createInterface} from 'node:readline';
+import {Readable} from 'node:stream';
+
+import {NdjsonParser, ndjsonStringify} from '../index.js';
+
+const RECORDS = 200_000;
+const CHUNK = 64 * 1024;
+const ROUNDS = 5;
+
+async function bench(name, fn)
+{
+    await fn();
+    const start = performance.now();
+    for (let i = 0; i < ROUNDS; i++)
+    {
+        await fn();
+    }
+    const ms = (performance.now() - start) / ROUNDS;
+    console.log(`${name.padEnd(28)} ${ms.toFixed(2)} ms/op`);
+}
+
+const records = Array.from({length: RECORDS}, (_, i) => ({
+    time: 1700000000000 + i,
+    level: i % 10 === 0 ? 'warn' : 'info',
+    msg: `request ${i} handled`,
+    req: {method: 'GET', url: `/items/${i}`, status: 200},
+}));
+const input = ndjsonStringify(records);
+const chunks = [];
+for (let i = 0; i < input.length; i += CHUNK)
+{
+    chunks.push(input.subarray(i, i + CHUNK));
+}
+
+bench('readline + JSON.parse', async () =>
+{
+    let count = 0;
+    for await (const line of createInterface({input: Readable.from(chunks), crlfDelay: Infinity}))
+    {
+        if (line)
+        {
+            JSON.parse(line);
+            count++;
+        }
+    }
+    return count;
+}).then(() => bench('NdjsonParser.push', async () =>
+{
+    const parser = new NdjsonParser();
+    let count = 0;
+    for await (const chunk of Readable.from(chunks))
+    {
+        count += parser.push(chunk).length;
+    }
+    return count + parser.end().length;
+}));
@@ -0,0 +1,37 @@
+import {performance} from 'node:perf_hooks';
+import {deserialize, serialize} from 'node:v8';
+
+import {pack, unpack} from '../index.js';
+
+const ROUNDS = 20;
+
+function bench(name, fn)
+{
+    fn();
+    const start = performance.now();
+    for (let i = 0; i < ROUNDS; i++)
+    {
+        fn();
+    }
+    const ms = (performance.now() - start) / ROUNDS;
+    console.log(`${name.padEnd(28)} ${ms.toFixed(2)} ms/op`);
+}
+
+const value = Array.from({length: 20_000}, (_, i) => ({
+    id: i,
+    name: `item ${i}`,
+    price: i * 1.25,
+    tags: ['a', 'b', 'c'],
+    active: i % 2 === 0,
+}));
+const json = Buffer.from(JSON.stringify(value));
+const packed = pack(value);
+const cloned = serialize(value);
+console.log(`sizes: json ${json.length}, pack ${packed.length}, v8 ${cloned.length} bytes`);
+
+bench('JSON.stringify', () => Buffer.from(JSON.stringify(value)));
+bench('v8.serialize', () => serialize(value));
+bench('native pack', () => pack(value));
+bench('JSON.parse', () => JSON.parse(json.toString()));
+bench('v8.deserialize', () => deserialize(cloned));
+bench('native unpack', () => unpack(packed));
@@ -0,0 +1,26 @@
+import {performance} from 'node:perf_hooks';
+
+import {parallelMapSquares, parallelSum} from '../index.js';
+
+const SIZE = 5_000_000;
+const ROUNDS = 5;
+
+async function bench(name, fn)
+{
+    await fn();
+    const start = performance.now();
+    for (let i = 0; i < ROUNDS; i++)
+    {
+        await fn();
+    }
+    const ms = (performance.now() - start) / ROUNDS;
+    console.log(`${name.padEnd(28)} ${ms.toFixed(2)} ms/op`);
+}
+
+const array = Array.from({length: SIZE}, () => Math.random());
+const typed = Float64Array.from(array);
+
+await bench('js map squares', () => array.map((x) => x * x));
+await bench('native parallelMapSquares', () => parallelMapSquares(array));
+await bench('js sum', () => typed.reduce((acc, x) => acc + x, 0));
+await bench('native parallelSum', () => parallelSum(typed));
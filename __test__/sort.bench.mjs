@@ -0,0 +1,33 @@
+import {performance} from 'node:perf_hooks';
+
+import {argsort, dedupStrings, sortF64, topK} from '../index.js';
+
+const SIZE = 1_000_000;
+const ROUNDS = 5;
+
+function bench(name, setup, fn)
+{
+    fn(setup());
+    let ms = 0;
+    for (let i = 0; i < ROUNDS; i++)
+    {
+        const input = setup();
+        const start = performance.now();
+        fn(input);
+        ms += performance.now() - start;
+    }
+    console.log(`${name.padEnd(28)} ${(ms / ROUNDS).toFixed(2)} ms/op`);
+}
+
+const source = Float64Array.from({length: SIZE}, () => Math.random());
+const words = Array.from({length: SIZE}, (_, i) => `word-${i % 50_000}`);
+
+bench('js Array sort', () => Array.from(source), (array) => array.sort((a, b) => a - b));
+bench('js Float64Array sort', () => Float64Array.from(source), (typed) => typed.sort());
+bench('native sortF64', () => Float64Array.from(source), (typed) => sortF64(typed));
+bench('js argsort', () => source, (typed) => Array.from(typed.keys()).sort((a, b) => typed[a] - typed[b]));
+bench('native argsort', () => source, (typed) => argsort(typed));
+bench('js top 100', () => source, (typed) => Float64Array.from(typed).sort().subarray(-100).reverse());
+bench('native topK', () => source, (typed) => topK(typed, 100));
+bench('js Set dedup', () => words, (list) => [...new Set(list)]);
+bench('native dedupStrings', () => words, (list) => dedupStrings(list));
@@ -1,5 +1,157 @@
 extern crate napi_build;
 
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Collects `file:line` for every `#[napi]` function or method under `dir`
+/// declared without `catch_unwind`, and for every `AsyncTask` created other
+/// than through `panic_guard::task`. See `src/panic_guard.rs`.
+fn unguarded(dir: &Path, found: &mut Vec<String>, tasks: &mut Vec<String>) {
+  let mut entries: Vec<_> = fs::read_dir(dir)
+    .expect("read src")
+    .map(|e| e.expect("read src entry").path())
+    .collect();
+  entries.sort();
+  for path in entries {
+    if path.is_dir() {
+      unguarded(&path, found, tasks);
+      continue;
+    }
+    if path.extension().is_none_or(|ext| ext != "rs") {
+      continue;
+    }
+    let source = fs::read_to_string(&path).expect("read source file");
+    let lines: Vec<&str> = source.lines().collect();
+    if !path.ends_with("panic_guard.rs") {
+      for (n, line) in lines.iter().enumerate() {
+        if line.contains("AsyncTask::new") || line.contains("AsyncTask::with_") {
+          tasks.push(format!("{}:{}", path.display(), n + 1));
+        }
+      }
+    }
+    let mut i = 0;
+    while i < lines.len() {
+      if !lines[i].trim_start().starts_with("#[napi") {
+        i += 1;
+        continue;
+      }
+      let start = i;
+      let mut attribute = String::new();
+      let mut depth = 0i32;
+      loop {
+        attribute.push_str(lines[i]);
+        depth += lines[i].matches('[').count() as i32 - lines[i].matches(']').count() as i32;
+        i += 1;
+        if depth <= 0 || i == lines.len() {
+          break;
+        }
+      }
+      // The item the attribute applies to, past doc comments and other
+      // attributes.
+      let item = lines[i..].iter().map(|l| l.trim()).find(|l| {
+        !l.is_empty() && !l.starts_with("//") && !l.starts_with("#[") && !l.starts_with(')')
+      });
+      let is_fn = item.is_some_and(|item| {
+        let item = item.strip_prefix("pub ").unwrap_or(item);
+        let item = item.strip_prefix("async ").unwrap_or(item);
+        item.starts_with("fn ")
+      });
+      if is_fn && !attribute.contains("catch_unwind") {
+        found.push(format!("{}:{}", path.display(), start + 1));
+      }
+    }
+  }
+}
+
+/// The first line `program args` prints, or an empty string when it can't
+/// run, e.g. a source tarball built without git.
+fn first_line(program: &str, args: &[&str]) -> String {
+  Command::new(program)
+    .args(args)
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+    .and_then(|stdout| stdout.lines().next().map(|line| line.trim().to_string()))
+    .unwrap_or_default()
+}
+
+/// Passes what `nativeInfo()` reports about the build to the compiler. See
+/// `src/introspect.rs`.
+fn build_info() {
+  // Rebuild on a new commit or checkout, but not when git isn't in use:
+  // watching a missing path reruns the script on every build.
+  for path in [".git/HEAD", ".git/packed-refs"] {
+    if Path::new(path).exists() {
+      println!("cargo:rerun-if-changed={}", path);
+    }
+  }
+  if let Some(head) = fs::read_to_string(".git/HEAD")
+    .ok()
+    .and_then(|head| head.strip_prefix("ref: ").map(|r| r.trim().to_string()))
+  {
+    let branch = Path::new(".git").join(head);
+    if branch.exists() {
+      println!("cargo:rerun-if-changed={}", branch.display());
+    }
+  }
+  let mut features: Vec<String> = env::vars()
+    .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+    .map(|feature| feature.to_lowercase().replace('_', "-"))
+    .collect();
+  features.sort();
+  let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+  for (key, value) in [
+    (
+      "BUILD_GIT_COMMIT",
+      first_line("git", &["rev-parse", "HEAD"]),
+    ),
+    (
+      "BUILD_TARGET",
+      env::var("TARGET").expect("cargo sets TARGET"),
+    ),
+    (
+      "BUILD_PROFILE",
+      env::var("PROFILE").expect("cargo sets PROFILE"),
+    ),
+    ("BUILD_FEATURES", features.join(",")),
+    ("BUILD_RUSTC", first_line(&rustc, &["--version"])),
+  ] {
+    println!("cargo:rustc-env={}={}", key, value);
+  }
+}
+
 fn main() {
+  println!("cargo:rerun-if-changed=src");
+  build_info();
+  let mut unguarded = Vec::new();
+  let mut tasks = Vec::new();
+  self::unguarded(Path::new("src"), &mut unguarded, &mut tasks);
+  if !tasks.is_empty() {
+    for location in &tasks {
+      println!(
+        "cargo:warning={}: AsyncTask created without panic_guard::task",
+        location
+      );
+    }
+    panic!(
+      "{} AsyncTask(s) would abort Node on panic in compute; create them with panic_guard::task",
+      tasks.len()
+    );
+  }
+  if !unguarded.is_empty() {
+    for location in &unguarded {
+      println!(
+        "cargo:warning={}: #[napi] function without catch_unwind",
+        location
+      );
+    }
+    panic!(
+      "{} exported function(s) would abort Node on panic; declare them with #[napi(catch_unwind, ...)]",
+      unguarded.len()
+    );
+  }
   napi_build::setup();
 }
@@ -252,7 +252,327 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { sum, hello } = nativeBinding
+const { sum, hello, sumAsync, sleepMs, readFileAsync, reverseBuffer, concatBuffers, fillPattern, invertBytes, TickerHandle, startTicker, Counter, Hasher, hashSha256, hashSha512, hashBlake3, xxhash64, gzip, gunzip, brotliCompress, brotliDecompress, zstdCompress, zstdDecompress, gzipAsync, gunzipAsync, brotliCompressAsync, brotliDecompressAsync, zstdCompressAsync, zstdDecompressAsync, computePrimes, parseJson, stringify, parseConfig, stringifyConfig, mergeObjects, hashPassword, verifyPassword, FileReader, LruCache, parallelMapSquares, parallelSum, UlidGenerator, uuidV4, uuidV7, uuidV4Batch, ulid, nanoid, signJwt, verifyJwt, encrypt, decrypt, encryptAsync, decryptAsync, processItems, parseCsv, writeCsv, parseCsvStream, sumBig, mulU64, factorial, Pattern, Database, Statement, Watcher, base64Encode, base64Decode, hexEncode, hexDecode, base58Encode, base58Decode, msgpackEncode, msgpackDecode, cborEncode, cborDecode, resizeImage, cropImage, convertFormat, imageMetadata, normalizeNfc, normalizeNfkd, slugify, camelCase, snakeCase, kebabCase, graphemeLength, truncateGraphemes, levenshtein, jaroWinkler, FuzzyMatcher, renderMarkdown, renderMarkdownAsync, sanitizeHtml, parseXml, xmlToJson, parseXmlStream, parseYaml, toYaml, parseToml, toToml, ZipArchive, ZipWriter, createTarball, extractTarball, walkDir, globMatch, GlobSet, crc32, crc32c, adler32, Crc32Stream, RateLimiter, BloomFilter, HyperLogLog, PriorityQueue, SortedMap, mean, median, stddev, percentile, histogram, describe, Matrix, matMul, transpose, solveLinearSystem, haversineDistance, boundingBox, geohashEncode, geohashDecode, pointInPolygon, nearestNeighbors, SearchIndex, httpRequest, HttpClient, WsClient, resolveA, resolveAaaa, resolveTxt, resolveMx, reverseLookup, parseUrl, canonicalizeUrl, punycodeToUnicode, unicodeToPunycode, extractDomain, validateEmail, parsePhone, validateIban, parseSemver, satisfies, maxSatisfying, sortVersions, CronSchedule, convertTimezone, formatInTz, parseRfc3339, parseRfc2822, businessDaysBetween, diffLines, diffWords, formatPatch, diffJson, applyPatch, SnowflakeGenerator, randomBytes, randomInt, randomToken, Rng, parseDotenv, toDotenv, parseIni, toIni, parseProperties, toProperties, TemplateEngine, generateQr, generateQrSvg, extractPdfText, pdfMetadata, readExif, stripExif, audioMetadata, Tokenizer, detectLanguage, simhash, hammingDistance, MinHasher, nearDuplicates, parseColor, toHexColor, rgbToHsl, hslToRgb, rgbToLab, labToRgb, rgbToOklch, oklchToRgb, contrastRatio, mix, extractPalette, cpuInfo, cpuInfoAsync, memoryInfo, diskUsage, diskUsageAsync, listProcesses, listProcessesAsync, processExists, runCommand, SharedMap, EventBus, shutdown, activeResources, raisePanic, raisePanicAsync, initLogger, stopLogger, enableMetrics, getMetrics, resetMetrics, NdjsonParser, ndjsonStringify, ProtoRegistry, frameMessage, FrameDecoder, NativeServer, Scheduler, Expression, evalExpr, Decimal, formatBytes, parseBytes, formatDuration, parseDuration, convert, sortF64, argsort, dedupStrings, topK, pack, unpack, deepClone, deepEqual, deepMerge, Validator, jsonQuery, Query, MultipartParser, parseMultipart, parseQuerystring, serializeQuerystring, parseCookies, serializeCookie, parseAccept, parseUserAgent, isBot, parseUserAgents, parseCidr, ipInCidr, ipToInt, intToIp, IpSet, GeoIpReader, detectFileType, isBinary, soundex, metaphone, doubleMetaphone, ngrams, shingleSimilarity, SpellChecker, KvStore, AppendLog, SharedRing, LockGuard, NamedMutex, Semaphore, readClipboardText, writeClipboardText, readClipboardImage, setSecret, getSecret, deleteSecret, registerHotkey, unregisterHotkey, parseCertificate, verifyChain, generateSelfSigned, generateSecret, generateHotp, generateTotp, verifyTotp, otpauthUrl, generateKeypair, sign, verify, derivePublicKey, exportRawKey, importRawKey, Chunker, chunkBuffer, createBinaryPatch, applyBinaryPatch, JobQueue, CircuitBreaker, RetryPolicy, benchmark, compare, FlagEngine, detectEncoding, decode, encode, Decoder, readParquet, writeParquet, Table, nativeInfo, raisePanicTask, FileLines } = nativeBinding
 
 module.exports.sum = sum
 module.exports.hello = hello
+module.exports.sumAsync = sumAsync
+module.exports.sleepMs = sleepMs
+module.exports.readFileAsync = readFileAsync
+module.exports.reverseBuffer = reverseBuffer
+module.exports.concatBuffers = concatBuffers
+module.exports.fillPattern = fillPattern
+module.exports.invertBytes = invertBytes
+module.exports.TickerHandle = TickerHandle
+module.exports.startTicker = startTicker
+module.exports.Counter = Counter
+module.exports.Hasher = Hasher
+module.exports.hashSha256 = hashSha256
+module.exports.hashSha512 = hashSha512
+module.exports.hashBlake3 = hashBlake3
+module.exports.xxhash64 = xxhash64
+module.exports.gzip = gzip
+module.exports.gunzip = gunzip
+module.exports.brotliCompress = brotliCompress
+module.exports.brotliDecompress = brotliDecompress
+module.exports.zstdCompress = zstdCompress
+module.exports.zstdDecompress = zstdDecompress
+module.exports.gzipAsync = gzipAsync
+module.exports.gunzipAsync = gunzipAsync
+module.exports.brotliCompressAsync = brotliCompressAsync
+module.exports.brotliDecompressAsync = brotliDecompressAsync
+module.exports.zstdCompressAsync = zstdCompressAsync
+module.exports.zstdDecompressAsync = zstdDecompressAsync
+module.exports.computePrimes = computePrimes
+module.exports.parseJson = parseJson
+module.exports.stringify = stringify
+module.exports.parseConfig = parseConfig
+module.exports.stringifyConfig = stringifyConfig
+module.exports.mergeObjects = mergeObjects
+module.exports.hashPassword = hashPassword
+module.exports.verifyPassword = verifyPassword
+module.exports.FileReader = FileReader
+module.exports.LruCache = LruCache
+module.exports.parallelMapSquares = parallelMapSquares
+module.exports.parallelSum = parallelSum
+module.exports.UlidGenerator = UlidGenerator
+module.exports.uuidV4 = uuidV4
+module.exports.uuidV7 = uuidV7
+module.exports.uuidV4Batch = uuidV4Batch
+module.exports.ulid = ulid
+module.exports.nanoid = nanoid
+module.exports.signJwt = signJwt
+module.exports.verifyJwt = verifyJwt
+module.exports.encrypt = encrypt
+module.exports.decrypt = decrypt
+module.exports.encryptAsync = encryptAsync
+module.exports.decryptAsync = decryptAsync
+module.exports.processItems = processItems
+module.exports.parseCsv = parseCsv
+module.exports.writeCsv = writeCsv
+module.exports.parseCsvStream = parseCsvStream
+module.exports.sumBig = sumBig
+module.exports.mulU64 = mulU64
+module.exports.factorial = factorial
+module.exports.Pattern = Pattern
+module.exports.Database = Database
+module.exports.Statement = Statement
+module.exports.Watcher = Watcher
+module.exports.base64Encode = base64Encode
+module.exports.base64Decode = base64Decode
+module.exports.hexEncode = hexEncode
+module.exports.hexDecode = hexDecode
+module.exports.base58Encode = base58Encode
+module.exports.base58Decode = base58Decode
+module.exports.msgpackEncode = msgpackEncode
+module.exports.msgpackDecode = msgpackDecode
+module.exports.cborEncode = cborEncode
+module.exports.cborDecode = cborDecode
+module.exports.resizeImage = resizeImage
+module.exports.cropImage = cropImage
+module.exports.convertFormat = convertFormat
+module.exports.imageMetadata = imageMetadata
+module.exports.normalizeNfc = normalizeNfc
+module.exports.normalizeNfkd = normalizeNfkd
+module.exports.slugify = slugify
+module.exports.camelCase = camelCase
+module.exports.snakeCase = snakeCase
+module.exports.kebabCase = kebabCase
+module.exports.graphemeLength = graphemeLength
+module.exports.truncateGraphemes = truncateGraphemes
+module.exports.levenshtein = levenshtein
+module.exports.jaroWinkler = jaroWinkler
+module.exports.FuzzyMatcher = FuzzyMatcher
+module.exports.renderMarkdown = renderMarkdown
+module.exports.renderMarkdownAsync = renderMarkdownAsync
+module.exports.sanitizeHtml = sanitizeHtml
+module.exports.parseXml = parseXml
+module.exports.xmlToJson = xmlToJson
+module.exports.parseXmlStream = parseXmlStream
+module.exports.parseYaml = parseYaml
+module.exports.toYaml = toYaml
+module.exports.parseToml = parseToml
+module.exports.toToml = toToml
+module.exports.ZipArchive = ZipArchive
+module.exports.ZipWriter = ZipWriter
+module.exports.createTarball = createTarball
+module.exports.extractTarball = extractTarball
+module.exports.walkDir = walkDir
+module.exports.globMatch = globMatch
+module.exports.GlobSet = GlobSet
+module.exports.crc32 = crc32
+module.exports.crc32c = crc32c
+module.exports.adler32 = adler32
+module.exports.Crc32Stream = Crc32Stream
+module.exports.RateLimiter = RateLimiter
+module.exports.BloomFilter = BloomFilter
+module.exports.HyperLogLog = HyperLogLog
+module.exports.PriorityQueue = PriorityQueue
+module.exports.SortedMap = SortedMap
+module.exports.mean = mean
+module.exports.median = median
+module.exports.stddev = stddev
+module.exports.percentile = percentile
+module.exports.histogram = histogram
+module.exports.describe = describe
+module.exports.Matrix = Matrix
+module.exports.matMul = matMul
+module.exports.transpose = transpose
+module.exports.solveLinearSystem = solveLinearSystem
+module.exports.haversineDistance = haversineDistance
+module.exports.boundingBox = boundingBox
+module.exports.geohashEncode = geohashEncode
+module.exports.geohashDecode = geohashDecode
+module.exports.pointInPolygon = pointInPolygon
+module.exports.nearestNeighbors = nearestNeighbors
+module.exports.SearchIndex = SearchIndex
+module.exports.httpRequest = httpRequest
+module.exports.HttpClient = HttpClient
+module.exports.WsClient = WsClient
+module.exports.resolveA = resolveA
+module.exports.resolveAaaa = resolveAaaa
+module.exports.resolveTxt = resolveTxt
+module.exports.resolveMx = resolveMx
+module.exports.reverseLookup = reverseLookup
+module.exports.parseUrl = parseUrl
+module.exports.canonicalizeUrl = canonicalizeUrl
+module.exports.punycodeToUnicode = punycodeToUnicode
+module.exports.unicodeToPunycode = unicodeToPunycode
+module.exports.extractDomain = extractDomain
+module.exports.validateEmail = validateEmail
+module.exports.parsePhone = parsePhone
+module.exports.validateIban = validateIban
+module.exports.parseSemver = parseSemver
+module.exports.satisfies = satisfies
+module.exports.maxSatisfying = maxSatisfying
+module.exports.sortVersions = sortVersions
+module.exports.CronSchedule = CronSchedule
+module.exports.convertTimezone = convertTimezone
+module.exports.formatInTz = formatInTz
+module.exports.parseRfc3339 = parseRfc3339
+module.exports.parseRfc2822 = parseRfc2822
+module.exports.businessDaysBetween = businessDaysBetween
+module.exports.diffLines = diffLines
+module.exports.diffWords = diffWords
+module.exports.formatPatch = formatPatch
+module.exports.diffJson = diffJson
+module.exports.applyPatch = applyPatch
+module.exports.SnowflakeGenerator = SnowflakeGenerator
+module.exports.randomBytes = randomBytes
+module.exports.randomInt = randomInt
+module.exports.randomToken = randomToken
+module.exports.Rng = Rng
+module.exports.parseDotenv = parseDotenv
+module.exports.toDotenv = toDotenv
+module.exports.parseIni = parseIni
+module.exports.toIni = toIni
+module.exports.parseProperties = parseProperties
+module.exports.toProperties = toProperties
+module.exports.TemplateEngine = TemplateEngine
+module.exports.generateQr = generateQr
+module.exports.generateQrSvg = generateQrSvg
+module.exports.extractPdfText = extractPdfText
+module.exports.pdfMetadata = pdfMetadata
+module.exports.readExif = readExif
+module.exports.stripExif = stripExif
+module.exports.audioMetadata = audioMetadata
+module.exports.Tokenizer = Tokenizer
+module.exports.detectLanguage = detectLanguage
+module.exports.simhash = simhash
+module.exports.hammingDistance = hammingDistance
+module.exports.MinHasher = MinHasher
+module.exports.nearDuplicates = nearDuplicates
+module.exports.parseColor = parseColor
+module.exports.toHexColor = toHexColor
+module.exports.rgbToHsl = rgbToHsl
+module.exports.hslToRgb = hslToRgb
+module.exports.rgbToLab = rgbToLab
+module.exports.labToRgb = labToRgb
+module.exports.rgbToOklch = rgbToOklch
+module.exports.oklchToRgb = oklchToRgb
+module.exports.contrastRatio = contrastRatio
+module.exports.mix = mix
+module.exports.extractPalette = extractPalette
+module.exports.cpuInfo = cpuInfo
+module.exports.cpuInfoAsync = cpuInfoAsync
+module.exports.memoryInfo = memoryInfo
+module.exports.diskUsage = diskUsage
+module.exports.diskUsageAsync = diskUsageAsync
+module.exports.listProcesses = listProcesses
+module.exports.listProcessesAsync = listProcessesAsync
+module.exports.processExists = processExists
+module.exports.runCommand = runCommand
+module.exports.SharedMap = SharedMap
+module.exports.EventBus = EventBus
+module.exports.shutdown = shutdown
+module.exports.activeResources = activeResources
+module.exports.raisePanic = raisePanic
+module.exports.raisePanicAsync = raisePanicAsync
+module.exports.initLogger = initLogger
+module.exports.stopLogger = stopLogger
+module.exports.enableMetrics = enableMetrics
+module.exports.getMetrics = getMetrics
+module.exports.resetMetrics = resetMetrics
+module.exports.NdjsonParser = NdjsonParser
+module.exports.ndjsonStringify = ndjsonStringify
+module.exports.ProtoRegistry = ProtoRegistry
+module.exports.frameMessage = frameMessage
+module.exports.FrameDecoder = FrameDecoder
+module.exports.NativeServer = NativeServer
+module.exports.Scheduler = Scheduler
+module.exports.Expression = Expression
+module.exports.evalExpr = evalExpr
+module.exports.Decimal = Decimal
+module.exports.formatBytes = formatBytes
+module.exports.parseBytes = parseBytes
+module.exports.formatDuration = formatDuration
+module.exports.parseDuration = parseDuration
+module.exports.convert = convert
+module.exports.sortF64 = sortF64
+module.exports.argsort = argsort
+module.exports.dedupStrings = dedupStrings
+module.exports.topK = topK
+module.exports.sortF64 = sortF64
+module.exports.argsort = argsort
+module.exports.dedupStrings = dedupStrings
+module.exports.topK = topK
+module.exports.pack = pack
+module.exports.unpack = unpack
+module.exports.deepClone = deepClone
+module.exports.deepEqual = deepEqual
+module.exports.deepMerge = deepMerge
+module.exports.Validator = Validator
+module.exports.jsonQuery = jsonQuery
+module.exports.Query = Query
+module.exports.MultipartParser = MultipartParser
+module.exports.parseMultipart = parseMultipart
+module.exports.parseQuerystring = parseQuerystring
+module.exports.serializeQuerystring = serializeQuerystring
+module.exports.parseCookies = parseCookies
+module.exports.serializeCookie = serializeCookie
+module.exports.parseAccept = parseAccept
+module.exports.parseUserAgent = parseUserAgent
+module.exports.isBot = isBot
+module.exports.parseUserAgents = parseUserAgents
+module.exports.parseCidr = parseCidr
+module.exports.ipInCidr = ipInCidr
+module.exports.ipToInt = ipToInt
+module.exports.intToIp = intToIp
+module.exports.IpSet = IpSet
+module.exports.GeoIpReader = GeoIpReader
+module.exports.detectFileType = detectFileType
+module.exports.isBinary = isBinary
+module.exports.soundex = soundex
+module.exports.metaphone = metaphone
+module.exports.doubleMetaphone = doubleMetaphone
+module.exports.ngrams = ngrams
+module.exports.shingleSimilarity = shingleSimilarity
+module.exports.SpellChecker = SpellChecker
+module.exports.KvStore = KvStore
+module.exports.AppendLog = AppendLog
+module.exports.SharedRing = SharedRing
+module.exports.LockGuard = LockGuard
+module.exports.NamedMutex = NamedMutex
+module.exports.Semaphore = Semaphore
+module.exports.readClipboardText = readClipboardText
+module.exports.writeClipboardText = writeClipboardText
+module.exports.readClipboardImage = readClipboardImage
+module.exports.setSecret = setSecret
+module.exports.getSecret = getSecret
+module.exports.deleteSecret = deleteSecret
+module.exports.registerHotkey = registerHotkey
+module.exports.unregisterHotkey = unregisterHotkey
+module.exports.parseCertificate = parseCertificate
+module.exports.verifyChain = verifyChain
+module.exports.generateSelfSigned = generateSelfSigned
+module.exports.generateSecret = generateSecret
+module.exports.generateHotp = generateHotp
+module.exports.generateTotp = generateTotp
+module.exports.verifyTotp = verifyTotp
+module.exports.otpauthUrl = otpauthUrl
+module.exports.generateKeypair = generateKeypair
+module.exports.sign = sign
+module.exports.verify = verify
+module.exports.derivePublicKey = derivePublicKey
+module.exports.exportRawKey = exportRawKey
+module.exports.importRawKey = importRawKey
+module.exports.Chunker = Chunker
+module.exports.chunkBuffer = chunkBuffer
+module.exports.createBinaryPatch = createBinaryPatch
+module.exports.applyBinaryPatch = applyBinaryPatch
+module.exports.JobQueue = JobQueue
+module.exports.CircuitBreaker = CircuitBreaker
+module.exports.RetryPolicy = RetryPolicy
+module.exports.benchmark = benchmark
+module.exports.compare = compare
+module.exports.FlagEngine = FlagEngine
+module.exports.detectEncoding = detectEncoding
+module.exports.decode = decode
+module.exports.encode = encode
+module.exports.Decoder = Decoder
+module.exports.readParquet = readParquet
+module.exports.writeParquet = writeParquet
+module.exports.Table = Table
+module.exports.nativeInfo = nativeInfo
+module.exports.raisePanicTask = raisePanicTask
+module.exports.FileLines = FileLines
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject};
+
+use crate::error::{self, AppError, Result};
+
+/// Same as `sum`, but resolved on the tokio runtime so the caller gets a Promise.
+#[napi(catch_unwind, ts_return_type = "Promise<number>")]
+pub fn sum_async(env: Env, a: i32, b: i32) -> Result<JsObject> {
+  error::spawn(&env, async move { crate::sum(a, b) })
+}
+
+/// Resolves after `ms` milliseconds without blocking the Node event loop.
+#[napi(catch_unwind)]
+pub async fn sleep_ms(ms: u32) {
+  tokio::time::sleep(Duration::from_millis(ms as u64)).await;
+}
+
+/// Reads the whole file at `path` into a Buffer.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn read_file_async(env: Env, path: String) -> Result<JsObject> {
+  error::spawn(&env, async move {
+    tokio::fs::read(&path)
+      .await
+      .map(Buffer::from)
+      .map_err(|e| AppError::io(&path, e))
+  })
+}
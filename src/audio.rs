@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use napi::bindgen_prelude::*;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct AudioInfo {
+  /// Container: `mp3`, `flac`, `ogg` or `wav`.
+  pub format: String,
+  /// Codec short name, e.g. `mp3`, `flac`, `vorbis`, `pcm_s16le`.
+  pub codec: String,
+  /// Seconds.
+  pub duration: Option<f64>,
+  /// Average bits per second over the whole input.
+  pub bitrate: Option<u32>,
+  pub sample_rate: Option<u32>,
+  pub channels: Option<u32>,
+  pub bits_per_sample: Option<u32>,
+  /// Title, artist, album and other tags. Well-known tags use the keys
+  /// `title`, `artist`, `album`, `albumArtist`, `date`, `genre`,
+  /// `trackNumber`, `discNumber`, `composer` and `comment`; others keep the
+  /// key from the file.
+  pub tags: HashMap<String, String>,
+}
+
+fn audio_error(e: SymphoniaError) -> AppError {
+  match e {
+    SymphoniaError::Unsupported(m) => AppError::Unsupported(format!("unsupported audio: {}", m)),
+    SymphoniaError::IoError(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+      AppError::Parse("invalid audio: unexpected end of data".into())
+    }
+    SymphoniaError::IoError(e) => AppError::Io(e.to_string()),
+    e => AppError::Parse(format!("invalid audio: {}", e)),
+  }
+}
+
+fn container(header: &[u8]) -> Option<&'static str> {
+  match header {
+    [b'f', b'L', b'a', b'C', ..] => Some("flac"),
+    [b'O', b'g', b'g', b'S', ..] => Some("ogg"),
+    [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some("wav"),
+    [b'I', b'D', b'3', ..] => Some("mp3"),
+    [0xff, b, ..] if b & 0xe0 == 0xe0 => Some("mp3"),
+    _ => None,
+  }
+}
+
+fn tag_name(key: StandardTagKey) -> Option<&'static str> {
+  Some(match key {
+    StandardTagKey::TrackTitle => "title",
+    StandardTagKey::Artist => "artist",
+    StandardTagKey::Album => "album",
+    StandardTagKey::AlbumArtist => "albumArtist",
+    StandardTagKey::Date => "date",
+    StandardTagKey::Genre => "genre",
+    StandardTagKey::TrackNumber => "trackNumber",
+    StandardTagKey::DiscNumber => "discNumber",
+    StandardTagKey::Composer => "composer",
+    StandardTagKey::Comment => "comment",
+    _ => return None,
+  })
+}
+
+fn collect_tags(revision: Option<&MetadataRevision>, tags: &mut HashMap<String, String>) {
+  for tag in revision.into_iter().flat_map(|r| r.tags()) {
+    let key = tag
+      .std_key
+      .and_then(tag_name)
+      .map_or_else(|| tag.key.clone(), str::to_string);
+    // RIFF INFO strings keep their NUL terminator.
+    let value = tag.value.to_string().trim_end_matches('\0').to_string();
+    if !value.is_empty() {
+      tags.entry(key).or_insert(value);
+    }
+  }
+}
+
+fn probe(source: Box<dyn MediaSource>, format: &str, size: u64) -> Result<AudioInfo> {
+  let mut hint = Hint::new();
+  hint.with_extension(format);
+  let mut probed = symphonia::default::get_probe()
+    .format(
+      &hint,
+      MediaSourceStream::new(source, Default::default()),
+      &FormatOptions::default(),
+      &MetadataOptions::default(),
+    )
+    .map_err(audio_error)?;
+
+  let mut tags = HashMap::new();
+  collect_tags(probed.format.metadata().current(), &mut tags);
+  if let Some(metadata) = probed.metadata.get() {
+    collect_tags(metadata.current(), &mut tags);
+  }
+
+  let track = probed
+    .format
+    .default_track()
+    .ok_or_else(|| AppError::Parse("invalid audio: no audio track".into()))?;
+  let params = track.codec_params.clone();
+  let track_id = track.id;
+  let codec = symphonia::default::get_codecs()
+    .get_codec(params.codec)
+    .map_or("unknown", |c| c.short_name)
+    .to_string();
+
+  // Streams without a frame count in their headers (e.g. MP3 without a
+  // Xing/Info frame) are measured by walking the packets, which demuxes but
+  // does not decode.
+  let frames = match params.n_frames {
+    Some(n) => Some(n),
+    None => {
+      let mut total = 0u64;
+      loop {
+        match probed.format.next_packet() {
+          Ok(packet) if packet.track_id() == track_id => total += packet.dur,
+          Ok(_) => {}
+          Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+          Err(e) => return Err(audio_error(e)),
+        }
+      }
+      Some(total)
+    }
+  };
+  let duration = match (frames, params.time_base, params.sample_rate) {
+    (Some(n), Some(tb), _) => {
+      let time = tb.calc_time(n);
+      Some(time.seconds as f64 + time.frac)
+    }
+    (Some(n), None, Some(rate)) => Some(n as f64 / rate as f64),
+    _ => None,
+  };
+  let bitrate = duration
+    .filter(|d| *d > 0.0)
+    .map(|d| (size as f64 * 8.0 / d).round() as u32);
+
+  Ok(AudioInfo {
+    format: format.to_string(),
+    codec,
+    duration,
+    bitrate,
+    sample_rate: params.sample_rate,
+    channels: params.channels.map(|c| c.count() as u32),
+    bits_per_sample: params.bits_per_sample,
+    tags,
+  })
+}
+
+fn unrecognized() -> AppError {
+  AppError::Unsupported("unrecognized audio format; expected mp3, flac, ogg or wav".into())
+}
+
+/// Probes an MP3, FLAC, Ogg Vorbis or WAV file — given as bytes or a path —
+/// for its duration, stream parameters and tags without decoding any audio.
+#[napi(catch_unwind, ts_args_type = "input: Buffer | string")]
+pub fn audio_metadata(input: Either<Buffer, String>) -> Result<AudioInfo> {
+  match input {
+    Either::A(bytes) => {
+      let format = container(&bytes).ok_or_else(unrecognized)?;
+      let size = bytes.len() as u64;
+      probe(Box::new(Cursor::new(bytes.to_vec())), format, size)
+    }
+    Either::B(path) => {
+      let mut file = File::open(&path).map_err(|e| AppError::io(&path, e))?;
+      let mut header = [0u8; 12];
+      let read = file.read(&mut header).map_err(|e| AppError::io(&path, e))?;
+      let format = container(&header[..read]).ok_or_else(unrecognized)?;
+      let size = file.metadata().map_err(|e| AppError::io(&path, e))?.len();
+      file
+        .seek(SeekFrom::Start(0))
+        .map_err(|e| AppError::io(&path, e))?;
+      probe(Box::new(file), format, size)
+    }
+  }
+}
@@ -0,0 +1,136 @@
+use std::time::Instant;
+
+use napi::JsFunction;
+
+use crate::error::{AppError, Result};
+use crate::stats::quantile;
+
+const MAX_ITERATIONS: u32 = 1_000_000;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct BenchOptions {
+  /// Untimed calls first, to let the JIT settle. Defaults to 10.
+  pub warmup: Option<u32>,
+  /// Timed calls, 1 to 1,000,000. Defaults to 1000.
+  pub iterations: Option<u32>,
+}
+
+#[napi(object)]
+pub struct BenchResult {
+  pub name: String,
+  pub iterations: u32,
+  pub total_ms: f64,
+  pub mean_ms: f64,
+  pub stddev_ms: f64,
+  pub min_ms: f64,
+  pub p50_ms: f64,
+  pub p99_ms: f64,
+  pub max_ms: f64,
+  /// Calls per second over the whole timed run.
+  pub ops_per_sec: f64,
+  /// Only set by `compare`: how many times slower than the fastest entry
+  /// this one is, so 1 for the fastest.
+  pub relative: Option<f64>,
+}
+
+#[napi(object)]
+pub struct BenchSpec {
+  pub name: String,
+  #[napi(js_name = "fn", ts_type = "() => unknown")]
+  pub callback: JsFunction,
+  pub options: Option<BenchOptions>,
+}
+
+#[napi(object)]
+pub struct Report {
+  /// Name of the entry with the highest `opsPerSec`.
+  pub fastest: String,
+  /// In the order given.
+  pub results: Vec<BenchResult>,
+}
+
+fn run(name: String, callback: &JsFunction, options: Option<BenchOptions>) -> Result<BenchResult> {
+  let options = options.unwrap_or_default();
+  let iterations = options.iterations.unwrap_or(1000);
+  if !(1..=MAX_ITERATIONS).contains(&iterations) {
+    return Err(AppError::InvalidInput(format!(
+      "iterations must be 1 to {}, got {}",
+      MAX_ITERATIONS, iterations
+    )));
+  }
+  for _ in 0..options.warmup.unwrap_or(10) {
+    callback.call_without_args(None)?;
+  }
+  let mut samples = Vec::with_capacity(iterations as usize);
+  for _ in 0..iterations {
+    let start = Instant::now();
+    callback.call_without_args(None)?;
+    samples.push(start.elapsed().as_secs_f64() * 1000.0);
+  }
+  let total_ms: f64 = samples.iter().sum();
+  let mean_ms = total_ms / samples.len() as f64;
+  let variance = samples.iter().map(|&s| (s - mean_ms).powi(2)).sum::<f64>() / samples.len() as f64;
+  samples.sort_by(f64::total_cmp);
+  Ok(BenchResult {
+    name,
+    iterations,
+    total_ms,
+    mean_ms,
+    stddev_ms: variance.sqrt(),
+    min_ms: samples[0],
+    p50_ms: quantile(&samples, 0.5),
+    p99_ms: quantile(&samples, 0.99),
+    max_ms: samples[samples.len() - 1],
+    ops_per_sec: if total_ms > 0.0 {
+      iterations as f64 * 1000.0 / total_ms
+    } else {
+      f64::INFINITY
+    },
+    relative: None,
+  })
+}
+
+/// Times `iterations` synchronous calls of `fn` one by one with the
+/// monotonic clock, after `warmup` untimed ones. Only the calls are
+/// timed, not the loop around them, so the percentiles reflect `fn`
+/// itself. A returned promise is not awaited, and a throw ends the run
+/// with that error.
+#[napi(
+  catch_unwind,
+  ts_args_type = "name: string, fn: () => unknown, options?: BenchOptions"
+)]
+pub fn benchmark(
+  name: String,
+  callback: JsFunction,
+  options: Option<BenchOptions>,
+) -> Result<BenchResult> {
+  run(name, &callback, options)
+}
+
+/// Runs each benchmark in turn and ranks them against the fastest, e.g.
+/// a JS implementation against its native counterpart.
+#[napi(catch_unwind)]
+pub fn compare(specs: Vec<BenchSpec>) -> Result<Report> {
+  if specs.is_empty() {
+    return Err(AppError::InvalidInput(
+      "compare needs at least one benchmark".into(),
+    ));
+  }
+  let mut results = specs
+    .into_iter()
+    .map(|spec| run(spec.name, &spec.callback, spec.options))
+    .collect::<Result<Vec<_>>>()?;
+  let fastest = results
+    .iter()
+    .max_by(|a, b| a.ops_per_sec.total_cmp(&b.ops_per_sec))
+    .map(|result| (result.name.clone(), result.ops_per_sec))
+    .expect("at least one result");
+  for result in &mut results {
+    result.relative = Some(fastest.1 / result.ops_per_sec);
+  }
+  Ok(Report {
+    fastest: fastest.0,
+    results,
+  })
+}
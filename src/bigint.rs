@@ -0,0 +1,87 @@
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+/// Largest `n` accepted by `factorial`; the result already has ~35k digits.
+const MAX_FACTORIAL: u32 = 10_000;
+
+/// `value` as an i128, or `None` if it doesn't fit. napi's own `get_i128`
+/// reports a magnitude of exactly 2^127 the wrong way round: rejected when
+/// negative (`i128::MIN`), and accepted as `i128::MIN` alongside a third word.
+pub(crate) fn bigint_to_i128(value: &BigInt) -> Option<i128> {
+  let significant = value
+    .words
+    .iter()
+    .rposition(|&word| word != 0)
+    .map_or(0, |last| last + 1);
+  if significant > 2 {
+    return None;
+  }
+  let word = |i: usize| value.words.get(i).copied().unwrap_or(0) as u128;
+  let magnitude = word(0) | word(1) << 64;
+  if value.sign_bit {
+    (magnitude <= i128::MIN.unsigned_abs()).then(|| (magnitude as i128).wrapping_neg())
+  } else {
+    i128::try_from(magnitude).ok()
+  }
+}
+
+fn to_i128(name: &str, value: &BigInt) -> Result<i128> {
+  bigint_to_i128(value).ok_or_else(|| AppError::Overflow(format!("{} does not fit in i128", name)))
+}
+
+fn to_u64(name: &str, value: &BigInt) -> Result<u64> {
+  match value.get_u64() {
+    (false, v, true) => Ok(v),
+    (true, _, _) => Err(AppError::InvalidInput(format!(
+      "{} must not be negative",
+      name
+    ))),
+    _ => Err(AppError::Overflow(format!("{} does not fit in u64", name))),
+  }
+}
+
+/// Adds two BigInts in i128 precision; throws `ERR_OVERFLOW` if either input
+/// or the result is outside the i128 range.
+#[napi(catch_unwind)]
+pub fn sum_big(a: BigInt, b: BigInt) -> Result<BigInt> {
+  let (a, b) = (to_i128("a", &a)?, to_i128("b", &b)?);
+  a.checked_add(b)
+    .map(BigInt::from)
+    .ok_or_else(|| AppError::Overflow(format!("{} + {} overflows i128", a, b)))
+}
+
+/// Multiplies two u64 BigInts. The u128 product is always exact.
+#[napi(catch_unwind)]
+pub fn mul_u64(a: BigInt, b: BigInt) -> Result<BigInt> {
+  let (a, b) = (to_u64("a", &a)?, to_u64("b", &b)?);
+  Ok(BigInt::from(a as u128 * b as u128))
+}
+
+/// `n!` as an arbitrary-precision BigInt, for `n` up to 10000.
+#[napi(catch_unwind)]
+pub fn factorial(n: u32) -> Result<BigInt> {
+  if n > MAX_FACTORIAL {
+    return Err(AppError::InvalidInput(format!(
+      "factorial is limited to n <= {}, got {}",
+      MAX_FACTORIAL, n
+    )));
+  }
+  // Little-endian base-2^64 words, the same layout napi uses for BigInt.
+  let mut words = vec![1u64];
+  for i in 2..=n as u128 {
+    let mut carry = 0u128;
+    for word in words.iter_mut() {
+      let product = *word as u128 * i + carry;
+      *word = product as u64;
+      carry = product >> 64;
+    }
+    if carry > 0 {
+      words.push(carry as u64);
+    }
+  }
+  Ok(BigInt {
+    sign_bit: false,
+    words,
+  })
+}
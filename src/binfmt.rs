@@ -0,0 +1,350 @@
+use ciborium::value::Value as CborValue;
+use napi::bindgen_prelude::*;
+use napi::{
+  Env, JsBoolean, JsDate, JsNumber, JsObject, JsString, JsUnknown, NapiRaw, NapiValue, ValueType,
+};
+use rmpv::Value as MsgpackValue;
+
+use crate::bigint::bigint_to_i128;
+use crate::buffer_ops::buffer_bytes;
+use crate::error::{AppError, Result};
+use crate::json::check_depth;
+
+/// Integers beyond this are encoded from / decoded to BigInt.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+/// MessagePack's reserved timestamp extension type.
+const MSGPACK_TIMESTAMP: i8 = -1;
+/// CBOR tag for an epoch-based date/time (RFC 8949 §3.4.2).
+const CBOR_EPOCH_DATE: u64 = 1;
+
+/// The JS values both formats can represent, so each format only has to
+/// convert to and from this instead of dealing with napi directly.
+enum Item {
+  Nil,
+  Bool(bool),
+  Int(i128),
+  Float(f64),
+  Str(String),
+  Bytes(Vec<u8>),
+  Array(Vec<Item>),
+  Map(Vec<(String, Item)>),
+  /// Milliseconds since the Unix epoch, like `Date.prototype.valueOf`.
+  Date(f64),
+}
+
+fn from_js(env: &Env, value: JsUnknown, depth: usize) -> Result<Item> {
+  check_depth(depth)?;
+  Ok(match value.get_type()? {
+    ValueType::Null | ValueType::Undefined => Item::Nil,
+    ValueType::Boolean => Item::Bool(unsafe { value.cast::<JsBoolean>() }.get_value()?),
+    ValueType::Number => {
+      let n = unsafe { value.cast::<JsNumber>() }.get_double()?;
+      if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        Item::Int(n as i128)
+      } else {
+        Item::Float(n)
+      }
+    }
+    ValueType::String => Item::Str(
+      unsafe { value.cast::<JsString>() }
+        .into_utf8()?
+        .into_owned()?,
+    ),
+    ValueType::BigInt => {
+      let bigint = unsafe { BigInt::from_napi_value(env.raw(), value.raw())? };
+      Item::Int(
+        bigint_to_i128(&bigint)
+          .ok_or_else(|| AppError::Overflow("BigInt does not fit in 128 bits".into()))?,
+      )
+    }
+    ValueType::Object if value.is_date()? => {
+      let ms = unsafe { value.cast::<JsDate>() }.value_of()?;
+      if ms.is_nan() {
+        return Err(AppError::InvalidInput(
+          "cannot encode an invalid Date".into(),
+        ));
+      }
+      Item::Date(ms)
+    }
+    ValueType::Object if value.is_buffer()? => Item::Bytes(buffer_bytes(&value)?),
+    ValueType::Object if value.is_typedarray()? => {
+      let bytes = unsafe { Uint8Array::from_napi_value(env.raw(), value.raw()) }
+        .map_err(|_| AppError::Unsupported("only Uint8Array typed arrays can be encoded".into()))?;
+      Item::Bytes(bytes.to_vec())
+    }
+    ValueType::Object if value.is_array()? => {
+      let array = unsafe { value.cast::<JsObject>() };
+      let items = (0..array.get_array_length()?)
+        .map(|i| from_js(env, array.get_element::<JsUnknown>(i)?, depth + 1))
+        .collect::<Result<_>>()?;
+      Item::Array(items)
+    }
+    ValueType::Object => {
+      let object = unsafe { value.cast::<JsObject>() };
+      let keys = object.get_property_names()?;
+      let mut entries = Vec::new();
+      for i in 0..keys.get_array_length()? {
+        let key = keys.get_element::<JsString>(i)?.into_utf8()?.into_owned()?;
+        let value = object.get_named_property::<JsUnknown>(&key)?;
+        // Same as JSON: properties set to `undefined` are left out.
+        if value.get_type()? != ValueType::Undefined {
+          entries.push((key, from_js(env, value, depth + 1)?));
+        }
+      }
+      Item::Map(entries)
+    }
+    other => {
+      return Err(AppError::Unsupported(format!(
+        "cannot encode a {:?}",
+        other
+      )))
+    }
+  })
+}
+
+fn to_js(env: &Env, item: Item) -> napi::Result<JsUnknown> {
+  Ok(match item {
+    Item::Nil => env.get_null()?.into_unknown(),
+    Item::Bool(b) => env.get_boolean(b)?.into_unknown(),
+    Item::Int(i) if (i as f64).abs() <= MAX_SAFE_INTEGER => {
+      env.create_int64(i as i64)?.into_unknown()
+    }
+    Item::Int(i) => unsafe {
+      JsUnknown::from_raw_unchecked(
+        env.raw(),
+        BigInt::to_napi_value(env.raw(), BigInt::from(i))?,
+      )
+    },
+    Item::Float(f) => env.create_double(f)?.into_unknown(),
+    Item::Str(s) => env.create_string_from_std(s)?.into_unknown(),
+    Item::Bytes(b) => env.create_buffer_with_data(b)?.into_raw().into_unknown(),
+    Item::Array(items) => {
+      let mut array = env.create_array_with_length(items.len())?;
+      for (i, item) in items.into_iter().enumerate() {
+        array.set_element(i as u32, to_js(env, item)?)?;
+      }
+      array.into_unknown()
+    }
+    Item::Map(entries) => {
+      let mut object = env.create_object()?;
+      for (key, item) in entries {
+        object.set_named_property(&key, to_js(env, item)?)?;
+      }
+      object.into_unknown()
+    }
+    Item::Date(ms) => env.create_date(ms)?.into_unknown(),
+  })
+}
+
+fn decode_error(format: &str, e: impl std::fmt::Display) -> AppError {
+  AppError::Parse(format!("invalid {}: {}", format, e))
+}
+
+/// Splits epoch milliseconds into whole seconds and a sub-second nanosecond
+/// part in `0..1e9`.
+fn split_millis(ms: f64) -> (i64, u32) {
+  let secs = (ms / 1000.0).floor();
+  let nanos = ((ms - secs * 1000.0) * 1_000_000.0).round() as u32;
+  (secs as i64, nanos.min(999_999_999))
+}
+
+fn to_msgpack(item: Item) -> Result<MsgpackValue> {
+  Ok(match item {
+    Item::Nil => MsgpackValue::Nil,
+    Item::Bool(b) => MsgpackValue::Boolean(b),
+    Item::Int(i) => {
+      if let Ok(i) = i64::try_from(i) {
+        MsgpackValue::from(i)
+      } else if let Ok(u) = u64::try_from(i) {
+        MsgpackValue::from(u)
+      } else {
+        return Err(AppError::Overflow(format!(
+          "{} does not fit in a MessagePack integer",
+          i
+        )));
+      }
+    }
+    Item::Float(f) => MsgpackValue::F64(f),
+    Item::Str(s) => MsgpackValue::from(s),
+    Item::Bytes(b) => MsgpackValue::Binary(b),
+    Item::Array(items) => {
+      MsgpackValue::Array(items.into_iter().map(to_msgpack).collect::<Result<_>>()?)
+    }
+    Item::Map(entries) => MsgpackValue::Map(
+      entries
+        .into_iter()
+        .map(|(k, v)| Ok((MsgpackValue::from(k), to_msgpack(v)?)))
+        .collect::<Result<_>>()?,
+    ),
+    Item::Date(ms) => MsgpackValue::Ext(MSGPACK_TIMESTAMP, encode_timestamp(ms)),
+  })
+}
+
+/// Uses the smallest of the three timestamp layouts that fits.
+fn encode_timestamp(ms: f64) -> Vec<u8> {
+  let (secs, nanos) = split_millis(ms);
+  if nanos == 0 && (0..=u32::MAX as i64).contains(&secs) {
+    (secs as u32).to_be_bytes().to_vec()
+  } else if (0..1 << 34).contains(&secs) {
+    ((nanos as u64) << 34 | secs as u64).to_be_bytes().to_vec()
+  } else {
+    let mut out = nanos.to_be_bytes().to_vec();
+    out.extend_from_slice(&secs.to_be_bytes());
+    out
+  }
+}
+
+fn decode_timestamp(data: &[u8]) -> Result<f64> {
+  let (secs, nanos) = match data.len() {
+    4 => (u32::from_be_bytes(data.try_into().unwrap()) as i64, 0),
+    8 => {
+      let packed = u64::from_be_bytes(data.try_into().unwrap());
+      ((packed & ((1 << 34) - 1)) as i64, (packed >> 34) as u32)
+    }
+    12 => (
+      i64::from_be_bytes(data[4..].try_into().unwrap()),
+      u32::from_be_bytes(data[..4].try_into().unwrap()),
+    ),
+    n => return Err(decode_error("MessagePack", format!("{}-byte timestamp", n))),
+  };
+  Ok(secs as f64 * 1000.0 + nanos as f64 / 1_000_000.0)
+}
+
+fn map_key(key: Item, format: &str) -> Result<String> {
+  match key {
+    Item::Str(s) => Ok(s),
+    Item::Int(i) => Ok(i.to_string()),
+    _ => Err(decode_error(format, "map keys must be strings or integers")),
+  }
+}
+
+fn from_msgpack(value: MsgpackValue) -> Result<Item> {
+  Ok(match value {
+    MsgpackValue::Nil => Item::Nil,
+    MsgpackValue::Boolean(b) => Item::Bool(b),
+    MsgpackValue::Integer(i) => match (i.as_i64(), i.as_u64()) {
+      (Some(i), _) => Item::Int(i as i128),
+      (_, Some(u)) => Item::Int(u as i128),
+      _ => unreachable!("MessagePack integers are always i64 or u64"),
+    },
+    MsgpackValue::F32(f) => Item::Float(f as f64),
+    MsgpackValue::F64(f) => Item::Float(f),
+    MsgpackValue::String(s) => match s.into_str() {
+      Some(s) => Item::Str(s),
+      None => return Err(decode_error("MessagePack", "string is not valid UTF-8")),
+    },
+    MsgpackValue::Binary(b) => Item::Bytes(b),
+    MsgpackValue::Array(items) => {
+      Item::Array(items.into_iter().map(from_msgpack).collect::<Result<_>>()?)
+    }
+    MsgpackValue::Map(entries) => Item::Map(
+      entries
+        .into_iter()
+        .map(|(k, v)| Ok((map_key(from_msgpack(k)?, "MessagePack")?, from_msgpack(v)?)))
+        .collect::<Result<_>>()?,
+    ),
+    MsgpackValue::Ext(MSGPACK_TIMESTAMP, data) => Item::Date(decode_timestamp(&data)?),
+    MsgpackValue::Ext(kind, _) => {
+      return Err(AppError::Unsupported(format!(
+        "MessagePack extension type {}",
+        kind
+      )))
+    }
+  })
+}
+
+fn to_cbor(item: Item) -> Result<CborValue> {
+  Ok(match item {
+    Item::Nil => CborValue::Null,
+    Item::Bool(b) => CborValue::Bool(b),
+    Item::Int(i) => CborValue::Integer(
+      i.try_into()
+        .map_err(|_| AppError::Overflow(format!("{} does not fit in a CBOR integer", i)))?,
+    ),
+    Item::Float(f) => CborValue::Float(f),
+    Item::Str(s) => CborValue::Text(s),
+    Item::Bytes(b) => CborValue::Bytes(b),
+    Item::Array(items) => CborValue::Array(items.into_iter().map(to_cbor).collect::<Result<_>>()?),
+    Item::Map(entries) => CborValue::Map(
+      entries
+        .into_iter()
+        .map(|(k, v)| Ok((CborValue::Text(k), to_cbor(v)?)))
+        .collect::<Result<_>>()?,
+    ),
+    Item::Date(ms) => {
+      let secs = match split_millis(ms) {
+        (secs, 0) => CborValue::Integer(secs.into()),
+        _ => CborValue::Float(ms / 1000.0),
+      };
+      CborValue::Tag(CBOR_EPOCH_DATE, Box::new(secs))
+    }
+  })
+}
+
+fn from_cbor(value: CborValue) -> Result<Item> {
+  Ok(match value {
+    CborValue::Null => Item::Nil,
+    CborValue::Bool(b) => Item::Bool(b),
+    CborValue::Integer(i) => Item::Int(i.into()),
+    CborValue::Float(f) => Item::Float(f),
+    CborValue::Text(s) => Item::Str(s),
+    CborValue::Bytes(b) => Item::Bytes(b),
+    CborValue::Array(items) => {
+      Item::Array(items.into_iter().map(from_cbor).collect::<Result<_>>()?)
+    }
+    CborValue::Map(entries) => Item::Map(
+      entries
+        .into_iter()
+        .map(|(k, v)| Ok((map_key(from_cbor(k)?, "CBOR")?, from_cbor(v)?)))
+        .collect::<Result<_>>()?,
+    ),
+    CborValue::Tag(CBOR_EPOCH_DATE, inner) => match *inner {
+      CborValue::Integer(secs) => Item::Date(i128::from(secs) as f64 * 1000.0),
+      CborValue::Float(secs) => Item::Date(secs * 1000.0),
+      _ => return Err(decode_error("CBOR", "epoch date must be a number")),
+    },
+    // Other tags (bignums, URIs, ...) decode as their untagged content.
+    CborValue::Tag(_, inner) => from_cbor(*inner)?,
+    _ => return Err(AppError::Unsupported("unknown CBOR value".into())),
+  })
+}
+
+/// Encodes a JS value as MessagePack. Dates use the timestamp extension
+/// (invalid Dates throw `ERR_INVALID_INPUT`), Buffers and Uint8Arrays
+/// become binary, and integers outside the safe range must be passed as
+/// BigInt.
+#[napi(catch_unwind, ts_args_type = "value: unknown")]
+pub fn msgpack_encode(env: Env, value: JsUnknown) -> Result<Buffer> {
+  let value = to_msgpack(from_js(&env, value, 0)?)?;
+  let mut out = Vec::new();
+  rmpv::encode::write_value(&mut out, &value).map_err(|e| AppError::Io(e.to_string()))?;
+  Ok(out.into())
+}
+
+/// Decodes a MessagePack document. Integers outside the safe range come back
+/// as BigInt and binary as Buffer.
+#[napi(catch_unwind, ts_return_type = "unknown")]
+pub fn msgpack_decode(env: Env, input: Buffer) -> Result<JsUnknown> {
+  let mut bytes = &input[..];
+  let value = rmpv::decode::read_value(&mut bytes).map_err(|e| decode_error("MessagePack", e))?;
+  if !bytes.is_empty() {
+    return Err(decode_error("MessagePack", "trailing bytes after value"));
+  }
+  Ok(to_js(&env, from_msgpack(value)?)?)
+}
+
+/// Encodes a JS value as CBOR, with the same mapping as `msgpackEncode`.
+/// Dates are written with tag 1.
+#[napi(catch_unwind, ts_args_type = "value: unknown")]
+pub fn cbor_encode(env: Env, value: JsUnknown) -> Result<Buffer> {
+  let value = to_cbor(from_js(&env, value, 0)?)?;
+  let mut out = Vec::new();
+  ciborium::into_writer(&value, &mut out).map_err(|e| AppError::Io(e.to_string()))?;
+  Ok(out.into())
+}
+
+#[napi(catch_unwind, ts_return_type = "unknown")]
+pub fn cbor_decode(env: Env, input: Buffer) -> Result<JsUnknown> {
+  let value: CborValue = ciborium::from_reader(&input[..]).map_err(|e| decode_error("CBOR", e))?;
+  Ok(to_js(&env, from_cbor(value)?)?)
+}
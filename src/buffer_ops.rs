@@ -0,0 +1,56 @@
+use napi::bindgen_prelude::*;
+use napi::{JsBuffer, JsObject, JsUnknown};
+
+use crate::error::Result;
+
+/// A copy of the bytes of `value`, which must be a Buffer.
+///
+/// `JsBuffer::into_value` builds a `Vec` straight from the data pointer,
+/// which may be null for an empty Buffer; debug builds then abort the
+/// process, past `catch_unwind`. Zero-length Buffers never get that far.
+pub(crate) fn buffer_bytes(value: &JsUnknown) -> Result<Vec<u8>> {
+  let len: u32 = unsafe { value.cast::<JsObject>() }.get_named_property("length")?;
+  if len == 0 {
+    return Ok(Vec::new());
+  }
+  Ok(unsafe { value.cast::<JsBuffer>() }.into_value()?.to_vec())
+}
+
+/// Returns a reversed copy of `input`.
+///
+/// The result is built in a Rust-owned `Vec<u8>` and handed to JS as an
+/// external Buffer, so there is no second copy on the way out.
+#[napi(catch_unwind)]
+pub fn reverse_buffer(input: Buffer) -> Buffer {
+  let mut out = input.to_vec();
+  out.reverse();
+  out.into()
+}
+
+/// Concatenates all `buffers` into a single Buffer.
+#[napi(catch_unwind)]
+pub fn concat_buffers(buffers: Vec<Buffer>) -> Buffer {
+  let total = buffers.iter().map(|b| b.len()).sum();
+  let mut out = Vec::with_capacity(total);
+  for buf in &buffers {
+    out.extend_from_slice(buf);
+  }
+  out.into()
+}
+
+/// Allocates a Buffer of `len` bytes, all set to `byte`.
+#[napi(catch_unwind)]
+pub fn fill_pattern(len: u32, byte: u8) -> Buffer {
+  vec![byte; len as usize].into()
+}
+
+/// Flips every bit of `data` in place.
+///
+/// `Uint8Array` borrows the JS-owned memory directly, so the caller sees the
+/// change without anything being copied in either direction.
+#[napi(catch_unwind)]
+pub fn invert_bytes(mut data: Uint8Array) {
+  for b in data.iter_mut() {
+    *b = !*b;
+  }
+}
@@ -0,0 +1,136 @@
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsUnknown};
+
+use crate::error::{AppError, Result};
+use crate::held::Held;
+
+struct Entry {
+  value: Held,
+  expires_at: Option<Instant>,
+}
+
+impl Entry {
+  fn expired(&self, now: Instant) -> bool {
+    self.expires_at.is_some_and(|at| at <= now)
+  }
+
+  fn release(self, env: Env) -> Result<()> {
+    self.value.release(env)
+  }
+}
+
+/// Fixed-capacity least-recently-used cache holding arbitrary JS values.
+///
+/// Lookups, inserts and evictions are O(1). Entries with a TTL are dropped
+/// lazily, the next time they are touched or when `size` is read.
+#[napi(custom_finalize)]
+pub struct LruCache {
+  entries: lru::LruCache<String, Entry>,
+}
+
+#[napi]
+impl LruCache {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(capacity: u32) -> Result<Self> {
+    let capacity = NonZeroUsize::new(capacity as usize)
+      .ok_or_else(|| AppError::InvalidInput("capacity must be greater than 0".into()))?;
+    Ok(LruCache {
+      entries: lru::LruCache::new(capacity),
+    })
+  }
+
+  /// Inserts or replaces `key`, evicting the least recently used entry if
+  /// the cache is full. With `ttl_ms` the entry expires after that long.
+  #[napi(catch_unwind)]
+  pub fn set(
+    &mut self,
+    env: Env,
+    key: String,
+    value: JsUnknown,
+    ttl_ms: Option<u32>,
+  ) -> Result<()> {
+    let entry = Entry {
+      value: Held::new(env, value)?,
+      expires_at: ttl_ms.map(|ms| Instant::now() + Duration::from_millis(ms as u64)),
+    };
+    // `push` hands back either the previous value for `key` or the evicted
+    // entry; both need their reference released.
+    if let Some((_, old)) = self.entries.push(key, entry) {
+      old.release(env)?;
+    }
+    Ok(())
+  }
+
+  /// Returns the value for `key` and marks it as most recently used, or
+  /// `undefined` if it is missing or expired.
+  #[napi(catch_unwind, ts_return_type = "unknown")]
+  pub fn get(&mut self, env: Env, key: String) -> Result<JsUnknown> {
+    if self
+      .entries
+      .peek(&key)
+      .is_some_and(|e| e.expired(Instant::now()))
+    {
+      self.delete(env, key.clone())?;
+    }
+    match self.entries.get(&key) {
+      Some(entry) => entry.value.get(env),
+      None => Ok(env.get_undefined()?.into_unknown()),
+    }
+  }
+
+  /// Whether `key` is present and not expired. Does not affect recency.
+  #[napi(catch_unwind)]
+  pub fn has(&self, key: String) -> bool {
+    self
+      .entries
+      .peek(&key)
+      .is_some_and(|e| !e.expired(Instant::now()))
+  }
+
+  /// Removes `key`. Returns whether it was present.
+  #[napi(catch_unwind)]
+  pub fn delete(&mut self, env: Env, key: String) -> Result<bool> {
+    match self.entries.pop(&key) {
+      Some(entry) => entry.release(env).map(|_| true),
+      None => Ok(false),
+    }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn clear(&mut self, env: Env) -> Result<()> {
+    while let Some((_, entry)) = self.entries.pop_lru() {
+      entry.release(env)?;
+    }
+    Ok(())
+  }
+
+  /// Number of live entries. Expired entries are purged first.
+  #[napi(catch_unwind, getter)]
+  pub fn size(&mut self, env: Env) -> Result<u32> {
+    let now = Instant::now();
+    let expired: Vec<String> = self
+      .entries
+      .iter()
+      .filter(|(_, e)| e.expired(now))
+      .map(|(k, _)| k.clone())
+      .collect();
+    for key in expired {
+      self.delete(env, key)?;
+    }
+    Ok(self.entries.len() as u32)
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn capacity(&self) -> u32 {
+    self.entries.cap().get() as u32
+  }
+}
+
+impl ObjectFinalize for LruCache {
+  fn finalize(mut self, env: Env) -> napi::Result<()> {
+    self.clear(env).map_err(napi::Error::from)
+  }
+}
@@ -0,0 +1,68 @@
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+use crate::hash::input_bytes;
+
+/// CRC-32 (IEEE, as used by zip, gzip and PNG). Uses PCLMULQDQ / ARMv8 CRC
+/// instructions when available.
+#[napi(catch_unwind)]
+pub fn crc32(input: Either<String, Buffer>) -> u32 {
+  crc32fast::hash(input_bytes(&input))
+}
+
+/// CRC-32C (Castagnoli, as used by iSCSI, ext4 and gRPC). Uses SSE 4.2 /
+/// ARMv8 CRC instructions when available.
+#[napi(catch_unwind, js_name = "crc32c")]
+pub fn crc32c(input: Either<String, Buffer>) -> u32 {
+  crc32c::crc32c(input_bytes(&input))
+}
+
+/// Adler-32 (as used by zlib), SIMD-accelerated.
+#[napi(catch_unwind)]
+pub fn adler32(input: Either<String, Buffer>) -> u32 {
+  simd_adler32::adler32(&input_bytes(&input))
+}
+
+/// Incremental CRC-32 for data that arrives in chunks, e.g. while a download
+/// is being written to disk.
+#[napi]
+pub struct Crc32Stream {
+  hasher: Option<crc32fast::Hasher>,
+}
+
+#[napi]
+impl Crc32Stream {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    Crc32Stream {
+      hasher: Some(crc32fast::Hasher::new()),
+    }
+  }
+
+  /// Feeds another chunk. Returns `this` for chaining.
+  #[napi(catch_unwind)]
+  pub fn update(&mut self, this: This, data: Either<String, Buffer>) -> Result<This> {
+    self
+      .hasher
+      .as_mut()
+      .ok_or_else(finalized)?
+      .update(input_bytes(&data));
+    Ok(this)
+  }
+
+  /// Returns the checksum. The stream can't be updated afterwards.
+  #[napi(catch_unwind)]
+  pub fn finalize(&mut self) -> Result<u32> {
+    Ok(self.hasher.take().ok_or_else(finalized)?.finalize())
+  }
+}
+
+impl Default for Crc32Stream {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn finalized() -> AppError {
+  AppError::Closed("checksum stream has already been finalized".into())
+}
@@ -0,0 +1,214 @@
+use fastcdc::v2020::{self, Normalization, MASKS};
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+
+use crate::error::{AppError, Result};
+
+const DEFAULT_AVG_SIZE: u32 = 16 * 1024;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ChunkOptions {
+  /// Smallest chunk except the last, 64 B to 1 MiB. Defaults to
+  /// `avgSize / 4`.
+  pub min_size: Option<u32>,
+  /// Target chunk size, 256 B to 4 MiB. Defaults to 16 KiB.
+  pub avg_size: Option<u32>,
+  /// Largest chunk, 1 KiB to 16 MiB. Defaults to `avgSize * 4`.
+  pub max_size: Option<u32>,
+  /// How tightly chunk sizes cluster around `avgSize`, 0 to 3. Defaults
+  /// to 1.
+  pub normalization: Option<u32>,
+}
+
+#[napi(object)]
+pub struct ChunkInfo {
+  /// Byte offset of the chunk from the start of the input.
+  pub offset: f64,
+  pub length: u32,
+  /// BLAKE3 of the chunk's contents, as hex: equal chunks have equal
+  /// hashes wherever they occur.
+  pub hash: String,
+  /// The chunk's bytes. Only set by `Chunker`, whose input isn't kept.
+  pub data: Option<Buffer>,
+}
+
+/// Validated sizes and the masks FastCDC derives from them.
+struct Params {
+  min_size: usize,
+  avg_size: usize,
+  max_size: usize,
+  mask_s: u64,
+  mask_l: u64,
+}
+
+impl Params {
+  fn from_options(options: &ChunkOptions) -> Result<Self> {
+    let avg_size = options.avg_size.unwrap_or(DEFAULT_AVG_SIZE);
+    let min_size = options.min_size.unwrap_or(avg_size / 4);
+    let max_size = options.max_size.unwrap_or(avg_size.saturating_mul(4));
+    let in_range = |name: &str, value: u32, min: u32, max: u32| {
+      if (min..=max).contains(&value) {
+        Ok(())
+      } else {
+        Err(AppError::InvalidInput(format!(
+          "{} must be {} to {}, got {}",
+          name, min, max, value
+        )))
+      }
+    };
+    in_range("avgSize", avg_size, v2020::AVERAGE_MIN, v2020::AVERAGE_MAX)?;
+    in_range("minSize", min_size, v2020::MINIMUM_MIN, v2020::MINIMUM_MAX)?;
+    in_range("maxSize", max_size, v2020::MAXIMUM_MIN, v2020::MAXIMUM_MAX)?;
+    if min_size > avg_size || avg_size > max_size {
+      return Err(AppError::InvalidInput(format!(
+        "need minSize <= avgSize <= maxSize, got {} / {} / {}",
+        min_size, avg_size, max_size
+      )));
+    }
+    let normalization = match options.normalization.unwrap_or(1) {
+      0 => Normalization::Level0,
+      1 => Normalization::Level1,
+      2 => Normalization::Level2,
+      3 => Normalization::Level3,
+      other => {
+        return Err(AppError::InvalidInput(format!(
+          "normalization must be 0 to 3, got {}",
+          other
+        )))
+      }
+    };
+    let bits = v2020::logarithm2(avg_size);
+    Ok(Params {
+      min_size: min_size as usize,
+      avg_size: avg_size as usize,
+      max_size: max_size as usize,
+      mask_s: MASKS[(bits + normalization.bits()) as usize],
+      mask_l: MASKS[(bits - normalization.bits()) as usize],
+    })
+  }
+
+  /// Length of the chunk at the start of `data`, which must hold either
+  /// `max_size` bytes or the rest of the input: FastCDC looks for cut
+  /// points differently when less than that remains.
+  fn cut(&self, data: &[u8]) -> usize {
+    v2020::cut(
+      data,
+      self.min_size,
+      self.avg_size,
+      self.max_size,
+      self.mask_s,
+      self.mask_l,
+      self.mask_s << 1,
+      self.mask_l << 1,
+    )
+    .1
+  }
+}
+
+/// Hashes the chunks `lengths` splits `data` into, in parallel. `base` is
+/// the offset of `data` in the whole input.
+fn describe(data: &[u8], lengths: &[usize], base: u64, with_data: bool) -> Vec<ChunkInfo> {
+  let mut starts = Vec::with_capacity(lengths.len());
+  let mut start = 0;
+  for &length in lengths {
+    starts.push(start);
+    start += length;
+  }
+  starts
+    .into_par_iter()
+    .zip(lengths.par_iter())
+    .map(|(start, &length)| {
+      let chunk = &data[start..start + length];
+      ChunkInfo {
+        offset: (base + start as u64) as f64,
+        length: length as u32,
+        hash: blake3::hash(chunk).to_hex().to_string(),
+        data: with_data.then(|| chunk.to_vec().into()),
+      }
+    })
+    .collect()
+}
+
+/// Splits `data` at content-defined boundaries with FastCDC and returns
+/// each chunk's offset, length and hash. Boundaries depend only on nearby
+/// bytes, so an insertion or deletion changes the chunks around it and
+/// leaves the rest, and their hashes, as they were.
+#[napi(catch_unwind)]
+pub fn chunk_buffer(data: Buffer, options: Option<ChunkOptions>) -> Result<Vec<ChunkInfo>> {
+  let params = Params::from_options(&options.unwrap_or_default())?;
+  let mut lengths = Vec::new();
+  let mut start = 0;
+  while start < data.len() {
+    let length = params.cut(&data[start..]);
+    lengths.push(length);
+    start += length;
+  }
+  Ok(describe(&data, &lengths, 0, false))
+}
+
+/// Content-defined chunking over a stream: the same chunks `chunkBuffer`
+/// would find in the concatenated input, with their bytes, at most
+/// `maxSize` bytes behind the input.
+#[napi]
+pub struct Chunker {
+  params: Params,
+  pending: Vec<u8>,
+  /// Offset of `pending` in the stream.
+  offset: u64,
+}
+
+#[napi]
+impl Chunker {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<ChunkOptions>) -> Result<Self> {
+    Ok(Chunker {
+      params: Params::from_options(&options.unwrap_or_default())?,
+      pending: Vec::new(),
+      offset: 0,
+    })
+  }
+
+  /// Returns the chunks completed by `data`.
+  #[napi(catch_unwind)]
+  pub fn push(&mut self, data: Buffer) -> Vec<ChunkInfo> {
+    self.pending.extend_from_slice(&data);
+    let mut lengths = Vec::new();
+    let mut start = 0;
+    while self.pending.len() - start >= self.params.max_size {
+      let length = self.params.cut(&self.pending[start..]);
+      lengths.push(length);
+      start += length;
+    }
+    self.take(start, &lengths)
+  }
+
+  /// Returns the chunks left at the end of the stream and resets the
+  /// chunker for a new one.
+  #[napi(catch_unwind)]
+  pub fn end(&mut self) -> Vec<ChunkInfo> {
+    let mut lengths = Vec::new();
+    let mut start = 0;
+    while start < self.pending.len() {
+      let length = self.params.cut(&self.pending[start..]);
+      lengths.push(length);
+      start += length;
+    }
+    let chunks = self.take(start, &lengths);
+    self.offset = 0;
+    chunks
+  }
+
+  /// Bytes received but not yet part of a returned chunk.
+  #[napi(catch_unwind, getter)]
+  pub fn buffered(&self) -> u32 {
+    self.pending.len() as u32
+  }
+
+  fn take(&mut self, consumed: usize, lengths: &[usize]) -> Vec<ChunkInfo> {
+    let chunks = describe(&self.pending[..consumed], lengths, self.offset, true);
+    self.pending.drain(..consumed);
+    self.offset += consumed as u64;
+    chunks
+  }
+}
@@ -0,0 +1,60 @@
+use crate::error::{AppError, Result};
+
+/// A simple stateful class: a signed counter with a getter/setter pair and a
+/// static factory.
+#[napi]
+pub struct Counter {
+  value: i64,
+}
+
+#[napi]
+impl Counter {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    Counter { value: 0 }
+  }
+
+  /// `Counter.from(initial)` — starts counting at `initial` instead of 0.
+  #[napi(catch_unwind, factory)]
+  pub fn from(initial: i64) -> Self {
+    Counter { value: initial }
+  }
+
+  /// Adds one and returns the new value. Throws `ERR_OVERFLOW` past the
+  /// i64 range.
+  #[napi(catch_unwind)]
+  pub fn increment(&mut self) -> Result<i64> {
+    self.value = self
+      .value
+      .checked_add(1)
+      .ok_or_else(|| AppError::Overflow(format!("{} + 1 overflows i64", self.value)))?;
+    Ok(self.value)
+  }
+
+  /// Subtracts one and returns the new value. Throws `ERR_OVERFLOW` past
+  /// the i64 range.
+  #[napi(catch_unwind)]
+  pub fn decrement(&mut self) -> Result<i64> {
+    self.value = self
+      .value
+      .checked_sub(1)
+      .ok_or_else(|| AppError::Overflow(format!("{} - 1 overflows i64", self.value)))?;
+    Ok(self.value)
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn value(&self) -> i64 {
+    self.value
+  }
+
+  #[napi(catch_unwind, setter)]
+  pub fn set_value(&mut self, value: i64) {
+    self.value = value;
+  }
+}
+
+impl Default for Counter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
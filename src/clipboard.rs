@@ -0,0 +1,124 @@
+use std::sync::Mutex;
+
+use arboard::Clipboard;
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use napi::bindgen_prelude::*;
+use napi::Env;
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// One connection for the life of the process. On X11 the contents written
+/// are served by the owning connection, so a per-call `Clipboard` would lose
+/// them as soon as it was dropped (unless a clipboard manager copies them).
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+fn clipboard_error(e: arboard::Error) -> AppError {
+  match e {
+    arboard::Error::ClipboardNotSupported => AppError::Unsupported(format!("clipboard: {}", e)),
+    arboard::Error::ConversionFailure => AppError::Parse(format!("clipboard: {}", e)),
+    _ => AppError::Io(format!("clipboard: {}", e)),
+  }
+}
+
+/// Runs `f` on the shared connection, opening it first if needed. A failed
+/// open isn't remembered, so a display that appears later is picked up.
+fn with_clipboard<T>(f: impl FnOnce(&mut Clipboard) -> Result<T>) -> Result<T> {
+  let mut guard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+  if guard.is_none() {
+    // Without a display arboard spends a while trying to reach one before
+    // failing with an opaque error.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if std::env::var_os("DISPLAY").is_none_or(|display| display.is_empty()) {
+      return Err(AppError::Unsupported(
+        "clipboard: no X11 display (DISPLAY is not set)".into(),
+      ));
+    }
+    *guard = Some(Clipboard::new().map_err(clipboard_error)?);
+  }
+  f(guard.as_mut().expect("opened above"))
+}
+
+fn read_text() -> Result<String> {
+  with_clipboard(|clipboard| match clipboard.get_text() {
+    Ok(text) => Ok(text),
+    Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+    Err(e) => Err(clipboard_error(e)),
+  })
+}
+
+fn read_image() -> Result<Option<Vec<u8>>> {
+  let image = with_clipboard(|clipboard| match clipboard.get_image() {
+    Ok(image) => Ok(Some(image)),
+    Err(arboard::Error::ContentNotAvailable) => Ok(None),
+    Err(e) => Err(clipboard_error(e)),
+  })?;
+  let Some(image) = image else {
+    return Ok(None);
+  };
+  let mut png = Vec::new();
+  PngEncoder::new(&mut png)
+    .write_image(
+      &image.bytes,
+      image.width as u32,
+      image.height as u32,
+      ExtendedColorType::Rgba8,
+    )
+    .map_err(|e| AppError::Parse(format!("clipboard image: {}", e)))?;
+  Ok(Some(png))
+}
+
+/// Reads the clipboard text on the libuv thread pool; talking to the
+/// display server can block for a while when another app owns the contents.
+pub struct ReadClipboardTextTask;
+
+impl Task for ReadClipboardTextTask {
+  type Output = Result<String>;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(read_text())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+pub struct ReadClipboardImageTask;
+
+impl Task for ReadClipboardImageTask {
+  type Output = Result<Option<Vec<u8>>>;
+  type JsValue = Option<Buffer>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(read_image())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output
+      .map(|png| png.map(Buffer::from))
+      .map_err(|e| e.into_napi(env))
+  }
+}
+
+/// The clipboard's text, or `""` when it holds none. Rejects with
+/// `ERR_UNSUPPORTED` where there is no clipboard, e.g. a Linux session
+/// without X11.
+#[napi(catch_unwind, ts_return_type = "Promise<string>")]
+pub fn read_clipboard_text() -> AsyncTask<Guarded<ReadClipboardTextTask>> {
+  panic_guard::task(ReadClipboardTextTask)
+}
+
+/// Replaces the clipboard contents with `text`.
+#[napi(catch_unwind)]
+pub fn write_clipboard_text(text: String) -> Result<()> {
+  with_clipboard(|clipboard| clipboard.set_text(text).map_err(clipboard_error))
+}
+
+/// The clipboard's image encoded as PNG, or `null` when it holds none.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer | null>")]
+pub fn read_clipboard_image() -> AsyncTask<Guarded<ReadClipboardImageTask>> {
+  panic_guard::task(ReadClipboardImageTask)
+}
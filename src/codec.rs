@@ -0,0 +1,52 @@
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+fn decode_error(codec: &str, e: impl std::fmt::Display) -> AppError {
+  AppError::Parse(format!("invalid {}: {}", codec, e))
+}
+
+/// Standard, padded base64. Uses SIMD where the CPU supports it.
+#[napi(catch_unwind)]
+pub fn base64_encode(input: Buffer) -> String {
+  base64_simd::STANDARD.encode_to_string(&input)
+}
+
+/// Decodes standard base64. Like `atob`, padding is optional and ASCII
+/// whitespace is ignored.
+#[napi(catch_unwind)]
+pub fn base64_decode(input: String) -> Result<Buffer> {
+  base64_simd::forgiving_decode_to_vec(input.as_bytes())
+    .map(Buffer::from)
+    .map_err(|e| decode_error("base64", e))
+}
+
+/// Lower-case hex. Uses SIMD where the CPU supports it.
+#[napi(catch_unwind)]
+pub fn hex_encode(input: Buffer) -> String {
+  hex_simd::encode_to_string(&input, hex_simd::AsciiCase::Lower)
+}
+
+/// Decodes hex in either case.
+#[napi(catch_unwind)]
+pub fn hex_decode(input: String) -> Result<Buffer> {
+  hex_simd::decode_to_vec(input.as_bytes())
+    .map(Buffer::from)
+    .map_err(|e| decode_error("hex", e))
+}
+
+/// Base58 with the Bitcoin alphabet. There is no SIMD variant: base58 is a
+/// big-number conversion rather than a lookup, so it's only practical for
+/// short inputs like keys and hashes.
+#[napi(catch_unwind)]
+pub fn base58_encode(input: Buffer) -> String {
+  bs58::encode(&input[..]).into_string()
+}
+
+#[napi(catch_unwind)]
+pub fn base58_decode(input: String) -> Result<Buffer> {
+  bs58::decode(&input)
+    .into_vec()
+    .map(Buffer::from)
+    .map_err(|e| decode_error("base58", e))
+}
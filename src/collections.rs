@@ -0,0 +1,370 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ops::Bound;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+
+use crate::error::{AppError, Result};
+use crate::held::Held;
+
+/// A number or string key. Numbers sort before strings; strings sort by
+/// UTF-8 bytes, which matches code point order.
+#[derive(Clone, Debug)]
+enum Key {
+  Num(f64),
+  Str(String),
+}
+
+impl Key {
+  fn from_js(key: Either<f64, String>) -> Result<Self> {
+    match key {
+      Either::A(n) if n.is_nan() => Err(AppError::InvalidInput("key must not be NaN".into())),
+      // Normalize -0 so it is the same key as 0.
+      Either::A(n) => Ok(Key::Num(n + 0.0)),
+      Either::B(s) => Ok(Key::Str(s)),
+    }
+  }
+
+  fn to_js(&self, env: Env) -> Result<JsUnknown> {
+    Ok(match self {
+      Key::Num(n) => env.create_double(*n)?.into_unknown(),
+      Key::Str(s) => env.create_string(s)?.into_unknown(),
+    })
+  }
+}
+
+impl Ord for Key {
+  fn cmp(&self, other: &Self) -> Ordering {
+    match (self, other) {
+      (Key::Num(a), Key::Num(b)) => a.total_cmp(b),
+      (Key::Num(_), Key::Str(_)) => Ordering::Less,
+      (Key::Str(_), Key::Num(_)) => Ordering::Greater,
+      (Key::Str(a), Key::Str(b)) => a.cmp(b),
+    }
+  }
+}
+
+impl PartialOrd for Key {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for Key {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Key {}
+
+fn undefined(env: Env) -> Result<JsUnknown> {
+  Ok(env.get_undefined()?.into_unknown())
+}
+
+/// Builds a `[key, value]` pair.
+fn pair(env: Env, key: &Key, value: &Held) -> Result<JsObject> {
+  let mut pair = env.create_array_with_length(2)?;
+  pair.set_element(0, key.to_js(env)?)?;
+  pair.set_element(1, value.get(env)?)?;
+  Ok(pair)
+}
+
+fn array(env: Env, items: Vec<JsUnknown>) -> Result<JsObject> {
+  let mut array = env.create_array_with_length(items.len())?;
+  for (i, item) in items.into_iter().enumerate() {
+    array.set_element(i as u32, item)?;
+  }
+  Ok(array)
+}
+
+struct QueueItem {
+  priority: Key,
+  /// Insertion order, so equal priorities pop first-in first-out.
+  seq: u64,
+  value: Held,
+  max_first: bool,
+}
+
+impl QueueItem {
+  /// `BinaryHeap` pops the greatest item, so "greater" means "pops sooner".
+  fn rank(&self, other: &Self) -> Ordering {
+    let by_priority = if self.max_first {
+      self.priority.cmp(&other.priority)
+    } else {
+      other.priority.cmp(&self.priority)
+    };
+    by_priority.then(other.seq.cmp(&self.seq))
+  }
+}
+
+impl Ord for QueueItem {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.rank(other)
+  }
+}
+
+impl PartialOrd for QueueItem {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for QueueItem {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for QueueItem {}
+
+/// Binary-heap priority queue of JS values. The lowest priority pops first
+/// unless constructed with `maxFirst`; equal priorities pop in insertion
+/// order.
+#[napi(custom_finalize)]
+pub struct PriorityQueue {
+  heap: BinaryHeap<QueueItem>,
+  max_first: bool,
+  next_seq: u64,
+}
+
+#[napi]
+impl PriorityQueue {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(max_first: Option<bool>) -> Self {
+    PriorityQueue {
+      heap: BinaryHeap::new(),
+      max_first: max_first.unwrap_or(false),
+      next_seq: 0,
+    }
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "value: unknown, priority: number | string"
+  )]
+  pub fn push(&mut self, env: Env, value: JsUnknown, priority: Either<f64, String>) -> Result<()> {
+    let priority = Key::from_js(priority)?;
+    let item = QueueItem {
+      priority,
+      seq: self.next_seq,
+      value: Held::new(env, value)?,
+      max_first: self.max_first,
+    };
+    self.next_seq += 1;
+    self.heap.push(item);
+    Ok(())
+  }
+
+  /// Removes and returns the next value, or `undefined` if empty.
+  #[napi(catch_unwind, ts_return_type = "unknown")]
+  pub fn pop(&mut self, env: Env) -> Result<JsUnknown> {
+    match self.heap.pop() {
+      Some(item) => {
+        let value = item.value.get(env)?;
+        item.value.release(env)?;
+        Ok(value)
+      }
+      None => undefined(env),
+    }
+  }
+
+  /// Returns the next value without removing it, or `undefined` if empty.
+  #[napi(catch_unwind, ts_return_type = "unknown")]
+  pub fn peek(&self, env: Env) -> Result<JsUnknown> {
+    match self.heap.peek() {
+      Some(item) => item.value.get(env),
+      None => undefined(env),
+    }
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.heap.len() as u32
+  }
+
+  /// All values in the order they would pop, without removing them.
+  #[napi(catch_unwind, ts_return_type = "unknown[]")]
+  pub fn to_array(&self, env: Env) -> Result<JsObject> {
+    let mut items: Vec<&QueueItem> = self.heap.iter().collect();
+    items.sort_by(|a, b| b.cmp(a));
+    let values = items
+      .iter()
+      .map(|item| item.value.get(env))
+      .collect::<Result<_>>()?;
+    array(env, values)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn clear(&mut self, env: Env) -> Result<()> {
+    for item in self.heap.drain() {
+      item.value.release(env)?;
+    }
+    Ok(())
+  }
+}
+
+impl ObjectFinalize for PriorityQueue {
+  fn finalize(mut self, env: Env) -> napi::Result<()> {
+    self.clear(env).map_err(napi::Error::from)
+  }
+}
+
+/// Ordered map from number or string keys to JS values, backed by a B-tree.
+/// Numbers sort before strings.
+#[napi(custom_finalize)]
+pub struct SortedMap {
+  entries: BTreeMap<Key, Held>,
+}
+
+type KeyArg = Option<Either<f64, String>>;
+
+#[napi]
+impl SortedMap {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    SortedMap {
+      entries: BTreeMap::new(),
+    }
+  }
+
+  #[napi(catch_unwind, ts_args_type = "key: number | string, value: unknown")]
+  pub fn set(&mut self, env: Env, key: Either<f64, String>, value: JsUnknown) -> Result<()> {
+    let key = Key::from_js(key)?;
+    let held = Held::new(env, value)?;
+    if let Some(old) = self.entries.insert(key, held) {
+      old.release(env)?;
+    }
+    Ok(())
+  }
+
+  /// The value for `key`, or `undefined` if it is missing.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "key: number | string",
+    ts_return_type = "unknown"
+  )]
+  pub fn get(&self, env: Env, key: Either<f64, String>) -> Result<JsUnknown> {
+    match self.entries.get(&Key::from_js(key)?) {
+      Some(value) => value.get(env),
+      None => undefined(env),
+    }
+  }
+
+  #[napi(catch_unwind, ts_args_type = "key: number | string")]
+  pub fn has(&self, key: Either<f64, String>) -> Result<bool> {
+    Ok(self.entries.contains_key(&Key::from_js(key)?))
+  }
+
+  /// Removes `key`. Returns whether it was present.
+  #[napi(catch_unwind, ts_args_type = "key: number | string")]
+  pub fn delete(&mut self, env: Env, key: Either<f64, String>) -> Result<bool> {
+    match self.entries.remove(&Key::from_js(key)?) {
+      Some(value) => value.release(env).map(|_| true),
+      None => Ok(false),
+    }
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.entries.len() as u32
+  }
+
+  /// The `[key, value]` pair with the smallest key, or `undefined`.
+  #[napi(
+    catch_unwind,
+    ts_return_type = "[number | string, unknown] | undefined"
+  )]
+  pub fn first(&self, env: Env) -> Result<JsUnknown> {
+    match self.entries.first_key_value() {
+      Some((key, value)) => Ok(pair(env, key, value)?.into_unknown()),
+      None => undefined(env),
+    }
+  }
+
+  /// The `[key, value]` pair with the largest key, or `undefined`.
+  #[napi(
+    catch_unwind,
+    ts_return_type = "[number | string, unknown] | undefined"
+  )]
+  pub fn last(&self, env: Env) -> Result<JsUnknown> {
+    match self.entries.last_key_value() {
+      Some((key, value)) => Ok(pair(env, key, value)?.into_unknown()),
+      None => undefined(env),
+    }
+  }
+
+  /// `[key, value]` pairs with `from <= key < to`, in key order. Either bound
+  /// may be omitted (or `null`) to leave that side open.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "from?: number | string | null, to?: number | string | null",
+    ts_return_type = "Array<[number | string, unknown]>"
+  )]
+  pub fn range(&self, env: Env, from: KeyArg, to: KeyArg) -> Result<JsObject> {
+    let lower = match from {
+      Some(key) => Bound::Included(Key::from_js(key)?),
+      None => Bound::Unbounded,
+    };
+    let upper = match to {
+      Some(key) => Bound::Excluded(Key::from_js(key)?),
+      None => Bound::Unbounded,
+    };
+    if let (Bound::Included(lo), Bound::Excluded(hi)) = (&lower, &upper) {
+      if lo >= hi {
+        return array(env, Vec::new());
+      }
+    }
+    let pairs = self
+      .entries
+      .range((lower, upper))
+      .map(|(key, value)| Ok(pair(env, key, value)?.into_unknown()))
+      .collect::<Result<_>>()?;
+    array(env, pairs)
+  }
+
+  /// All `[key, value]` pairs in key order.
+  #[napi(catch_unwind, ts_return_type = "Array<[number | string, unknown]>")]
+  pub fn to_array(&self, env: Env) -> Result<JsObject> {
+    self.range(env, None, None)
+  }
+
+  /// Calls `callback(value, key)` for each entry in key order. Iterates over
+  /// a snapshot, so the callback may modify the map.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (value: unknown, key: number | string) => void"
+  )]
+  pub fn for_each(&self, env: Env, callback: JsFunction) -> Result<()> {
+    let snapshot = self
+      .entries
+      .iter()
+      .map(|(key, value)| Ok((key.to_js(env)?, value.get(env)?)))
+      .collect::<Result<Vec<_>>>()?;
+    for (key, value) in snapshot {
+      callback.call(None, &[value, key])?;
+    }
+    Ok(())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn clear(&mut self, env: Env) -> Result<()> {
+    while let Some((_, value)) = self.entries.pop_first() {
+      value.release(env)?;
+    }
+    Ok(())
+  }
+}
+
+impl Default for SortedMap {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ObjectFinalize for SortedMap {
+  fn finalize(mut self, env: Env) -> napi::Result<()> {
+    self.clear(env).map_err(napi::Error::from)
+  }
+}
@@ -0,0 +1,430 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// An sRGB color. Channels are 0–255 and `alpha` is 0–1.
+#[napi(object)]
+pub struct Rgba {
+  pub r: u32,
+  pub g: u32,
+  pub b: u32,
+  pub alpha: f64,
+}
+
+/// Hue in degrees (0–360), saturation and lightness in percent (0–100).
+#[napi(object)]
+pub struct Hsl {
+  pub h: f64,
+  pub s: f64,
+  pub l: f64,
+  pub alpha: f64,
+}
+
+/// CIELAB under the D65 white point: lightness 0–100, `a` green–red and `b`
+/// blue–yellow, both roughly ±128.
+#[napi(object)]
+pub struct Lab {
+  pub l: f64,
+  pub a: f64,
+  pub b: f64,
+  pub alpha: f64,
+}
+
+/// OKLCH: lightness 0–1, chroma from 0 (grey) to about 0.4, hue in degrees.
+#[napi(object)]
+pub struct Oklch {
+  pub l: f64,
+  pub c: f64,
+  pub h: f64,
+  pub alpha: f64,
+}
+
+/// Unclamped sRGB with channels and alpha in 0–1, which the conversions work
+/// in before quantizing back to `Rgba`.
+#[derive(Clone, Copy)]
+struct Srgb([f64; 3], f64);
+
+impl Srgb {
+  fn from_rgba(c: &Rgba) -> Result<Self> {
+    if c.r > 255 || c.g > 255 || c.b > 255 || !(0.0..=1.0).contains(&c.alpha) {
+      return Err(AppError::InvalidInput(
+        "r, g and b must be 0–255 and alpha 0–1".into(),
+      ));
+    }
+    Ok(Srgb(
+      [c.r as f64 / 255.0, c.g as f64 / 255.0, c.b as f64 / 255.0],
+      c.alpha,
+    ))
+  }
+
+  /// Out-of-gamut colors are clamped channel by channel.
+  fn to_rgba(self) -> Rgba {
+    let [r, g, b] = self.0.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u32);
+    Rgba {
+      r,
+      g,
+      b,
+      alpha: self.1.clamp(0.0, 1.0),
+    }
+  }
+
+  fn to_hex(self) -> String {
+    let c = self.to_rgba();
+    let mut hex = format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
+    if c.alpha < 1.0 {
+      hex.push_str(&format!("{:02x}", (c.alpha * 255.0).round() as u8));
+    }
+    hex
+  }
+
+  fn linear(self) -> [f64; 3] {
+    self.0.map(|c| {
+      if c <= 0.04045 {
+        c / 12.92
+      } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+      }
+    })
+  }
+
+  fn from_linear(rgb: [f64; 3], alpha: f64) -> Self {
+    Srgb(
+      rgb.map(|c| {
+        if c <= 0.0031308 {
+          c * 12.92
+        } else {
+          1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+      }),
+      alpha,
+    )
+  }
+
+  /// Relative luminance as defined by WCAG 2.
+  fn luminance(self) -> f64 {
+    let [r, g, b] = self.linear();
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+  }
+
+  fn oklab(self) -> [f64; 3] {
+    let [r, g, b] = self.linear();
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+      0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+      1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+      0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+  }
+
+  fn from_oklab([l, a, b]: [f64; 3], alpha: f64) -> Self {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    Srgb::from_linear(
+      [
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+      ],
+      alpha,
+    )
+  }
+}
+
+/// D65 reference white.
+const WHITE: [f64; 3] = [0.95047, 1.0, 1.08883];
+const EPSILON: f64 = 6.0 / 29.0;
+
+fn parse(css: &str) -> Result<Srgb> {
+  let c = csscolorparser::parse(css)
+    .map_err(|e| AppError::InvalidInput(format!("invalid color {:?}: {}", css, e)))?;
+  Ok(Srgb([c.r as f64, c.g as f64, c.b as f64], c.a as f64))
+}
+
+fn check_alpha(alpha: f64) -> Result<()> {
+  if !(0.0..=1.0).contains(&alpha) {
+    return Err(AppError::InvalidInput("alpha must be 0–1".into()));
+  }
+  Ok(())
+}
+
+/// Parses any CSS color: hex (`#rgb`, `#rrggbbaa`), a named color, or the
+/// `rgb()`, `hsl()`, `hwb()`, `lab()`, `lch()`, `oklab()` and `oklch()`
+/// functions.
+#[napi(catch_unwind)]
+pub fn parse_color(css: String) -> Result<Rgba> {
+  Ok(parse(&css)?.to_rgba())
+}
+
+/// Formats a color as `#rrggbb`, or `#rrggbbaa` when it is translucent.
+#[napi(catch_unwind)]
+pub fn to_hex_color(color: Rgba) -> Result<String> {
+  Ok(Srgb::from_rgba(&color)?.to_hex())
+}
+
+#[napi(catch_unwind)]
+pub fn rgb_to_hsl(color: Rgba) -> Result<Hsl> {
+  let Srgb([r, g, b], alpha) = Srgb::from_rgba(&color)?;
+  let max = r.max(g).max(b);
+  let min = r.min(g).min(b);
+  let l = (max + min) / 2.0;
+  let d = max - min;
+  let (h, s) = if d == 0.0 {
+    (0.0, 0.0)
+  } else {
+    let h = if max == r {
+      (g - b) / d + if g < b { 6.0 } else { 0.0 }
+    } else if max == g {
+      (b - r) / d + 2.0
+    } else {
+      (r - g) / d + 4.0
+    };
+    (h * 60.0, d / (1.0 - (2.0 * l - 1.0).abs()))
+  };
+  Ok(Hsl {
+    h,
+    s: s * 100.0,
+    l: l * 100.0,
+    alpha,
+  })
+}
+
+#[napi(catch_unwind)]
+pub fn hsl_to_rgb(color: Hsl) -> Result<Rgba> {
+  check_alpha(color.alpha)?;
+  let h = color.h.rem_euclid(360.0) / 60.0;
+  let s = (color.s / 100.0).clamp(0.0, 1.0);
+  let l = (color.l / 100.0).clamp(0.0, 1.0);
+  let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+  let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+  let [r, g, b] = match h as u32 {
+    0 => [c, x, 0.0],
+    1 => [x, c, 0.0],
+    2 => [0.0, c, x],
+    3 => [0.0, x, c],
+    4 => [x, 0.0, c],
+    _ => [c, 0.0, x],
+  };
+  let m = l - c / 2.0;
+  Ok(Srgb([r + m, g + m, b + m], color.alpha).to_rgba())
+}
+
+#[napi(catch_unwind)]
+pub fn rgb_to_lab(color: Rgba) -> Result<Lab> {
+  let srgb = Srgb::from_rgba(&color)?;
+  let [r, g, b] = srgb.linear();
+  let xyz = [
+    0.4124564 * r + 0.3575761 * g + 0.1804375 * b,
+    0.2126729 * r + 0.7151522 * g + 0.0721750 * b,
+    0.0193339 * r + 0.1191920 * g + 0.9503041 * b,
+  ];
+  let [fx, fy, fz] = [0, 1, 2].map(|i| {
+    let t = xyz[i] / WHITE[i];
+    if t > EPSILON.powi(3) {
+      t.cbrt()
+    } else {
+      t / (3.0 * EPSILON * EPSILON) + 4.0 / 29.0
+    }
+  });
+  Ok(Lab {
+    l: 116.0 * fy - 16.0,
+    a: 500.0 * (fx - fy),
+    b: 200.0 * (fy - fz),
+    alpha: srgb.1,
+  })
+}
+
+#[napi(catch_unwind)]
+pub fn lab_to_rgb(color: Lab) -> Result<Rgba> {
+  check_alpha(color.alpha)?;
+  let fy = (color.l + 16.0) / 116.0;
+  let f = [fy + color.a / 500.0, fy, fy - color.b / 200.0];
+  let [x, y, z] = [0, 1, 2].map(|i| {
+    let t = f[i];
+    WHITE[i]
+      * if t > EPSILON {
+        t.powi(3)
+      } else {
+        3.0 * EPSILON * EPSILON * (t - 4.0 / 29.0)
+      }
+  });
+  let linear = [
+    3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+    -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+    0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+  ];
+  Ok(Srgb::from_linear(linear, color.alpha).to_rgba())
+}
+
+#[napi(catch_unwind)]
+pub fn rgb_to_oklch(color: Rgba) -> Result<Oklch> {
+  let srgb = Srgb::from_rgba(&color)?;
+  let [l, a, b] = srgb.oklab();
+  let c = a.hypot(b);
+  // Greys have no meaningful hue; report 0 rather than rounding noise.
+  let h = if c < 1e-6 {
+    0.0
+  } else {
+    b.atan2(a).to_degrees().rem_euclid(360.0)
+  };
+  Ok(Oklch {
+    l,
+    c,
+    h,
+    alpha: srgb.1,
+  })
+}
+
+#[napi(catch_unwind)]
+pub fn oklch_to_rgb(color: Oklch) -> Result<Rgba> {
+  check_alpha(color.alpha)?;
+  let h = color.h.to_radians();
+  let lab = [color.l, color.c * h.cos(), color.c * h.sin()];
+  Ok(Srgb::from_oklab(lab, color.alpha).to_rgba())
+}
+
+/// WCAG 2 contrast ratio (1–21) between a text color and its background.
+/// A translucent foreground is composited over the background first; the
+/// background's own alpha is ignored.
+#[napi(catch_unwind)]
+pub fn contrast_ratio(foreground: String, background: String) -> Result<f64> {
+  let fg = parse(&foreground)?;
+  let bg = parse(&background)?;
+  let mut blended = fg;
+  for i in 0..3 {
+    blended.0[i] = fg.0[i] * fg.1 + bg.0[i] * (1.0 - fg.1);
+  }
+  let (a, b) = (blended.luminance(), bg.luminance());
+  Ok((a.max(b) + 0.05) / (a.min(b) + 0.05))
+}
+
+/// Blends two CSS colors, from `a` at `t = 0` to `b` at `t = 1`. The
+/// interpolation happens in OKLab, which keeps perceived lightness even
+/// (no muddy midpoints) as CSS `color-mix()` does by default.
+#[napi(catch_unwind)]
+pub fn mix(a: String, b: String, t: f64) -> Result<String> {
+  if !(0.0..=1.0).contains(&t) {
+    return Err(AppError::InvalidInput(format!("t must be 0–1, got {}", t)));
+  }
+  let (a, b) = (parse(&a)?, parse(&b)?);
+  let (la, lb) = (a.oklab(), b.oklab());
+  let lab = [0, 1, 2].map(|i| la[i] + (lb[i] - la[i]) * t);
+  Ok(Srgb::from_oklab(lab, a.1 + (b.1 - a.1) * t).to_hex())
+}
+
+/// Median cut: repeatedly splits the box of pixels with the widest channel
+/// range near its median until there are `count` boxes, then averages each.
+fn median_cut(mut pixels: Vec<[u8; 3]>, count: usize) -> Vec<String> {
+  let range = |px: &[[u8; 3]]| {
+    (0..3)
+      .map(|c| {
+        let (lo, hi) = px
+          .iter()
+          .fold((u8::MAX, 0), |(lo, hi), p| (lo.min(p[c]), hi.max(p[c])));
+        (hi.saturating_sub(lo), c)
+      })
+      .max()
+      .expect("three channels")
+  };
+  let mut boxes: Vec<(u8, usize, Vec<[u8; 3]>)> = Vec::new();
+  if !pixels.is_empty() {
+    let (spread, channel) = range(&pixels);
+    boxes.push((spread, channel, std::mem::take(&mut pixels)));
+  }
+  while boxes.len() < count {
+    let Some((index, _)) = boxes
+      .iter()
+      .enumerate()
+      .filter(|(_, (spread, _, _))| *spread > 0)
+      .max_by_key(|(_, (spread, _, px))| (*spread as usize) * px.len())
+    else {
+      break;
+    };
+    let (_, channel, mut px) = boxes.swap_remove(index);
+    px.sort_unstable_by_key(|p| p[channel]);
+    // Cut where the channel value changes so that identical pixels stay in
+    // one box; the spread is non-zero, so one side of the median has a cut.
+    let median = px[px.len() / 2][channel];
+    let mut at = px.partition_point(|p| p[channel] < median);
+    if at == 0 {
+      at = px.partition_point(|p| p[channel] <= median);
+    }
+    let upper = px.split_off(at);
+    for half in [px, upper] {
+      let (spread, channel) = range(&half);
+      boxes.push((spread, channel, half));
+    }
+  }
+  boxes.sort_by_key(|(_, _, px)| std::cmp::Reverse(px.len()));
+  let mut colors: Vec<String> = Vec::with_capacity(boxes.len());
+  for (_, _, px) in boxes {
+    let sum = px.iter().fold([0u64; 3], |acc, p| {
+      [
+        acc[0] + p[0] as u64,
+        acc[1] + p[1] as u64,
+        acc[2] + p[2] as u64,
+      ]
+    });
+    let n = px.len() as f64;
+    let hex = Srgb(sum.map(|s| s as f64 / n / 255.0), 1.0).to_hex();
+    if !colors.contains(&hex) {
+      colors.push(hex);
+    }
+  }
+  colors
+}
+
+/// Decodes an image and extracts its dominant colors on the libuv thread
+/// pool.
+pub struct PaletteTask {
+  input: Buffer,
+  count: u32,
+}
+
+impl Task for PaletteTask {
+  type Output = Result<Vec<String>>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(crate::image::decode(&self.input).map(|(image, _)| {
+      // A thumbnail has the same dominant colors and bounds the work.
+      let image = if image.width() > 128 || image.height() > 128 {
+        image.thumbnail(128, 128)
+      } else {
+        image
+      };
+      let pixels = image
+        .into_rgba8()
+        .pixels()
+        .filter(|p| p[3] >= 128)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+      median_cut(pixels, self.count as usize)
+    }))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Up to `count` (1–256) dominant colors of a PNG, JPEG or WebP image as
+/// `#rrggbb` strings, most common first. Mostly transparent pixels are
+/// ignored, so a fully transparent image yields an empty array.
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn extract_palette(image: Buffer, count: u32) -> Result<AsyncTask<Guarded<PaletteTask>>> {
+  if !(1..=256).contains(&count) {
+    return Err(AppError::InvalidInput(format!(
+      "count must be 1–256, got {}",
+      count
+    )));
+  }
+  Ok(panic_guard::task(PaletteTask {
+    input: image,
+    count,
+  }))
+}
@@ -0,0 +1,165 @@
+use std::io::{Read, Write};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+const DEFAULT_BROTLI_QUALITY: u32 = 11;
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy)]
+enum Codec {
+  Gzip,
+  Gunzip,
+  BrotliCompress(u32),
+  BrotliDecompress,
+  ZstdCompress(i32),
+  ZstdDecompress,
+}
+
+impl Codec {
+  fn run(self, input: &[u8]) -> Result<Vec<u8>> {
+    self.run_io(input).map_err(|e| match self {
+      // Decoders only fail on corrupt input.
+      Codec::Gunzip | Codec::BrotliDecompress | Codec::ZstdDecompress => {
+        AppError::Parse(format!("invalid compressed data: {}", e))
+      }
+      _ => AppError::Io(e.to_string()),
+    })
+  }
+
+  fn run_io(self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+    match self {
+      Codec::Gzip => {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(input)?;
+        encoder.finish()
+      }
+      Codec::Gunzip => {
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(input).read_to_end(&mut out)?;
+        Ok(out)
+      }
+      Codec::BrotliCompress(quality) => {
+        let mut out = Vec::new();
+        {
+          let mut writer = brotli::CompressorWriter::new(&mut out, 4096, quality, 22);
+          writer.write_all(input)?;
+        }
+        Ok(out)
+      }
+      Codec::BrotliDecompress => {
+        let mut out = Vec::new();
+        brotli::Decompressor::new(input, 4096).read_to_end(&mut out)?;
+        Ok(out)
+      }
+      Codec::ZstdCompress(level) => zstd::encode_all(input, level),
+      Codec::ZstdDecompress => zstd::decode_all(input),
+    }
+  }
+}
+
+fn run_sync(codec: Codec, input: &[u8]) -> Result<Buffer> {
+  codec.run(input).map(Buffer::from)
+}
+
+/// Runs a codec on the libuv thread pool.
+///
+/// Failures travel through `Output` rather than `compute`'s own error so they
+/// can be turned into a coded JS error in `resolve`, where an `Env` exists.
+pub struct CompressTask {
+  codec: Codec,
+  input: Buffer,
+}
+
+impl Task for CompressTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.codec.run(&self.input))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+fn task(codec: Codec, input: Buffer) -> AsyncTask<Guarded<CompressTask>> {
+  panic_guard::task(CompressTask { codec, input })
+}
+
+#[napi(catch_unwind)]
+pub fn gzip(input: Buffer) -> Result<Buffer> {
+  run_sync(Codec::Gzip, &input)
+}
+
+#[napi(catch_unwind)]
+pub fn gunzip(input: Buffer) -> Result<Buffer> {
+  run_sync(Codec::Gunzip, &input)
+}
+
+/// `quality` ranges 0–11 (default 11).
+#[napi(catch_unwind)]
+pub fn brotli_compress(input: Buffer, quality: Option<u32>) -> Result<Buffer> {
+  let quality = quality.unwrap_or(DEFAULT_BROTLI_QUALITY).min(11);
+  run_sync(Codec::BrotliCompress(quality), &input)
+}
+
+#[napi(catch_unwind)]
+pub fn brotli_decompress(input: Buffer) -> Result<Buffer> {
+  run_sync(Codec::BrotliDecompress, &input)
+}
+
+/// `level` ranges 1–22 (default 3).
+#[napi(catch_unwind)]
+pub fn zstd_compress(input: Buffer, level: Option<i32>) -> Result<Buffer> {
+  run_sync(
+    Codec::ZstdCompress(level.unwrap_or(DEFAULT_ZSTD_LEVEL)),
+    &input,
+  )
+}
+
+#[napi(catch_unwind)]
+pub fn zstd_decompress(input: Buffer) -> Result<Buffer> {
+  run_sync(Codec::ZstdDecompress, &input)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn gzip_async(input: Buffer) -> AsyncTask<Guarded<CompressTask>> {
+  task(Codec::Gzip, input)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn gunzip_async(input: Buffer) -> AsyncTask<Guarded<CompressTask>> {
+  task(Codec::Gunzip, input)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn brotli_compress_async(
+  input: Buffer,
+  quality: Option<u32>,
+) -> AsyncTask<Guarded<CompressTask>> {
+  let quality = quality.unwrap_or(DEFAULT_BROTLI_QUALITY).min(11);
+  task(Codec::BrotliCompress(quality), input)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn brotli_decompress_async(input: Buffer) -> AsyncTask<Guarded<CompressTask>> {
+  task(Codec::BrotliDecompress, input)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn zstd_compress_async(input: Buffer, level: Option<i32>) -> AsyncTask<Guarded<CompressTask>> {
+  task(
+    Codec::ZstdCompress(level.unwrap_or(DEFAULT_ZSTD_LEVEL)),
+    input,
+  )
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn zstd_decompress_async(input: Buffer) -> AsyncTask<Guarded<CompressTask>> {
+  task(Codec::ZstdDecompress, input)
+}
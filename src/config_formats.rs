@@ -0,0 +1,122 @@
+use chrono::SecondsFormat;
+use napi::{Env, JsDate, JsObject, JsUnknown, ValueType};
+use serde_json::{Map, Value};
+use toml::value::{Datetime, Table};
+
+use crate::datetime::date_to_utc;
+use crate::error::{AppError, Result};
+use crate::json::{check_depth, from_js};
+
+/// Numbers up to this are written to TOML as integers.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Parses a single YAML document. Error messages include the line and
+/// column of the problem.
+#[napi(catch_unwind, ts_return_type = "unknown")]
+pub fn parse_yaml(input: String) -> Result<Value> {
+  serde_yaml::from_str(&input).map_err(|e| AppError::Parse(format!("invalid YAML: {}", e)))
+}
+
+#[napi(catch_unwind, ts_args_type = "value: unknown")]
+pub fn to_yaml(env: Env, value: JsUnknown) -> Result<String> {
+  let value: Value = from_js(&env, value)?;
+  serde_yaml::to_string(&value).map_err(|e| AppError::InvalidInput(e.to_string()))
+}
+
+fn from_toml(value: toml::Value) -> Value {
+  match value {
+    toml::Value::String(s) => Value::String(s),
+    toml::Value::Integer(i) => Value::from(i),
+    toml::Value::Float(f) => Value::from(f),
+    toml::Value::Boolean(b) => Value::Bool(b),
+    toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+    toml::Value::Array(items) => Value::Array(items.into_iter().map(from_toml).collect()),
+    toml::Value::Table(table) => Value::Object(
+      table
+        .into_iter()
+        .map(|(key, value)| (key, from_toml(value)))
+        .collect(),
+    ),
+  }
+}
+
+fn cannot_write(why: &str) -> AppError {
+  AppError::InvalidInput(format!("cannot write TOML: {}", why))
+}
+
+/// `None` for `undefined`, which tables skip as `JSON.stringify` does.
+fn to_toml_value(value: JsUnknown, depth: usize) -> Result<Option<toml::Value>> {
+  check_depth(depth)?;
+  Ok(Some(match value.get_type()? {
+    ValueType::Undefined => return Ok(None),
+    ValueType::Null => return Err(cannot_write("TOML has no null")),
+    ValueType::Boolean => toml::Value::Boolean(value.coerce_to_bool()?.get_value()?),
+    ValueType::Number => {
+      let n = value.coerce_to_number()?.get_double()?;
+      if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        toml::Value::Integer(n as i64)
+      } else {
+        toml::Value::Float(n)
+      }
+    }
+    ValueType::String => toml::Value::String(value.coerce_to_string()?.into_utf8()?.into_owned()?),
+    ValueType::Object if value.is_date()? => {
+      let utc = date_to_utc(&unsafe { value.cast::<JsDate>() })?;
+      let datetime: Datetime = utc
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        .parse()
+        .map_err(|_| cannot_write("Date is out of range"))?;
+      toml::Value::Datetime(datetime)
+    }
+    ValueType::Object if value.is_array()? => {
+      let array = unsafe { value.cast::<JsObject>() };
+      let mut items = Vec::new();
+      for i in 0..array.get_array_length()? {
+        let item = to_toml_value(array.get_element(i)?, depth + 1)?
+          .ok_or_else(|| cannot_write("arrays cannot hold undefined"))?;
+        items.push(item);
+      }
+      toml::Value::Array(items)
+    }
+    ValueType::Object => toml::Value::Table(to_table(unsafe { value.cast() }, depth)?),
+    other => return Err(cannot_write(&format!("a {:?} has no TOML form", other))),
+  }))
+}
+
+fn to_table(object: JsObject, depth: usize) -> Result<Table> {
+  let mut table = Table::new();
+  for key in JsObject::keys(&object)? {
+    if let Some(value) = to_toml_value(object.get_named_property(&key)?, depth + 1)? {
+      table.insert(key, value);
+    }
+  }
+  Ok(table)
+}
+
+/// Parses a TOML document into an object. Error messages include the line
+/// and column of the problem and a snippet of the offending input.
+/// Datetimes come back as strings in TOML's own syntax, e.g.
+/// `1979-05-27T07:32:00-08:00`, or `1979-05-27` for a local date.
+#[napi(catch_unwind, ts_return_type = "Record<string, unknown>")]
+pub fn parse_toml(input: String) -> Result<Map<String, Value>> {
+  let table: Table =
+    toml::from_str(&input).map_err(|e| AppError::Parse(format!("invalid TOML: {}", e)))?;
+  Ok(
+    table
+      .into_iter()
+      .map(|(key, value)| (key, from_toml(value)))
+      .collect(),
+  )
+}
+
+/// Serializes an object as TOML, writing Dates as UTC datetimes. A TOML
+/// document is a table, so anything but an object is rejected, as are
+/// objects containing `null`, which TOML has no way to write.
+#[napi(catch_unwind, ts_args_type = "value: Record<string, unknown>")]
+pub fn to_toml(value: JsUnknown) -> Result<String> {
+  if value.get_type()? != ValueType::Object || value.is_array()? || value.is_date()? {
+    return Err(cannot_write("the top level must be an object"));
+  }
+  let table = to_table(unsafe { value.cast() }, 0)?;
+  toml::to_string(&table).map_err(|e| cannot_write(&e.to_string()))
+}
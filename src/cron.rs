@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use napi::{Env, JsDate};
+
+use crate::datetime::{date_to_utc, parse_timezone, utc_to_date};
+use crate::error::{AppError, Result};
+
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+#[napi(object)]
+#[derive(Default)]
+pub struct CronOptions {
+  /// IANA zone the expression is evaluated in, e.g. `Europe/Berlin`.
+  /// Defaults to `UTC`.
+  pub timezone: Option<String>,
+}
+
+fn invalid(expr: &str, reason: impl std::fmt::Display) -> AppError {
+  AppError::Parse(format!("invalid cron expression {:?}: {}", expr, reason))
+}
+
+/// Rewrites one numeric day-of-week item (`1-5`, `*/2`, `0`) as day names.
+/// The cron crate numbers days 1–7 from Sunday, while standard cron uses
+/// 0–6 (and 7) from Sunday; names mean the same in both.
+fn day_names(item: &str) -> std::result::Result<String, String> {
+  let (base, step) = match item.split_once('/') {
+    Some((base, step)) => (base, Some(step)),
+    None => (item, None),
+  };
+  let numeric = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+  let (start, end) = match base.split_once('-') {
+    Some((a, b)) if numeric(a) && numeric(b) => (a, b),
+    None if base == "*" => ("0", "6"),
+    None if numeric(base) => (base, if step.is_some() { "6" } else { base }),
+    _ => return Ok(item.to_string()),
+  };
+  let day = |s: &str| match s.parse::<usize>() {
+    Ok(n) if n <= 7 => Ok(n),
+    _ => Err(format!("day of week {} is out of range 0-7", s)),
+  };
+  let (start, end) = (day(start)?, day(end)?);
+  let step = match step.map(str::parse::<usize>) {
+    None => 1,
+    Some(Ok(step)) if step > 0 => step,
+    Some(_) => return Err(format!("invalid step in {}", item)),
+  };
+  if start > end {
+    return Err(format!("day-of-week range {} is reversed", base));
+  }
+  let mut days: Vec<usize> = (start..=end).step_by(step).map(|d| d % 7).collect();
+  days.sort_unstable();
+  days.dedup();
+  Ok(
+    days
+      .into_iter()
+      .map(|d| DAY_NAMES[d])
+      .collect::<Vec<_>>()
+      .join(","),
+  )
+}
+
+/// Converts 5-, 6- or 7-field syntax to the cron crate's
+/// `sec min hour dom month dow [year]` form. Returns it with whether the
+/// expression has a seconds field.
+fn normalize(expr: &str) -> Result<(String, bool)> {
+  let trimmed = expr.trim();
+  if trimmed.starts_with('@') {
+    return Ok((trimmed.to_string(), false));
+  }
+  let mut fields: Vec<String> = trimmed.split_whitespace().map(String::from).collect();
+  let has_seconds = match fields.len() {
+    5 => {
+      fields.insert(0, "0".into());
+      false
+    }
+    6 | 7 => true,
+    n => {
+      return Err(invalid(
+        expr,
+        format!("expected 5, 6 or 7 fields, got {}", n),
+      ))
+    }
+  };
+  fields[5] = fields[5]
+    .split(',')
+    .map(day_names)
+    .collect::<std::result::Result<Vec<_>, _>>()
+    .map_err(|e| invalid(expr, e))?
+    .join(",");
+  Ok((fields.join(" "), has_seconds))
+}
+
+/// A parsed cron schedule.
+///
+/// Accepts standard 5-field expressions (`min hour dom month dow`), 6
+/// fields with leading seconds, 7 fields with trailing years, and the
+/// `@hourly`/`@daily`/`@weekly`/`@monthly`/`@yearly` shorthands. Days of
+/// week are 0–7 (0 and 7 are Sunday) or `SUN`–`SAT`.
+#[napi]
+pub struct CronSchedule {
+  schedule: Schedule,
+  expression: String,
+  timezone: Tz,
+  has_seconds: bool,
+}
+
+#[napi]
+impl CronSchedule {
+  #[napi(catch_unwind, factory)]
+  pub fn parse(expr: String, options: Option<CronOptions>) -> Result<Self> {
+    let timezone = match options.unwrap_or_default().timezone {
+      Some(name) => parse_timezone(&name)?,
+      None => Tz::UTC,
+    };
+    let (normalized, has_seconds) = normalize(&expr)?;
+    let schedule = Schedule::from_str(&normalized).map_err(|e| invalid(&expr, e))?;
+    Ok(CronSchedule {
+      schedule,
+      expression: expr,
+      timezone,
+      has_seconds,
+    })
+  }
+
+  /// The first run strictly after `after` (default: now), or `null` if the
+  /// schedule has no more runs.
+  #[napi(catch_unwind)]
+  pub fn next(&self, env: Env, after: Option<JsDate>) -> Result<Option<JsDate>> {
+    Ok(self.next_n(env, 1, after)?.pop())
+  }
+
+  /// Up to `n` consecutive runs after `after` (default: now).
+  #[napi(catch_unwind)]
+  pub fn next_n(&self, env: Env, n: u32, after: Option<JsDate>) -> Result<Vec<JsDate>> {
+    let after = match after {
+      Some(date) => date_to_utc(&date)?,
+      None => Utc::now(),
+    };
+    self
+      .schedule
+      .after(&after.with_timezone(&self.timezone))
+      .take(n as usize)
+      .map(|date| utc_to_date(&env, date))
+      .collect()
+  }
+
+  /// Whether the schedule fires at `date`. Without a seconds field the
+  /// comparison is to the minute.
+  #[napi(catch_unwind)]
+  pub fn matches(&self, date: JsDate) -> Result<bool> {
+    let mut date = date_to_utc(&date)?.with_timezone(&self.timezone);
+    if !self.has_seconds {
+      date = date
+        .with_second(0)
+        .and_then(|d| d.with_nanosecond(0))
+        .unwrap_or(date);
+    }
+    Ok(self.schedule.includes(date))
+  }
+
+  /// The expression as given to `parse`.
+  #[napi(catch_unwind, getter)]
+  pub fn expression(&self) -> String {
+    self.expression.clone()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn timezone(&self) -> String {
+    self.timezone.name().to_string()
+  }
+}
+
+impl CronSchedule {
+  /// The first run strictly after `after`, for the `Scheduler`.
+  pub(crate) fn next_after(&self, after: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+    self
+      .schedule
+      .after(&after.with_timezone(&self.timezone))
+      .next()
+      .map(|date| date.with_timezone(&Utc))
+  }
+}
@@ -0,0 +1,195 @@
+use aes_gcm::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+const AES_256_GCM: &str = "aes-256-gcm";
+const CHACHA20_POLY1305: &str = "chacha20-poly1305";
+
+/// Output of `encrypt`. Pass it back unchanged to `decrypt`.
+#[napi(object)]
+pub struct EncryptedPayload {
+  /// `aes-256-gcm` or `chacha20-poly1305`.
+  pub algorithm: String,
+  /// 12-byte random nonce.
+  pub nonce: Buffer,
+  pub ciphertext: Buffer,
+  /// 16-byte authentication tag.
+  pub tag: Buffer,
+}
+
+fn invalid_key() -> AppError {
+  AppError::InvalidInput("key must be exactly 32 bytes".into())
+}
+
+// The cipher instances are built straight from the caller's key bytes and
+// wipe their key material on drop (aes-gcm's `zeroize` feature; chacha20poly1305
+// always does), so no copy of the key outlives the call.
+fn seal<C: KeyInit + AeadInPlace + AeadCore>(
+  key: &[u8],
+  plaintext: &[u8],
+  aad: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+  let cipher = C::new_from_slice(key).map_err(|_| invalid_key())?;
+  let nonce = C::generate_nonce(&mut OsRng);
+  let mut buf = plaintext.to_vec();
+  let tag = cipher
+    .encrypt_in_place_detached(&nonce, aad, &mut buf)
+    .map_err(|_| AppError::Crypto("encryption failed".into()))?;
+  Ok((nonce.to_vec(), buf, tag.to_vec()))
+}
+
+fn open<C: KeyInit + AeadInPlace>(
+  key: &[u8],
+  payload: &EncryptedPayload,
+  aad: &[u8],
+) -> Result<Vec<u8>> {
+  let cipher = C::new_from_slice(key).map_err(|_| invalid_key())?;
+  if payload.nonce.len() != 12 || payload.tag.len() != 16 {
+    return Err(AppError::InvalidInput(
+      "nonce must be 12 bytes and tag 16 bytes".into(),
+    ));
+  }
+  let mut buf = payload.ciphertext.to_vec();
+  cipher
+    .decrypt_in_place_detached(
+      payload.nonce.as_ref().into(),
+      aad,
+      &mut buf,
+      payload.tag.as_ref().into(),
+    )
+    .map_err(|_| AppError::Crypto("decryption failed: data or key is wrong".into()))?;
+  Ok(buf)
+}
+
+fn do_encrypt(
+  plaintext: &[u8],
+  key: &[u8],
+  aad: &[u8],
+  algorithm: &str,
+) -> Result<EncryptedPayload> {
+  let (nonce, ciphertext, tag) = match algorithm {
+    AES_256_GCM => seal::<Aes256Gcm>(key, plaintext, aad)?,
+    CHACHA20_POLY1305 => seal::<ChaCha20Poly1305>(key, plaintext, aad)?,
+    other => return Err(unsupported(other)),
+  };
+  Ok(EncryptedPayload {
+    algorithm: algorithm.to_string(),
+    nonce: nonce.into(),
+    ciphertext: ciphertext.into(),
+    tag: tag.into(),
+  })
+}
+
+fn do_decrypt(payload: &EncryptedPayload, key: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+  match payload.algorithm.as_str() {
+    AES_256_GCM => open::<Aes256Gcm>(key, payload, aad),
+    CHACHA20_POLY1305 => open::<ChaCha20Poly1305>(key, payload, aad),
+    other => Err(unsupported(other)),
+  }
+}
+
+fn unsupported(algorithm: &str) -> AppError {
+  AppError::Unsupported(format!("unsupported algorithm: {}", algorithm))
+}
+
+fn aad_bytes(aad: &Option<Buffer>) -> &[u8] {
+  aad.as_deref().unwrap_or_default()
+}
+
+/// Encrypts `plaintext` with a 32-byte `key`. `algorithm` defaults to
+/// `aes-256-gcm`; `chacha20-poly1305` is also supported.
+#[napi(catch_unwind)]
+pub fn encrypt(
+  plaintext: Buffer,
+  key: Buffer,
+  aad: Option<Buffer>,
+  algorithm: Option<String>,
+) -> Result<EncryptedPayload> {
+  do_encrypt(
+    &plaintext,
+    &key,
+    aad_bytes(&aad),
+    algorithm.as_deref().unwrap_or(AES_256_GCM),
+  )
+}
+
+/// Decrypts and authenticates a payload produced by `encrypt`.
+#[napi(catch_unwind)]
+pub fn decrypt(payload: EncryptedPayload, key: Buffer, aad: Option<Buffer>) -> Result<Buffer> {
+  do_decrypt(&payload, &key, aad_bytes(&aad)).map(Buffer::from)
+}
+
+pub struct EncryptTask {
+  plaintext: Buffer,
+  key: Buffer,
+  aad: Option<Buffer>,
+  algorithm: String,
+}
+
+impl Task for EncryptTask {
+  type Output = Result<EncryptedPayload>;
+  type JsValue = EncryptedPayload;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(do_encrypt(
+      &self.plaintext,
+      &self.key,
+      aad_bytes(&self.aad),
+      &self.algorithm,
+    ))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+pub struct DecryptTask {
+  payload: EncryptedPayload,
+  key: Buffer,
+  aad: Option<Buffer>,
+}
+
+impl Task for DecryptTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(do_decrypt(&self.payload, &self.key, aad_bytes(&self.aad)))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+/// `encrypt` on the libuv thread pool, for large buffers.
+#[napi(catch_unwind, ts_return_type = "Promise<EncryptedPayload>")]
+pub fn encrypt_async(
+  plaintext: Buffer,
+  key: Buffer,
+  aad: Option<Buffer>,
+  algorithm: Option<String>,
+) -> AsyncTask<Guarded<EncryptTask>> {
+  panic_guard::task(EncryptTask {
+    plaintext,
+    key,
+    aad,
+    algorithm: algorithm.unwrap_or_else(|| AES_256_GCM.to_string()),
+  })
+}
+
+/// `decrypt` on the libuv thread pool, for large buffers.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn decrypt_async(
+  payload: EncryptedPayload,
+  key: Buffer,
+  aad: Option<Buffer>,
+) -> AsyncTask<Guarded<DecryptTask>> {
+  panic_guard::task(DecryptTask { payload, key, aad })
+}
@@ -0,0 +1,226 @@
+use std::fs::File;
+use std::io::Read;
+use std::sync::mpsc;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown, Task};
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, Result};
+use crate::hash::input_bytes;
+use crate::json::from_js;
+use crate::panic_guard::{self, Guarded};
+
+type Row = Map<String, Value>;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct CsvOptions {
+  /// Single-byte field delimiter. Defaults to `,`.
+  pub delimiter: Option<String>,
+  /// Whether the first record names the columns. Defaults to `true`; when
+  /// `false`, rows are keyed by column index (`"0"`, `"1"`, ...).
+  pub has_headers: Option<bool>,
+  /// Rows per `onBatch` call in `parseCsvStream`. Defaults to 1000.
+  pub batch_size: Option<u32>,
+}
+
+impl CsvOptions {
+  fn delimiter(&self) -> Result<u8> {
+    match self.delimiter.as_deref() {
+      None => Ok(b','),
+      Some(d) if d.len() == 1 => Ok(d.as_bytes()[0]),
+      Some(d) => Err(AppError::InvalidInput(format!(
+        "delimiter must be a single byte, got {:?}",
+        d
+      ))),
+    }
+  }
+
+  fn reader<R: Read>(&self, input: R) -> Result<::csv::Reader<R>> {
+    Ok(
+      ::csv::ReaderBuilder::new()
+        .delimiter(self.delimiter()?)
+        .has_headers(self.has_headers.unwrap_or(true))
+        .from_reader(input),
+    )
+  }
+}
+
+fn csv_error(e: ::csv::Error) -> AppError {
+  if e.is_io_error() {
+    AppError::Io(e.to_string())
+  } else {
+    AppError::Parse(format!("invalid CSV: {}", e))
+  }
+}
+
+/// Column names for `reader`: the header record, or `None` to key by index.
+fn headers<R: Read>(reader: &mut ::csv::Reader<R>) -> Result<Option<Vec<String>>> {
+  if !reader.has_headers() {
+    return Ok(None);
+  }
+  let headers = reader.headers().map_err(csv_error)?;
+  Ok(Some(headers.iter().map(str::to_string).collect()))
+}
+
+fn to_row(headers: &Option<Vec<String>>, record: &::csv::StringRecord) -> Row {
+  record
+    .iter()
+    .enumerate()
+    .map(|(i, field)| {
+      let key = match headers.as_ref().and_then(|h| h.get(i)) {
+        Some(name) => name.clone(),
+        None => i.to_string(),
+      };
+      (key, Value::String(field.to_string()))
+    })
+    .collect()
+}
+
+/// Parses CSV text into an array of row objects. All values are strings.
+#[napi(catch_unwind)]
+pub fn parse_csv(input: Either<String, Buffer>, opts: Option<CsvOptions>) -> Result<Vec<Row>> {
+  let opts = opts.unwrap_or_default();
+  let mut reader = opts.reader(input_bytes(&input))?;
+  let headers = headers(&mut reader)?;
+  reader
+    .records()
+    .map(|record| Ok(to_row(&headers, &record.map_err(csv_error)?)))
+    .collect()
+}
+
+/// Serializes row objects to CSV. The header is the union of all keys in
+/// first-seen order; missing fields and `null` are written as empty, strings
+/// as-is, and other values as JSON. Rows without any keys give `""`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "rows: Array<Record<string, any>>, opts?: CsvOptions"
+)]
+pub fn write_csv(env: Env, rows: JsUnknown, opts: Option<CsvOptions>) -> Result<String> {
+  let rows: Vec<Row> = from_js(&env, rows)?;
+  let opts = opts.unwrap_or_default();
+  let delimiter = opts.delimiter()?;
+  let mut columns: Vec<&String> = Vec::new();
+  for row in &rows {
+    for key in row.keys() {
+      if !columns.contains(&key) {
+        columns.push(key);
+      }
+    }
+  }
+  // The csv crate writes a record with no fields as `""`.
+  if columns.is_empty() {
+    return Ok(String::new());
+  }
+
+  let mut writer = ::csv::WriterBuilder::new()
+    .delimiter(delimiter)
+    .from_writer(Vec::new());
+  if opts.has_headers.unwrap_or(true) {
+    writer.write_record(&columns).map_err(csv_error)?;
+  }
+  for row in &rows {
+    let record = columns.iter().map(|column| match row.get(*column) {
+      None | Some(Value::Null) => String::new(),
+      Some(Value::String(s)) => s.clone(),
+      Some(other) => other.to_string(),
+    });
+    writer.write_record(record).map_err(csv_error)?;
+  }
+  let bytes = writer
+    .into_inner()
+    .map_err(|e| AppError::Io(e.to_string()))?;
+  String::from_utf8(bytes).map_err(|e| AppError::Parse(e.to_string()))
+}
+
+type BatchFn = ThreadsafeFunction<Vec<Row>, ErrorStrategy::Fatal>;
+
+pub struct CsvStreamTask {
+  path: String,
+  opts: CsvOptions,
+  on_batch: BatchFn,
+}
+
+impl CsvStreamTask {
+  /// Hands `batch` to JS and waits for the callback to return, so a slow
+  /// consumer throttles parsing instead of batches piling up in memory.
+  fn deliver(&self, batch: Vec<Row>) {
+    let (done_tx, done_rx) = mpsc::channel();
+    self.on_batch.call_with_return_value(
+      batch,
+      ThreadsafeFunctionCallMode::Blocking,
+      move |_: JsUnknown| {
+        let _ = done_tx.send(());
+        Ok(())
+      },
+    );
+    let _ = done_rx.recv();
+  }
+
+  fn run(&self) -> Result<u32> {
+    let file = File::open(&self.path).map_err(|e| AppError::io(&self.path, e))?;
+    let mut reader = self.opts.reader(file)?;
+    let headers = headers(&mut reader)?;
+    let batch_size = self.opts.batch_size.unwrap_or(1000).max(1) as usize;
+
+    let mut total = 0;
+    let mut batch = Vec::with_capacity(batch_size);
+    for record in reader.records() {
+      batch.push(to_row(&headers, &record.map_err(csv_error)?));
+      total += 1;
+      if batch.len() == batch_size {
+        self.deliver(std::mem::replace(
+          &mut batch,
+          Vec::with_capacity(batch_size),
+        ));
+      }
+    }
+    if !batch.is_empty() {
+      self.deliver(batch);
+    }
+    Ok(total)
+  }
+}
+
+impl Task for CsvStreamTask {
+  type Output = Result<u32>;
+  type JsValue = u32;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Parses the CSV file at `path` on the thread pool, passing rows to
+/// `onBatch` in batches of `batchSize`. Resolves with the total row count.
+#[napi(
+  catch_unwind,
+  ts_args_type = "path: string, onBatch: (rows: Array<Record<string, string>>) => void, opts?: CsvOptions",
+  ts_return_type = "Promise<number>"
+)]
+pub fn parse_csv_stream(
+  path: String,
+  on_batch: JsFunction,
+  opts: Option<CsvOptions>,
+) -> Result<AsyncTask<Guarded<CsvStreamTask>>> {
+  let opts = opts.unwrap_or_default();
+  // Fail fast on bad options instead of after the file is opened.
+  opts.delimiter()?;
+  let on_batch: BatchFn = on_batch
+    .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<Row>>| {
+      Ok(vec![ctx.env.to_js_value(&ctx.value)?])
+    })?;
+  Ok(panic_guard::task(CsvStreamTask {
+    path,
+    opts,
+    on_batch,
+  }))
+}
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::format::StrftimeItems;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use napi::{Env, JsDate};
+
+use crate::error::{AppError, Result};
+
+/// Looks up an IANA zone name in the embedded tz database.
+pub(crate) fn parse_timezone(name: &str) -> Result<Tz> {
+  Tz::from_str(name).map_err(|_| AppError::InvalidInput(format!("unknown timezone: {}", name)))
+}
+
+pub(crate) fn date_to_utc(date: &JsDate) -> Result<DateTime<Utc>> {
+  let ms = date.value_of()?;
+  if !ms.is_finite() {
+    return Err(AppError::InvalidInput("invalid Date".into()));
+  }
+  Utc
+    .timestamp_millis_opt(ms as i64)
+    .single()
+    .ok_or_else(|| AppError::InvalidInput("Date is out of range".into()))
+}
+
+pub(crate) fn utc_to_date<Z: TimeZone>(env: &Env, date: DateTime<Z>) -> Result<JsDate> {
+  Ok(env.create_date(date.timestamp_millis() as f64)?)
+}
+
+fn timezone_or_utc(name: Option<String>) -> Result<Tz> {
+  name.map_or(Ok(Tz::UTC), |name| parse_timezone(&name))
+}
+
+/// Shifts `date` so its UTC fields (`getUTCHours()` and friends) read as
+/// the wall-clock time in `timezone`. The result is for display and
+/// field access; it no longer denotes the original instant.
+#[napi(catch_unwind)]
+pub fn convert_timezone(env: Env, date: JsDate, timezone: String) -> Result<JsDate> {
+  let local = date_to_utc(&date)?
+    .with_timezone(&parse_timezone(&timezone)?)
+    .naive_local();
+  utc_to_date(&env, local.and_utc())
+}
+
+/// Formats `date` as wall-clock time in `timezone` with a strftime
+/// pattern, e.g. `%Y-%m-%d %H:%M %Z`.
+#[napi(catch_unwind)]
+pub fn format_in_tz(date: JsDate, timezone: String, format: String) -> Result<String> {
+  let items = StrftimeItems::new(&format)
+    .parse()
+    .map_err(|_| AppError::InvalidInput(format!("invalid format string: {:?}", format)))?;
+  let zoned = date_to_utc(&date)?.with_timezone(&parse_timezone(&timezone)?);
+  Ok(zoned.format_with_items(items.iter()).to_string())
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-05-01T12:00:00+02:00`.
+#[napi(catch_unwind)]
+pub fn parse_rfc3339(env: Env, input: String) -> Result<JsDate> {
+  let date = DateTime::parse_from_rfc3339(&input)
+    .map_err(|e| AppError::Parse(format!("invalid RFC 3339 date {:?}: {}", input, e)))?;
+  utc_to_date(&env, date)
+}
+
+/// Parses an RFC 2822 (e-mail / HTTP) date such as
+/// `Wed, 01 May 2024 12:00:00 +0200`.
+#[napi(catch_unwind)]
+pub fn parse_rfc2822(env: Env, input: String) -> Result<JsDate> {
+  let date = DateTime::parse_from_rfc2822(&input)
+    .map_err(|e| AppError::Parse(format!("invalid RFC 2822 date {:?}: {}", input, e)))?;
+  utc_to_date(&env, date)
+}
+
+/// Counts Monday–Friday calendar days from `start` (inclusive) to `end`
+/// (exclusive), skipping any day that falls on one of `holidays`. Days are
+/// taken in `timezone` (default UTC). Negative when `end` is before
+/// `start`.
+#[napi(catch_unwind)]
+pub fn business_days_between(
+  start: JsDate,
+  end: JsDate,
+  holidays: Option<Vec<JsDate>>,
+  timezone: Option<String>,
+) -> Result<i64> {
+  let tz = timezone_or_utc(timezone)?;
+  let day =
+    |date: &JsDate| -> Result<NaiveDate> { Ok(date_to_utc(date)?.with_timezone(&tz).date_naive()) };
+  let (mut from, mut to) = (day(&start)?, day(&end)?);
+  let sign = if to < from {
+    std::mem::swap(&mut from, &mut to);
+    -1
+  } else {
+    1
+  };
+  let holidays = holidays
+    .unwrap_or_default()
+    .iter()
+    .map(day)
+    .collect::<Result<HashSet<_>>>()?;
+  // Whole weeks contribute five days each; only the remainder is walked.
+  let span = (to - from).num_days();
+  let mut count = span / 7 * 5;
+  let mut cursor = from + chrono::Days::new((span / 7 * 7) as u64);
+  while cursor < to {
+    if !matches!(cursor.weekday(), Weekday::Sat | Weekday::Sun) {
+      count += 1;
+    }
+    cursor = cursor.succ_opt().unwrap_or(to);
+  }
+  let skipped = holidays
+    .iter()
+    .filter(|d| **d >= from && **d < to && !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+    .count() as i64;
+  Ok(sign * (count - skipped))
+}
@@ -0,0 +1,348 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use napi::bindgen_prelude::*;
+use rust_decimal::{Decimal as Dec, RoundingStrategy};
+
+use crate::error::{AppError, Result};
+
+/// Most digits after the point a `Decimal` can hold.
+const MAX_SCALE: u32 = 28;
+
+type DecimalLike = Either4<ClassInstance<Decimal>, String, f64, BigInt>;
+
+fn parse(value: DecimalLike) -> Result<Dec> {
+  match value {
+    Either4::A(decimal) => Ok(decimal.as_ref().value),
+    Either4::B(text) => {
+      let trimmed = text.trim();
+      let parsed = if trimmed.contains(['e', 'E']) {
+        Dec::from_scientific(trimmed)
+      } else {
+        Dec::from_str(trimmed)
+      };
+      parsed.map_err(|e| AppError::Parse(format!("invalid decimal {:?}: {}", text, e)))
+    }
+    Either4::C(number) => {
+      if !number.is_finite() {
+        return Err(AppError::InvalidInput(format!(
+          "{} cannot be represented as a decimal",
+          number
+        )));
+      }
+      // The shortest text that round-trips, as JS prints it: 0.1 is
+      // exactly 0.1, not 0.1000000000000000055511151231257827.
+      Dec::from_str(&number.to_string())
+        .map_err(|_| AppError::Overflow(format!("{} is out of the decimal range", number)))
+    }
+    Either4::D(bigint) => match bigint.get_i128() {
+      (v, true) => Dec::try_from_i128_with_scale(v, 0).ok(),
+      _ => None,
+    }
+    .ok_or_else(|| AppError::Overflow("BigInt is out of the decimal range".into())),
+  }
+}
+
+fn rounding(mode: Option<&str>) -> Result<RoundingStrategy> {
+  Ok(match mode.unwrap_or("half-up") {
+    "half-up" => RoundingStrategy::MidpointAwayFromZero,
+    "half-down" => RoundingStrategy::MidpointTowardZero,
+    "half-even" => RoundingStrategy::MidpointNearestEven,
+    "up" => RoundingStrategy::AwayFromZero,
+    "down" => RoundingStrategy::ToZero,
+    "ceil" => RoundingStrategy::ToPositiveInfinity,
+    "floor" => RoundingStrategy::ToNegativeInfinity,
+    other => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown rounding mode: {}",
+        other
+      )))
+    }
+  })
+}
+
+fn check_scale(places: u32) -> Result<u32> {
+  if places > MAX_SCALE {
+    return Err(AppError::InvalidInput(format!(
+      "decimal places must be at most {}, got {}",
+      MAX_SCALE, places
+    )));
+  }
+  Ok(places)
+}
+
+fn overflow(op: &str, a: Dec, b: Dec) -> AppError {
+  AppError::Overflow(format!("{} {} {} is out of the decimal range", a, op, b))
+}
+
+/// An exact base-10 number with up to 28 digits after the point, for money
+/// and other values where `0.1 + 0.2` must be `0.3`.
+///
+/// Values are immutable: arithmetic returns a new `Decimal`. Operands may be
+/// a `Decimal`, a numeric string (`"12.50"`, `"1e-3"`), a number (taken as
+/// the digits JS prints for it) or a BigInt. Results outside roughly
+/// ±7.9e28 throw `ERR_OVERFLOW`.
+///
+/// Rounding modes: `half-up` (the default; halves away from zero),
+/// `half-down`, `half-even` (banker's rounding), `up` (away from zero),
+/// `down` (towards zero), `ceil` and `floor`.
+#[napi]
+pub struct Decimal {
+  value: Dec,
+}
+
+#[napi]
+impl Decimal {
+  #[napi(
+    catch_unwind,
+    constructor,
+    ts_args_type = "value: Decimal | string | number | bigint"
+  )]
+  pub fn new(value: DecimalLike) -> Result<Self> {
+    Ok(Decimal {
+      value: parse(value)?,
+    })
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn add(&self, other: DecimalLike) -> Result<Decimal> {
+    let other = parse(other)?;
+    self.wrap(self.value.checked_add(other), "+", other)
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn sub(&self, other: DecimalLike) -> Result<Decimal> {
+    let other = parse(other)?;
+    self.wrap(self.value.checked_sub(other), "-", other)
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn mul(&self, other: DecimalLike) -> Result<Decimal> {
+    let other = parse(other)?;
+    self.wrap(self.value.checked_mul(other), "*", other)
+  }
+
+  /// Divides to 28 significant digits; round the result to the scale you
+  /// need. Dividing by zero throws `ERR_INVALID_INPUT`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn div(&self, other: DecimalLike) -> Result<Decimal> {
+    let other = parse(other)?;
+    if other.is_zero() {
+      return Err(AppError::InvalidInput("division by zero".into()));
+    }
+    self.wrap(self.value.checked_div(other), "/", other)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn neg(&self) -> Decimal {
+    Decimal { value: -self.value }
+  }
+
+  #[napi(catch_unwind)]
+  pub fn abs(&self) -> Decimal {
+    Decimal {
+      value: self.value.abs(),
+    }
+  }
+
+  /// Rounds to `places` digits after the point (default 0).
+  #[napi(
+    catch_unwind,
+    ts_args_type = "places?: number, mode?: 'half-up' | 'half-down' | 'half-even' | 'up' | 'down' | 'ceil' | 'floor'"
+  )]
+  pub fn round(&self, places: Option<u32>, mode: Option<String>) -> Result<Decimal> {
+    let places = check_scale(places.unwrap_or(0))?;
+    Ok(Decimal {
+      value: self
+        .value
+        .round_dp_with_strategy(places, rounding(mode.as_deref())?),
+    })
+  }
+
+  /// The value rounded to exactly `places` digits after the point, padding
+  /// with zeros: `new Decimal('2.5').toFixed(2)` is `"2.50"`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "places: number, mode?: 'half-up' | 'half-down' | 'half-even' | 'up' | 'down' | 'ceil' | 'floor'"
+  )]
+  pub fn to_fixed(&self, places: u32, mode: Option<String>) -> Result<String> {
+    let places = check_scale(places)?;
+    let mut value = self
+      .value
+      .round_dp_with_strategy(places, rounding(mode.as_deref())?);
+    value.rescale(places);
+    Ok(value.to_string())
+  }
+
+  /// -1, 0 or 1 as this value is less than, equal to or greater than
+  /// `other`. Trailing zeros do not matter: `1.50` equals `1.5`.
+  #[napi(
+    catch_unwind,
+    js_name = "cmp",
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn compare(&self, other: DecimalLike) -> Result<i32> {
+    Ok(match self.value.cmp(&parse(other)?) {
+      Ordering::Less => -1,
+      Ordering::Equal => 0,
+      Ordering::Greater => 1,
+    })
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn eq(&self, other: DecimalLike) -> Result<bool> {
+    Ok(self.value == parse(other)?)
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn lt(&self, other: DecimalLike) -> Result<bool> {
+    Ok(self.value < parse(other)?)
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "other: Decimal | string | number | bigint"
+  )]
+  pub fn gt(&self, other: DecimalLike) -> Result<bool> {
+    Ok(self.value > parse(other)?)
+  }
+
+  /// Digits after the point, including trailing zeros.
+  #[napi(catch_unwind, getter)]
+  pub fn scale(&self) -> u32 {
+    self.value.scale()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn is_zero(&self) -> bool {
+    self.value.is_zero()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn is_negative(&self) -> bool {
+    self.value.is_sign_negative() && !self.value.is_zero()
+  }
+
+  /// The exact digits, keeping trailing zeros: `"12.50"`.
+  #[napi(catch_unwind)]
+  #[allow(clippy::inherent_to_string)]
+  pub fn to_string(&self) -> String {
+    self.value.to_string()
+  }
+
+  /// Same as `toString`, so `JSON.stringify` writes the exact digits.
+  #[napi(catch_unwind, js_name = "toJSON")]
+  pub fn to_json(&self) -> String {
+    self.value.to_string()
+  }
+
+  /// The nearest JS number. Precision may be lost.
+  #[napi(catch_unwind)]
+  pub fn to_number(&self) -> f64 {
+    self.value.to_string().parse().unwrap_or(f64::NAN)
+  }
+
+  /// Splits `total` in proportion to `ratios` without losing or creating a
+  /// cent: each share is rounded down to `places` digits (default: the
+  /// scale of `total`), and the units left over go one each to the shares
+  /// with the largest remainders, earlier shares winning ties. The shares
+  /// always add up to `total`, e.g. `allocate("100", [1, 1, 1], 2)` is
+  /// 33.34, 33.33, 33.33.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "total: Decimal | string | number | bigint, ratios: Array<Decimal | string | number | bigint>, places?: number"
+  )]
+  pub fn allocate(
+    total: DecimalLike,
+    ratios: Vec<DecimalLike>,
+    places: Option<u32>,
+  ) -> Result<Vec<Decimal>> {
+    let total = parse(total)?;
+    let places = check_scale(places.unwrap_or(total.scale()))?;
+    let ratios = ratios.into_iter().map(parse).collect::<Result<Vec<_>>>()?;
+    if ratios.is_empty() || ratios.iter().any(|r| r.is_sign_negative() && !r.is_zero()) {
+      return Err(AppError::InvalidInput(
+        "ratios must be a non-empty list of non-negative numbers".into(),
+      ));
+    }
+    let sum = ratios
+      .iter()
+      .try_fold(Dec::ZERO, |sum, r| sum.checked_add(*r))
+      .ok_or_else(|| AppError::Overflow("sum of ratios is out of the decimal range".into()))?;
+    if sum.is_zero() {
+      return Err(AppError::InvalidInput("ratios must not all be zero".into()));
+    }
+    if total.round_dp_with_strategy(places, RoundingStrategy::ToZero) != total {
+      return Err(AppError::InvalidInput(format!(
+        "total {} has more than {} decimal places",
+        total, places
+      )));
+    }
+
+    // Work on the magnitude in whole units of 10^-places, so shares of a
+    // negative total come out negative and rounded the same way.
+    let unit = Dec::new(1, places);
+    let magnitude = total.abs();
+    let mut shares = Vec::with_capacity(ratios.len());
+    let mut remainders = Vec::with_capacity(ratios.len());
+    let mut allocated = Dec::ZERO;
+    for ratio in &ratios {
+      let exact = magnitude
+        .checked_mul(*ratio)
+        .and_then(|m| m.checked_div(sum))
+        .ok_or_else(|| AppError::Overflow("allocation is out of the decimal range".into()))?;
+      let share = exact.round_dp_with_strategy(places, RoundingStrategy::ToZero);
+      remainders.push(exact - share);
+      allocated += share;
+      shares.push(share);
+    }
+    let mut order: Vec<usize> = (0..shares.len()).collect();
+    order.sort_by(|&a, &b| remainders[b].cmp(&remainders[a]).then(a.cmp(&b)));
+    let mut left = magnitude - allocated;
+    for &i in order.iter().cycle() {
+      if left < unit {
+        break;
+      }
+      shares[i] += unit;
+      left -= unit;
+    }
+    Ok(
+      shares
+        .into_iter()
+        .map(|mut share| {
+          if total.is_sign_negative() && !share.is_zero() {
+            share = -share;
+          }
+          share.rescale(places);
+          Decimal { value: share }
+        })
+        .collect(),
+    )
+  }
+}
+
+impl Decimal {
+  fn wrap(&self, result: Option<Dec>, op: &str, other: Dec) -> Result<Decimal> {
+    result
+      .map(|value| Decimal { value })
+      .ok_or_else(|| overflow(op, self.value, other))
+  }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+use xxhash_rust::xxh64::xxh64;
+
+use crate::error::{AppError, Result};
+
+const DEFAULT_SHINGLE_SIZE: u32 = 3;
+const DEFAULT_NUM_HASHES: u32 = 128;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ShingleOptions {
+  /// Words per shingle. Defaults to 3.
+  pub shingle_size: Option<u32>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct MinHashOptions {
+  /// Signature length; more hashes give a tighter similarity estimate.
+  /// Defaults to 128.
+  pub num_hashes: Option<u32>,
+  /// Words per shingle. Defaults to 3.
+  pub shingle_size: Option<u32>,
+  /// Signatures are only comparable between hashers with the same seed.
+  /// Defaults to 0.
+  pub seed: Option<u32>,
+}
+
+/// Hashes of the overlapping `size`-word shingles of `text`, with repeats.
+/// Words are lowercased runs of letters and digits, so punctuation and
+/// whitespace differences do not matter. Texts shorter than `size` words
+/// form a single shingle.
+fn shingles(text: &str, size: u32) -> Vec<u64> {
+  let text = text.to_lowercase();
+  let words: Vec<&str> = text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty())
+    .collect();
+  let size = (size as usize).clamp(1, words.len().max(1));
+  words
+    .windows(size)
+    .map(|window| xxh64(window.join(" ").as_bytes(), 0))
+    .collect()
+}
+
+fn shingle_size(size: Option<u32>) -> Result<u32> {
+  match size.unwrap_or(DEFAULT_SHINGLE_SIZE) {
+    0 => Err(AppError::InvalidInput(
+      "shingleSize must be at least 1".into(),
+    )),
+    size => Ok(size),
+  }
+}
+
+fn to_u64(name: &str, value: &BigInt) -> Result<u64> {
+  match value.get_u64() {
+    (false, v, true) => Ok(v),
+    _ => Err(AppError::InvalidInput(format!(
+      "{} must be an unsigned 64-bit BigInt",
+      name
+    ))),
+  }
+}
+
+/// A 64-bit SimHash fingerprint of `text`'s word shingles. Similar texts
+/// get fingerprints that differ in few bits; compare them with
+/// `hammingDistance`.
+#[napi(catch_unwind)]
+pub fn simhash(text: String, options: Option<ShingleOptions>) -> Result<BigInt> {
+  let size = shingle_size(options.unwrap_or_default().shingle_size)?;
+  let mut weights = [0i64; 64];
+  for hash in shingles(&text, size) {
+    for (bit, weight) in weights.iter_mut().enumerate() {
+      *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+    }
+  }
+  let fingerprint = weights
+    .iter()
+    .enumerate()
+    .filter(|(_, w)| **w > 0)
+    .fold(0u64, |acc, (bit, _)| acc | 1 << bit);
+  Ok(BigInt::from(fingerprint))
+}
+
+/// Number of differing bits between two 64-bit fingerprints.
+#[napi(catch_unwind)]
+pub fn hamming_distance(a: BigInt, b: BigInt) -> Result<u32> {
+  Ok((to_u64("a", &a)? ^ to_u64("b", &b)?).count_ones())
+}
+
+struct MinHashParams {
+  num_hashes: u32,
+  shingle_size: u32,
+  seed: u64,
+}
+
+impl MinHashParams {
+  fn new(options: Option<MinHashOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let num_hashes = options.num_hashes.unwrap_or(DEFAULT_NUM_HASHES);
+    if num_hashes == 0 {
+      return Err(AppError::InvalidInput(
+        "numHashes must be at least 1".into(),
+      ));
+    }
+    Ok(MinHashParams {
+      num_hashes,
+      shingle_size: shingle_size(options.shingle_size)?,
+      seed: options.seed.unwrap_or(0) as u64,
+    })
+  }
+
+  /// Permutation `i` of a shingle is `h1 + i·h2` (Kirsch–Mitzenmacher), so
+  /// each shingle is hashed twice instead of `num_hashes` times.
+  fn signature(&self, shingles: &[u64]) -> Vec<u32> {
+    let mut signature = vec![u32::MAX; self.num_hashes as usize];
+    for &shingle in shingles {
+      let bytes = shingle.to_le_bytes();
+      let h1 = xxh64(&bytes, self.seed);
+      let h2 = xxh64(&bytes, self.seed ^ 0x9e37_79b9_7f4a_7c15) | 1;
+      for (i, min) in signature.iter_mut().enumerate() {
+        let value = (h1.wrapping_add((i as u64).wrapping_mul(h2)) >> 32) as u32;
+        *min = (*min).min(value);
+      }
+    }
+    signature
+  }
+}
+
+/// Computes MinHash signatures, whose agreement estimates the Jaccard
+/// similarity of two texts' word-shingle sets.
+#[napi]
+pub struct MinHasher {
+  params: MinHashParams,
+}
+
+#[napi]
+impl MinHasher {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<MinHashOptions>) -> Result<Self> {
+    Ok(MinHasher {
+      params: MinHashParams::new(options)?,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn num_hashes(&self) -> u32 {
+    self.params.num_hashes
+  }
+
+  #[napi(catch_unwind)]
+  pub fn signature(&self, text: String) -> Uint32Array {
+    let shingles = shingles(&text, self.params.shingle_size);
+    Uint32Array::new(self.params.signature(&shingles))
+  }
+
+  /// Estimated Jaccard similarity (0–1) of the texts behind two signatures
+  /// from this hasher.
+  #[napi(catch_unwind)]
+  pub fn similarity(&self, a: Uint32Array, b: Uint32Array) -> Result<f64> {
+    if a.len() != self.params.num_hashes as usize || b.len() != a.len() {
+      return Err(AppError::InvalidInput(format!(
+        "signatures must both have {} values",
+        self.params.num_hashes
+      )));
+    }
+    let equal = a.iter().zip(b.iter()).filter(|(x, y)| x == y).count();
+    Ok(equal as f64 / a.len() as f64)
+  }
+}
+
+/// Picks `bands × rows = num_hashes` so that the LSH collision curve's
+/// midpoint, `(1/bands)^(1/rows)`, sits at or just below `threshold`.
+fn bands_for(num_hashes: u32, threshold: f64) -> (usize, usize) {
+  (1..=num_hashes)
+    .filter(|rows| num_hashes.is_multiple_of(*rows))
+    .map(|rows| {
+      let bands = num_hashes / rows;
+      let midpoint = (1.0 / bands as f64).powf(1.0 / rows as f64);
+      (bands as usize, rows as usize, midpoint)
+    })
+    .filter(|(_, _, midpoint)| *midpoint <= threshold)
+    .max_by(|a, b| a.2.total_cmp(&b.2))
+    .map_or((num_hashes as usize, 1), |(bands, rows, _)| (bands, rows))
+}
+
+fn jaccard(a: &[u64], b: &[u64]) -> f64 {
+  let (mut i, mut j, mut common) = (0, 0, 0);
+  while i < a.len() && j < b.len() {
+    match a[i].cmp(&b[j]) {
+      std::cmp::Ordering::Less => i += 1,
+      std::cmp::Ordering::Greater => j += 1,
+      std::cmp::Ordering::Equal => {
+        common += 1;
+        i += 1;
+        j += 1;
+      }
+    }
+  }
+  common as f64 / (a.len() + b.len() - common) as f64
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+  while parent[i] != i {
+    parent[i] = parent[parent[i]];
+    i = parent[i];
+  }
+  i
+}
+
+/// Groups documents whose word-shingle Jaccard similarity is at least
+/// `threshold` (0–1], transitively. MinHash banding finds the candidate
+/// pairs and each candidate is then checked exactly, all on the rayon pool.
+/// Returns only groups of two or more, as sorted document indexes ordered
+/// by their first member. Documents without any words never match.
+#[napi(catch_unwind)]
+pub fn near_duplicates(
+  docs: Vec<String>,
+  threshold: f64,
+  options: Option<MinHashOptions>,
+) -> Result<Vec<Vec<u32>>> {
+  if !(threshold > 0.0 && threshold <= 1.0) {
+    return Err(AppError::InvalidInput(format!(
+      "threshold must be in (0, 1], got {}",
+      threshold
+    )));
+  }
+  let params = MinHashParams::new(options)?;
+  let prepared: Vec<(Vec<u64>, Vec<u32>)> = docs
+    .par_iter()
+    .map(|doc| {
+      let mut set = if doc.chars().any(char::is_alphanumeric) {
+        shingles(doc, params.shingle_size)
+      } else {
+        Vec::new()
+      };
+      set.sort_unstable();
+      set.dedup();
+      let signature = params.signature(&set);
+      (set, signature)
+    })
+    .collect();
+
+  let (bands, rows) = bands_for(params.num_hashes, threshold);
+  let mut candidates: Vec<(usize, usize)> = (0..bands)
+    .into_par_iter()
+    .flat_map_iter(|band| {
+      let mut buckets: HashMap<&[u32], Vec<usize>> = HashMap::new();
+      for (i, (set, signature)) in prepared.iter().enumerate() {
+        if !set.is_empty() {
+          buckets
+            .entry(&signature[band * rows..(band + 1) * rows])
+            .or_default()
+            .push(i);
+        }
+      }
+      buckets
+        .into_values()
+        .flat_map(|members| {
+          (0..members.len())
+            .flat_map(|a| ((a + 1)..members.len()).map(move |b| (a, b)))
+            .map(|(a, b)| (members[a], members[b]))
+            .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+    })
+    .collect();
+  candidates.par_sort_unstable();
+  candidates.dedup();
+  let matches: Vec<(usize, usize)> = candidates
+    .into_par_iter()
+    .filter(|&(a, b)| jaccard(&prepared[a].0, &prepared[b].0) >= threshold)
+    .collect();
+
+  let mut parent: Vec<usize> = (0..docs.len()).collect();
+  for (a, b) in matches {
+    let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+    parent[ra.max(rb)] = ra.min(rb);
+  }
+  let mut groups: HashMap<usize, Vec<u32>> = HashMap::new();
+  for i in 0..docs.len() {
+    let root = find(&mut parent, i);
+    groups.entry(root).or_default().push(i as u32);
+  }
+  let mut groups: Vec<Vec<u32>> = groups.into_values().filter(|g| g.len() > 1).collect();
+  groups.sort_unstable_by_key(|g| g[0]);
+  Ok(groups)
+}
@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+
+use napi::bindgen_prelude::*;
+use napi::{
+  Env, JsArrayBuffer, JsDate, JsFunction, JsNumber, JsObject, JsString, JsTypedArray, JsUnknown,
+  ValueType,
+};
+
+use crate::buffer_ops::buffer_bytes;
+use crate::error::{AppError, Result};
+use crate::json::check_depth;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct DeepMergeOptions {
+  /// What to do when both sides have an array at the same key: `replace`
+  /// it with the one from `b` (the default), `concat` the two, or `merge`
+  /// them index by index.
+  #[napi(ts_type = "'replace' | 'concat' | 'merge'")]
+  pub arrays: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum ArrayMerge {
+  Replace,
+  Concat,
+  Merge,
+}
+
+#[derive(PartialEq)]
+enum Kind {
+  Date,
+  /// Buffers and every other typed array.
+  Bytes,
+  Array,
+  Object,
+  /// Primitives, functions and symbols: compared and copied as they are.
+  Plain,
+}
+
+fn kind(value: &JsUnknown) -> Result<Kind> {
+  Ok(match value.get_type()? {
+    ValueType::Object if value.is_date()? => Kind::Date,
+    ValueType::Object if value.is_typedarray()? => Kind::Bytes,
+    ValueType::Object if value.is_array()? => Kind::Array,
+    ValueType::Object => Kind::Object,
+    _ => Kind::Plain,
+  })
+}
+
+/// Own `__proto__` keys, as `JSON.parse` creates them, would replace the
+/// prototype of the copy when set, so they are never copied or merged.
+fn is_proto(key: JsString) -> Result<bool> {
+  Ok(key.utf8_len()? == 9 && key.into_utf8()?.as_str()? == "__proto__")
+}
+
+/// The element type and raw bytes of a typed array.
+fn typed_bytes(value: &JsUnknown) -> Result<(String, Vec<u8>)> {
+  let info = unsafe { value.cast::<JsTypedArray>() }.into_value()?;
+  let width = match info.typedarray_type {
+    TypedArrayType::Int8 | TypedArrayType::Uint8 | TypedArrayType::Uint8Clamped => 1,
+    TypedArrayType::Int16 | TypedArrayType::Uint16 => 2,
+    TypedArrayType::Int32 | TypedArrayType::Uint32 | TypedArrayType::Float32 => 4,
+    _ => 8,
+  };
+  let buffer = JsArrayBuffer::into_value(info.arraybuffer)?;
+  let bytes = buffer[info.byte_offset..info.byte_offset + info.length * width].to_vec();
+  Ok((format!("{:?}", info.typedarray_type), bytes))
+}
+
+fn clone_value(env: &Env, value: JsUnknown, depth: usize) -> Result<JsUnknown> {
+  check_depth(depth)?;
+  Ok(match kind(&value)? {
+    Kind::Date => {
+      let ms = unsafe { value.cast::<JsDate>() }.value_of()?;
+      env.create_date(ms)?.into_unknown()
+    }
+    Kind::Bytes => {
+      // `is_buffer` is true for any typed array, and `Buffer#slice` makes a
+      // view rather than a copy, so Buffers need telling apart.
+      let buffer_class: JsFunction = env.get_global()?.get_named_property("Buffer")?;
+      if value.instanceof(buffer_class)? {
+        env
+          .create_buffer_with_data(buffer_bytes(&value)?)?
+          .into_raw()
+          .into_unknown()
+      } else {
+        let array = unsafe { value.cast::<JsObject>() };
+        let slice: JsFunction = array.get_named_property("slice")?;
+        slice.call_without_args(Some(&array))?
+      }
+    }
+    Kind::Array => {
+      let source = unsafe { value.cast::<JsObject>() };
+      let len = source.get_array_length()?;
+      let mut array = env.create_array_with_length(len as usize)?;
+      for i in 0..len {
+        array.set_element(i, clone_value(env, source.get_element(i)?, depth + 1)?)?;
+      }
+      array.into_unknown()
+    }
+    Kind::Object => {
+      let source = unsafe { value.cast::<JsObject>() };
+      let keys = source.get_property_names()?;
+      let mut object = env.create_object()?;
+      for i in 0..keys.get_array_length()? {
+        let key = keys.get_element::<JsString>(i)?;
+        if !is_proto(key)? {
+          let value = clone_value(env, source.get_property(key)?, depth + 1)?;
+          object.set_property(key, value)?;
+        }
+      }
+      object.into_unknown()
+    }
+    Kind::Plain => value,
+  })
+}
+
+fn equal(env: &Env, a: JsUnknown, b: JsUnknown, depth: usize) -> Result<bool> {
+  check_depth(depth)?;
+  if env.strict_equals(&a, &b)? {
+    return Ok(true);
+  }
+  let (kind_a, kind_b) = (kind(&a)?, kind(&b)?);
+  if kind_a != kind_b {
+    return Ok(false);
+  }
+  Ok(match kind_a {
+    Kind::Plain => {
+      // `===` except that NaN equals itself.
+      a.get_type()? == ValueType::Number
+        && b.get_type()? == ValueType::Number
+        && unsafe { a.cast::<JsNumber>() }.get_double()?.is_nan()
+        && unsafe { b.cast::<JsNumber>() }.get_double()?.is_nan()
+    }
+    Kind::Date => {
+      let a = unsafe { a.cast::<JsDate>() }.value_of()?;
+      let b = unsafe { b.cast::<JsDate>() }.value_of()?;
+      a == b || (a.is_nan() && b.is_nan())
+    }
+    Kind::Bytes => typed_bytes(&a)? == typed_bytes(&b)?,
+    Kind::Array => {
+      let (a, b) = unsafe { (a.cast::<JsObject>(), b.cast::<JsObject>()) };
+      let len = a.get_array_length()?;
+      if len != b.get_array_length()? {
+        return Ok(false);
+      }
+      for i in 0..len {
+        if !equal(env, a.get_element(i)?, b.get_element(i)?, depth + 1)? {
+          return Ok(false);
+        }
+      }
+      true
+    }
+    Kind::Object => {
+      let (a, b) = unsafe { (a.cast::<JsObject>(), b.cast::<JsObject>()) };
+      let (keys_a, keys_b) = (a.get_property_names()?, b.get_property_names()?);
+      let len = keys_a.get_array_length()?;
+      if len != keys_b.get_array_length()? {
+        return Ok(false);
+      }
+      let mut names_b = HashSet::with_capacity(len as usize);
+      for i in 0..len {
+        names_b.insert(
+          keys_b
+            .get_element::<JsString>(i)?
+            .into_utf8()?
+            .into_owned()?,
+        );
+      }
+      for i in 0..len {
+        let key = keys_a.get_element::<JsString>(i)?;
+        if !names_b.contains(key.into_utf8()?.as_str()?)
+          || !equal(env, a.get_property(key)?, b.get_property(key)?, depth + 1)?
+        {
+          return Ok(false);
+        }
+      }
+      true
+    }
+  })
+}
+
+/// Merges `incoming` into `existing`, a value this call owns (a clone),
+/// and returns the result.
+fn merge_value(
+  env: &Env,
+  existing: JsUnknown,
+  incoming: JsUnknown,
+  arrays: ArrayMerge,
+  depth: usize,
+) -> Result<JsUnknown> {
+  check_depth(depth)?;
+  if incoming.get_type()? == ValueType::Undefined {
+    return Ok(existing);
+  }
+  match (kind(&existing)?, kind(&incoming)?) {
+    (Kind::Object, Kind::Object) => {
+      let mut target = unsafe { existing.cast::<JsObject>() };
+      let source = unsafe { incoming.cast::<JsObject>() };
+      let keys = source.get_property_names()?;
+      for i in 0..keys.get_array_length()? {
+        let key = keys.get_element::<JsString>(i)?;
+        if is_proto(key)? {
+          continue;
+        }
+        let current = if target.has_own_property_js(key)? {
+          target.get_property(key)?
+        } else {
+          env.get_undefined()?.into_unknown()
+        };
+        let merged = merge_value(env, current, source.get_property(key)?, arrays, depth + 1)?;
+        target.set_property(key, merged)?;
+      }
+      Ok(existing)
+    }
+    (Kind::Array, Kind::Array) if !matches!(arrays, ArrayMerge::Replace) => {
+      let mut target = unsafe { existing.cast::<JsObject>() };
+      let source = unsafe { incoming.cast::<JsObject>() };
+      let offset = match arrays {
+        ArrayMerge::Concat => target.get_array_length()?,
+        _ => 0,
+      };
+      let target_len = target.get_array_length()?;
+      for i in 0..source.get_array_length()? {
+        let value = source.get_element::<JsUnknown>(i)?;
+        let merged = if offset + i < target_len {
+          merge_value(env, target.get_element(i)?, value, arrays, depth + 1)?
+        } else {
+          clone_value(env, value, depth + 1)?
+        };
+        target.set_element(offset + i, merged)?;
+      }
+      Ok(existing)
+    }
+    _ => clone_value(env, incoming, depth),
+  }
+}
+
+/// A deep copy of `value`: plain objects (their enumerable string keys),
+/// arrays, Dates and Buffers or other typed arrays are copied all the way
+/// down; everything else, functions included, is shared. Cyclic values
+/// throw `ERR_INVALID_INPUT`.
+#[napi(catch_unwind, ts_args_type = "value: T", ts_generic_types = "T")]
+pub fn deep_clone(env: Env, value: JsUnknown) -> Result<JsUnknown> {
+  clone_value(&env, value, 0)
+}
+
+/// Whether `a` and `b` hold the same data: equal primitives (`NaN` equals
+/// `NaN`), Dates with the same time, typed arrays of the same type and
+/// bytes, and arrays and objects with equal entries. Prototypes are not
+/// compared, and a key set to `undefined` is not the same as a missing one.
+#[napi(catch_unwind, ts_args_type = "a: unknown, b: unknown")]
+pub fn deep_equal(env: Env, a: JsUnknown, b: JsUnknown) -> Result<bool> {
+  equal(&env, a, b, 0)
+}
+
+/// A new object with `b` merged into `a`; neither is changed. Nested
+/// objects are merged key by key, arrays as `options.arrays` says, and any
+/// other value in `b` replaces the one in `a` unless it is `undefined`.
+/// Merged values are deep copies, so the result shares nothing mutable
+/// with the inputs.
+#[napi(
+  catch_unwind,
+  ts_args_type = "a: object, b: object, options?: DeepMergeOptions",
+  ts_return_type = "object"
+)]
+pub fn deep_merge(
+  env: Env,
+  a: JsUnknown,
+  b: JsUnknown,
+  options: Option<DeepMergeOptions>,
+) -> Result<JsUnknown> {
+  let arrays = match options.unwrap_or_default().arrays.as_deref() {
+    None | Some("replace") => ArrayMerge::Replace,
+    Some("concat") => ArrayMerge::Concat,
+    Some("merge") => ArrayMerge::Merge,
+    Some(other) => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown array merge strategy: {}",
+        other
+      )))
+    }
+  };
+  if kind(&a)? != Kind::Object || kind(&b)? != Kind::Object {
+    return Err(AppError::InvalidInput(
+      "deepMerge expects two plain objects".into(),
+    ));
+  }
+  let target = clone_value(&env, a, 0)?;
+  merge_value(&env, target, b, arrays, 0)
+}
@@ -0,0 +1,148 @@
+use napi::{Env, JsUnknown};
+use serde_json::Value;
+use similar::{DiffTag, TextDiff};
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+/// A run of consecutive units (lines or words) with the same status.
+#[napi(object)]
+pub struct DiffHunk {
+  /// `equal`, `insert` or `delete`.
+  pub tag: String,
+  /// The units of the run, concatenated.
+  pub value: String,
+  /// Position of the run in the old and new sequence, in units.
+  pub old_start: u32,
+  pub old_count: u32,
+  pub new_start: u32,
+  pub new_count: u32,
+}
+
+#[napi(object)]
+pub struct PatchOptions {
+  /// Unchanged lines shown around each change. Defaults to 3.
+  pub context: Option<u32>,
+  /// File names for the `---`/`+++` header. Default to `a` and `b`.
+  pub old_name: Option<String>,
+  pub new_name: Option<String>,
+}
+
+/// One RFC 6902 operation.
+#[napi(object)]
+pub struct JsonPatchOp {
+  /// `add`, `remove`, `replace`, `move`, `copy` or `test`.
+  pub op: String,
+  /// JSON Pointer to the target location.
+  pub path: String,
+  pub value: Option<Value>,
+  /// Source location of `move` and `copy`.
+  pub from: Option<String>,
+}
+
+fn hunks<'a>(diff: &TextDiff<'a, 'a, 'a, str>) -> Vec<DiffHunk> {
+  let (old, new) = (diff.old_slices(), diff.new_slices());
+  let mut out = Vec::new();
+  let mut push = |tag: &str, slices: &[&str], old: (usize, usize), new: (usize, usize)| {
+    out.push(DiffHunk {
+      tag: tag.to_string(),
+      value: slices.concat(),
+      old_start: old.0 as u32,
+      old_count: old.1 as u32,
+      new_start: new.0 as u32,
+      new_count: new.1 as u32,
+    })
+  };
+  for op in diff.ops() {
+    let (tag, o, n) = op.as_tag_tuple();
+    match tag {
+      DiffTag::Equal => push(
+        "equal",
+        &old[o.clone()],
+        (o.start, o.len()),
+        (n.start, n.len()),
+      ),
+      DiffTag::Delete => push("delete", &old[o.clone()], (o.start, o.len()), (n.start, 0)),
+      DiffTag::Insert => push("insert", &new[n.clone()], (o.start, 0), (n.start, n.len())),
+      DiffTag::Replace => {
+        push("delete", &old[o.clone()], (o.start, o.len()), (n.start, 0));
+        push("insert", &new[n.clone()], (o.end, 0), (n.start, n.len()));
+      }
+    }
+  }
+  out
+}
+
+/// Line-by-line diff (Myers). Line endings stay attached to their lines.
+#[napi(catch_unwind)]
+pub fn diff_lines(old: String, new: String) -> Vec<DiffHunk> {
+  hunks(&TextDiff::from_lines(&old, &new))
+}
+
+/// Word-level diff; runs of whitespace count as words, so concatenating
+/// the `equal` and `insert` values rebuilds `new`.
+#[napi(catch_unwind)]
+pub fn diff_words(old: String, new: String) -> Vec<DiffHunk> {
+  hunks(&TextDiff::from_words(&old, &new))
+}
+
+/// A unified diff (`diff -u` / `git diff` format) from `old` to `new`.
+/// Empty when the inputs are equal.
+#[napi(catch_unwind)]
+pub fn format_patch(old: String, new: String, options: Option<PatchOptions>) -> String {
+  let (context, old_name, new_name) = match options {
+    Some(o) => (o.context, o.old_name, o.new_name),
+    None => (None, None, None),
+  };
+  if old == new {
+    return String::new();
+  }
+  TextDiff::from_lines(&old, &new)
+    .unified_diff()
+    .context_radius(context.unwrap_or(3) as usize)
+    .header(
+      old_name.as_deref().unwrap_or("a"),
+      new_name.as_deref().unwrap_or("b"),
+    )
+    .to_string()
+}
+
+/// The RFC 6902 JSON Patch that turns `a` into `b`.
+#[napi(catch_unwind, ts_args_type = "a: unknown, b: unknown")]
+pub fn diff_json(env: Env, a: JsUnknown, b: JsUnknown) -> Result<Vec<JsonPatchOp>> {
+  let a: Value = from_js(&env, a)?;
+  let b: Value = from_js(&env, b)?;
+  json_patch::diff(&a, &b)
+    .0
+    .into_iter()
+    .map(|op| {
+      let Value::Object(mut fields) = serde_json::to_value(op)? else {
+        unreachable!("patch operations serialize as objects");
+      };
+      let mut text = |key: &str| match fields.remove(key) {
+        Some(Value::String(s)) => Some(s),
+        _ => None,
+      };
+      Ok(JsonPatchOp {
+        op: text("op").unwrap_or_default(),
+        path: text("path").unwrap_or_default(),
+        from: text("from"),
+        value: fields.remove("value"),
+      })
+    })
+    .collect()
+}
+
+/// Applies an RFC 6902 patch and returns the patched document; `doc`
+/// itself is not modified. Fails with `ERR_INVALID_INPUT` if an operation
+/// is malformed, a path does not exist or a `test` fails.
+#[napi(catch_unwind, ts_args_type = "doc: unknown, patch: JsonPatchOp[]")]
+pub fn apply_patch(env: Env, doc: JsUnknown, patch: JsUnknown) -> Result<Value> {
+  let mut doc: Value = from_js(&env, doc)?;
+  let patch: Vec<Value> = from_js(&env, patch)?;
+  let patch: json_patch::Patch = serde_json::from_value(Value::Array(patch))
+    .map_err(|e| AppError::InvalidInput(format!("invalid JSON Patch: {}", e)))?;
+  json_patch::patch(&mut doc, &patch)
+    .map_err(|e| AppError::InvalidInput(format!("cannot apply patch: {}", e)))?;
+  Ok(doc)
+}
@@ -0,0 +1,173 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use hickory_resolver::config::{NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig};
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::TokioAsyncResolver;
+use napi::{Env, JsObject};
+
+use crate::error::{self, AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct DnsOptions {
+  /// Servers to query as `ip` or `ip:port` (IPv6 with a port as
+  /// `[ip]:port`). Defaults to the system configuration.
+  pub nameservers: Option<Vec<String>>,
+  /// Per-attempt timeout. Defaults to 5000.
+  pub timeout_ms: Option<u32>,
+  /// Attempts per nameserver before giving up. Defaults to 2.
+  pub attempts: Option<u32>,
+}
+
+#[napi(object)]
+pub struct MxRecord {
+  pub exchange: String,
+  pub priority: u16,
+}
+
+fn parse_nameserver(server: &str) -> Result<SocketAddr> {
+  server
+    .parse::<SocketAddr>()
+    .or_else(|_| server.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+    .map_err(|_| AppError::InvalidInput(format!("invalid nameserver address: {}", server)))
+}
+
+/// Builds a resolver on the JS thread so bad options throw synchronously.
+/// The resolver only opens sockets once a query runs.
+fn resolver(options: Option<DnsOptions>) -> Result<TokioAsyncResolver> {
+  let options = options.unwrap_or_default();
+  let (config, mut opts) = match options.nameservers {
+    Some(servers) => {
+      let mut group = NameServerConfigGroup::new();
+      for server in &servers {
+        let addr = parse_nameserver(server)?;
+        // TCP is only used when a UDP answer comes back truncated.
+        group.push(NameServerConfig::new(addr, Protocol::Udp));
+        group.push(NameServerConfig::new(addr, Protocol::Tcp));
+      }
+      (
+        ResolverConfig::from_parts(None, Vec::new(), group),
+        Default::default(),
+      )
+    }
+    None => hickory_resolver::system_conf::read_system_conf()
+      .map_err(|e| AppError::Io(format!("cannot read the system DNS configuration: {}", e)))?,
+  };
+  if let Some(ms) = options.timeout_ms {
+    opts.timeout = Duration::from_millis(ms as u64);
+  }
+  if let Some(attempts) = options.attempts {
+    opts.attempts = attempts as usize;
+  }
+  Ok(TokioAsyncResolver::tokio(config, opts))
+}
+
+fn dns_error(name: &str, e: ResolveError) -> AppError {
+  match e.kind() {
+    ResolveErrorKind::NoRecordsFound { .. } => {
+      AppError::NotFound(format!("{}: no DNS records found", name))
+    }
+    ResolveErrorKind::Timeout => AppError::Timeout(format!("{}: DNS query timed out", name)),
+    _ => AppError::Network(format!("{}: {}", name, e)),
+  }
+}
+
+fn trim_dot(name: String) -> String {
+  match name.strip_suffix('.') {
+    Some(name) => name.to_string(),
+    None => name,
+  }
+}
+
+/// IPv4 addresses for `host`. Rejects with `ERR_NOT_FOUND` when the name
+/// has no A records.
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn resolve_a(env: Env, host: String, options: Option<DnsOptions>) -> Result<JsObject> {
+  let resolver = resolver(options)?;
+  error::spawn(&env, async move {
+    let lookup = resolver
+      .ipv4_lookup(host.as_str())
+      .await
+      .map_err(|e| dns_error(&host, e))?;
+    Ok(lookup.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+  })
+}
+
+/// IPv6 addresses for `host`.
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn resolve_aaaa(env: Env, host: String, options: Option<DnsOptions>) -> Result<JsObject> {
+  let resolver = resolver(options)?;
+  error::spawn(&env, async move {
+    let lookup = resolver
+      .ipv6_lookup(host.as_str())
+      .await
+      .map_err(|e| dns_error(&host, e))?;
+    Ok(lookup.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+  })
+}
+
+/// TXT records for `host`, one string per record with its character
+/// strings concatenated (as SPF and DKIM expect).
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn resolve_txt(env: Env, host: String, options: Option<DnsOptions>) -> Result<JsObject> {
+  let resolver = resolver(options)?;
+  error::spawn(&env, async move {
+    let lookup = resolver
+      .txt_lookup(host.as_str())
+      .await
+      .map_err(|e| dns_error(&host, e))?;
+    Ok(
+      lookup
+        .iter()
+        .map(|txt| String::from_utf8_lossy(&txt.txt_data().concat()).into_owned())
+        .collect::<Vec<_>>(),
+    )
+  })
+}
+
+/// Mail exchangers for `host`, lowest priority value (most preferred) first.
+#[napi(catch_unwind, ts_return_type = "Promise<MxRecord[]>")]
+pub fn resolve_mx(env: Env, host: String, options: Option<DnsOptions>) -> Result<JsObject> {
+  let resolver = resolver(options)?;
+  error::spawn(&env, async move {
+    let lookup = resolver
+      .mx_lookup(host.as_str())
+      .await
+      .map_err(|e| dns_error(&host, e))?;
+    let mut records: Vec<MxRecord> = lookup
+      .iter()
+      .map(|mx| MxRecord {
+        exchange: trim_dot(mx.exchange().to_utf8()),
+        priority: mx.preference(),
+      })
+      .collect();
+    records.sort_by(|a, b| {
+      a.priority
+        .cmp(&b.priority)
+        .then_with(|| a.exchange.cmp(&b.exchange))
+    });
+    Ok(records)
+  })
+}
+
+/// Host names for `ip` from its PTR records.
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn reverse_lookup(env: Env, ip: String, options: Option<DnsOptions>) -> Result<JsObject> {
+  let addr: IpAddr = ip
+    .parse()
+    .map_err(|_| AppError::InvalidInput(format!("invalid IP address: {}", ip)))?;
+  let resolver = resolver(options)?;
+  error::spawn(&env, async move {
+    let lookup = resolver
+      .reverse_lookup(addr)
+      .await
+      .map_err(|e| dns_error(&ip, e))?;
+    Ok(
+      lookup
+        .iter()
+        .map(|name| trim_dot(name.to_utf8()))
+        .collect::<Vec<_>>(),
+    )
+  })
+}
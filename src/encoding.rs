@@ -0,0 +1,265 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{CoderResult, DecoderResult, EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct EncodingGuess {
+  /// The WHATWG name of the encoding, e.g. `UTF-8` or `windows-1252`.
+  pub encoding: String,
+  /// False when another encoding scored about as well, so the guess is
+  /// likely to be wrong. Short inputs are rarely confident.
+  pub confident: bool,
+  /// Whether the input starts with a byte order mark, which settles the
+  /// encoding.
+  pub bom: bool,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct DecodeOptions {
+  /// Throw `ERR_PARSE` on malformed input instead of decoding it as
+  /// U+FFFD.
+  pub fatal: Option<bool>,
+  /// Keep a leading byte order mark in the output instead of dropping it.
+  pub ignore_bom: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct EncodeOptions {
+  /// Throw `ERR_INVALID_INPUT` on a character the encoding can't represent
+  /// instead of writing it as an HTML character reference like `&#8364;`,
+  /// as browsers do.
+  pub fatal: Option<bool>,
+  /// Start the output with a byte order mark. Only UTF-8 and UTF-16 have
+  /// one.
+  pub bom: Option<bool>,
+}
+
+/// Resolves a WHATWG encoding label such as `latin1`, `cp1251`, `sjis` or
+/// `utf-16le`, case-insensitively.
+fn lookup(label: Option<&str>) -> Result<&'static Encoding> {
+  let label = label.unwrap_or("utf-8");
+  Encoding::for_label(label.trim().as_bytes())
+    .ok_or_else(|| AppError::Unsupported(format!("unknown encoding: {}", label)))
+}
+
+/// Wraps `encoding_rs::Decoder` with the stream offset, to say where
+/// malformed input is.
+struct Stream {
+  decoder: encoding_rs::Decoder,
+  encoding: &'static Encoding,
+  fatal: bool,
+  ignore_bom: bool,
+  offset: u64,
+}
+
+impl Stream {
+  fn new(label: Option<&str>, options: Option<DecodeOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let encoding = lookup(label)?;
+    let ignore_bom = options.ignore_bom.unwrap_or(false);
+    Ok(Stream {
+      decoder: Self::decoder(encoding, ignore_bom),
+      encoding,
+      fatal: options.fatal.unwrap_or(false),
+      ignore_bom,
+      offset: 0,
+    })
+  }
+
+  fn decoder(encoding: &'static Encoding, ignore_bom: bool) -> encoding_rs::Decoder {
+    if ignore_bom {
+      encoding.new_decoder_without_bom_handling()
+    } else {
+      encoding.new_decoder_with_bom_removal()
+    }
+  }
+
+  fn decode(&mut self, mut input: &[u8], last: bool) -> Result<String> {
+    let mut output = String::new();
+    loop {
+      let needed = self
+        .decoder
+        .max_utf8_buffer_length(input.len())
+        .ok_or_else(|| AppError::Overflow("input is too large to decode".into()))?;
+      output.reserve(needed);
+      let (read, done) = if self.fatal {
+        let (result, read) =
+          self
+            .decoder
+            .decode_to_string_without_replacement(input, &mut output, last);
+        if let DecoderResult::Malformed(length, after) = result {
+          let at = (self.offset + read as u64).saturating_sub(u64::from(length) + u64::from(after));
+          return Err(AppError::Parse(format!(
+            "invalid {} at byte {}",
+            self.encoding.name(),
+            at
+          )));
+        }
+        (read, result == DecoderResult::InputEmpty)
+      } else {
+        let (result, read, _) = self.decoder.decode_to_string(input, &mut output, last);
+        (read, result == CoderResult::InputEmpty)
+      };
+      self.offset += read as u64;
+      input = &input[read..];
+      if done {
+        return Ok(output);
+      }
+    }
+  }
+}
+
+/// Guesses the encoding of `data` the way Firefox does for unlabeled
+/// pages: a byte order mark wins, otherwise the bytes are scored against
+/// each encoding's typical letter statistics. `tld`, the top-level domain
+/// the text came from such as `jp` or `ru`, breaks ties towards the
+/// region's usual encoding. Pure ASCII is reported as `UTF-8`.
+#[napi(catch_unwind)]
+pub fn detect_encoding(data: Buffer, tld: Option<String>) -> Result<EncodingGuess> {
+  if let Some((encoding, _)) = Encoding::for_bom(&data) {
+    return Ok(EncodingGuess {
+      encoding: encoding.name().into(),
+      confident: true,
+      bom: true,
+    });
+  }
+  let tld = tld.map(|tld| tld.to_ascii_lowercase());
+  if let Some(tld) = &tld {
+    if tld.is_empty() || !tld.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+      return Err(AppError::InvalidInput(format!(
+        "tld must be a single ASCII domain label, got {:?}",
+        tld
+      )));
+    }
+  }
+  let mut detector = EncodingDetector::new();
+  let non_ascii = detector.feed(&data, true);
+  let (encoding, confident) = detector.guess_assess(tld.as_deref().map(str::as_bytes), true);
+  let encoding = if non_ascii || encoding != encoding_rs::WINDOWS_1252 {
+    encoding
+  } else {
+    UTF_8
+  };
+  Ok(EncodingGuess {
+    encoding: encoding.name().into(),
+    confident,
+    bom: false,
+  })
+}
+
+/// Decodes `data` from the encoding named by `label` (default `utf-8`):
+/// any WHATWG encoding, which covers the legacy Windows, ISO-8859, KOI8,
+/// Mac, DOS Cyrillic and CJK codepages. Unknown labels throw
+/// `ERR_UNSUPPORTED`.
+#[napi(catch_unwind)]
+pub fn decode(
+  data: Buffer,
+  label: Option<String>,
+  options: Option<DecodeOptions>,
+) -> Result<String> {
+  Stream::new(label.as_deref(), options)?.decode(&data, true)
+}
+
+/// Encodes `text` in the encoding named by `label` (default `utf-8`).
+#[napi(catch_unwind)]
+pub fn encode(
+  text: String,
+  label: Option<String>,
+  options: Option<EncodeOptions>,
+) -> Result<Buffer> {
+  let options = options.unwrap_or_default();
+  let encoding = lookup(label.as_deref())?;
+  let mut output = Vec::new();
+  if options.bom.unwrap_or(false) {
+    if encoding == UTF_8 {
+      output.extend_from_slice(b"\xEF\xBB\xBF");
+    } else if encoding == UTF_16LE {
+      output.extend_from_slice(b"\xFF\xFE");
+    } else if encoding == UTF_16BE {
+      output.extend_from_slice(b"\xFE\xFF");
+    }
+  }
+  // encoding_rs only decodes UTF-16, as the web never sends it.
+  if encoding == UTF_16LE || encoding == UTF_16BE {
+    for unit in text.encode_utf16() {
+      output.extend_from_slice(&if encoding == UTF_16LE {
+        unit.to_le_bytes()
+      } else {
+        unit.to_be_bytes()
+      });
+    }
+    return Ok(output.into());
+  }
+  if !options.fatal.unwrap_or(false) {
+    output.extend_from_slice(&encoding.encode(&text).0);
+    return Ok(output.into());
+  }
+  let mut encoder = encoding.new_encoder();
+  let mut input = text.as_str();
+  loop {
+    let needed = encoder
+      .max_buffer_length_from_utf8_without_replacement(input.len())
+      .ok_or_else(|| AppError::Overflow("text is too large to encode".into()))?;
+    output.reserve(needed);
+    let (result, read) =
+      encoder.encode_from_utf8_to_vec_without_replacement(input, &mut output, true);
+    input = &input[read..];
+    match result {
+      EncoderResult::InputEmpty => return Ok(output.into()),
+      EncoderResult::OutputFull => continue,
+      EncoderResult::Unmappable(c) => {
+        return Err(AppError::InvalidInput(format!(
+          "U+{:04X} cannot be encoded in {}",
+          c as u32,
+          encoding.name()
+        )))
+      }
+    }
+  }
+}
+
+/// Decodes a stream that arrives in chunks. Characters split across
+/// chunks come out whole in the call that completes them.
+#[napi]
+pub struct Decoder {
+  stream: Stream,
+}
+
+#[napi]
+impl Decoder {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(label: Option<String>, options: Option<DecodeOptions>) -> Result<Self> {
+    Ok(Decoder {
+      stream: Stream::new(label.as_deref(), options)?,
+    })
+  }
+
+  /// The text completed by `chunk`.
+  #[napi(catch_unwind)]
+  pub fn write(&mut self, chunk: Buffer) -> Result<String> {
+    self.stream.decode(&chunk, false)
+  }
+
+  /// Decodes the final `chunk`, if any, and whatever incomplete character
+  /// is left, then resets the decoder for a new stream.
+  #[napi(catch_unwind)]
+  pub fn end(&mut self, chunk: Option<Buffer>) -> Result<String> {
+    let text = self
+      .stream
+      .decode(chunk.as_deref().unwrap_or_default(), true);
+    self.stream.decoder = Stream::decoder(self.stream.encoding, self.stream.ignore_bom);
+    self.stream.offset = 0;
+    text
+  }
+
+  /// The WHATWG name of the encoding, e.g. `Shift_JIS` for the label
+  /// `sjis`.
+  #[napi(catch_unwind, getter)]
+  pub fn encoding(&self) -> &'static str {
+    self.stream.encoding.name()
+  }
+}
@@ -0,0 +1,634 @@
+use std::collections::HashMap;
+
+use napi::{Env, JsObject, JsString, JsUnknown, ValueType};
+
+use crate::error::{AppError, Result};
+
+/// An ordered list of key/value pairs, kept in file order so parsed objects
+/// and written files follow the same key order.
+type Pairs = Vec<(String, String)>;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct DotenvOptions {
+  /// Expand `$VAR`, `${VAR}` and `${VAR:-default}` in unquoted and
+  /// double-quoted values. Defaults to `true`.
+  pub expand: Option<bool>,
+  /// Also assign the parsed variables to `process.env`.
+  pub apply: Option<bool>,
+  /// With `apply`, replace variables that are already set in
+  /// `process.env`. Defaults to `false`.
+  #[napi(js_name = "override")]
+  pub override_existing: Option<bool>,
+}
+
+fn insert(pairs: &mut Pairs, key: String, value: String) {
+  match pairs.iter_mut().find(|(k, _)| *k == key) {
+    Some(entry) => entry.1 = value,
+    None => pairs.push((key, value)),
+  }
+}
+
+fn to_object(env: &Env, pairs: &[(String, String)]) -> Result<JsObject> {
+  let mut object = env.create_object()?;
+  for (key, value) in pairs {
+    object.set_named_property(key, env.create_string(value)?)?;
+  }
+  Ok(object)
+}
+
+/// `object`'s own keys in property order.
+fn keys(object: &JsObject) -> Result<Vec<String>> {
+  let names = object.get_property_names()?;
+  (0..names.get_array_length()?)
+    .map(|i| {
+      Ok(
+        names
+          .get_element::<JsString>(i)?
+          .into_utf8()?
+          .into_owned()?,
+      )
+    })
+    .collect()
+}
+
+/// Strings, numbers and booleans as text; anything else is rejected.
+fn scalar(key: &str, value: JsUnknown) -> Result<String> {
+  match value.get_type()? {
+    ValueType::String | ValueType::Number | ValueType::Boolean => {
+      Ok(value.coerce_to_string()?.into_utf8()?.into_owned()?)
+    }
+    other => Err(AppError::InvalidInput(format!(
+      "{}: expected a string, number or boolean, got {:?}",
+      key, other
+    ))),
+  }
+}
+
+fn line_of(input: &str, pos: usize) -> usize {
+  input[..pos].matches('\n').count() + 1
+}
+
+fn process_env(env: &Env) -> Result<JsObject> {
+  let process: JsObject = env.get_global()?.get_named_property("process")?;
+  Ok(process.get_named_property("env")?)
+}
+
+struct DotenvParser<'a> {
+  input: &'a str,
+  pos: usize,
+}
+
+impl<'a> DotenvParser<'a> {
+  fn error(&self, message: &str) -> AppError {
+    AppError::Parse(format!(
+      "invalid dotenv at line {}: {}",
+      line_of(self.input, self.pos),
+      message
+    ))
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.input[self.pos..]
+  }
+
+  fn skip_blank(&mut self) {
+    let rest = self.rest();
+    self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+  }
+
+  fn skip_comment_or_eol(&mut self) -> Result<()> {
+    self.skip_blank();
+    let rest = self.rest();
+    if rest.is_empty() || rest.starts_with(['\n', '\r', '#']) {
+      self.pos += rest.find('\n').map_or(rest.len(), |i| i + 1);
+      Ok(())
+    } else {
+      Err(self.error("unexpected text after value"))
+    }
+  }
+
+  fn key(&mut self) -> Result<String> {
+    let rest = self.rest();
+    let len = rest
+      .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')))
+      .unwrap_or(rest.len());
+    let key = &rest[..len];
+    if !key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+      return Err(self.error("expected a variable name"));
+    }
+    self.pos += len;
+    Ok(key.to_string())
+  }
+
+  /// The raw text between `quote` and its closing match, which may span
+  /// lines. Inside double quotes a backslash escapes the next character.
+  fn quoted(&mut self, quote: char) -> Result<&'a str> {
+    let start = self.pos + 1;
+    let mut chars = self.input[start..].char_indices();
+    while let Some((i, c)) = chars.next() {
+      if c == quote {
+        self.pos = start + i + 1;
+        return Ok(&self.input[start..start + i]);
+      }
+      if c == '\\' && quote == '"' {
+        chars.next();
+      }
+    }
+    Err(self.error("unterminated quoted value"))
+  }
+
+  /// An unquoted value runs to the end of the line; ` #` starts a comment.
+  fn unquoted(&mut self) -> &'a str {
+    let rest = self.rest();
+    let end = rest.find('\n').unwrap_or(rest.len());
+    let line = &rest[..end];
+    let line = line
+      .char_indices()
+      .find(|&(i, c)| c == '#' && line[..i].ends_with([' ', '\t']))
+      .map_or(line, |(i, _)| &line[..i]);
+    self.pos += end;
+    line.trim_end()
+  }
+}
+
+type Lookup<'a> = dyn FnMut(&str) -> Result<Option<String>> + 'a;
+
+/// Applies backslash escapes (when `escapes`) and variable references
+/// (when `lookup` is given) to a raw value.
+fn render(raw: &str, escapes: bool, lookup: &mut Option<&mut Lookup>) -> Result<String> {
+  let mut out = String::with_capacity(raw.len());
+  let mut chars = raw.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' if escapes => match chars.next() {
+        Some('n') => out.push('\n'),
+        Some('r') => out.push('\r'),
+        Some('t') => out.push('\t'),
+        Some(c @ ('"' | '\\' | '$')) => out.push(c),
+        Some(c) => {
+          out.push('\\');
+          out.push(c);
+        }
+        None => out.push('\\'),
+      },
+      '$' if lookup.is_some() => {
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+          chars.next();
+          let mut body = String::new();
+          let mut depth = 0;
+          loop {
+            match chars.next() {
+              Some('}') if depth == 0 => break,
+              Some(c) => {
+                depth += (c == '{') as i32 - (c == '}') as i32;
+                body.push(c);
+              }
+              None => {
+                return Err(AppError::Parse(format!(
+                  "invalid dotenv: unterminated ${{{}",
+                  body
+                )))
+              }
+            }
+          }
+          let (name, default) = match body.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (body.as_str(), None),
+          };
+          match (lookup.as_mut().expect("checked above")(name)?, default) {
+            (Some(v), _) if !v.is_empty() => out.push_str(&v),
+            (_, Some(default)) => out.push_str(&render(default, false, lookup)?),
+            _ => {}
+          }
+        } else {
+          let mut name = String::new();
+          while let Some(&c) = chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_') {
+              break;
+            }
+            name.push(c);
+            chars.next();
+          }
+          if name.is_empty() {
+            out.push('$');
+          } else if let Some(v) = lookup.as_mut().expect("checked above")(&name)? {
+            out.push_str(&v);
+          }
+        }
+      }
+      c => out.push(c),
+    }
+  }
+  Ok(out)
+}
+
+fn parse_dotenv_pairs(
+  input: &str,
+  mut env_lookup: impl FnMut(&str) -> Result<Option<String>>,
+  expand: bool,
+) -> Result<Pairs> {
+  let mut parser = DotenvParser { input, pos: 0 };
+  let mut pairs = Pairs::new();
+  let mut known: HashMap<String, String> = HashMap::new();
+  loop {
+    let rest = parser.rest();
+    parser.pos += rest.len() - rest.trim_start().len();
+    if parser.rest().is_empty() {
+      return Ok(pairs);
+    }
+    if parser.rest().starts_with('#') {
+      parser.skip_comment_or_eol()?;
+      continue;
+    }
+    if let Some(after) = parser.rest().strip_prefix("export") {
+      if after.starts_with([' ', '\t']) {
+        parser.pos += "export".len();
+        parser.skip_blank();
+      }
+    }
+    let key = parser.key()?;
+    parser.skip_blank();
+    if !parser.rest().starts_with('=') {
+      return Err(parser.error(&format!("expected '=' after {}", key)));
+    }
+    parser.pos += 1;
+    parser.skip_blank();
+
+    let mut lookup = |name: &str| match known.get(name) {
+      Some(v) => Ok(Some(v.clone())),
+      None => env_lookup(name),
+    };
+    let mut lookup: Option<&mut Lookup> = if expand { Some(&mut lookup) } else { None };
+    let value = if parser.rest().starts_with('\'') {
+      let raw = parser.quoted('\'')?;
+      parser.skip_comment_or_eol()?;
+      raw.to_string()
+    } else if parser.rest().starts_with('"') {
+      let raw = parser.quoted('"')?;
+      parser.skip_comment_or_eol()?;
+      render(raw, true, &mut lookup)?
+    } else {
+      let raw = parser.unquoted();
+      render(raw, false, &mut lookup)?
+    };
+    known.insert(key.clone(), value.clone());
+    insert(&mut pairs, key, value);
+  }
+}
+
+/// Parses a `.env` file into an object of strings.
+///
+/// Supports `#` comments, an optional `export` prefix, single-quoted
+/// (literal) and double-quoted (escaped) values that may span lines, and
+/// variable expansion. References resolve against earlier variables in the
+/// file, then `process.env`; unset variables expand to an empty string.
+#[napi(catch_unwind, ts_return_type = "Record<string, string>")]
+pub fn parse_dotenv(env: Env, input: String, options: Option<DotenvOptions>) -> Result<JsObject> {
+  let options = options.unwrap_or_default();
+  let mut process_vars = process_env(&env)?;
+  let pairs = parse_dotenv_pairs(
+    &input,
+    |name| {
+      let value: JsUnknown = process_vars.get_named_property(name)?;
+      Ok(match value.get_type()? {
+        ValueType::Undefined => None,
+        _ => Some(value.coerce_to_string()?.into_utf8()?.into_owned()?),
+      })
+    },
+    options.expand.unwrap_or(true),
+  )?;
+  if options.apply.unwrap_or(false) {
+    let replace = options.override_existing.unwrap_or(false);
+    for (key, value) in &pairs {
+      if replace || !process_vars.has_named_property(key)? {
+        process_vars.set_named_property(key, env.create_string(value)?)?;
+      }
+    }
+  }
+  to_object(&env, &pairs)
+}
+
+/// Writes an object as a `.env` file. Values that are not plain words are
+/// double-quoted with `\n`, `"`, `\` and `$` escaped, so `parseDotenv` reads
+/// them back unchanged.
+#[napi(
+  catch_unwind,
+  ts_args_type = "vars: Record<string, string | number | boolean>"
+)]
+pub fn to_dotenv(vars: JsObject) -> Result<String> {
+  let mut out = String::new();
+  for key in keys(&vars)? {
+    let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+      && key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+      return Err(AppError::InvalidInput(format!(
+        "invalid variable name: {:?}",
+        key
+      )));
+    }
+    let value = scalar(&key, vars.get_named_property(&key)?)?;
+    let plain = value
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "_./:@,+-".contains(c));
+    out.push_str(&key);
+    out.push('=');
+    if plain {
+      out.push_str(&value);
+    } else {
+      out.push('"');
+      for c in value.chars() {
+        match c {
+          '\n' => out.push_str("\\n"),
+          '\r' => out.push_str("\\r"),
+          '\t' => out.push_str("\\t"),
+          '"' | '\\' | '$' => {
+            out.push('\\');
+            out.push(c);
+          }
+          c => out.push(c),
+        }
+      }
+      out.push('"');
+    }
+    out.push('\n');
+  }
+  Ok(out)
+}
+
+fn unquote(value: &str) -> &str {
+  for quote in ['"', '\''] {
+    if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+      return &value[1..value.len() - 1];
+    }
+  }
+  value
+}
+
+/// Parses an INI file. Keys before the first `[section]` become top-level
+/// strings; each section becomes a nested object. `key = value` and
+/// `key: value` are both accepted, lines starting with `;` or `#` are
+/// comments, and values are kept as strings with surrounding quotes removed.
+/// Repeated sections are merged and repeated keys keep the last value.
+#[napi(
+  catch_unwind,
+  ts_return_type = "Record<string, string | Record<string, string>>"
+)]
+pub fn parse_ini(env: Env, input: String) -> Result<JsObject> {
+  let mut globals = Pairs::new();
+  let mut sections: Vec<(String, Pairs)> = Vec::new();
+  let mut current: Option<usize> = None;
+  for (n, line) in input.lines().enumerate() {
+    let error =
+      |message: &str| AppError::Parse(format!("invalid INI at line {}: {}", n + 1, message));
+    let line = line.trim();
+    if line.is_empty() || line.starts_with([';', '#']) {
+      continue;
+    }
+    if let Some(header) = line.strip_prefix('[') {
+      let name = header
+        .strip_suffix(']')
+        .ok_or_else(|| error("expected ']'"))?
+        .trim();
+      if name.is_empty() {
+        return Err(error("empty section name"));
+      }
+      current = Some(match sections.iter().position(|(s, _)| s == name) {
+        Some(i) => i,
+        None => {
+          sections.push((name.to_string(), Pairs::new()));
+          sections.len() - 1
+        }
+      });
+      continue;
+    }
+    let split = line
+      .find(['=', ':'])
+      .ok_or_else(|| error("expected 'key = value'"))?;
+    let key = line[..split].trim();
+    if key.is_empty() {
+      return Err(error("empty key"));
+    }
+    let value = unquote(line[split + 1..].trim()).to_string();
+    match current {
+      Some(i) => insert(&mut sections[i].1, key.to_string(), value),
+      None => insert(&mut globals, key.to_string(), value),
+    }
+  }
+  if let Some((key, _)) = globals
+    .iter()
+    .find(|(k, _)| sections.iter().any(|(s, _)| s == k))
+  {
+    return Err(AppError::Parse(format!(
+      "invalid INI: {} is both a key and a section",
+      key
+    )));
+  }
+  let mut object = to_object(&env, &globals)?;
+  for (name, pairs) in &sections {
+    object.set_named_property(name, to_object(&env, pairs)?)?;
+  }
+  Ok(object)
+}
+
+fn ini_line(out: &mut String, key: &str, value: &str) -> Result<()> {
+  if key.is_empty()
+    || key.contains(['=', ':', '[', '\n', '\r'])
+    || key.starts_with([';', '#'])
+    || key.trim() != key
+  {
+    return Err(AppError::InvalidInput(format!(
+      "invalid INI key: {:?}",
+      key
+    )));
+  }
+  if value.contains(['\n', '\r']) {
+    return Err(AppError::InvalidInput(format!(
+      "{}: INI values cannot contain line breaks",
+      key
+    )));
+  }
+  out.push_str(key);
+  out.push_str(" = ");
+  // Quote values whose edges `parseIni` would otherwise trim or unquote.
+  if value.trim() != value || unquote(value) != value {
+    out.push('"');
+    out.push_str(value);
+    out.push('"');
+  } else {
+    out.push_str(value);
+  }
+  out.push('\n');
+  Ok(())
+}
+
+/// Writes an INI file from the shape `parseIni` returns: top-level scalars
+/// first, then one `[section]` per nested object. Deeper nesting, arrays and
+/// `null` are rejected.
+#[napi(
+  catch_unwind,
+  ts_args_type = "value: Record<string, string | number | boolean | Record<string, string | number | boolean>>"
+)]
+pub fn to_ini(value: JsObject) -> Result<String> {
+  let mut globals = String::new();
+  let mut sections = String::new();
+  for key in keys(&value)? {
+    let item: JsUnknown = value.get_named_property(&key)?;
+    if item.get_type()? == ValueType::Object && !item.is_array()? {
+      let section = unsafe { item.cast::<JsObject>() };
+      if key.is_empty() || key.contains([']', '\n', '\r']) || key.trim() != key {
+        return Err(AppError::InvalidInput(format!(
+          "invalid INI section name: {:?}",
+          key
+        )));
+      }
+      if !sections.is_empty() || !globals.is_empty() {
+        sections.push('\n');
+      }
+      sections.push_str(&format!("[{}]\n", key));
+      for name in keys(&section)? {
+        let field = scalar(
+          &format!("{}.{}", key, name),
+          section.get_named_property(&name)?,
+        )?;
+        ini_line(&mut sections, &name, &field)?;
+      }
+    } else {
+      let field = scalar(&key, item)?;
+      ini_line(&mut globals, &key, &field)?;
+    }
+  }
+  globals.push_str(&sections);
+  Ok(globals)
+}
+
+/// Parses a Java `.properties` file: `#`/`!` comments, `=`, `:` or
+/// whitespace separators, backslash line continuations and `\t`, `\n`,
+/// `\uXXXX` escapes.
+#[napi(catch_unwind, ts_return_type = "Record<string, string>")]
+pub fn parse_properties(env: Env, input: String) -> Result<JsObject> {
+  let mut pairs = Pairs::new();
+  let mut lines = input.lines().enumerate();
+  while let Some((n, line)) = lines.next() {
+    let mut logical = line.trim_start().to_string();
+    if logical.is_empty() || logical.starts_with(['#', '!']) {
+      continue;
+    }
+    // An odd number of trailing backslashes joins the next line.
+    while (logical.len() - logical.trim_end_matches('\\').len()) % 2 == 1 {
+      logical.pop();
+      match lines.next() {
+        Some((_, next)) => logical.push_str(next.trim_start()),
+        None => break,
+      }
+    }
+    let error =
+      |message: &str| AppError::Parse(format!("invalid properties at line {}: {}", n + 1, message));
+
+    let mut key_end = logical.len();
+    let mut escaped = false;
+    for (i, c) in logical.char_indices() {
+      if escaped {
+        escaped = false;
+      } else if c == '\\' {
+        escaped = true;
+      } else if matches!(c, '=' | ':' | ' ' | '\t' | '\x0c') {
+        key_end = i;
+        break;
+      }
+    }
+    let rest = logical[key_end..].trim_start_matches([' ', '\t', '\x0c']);
+    let rest = rest
+      .strip_prefix(['=', ':'])
+      .unwrap_or(rest)
+      .trim_start_matches([' ', '\t', '\x0c']);
+    let key = unescape_properties(&logical[..key_end]).map_err(|m| error(&m))?;
+    let value = unescape_properties(rest).map_err(|m| error(&m))?;
+    insert(&mut pairs, key, value);
+  }
+  to_object(&env, &pairs)
+}
+
+fn unescape_properties(raw: &str) -> std::result::Result<String, String> {
+  let mut out = String::with_capacity(raw.len());
+  let mut chars = raw.chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      out.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('t') => out.push('\t'),
+      Some('n') => out.push('\n'),
+      Some('r') => out.push('\r'),
+      Some('f') => out.push('\x0c'),
+      Some('u') => {
+        let hex: String = chars.by_ref().take(4).collect();
+        let unit = u16::from_str_radix(&hex, 16)
+          .ok()
+          .filter(|_| hex.len() == 4)
+          .ok_or_else(|| format!("invalid \\u escape: \\u{}", hex))?;
+        // Surrogate pairs arrive as two consecutive \u escapes.
+        let decoded = if (0xd800..0xdc00).contains(&unit) {
+          let mut rest = chars.clone();
+          let low = (rest.next() == Some('\\') && rest.next() == Some('u'))
+            .then(|| rest.by_ref().take(4).collect::<String>())
+            .and_then(|h| u16::from_str_radix(&h, 16).ok());
+          match low {
+            Some(low) if (0xdc00..0xe000).contains(&low) => {
+              chars = rest;
+              char::decode_utf16([unit, low]).next().and_then(|r| r.ok())
+            }
+            _ => None,
+          }
+        } else {
+          char::from_u32(unit as u32)
+        };
+        out.push(decoded.ok_or_else(|| format!("unpaired surrogate \\u{}", hex))?);
+      }
+      Some(c) => out.push(c),
+      None => {}
+    }
+  }
+  Ok(out)
+}
+
+fn escape_properties(out: &mut String, text: &str, is_key: bool) {
+  for (i, c) in text.chars().enumerate() {
+    match c {
+      '\\' => out.push_str("\\\\"),
+      '\t' => out.push_str("\\t"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\x0c' => out.push_str("\\f"),
+      ' ' if is_key || i == 0 => out.push_str("\\ "),
+      '=' | ':' | '#' | '!' if is_key || i == 0 => {
+        out.push('\\');
+        out.push(c);
+      }
+      c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+      c => out.push(c),
+    }
+  }
+}
+
+/// Writes an object as a `.properties` file (UTF-8, one `key=value` per
+/// line), escaping whatever `parseProperties` would otherwise reinterpret.
+#[napi(
+  catch_unwind,
+  ts_args_type = "value: Record<string, string | number | boolean>"
+)]
+pub fn to_properties(value: JsObject) -> Result<String> {
+  let mut out = String::new();
+  for key in keys(&value)? {
+    let field = scalar(&key, value.get_named_property(&key)?)?;
+    escape_properties(&mut out, &key, true);
+    out.push('=');
+    escape_properties(&mut out, &field, false);
+    out.push('\n');
+  }
+  Ok(out)
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+use std::future::Future;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject, JsUnknown, NapiValue};
+
+/// Crate-wide error type.
+///
+/// Every variant maps to a stable `code` string on the thrown JS error, so
+/// callers can `switch (err.code)` instead of matching on messages. Errors
+/// coming out of napi itself keep napi's own status as their code.
+#[derive(Debug)]
+pub enum AppError {
+  /// An arithmetic result does not fit in the target type.
+  Overflow(String),
+  /// An argument is out of range or otherwise malformed.
+  InvalidInput(String),
+  /// A file, key or other named resource does not exist.
+  NotFound(String),
+  /// An I/O operation failed for a reason other than the resource missing.
+  Io(String),
+  /// Input text or bytes could not be parsed or decoded.
+  Parse(String),
+  /// An algorithm, format or option value is not supported.
+  Unsupported(String),
+  /// Encryption, decryption or hashing failed.
+  Crypto(String),
+  /// The object was already closed or finalized.
+  Closed(String),
+  /// A SQLite statement or key-value store transaction failed.
+  Database(String),
+  /// The full-text index could not be opened, written or searched.
+  Search(String),
+  /// A connection could not be established or broke mid-transfer.
+  Network(String),
+  /// An operation did not complete within its deadline.
+  Timeout(String),
+  /// A circuit breaker is open and refused the call without making it.
+  CircuitOpen(String),
+  /// Rust code panicked; the message names the panic and its location.
+  Panic(String),
+  /// A JWT's `exp` claim is in the past.
+  JwtExpired(String),
+  /// A JWT's `nbf` claim is still in the future.
+  JwtNotYetValid(String),
+  /// A JWT's signature does not match its header and claims.
+  JwtInvalidSignature(String),
+  /// A JWT's `iss`, `aud` or `sub` claim is not the expected one.
+  JwtClaimMismatch(String),
+  /// A JWT key is not a valid secret, PEM key or key for the algorithm.
+  JwtInvalidKey(String),
+  /// A JWT algorithm is unknown, unsupported or not the expected one.
+  JwtInvalidAlgorithm(String),
+  /// A JWT could not be decoded, or failed validation for another reason.
+  JwtMalformed(String),
+  /// An error raised by napi, e.g. a failed value conversion or an
+  /// exception thrown by a JS callback.
+  Napi(napi::Error),
+}
+
+pub type Result<T> = std::result::Result<T, AppError>;
+
+impl AppError {
+  /// The value of the `code` property on the JS error.
+  pub fn code(&self) -> &str {
+    match self {
+      AppError::Overflow(_) => "ERR_OVERFLOW",
+      AppError::InvalidInput(_) => "ERR_INVALID_INPUT",
+      AppError::NotFound(_) => "ERR_NOT_FOUND",
+      AppError::Io(_) => "ERR_IO",
+      AppError::Parse(_) => "ERR_PARSE",
+      AppError::Unsupported(_) => "ERR_UNSUPPORTED",
+      AppError::Crypto(_) => "ERR_CRYPTO",
+      AppError::Closed(_) => "ERR_CLOSED",
+      AppError::Database(_) => "ERR_DATABASE",
+      AppError::Search(_) => "ERR_SEARCH",
+      AppError::Network(_) => "ERR_NETWORK",
+      AppError::Timeout(_) => "ERR_TIMEOUT",
+      AppError::CircuitOpen(_) => "ERR_CIRCUIT_OPEN",
+      AppError::Panic(_) => "RUST_PANIC",
+      AppError::JwtExpired(_) => "ERR_JWT_EXPIRED",
+      AppError::JwtNotYetValid(_) => "ERR_JWT_NOT_YET_VALID",
+      AppError::JwtInvalidSignature(_) => "ERR_JWT_INVALID_SIGNATURE",
+      AppError::JwtClaimMismatch(_) => "ERR_JWT_CLAIM_MISMATCH",
+      AppError::JwtInvalidKey(_) => "ERR_JWT_INVALID_KEY",
+      AppError::JwtInvalidAlgorithm(_) => "ERR_JWT_INVALID_ALGORITHM",
+      AppError::JwtMalformed(_) => "ERR_JWT_MALFORMED",
+      AppError::Napi(e) => e.status.as_ref(),
+    }
+  }
+
+  /// Builds an `Io` error (or `NotFound` if the file is missing) naming `path`.
+  pub fn io(path: &str, e: std::io::Error) -> Self {
+    let message = format!("{}: {}", path, e);
+    if e.kind() == std::io::ErrorKind::NotFound {
+      AppError::NotFound(message)
+    } else {
+      AppError::Io(message)
+    }
+  }
+
+  /// Converts into a `napi::Error` that wraps an already-created JS error, so
+  /// the `code` survives paths that only accept `napi::Error` — Task
+  /// `resolve`/`reject` and Promise resolvers. Must be called on the JS thread.
+  pub fn into_napi(self, env: Env) -> napi::Error {
+    if let AppError::Napi(e) = self {
+      return e;
+    }
+    let raw = unsafe { JsError::from(self).into_value(env.raw()) };
+    napi::Error::from(unsafe { JsUnknown::from_raw_unchecked(env.raw(), raw) })
+  }
+}
+
+impl fmt::Display for AppError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AppError::Overflow(m)
+      | AppError::InvalidInput(m)
+      | AppError::NotFound(m)
+      | AppError::Io(m)
+      | AppError::Parse(m)
+      | AppError::Unsupported(m)
+      | AppError::Crypto(m)
+      | AppError::Closed(m)
+      | AppError::Database(m)
+      | AppError::Search(m)
+      | AppError::Network(m)
+      | AppError::Timeout(m)
+      | AppError::CircuitOpen(m)
+      | AppError::Panic(m)
+      | AppError::JwtExpired(m)
+      | AppError::JwtNotYetValid(m)
+      | AppError::JwtInvalidSignature(m)
+      | AppError::JwtClaimMismatch(m)
+      | AppError::JwtInvalidKey(m)
+      | AppError::JwtInvalidAlgorithm(m)
+      | AppError::JwtMalformed(m) => f.write_str(m),
+      AppError::Napi(e) => f.write_str(&e.reason),
+    }
+  }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<napi::Error> for AppError {
+  fn from(e: napi::Error) -> Self {
+    AppError::Napi(e)
+  }
+}
+
+impl From<std::io::Error> for AppError {
+  fn from(e: std::io::Error) -> Self {
+    match e.kind() {
+      std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+      _ => AppError::Io(e.to_string()),
+    }
+  }
+}
+
+impl From<serde_json::Error> for AppError {
+  fn from(e: serde_json::Error) -> Self {
+    AppError::Parse(format!("invalid JSON: {}", e))
+  }
+}
+
+/// Lets `#[napi]` functions return `crate::error::Result` directly: the
+/// generated glue throws whatever `JsError::from` gives it.
+impl From<AppError> for JsError<String> {
+  fn from(e: AppError) -> Self {
+    let code = e.code().to_string();
+    crate::metrics::count_error(&code);
+    JsError::from(napi::Error::new(code, e.to_string()))
+  }
+}
+
+impl From<AppError> for napi::Error {
+  fn from(e: AppError) -> Self {
+    match e {
+      AppError::Napi(e) => e,
+      e => napi::Error::new(Status::GenericFailure, format!("[{}] {}", e.code(), e)),
+    }
+  }
+}
+
+/// Runs `fut` on the tokio runtime and returns the Promise for its result. A
+/// panic in `fut` rejects with `RUST_PANIC`.
+///
+/// `#[napi] async fn` can only reject with a plain `napi::Error`, which
+/// drops the `code`; use this instead when the future can fail with an
+/// `AppError`.
+pub fn spawn<T, F>(env: &Env, fut: F) -> Result<JsObject>
+where
+  T: ToNapiValue + Send + 'static,
+  F: Future<Output = Result<T>> + Send + 'static,
+{
+  let call = crate::metrics::defer();
+  env
+    .execute_tokio_future(
+      async move { Ok(crate::panic_guard::guard_future(fut).await) },
+      move |env, result| {
+        if let Some(call) = call {
+          call.finish(result.is_err());
+        }
+        result.map_err(|e| e.into_napi(*env))
+      },
+    )
+    .map_err(AppError::from)
+}
@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+/// Subscribing to this topic receives every publication.
+const ALL_TOPICS: &str = "*";
+
+type Listener = ThreadsafeFunction<(Value, String), ErrorStrategy::Fatal>;
+
+struct Subscription {
+  id: u32,
+  topic: String,
+  listener: Listener,
+}
+
+#[derive(Default)]
+struct Subscriptions {
+  next_id: AtomicU32,
+  list: Mutex<Vec<Subscription>>,
+}
+
+impl Subscriptions {
+  fn lock(&self) -> MutexGuard<'_, Vec<Subscription>> {
+    self.list.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// The sending half of an `EventBus`, for Rust code that publishes from its
+/// own threads. Cloning is cheap, and publishing after the bus has been
+/// garbage-collected still reaches subscribers that were registered.
+#[derive(Clone)]
+pub struct Publisher {
+  subscriptions: Arc<Subscriptions>,
+}
+
+impl Publisher {
+  /// Queues `payload` for every subscriber of `topic` and of `"*"`, and
+  /// returns how many there were. Each subscriber sees publications in the
+  /// order they were made, whichever thread made them.
+  pub fn publish(&self, topic: &str, payload: Value) -> u32 {
+    // Holding the lock while queueing keeps concurrent publishers from
+    // interleaving differently for different subscribers.
+    let subscriptions = self.subscriptions.lock();
+    let mut delivered = 0;
+    for sub in subscriptions
+      .iter()
+      .filter(|s| s.topic == topic || s.topic == ALL_TOPICS)
+    {
+      sub.listener.call(
+        (payload.clone(), topic.to_string()),
+        ThreadsafeFunctionCallMode::NonBlocking,
+      );
+      delivered += 1;
+    }
+    delivered
+  }
+}
+
+/// A topic-based publish/subscribe bus between Rust and JS.
+///
+/// Subscribers are called on the JS thread with `(payload, topic)`.
+/// Delivery is always asynchronous, even for `publish` calls made from JS,
+/// so a subscriber never runs inside the publisher's stack. Payloads travel
+/// as JSON values and are copied for each subscriber. Subscriptions do not
+/// keep the process alive.
+#[napi]
+pub struct EventBus {
+  publisher: Publisher,
+}
+
+#[napi]
+impl EventBus {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    EventBus {
+      publisher: Publisher {
+        subscriptions: Arc::default(),
+      },
+    }
+  }
+
+  /// Calls `callback` for each publication on `topic`, or on every topic
+  /// for `"*"`. Returns a handle for `unsubscribe`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "topic: string, callback: (payload: unknown, topic: string) => void"
+  )]
+  pub fn subscribe(&self, env: Env, topic: String, callback: JsFunction) -> Result<u32> {
+    if topic.is_empty() {
+      return Err(AppError::InvalidInput("topic must not be empty".into()));
+    }
+    let mut listener: Listener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(Value, String)>| {
+        let (payload, topic) = ctx.value;
+        Ok(vec![
+          ctx.env.to_js_value(&payload)?,
+          ctx.env.create_string_from_std(topic)?.into_unknown(),
+        ])
+      })?;
+    listener.unref(&env)?;
+    let subscriptions = &self.publisher.subscriptions;
+    let id = subscriptions.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    subscriptions.lock().push(Subscription {
+      id,
+      topic,
+      listener,
+    });
+    Ok(id)
+  }
+
+  /// Returns whether the handle was subscribed. Publications already queued
+  /// for it are still delivered.
+  #[napi(catch_unwind)]
+  pub fn unsubscribe(&self, handle: u32) -> bool {
+    let mut subscriptions = self.publisher.subscriptions.lock();
+    let before = subscriptions.len();
+    subscriptions.retain(|s| s.id != handle);
+    subscriptions.len() != before
+  }
+
+  /// Queues `payload` for the subscribers of `topic` and returns how many
+  /// there were. The payload must be JSON-serializable.
+  #[napi(catch_unwind, ts_args_type = "topic: string, payload?: unknown")]
+  pub fn publish(&self, env: Env, topic: String, payload: Option<JsUnknown>) -> Result<u32> {
+    let payload = match payload {
+      Some(payload) => from_js(&env, payload)?,
+      None => Value::Null,
+    };
+    Ok(self.publisher.publish(&topic, payload))
+  }
+
+  /// Publishes `{thread, seq}` on `topic` from each of `threads` Rust
+  /// threads at once, `seq` counting from 0 to `count - 1` per thread, and
+  /// returns the number of deliveries queued once every thread is done.
+  /// Demonstrates `Publisher`: each subscriber sees a thread's payloads in
+  /// `seq` order, and all subscribers see the same interleaving.
+  #[napi(catch_unwind)]
+  pub fn publish_from_threads(&self, topic: String, threads: u32, count: u32) -> u32 {
+    let topic = Arc::new(topic);
+    let handles: Vec<_> = (0..threads)
+      .map(|thread| {
+        let publisher = self.publisher();
+        let topic = Arc::clone(&topic);
+        thread::spawn(move || {
+          (0..count)
+            .map(|seq| publisher.publish(&topic, json!({"thread": thread, "seq": seq})))
+            .sum::<u32>()
+        })
+      })
+      .collect();
+    handles
+      .into_iter()
+      .map(|handle| {
+        handle
+          .join()
+          .unwrap_or_else(|e| std::panic::resume_unwind(e))
+      })
+      .sum()
+  }
+
+  /// Number of subscriptions, optionally only those for `topic`.
+  #[napi(catch_unwind)]
+  pub fn subscriber_count(&self, topic: Option<String>) -> u32 {
+    let subscriptions = self.publisher.subscriptions.lock();
+    subscriptions
+      .iter()
+      .filter(|s| topic.as_ref().is_none_or(|t| &s.topic == t))
+      .count() as u32
+  }
+}
+
+impl Default for EventBus {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl EventBus {
+  /// A handle Rust code can move to other threads to publish on this bus.
+  pub fn publisher(&self) -> Publisher {
+    self.publisher.clone()
+  }
+}
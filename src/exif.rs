@@ -0,0 +1,232 @@
+use std::io::Cursor;
+
+use ::exif::{DateTime, Exif, In, Reader, Tag, Value};
+use napi::bindgen_prelude::Buffer;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct GpsInfo {
+  /// Decimal degrees, negative south of the equator.
+  pub latitude: f64,
+  /// Decimal degrees, negative west of Greenwich.
+  pub longitude: f64,
+  /// Metres, negative below sea level.
+  pub altitude: Option<f64>,
+}
+
+#[napi(object)]
+pub struct ExifData {
+  pub make: Option<String>,
+  pub model: Option<String>,
+  pub lens_model: Option<String>,
+  pub software: Option<String>,
+  /// 1–8 as defined by EXIF; 1 means no rotation.
+  pub orientation: Option<u32>,
+  /// When the photo was taken, as `YYYY-MM-DDTHH:MM:SS`, with the UTC offset
+  /// appended when the camera recorded one.
+  pub date_time: Option<String>,
+  /// Seconds.
+  pub exposure_time: Option<f64>,
+  pub f_number: Option<f64>,
+  pub iso: Option<u32>,
+  /// Millimetres.
+  pub focal_length: Option<f64>,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  pub gps: Option<GpsInfo>,
+}
+
+fn field(exif: &Exif, tag: Tag) -> Option<&Value> {
+  exif.get_field(tag, In::PRIMARY).map(|f| &f.value)
+}
+
+fn ascii(exif: &Exif, tag: Tag) -> Option<String> {
+  match field(exif, tag)? {
+    Value::Ascii(parts) => parts
+      .iter()
+      .map(|p| String::from_utf8_lossy(p).trim().to_string())
+      .find(|s| !s.is_empty()),
+    _ => None,
+  }
+}
+
+fn uint(exif: &Exif, tag: Tag) -> Option<u32> {
+  field(exif, tag)?.get_uint(0)
+}
+
+fn rational(value: &Value, index: usize) -> Option<f64> {
+  let n = match value {
+    Value::Rational(v) => v.get(index)?.to_f64(),
+    Value::SRational(v) => v.get(index)?.to_f64(),
+    _ => return None,
+  };
+  n.is_finite().then_some(n)
+}
+
+fn number(exif: &Exif, tag: Tag) -> Option<f64> {
+  rational(field(exif, tag)?, 0)
+}
+
+fn date_time(exif: &Exif) -> Option<String> {
+  let (tag, offset) = [
+    (Tag::DateTimeOriginal, Tag::OffsetTimeOriginal),
+    (Tag::DateTime, Tag::OffsetTime),
+  ]
+  .into_iter()
+  .find(|&(tag, _)| field(exif, tag).is_some())?;
+  let Value::Ascii(parts) = field(exif, tag)? else {
+    return None;
+  };
+  let dt = DateTime::from_ascii(parts.first()?).ok()?;
+  let mut out = format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+    dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+  );
+  if let Some(offset) = ascii(exif, offset) {
+    out.push_str(&offset);
+  }
+  Some(out)
+}
+
+fn gps(exif: &Exif) -> Option<GpsInfo> {
+  let degrees = |tag: Tag, negative_ref: &str, ref_tag: Tag| -> Option<f64> {
+    let value = field(exif, tag)?;
+    let dms = rational(value, 0)? + rational(value, 1)? / 60.0 + rational(value, 2)? / 3600.0;
+    let negative = ascii(exif, ref_tag).is_some_and(|r| r.eq_ignore_ascii_case(negative_ref));
+    Some(if negative { -dms } else { dms })
+  };
+  let altitude = number(exif, Tag::GPSAltitude).map(|alt| match uint(exif, Tag::GPSAltitudeRef) {
+    Some(1) => -alt,
+    _ => alt,
+  });
+  Some(GpsInfo {
+    latitude: degrees(Tag::GPSLatitude, "S", Tag::GPSLatitudeRef)?,
+    longitude: degrees(Tag::GPSLongitude, "W", Tag::GPSLongitudeRef)?,
+    altitude,
+  })
+}
+
+/// Reads EXIF metadata from a JPEG, PNG, WebP, HEIF or TIFF image. Returns
+/// `null` when the image has no EXIF block.
+#[napi(catch_unwind)]
+pub fn read_exif(input: Buffer) -> Result<Option<ExifData>> {
+  let exif = match Reader::new().read_from_container(&mut Cursor::new(&input[..])) {
+    Ok(exif) => exif,
+    Err(::exif::Error::NotFound(_)) | Err(::exif::Error::BlankValue(_)) => return Ok(None),
+    Err(::exif::Error::NotSupported(m)) => return Err(AppError::Unsupported(m.to_string())),
+    Err(e) => return Err(AppError::Parse(format!("invalid EXIF: {}", e))),
+  };
+  Ok(Some(ExifData {
+    make: ascii(&exif, Tag::Make),
+    model: ascii(&exif, Tag::Model),
+    lens_model: ascii(&exif, Tag::LensModel),
+    software: ascii(&exif, Tag::Software),
+    orientation: uint(&exif, Tag::Orientation),
+    date_time: date_time(&exif),
+    exposure_time: number(&exif, Tag::ExposureTime),
+    f_number: number(&exif, Tag::FNumber),
+    iso: uint(&exif, Tag::PhotographicSensitivity),
+    focal_length: number(&exif, Tag::FocalLength),
+    width: uint(&exif, Tag::PixelXDimension),
+    height: uint(&exif, Tag::PixelYDimension),
+    gps: gps(&exif),
+  }))
+}
+
+fn truncated() -> AppError {
+  AppError::Parse("invalid image: unexpected end of data".into())
+}
+
+/// Drops APP1 (EXIF, XMP), APP13 (IPTC) and comment segments; the color
+/// profile in APP2 is kept.
+fn strip_jpeg(input: &[u8]) -> Result<Vec<u8>> {
+  let mut out = input[..2].to_vec();
+  let mut pos = 2;
+  loop {
+    let header = input.get(pos..pos + 4).ok_or_else(truncated)?;
+    if header[0] != 0xff {
+      return Err(AppError::Parse("invalid JPEG: bad segment marker".into()));
+    }
+    let marker = header[1];
+    // Start of scan: the entropy-coded data and everything after it is
+    // copied verbatim.
+    if marker == 0xda {
+      out.extend_from_slice(&input[pos..]);
+      return Ok(out);
+    }
+    let end = pos + 2 + u16::from_be_bytes([header[2], header[3]]) as usize;
+    let segment = input.get(pos..end).ok_or_else(truncated)?;
+    if !matches!(marker, 0xe1 | 0xed | 0xfe) {
+      out.extend_from_slice(segment);
+    }
+    pos = end;
+  }
+}
+
+/// Drops the `eXIf`, text and timestamp chunks.
+fn strip_png(input: &[u8]) -> Result<Vec<u8>> {
+  let mut out = input[..8].to_vec();
+  let mut pos = 8;
+  while pos < input.len() {
+    let header = input.get(pos..pos + 8).ok_or_else(truncated)?;
+    let len = u32::from_be_bytes(header[..4].try_into().expect("4 bytes")) as usize;
+    let end = pos + 12 + len;
+    let chunk = input.get(pos..end).ok_or_else(truncated)?;
+    if !matches!(
+      &header[4..8],
+      b"eXIf" | b"tEXt" | b"iTXt" | b"zTXt" | b"tIME"
+    ) {
+      out.extend_from_slice(chunk);
+    }
+    pos = end;
+  }
+  Ok(out)
+}
+
+/// Drops the `EXIF` and `XMP ` chunks and clears their flags in `VP8X`.
+fn strip_webp(input: &[u8]) -> Result<Vec<u8>> {
+  let mut out = input[..12].to_vec();
+  let mut pos = 12;
+  while pos < input.len() {
+    let header = input.get(pos..pos + 8).ok_or_else(truncated)?;
+    let len = u32::from_le_bytes(header[4..].try_into().expect("4 bytes")) as usize;
+    if pos + 8 + len > input.len() {
+      return Err(truncated());
+    }
+    // Chunks are padded to an even length.
+    let end = (pos + 8 + len + (len & 1)).min(input.len());
+    match &header[..4] {
+      b"EXIF" | b"XMP " => {}
+      b"VP8X" if len >= 1 => {
+        let flags = out.len() + 8;
+        out.extend_from_slice(&input[pos..end]);
+        out[flags] &= !(0x08 | 0x04);
+      }
+      _ => out.extend_from_slice(&input[pos..end]),
+    }
+    pos = end;
+  }
+  let riff_len = (out.len() - 8) as u32;
+  out[4..8].copy_from_slice(&riff_len.to_le_bytes());
+  Ok(out)
+}
+
+/// Returns a copy of a JPEG, PNG or WebP image with its EXIF, XMP and text
+/// metadata removed and the pixel data untouched. The orientation tag goes
+/// too, so rotate the image first if that matters.
+#[napi(catch_unwind)]
+pub fn strip_exif(input: Buffer) -> Result<Buffer> {
+  let stripped = if input.starts_with(&[0xff, 0xd8]) {
+    strip_jpeg(&input)?
+  } else if input.starts_with(b"\x89PNG\r\n\x1a\n") {
+    strip_png(&input)?
+  } else if input.len() >= 12 && input.starts_with(b"RIFF") && &input[8..12] == b"WEBP" {
+    strip_webp(&input)?
+  } else {
+    return Err(AppError::Unsupported(
+      "metadata can only be stripped from JPEG, PNG or WebP images".into(),
+    ));
+  };
+  Ok(stripped.into())
+}
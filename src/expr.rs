@@ -0,0 +1,667 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::{AppError, Result};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Op {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+  Pow,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  Eq,
+  Ne,
+  And,
+  Or,
+}
+
+impl Op {
+  /// Binding power: higher binds tighter.
+  fn precedence(self) -> u8 {
+    match self {
+      Op::Or => 1,
+      Op::And => 2,
+      Op::Eq | Op::Ne => 3,
+      Op::Lt | Op::Le | Op::Gt | Op::Ge => 4,
+      Op::Add | Op::Sub => 5,
+      Op::Mul | Op::Div | Op::Rem => 6,
+      Op::Pow => 8,
+    }
+  }
+
+  fn apply(self, a: f64, b: f64) -> f64 {
+    let truth = |t: bool| if t { 1.0 } else { 0.0 };
+    match self {
+      Op::Add => a + b,
+      Op::Sub => a - b,
+      Op::Mul => a * b,
+      Op::Div => a / b,
+      Op::Rem => a % b,
+      Op::Pow => a.powf(b),
+      Op::Lt => truth(a < b),
+      Op::Le => truth(a <= b),
+      Op::Gt => truth(a > b),
+      Op::Ge => truth(a >= b),
+      Op::Eq => truth(a == b),
+      Op::Ne => truth(a != b),
+      Op::And => truth(a != 0.0 && b != 0.0),
+      Op::Or => truth(a != 0.0 || b != 0.0),
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Func {
+  Abs,
+  Acos,
+  Asin,
+  Atan,
+  Atan2,
+  Ceil,
+  Cos,
+  Exp,
+  Floor,
+  Hypot,
+  Ln,
+  Log,
+  Log10,
+  Log2,
+  Max,
+  Min,
+  Pow,
+  Round,
+  Sign,
+  Sin,
+  Sqrt,
+  Tan,
+  Trunc,
+}
+
+impl Func {
+  fn lookup(name: &str) -> Option<Func> {
+    Some(match name {
+      "abs" => Func::Abs,
+      "acos" => Func::Acos,
+      "asin" => Func::Asin,
+      "atan" => Func::Atan,
+      "atan2" => Func::Atan2,
+      "ceil" => Func::Ceil,
+      "cos" => Func::Cos,
+      "exp" => Func::Exp,
+      "floor" => Func::Floor,
+      "hypot" => Func::Hypot,
+      "ln" => Func::Ln,
+      "log" => Func::Log,
+      "log10" => Func::Log10,
+      "log2" => Func::Log2,
+      "max" => Func::Max,
+      "min" => Func::Min,
+      "pow" => Func::Pow,
+      "round" => Func::Round,
+      "sign" => Func::Sign,
+      "sin" => Func::Sin,
+      "sqrt" => Func::Sqrt,
+      "tan" => Func::Tan,
+      "trunc" => Func::Trunc,
+      _ => return None,
+    })
+  }
+
+  /// The accepted argument counts, as an inclusive range.
+  fn arity(self) -> (usize, usize) {
+    match self {
+      Func::Atan2 | Func::Pow => (2, 2),
+      Func::Log => (1, 2),
+      Func::Hypot | Func::Max | Func::Min => (1, usize::MAX),
+      _ => (1, 1),
+    }
+  }
+
+  fn apply(self, args: &[f64]) -> f64 {
+    let x = args[0];
+    match self {
+      Func::Abs => x.abs(),
+      Func::Acos => x.acos(),
+      Func::Asin => x.asin(),
+      Func::Atan => x.atan(),
+      Func::Atan2 => x.atan2(args[1]),
+      Func::Ceil => x.ceil(),
+      Func::Cos => x.cos(),
+      Func::Exp => x.exp(),
+      Func::Floor => x.floor(),
+      Func::Hypot => args.iter().map(|a| a * a).sum::<f64>().sqrt(),
+      Func::Ln => x.ln(),
+      Func::Log => match args.get(1) {
+        Some(base) => x.log(*base),
+        None => x.ln(),
+      },
+      Func::Log10 => x.log10(),
+      Func::Log2 => x.log2(),
+      Func::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+      Func::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+      Func::Pow => x.powf(args[1]),
+      // Like `Math.round`: halves round up, not away from zero.
+      Func::Round => (x + 0.5).floor(),
+      Func::Sign => {
+        if x == 0.0 || x.is_nan() {
+          x
+        } else {
+          x.signum()
+        }
+      }
+      Func::Sin => x.sin(),
+      Func::Sqrt => x.sqrt(),
+      Func::Tan => x.tan(),
+      Func::Trunc => x.trunc(),
+    }
+  }
+}
+
+/// Parentheses, function arguments, `?:` branches and `^` operands nested
+/// deeper than this are a parse error rather than a stack overflow.
+const MAX_DEPTH: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Prefix {
+  Neg,
+  Not,
+}
+
+#[derive(Debug)]
+enum Node {
+  Number(f64),
+  Var(String),
+  /// Prefix operators, outermost first, kept flat so that `- - - x`
+  /// doesn't nest.
+  Prefix(Vec<Prefix>, Box<Node>),
+  /// Operators of one precedence applied left to right, kept flat so that
+  /// a sum of thousands of terms doesn't nest either.
+  Chain(Box<Node>, Vec<(Op, Node)>),
+  /// `cond ? then : otherwise`; only the chosen branch is evaluated.
+  Cond(Box<Node>, Box<Node>, Box<Node>),
+  Call(Func, Vec<Node>),
+}
+
+impl Node {
+  fn eval(&self, vars: &HashMap<String, f64>) -> Result<f64> {
+    Ok(match self {
+      Node::Number(n) => *n,
+      Node::Var(name) => match vars.get(name) {
+        Some(value) => *value,
+        None => match name.as_str() {
+          "pi" => std::f64::consts::PI,
+          "e" => std::f64::consts::E,
+          _ => {
+            return Err(AppError::InvalidInput(format!(
+              "no value for variable {:?}",
+              name
+            )))
+          }
+        },
+      },
+      Node::Prefix(prefixes, node) => {
+        prefixes
+          .iter()
+          .rev()
+          .fold(node.eval(vars)?, |value, prefix| match prefix {
+            Prefix::Neg => -value,
+            Prefix::Not if value == 0.0 => 1.0,
+            Prefix::Not => 0.0,
+          })
+      }
+      Node::Chain(first, rest) => {
+        let mut value = first.eval(vars)?;
+        for (op, node) in rest {
+          value = match op {
+            // `&&` and `||` short-circuit, so `x != 0 && 1 / x > 2` is safe.
+            Op::And if value == 0.0 => 0.0,
+            Op::Or if value != 0.0 => 1.0,
+            _ => op.apply(value, node.eval(vars)?),
+          };
+        }
+        value
+      }
+      Node::Cond(cond, then, otherwise) => {
+        if cond.eval(vars)? != 0.0 {
+          then.eval(vars)?
+        } else {
+          otherwise.eval(vars)?
+        }
+      }
+      Node::Call(func, args) => {
+        let args = args
+          .iter()
+          .map(|arg| arg.eval(vars))
+          .collect::<Result<Vec<_>>>()?;
+        func.apply(&args)
+      }
+    })
+  }
+
+  fn collect_vars<'a>(&'a self, out: &mut BTreeSet<&'a str>) {
+    match self {
+      Node::Number(_) => {}
+      Node::Var(name) => {
+        out.insert(name);
+      }
+      Node::Prefix(_, node) => node.collect_vars(out),
+      Node::Chain(first, rest) => {
+        first.collect_vars(out);
+        rest.iter().for_each(|(_, node)| node.collect_vars(out));
+      }
+      Node::Cond(cond, then, otherwise) => {
+        cond.collect_vars(out);
+        then.collect_vars(out);
+        otherwise.collect_vars(out);
+      }
+      Node::Call(_, args) => args.iter().for_each(|arg| arg.collect_vars(out)),
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+  Number(f64),
+  Ident(String),
+  Op(Op),
+  Not,
+  LParen,
+  RParen,
+  Comma,
+  Question,
+  Colon,
+  End,
+}
+
+impl Token {
+  fn describe(&self, source: &str, at: usize) -> String {
+    match self {
+      Token::End => "end of expression".to_string(),
+      _ => format!("{:?}", token_text(source, at)),
+    }
+  }
+}
+
+/// The source text of the token starting at byte `at`.
+fn token_text(source: &str, at: usize) -> &str {
+  let rest = &source[at..];
+  let first = rest.chars().next().map_or(0, char::len_utf8);
+  let len = if rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+    rest
+      .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+      .unwrap_or(rest.len())
+  } else if ["<=", ">=", "==", "!=", "&&", "||", "**"]
+    .iter()
+    .any(|op| rest.starts_with(op))
+  {
+    2
+  } else {
+    first
+  };
+  &rest[..len]
+}
+
+struct Parser<'a> {
+  source: &'a str,
+  /// Tokens with the byte offset each starts at.
+  tokens: Vec<(Token, usize)>,
+  pos: usize,
+  depth: usize,
+}
+
+fn parse_error(source: &str, at: usize, reason: impl std::fmt::Display) -> AppError {
+  let column = source[..at].chars().count() + 1;
+  AppError::Parse(format!(
+    "invalid expression at column {}: {}\n  {}\n  {}^",
+    column,
+    reason,
+    source,
+    " ".repeat(column - 1)
+  ))
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+  let bytes = source.as_bytes();
+  let mut tokens = Vec::new();
+  let mut i = 0;
+  while i < bytes.len() {
+    let c = bytes[i];
+    if c.is_ascii_whitespace() {
+      i += 1;
+      continue;
+    }
+    let start = i;
+    let two = |s: &str| source[i..].starts_with(s);
+    let token =
+      if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) {
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+          i += 1;
+        }
+        if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+          let mut j = i + 1;
+          if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+            j += 1;
+          }
+          if j < bytes.len() && bytes[j].is_ascii_digit() {
+            i = j;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+              i += 1;
+            }
+          }
+        }
+        let text = &source[start..i];
+        let value = text
+          .parse()
+          .map_err(|_| parse_error(source, start, format!("invalid number {:?}", text)))?;
+        tokens.push((Token::Number(value), start));
+        continue;
+      } else if c.is_ascii_alphabetic() || c == b'_' {
+        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+          i += 1;
+        }
+        tokens.push((Token::Ident(source[start..i].to_string()), start));
+        continue;
+      } else if two("<=") {
+        Token::Op(Op::Le)
+      } else if two(">=") {
+        Token::Op(Op::Ge)
+      } else if two("==") {
+        Token::Op(Op::Eq)
+      } else if two("!=") {
+        Token::Op(Op::Ne)
+      } else if two("&&") {
+        Token::Op(Op::And)
+      } else if two("||") {
+        Token::Op(Op::Or)
+      } else if two("**") {
+        Token::Op(Op::Pow)
+      } else {
+        let token = match c {
+          b'+' => Token::Op(Op::Add),
+          b'-' => Token::Op(Op::Sub),
+          b'*' => Token::Op(Op::Mul),
+          b'/' => Token::Op(Op::Div),
+          b'%' => Token::Op(Op::Rem),
+          b'^' => Token::Op(Op::Pow),
+          b'<' => Token::Op(Op::Lt),
+          b'>' => Token::Op(Op::Gt),
+          b'!' => Token::Not,
+          b'(' => Token::LParen,
+          b')' => Token::RParen,
+          b',' => Token::Comma,
+          b'?' => Token::Question,
+          b':' => Token::Colon,
+          _ => {
+            return Err(parse_error(
+              source,
+              start,
+              format!("unexpected {:?}", token_text(source, start)),
+            ))
+          }
+        };
+        i += 1;
+        tokens.push((token, start));
+        continue;
+      };
+    i += 2;
+    tokens.push((token, start));
+  }
+  tokens.push((Token::End, source.len()));
+  Ok(tokens)
+}
+
+impl Parser<'_> {
+  fn peek(&self) -> &Token {
+    &self.tokens[self.pos].0
+  }
+
+  fn next(&mut self) -> (Token, usize) {
+    let token = self.tokens[self.pos].clone();
+    if token.0 != Token::End {
+      self.pos += 1;
+    }
+    token
+  }
+
+  fn unexpected(&self, expected: &str) -> AppError {
+    let (token, at) = &self.tokens[self.pos];
+    parse_error(
+      self.source,
+      *at,
+      format!(
+        "expected {}, found {}",
+        expected,
+        token.describe(self.source, *at)
+      ),
+    )
+  }
+
+  fn expect(&mut self, token: Token, expected: &str) -> Result<()> {
+    if *self.peek() != token {
+      return Err(self.unexpected(expected));
+    }
+    self.next();
+    Ok(())
+  }
+
+  /// Runs `parse` one level deeper, failing past `MAX_DEPTH`.
+  fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<Node>) -> Result<Node> {
+    if self.depth == MAX_DEPTH {
+      return Err(parse_error(
+        self.source,
+        self.tokens[self.pos].1,
+        format!("nested more than {} levels", MAX_DEPTH),
+      ));
+    }
+    self.depth += 1;
+    let node = parse(self);
+    self.depth -= 1;
+    node
+  }
+
+  /// `binary ('?' conditional ':' conditional)?`, right-associative.
+  fn conditional(&mut self) -> Result<Node> {
+    let cond = self.binary(0)?;
+    if *self.peek() != Token::Question {
+      return Ok(cond);
+    }
+    self.next();
+    let then = self.nested(Self::conditional)?;
+    self.expect(Token::Colon, "\":\"")?;
+    let otherwise = self.nested(Self::conditional)?;
+    Ok(Node::Cond(
+      Box::new(cond),
+      Box::new(then),
+      Box::new(otherwise),
+    ))
+  }
+
+  /// Precedence climbing over the binary operators binding tighter than
+  /// `min`.
+  fn binary(&mut self, min: u8) -> Result<Node> {
+    let mut left = self.unary()?;
+    let mut rest: Vec<(Op, Node)> = Vec::new();
+    while let Token::Op(op) = *self.peek() {
+      if op.precedence() <= min {
+        break;
+      }
+      self.next();
+      // `^` is right-associative: `2 ^ 3 ^ 2` is `2 ^ 9`.
+      let right = if op == Op::Pow {
+        self.nested(|parser| parser.binary(op.precedence() - 1))?
+      } else {
+        self.binary(op.precedence())?
+      };
+      // Precedence only falls from here on, as `right` took the tighter
+      // operators, so a change closes the chain so far.
+      if rest
+        .first()
+        .is_some_and(|(first, _)| first.precedence() != op.precedence())
+      {
+        left = Node::Chain(Box::new(left), std::mem::take(&mut rest));
+      }
+      rest.push((op, right));
+    }
+    Ok(if rest.is_empty() {
+      left
+    } else {
+      Node::Chain(Box::new(left), rest)
+    })
+  }
+
+  /// Prefix `-`, `+` and `!`. They bind looser than `^`, so `-2 ^ 2` is -4.
+  fn unary(&mut self) -> Result<Node> {
+    let mut prefixes = Vec::new();
+    let mut prefixed = false;
+    loop {
+      match self.peek() {
+        Token::Op(Op::Sub) => prefixes.push(Prefix::Neg),
+        Token::Op(Op::Add) => {}
+        Token::Not => prefixes.push(Prefix::Not),
+        _ => break,
+      }
+      prefixed = true;
+      self.next();
+    }
+    if !prefixed {
+      return self.primary();
+    }
+    let operand = self.binary(Op::Mul.precedence())?;
+    Ok(if prefixes.is_empty() {
+      operand
+    } else {
+      Node::Prefix(prefixes, Box::new(operand))
+    })
+  }
+
+  fn primary(&mut self) -> Result<Node> {
+    let (token, at) = self.next();
+    match token {
+      Token::Number(n) => Ok(Node::Number(n)),
+      Token::LParen => {
+        let node = self.nested(Self::conditional)?;
+        self.expect(Token::RParen, "\")\"")?;
+        Ok(node)
+      }
+      Token::Ident(name) if *self.peek() == Token::LParen => {
+        let func = Func::lookup(&name)
+          .ok_or_else(|| parse_error(self.source, at, format!("unknown function {:?}", name)))?;
+        self.next();
+        let mut args = Vec::new();
+        if *self.peek() != Token::RParen {
+          loop {
+            args.push(self.nested(Self::conditional)?);
+            if *self.peek() != Token::Comma {
+              break;
+            }
+            self.next();
+          }
+        }
+        self.expect(Token::RParen, "\",\" or \")\"")?;
+        let (min, max) = func.arity();
+        if args.len() < min || args.len() > max {
+          let expected = match (min, max) {
+            (min, max) if min == max => format!("{}", min),
+            (min, usize::MAX) => format!("at least {}", min),
+            (min, max) => format!("{} to {}", min, max),
+          };
+          return Err(parse_error(
+            self.source,
+            at,
+            format!(
+              "{} takes {} argument(s), got {}",
+              name,
+              expected,
+              args.len()
+            ),
+          ));
+        }
+        Ok(Node::Call(func, args))
+      }
+      Token::Ident(name) => Ok(Node::Var(name)),
+      _ => {
+        self.pos -= usize::from(token != Token::End);
+        Err(self.unexpected("a number, variable or \"(\""))
+      }
+    }
+  }
+}
+
+fn parse(source: &str) -> Result<Node> {
+  let mut parser = Parser {
+    source,
+    tokens: tokenize(source)?,
+    pos: 0,
+    depth: 0,
+  };
+  let node = parser.conditional()?;
+  if *parser.peek() != Token::End {
+    return Err(parser.unexpected("an operator or end of expression"));
+  }
+  Ok(node)
+}
+
+/// A formula compiled once and evaluated many times, e.g. per row or per
+/// frame, without going through JS `eval()`.
+///
+/// Supports numbers (`1.5`, `2e-3`), variables, `+ - * / %`, `^` or `**`
+/// (right-associative), comparisons `< <= > >= == !=` and `&& || !`
+/// (yielding 1 or 0), `cond ? a : b`, the constants `pi` and `e`, and the
+/// functions `abs acos asin atan atan2 ceil cos exp floor hypot ln log
+/// log10 log2 max min pow round sign sin sqrt tan trunc`. `log(x)` is the
+/// natural logarithm and `log(x, base)` takes a base. Syntax errors throw
+/// `ERR_PARSE` with the column and a caret under it, as does nesting
+/// parentheses, calls, `?:` or `^` more than 256 levels deep.
+#[napi]
+pub struct Expression {
+  root: Node,
+  source: String,
+}
+
+#[napi]
+impl Expression {
+  #[napi(catch_unwind, factory)]
+  pub fn compile(expr: String) -> Result<Self> {
+    Ok(Expression {
+      root: parse(&expr)?,
+      source: expr,
+    })
+  }
+
+  /// Evaluates the expression with `vars`. A variable without a value
+  /// throws `ERR_INVALID_INPUT`. Arithmetic follows IEEE 754, so `1 / 0`
+  /// is `Infinity`.
+  #[napi(catch_unwind, ts_args_type = "vars?: Record<string, number>")]
+  pub fn eval(&self, vars: Option<HashMap<String, f64>>) -> Result<f64> {
+    self.root.eval(&vars.unwrap_or_default())
+  }
+
+  /// Names of the variables the expression uses, sorted, including `pi`
+  /// and `e` when used.
+  #[napi(catch_unwind, getter)]
+  pub fn variables(&self) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    self.root.collect_vars(&mut names);
+    names.into_iter().map(str::to_string).collect()
+  }
+
+  /// The expression as given to `compile`.
+  #[napi(catch_unwind, getter)]
+  pub fn source(&self) -> String {
+    self.source.clone()
+  }
+}
+
+/// Parses and evaluates `expr` once; see `Expression` for the syntax.
+#[napi(
+  catch_unwind,
+  ts_args_type = "expr: string, vars?: Record<string, number>"
+)]
+pub fn eval_expr(expr: String, vars: Option<HashMap<String, f64>>) -> Result<f64> {
+  parse(&expr)?.eval(&vars.unwrap_or_default())
+}
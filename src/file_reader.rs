@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject, JsSymbol};
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::sync::Mutex;
+
+use crate::error::{self, AppError, Result};
+
+type SharedReader = Arc<Mutex<Option<BufReader<File>>>>;
+
+/// Pull-based file reader. Nothing is read until JS asks for it, so the
+/// consumer sets the pace and large files never have to fit in memory.
+#[napi]
+pub struct FileReader {
+  reader: SharedReader,
+}
+
+#[napi]
+impl FileReader {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(path: String) -> Result<Self> {
+    let file = std::fs::File::open(&path).map_err(|e| AppError::io(&path, e))?;
+    Ok(FileReader {
+      reader: Arc::new(Mutex::new(Some(BufReader::new(File::from_std(file))))),
+    })
+  }
+
+  /// Reads up to `size` bytes. Resolves with `null` at end of file.
+  #[napi(catch_unwind, ts_return_type = "Promise<Buffer | null>")]
+  pub fn read_chunk(&self, env: Env, size: u32) -> Result<JsObject> {
+    let reader = self.reader.clone();
+    error::spawn(&env, async move {
+      let mut guard = reader.lock().await;
+      let reader = guard.as_mut().ok_or_else(closed)?;
+      let mut buf = Vec::with_capacity(size as usize);
+      reader.take(size as u64).read_to_end(&mut buf).await?;
+      Ok(if buf.is_empty() {
+        None
+      } else {
+        Some(Buffer::from(buf))
+      })
+    })
+  }
+
+  /// Reads the next line without its trailing `\n` / `\r\n`. Resolves with
+  /// `null` at end of file.
+  #[napi(catch_unwind, ts_return_type = "Promise<string | null>")]
+  pub fn read_line(&self, env: Env) -> Result<JsObject> {
+    error::spawn(&env, next_line(self.reader.clone()))
+  }
+
+  /// The remaining lines as an async iterator, for `for await` loops:
+  ///
+  /// ```js
+  /// for await (const line of reader.lines()) { ... }
+  /// ```
+  ///
+  /// It shares the reader's position with `readLine`, and leaving the
+  /// loop early leaves the file open.
+  #[napi(catch_unwind, ts_return_type = "AsyncIterableIterator<string>")]
+  pub fn lines(&self, env: Env) -> Result<JsObject> {
+    let lines = FileLines {
+      reader: self.reader.clone(),
+    }
+    .into_instance(env)?;
+    let mut lines = lines.as_object(env);
+    // `Symbol` is a function, which the checked getter rejects as an object.
+    let async_iterator = env
+      .get_global()?
+      .get_named_property_unchecked::<JsObject>("Symbol")?
+      .get_named_property::<JsSymbol>("asyncIterator")?;
+    let itself = env.create_function_from_closure("asyncIterator", |ctx| ctx.this::<JsObject>())?;
+    lines.set_property(async_iterator, itself)?;
+    Ok(lines)
+  }
+
+  /// Closes the underlying file. Later reads reject.
+  #[napi(catch_unwind)]
+  pub async fn close(&self) {
+    self.reader.lock().await.take();
+  }
+}
+
+/// The iterator behind `FileReader.lines()`.
+#[napi]
+pub struct FileLines {
+  reader: SharedReader,
+}
+
+#[napi(object)]
+pub struct LineResult {
+  pub done: bool,
+  pub value: Option<String>,
+}
+
+#[napi]
+impl FileLines {
+  #[napi(catch_unwind, ts_return_type = "Promise<IteratorResult<string>>")]
+  pub fn next(&self, env: Env) -> Result<JsObject> {
+    let reader = self.reader.clone();
+    error::spawn(&env, async move {
+      let value = next_line(reader).await?;
+      Ok(LineResult {
+        done: value.is_none(),
+        value,
+      })
+    })
+  }
+}
+
+async fn next_line(reader: SharedReader) -> Result<Option<String>> {
+  let mut guard = reader.lock().await;
+  let reader = guard.as_mut().ok_or_else(closed)?;
+  let mut line = Vec::new();
+  if reader.read_until(b'\n', &mut line).await? == 0 {
+    return Ok(None);
+  }
+  if line.last() == Some(&b'\n') {
+    line.pop();
+    if line.last() == Some(&b'\r') {
+      line.pop();
+    }
+  }
+  String::from_utf8(line)
+    .map(Some)
+    .map_err(|e| AppError::Parse(format!("line is not valid UTF-8: {}", e)))
+}
+
+fn closed() -> AppError {
+  AppError::Closed("FileReader is closed".into())
+}
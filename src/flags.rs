@@ -0,0 +1,474 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use napi::{Env, JsUnknown};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use xxhash_rust::xxh64::xxh64;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+/// Rollout buckets per percentage point, so weights can have three
+/// decimals.
+const BUCKETS_PER_PERCENT: u64 = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RulesDoc {
+  flags: HashMap<String, FlagDoc>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct FlagDoc {
+  #[serde(default = "enabled_by_default")]
+  enabled: bool,
+  variants: HashMap<String, Value>,
+  default_variant: String,
+  off_variant: Option<String>,
+  #[serde(default)]
+  rules: Vec<RuleDoc>,
+  rollout: Option<BTreeMap<String, f64>>,
+  bucket_by: Option<String>,
+  salt: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RuleDoc {
+  #[serde(default)]
+  conditions: Vec<ConditionDoc>,
+  variant: Option<String>,
+  rollout: Option<BTreeMap<String, f64>>,
+  bucket_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ConditionDoc {
+  attribute: String,
+  op: String,
+  #[serde(default)]
+  values: Vec<Value>,
+}
+
+fn enabled_by_default() -> bool {
+  true
+}
+
+enum Op {
+  In,
+  NotIn,
+  Contains,
+  StartsWith,
+  EndsWith,
+  Matches(Vec<Regex>),
+  Gt,
+  Gte,
+  Lt,
+  Lte,
+  Exists,
+}
+
+struct Condition {
+  attribute: String,
+  op: Op,
+  values: Vec<Value>,
+}
+
+enum Serve {
+  Variant(String),
+  /// Cumulative upper bucket of each variant.
+  Rollout {
+    buckets: Vec<(u64, String)>,
+    bucket_by: String,
+  },
+}
+
+struct Rule {
+  conditions: Vec<Condition>,
+  serve: Serve,
+}
+
+struct Flag {
+  enabled: bool,
+  variants: HashMap<String, Value>,
+  default_variant: String,
+  off_variant: String,
+  rules: Vec<Rule>,
+  fallthrough: Option<Serve>,
+  salt: String,
+}
+
+/// How a flag was evaluated for one context.
+#[napi(object)]
+pub struct Variant {
+  pub flag: String,
+  pub variant: String,
+  #[napi(ts_type = "unknown")]
+  pub value: Value,
+  /// `off` for a disabled flag, `rule` when a rule matched,
+  /// `fallthrough` for the flag's own rollout, and `default` otherwise.
+  #[napi(ts_type = "'off' | 'rule' | 'fallthrough' | 'default'")]
+  pub reason: String,
+  /// Index of the matching rule when `reason` is `rule`.
+  pub rule_index: Option<u32>,
+}
+
+/// JSON equality, except that numbers compare by value, so `1` equals
+/// `1.0`.
+fn json_eq(a: &Value, b: &Value) -> bool {
+  match (a, b) {
+    (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+    _ => a == b,
+  }
+}
+
+fn json_cmp(a: &Value, b: &Value) -> Option<Ordering> {
+  match (a, b) {
+    (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+    _ => None,
+  }
+}
+
+impl Condition {
+  fn compile(flag: &str, doc: ConditionDoc) -> Result<Self> {
+    let invalid = |message: String| AppError::InvalidInput(format!("flag {}: {}", flag, message));
+    let op = match doc.op.as_str() {
+      "in" => Op::In,
+      "notIn" => Op::NotIn,
+      "contains" => Op::Contains,
+      "startsWith" => Op::StartsWith,
+      "endsWith" => Op::EndsWith,
+      "matches" => Op::Matches(
+        doc
+          .values
+          .iter()
+          .map(|pattern| {
+            let pattern = pattern
+              .as_str()
+              .ok_or_else(|| invalid("matches takes string patterns".into()))?;
+            Regex::new(pattern).map_err(|e| invalid(format!("invalid pattern: {}", e)))
+          })
+          .collect::<Result<_>>()?,
+      ),
+      "gt" => Op::Gt,
+      "gte" => Op::Gte,
+      "lt" => Op::Lt,
+      "lte" => Op::Lte,
+      "exists" => Op::Exists,
+      other => return Err(invalid(format!("unknown op: {}", other))),
+    };
+    if doc.values.is_empty() && !matches!(op, Op::Exists) {
+      return Err(invalid(format!("{} needs at least one value", doc.op)));
+    }
+    Ok(Condition {
+      attribute: doc.attribute,
+      op,
+      values: doc.values,
+    })
+  }
+
+  /// Whether the context's attribute satisfies the condition for any of
+  /// its values. A missing attribute satisfies only `notIn`.
+  fn matches(&self, context: &Map<String, Value>) -> bool {
+    let actual = context.get(&self.attribute).unwrap_or(&Value::Null);
+    let text = actual.as_str();
+    let any = |f: &dyn Fn(&Value) -> bool| !actual.is_null() && self.values.iter().any(f);
+    let strings = |f: &dyn Fn(&str, &str) -> bool| {
+      any(&|value| matches!((text, value.as_str()), (Some(text), Some(value)) if f(text, value)))
+    };
+    let ordered = |f: fn(Ordering) -> bool| any(&|value| json_cmp(actual, value).is_some_and(f));
+    match &self.op {
+      Op::In => any(&|value| json_eq(actual, value)),
+      Op::NotIn => !any(&|value| json_eq(actual, value)),
+      Op::Contains => match actual {
+        Value::Array(items) => any(&|value| items.iter().any(|item| json_eq(item, value))),
+        _ => strings(&|text, value| text.contains(value)),
+      },
+      Op::StartsWith => strings(&|text, value| text.starts_with(value)),
+      Op::EndsWith => strings(&|text, value| text.ends_with(value)),
+      Op::Matches(patterns) => text.is_some_and(|text| patterns.iter().any(|p| p.is_match(text))),
+      Op::Gt => ordered(Ordering::is_gt),
+      Op::Gte => ordered(Ordering::is_ge),
+      Op::Lt => ordered(Ordering::is_lt),
+      Op::Lte => ordered(Ordering::is_le),
+      Op::Exists => !actual.is_null(),
+    }
+  }
+}
+
+impl Serve {
+  fn compile(
+    flag: &str,
+    variants: &HashMap<String, Value>,
+    variant: Option<String>,
+    rollout: Option<BTreeMap<String, f64>>,
+    bucket_by: String,
+  ) -> Result<Self> {
+    let invalid = |message: String| AppError::InvalidInput(format!("flag {}: {}", flag, message));
+    let known = |variant: &str| {
+      if variants.contains_key(variant) {
+        Ok(())
+      } else {
+        Err(invalid(format!("unknown variant: {}", variant)))
+      }
+    };
+    match (variant, rollout) {
+      (Some(variant), None) => {
+        known(&variant)?;
+        Ok(Serve::Variant(variant))
+      }
+      (None, Some(weights)) => {
+        let total: f64 = weights.values().sum();
+        if weights.values().any(|&weight| weight < 0.0) || (total - 100.0).abs() > 1e-6 {
+          return Err(invalid(format!(
+            "rollout weights must be non-negative and add up to 100, got {}",
+            total
+          )));
+        }
+        let mut upper = 0.0;
+        let buckets = weights
+          .into_iter()
+          .map(|(variant, weight)| {
+            known(&variant)?;
+            upper += weight;
+            Ok(((upper * BUCKETS_PER_PERCENT as f64).round() as u64, variant))
+          })
+          .collect::<Result<_>>()?;
+        Ok(Serve::Rollout { buckets, bucket_by })
+      }
+      _ => Err(invalid(
+        "a rule needs exactly one of variant and rollout".into(),
+      )),
+    }
+  }
+
+  /// The variant to serve, or `None` when a rollout's bucketing attribute
+  /// is missing from the context.
+  fn pick<'a>(&'a self, salt: &str, context: &Map<String, Value>) -> Option<&'a str> {
+    match self {
+      Serve::Variant(variant) => Some(variant),
+      Serve::Rollout { buckets, bucket_by } => {
+        let key = match context.get(bucket_by)? {
+          Value::String(key) => key.clone(),
+          Value::Number(key) => key.to_string(),
+          _ => return None,
+        };
+        let bucket = xxh64(format!("{}:{}", salt, key).as_bytes(), 0) % (100 * BUCKETS_PER_PERCENT);
+        buckets
+          .iter()
+          .find(|(upper, _)| bucket < *upper)
+          .map(|(_, variant)| variant.as_str())
+      }
+    }
+  }
+}
+
+impl Flag {
+  fn compile(key: &str, doc: FlagDoc) -> Result<Self> {
+    if !doc.variants.contains_key(&doc.default_variant) {
+      return Err(AppError::InvalidInput(format!(
+        "flag {}: unknown variant: {}",
+        key, doc.default_variant
+      )));
+    }
+    let bucket_by = doc.bucket_by.unwrap_or_else(|| "key".into());
+    let rules = doc
+      .rules
+      .into_iter()
+      .map(|rule| {
+        Ok(Rule {
+          conditions: rule
+            .conditions
+            .into_iter()
+            .map(|condition| Condition::compile(key, condition))
+            .collect::<Result<_>>()?,
+          serve: Serve::compile(
+            key,
+            &doc.variants,
+            rule.variant,
+            rule.rollout,
+            rule.bucket_by.unwrap_or_else(|| bucket_by.clone()),
+          )?,
+        })
+      })
+      .collect::<Result<_>>()?;
+    let fallthrough = doc
+      .rollout
+      .map(|rollout| Serve::compile(key, &doc.variants, None, Some(rollout), bucket_by))
+      .transpose()?;
+    let off_variant = doc
+      .off_variant
+      .unwrap_or_else(|| doc.default_variant.clone());
+    if !doc.variants.contains_key(&off_variant) {
+      return Err(AppError::InvalidInput(format!(
+        "flag {}: unknown variant: {}",
+        key, off_variant
+      )));
+    }
+    Ok(Flag {
+      enabled: doc.enabled,
+      variants: doc.variants,
+      default_variant: doc.default_variant,
+      off_variant,
+      rules,
+      fallthrough,
+      salt: doc.salt.unwrap_or_else(|| key.into()),
+    })
+  }
+
+  fn evaluate(&self, key: &str, context: &Map<String, Value>) -> Variant {
+    let (variant, reason, rule_index) = self.decide(context);
+    Variant {
+      flag: key.into(),
+      variant: variant.into(),
+      value: self.variants[variant].clone(),
+      reason: reason.into(),
+      rule_index,
+    }
+  }
+
+  fn decide(&self, context: &Map<String, Value>) -> (&str, &'static str, Option<u32>) {
+    if !self.enabled {
+      return (&self.off_variant, "off", None);
+    }
+    for (index, rule) in self.rules.iter().enumerate() {
+      if rule
+        .conditions
+        .iter()
+        .all(|condition| condition.matches(context))
+      {
+        if let Some(variant) = rule.serve.pick(&self.salt, context) {
+          return (variant, "rule", Some(index as u32));
+        }
+      }
+    }
+    if let Some(variant) = self
+      .fallthrough
+      .as_ref()
+      .and_then(|fallthrough| fallthrough.pick(&self.salt, context))
+    {
+      return (variant, "fallthrough", None);
+    }
+    (&self.default_variant, "default", None)
+  }
+}
+
+fn compile(json: &str) -> Result<HashMap<String, Flag>> {
+  let doc: RulesDoc = serde_json::from_str(json)?;
+  doc
+    .flags
+    .into_iter()
+    .map(|(key, flag)| Ok((key.clone(), Flag::compile(&key, flag)?)))
+    .collect()
+}
+
+fn parse_context(env: &Env, context: Option<JsUnknown>) -> Result<Map<String, Value>> {
+  match context {
+    Some(context) => from_js(env, context),
+    None => Ok(Map::new()),
+  }
+}
+
+/// Evaluates feature flags from a JSON rules document:
+///
+/// ```json
+/// {"flags": {"checkout": {
+///   "variants": {"old": false, "new": true},
+///   "defaultVariant": "old",
+///   "rules": [{"conditions": [{"attribute": "country", "op": "in", "values": ["NL"]}],
+///              "variant": "new"}],
+///   "rollout": {"new": 10, "old": 90}
+/// }}}
+/// ```
+///
+/// Rules are tried in order and the first whose conditions all hold
+/// serves its `variant` or `rollout`; otherwise the flag's own `rollout`
+/// applies, else `defaultVariant`. A disabled flag (`"enabled": false`)
+/// serves `offVariant`, which defaults to `defaultVariant`. Conditions
+/// test a context attribute with `in`, `notIn`, `contains`, `startsWith`,
+/// `endsWith`, `matches` (regex), `gt`, `gte`, `lt`, `lte` or `exists`,
+/// holding if any of `values` does.
+///
+/// Rollouts give each variant a percentage and place a context by
+/// hashing its `bucketBy` attribute (default `key`) with the flag's
+/// `salt` (default the flag key), so the same user always lands in the
+/// same variant and raising a percentage only moves users into it.
+#[napi]
+pub struct FlagEngine {
+  flags: HashMap<String, Flag>,
+}
+
+#[napi]
+impl FlagEngine {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(rules: Option<String>) -> Result<Self> {
+    Ok(FlagEngine {
+      flags: match rules {
+        Some(rules) => compile(&rules)?,
+        None => HashMap::new(),
+      },
+    })
+  }
+
+  /// Replaces every flag with those in `json`. Invalid JSON throws
+  /// `ERR_PARSE` and an invalid rule `ERR_INVALID_INPUT`; either way the
+  /// current flags stay in place.
+  #[napi(catch_unwind)]
+  pub fn load_rules(&mut self, json: String) -> Result<()> {
+    self.flags = compile(&json)?;
+    Ok(())
+  }
+
+  /// Throws `ERR_NOT_FOUND` for a flag that isn't loaded.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "flagKey: string, context?: Record<string, unknown>"
+  )]
+  pub fn evaluate(
+    &self,
+    env: Env,
+    flag_key: String,
+    context: Option<JsUnknown>,
+  ) -> Result<Variant> {
+    let context = parse_context(&env, context)?;
+    let flag = self
+      .flags
+      .get(&flag_key)
+      .ok_or_else(|| AppError::NotFound(format!("unknown flag: {}", flag_key)))?;
+    Ok(flag.evaluate(&flag_key, &context))
+  }
+
+  /// Every flag evaluated for `context`, keyed by flag.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "context?: Record<string, unknown>",
+    ts_return_type = "Record<string, Variant>"
+  )]
+  pub fn evaluate_all(
+    &self,
+    env: Env,
+    context: Option<JsUnknown>,
+  ) -> Result<HashMap<String, Variant>> {
+    let context = parse_context(&env, context)?;
+    Ok(
+      self
+        .flags
+        .iter()
+        .map(|(key, flag)| (key.clone(), flag.evaluate(key, &context)))
+        .collect(),
+    )
+  }
+
+  /// Keys of the loaded flags, sorted.
+  #[napi(catch_unwind, getter)]
+  pub fn flags(&self) -> Vec<String> {
+    let mut keys: Vec<String> = self.flags.keys().cloned().collect();
+    keys.sort();
+    keys
+  }
+}
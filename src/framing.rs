@@ -0,0 +1,204 @@
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+const DEFAULT_MAX_FRAME_BYTES: u32 = 64 * 1024 * 1024;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct FramingOptions {
+  /// How each frame's length is written: `varint` (unsigned LEB128, as in
+  /// protobuf's `writeDelimited`), `u32be` or `u32le`. Defaults to
+  /// `varint`.
+  pub prefix: Option<String>,
+  /// Largest payload accepted, in bytes. Defaults to 64 MiB, so a corrupt
+  /// length cannot make the decoder buffer without bound.
+  pub max_frame_bytes: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+enum Prefix {
+  Varint,
+  U32Be,
+  U32Le,
+}
+
+impl Prefix {
+  fn parse(name: Option<&str>) -> Result<Self> {
+    match name.unwrap_or("varint") {
+      "varint" => Ok(Prefix::Varint),
+      "u32be" => Ok(Prefix::U32Be),
+      "u32le" => Ok(Prefix::U32Le),
+      other => Err(AppError::InvalidInput(format!(
+        "unknown length prefix: {}",
+        other
+      ))),
+    }
+  }
+
+  fn write(self, len: usize, out: &mut Vec<u8>) -> Result<()> {
+    match self {
+      Prefix::Varint => {
+        let mut len = len as u64;
+        while len >= 0x80 {
+          out.push(len as u8 | 0x80);
+          len >>= 7;
+        }
+        out.push(len as u8);
+      }
+      Prefix::U32Be | Prefix::U32Le => {
+        let len = u32::try_from(len).map_err(|_| {
+          AppError::Overflow(format!("frame of {} bytes does not fit a u32 prefix", len))
+        })?;
+        out.extend_from_slice(&match self {
+          Prefix::U32Be => len.to_be_bytes(),
+          _ => len.to_le_bytes(),
+        });
+      }
+    }
+    Ok(())
+  }
+
+  /// Reads a length from the start of `bytes`. Returns the prefix size and
+  /// the length, or `None` if `bytes` ends inside the prefix.
+  fn read(self, bytes: &[u8]) -> Result<Option<(usize, u64)>> {
+    match self {
+      Prefix::Varint => {
+        let mut len = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+          if i == 10 || (i == 9 && *byte > 1) {
+            return Err(AppError::Parse(
+              "varint length prefix overflows 64 bits".into(),
+            ));
+          }
+          len |= u64::from(byte & 0x7f) << (7 * i);
+          if byte & 0x80 == 0 {
+            return Ok(Some((i + 1, len)));
+          }
+        }
+        Ok(None)
+      }
+      Prefix::U32Be | Prefix::U32Le => {
+        let Some(prefix) = bytes.get(..4) else {
+          return Ok(None);
+        };
+        let prefix: [u8; 4] = prefix.try_into().expect("4-byte slice");
+        let len = match self {
+          Prefix::U32Be => u32::from_be_bytes(prefix),
+          _ => u32::from_le_bytes(prefix),
+        };
+        Ok(Some((4, u64::from(len))))
+      }
+    }
+  }
+}
+
+/// Prepends the length of `payload` to it, for writing to a socket read by
+/// a `FrameDecoder` with the same `prefix`.
+#[napi(catch_unwind)]
+pub fn frame_message(payload: Buffer, options: Option<FramingOptions>) -> Result<Buffer> {
+  let options = options.unwrap_or_default();
+  let prefix = Prefix::parse(options.prefix.as_deref())?;
+  let mut out = Vec::with_capacity(payload.len() + 10);
+  prefix.write(payload.len(), &mut out)?;
+  out.extend_from_slice(&payload);
+  Ok(out.into())
+}
+
+/// Splits a byte stream of length-prefixed frames back into payloads.
+///
+/// Feed it every chunk from e.g. a `net.Socket`'s `data` events, in order;
+/// frames split across chunks are held back until complete.
+#[napi]
+pub struct FrameDecoder {
+  prefix: Prefix,
+  max_frame_bytes: u64,
+  pending: Vec<u8>,
+  /// Bytes still to drop from a rejected oversized frame.
+  skip: u64,
+}
+
+#[napi]
+impl FrameDecoder {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<FramingOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    Ok(FrameDecoder {
+      prefix: Prefix::parse(options.prefix.as_deref())?,
+      max_frame_bytes: u64::from(options.max_frame_bytes.unwrap_or(DEFAULT_MAX_FRAME_BYTES)),
+      pending: Vec::new(),
+      skip: 0,
+    })
+  }
+
+  /// Returns the payloads of every frame completed by `chunk`.
+  ///
+  /// A frame longer than `maxFrameBytes` throws `ERR_INVALID_INPUT`; the
+  /// frames before it are dropped with it, and its payload is skipped as it
+  /// arrives, so decoding resumes with the next frame. A malformed varint
+  /// prefix throws `ERR_PARSE` and discards everything buffered, as the
+  /// frame boundaries are lost.
+  #[napi(catch_unwind)]
+  pub fn push(&mut self, chunk: Buffer) -> Result<Vec<Buffer>> {
+    let mut chunk: &[u8] = &chunk;
+    if self.skip > 0 {
+      let skipped = self.skip.min(chunk.len() as u64);
+      self.skip -= skipped;
+      chunk = &chunk[skipped as usize..];
+    }
+    self.pending.extend_from_slice(chunk);
+
+    let mut frames = Vec::new();
+    let mut start = 0;
+    let result = loop {
+      let header = match self.prefix.read(&self.pending[start..]) {
+        Ok(Some(header)) => header,
+        Ok(None) => break Ok(()),
+        Err(e) => {
+          start = self.pending.len();
+          break Err(e);
+        }
+      };
+      let (prefix_len, len) = header;
+      if len > self.max_frame_bytes {
+        let available = (self.pending.len() - start - prefix_len) as u64;
+        self.skip = len.saturating_sub(available);
+        start = (start + prefix_len).saturating_add(len.min(available) as usize);
+        break Err(AppError::InvalidInput(format!(
+          "frame of {} bytes exceeds maxFrameBytes ({})",
+          len, self.max_frame_bytes
+        )));
+      }
+      let end = start + prefix_len + len as usize;
+      if end > self.pending.len() {
+        break Ok(());
+      }
+      frames.push(self.pending[start + prefix_len..end].to_vec().into());
+      start = end;
+    };
+    self.pending.drain(..start);
+    result.map(|_| frames)
+  }
+
+  /// Checks that the stream ended on a frame boundary, and resets the
+  /// decoder. A partial frame left over throws `ERR_PARSE`.
+  #[napi(catch_unwind)]
+  pub fn end(&mut self) -> Result<()> {
+    let left = self.pending.len() as u64 + self.skip;
+    self.pending.clear();
+    self.skip = 0;
+    if left > 0 {
+      return Err(AppError::Parse(format!(
+        "stream ended inside a frame ({} bytes buffered)",
+        left
+      )));
+    }
+    Ok(())
+  }
+
+  /// Bytes of an incomplete frame waiting for the rest of it.
+  #[napi(catch_unwind, getter)]
+  pub fn buffered(&self) -> u32 {
+    self.pending.len() as u32
+  }
+}
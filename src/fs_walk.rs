@@ -0,0 +1,172 @@
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct WalkOptions {
+  /// Globs matched against paths relative to the root, e.g. `**/*.rs`. When
+  /// set, only matching files are returned.
+  pub include: Option<Vec<String>>,
+  /// Globs for files and directories to skip. A matching directory is not
+  /// descended into.
+  pub exclude: Option<Vec<String>>,
+  /// Honour `.gitignore` and `.ignore` files. Defaults to `true`.
+  pub gitignore: Option<bool>,
+  /// Include dot-files and dot-directories. Defaults to `false`.
+  pub hidden: Option<bool>,
+  /// How many levels below the root to descend; 1 lists only the root's
+  /// direct children.
+  pub max_depth: Option<u32>,
+  /// Return directories as well as files. Defaults to `false`.
+  pub include_dirs: Option<bool>,
+  pub follow_links: Option<bool>,
+}
+
+#[napi(object)]
+pub struct FileEntry {
+  pub path: String,
+  /// `path` relative to the walk root, always with `/` separators.
+  pub relative_path: String,
+  pub is_dir: bool,
+  pub size: i64,
+  pub mtime_ms: f64,
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+  let mut builder = GlobSetBuilder::new();
+  for pattern in patterns {
+    let glob =
+      Glob::new(pattern).map_err(|e| AppError::InvalidInput(format!("invalid glob: {}", e)))?;
+    builder.add(glob);
+  }
+  builder
+    .build()
+    .map_err(|e| AppError::InvalidInput(e.to_string()))
+}
+
+pub struct WalkTask {
+  root: String,
+  include: Option<GlobSet>,
+  exclude: Option<GlobSet>,
+  opts: WalkOptions,
+}
+
+impl WalkTask {
+  fn run(&self) -> Result<Vec<FileEntry>> {
+    let root = Path::new(&self.root);
+    if !root.is_dir() {
+      std::fs::read_dir(root).map_err(|e| AppError::io(&self.root, e))?;
+    }
+    let gitignore = self.opts.gitignore.unwrap_or(true);
+    let mut builder = WalkBuilder::new(root);
+    builder
+      .hidden(!self.opts.hidden.unwrap_or(false))
+      .git_ignore(gitignore)
+      .git_exclude(gitignore)
+      .ignore(gitignore)
+      .parents(gitignore)
+      .git_global(false)
+      .require_git(false)
+      .follow_links(self.opts.follow_links.unwrap_or(false))
+      .max_depth(self.opts.max_depth.map(|d| d as usize));
+    let include_dirs = self.opts.include_dirs.unwrap_or(false);
+
+    let found = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+      Box::new(|entry| {
+        // Unreadable entries (permissions, races with deletion) are skipped.
+        let Ok(entry) = entry else {
+          return WalkState::Continue;
+        };
+        if entry.depth() == 0 {
+          return WalkState::Continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+          return WalkState::Continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        if self
+          .exclude
+          .as_ref()
+          .is_some_and(|set| set.is_match(&relative))
+        {
+          return if is_dir {
+            WalkState::Skip
+          } else {
+            WalkState::Continue
+          };
+        }
+        if is_dir && !include_dirs {
+          return WalkState::Continue;
+        }
+        if !is_dir
+          && self
+            .include
+            .as_ref()
+            .is_some_and(|set| !set.is_match(&relative))
+        {
+          return WalkState::Continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+          return WalkState::Continue;
+        };
+        let mtime_ms = metadata
+          .modified()
+          .ok()
+          .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+          .map_or(0.0, |d| d.as_secs_f64() * 1000.0);
+        let file = FileEntry {
+          path: entry.path().to_string_lossy().into_owned(),
+          relative_path: relative,
+          is_dir,
+          size: metadata.len() as i64,
+          mtime_ms,
+        };
+        found.lock().unwrap_or_else(|e| e.into_inner()).push(file);
+        WalkState::Continue
+      })
+    });
+
+    let mut found = found.into_inner().unwrap_or_else(|e| e.into_inner());
+    found.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(found)
+  }
+}
+
+impl Task for WalkTask {
+  type Output = Result<Vec<FileEntry>>;
+  type JsValue = Vec<FileEntry>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Recursively lists `root` using all cores. Results are sorted by relative
+/// path; entries that can't be read are skipped.
+#[napi(catch_unwind, ts_return_type = "Promise<Array<FileEntry>>")]
+pub fn walk_dir(root: String, opts: Option<WalkOptions>) -> Result<AsyncTask<Guarded<WalkTask>>> {
+  let opts = opts.unwrap_or_default();
+  let include = opts.include.as_deref().map(glob_set).transpose()?;
+  let exclude = opts.exclude.as_deref().map(glob_set).transpose()?;
+  Ok(panic_guard::task(WalkTask {
+    root,
+    include,
+    exclude,
+    opts,
+  }))
+}
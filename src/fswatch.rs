@@ -0,0 +1,200 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::ObjectFinalize;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsFunction};
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::error::{AppError, Result};
+use crate::lifecycle::{self, Resource};
+
+/// A file-system change delivered to `Watcher.on` listeners.
+#[napi(object)]
+#[derive(Clone)]
+pub struct FsEvent {
+  /// `create`, `modify`, `rename`, `remove`, `access`, `other` or `error`.
+  pub kind: String,
+  pub paths: Vec<String>,
+  /// Set only for `error` events.
+  pub message: Option<String>,
+}
+
+type Listener = ThreadsafeFunction<FsEvent, ErrorStrategy::Fatal>;
+type Listeners = Arc<Mutex<Vec<Listener>>>;
+
+fn to_event(result: notify::Result<notify::Event>) -> FsEvent {
+  let paths = |paths: Vec<std::path::PathBuf>| {
+    paths
+      .into_iter()
+      .map(|p| p.to_string_lossy().into_owned())
+      .collect()
+  };
+  match result {
+    Ok(event) => {
+      let kind = match event.kind {
+        EventKind::Create(_) => "create",
+        EventKind::Modify(ModifyKind::Name(_)) => "rename",
+        EventKind::Modify(_) => "modify",
+        EventKind::Remove(_) => "remove",
+        EventKind::Access(_) => "access",
+        EventKind::Any | EventKind::Other => "other",
+      };
+      FsEvent {
+        kind: kind.to_string(),
+        paths: paths(event.paths),
+        message: None,
+      }
+    }
+    Err(e) => FsEvent {
+      kind: "error".to_string(),
+      message: Some(e.to_string()),
+      paths: paths(e.paths),
+    },
+  }
+}
+
+/// The notify watcher and its listeners, shared with the lifecycle registry
+/// so `shutdown()` can close them.
+struct WatcherState {
+  inner: Mutex<Option<RecommendedWatcher>>,
+  listeners: Listeners,
+}
+
+impl WatcherState {
+  fn close(&self) {
+    // Dropping the notify watcher joins its event thread, so no event can
+    // reach a listener after this.
+    let watcher = self.inner.lock().unwrap_or_else(|e| e.into_inner()).take();
+    drop(watcher);
+    self
+      .listeners
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .clear();
+  }
+}
+
+impl Resource for WatcherState {
+  fn kind(&self) -> &'static str {
+    "watcher"
+  }
+
+  fn active(&self) -> bool {
+    self
+      .inner
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .is_some()
+  }
+
+  fn shutdown(&self) {
+    self.close();
+  }
+}
+
+/// Watches files and directories using the platform's native notification
+/// API (inotify, FSEvents, ReadDirectoryChangesW).
+///
+/// Like `fs.watch`, registered listeners keep the process alive until
+/// `close()` is called, the watcher is garbage-collected or `shutdown()`
+/// runs.
+#[napi(custom_finalize)]
+pub struct Watcher {
+  state: Arc<WatcherState>,
+}
+
+#[napi]
+impl Watcher {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(env: Env) -> Result<Self> {
+    let listeners = Listeners::default();
+    let dispatch = listeners.clone();
+    let inner = notify::recommended_watcher(move |result| {
+      let event = to_event(result);
+      for listener in dispatch.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        listener.call(event.clone(), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    })
+    .map_err(watch_error)?;
+    let state = Arc::new(WatcherState {
+      inner: Mutex::new(Some(inner)),
+      listeners,
+    });
+    lifecycle::register(&env, &state)?;
+    Ok(Watcher { state })
+  }
+
+  /// Starts watching `path`; directories are watched recursively unless
+  /// `recursive` is `false`.
+  #[napi(catch_unwind)]
+  pub fn watch(&mut self, path: String, recursive: Option<bool>) -> Result<()> {
+    let mode = if recursive.unwrap_or(true) {
+      RecursiveMode::Recursive
+    } else {
+      RecursiveMode::NonRecursive
+    };
+    self
+      .with_watcher(|w| w.watch(Path::new(&path), mode))?
+      .map_err(|e| match e.kind {
+        notify::ErrorKind::PathNotFound => AppError::NotFound(format!("{}: path not found", path)),
+        notify::ErrorKind::Io(e) => AppError::io(&path, e),
+        _ => watch_error(e),
+      })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn unwatch(&mut self, path: String) -> Result<()> {
+    self
+      .with_watcher(|w| w.unwatch(Path::new(&path)))?
+      .map_err(watch_error)
+  }
+
+  /// Registers `callback(event)` for every change under the watched paths.
+  #[napi(catch_unwind, ts_args_type = "callback: (event: FsEvent) => void")]
+  pub fn on(&mut self, callback: JsFunction) -> Result<()> {
+    self.with_watcher(|_| ())?;
+    let listener: Listener = callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+    self.lock_listeners().push(listener);
+    Ok(())
+  }
+
+  /// Stops watching and releases all listeners. Closing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn close(&mut self) {
+    self.state.close();
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn closed(&self) -> bool {
+    !self.state.active()
+  }
+
+  fn with_watcher<R>(&self, f: impl FnOnce(&mut RecommendedWatcher) -> R) -> Result<R> {
+    let mut inner = self.state.inner.lock().unwrap_or_else(|e| e.into_inner());
+    let watcher = inner
+      .as_mut()
+      .ok_or_else(|| AppError::Closed("watcher is closed".into()))?;
+    Ok(f(watcher))
+  }
+
+  fn lock_listeners(&self) -> std::sync::MutexGuard<'_, Vec<Listener>> {
+    self
+      .state
+      .listeners
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+impl ObjectFinalize for Watcher {
+  fn finalize(mut self, _: Env) -> napi::Result<()> {
+    self.close();
+    Ok(())
+  }
+}
+
+fn watch_error(e: notify::Error) -> AppError {
+  AppError::Io(e.to_string())
+}
@@ -0,0 +1,89 @@
+use rayon::prelude::*;
+
+/// Edit distance counted in Unicode scalar values.
+#[napi(catch_unwind)]
+pub fn levenshtein(a: String, b: String) -> u32 {
+  strsim::levenshtein(&a, &b) as u32
+}
+
+/// Jaro-Winkler similarity from 0 (nothing in common) to 1 (identical).
+#[napi(catch_unwind)]
+pub fn jaro_winkler(a: String, b: String) -> f64 {
+  strsim::jaro_winkler(&a, &b)
+}
+
+#[napi(object)]
+pub struct FuzzyMatch {
+  pub candidate: String,
+  /// Position of the candidate in the list passed to the constructor.
+  pub index: u32,
+  /// Similarity from 0 to 1; higher is better.
+  pub score: f64,
+}
+
+/// Scores a lower-cased query against a lower-cased candidate. A candidate
+/// that contains the query as a prefix or substring always beats one that
+/// merely looks similar, which is what autocomplete users expect.
+fn score(query: &str, candidate: &str) -> f64 {
+  let similarity = strsim::jaro_winkler(query, candidate);
+  if candidate.starts_with(query) {
+    0.75 + similarity / 4.0
+  } else if candidate.contains(query) {
+    0.5 + similarity / 4.0
+  } else {
+    similarity / 2.0
+  }
+}
+
+/// Indexes a fixed candidate list once and answers top-k fuzzy queries
+/// against it. Matching is case-insensitive and scored in parallel.
+#[napi]
+pub struct FuzzyMatcher {
+  candidates: Vec<String>,
+  folded: Vec<String>,
+}
+
+#[napi]
+impl FuzzyMatcher {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(candidates: Vec<String>) -> Self {
+    let folded = candidates.iter().map(|c| c.to_lowercase()).collect();
+    FuzzyMatcher { candidates, folded }
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.candidates.len() as u32
+  }
+
+  /// Returns up to `limit` (default 10) best matches, best first. Matches
+  /// scoring below `minScore` (default 0) are dropped.
+  #[napi(catch_unwind)]
+  pub fn search(
+    &self,
+    query: String,
+    limit: Option<u32>,
+    min_score: Option<f64>,
+  ) -> Vec<FuzzyMatch> {
+    let query = query.to_lowercase();
+    let min_score = min_score.unwrap_or(0.0);
+    let mut scored: Vec<(usize, f64)> = self
+      .folded
+      .par_iter()
+      .enumerate()
+      .map(|(i, candidate)| (i, score(&query, candidate)))
+      .filter(|&(_, score)| score >= min_score)
+      .collect();
+    // Ties keep the original candidate order.
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored
+      .into_iter()
+      .take(limit.unwrap_or(10) as usize)
+      .map(|(i, score)| FuzzyMatch {
+        candidate: self.candidates[i].clone(),
+        index: i as u32,
+        score,
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,230 @@
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+
+use crate::error::{AppError, Result};
+
+/// Mean Earth radius in metres (IUGG).
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[napi(object)]
+#[derive(Clone, Copy)]
+pub struct Point {
+  pub lat: f64,
+  pub lon: f64,
+}
+
+#[napi(object)]
+pub struct BoundingBox {
+  pub min_lat: f64,
+  pub min_lon: f64,
+  pub max_lat: f64,
+  pub max_lon: f64,
+}
+
+#[napi(object)]
+pub struct DecodedGeohash {
+  /// Centre of the cell.
+  pub lat: f64,
+  pub lon: f64,
+  pub bounds: BoundingBox,
+}
+
+#[napi(object)]
+pub struct Neighbor {
+  /// Index of the point, i.e. its pair offset in the input array.
+  pub index: u32,
+  /// Great-circle distance in metres.
+  pub distance: f64,
+}
+
+fn check_coords(lat: f64, lon: f64) -> Result<()> {
+  if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+    return Err(AppError::InvalidInput(format!(
+      "invalid coordinates ({}, {}): latitude must be within ±90 and longitude within ±180",
+      lat, lon
+    )));
+  }
+  Ok(())
+}
+
+fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+  let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+  let d_phi = (lat2 - lat1).to_radians();
+  let d_lambda = (lon2 - lon1).to_radians();
+  let h = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+  2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+}
+
+/// Great-circle distance in metres between two points given in degrees.
+#[napi(catch_unwind)]
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Result<f64> {
+  check_coords(lat1, lon1)?;
+  check_coords(lat2, lon2)?;
+  Ok(haversine(lat1, lon1, lat2, lon2))
+}
+
+/// The smallest latitude/longitude box containing every point within
+/// `radius` metres of the centre. Longitudes are not wrapped, so a box
+/// crossing the antimeridian extends past ±180; near a pole the box spans
+/// all longitudes.
+#[napi(catch_unwind)]
+pub fn bounding_box(lat: f64, lon: f64, radius: f64) -> Result<BoundingBox> {
+  check_coords(lat, lon)?;
+  if !(radius >= 0.0 && radius.is_finite()) {
+    return Err(AppError::InvalidInput(
+      "radius must be a non-negative number".into(),
+    ));
+  }
+  let d_lat = (radius / EARTH_RADIUS_M).to_degrees();
+  let (min_lat, max_lat) = (lat - d_lat, lat + d_lat);
+  if min_lat <= -90.0 || max_lat >= 90.0 {
+    return Ok(BoundingBox {
+      min_lat: min_lat.max(-90.0),
+      min_lon: -180.0,
+      max_lat: max_lat.min(90.0),
+      max_lon: 180.0,
+    });
+  }
+  let d_lon = ((radius / EARTH_RADIUS_M).sin() / lat.to_radians().cos())
+    .min(1.0)
+    .asin()
+    .to_degrees();
+  Ok(BoundingBox {
+    min_lat,
+    min_lon: lon - d_lon,
+    max_lat,
+    max_lon: lon + d_lon,
+  })
+}
+
+/// Encodes a point as a geohash of `precision` characters (1–12, default 9,
+/// which is a cell of roughly 5 m).
+#[napi(catch_unwind)]
+pub fn geohash_encode(lat: f64, lon: f64, precision: Option<u32>) -> Result<String> {
+  check_coords(lat, lon)?;
+  let precision = precision.unwrap_or(9);
+  if !(1..=12).contains(&precision) {
+    return Err(AppError::InvalidInput(
+      "geohash precision must be between 1 and 12".into(),
+    ));
+  }
+  let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+  let mut hash = String::with_capacity(precision as usize);
+  let mut even = true;
+  for _ in 0..precision {
+    let mut index = 0;
+    for _ in 0..5 {
+      let (range, value): (&mut (f64, f64), f64) = if even {
+        (&mut lon_range, lon)
+      } else {
+        (&mut lat_range, lat)
+      };
+      let mid = (range.0 + range.1) / 2.0;
+      index <<= 1;
+      if value >= mid {
+        index |= 1;
+        range.0 = mid;
+      } else {
+        range.1 = mid;
+      }
+      even = !even;
+    }
+    hash.push(BASE32[index] as char);
+  }
+  Ok(hash)
+}
+
+/// Decodes a geohash into its cell's centre and bounds. Case-insensitive.
+#[napi(catch_unwind)]
+pub fn geohash_decode(hash: String) -> Result<DecodedGeohash> {
+  if hash.is_empty() {
+    return Err(AppError::Parse("geohash must not be empty".into()));
+  }
+  let (mut lat_range, mut lon_range) = ((-90.0f64, 90.0f64), (-180.0f64, 180.0f64));
+  let mut even = true;
+  for c in hash.chars() {
+    let index = BASE32
+      .iter()
+      .position(|&b| b as char == c.to_ascii_lowercase())
+      .ok_or_else(|| AppError::Parse(format!("invalid geohash character {:?}", c)))?;
+    for bit in (0..5).rev() {
+      let range = if even { &mut lon_range } else { &mut lat_range };
+      let mid = (range.0 + range.1) / 2.0;
+      if index >> bit & 1 == 1 {
+        range.0 = mid;
+      } else {
+        range.1 = mid;
+      }
+      even = !even;
+    }
+  }
+  Ok(DecodedGeohash {
+    lat: (lat_range.0 + lat_range.1) / 2.0,
+    lon: (lon_range.0 + lon_range.1) / 2.0,
+    bounds: BoundingBox {
+      min_lat: lat_range.0,
+      min_lon: lon_range.0,
+      max_lat: lat_range.1,
+      max_lon: lon_range.1,
+    },
+  })
+}
+
+/// Whether `point` lies inside `polygon` (even-odd rule), treating
+/// coordinates as planar. The polygon may be closed or open.
+#[napi(catch_unwind)]
+pub fn point_in_polygon(point: Point, polygon: Vec<Point>) -> Result<bool> {
+  if polygon.len() < 3 {
+    return Err(AppError::InvalidInput(
+      "polygon needs at least three vertices".into(),
+    ));
+  }
+  let mut inside = false;
+  let mut prev = polygon[polygon.len() - 1];
+  for &cur in &polygon {
+    if (cur.lat > point.lat) != (prev.lat > point.lat)
+      && point.lon < (prev.lon - cur.lon) * (point.lat - cur.lat) / (prev.lat - cur.lat) + cur.lon
+    {
+      inside = !inside;
+    }
+    prev = cur;
+  }
+  Ok(inside)
+}
+
+/// The `k` points closest to `query` by great-circle distance, nearest
+/// first. `points` holds interleaved `[lat, lon, lat, lon, …]` pairs.
+#[napi(catch_unwind)]
+pub fn nearest_neighbors(points: Float64Array, query: Point, k: u32) -> Result<Vec<Neighbor>> {
+  check_coords(query.lat, query.lon)?;
+  if !points.len().is_multiple_of(2) {
+    return Err(AppError::InvalidInput(
+      "points must hold [lat, lon] pairs".into(),
+    ));
+  }
+  let mut distances = points
+    .par_chunks_exact(2)
+    .enumerate()
+    .map(|(index, pair)| {
+      check_coords(pair[0], pair[1])?;
+      Ok((haversine(query.lat, query.lon, pair[0], pair[1]), index))
+    })
+    .collect::<Result<Vec<_>>>()?;
+  let k = (k as usize).min(distances.len());
+  if k == 0 {
+    return Ok(Vec::new());
+  }
+  distances.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+  distances.truncate(k);
+  distances.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+  Ok(
+    distances
+      .into_iter()
+      .map(|(distance, index)| Neighbor {
+        index: index as u32,
+        distance,
+      })
+      .collect(),
+  )
+}
@@ -0,0 +1,149 @@
+use globset::{Glob, GlobBuilder, GlobSet as CompiledSet, GlobSetBuilder};
+
+use crate::error::{AppError, Result};
+
+/// A pattern translated to globset syntax. Ignoring a directory ignores
+/// everything in it, so each pattern also gets a glob for its descendants.
+struct Pattern {
+  glob: Glob,
+  descendants: Glob,
+  /// Written with a trailing `/`: the pattern itself only matches
+  /// directories.
+  dir_only: bool,
+  negated: bool,
+}
+
+fn build(glob: &str) -> Result<Glob> {
+  GlobBuilder::new(glob)
+    .literal_separator(true)
+    .build()
+    .map_err(|e| AppError::InvalidInput(format!("invalid glob: {}", e)))
+}
+
+/// Applies gitignore rules: `*` never crosses `/`, a pattern without a
+/// slash matches at any depth, a leading `/` anchors to the root, a
+/// trailing `/` matches only directories, `!` negates, and a pattern
+/// matching a directory matches everything inside it.
+fn compile(pattern: &str) -> Result<Pattern> {
+  let (negated, pattern) = match pattern.strip_prefix('!') {
+    Some(rest) => (true, rest),
+    None => (false, pattern),
+  };
+  let (dir_only, pattern) = match pattern.strip_suffix('/') {
+    Some(dir) => (true, dir),
+    None => (false, pattern),
+  };
+  let pattern = match pattern.strip_prefix('/') {
+    Some(anchored) => anchored.to_string(),
+    None if !pattern.contains('/') => format!("**/{}", pattern),
+    None => pattern.to_string(),
+  };
+  Ok(Pattern {
+    glob: build(&pattern)?,
+    descendants: build(&format!("{}/**", pattern))?,
+    dir_only,
+    negated,
+  })
+}
+
+/// Splits a trailing `/` off `path`, which marks it as a directory unless
+/// `is_dir` says otherwise.
+fn target(path: &str, is_dir: Option<bool>) -> (&str, bool) {
+  match path.strip_suffix('/') {
+    Some(dir) => (dir, is_dir.unwrap_or(true)),
+    None => (path, is_dir.unwrap_or(false)),
+  }
+}
+
+/// Matches `path` against a single gitignore-style pattern. A `!` pattern
+/// matches exactly the paths the rest of it doesn't. `isDir` says whether
+/// `path` is a directory, for patterns ending in `/`; it defaults to
+/// whether `path` itself ends in `/`.
+#[napi(catch_unwind)]
+pub fn glob_match(pattern: String, path: String, is_dir: Option<bool>) -> Result<bool> {
+  let pattern = compile(&pattern)?;
+  let (path, is_dir) = target(&path, is_dir);
+  let matched = ((is_dir || !pattern.dir_only) && pattern.glob.compile_matcher().is_match(path))
+    || pattern.descendants.compile_matcher().is_match(path);
+  Ok(matched != pattern.negated)
+}
+
+/// A list of gitignore-style patterns compiled once into a single matcher.
+#[napi]
+pub struct GlobSet {
+  set: CompiledSet,
+  /// For each glob in `set`: its pattern's index, and whether it is the
+  /// glob for the path itself rather than for descendants.
+  owners: Vec<(usize, bool)>,
+  dir_only: Vec<bool>,
+  negated: Vec<bool>,
+}
+
+#[napi]
+impl GlobSet {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(patterns: Vec<String>) -> Result<Self> {
+    let mut builder = GlobSetBuilder::new();
+    let mut owners = Vec::with_capacity(patterns.len() * 2);
+    let mut dir_only = Vec::with_capacity(patterns.len());
+    let mut negated = Vec::with_capacity(patterns.len());
+    for (i, pattern) in patterns.iter().enumerate() {
+      let pattern = compile(pattern)?;
+      builder.add(pattern.glob);
+      builder.add(pattern.descendants);
+      owners.extend([(i, true), (i, false)]);
+      dir_only.push(pattern.dir_only);
+      negated.push(pattern.negated);
+    }
+    let set = builder
+      .build()
+      .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok(GlobSet {
+      set,
+      owners,
+      dir_only,
+      negated,
+    })
+  }
+
+  fn matching(&self, path: &str, is_dir: Option<bool>) -> Vec<usize> {
+    let (path, is_dir) = target(path, is_dir);
+    let mut matched: Vec<usize> = self
+      .set
+      .matches(path)
+      .into_iter()
+      .map(|glob| self.owners[glob])
+      .filter(|&(pattern, itself)| !itself || is_dir || !self.dir_only[pattern])
+      .map(|(pattern, _)| pattern)
+      .collect();
+    matched.dedup();
+    matched
+  }
+
+  /// Like gitignore, the last pattern that matches decides: a path is
+  /// matched unless that pattern is a `!` negation. `isDir` is as for
+  /// `globMatch`.
+  #[napi(catch_unwind)]
+  pub fn is_match(&self, path: String, is_dir: Option<bool>) -> bool {
+    match self.matching(&path, is_dir).last() {
+      Some(&last) => !self.negated[last],
+      None => false,
+    }
+  }
+
+  /// Indexes of every pattern that matches `path`, negated ones included,
+  /// in ascending order.
+  #[napi(catch_unwind)]
+  pub fn matches(&self, path: String, is_dir: Option<bool>) -> Vec<u32> {
+    self
+      .matching(&path, is_dir)
+      .into_iter()
+      .map(|i| i as u32)
+      .collect()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.negated.len() as u32
+  }
+}
@@ -0,0 +1,106 @@
+use napi::bindgen_prelude::*;
+use sha2::{Digest, Sha256, Sha512};
+use xxhash_rust::xxh64::{xxh64, Xxh64};
+
+use crate::error::{AppError, Result};
+
+/// Borrows the bytes of a `string | Buffer` argument.
+pub(crate) fn input_bytes(input: &Either<String, Buffer>) -> &[u8] {
+  match input {
+    Either::A(s) => s.as_bytes(),
+    Either::B(b) => b.as_ref(),
+  }
+}
+
+/// Hex-encoded SHA-256 of a string (UTF-8) or Buffer.
+#[napi(catch_unwind)]
+pub fn hash_sha256(input: Either<String, Buffer>) -> String {
+  hex::encode(Sha256::digest(input_bytes(&input)))
+}
+
+/// Hex-encoded SHA-512 of a string (UTF-8) or Buffer.
+#[napi(catch_unwind)]
+pub fn hash_sha512(input: Either<String, Buffer>) -> String {
+  hex::encode(Sha512::digest(input_bytes(&input)))
+}
+
+/// Hex-encoded BLAKE3 (256-bit) of a string (UTF-8) or Buffer.
+#[napi(catch_unwind)]
+pub fn hash_blake3(input: Either<String, Buffer>) -> String {
+  blake3::hash(input_bytes(&input)).to_hex().to_string()
+}
+
+/// xxHash64 with seed 0. Returned as a BigInt since it is a full 64-bit value.
+#[napi(catch_unwind)]
+pub fn xxhash64(input: Buffer) -> BigInt {
+  BigInt::from(xxh64(&input, 0))
+}
+
+enum HasherState {
+  Sha256(Sha256),
+  Sha512(Sha512),
+  Blake3(Box<blake3::Hasher>),
+  Xxh64(Box<Xxh64>),
+}
+
+/// Incremental hasher for data that arrives in chunks.
+///
+/// `algorithm` is one of `sha256` (default), `sha512`, `blake3` or `xxhash64`.
+#[napi]
+pub struct Hasher {
+  state: Option<HasherState>,
+}
+
+#[napi]
+impl Hasher {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(algorithm: Option<String>) -> Result<Self> {
+    let state = match algorithm.as_deref().unwrap_or("sha256") {
+      "sha256" => HasherState::Sha256(Sha256::new()),
+      "sha512" => HasherState::Sha512(Sha512::new()),
+      "blake3" => HasherState::Blake3(Box::default()),
+      "xxhash64" => HasherState::Xxh64(Box::new(Xxh64::new(0))),
+      other => {
+        return Err(AppError::Unsupported(format!(
+          "unsupported hash algorithm: {}",
+          other
+        )))
+      }
+    };
+    Ok(Hasher { state: Some(state) })
+  }
+
+  /// Feeds another chunk into the hasher. Returns `this` for chaining.
+  #[napi(catch_unwind)]
+  pub fn update(&mut self, this: This, data: Either<String, Buffer>) -> Result<This> {
+    let bytes = input_bytes(&data);
+    match self.state.as_mut() {
+      Some(HasherState::Sha256(h)) => h.update(bytes),
+      Some(HasherState::Sha512(h)) => h.update(bytes),
+      Some(HasherState::Blake3(h)) => {
+        h.update(bytes);
+      }
+      Some(HasherState::Xxh64(h)) => h.update(bytes),
+      None => return Err(finalized()),
+    }
+    Ok(this)
+  }
+
+  /// Finishes the hash and returns it hex-encoded. The hasher can't be
+  /// updated afterwards.
+  #[napi(catch_unwind)]
+  pub fn digest_hex(&mut self) -> Result<String> {
+    let digest = match self.state.take() {
+      Some(HasherState::Sha256(h)) => hex::encode(h.finalize()),
+      Some(HasherState::Sha512(h)) => hex::encode(h.finalize()),
+      Some(HasherState::Blake3(h)) => h.finalize().to_hex().to_string(),
+      Some(HasherState::Xxh64(h)) => format!("{:016x}", h.digest()),
+      None => return Err(finalized()),
+    };
+    Ok(digest)
+  }
+}
+
+fn finalized() -> AppError {
+  AppError::Closed("hasher has already been finalized".into())
+}
@@ -0,0 +1,26 @@
+use napi::{Env, JsObject, JsUnknown, Ref};
+
+use crate::error::Result;
+
+/// A JS value kept alive by a reference from Rust. References to primitives
+/// are not allowed before N-API 10, so the value sits at index 0 of a
+/// one-element holder array. Must be `release`d, or the value leaks.
+pub(crate) struct Held(Ref<()>);
+
+impl Held {
+  pub(crate) fn new(env: Env, value: JsUnknown) -> Result<Self> {
+    let mut holder = env.create_array_with_length(1)?;
+    holder.set_element(0, value)?;
+    Ok(Held(env.create_reference(holder)?))
+  }
+
+  pub(crate) fn get(&self, env: Env) -> Result<JsUnknown> {
+    let holder: JsObject = env.get_reference_value(&self.0)?;
+    Ok(holder.get_element(0)?)
+  }
+
+  pub(crate) fn release(mut self, env: Env) -> Result<()> {
+    self.0.unref(env)?;
+    Ok(())
+  }
+}
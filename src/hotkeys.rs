@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction};
+
+use crate::error::{AppError, Result};
+use crate::lifecycle::{self, Resource};
+
+/// Called with `pressed` or `released` and the hotkey's id.
+type HotkeyCallback = ThreadsafeFunction<(&'static str, u32), ErrorStrategy::Fatal>;
+
+struct Registration {
+  hotkey: HotKey,
+  callback: Mutex<Option<HotkeyCallback>>,
+}
+
+impl Resource for Registration {
+  fn kind(&self) -> &'static str {
+    "hotkey"
+  }
+
+  fn active(&self) -> bool {
+    self
+      .callback
+      .lock()
+      .unwrap_or_else(|e| e.into_inner())
+      .is_some()
+  }
+
+  fn shutdown(&self) {
+    unregister(self.hotkey.id());
+  }
+}
+
+/// Registered hotkeys by id. Holds the only strong references, so a
+/// registration lives until it is unregistered.
+fn registrations() -> MutexGuard<'static, HashMap<u32, Arc<Registration>>> {
+  static REGISTRATIONS: OnceLock<Mutex<HashMap<u32, Arc<Registration>>>> = OnceLock::new();
+  REGISTRATIONS
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Runs on whatever thread the OS reports hotkeys on.
+fn dispatch(event: GlobalHotKeyEvent) {
+  let Some(registration) = registrations().get(&event.id).cloned() else {
+    return;
+  };
+  let state = match event.state {
+    HotKeyState::Pressed => "pressed",
+    HotKeyState::Released => "released",
+  };
+  let callback = registration
+    .callback
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+  if let Some(callback) = &*callback {
+    callback.call((state, event.id), ThreadsafeFunctionCallMode::NonBlocking);
+  }
+}
+
+fn hotkey_error(e: global_hotkey::Error) -> AppError {
+  AppError::Io(format!("hotkey: {}", e))
+}
+
+fn new_manager() -> Result<GlobalHotKeyManager> {
+  GlobalHotKeyEvent::set_event_handler(Some(dispatch));
+  GlobalHotKeyManager::new().map_err(hotkey_error)
+}
+
+type Job = Box<dyn FnOnce(&Result<GlobalHotKeyManager>) + Send>;
+
+/// Runs `f` with the process's hotkey manager.
+///
+/// Windows delivers hotkeys as messages to the thread that registered them,
+/// and the X11 backend blocks on its connection, so the manager lives on a
+/// thread of its own that also pumps messages.
+#[cfg(not(target_os = "macos"))]
+fn with_manager<T: Send + 'static>(
+  f: impl FnOnce(&GlobalHotKeyManager) -> Result<T> + Send + 'static,
+) -> Result<T> {
+  use std::sync::mpsc::{self, Sender};
+
+  static JOBS: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+  let jobs = JOBS.get_or_init(|| {
+    let (tx, rx) = mpsc::channel::<Job>();
+    let spawned = std::thread::Builder::new()
+      .name("hotkeys".into())
+      .spawn(move || {
+        let manager = new_manager();
+        loop {
+          #[cfg(windows)]
+          pump_messages();
+          #[cfg(windows)]
+          let job = match rx.recv_timeout(std::time::Duration::from_millis(10)) {
+            Ok(job) => job,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+          };
+          #[cfg(not(windows))]
+          let Ok(job) = rx.recv() else {
+            return;
+          };
+          job(&manager);
+        }
+      });
+    if let Err(e) = spawned {
+      tracing::warn!("hotkey thread failed to start: {}", e);
+    }
+    Mutex::new(tx)
+  });
+  let (reply_tx, reply_rx) = std::sync::mpsc::sync_channel(1);
+  let job: Job = Box::new(move |manager| {
+    let result = match manager {
+      Ok(manager) => f(manager),
+      Err(e) => Err(AppError::Io(e.to_string())),
+    };
+    let _ = reply_tx.send(result);
+  });
+  jobs
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .send(job)
+    .map_err(|_| AppError::Closed("hotkey thread is not running".into()))?;
+  reply_rx
+    .recv()
+    .map_err(|_| AppError::Closed("hotkey thread is not running".into()))?
+}
+
+/// macOS only delivers hotkeys through the main thread's run loop, so the
+/// manager is created on the calling thread, which should be the main one.
+#[cfg(target_os = "macos")]
+fn with_manager<T: Send + 'static>(
+  f: impl FnOnce(&GlobalHotKeyManager) -> Result<T> + Send + 'static,
+) -> Result<T> {
+  thread_local! {
+    static MANAGER: std::cell::OnceCell<Result<GlobalHotKeyManager>> =
+      const { std::cell::OnceCell::new() };
+  }
+  MANAGER.with(|manager| match manager.get_or_init(new_manager) {
+    Ok(manager) => f(manager),
+    Err(e) => Err(AppError::Io(e.to_string())),
+  })
+}
+
+#[cfg(windows)]
+fn pump_messages() {
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE,
+  };
+  unsafe {
+    let mut msg: MSG = std::mem::zeroed();
+    while PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+      TranslateMessage(&msg);
+      DispatchMessageW(&msg);
+    }
+  }
+}
+
+fn unregister(id: u32) -> bool {
+  let Some(registration) = registrations().remove(&id) else {
+    return false;
+  };
+  registration
+    .callback
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .take();
+  let hotkey = registration.hotkey;
+  if let Err(e) = with_manager(move |manager| manager.unregister(hotkey).map_err(hotkey_error)) {
+    tracing::warn!("unregistering hotkey {} failed: {}", id, e);
+  }
+  true
+}
+
+/// Calls `callback` whenever the key combination `accelerator` is pressed
+/// or released anywhere on the desktop, even while another app has focus,
+/// and returns an id for `unregisterHotkey`. Accelerators are modifiers and
+/// one key joined by `+`, e.g. `CmdOrCtrl+Shift+K` or `Alt+F4`.
+///
+/// Linux needs an X11 display (Wayland sessions work through XWayland);
+/// without one this throws `ERR_UNSUPPORTED`. On macOS events only arrive
+/// while the main thread runs a Cocoa event loop, as in Electron.
+#[napi(
+  catch_unwind,
+  ts_args_type = "accelerator: string, callback: (state: 'pressed' | 'released', id: number) => void"
+)]
+pub fn register_hotkey(env: Env, accelerator: String, callback: JsFunction) -> Result<u32> {
+  let hotkey: HotKey = accelerator
+    .parse()
+    .map_err(|e| AppError::InvalidInput(format!("invalid accelerator {:?}: {}", accelerator, e)))?;
+  // The X11 backend reports a missing display only in its own log.
+  #[cfg(all(unix, not(target_os = "macos")))]
+  if std::env::var_os("DISPLAY").is_none_or(|display| display.is_empty()) {
+    return Err(AppError::Unsupported(
+      "hotkeys: no X11 display (DISPLAY is not set)".into(),
+    ));
+  }
+  let id = hotkey.id();
+  if registrations().contains_key(&id) {
+    return Err(AppError::InvalidInput(format!(
+      "{} is already registered",
+      accelerator
+    )));
+  }
+  let callback: HotkeyCallback =
+    callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(&'static str, u32)>| {
+      let (state, id) = ctx.value;
+      Ok(vec![
+        ctx.env.create_string(state)?.into_unknown(),
+        ctx.env.create_uint32(id)?.into_unknown(),
+      ])
+    })?;
+  let registration = Arc::new(Registration {
+    hotkey,
+    callback: Mutex::new(Some(callback)),
+  });
+  lifecycle::register(&env, &registration)?;
+  with_manager(move |manager| manager.register(hotkey).map_err(hotkey_error))?;
+  registrations().insert(id, registration);
+  Ok(id)
+}
+
+/// Stops listening for the hotkey `id`. Returns false if it wasn't
+/// registered.
+#[napi(catch_unwind)]
+pub fn unregister_hotkey(id: u32) -> bool {
+  unregister(id)
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method, Url};
+
+use crate::error::{self, AppError, Result};
+
+const DEFAULT_MAX_REDIRECTS: u32 = 10;
+
+#[napi(object)]
+pub struct RequestOptions {
+  pub url: String,
+  /// Defaults to `GET`.
+  pub method: Option<String>,
+  pub headers: Option<HashMap<String, String>>,
+  pub body: Option<Either<String, Buffer>>,
+  /// Deadline for the whole request, including reading the body.
+  pub timeout_ms: Option<u32>,
+  /// Redirects to follow before failing; `0` returns the 3xx response
+  /// as-is. Defaults to 10. Only `httpRequest` reads this; an `HttpClient`
+  /// takes it from its own options.
+  pub max_redirects: Option<u32>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct HttpClientOptions {
+  /// Default deadline for each request; `RequestOptions.timeoutMs` overrides
+  /// it.
+  pub timeout_ms: Option<u32>,
+  pub max_redirects: Option<u32>,
+  /// Sent with every request unless the request sets the same header.
+  pub headers: Option<HashMap<String, String>>,
+  /// How long an idle pooled connection is kept open. Defaults to 90 s.
+  pub pool_idle_timeout_ms: Option<u32>,
+  pub pool_max_idle_per_host: Option<u32>,
+}
+
+#[napi(object)]
+pub struct HttpResponse {
+  pub status: u16,
+  pub status_text: String,
+  /// Final URL after redirects.
+  pub url: String,
+  /// Lower-cased names; repeated headers are joined with `, `.
+  pub headers: HashMap<String, String>,
+  /// Body after undoing any gzip or brotli content encoding.
+  pub body: Buffer,
+}
+
+fn header_map(headers: Option<HashMap<String, String>>) -> Result<HeaderMap> {
+  let mut map = HeaderMap::new();
+  for (name, value) in headers.unwrap_or_default() {
+    let name = HeaderName::from_bytes(name.as_bytes())
+      .map_err(|_| AppError::InvalidInput(format!("invalid header name: {}", name)))?;
+    let value = HeaderValue::from_str(&value)
+      .map_err(|_| AppError::InvalidInput(format!("invalid value for header {}", name)))?;
+    map.insert(name, value);
+  }
+  Ok(map)
+}
+
+fn redirect_policy(max_redirects: Option<u32>) -> Policy {
+  match max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS) {
+    0 => Policy::none(),
+    n => Policy::limited(n as usize),
+  }
+}
+
+fn http_error(e: reqwest::Error) -> AppError {
+  // reqwest's own message omits the underlying cause, e.g. "connection
+  // refused", so append the source chain.
+  let mut message = e.to_string();
+  let mut source = std::error::Error::source(&e);
+  while let Some(cause) = source {
+    message = format!("{}: {}", message, cause);
+    source = cause.source();
+  }
+  if e.is_timeout() {
+    AppError::Timeout(message)
+  } else if e.is_builder() {
+    AppError::InvalidInput(message)
+  } else {
+    AppError::Network(message)
+  }
+}
+
+/// A request validated and converted on the JS thread, ready to send.
+struct Prepared {
+  method: Method,
+  url: Url,
+  headers: HeaderMap,
+  body: Option<Vec<u8>>,
+  timeout: Option<Duration>,
+}
+
+impl Prepared {
+  fn new(opts: RequestOptions) -> Result<Self> {
+    let method = opts.method.as_deref().unwrap_or("GET").to_ascii_uppercase();
+    let method = Method::from_bytes(method.as_bytes())
+      .map_err(|_| AppError::InvalidInput(format!("invalid HTTP method: {}", method)))?;
+    let url = Url::parse(&opts.url)
+      .map_err(|e| AppError::InvalidInput(format!("invalid URL {}: {}", opts.url, e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+      return Err(AppError::Unsupported(format!(
+        "unsupported URL scheme: {}",
+        url.scheme()
+      )));
+    }
+    Ok(Prepared {
+      method,
+      url,
+      headers: header_map(opts.headers)?,
+      body: opts.body.map(|body| match body {
+        Either::A(s) => s.into_bytes(),
+        Either::B(b) => b.to_vec(),
+      }),
+      timeout: opts.timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+    })
+  }
+
+  async fn send(self, client: Client) -> Result<HttpResponse> {
+    let started = Instant::now();
+    let (method, target) = (self.method.to_string(), self.url.to_string());
+    let mut request = client.request(self.method, self.url).headers(self.headers);
+    if let Some(body) = self.body {
+      request = request.body(body);
+    }
+    if let Some(timeout) = self.timeout {
+      request = request.timeout(timeout);
+    }
+    let response = request.send().await.map_err(|e| {
+      tracing::debug!(method, url = target, error = %e, "request failed");
+      http_error(e)
+    })?;
+    let status = response.status();
+    let url = response.url().to_string();
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in response.headers() {
+      let value = String::from_utf8_lossy(value.as_bytes());
+      headers
+        .entry(name.as_str().to_string())
+        .and_modify(|v| {
+          v.push_str(", ");
+          v.push_str(&value);
+        })
+        .or_insert_with(|| value.into_owned());
+    }
+    let body = response.bytes().await.map_err(http_error)?;
+    tracing::debug!(
+      method,
+      url = target,
+      status = status.as_u16(),
+      bytes = body.len(),
+      duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+      "request finished"
+    );
+    Ok(HttpResponse {
+      status: status.as_u16(),
+      status_text: status.canonical_reason().unwrap_or("").to_string(),
+      url,
+      headers,
+      body: body.to_vec().into(),
+    })
+  }
+}
+
+/// Sends one HTTP(S) request on a fresh connection. Non-2xx statuses
+/// resolve normally; network failures reject with `ERR_NETWORK` and
+/// timeouts with `ERR_TIMEOUT`. Use `HttpClient` for repeated requests.
+#[napi(catch_unwind, ts_return_type = "Promise<HttpResponse>")]
+pub fn http_request(env: Env, opts: RequestOptions) -> Result<JsObject> {
+  let client = Client::builder()
+    .redirect(redirect_policy(opts.max_redirects))
+    .build()
+    .map_err(http_error)?;
+  let prepared = Prepared::new(opts)?;
+  error::spawn(&env, prepared.send(client))
+}
+
+/// A pooled HTTP client: requests to the same host reuse open connections.
+#[napi]
+pub struct HttpClient {
+  client: Client,
+}
+
+#[napi]
+impl HttpClient {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<HttpClientOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let mut builder = Client::builder()
+      .redirect(redirect_policy(options.max_redirects))
+      .default_headers(header_map(options.headers)?);
+    if let Some(ms) = options.timeout_ms {
+      builder = builder.timeout(Duration::from_millis(ms as u64));
+    }
+    if let Some(ms) = options.pool_idle_timeout_ms {
+      builder = builder.pool_idle_timeout(Duration::from_millis(ms as u64));
+    }
+    if let Some(max) = options.pool_max_idle_per_host {
+      builder = builder.pool_max_idle_per_host(max as usize);
+    }
+    Ok(HttpClient {
+      client: builder.build().map_err(http_error)?,
+    })
+  }
+
+  /// Like `httpRequest`, but over this client's connection pool.
+  /// `maxRedirects` must not be set here; it is fixed per client.
+  #[napi(catch_unwind, ts_return_type = "Promise<HttpResponse>")]
+  pub fn request(&self, env: Env, opts: RequestOptions) -> Result<JsObject> {
+    if opts.max_redirects.is_some() {
+      return Err(AppError::InvalidInput(
+        "maxRedirects is set in the HttpClient options, not per request".into(),
+      ));
+    }
+    let prepared = Prepared::new(opts)?;
+    error::spawn(&env, prepared.send(self.client.clone()))
+  }
+}
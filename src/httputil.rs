@@ -0,0 +1,481 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use napi::{Env, JsDate, JsUnknown};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{Map, Value};
+use url::form_urlencoded;
+
+use crate::datetime::date_to_utc;
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+/// What `encodeURIComponent` leaves alone.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'-')
+  .remove(b'_')
+  .remove(b'.')
+  .remove(b'!')
+  .remove(b'~')
+  .remove(b'*')
+  .remove(b'\'')
+  .remove(b'(')
+  .remove(b')');
+/// Bracket segments past this depth stay part of the last key, as in `qs`.
+const MAX_KEY_DEPTH: usize = 5;
+/// Larger `a[i]` indices make object keys instead of sparse arrays.
+const MAX_ARRAY_INDEX: usize = 20;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ParseQuerystringOptions {
+  /// Pairs after this many are ignored. Defaults to 1000; 0 means no
+  /// limit.
+  pub max_keys: Option<u32>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SerializeQuerystringOptions {
+  /// How arrays are written: `repeat` (`a=1&a=2`, the default), `brackets`
+  /// (`a[]=1&a[]=2`) or `indices` (`a[0]=1&a[1]=2`).
+  #[napi(ts_type = "'repeat' | 'brackets' | 'indices'")]
+  pub array_format: Option<String>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct CookieOptions {
+  /// Seconds until the cookie expires; 0 or less deletes it.
+  pub max_age: Option<i64>,
+  pub expires: Option<JsDate>,
+  pub domain: Option<String>,
+  pub path: Option<String>,
+  pub secure: Option<bool>,
+  pub http_only: Option<bool>,
+  #[napi(ts_type = "'strict' | 'lax' | 'none'")]
+  pub same_site: Option<String>,
+  /// Sets `Partitioned` (CHIPS); browsers also require `secure`.
+  pub partitioned: Option<bool>,
+}
+
+/// One entry of an `Accept` header.
+#[napi(object)]
+pub struct MediaRange {
+  /// `type/subtype`, lowercased, e.g. `text/html` or `*/*`.
+  pub media_type: String,
+  #[napi(js_name = "type")]
+  pub kind: String,
+  pub subtype: String,
+  pub q: f64,
+  /// Parameters other than `q`, names lowercased.
+  pub params: HashMap<String, String>,
+}
+
+enum Segment {
+  Key(String),
+  Index(usize),
+  Push,
+}
+
+/// Splits `a[b][0][]` into `a` and its bracket segments. Keys that are not
+/// well formed are taken literally.
+fn key_path(key: &str) -> (String, Vec<Segment>) {
+  let literal = || (key.to_string(), Vec::new());
+  let Some(open) = key.find('[').filter(|&i| i > 0) else {
+    return literal();
+  };
+  let mut segments = Vec::new();
+  let mut rest = &key[open..];
+  while let Some(inner) = rest.strip_prefix('[') {
+    let Some(close) = inner.find(']') else {
+      return literal();
+    };
+    if segments.len() == MAX_KEY_DEPTH {
+      segments.push(Segment::Key(rest.to_string()));
+      rest = "";
+      break;
+    }
+    let name = &inner[..close];
+    segments.push(if name.is_empty() {
+      Segment::Push
+    } else {
+      match name.parse::<usize>() {
+        Ok(i) if i <= MAX_ARRAY_INDEX => Segment::Index(i),
+        _ => Segment::Key(name.to_string()),
+      }
+    });
+    rest = &inner[close + 1..];
+  }
+  if !rest.is_empty() {
+    return literal();
+  }
+  (key[..open].to_string(), segments)
+}
+
+/// An object to index into, turning anything else at `slot` into one. An
+/// array keeps its elements under their indices, and a string stays as
+/// index `0`, the way it would as the sole element of an array.
+fn as_object(slot: &mut Value) -> &mut Map<String, Value> {
+  if !slot.is_object() {
+    let map = match slot.take() {
+      Value::Array(items) => items
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| (i.to_string(), v))
+        .collect(),
+      Value::String(s) => Map::from_iter([("0".to_string(), Value::String(s))]),
+      _ => Map::new(),
+    };
+    *slot = Value::Object(map);
+  }
+  slot.as_object_mut().expect("just made an object")
+}
+
+fn as_array(slot: &mut Value) -> &mut Vec<Value> {
+  if !slot.is_array() {
+    *slot = match slot.take() {
+      Value::String(s) => Value::Array(vec![Value::String(s)]),
+      _ => Value::Array(Vec::new()),
+    };
+  }
+  slot.as_array_mut().expect("just made an array")
+}
+
+fn assign(slot: &mut Value, segments: &[Segment], value: String) {
+  let Some((first, rest)) = segments.split_first() else {
+    // A repeated key collects its values into an array.
+    match slot {
+      Value::Null => *slot = Value::String(value),
+      Value::String(_) => as_array(slot).push(Value::String(value)),
+      Value::Array(items) => items.push(Value::String(value)),
+      _ => *slot = Value::String(value),
+    }
+    return;
+  };
+  match first {
+    Segment::Push => {
+      let items = as_array(slot);
+      items.push(Value::Null);
+      let last = items.last_mut().expect("just pushed");
+      assign(last, rest, value);
+    }
+    Segment::Index(i) if matches!(slot, Value::Null | Value::Array(_)) => {
+      let items = as_array(slot);
+      if *i < items.len() {
+        assign(&mut items[*i], rest, value);
+      } else if *i == items.len() {
+        items.push(Value::Null);
+        assign(&mut items[*i], rest, value);
+      } else {
+        let entry = as_object(slot).entry(i.to_string()).or_insert(Value::Null);
+        assign(entry, rest, value);
+      }
+    }
+    Segment::Index(i) => {
+      let entry = as_object(slot).entry(i.to_string()).or_insert(Value::Null);
+      assign(entry, rest, value);
+    }
+    Segment::Key(key) => {
+      let entry = as_object(slot).entry(key.clone()).or_insert(Value::Null);
+      assign(entry, rest, value);
+    }
+  }
+}
+
+/// Parses a query string (a leading `?` is fine) into an object, decoding
+/// `+` and percent-escapes.
+///
+/// Repeated keys and `a[]=` collect arrays, `a[b]=` builds nested
+/// objects and `a[0]=` indexes arrays, as the `qs` package does. When
+/// shapes conflict, brackets after plain values turn them into an object
+/// keeping those values under their indices (`a=1&a[x]=2` gives
+/// `{a: {0: '1', x: '2'}}`), while a plain value after brackets replaces
+/// whatever they built (`a[x]=2&a=1` gives `{a: '1'}`). Keys naming
+/// `__proto__` are dropped.
+#[napi(catch_unwind, ts_return_type = "Record<string, unknown>")]
+pub fn parse_querystring(
+  input: String,
+  options: Option<ParseQuerystringOptions>,
+) -> Map<String, Value> {
+  let max_keys = match options.unwrap_or_default().max_keys.unwrap_or(1000) {
+    0 => usize::MAX,
+    n => n as usize,
+  };
+  let input = input.strip_prefix('?').unwrap_or(&input);
+  let mut root = Value::Object(Map::new());
+  for (key, value) in form_urlencoded::parse(input.as_bytes()).take(max_keys) {
+    let (base, segments) = key_path(&key);
+    let polluting = base == "__proto__"
+      || segments
+        .iter()
+        .any(|s| matches!(s, Segment::Key(k) if k == "__proto__"));
+    if !polluting {
+      let entry = as_object(&mut root).entry(base).or_insert(Value::Null);
+      assign(entry, &segments, value.into_owned());
+    }
+  }
+  match root {
+    Value::Object(map) => map,
+    _ => unreachable!("root is always an object"),
+  }
+}
+
+#[derive(Clone, Copy)]
+enum ArrayFormat {
+  Repeat,
+  Brackets,
+  Indices,
+}
+
+fn serialize_value(
+  out: &mut form_urlencoded::Serializer<String>,
+  key: &str,
+  value: &Value,
+  format: ArrayFormat,
+) {
+  match value {
+    Value::Null => {
+      out.append_pair(key, "");
+    }
+    Value::Bool(b) => {
+      out.append_pair(key, if *b { "true" } else { "false" });
+    }
+    Value::Number(n) => {
+      out.append_pair(key, &n.to_string());
+    }
+    Value::String(s) => {
+      out.append_pair(key, s);
+    }
+    Value::Array(items) => {
+      for (i, item) in items.iter().enumerate() {
+        // Nested containers need indices to come back apart.
+        let nested = item.is_object() || item.is_array();
+        let key = match format {
+          ArrayFormat::Indices => format!("{}[{}]", key, i),
+          _ if nested => format!("{}[{}]", key, i),
+          ArrayFormat::Brackets => format!("{}[]", key),
+          ArrayFormat::Repeat => key.to_string(),
+        };
+        serialize_value(out, &key, item, format);
+      }
+    }
+    Value::Object(map) => {
+      for (name, item) in map {
+        serialize_value(out, &format!("{}[{}]", key, name), item, format);
+      }
+    }
+  }
+}
+
+/// Writes an object as a query string (without the `?`), nesting objects
+/// as `a[b]=` so `parseQuerystring` reads it back. `null` becomes an empty
+/// value.
+#[napi(
+  catch_unwind,
+  ts_args_type = "value: Record<string, unknown>, options?: SerializeQuerystringOptions"
+)]
+pub fn serialize_querystring(
+  env: Env,
+  value: JsUnknown,
+  options: Option<SerializeQuerystringOptions>,
+) -> Result<String> {
+  let value: Map<String, Value> = from_js(&env, value)?;
+  let format = match options.unwrap_or_default().array_format.as_deref() {
+    None | Some("repeat") => ArrayFormat::Repeat,
+    Some("brackets") => ArrayFormat::Brackets,
+    Some("indices") => ArrayFormat::Indices,
+    Some(other) => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown array format: {}",
+        other
+      )))
+    }
+  };
+  let mut out = form_urlencoded::Serializer::new(String::new());
+  for (key, item) in &value {
+    serialize_value(&mut out, key, item, format);
+  }
+  Ok(out.finish())
+}
+
+/// Parses a `Cookie` request header into name/value pairs. Values are
+/// unquoted and percent-decoded (left as sent when that fails); the first
+/// of several cookies with one name wins.
+#[napi(catch_unwind)]
+pub fn parse_cookies(header: String) -> HashMap<String, String> {
+  let mut cookies = HashMap::new();
+  for pair in header.split(';') {
+    let Some((name, value)) = pair.split_once('=') else {
+      continue;
+    };
+    let name = name.trim();
+    if name.is_empty() || cookies.contains_key(name) {
+      continue;
+    }
+    let value = value.trim();
+    let value = value
+      .strip_prefix('"')
+      .and_then(|v| v.strip_suffix('"'))
+      .unwrap_or(value);
+    let decoded = percent_decode_str(value)
+      .decode_utf8()
+      .map_or_else(|_| value.to_string(), |v| v.into_owned());
+    cookies.insert(name.to_string(), decoded);
+  }
+  cookies
+}
+
+/// An RFC 7230 token, as cookie names must be.
+fn is_token(s: &str) -> bool {
+  !s.is_empty()
+    && s
+      .bytes()
+      .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b))
+}
+
+fn check_attribute(name: &str, value: &str) -> Result<()> {
+  if value.bytes().any(|b| b == b';' || b.is_ascii_control()) {
+    return Err(AppError::InvalidInput(format!(
+      "cookie {} must not contain ';' or control characters",
+      name
+    )));
+  }
+  Ok(())
+}
+
+/// Builds a `Set-Cookie` header value. The value is percent-encoded as by
+/// `encodeURIComponent`; an invalid name or attribute, or `sameSite:
+/// 'none'` without `secure`, throws `ERR_INVALID_INPUT`.
+#[napi(catch_unwind)]
+pub fn serialize_cookie(
+  name: String,
+  value: String,
+  options: Option<CookieOptions>,
+) -> Result<String> {
+  if !is_token(&name) {
+    return Err(AppError::InvalidInput(format!(
+      "invalid cookie name: {:?}",
+      name
+    )));
+  }
+  let options = options.unwrap_or_default();
+  let mut out = format!("{}={}", name, utf8_percent_encode(&value, COMPONENT));
+  if let Some(max_age) = options.max_age {
+    out.push_str(&format!("; Max-Age={}", max_age));
+  }
+  if let Some(domain) = &options.domain {
+    check_attribute("domain", domain)?;
+    out.push_str(&format!("; Domain={}", domain));
+  }
+  if let Some(path) = &options.path {
+    check_attribute("path", path)?;
+    out.push_str(&format!("; Path={}", path));
+  }
+  if let Some(expires) = &options.expires {
+    let expires = date_to_utc(expires)?;
+    out.push_str(
+      &expires
+        .format("; Expires=%a, %d %b %Y %H:%M:%S GMT")
+        .to_string(),
+    );
+  }
+  if options.http_only.unwrap_or(false) {
+    out.push_str("; HttpOnly");
+  }
+  let secure = options.secure.unwrap_or(false);
+  if secure {
+    out.push_str("; Secure");
+  }
+  if let Some(same_site) = &options.same_site {
+    let same_site = match same_site.to_ascii_lowercase().as_str() {
+      "strict" => "Strict",
+      "lax" => "Lax",
+      "none" if secure => "None",
+      "none" => {
+        return Err(AppError::InvalidInput(
+          "sameSite 'none' requires secure".into(),
+        ))
+      }
+      other => {
+        return Err(AppError::InvalidInput(format!(
+          "unknown sameSite value: {}",
+          other
+        )))
+      }
+    };
+    out.push_str(&format!("; SameSite={}", same_site));
+  }
+  if options.partitioned.unwrap_or(false) {
+    out.push_str("; Partitioned");
+  }
+  Ok(out)
+}
+
+fn media_range(entry: &str) -> Option<MediaRange> {
+  let mut parts = entry.split(';');
+  let full = parts.next()?.trim().to_ascii_lowercase();
+  let (kind, subtype) = match full.as_str() {
+    // Some clients send a bare `*`.
+    "*" => ("*", "*"),
+    _ => full.split_once('/')?,
+  };
+  if kind.is_empty() || subtype.is_empty() || (kind == "*" && subtype != "*") {
+    return None;
+  }
+  let mut q = 1.0;
+  let mut params = HashMap::new();
+  for param in parts {
+    let Some((name, value)) = param.split_once('=') else {
+      continue;
+    };
+    let name = name.trim().to_ascii_lowercase();
+    let value = value.trim().trim_matches('"');
+    if name == "q" {
+      q = value
+        .parse::<f64>()
+        .ok()
+        .filter(|q| (0.0..=1.0).contains(q))?;
+      // Anything after q is an accept-extension, not a media type parameter.
+      break;
+    }
+    params.insert(name, value.to_string());
+  }
+  Some(MediaRange {
+    media_type: format!("{}/{}", kind, subtype),
+    kind: kind.to_string(),
+    subtype: subtype.to_string(),
+    q,
+    params,
+  })
+}
+
+/// How specific a range is: `*/*` < `text/*` < `text/html` < `text/html`
+/// with parameters.
+fn specificity(range: &MediaRange) -> (u8, usize) {
+  let level = match (range.kind.as_str(), range.subtype.as_str()) {
+    ("*", _) => 0,
+    (_, "*") => 1,
+    _ => 2,
+  };
+  (level, range.params.len())
+}
+
+/// Parses an `Accept` header into media ranges, most preferred first: by
+/// `q`, then by specificity, then in header order. Ranges with `q=0`
+/// (explicitly unacceptable) and malformed entries are left out.
+#[napi(catch_unwind)]
+pub fn parse_accept(header: String) -> Vec<MediaRange> {
+  let mut ranges: Vec<MediaRange> = header
+    .split(',')
+    .filter_map(media_range)
+    .filter(|range| range.q > 0.0)
+    .collect();
+  ranges.sort_by(|a, b| {
+    b.q
+      .partial_cmp(&a.q)
+      .unwrap_or(Ordering::Equal)
+      .then_with(|| specificity(b).cmp(&specificity(a)))
+  });
+  ranges
+}
@@ -0,0 +1,72 @@
+use crate::error::{AppError, Result};
+
+const DEFAULT_NANOID_SIZE: u32 = 21;
+
+/// Random (version 4) UUID in canonical hyphenated form.
+#[napi(catch_unwind)]
+pub fn uuid_v4() -> String {
+  uuid::Uuid::new_v4().to_string()
+}
+
+/// Time-ordered (version 7) UUID.
+#[napi(catch_unwind)]
+pub fn uuid_v7() -> String {
+  uuid::Uuid::now_v7().to_string()
+}
+
+/// Generates `n` v4 UUIDs in one call, avoiding per-call FFI overhead.
+#[napi(catch_unwind)]
+pub fn uuid_v4_batch(n: u32) -> Vec<String> {
+  (0..n).map(|_| uuid::Uuid::new_v4().to_string()).collect()
+}
+
+/// 26-character Crockford base32 ULID.
+#[napi(catch_unwind)]
+pub fn ulid() -> String {
+  ulid::Ulid::new().to_string()
+}
+
+/// URL-safe nanoid, 21 characters unless `size` is given.
+#[napi(catch_unwind)]
+pub fn nanoid(size: Option<u32>) -> Result<String> {
+  let size = size.unwrap_or(DEFAULT_NANOID_SIZE) as usize;
+  // nanoid's generator never returns for 0.
+  if size == 0 {
+    return Err(AppError::InvalidInput("size must be positive".into()));
+  }
+  Ok(nanoid::nanoid!(size))
+}
+
+/// Monotonic ULID generator: ULIDs created within the same millisecond are
+/// strictly increasing instead of being randomly ordered.
+#[napi]
+pub struct UlidGenerator {
+  inner: ulid::Generator,
+}
+
+#[napi]
+impl UlidGenerator {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    UlidGenerator {
+      inner: ulid::Generator::new(),
+    }
+  }
+
+  /// Returns the next ULID. Throws in the (astronomically unlikely) case that
+  /// the random component overflows within a single millisecond.
+  #[napi(catch_unwind)]
+  pub fn generate(&mut self) -> Result<String> {
+    self
+      .inner
+      .generate()
+      .map(|id| id.to_string())
+      .map_err(|e| AppError::Overflow(e.to_string()))
+  }
+}
+
+impl Default for UlidGenerator {
+  fn default() -> Self {
+    Self::new()
+  }
+}
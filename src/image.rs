@@ -0,0 +1,267 @@
+use std::io::Cursor;
+
+use ::image::imageops::FilterType;
+use ::image::{ColorType, DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader};
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+pub struct ImageInfo {
+  pub width: u32,
+  pub height: u32,
+  /// `png`, `jpeg` or `webp`.
+  pub format: String,
+  /// Pixel layout, e.g. `rgb8`, `rgba8`, `l16`.
+  pub color_type: String,
+  pub has_alpha: bool,
+}
+
+fn image_error(e: ImageError) -> AppError {
+  match e {
+    ImageError::Decoding(e) => AppError::Parse(format!("invalid image: {}", e)),
+    ImageError::Unsupported(e) => AppError::Unsupported(e.to_string()),
+    // Images are only ever read from memory, so an I/O error means the data
+    // ended early.
+    ImageError::IoError(e) => AppError::Parse(format!("invalid image: {}", e)),
+    e => AppError::InvalidInput(e.to_string()),
+  }
+}
+
+fn format_name(format: ImageFormat) -> &'static str {
+  match format {
+    ImageFormat::Png => "png",
+    ImageFormat::Jpeg => "jpeg",
+    ImageFormat::WebP => "webp",
+    _ => "unknown",
+  }
+}
+
+fn parse_format(format: &str) -> Result<ImageFormat> {
+  match format {
+    "png" => Ok(ImageFormat::Png),
+    "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+    "webp" => Ok(ImageFormat::WebP),
+    other => Err(AppError::Unsupported(format!(
+      "unsupported image format: {}",
+      other
+    ))),
+  }
+}
+
+fn parse_filter(filter: Option<&str>) -> Result<FilterType> {
+  match filter.unwrap_or("lanczos3") {
+    "nearest" => Ok(FilterType::Nearest),
+    "triangle" => Ok(FilterType::Triangle),
+    "catmullrom" => Ok(FilterType::CatmullRom),
+    "gaussian" => Ok(FilterType::Gaussian),
+    "lanczos3" => Ok(FilterType::Lanczos3),
+    other => Err(AppError::InvalidInput(format!(
+      "unknown resize filter: {}",
+      other
+    ))),
+  }
+}
+
+fn reader(input: &[u8]) -> Result<ImageReader<Cursor<&[u8]>>> {
+  let reader = ImageReader::new(Cursor::new(input)).with_guessed_format()?;
+  if reader.format().is_none() {
+    return Err(AppError::Unsupported("unrecognized image format".into()));
+  }
+  Ok(reader)
+}
+
+pub(crate) fn decode(input: &[u8]) -> Result<(DynamicImage, ImageFormat)> {
+  let reader = reader(input)?;
+  let format = reader.format().expect("checked by reader()");
+  Ok((reader.decode().map_err(image_error)?, format))
+}
+
+fn encode(image: DynamicImage, format: ImageFormat) -> Result<Vec<u8>> {
+  // The JPEG encoder has no alpha channel and the WebP encoder only takes
+  // 8-bit RGB(A), so normalize the pixel layout first.
+  let image = match format {
+    ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.into_rgb8()),
+    ImageFormat::WebP if image.color().has_alpha() => DynamicImage::ImageRgba8(image.into_rgba8()),
+    ImageFormat::WebP => DynamicImage::ImageRgb8(image.into_rgb8()),
+    _ => image,
+  };
+  let mut out = Cursor::new(Vec::new());
+  image.write_to(&mut out, format).map_err(image_error)?;
+  Ok(out.into_inner())
+}
+
+enum ImageOp {
+  Resize {
+    width: u32,
+    height: u32,
+    filter: FilterType,
+  },
+  Crop {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+  },
+  Convert(ImageFormat),
+}
+
+impl ImageOp {
+  fn run(&self, input: &[u8]) -> Result<Vec<u8>> {
+    let (image, format) = decode(input)?;
+    match *self {
+      ImageOp::Resize {
+        width,
+        height,
+        filter,
+      } => encode(image.resize_exact(width, height, filter), format),
+      ImageOp::Crop {
+        x,
+        y,
+        width,
+        height,
+      } => {
+        let fits =
+          |offset: u32, len: u32, max: u32| offset.checked_add(len).is_some_and(|end| end <= max);
+        if !fits(x, width, image.width()) || !fits(y, height, image.height()) {
+          return Err(AppError::InvalidInput(format!(
+            "crop {}x{}+{}+{} is outside the {}x{} image",
+            width,
+            height,
+            x,
+            y,
+            image.width(),
+            image.height()
+          )));
+        }
+        encode(image.crop_imm(x, y, width, height), format)
+      }
+      ImageOp::Convert(target) => encode(image, target),
+    }
+  }
+}
+
+/// Decodes, transforms and re-encodes an image on the libuv thread pool.
+pub struct ImageTask {
+  input: Buffer,
+  op: ImageOp,
+}
+
+impl Task for ImageTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.op.run(&self.input))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+fn non_zero(width: u32, height: u32) -> Result<()> {
+  if width == 0 || height == 0 {
+    return Err(AppError::InvalidInput(
+      "width and height must be non-zero".into(),
+    ));
+  }
+  Ok(())
+}
+
+/// Resizes to exactly `width`×`height`, keeping the input's format. `filter`
+/// is `nearest`, `triangle`, `catmullrom`, `gaussian` or `lanczos3`
+/// (default).
+#[napi(
+  catch_unwind,
+  ts_args_type = "input: Buffer, width: number, height: number, filter?: 'nearest' | 'triangle' | 'catmullrom' | 'gaussian' | 'lanczos3'",
+  ts_return_type = "Promise<Buffer>"
+)]
+pub fn resize_image(
+  input: Buffer,
+  width: u32,
+  height: u32,
+  filter: Option<String>,
+) -> Result<AsyncTask<Guarded<ImageTask>>> {
+  non_zero(width, height)?;
+  let filter = parse_filter(filter.as_deref())?;
+  Ok(panic_guard::task(ImageTask {
+    input,
+    op: ImageOp::Resize {
+      width,
+      height,
+      filter,
+    },
+  }))
+}
+
+/// Cuts out the `width`×`height` region whose top-left corner is at `x`,`y`.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn crop_image(
+  input: Buffer,
+  x: u32,
+  y: u32,
+  width: u32,
+  height: u32,
+) -> Result<AsyncTask<Guarded<ImageTask>>> {
+  non_zero(width, height)?;
+  Ok(panic_guard::task(ImageTask {
+    input,
+    op: ImageOp::Crop {
+      x,
+      y,
+      width,
+      height,
+    },
+  }))
+}
+
+/// Re-encodes an image as `png`, `jpeg` or (lossless) `webp`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "input: Buffer, format: 'png' | 'jpeg' | 'webp'",
+  ts_return_type = "Promise<Buffer>"
+)]
+pub fn convert_format(input: Buffer, format: String) -> Result<AsyncTask<Guarded<ImageTask>>> {
+  let format = parse_format(&format)?;
+  Ok(panic_guard::task(ImageTask {
+    input,
+    op: ImageOp::Convert(format),
+  }))
+}
+
+/// Reads dimensions and pixel layout from the image header without decoding
+/// the pixels.
+#[napi(catch_unwind)]
+pub fn image_metadata(input: Buffer) -> Result<ImageInfo> {
+  let reader = reader(&input)?;
+  let format = reader.format().expect("checked by reader()");
+  let decoder = reader.into_decoder().map_err(image_error)?;
+  let (width, height) = decoder.dimensions();
+  let color = decoder.color_type();
+  Ok(ImageInfo {
+    width,
+    height,
+    format: format_name(format).to_string(),
+    color_type: color_name(color).to_string(),
+    has_alpha: color.has_alpha(),
+  })
+}
+
+fn color_name(color: ColorType) -> &'static str {
+  match color {
+    ColorType::L8 => "l8",
+    ColorType::La8 => "la8",
+    ColorType::Rgb8 => "rgb8",
+    ColorType::Rgba8 => "rgba8",
+    ColorType::L16 => "l16",
+    ColorType::La16 => "la16",
+    ColorType::Rgb16 => "rgb16",
+    ColorType::Rgba16 => "rgba16",
+    ColorType::Rgb32F => "rgb32f",
+    ColorType::Rgba32F => "rgba32f",
+    _ => "unknown",
+  }
+}
@@ -0,0 +1,121 @@
+use std::sync::OnceLock;
+
+use napi::Env;
+
+use crate::error::Result;
+
+#[napi(object)]
+pub struct NativeInfo {
+  /// The crate version the addon was built from.
+  pub version: String,
+  /// The commit the addon was built from, or null when it wasn't built
+  /// from a git checkout.
+  pub git_commit: Option<String>,
+  /// The Rust target triple, e.g. `x86_64-unknown-linux-gnu`.
+  pub target: String,
+  #[napi(ts_type = "'debug' | 'release'")]
+  pub profile: String,
+  /// `rustc --version` of the compiler that built it.
+  pub rustc: String,
+  /// The Node-API version the running Node provides.
+  pub napi_version: u32,
+  /// Cargo features the crate was compiled with.
+  pub features: Vec<String>,
+  /// SIMD and crypto extensions this CPU supports, detected at runtime,
+  /// e.g. `avx2` or `neon`. Code compiled for them only takes that path
+  /// when they're listed.
+  pub simd: Vec<String>,
+  /// Sorted.
+  pub functions: Vec<String>,
+  /// Sorted.
+  pub classes: Vec<String>,
+}
+
+#[cfg_attr(test, allow(dead_code))]
+struct Exports {
+  functions: Vec<String>,
+  classes: Vec<String>,
+}
+
+static EXPORTS: OnceLock<Exports> = OnceLock::new();
+
+/// Records what the addon exports once napi has registered everything.
+/// napi names classes in PascalCase and functions in camelCase, which is
+/// what tells them apart: both are JS functions. Like napi's own
+/// registration, it's left out of test builds, which don't link Node.
+#[cfg(not(test))]
+#[module_exports]
+fn record_exports(exports: napi::JsObject) -> napi::Result<()> {
+  use napi::{JsString, JsUnknown, ValueType};
+
+  if EXPORTS.get().is_some() {
+    return Ok(());
+  }
+  let names = exports.get_property_names()?;
+  let mut functions = Vec::new();
+  let mut classes = Vec::new();
+  for i in 0..names.get_array_length()? {
+    let name = names
+      .get_element::<JsString>(i)?
+      .into_utf8()?
+      .into_owned()?;
+    let value: JsUnknown = exports.get_named_property(&name)?;
+    if value.get_type()? != ValueType::Function {
+      continue;
+    }
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+      classes.push(name);
+    } else {
+      functions.push(name);
+    }
+  }
+  functions.sort();
+  classes.sort();
+  let _ = EXPORTS.set(Exports { functions, classes });
+  Ok(())
+}
+
+#[allow(unused_mut)]
+fn simd() -> Vec<String> {
+  let mut found: Vec<&str> = Vec::new();
+  macro_rules! detect {
+    ($detected:ident: $($feature:tt),*) => {
+      $(if std::arch::$detected!($feature) {
+        found.push($feature);
+      })*
+    };
+  }
+  #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+  detect!(is_x86_feature_detected: "sse2", "sse3", "ssse3", "sse4.1", "sse4.2", "popcnt", "avx",
+    "avx2", "fma", "bmi1", "bmi2", "avx512f", "avx512bw", "avx512vl", "aes", "pclmulqdq", "sha");
+  #[cfg(target_arch = "aarch64")]
+  detect!(is_aarch64_feature_detected: "neon", "aes", "pmull", "sha2", "sha3", "crc", "dotprod",
+    "sve", "sve2");
+  found.into_iter().map(String::from).collect()
+}
+
+/// Describes the native build that was loaded, to log at startup or to
+/// check before relying on an optional capability, e.g. whether
+/// `functions` includes one added in a later version.
+#[napi(catch_unwind)]
+pub fn native_info(env: Env) -> Result<NativeInfo> {
+  let exports = EXPORTS.get();
+  Ok(NativeInfo {
+    version: env!("CARGO_PKG_VERSION").into(),
+    git_commit: Some(env!("BUILD_GIT_COMMIT"))
+      .filter(|commit| !commit.is_empty())
+      .map(String::from),
+    target: env!("BUILD_TARGET").into(),
+    profile: env!("BUILD_PROFILE").into(),
+    rustc: env!("BUILD_RUSTC").into(),
+    napi_version: env.get_napi_version()?,
+    features: env!("BUILD_FEATURES")
+      .split(',')
+      .filter(|feature| !feature.is_empty())
+      .map(String::from)
+      .collect(),
+    simd: simd(),
+    functions: exports.map_or_else(Vec::new, |exports| exports.functions.clone()),
+    classes: exports.map_or_else(Vec::new, |exports| exports.classes.clone()),
+  })
+}
@@ -0,0 +1,333 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::IpNet;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use napi::bindgen_prelude::BigInt;
+
+use crate::error::{AppError, Result};
+
+/// An IPv4-mapped IPv6 address (`::ffff:1.2.3.4`) is read as the IPv4
+/// address it carries, so it matches IPv4 ranges.
+fn parse_ip(input: &str) -> Result<IpAddr> {
+  input
+    .trim()
+    .parse::<IpAddr>()
+    .map(|ip| ip.to_canonical())
+    .map_err(|_| AppError::Parse(format!("invalid IP address: {:?}", input)))
+}
+
+/// A bare address is its own /32 or /128; host bits past the prefix are
+/// cleared.
+fn parse_net(input: &str) -> Result<IpNet> {
+  let trimmed = input.trim();
+  let net = match trimmed.parse::<IpNet>() {
+    Ok(net) => net,
+    Err(_) => IpNet::from(
+      trimmed
+        .parse::<IpAddr>()
+        .map_err(|_| AppError::Parse(format!("invalid CIDR: {:?}", input)))?,
+    ),
+  };
+  Ok(net.trunc())
+}
+
+fn to_int(ip: IpAddr) -> u128 {
+  match ip {
+    IpAddr::V4(ip) => u32::from(ip) as u128,
+    IpAddr::V6(ip) => u128::from(ip),
+  }
+}
+
+fn bounds(net: &IpNet) -> (u128, u128) {
+  (to_int(net.network()), to_int(net.broadcast()))
+}
+
+#[napi(object)]
+pub struct Cidr {
+  /// Normalized, e.g. `10.0.0.0/8` for `10.1.2.3/8`.
+  pub cidr: String,
+  /// The first address of the range.
+  pub network: String,
+  /// The last address of the range; the broadcast address for IPv4.
+  pub last: String,
+  pub prefix_length: u32,
+  #[napi(ts_type = "4 | 6")]
+  pub version: u32,
+  /// How many addresses the range holds.
+  pub size: BigInt,
+}
+
+/// Parses `a.b.c.d/n` or an IPv6 CIDR. A bare address is a single-address
+/// range, and host bits past the prefix are cleared. Throws `ERR_PARSE`.
+#[napi(catch_unwind)]
+pub fn parse_cidr(cidr: String) -> Result<Cidr> {
+  let net = parse_net(&cidr)?;
+  let (first, last) = bounds(&net);
+  // A /0 holds one more address than fits in the address width.
+  let size = match (last - first).checked_add(1) {
+    Some(size) => BigInt::from(size),
+    None => BigInt {
+      sign_bit: false,
+      words: vec![0, 0, 1],
+    },
+  };
+  Ok(Cidr {
+    cidr: net.to_string(),
+    network: net.network().to_string(),
+    last: net.broadcast().to_string(),
+    prefix_length: net.prefix_len() as u32,
+    version: if net.network().is_ipv4() { 4 } else { 6 },
+    size,
+  })
+}
+
+/// Whether `ip` falls inside `cidr`. An IPv4 address is never inside an
+/// IPv6 range or the other way round, except for IPv4-mapped addresses.
+#[napi(catch_unwind)]
+pub fn ip_in_cidr(ip: String, cidr: String) -> Result<bool> {
+  Ok(parse_net(&cidr)?.contains(&parse_ip(&ip)?))
+}
+
+/// The address as an unsigned integer: 0 to 2^32 − 1 for IPv4, 0 to
+/// 2^128 − 1 for IPv6.
+#[napi(catch_unwind)]
+pub fn ip_to_int(ip: String) -> Result<BigInt> {
+  Ok(BigInt::from(to_int(parse_ip(&ip)?)))
+}
+
+/// The address with the numeric value `value`. `version` defaults to 4 for
+/// values that fit in 32 bits and 6 otherwise.
+#[napi(catch_unwind, ts_args_type = "value: bigint | number, version?: 4 | 6")]
+pub fn int_to_ip(value: napi::Either<BigInt, f64>, version: Option<u32>) -> Result<String> {
+  let value = match value {
+    napi::Either::A(value) => match value.get_u128() {
+      (false, value, true) => value,
+      _ => {
+        return Err(AppError::InvalidInput(
+          "IP address integer must be between 0 and 2^128 - 1".into(),
+        ))
+      }
+    },
+    napi::Either::B(value) if value >= 0.0 && value.fract() == 0.0 && value <= u32::MAX as f64 => {
+      value as u128
+    }
+    napi::Either::B(value) => {
+      return Err(AppError::InvalidInput(format!(
+        "IP address number must be an integer between 0 and 2^32 - 1, got {}",
+        value
+      )))
+    }
+  };
+  let version = version.unwrap_or(if value <= u32::MAX as u128 { 4 } else { 6 });
+  match version {
+    4 => u32::try_from(value)
+      .map(|v| Ipv4Addr::from(v).to_string())
+      .map_err(|_| AppError::InvalidInput(format!("{} does not fit in an IPv4 address", value))),
+    6 => Ok(Ipv6Addr::from(value).to_string()),
+    other => Err(AppError::InvalidInput(format!(
+      "IP version must be 4 or 6, got {}",
+      other
+    ))),
+  }
+}
+
+/// Sorted, disjoint, non-adjacent inclusive ranges.
+struct Ranges(Vec<(u128, u128)>);
+
+impl Ranges {
+  fn from_unsorted(mut ranges: Vec<(u128, u128)>) -> Self {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u128, u128)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+      match merged.last_mut() {
+        Some(last) if last.1.saturating_add(1) >= start => last.1 = last.1.max(end),
+        _ => merged.push((start, end)),
+      }
+    }
+    Ranges(merged)
+  }
+
+  fn insert(&mut self, start: u128, end: u128) {
+    let ranges = &mut self.0;
+    // Everything in i..j overlaps or touches the new range.
+    let i = ranges.partition_point(|r| r.1.saturating_add(1) < start);
+    let j = ranges.partition_point(|r| r.0 <= end.saturating_add(1));
+    if i < j {
+      let merged = (start.min(ranges[i].0), end.max(ranges[j - 1].1));
+      ranges.drain(i..j);
+      ranges.insert(i, merged);
+    } else {
+      ranges.insert(i, (start, end));
+    }
+  }
+
+  fn contains(&self, value: u128) -> bool {
+    let i = self.0.partition_point(|r| r.1 < value);
+    i < self.0.len() && self.0[i].0 <= value
+  }
+}
+
+/// A set of IPv4 and IPv6 ranges answering membership in O(log n).
+///
+/// Overlapping and adjacent CIDRs are merged as they are added, so
+/// thousands of entries (a cloud provider's published ranges, a blocklist)
+/// cost one binary search per lookup.
+#[napi]
+pub struct IpSet {
+  v4: Ranges,
+  v6: Ranges,
+}
+
+impl IpSet {
+  fn family(&mut self, net: &IpNet) -> &mut Ranges {
+    match net {
+      IpNet::V4(_) => &mut self.v4,
+      IpNet::V6(_) => &mut self.v6,
+    }
+  }
+}
+
+#[napi]
+impl IpSet {
+  /// Starts with `cidrs`, if given; any invalid entry throws `ERR_PARSE`.
+  #[napi(catch_unwind, constructor)]
+  pub fn new(cidrs: Option<Vec<String>>) -> Result<Self> {
+    let (mut v4, mut v6) = (Vec::new(), Vec::new());
+    for cidr in cidrs.unwrap_or_default() {
+      let net = parse_net(&cidr)?;
+      match net {
+        IpNet::V4(_) => v4.push(bounds(&net)),
+        IpNet::V6(_) => v6.push(bounds(&net)),
+      }
+    }
+    Ok(IpSet {
+      v4: Ranges::from_unsorted(v4),
+      v6: Ranges::from_unsorted(v6),
+    })
+  }
+
+  /// Adds a CIDR or single address.
+  #[napi(catch_unwind)]
+  pub fn add(&mut self, cidr: String) -> Result<()> {
+    let net = parse_net(&cidr)?;
+    let (start, end) = bounds(&net);
+    self.family(&net).insert(start, end);
+    Ok(())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn has(&self, ip: String) -> Result<bool> {
+    let ip = parse_ip(&ip)?;
+    let ranges = if ip.is_ipv4() { &self.v4 } else { &self.v6 };
+    Ok(ranges.contains(to_int(ip)))
+  }
+
+  /// The number of disjoint ranges left after merging.
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    (self.v4.0.len() + self.v6.0.len()) as u32
+  }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct GeoIpOptions {
+  /// Language for place names, as listed in the database. Defaults to `en`.
+  pub language: Option<String>,
+}
+
+#[napi(object)]
+pub struct GeoIpRecord {
+  /// ISO 3166-1 alpha-2 code, e.g. `DE`.
+  pub country_code: Option<String>,
+  pub country_name: Option<String>,
+  /// `AF`, `AN`, `AS`, `EU`, `NA`, `OC` or `SA`.
+  pub continent_code: Option<String>,
+  pub city: Option<String>,
+  /// The largest subdivision (state, region) as its ISO 3166-2 code.
+  pub region: Option<String>,
+  pub postal_code: Option<String>,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+  /// IANA time zone, e.g. `Europe/Berlin`.
+  pub time_zone: Option<String>,
+  /// The database network the address matched, e.g. `81.2.69.0/24`.
+  pub network: String,
+}
+
+fn geoip_error(e: MaxMindDBError) -> AppError {
+  match e {
+    MaxMindDBError::IoError(message) => AppError::Io(message),
+    other => AppError::Parse(format!("invalid GeoIP database: {}", other)),
+  }
+}
+
+/// A MaxMind DB (`.mmdb`) file such as GeoLite2-City or GeoLite2-Country,
+/// read into memory once.
+///
+/// Fields the database does not have are absent: a country database has no
+/// `city`, `latitude` or `timeZone`.
+#[napi]
+pub struct GeoIpReader {
+  reader: Reader<Vec<u8>>,
+  language: String,
+}
+
+#[napi]
+impl GeoIpReader {
+  /// Reads the database at `path`. A missing file throws `ERR_NOT_FOUND`; a
+  /// file that is not a MaxMind DB throws `ERR_PARSE`.
+  #[napi(catch_unwind, factory)]
+  pub fn open(path: String, options: Option<GeoIpOptions>) -> Result<Self> {
+    let bytes = std::fs::read(&path).map_err(|e| AppError::io(&path, e))?;
+    let reader = Reader::from_source(bytes).map_err(geoip_error)?;
+    Ok(GeoIpReader {
+      reader,
+      language: options
+        .unwrap_or_default()
+        .language
+        .unwrap_or_else(|| "en".into()),
+    })
+  }
+
+  /// The database's own name for its contents, e.g. `GeoLite2-City`.
+  #[napi(catch_unwind, getter)]
+  pub fn database_type(&self) -> String {
+    self.reader.metadata.database_type.clone()
+  }
+
+  /// What the database knows about `ip`, or `null` when it has no entry.
+  #[napi(catch_unwind)]
+  pub fn lookup(&self, ip: String) -> Result<Option<GeoIpRecord>> {
+    let ip = parse_ip(&ip)?;
+    let (city, prefix) = match self.reader.lookup_prefix::<geoip2::City>(ip) {
+      Ok(found) => found,
+      Err(MaxMindDBError::AddressNotFoundError(_)) => return Ok(None),
+      Err(e) => return Err(geoip_error(e)),
+    };
+    let network = IpNet::new(ip, prefix as u8)
+      .map(|net| net.trunc().to_string())
+      .map_err(|e| AppError::Parse(format!("invalid GeoIP database: {}", e)))?;
+    let name = |names: Option<std::collections::BTreeMap<&str, &str>>| {
+      names.and_then(|names| names.get(self.language.as_str()).map(|s| s.to_string()))
+    };
+    let country = city.country;
+    let location = city.location;
+    Ok(Some(GeoIpRecord {
+      country_code: country.as_ref().and_then(|c| c.iso_code).map(String::from),
+      country_name: country.and_then(|c| name(c.names)),
+      continent_code: city.continent.and_then(|c| c.code).map(String::from),
+      city: city.city.and_then(|c| name(c.names)),
+      region: city
+        .subdivisions
+        .and_then(|s| s.into_iter().next())
+        .and_then(|s| s.iso_code)
+        .map(String::from),
+      postal_code: city.postal.and_then(|p| p.code).map(String::from),
+      latitude: location.as_ref().and_then(|l| l.latitude),
+      longitude: location.as_ref().and_then(|l| l.longitude),
+      time_zone: location.and_then(|l| l.time_zone).map(String::from),
+      network,
+    }))
+  }
+}
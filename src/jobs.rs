@@ -0,0 +1,625 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{CallContext, Env, JsFunction, JsNumber, JsObject, JsUnknown, NapiValue, ValueType};
+use serde_json::Value;
+use tokio::sync::{oneshot, Notify};
+use tokio::task::AbortHandle;
+
+use crate::error::{self, AppError, Result};
+use crate::json::from_js;
+use crate::lifecycle::{self, Resource};
+
+const MAX_CONCURRENCY: u32 = 1024;
+const DEFAULT_BACKOFF_MS: u32 = 1000;
+
+#[napi(object)]
+#[derive(Clone, Copy)]
+pub struct JobInfo {
+  pub id: u32,
+  /// Counts from 1; retries increase it.
+  pub attempt: u32,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct JobOptions {
+  /// Higher priorities run first; equal ones in the order pushed.
+  /// Defaults to 0.
+  pub priority: Option<i32>,
+  /// Milliseconds to wait before the job becomes ready to run.
+  pub delay_ms: Option<u32>,
+  /// Further attempts after the handler fails. Defaults to 0.
+  pub retries: Option<u32>,
+  /// Milliseconds before the first retry, doubling for each one after.
+  /// Defaults to 1000.
+  pub backoff_ms: Option<u32>,
+  /// Called with the handler's result once the job succeeds.
+  #[napi(ts_type = "(result: unknown, job: JobInfo) => void")]
+  pub on_complete: Option<JsFunction>,
+  /// Called with the last error once the job has failed every attempt.
+  #[napi(ts_type = "(error: Error, job: JobInfo) => void")]
+  pub on_fail: Option<JsFunction>,
+}
+
+/// Calls the handler with the job and its payload.
+type Worker = ThreadsafeFunction<(JobInfo, Value), ErrorStrategy::Fatal>;
+type Completion = ThreadsafeFunction<(Value, JobInfo), ErrorStrategy::Fatal>;
+type Failure = ThreadsafeFunction<(String, JobInfo), ErrorStrategy::Fatal>;
+/// Gets onto the JS thread to stop the worker holding the process open.
+type Wake = ThreadsafeFunction<(), ErrorStrategy::Fatal>;
+
+/// The handler's result, or why it failed.
+type Outcome = std::result::Result<Value, String>;
+
+struct Job {
+  info: JobInfo,
+  /// Push order, to keep equal priorities first in, first out.
+  seq: u64,
+  priority: i32,
+  payload: Value,
+  retries: u32,
+  backoff_ms: u32,
+  on_complete: Option<Completion>,
+  on_fail: Option<Failure>,
+}
+
+impl PartialEq for Job {
+  fn eq(&self, other: &Self) -> bool {
+    self.seq == other.seq
+  }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Job {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .priority
+      .cmp(&other.priority)
+      .then_with(|| other.seq.cmp(&self.seq))
+  }
+}
+
+struct Inner {
+  runtime: Option<tokio::runtime::Handle>,
+  /// Dropping it ends the runtime thread.
+  stop: Option<oneshot::Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+  next_id: u32,
+  next_seq: u64,
+  ready: BinaryHeap<Job>,
+  /// Timers of jobs waiting out a delay or a retry backoff.
+  waiting: HashMap<u32, AbortHandle>,
+  /// Where to report the outcome of each job the handler is working on.
+  running: HashMap<u32, oneshot::Sender<Outcome>>,
+  /// Jobs taken off `ready` and not yet finished.
+  active: u32,
+  concurrency: u32,
+  paused: bool,
+  worker: Option<Worker>,
+  wake: Option<Wake>,
+}
+
+impl Inner {
+  fn busy(&self) -> bool {
+    !self.ready.is_empty() || !self.waiting.is_empty() || self.active > 0
+  }
+}
+
+/// Shared with the runtime thread, the handler's callbacks and the
+/// lifecycle registry.
+struct QueueState {
+  inner: Mutex<Inner>,
+  /// Notified whenever the queue runs out of work.
+  idle: Notify,
+}
+
+impl QueueState {
+  fn lock(&self) -> MutexGuard<'_, Inner> {
+    self.inner.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn stop(&self) {
+    let thread = {
+      let mut inner = self.lock();
+      for (_, timer) in inner.waiting.drain() {
+        timer.abort();
+      }
+      inner.ready.clear();
+      inner.running.clear();
+      inner.active = 0;
+      inner.worker = None;
+      inner.wake = None;
+      inner.runtime = None;
+      inner.stop = None;
+      inner.thread.take()
+    };
+    if let Some(thread) = thread {
+      let _ = thread.join();
+    }
+    self.idle.notify_waiters();
+  }
+
+  /// Keeps the process alive exactly while there is work the handler will
+  /// get to. Must run on the JS thread.
+  fn hold_open(&self, env: &Env) -> Result<()> {
+    let mut inner = self.lock();
+    let wanted = !inner.paused && inner.busy();
+    if let Some(worker) = inner.worker.as_mut() {
+      if wanted {
+        worker.refer(env)?;
+      } else {
+        worker.unref(env)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Hands ready jobs to the handler up to the concurrency limit.
+  fn pump(self: &Arc<Self>) {
+    let mut inner = self.lock();
+    let Some(runtime) = inner.runtime.clone() else {
+      return;
+    };
+    while !inner.paused && inner.worker.is_some() && inner.active < inner.concurrency {
+      let Some(job) = inner.ready.pop() else {
+        break;
+      };
+      inner.active += 1;
+      runtime.spawn(run(self.clone(), job));
+    }
+  }
+
+  /// Moves `job` to `ready` after `wait`. The caller holds the lock until
+  /// the timer is recorded, so a timer that fires at once still finds it.
+  fn delay(self: &Arc<Self>, inner: &mut Inner, job: Job, wait: Duration) {
+    let Some(runtime) = inner.runtime.clone() else {
+      return;
+    };
+    let id = job.info.id;
+    let state: Weak<QueueState> = Arc::downgrade(self);
+    let timer = runtime.spawn(async move {
+      tokio::time::sleep(wait).await;
+      if let Some(state) = state.upgrade() {
+        {
+          let mut inner = state.lock();
+          inner.waiting.remove(&id);
+          inner.ready.push(job);
+        }
+        state.pump();
+      }
+    });
+    inner.waiting.insert(id, timer.abort_handle());
+  }
+
+  fn report(&self, id: u32, outcome: Outcome) {
+    if let Some(done) = self.lock().running.remove(&id) {
+      let _ = done.send(outcome);
+    }
+  }
+}
+
+impl Resource for QueueState {
+  fn kind(&self) -> &'static str {
+    "job queue"
+  }
+
+  fn active(&self) -> bool {
+    let inner = self.lock();
+    inner.worker.is_some() && inner.busy()
+  }
+
+  fn shutdown(&self) {
+    self.stop();
+  }
+}
+
+/// One attempt at `job`, then its retry or callbacks.
+async fn run(state: Arc<QueueState>, mut job: Job) {
+  let (done_tx, done_rx) = oneshot::channel();
+  {
+    let mut inner = state.lock();
+    inner.running.insert(job.info.id, done_tx);
+    let Some(worker) = &inner.worker else {
+      return;
+    };
+    worker.call(
+      (job.info, job.payload.clone()),
+      ThreadsafeFunctionCallMode::NonBlocking,
+    );
+  }
+  // The sender is dropped when the queue shuts down.
+  let Ok(outcome) = done_rx.await else {
+    return;
+  };
+  let mut inner = state.lock();
+  match outcome {
+    Ok(result) => {
+      if let Some(on_complete) = &job.on_complete {
+        on_complete.call((result, job.info), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    }
+    Err(_) if job.info.attempt <= job.retries => {
+      let backoff = u64::from(job.backoff_ms) << (job.info.attempt - 1).min(20);
+      job.info.attempt += 1;
+      state.delay(&mut inner, job, Duration::from_millis(backoff));
+    }
+    Err(message) => {
+      if let Some(on_fail) = &job.on_fail {
+        on_fail.call((message, job.info), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+    }
+  }
+  inner.active -= 1;
+  drop(inner);
+  state.pump();
+  let inner = state.lock();
+  if !inner.busy() {
+    if let Some(wake) = &inner.wake {
+      wake.call((), ThreadsafeFunctionCallMode::NonBlocking);
+    }
+    drop(inner);
+    state.idle.notify_waiters();
+  }
+}
+
+fn to_unknown<T: ToNapiValue>(env: &Env, value: T) -> napi::Result<JsUnknown> {
+  unsafe {
+    Ok(JsUnknown::from_raw_unchecked(
+      env.raw(),
+      T::to_napi_value(env.raw(), value)?,
+    ))
+  }
+}
+
+/// What a rejection says, preferring an `Error`'s `message`.
+fn error_message(error: JsUnknown) -> napi::Result<String> {
+  if error.get_type()? == ValueType::Object {
+    let message: JsUnknown = error.coerce_to_object()?.get_named_property("message")?;
+    if message.get_type()? == ValueType::String {
+      return message.coerce_to_string()?.into_utf8()?.into_owned();
+    }
+    return Ok("job failed".into());
+  }
+  error.coerce_to_string()?.into_utf8()?.into_owned()
+}
+
+/// Calls `handler(payload, job)` and reports how it settles. The call is
+/// made from a `then` callback, which turns a throw into a rejection, so
+/// both outcomes arrive as callback arguments and no JS exception has to
+/// cross into Rust.
+fn start_handler(env: &Env, state: &Weak<QueueState>, ctx: &CallContext) -> napi::Result<()> {
+  let handler = ctx.get::<JsFunction>(0)?.coerce_to_object()?;
+  let id = ctx.get::<JsNumber>(1)?.get_uint32()?;
+  let attempt = ctx.get::<JsNumber>(2)?.get_uint32()?;
+  let payload = ctx.get::<JsUnknown>(3)?;
+
+  let promise = env
+    .get_global()?
+    .get_named_property::<JsFunction>("Promise")?
+    .coerce_to_object()?;
+  let resolve: JsFunction = promise.get_named_property("resolve")?;
+  let started = resolve
+    .call_without_args(Some(&promise))?
+    .coerce_to_object()?;
+  let bind: JsFunction = handler.get_named_property("bind")?;
+  let attempt_job = bind.call(
+    Some(&handler),
+    &[
+      env.get_undefined()?.into_unknown(),
+      payload,
+      to_unknown(env, JobInfo { id, attempt })?,
+    ],
+  )?;
+  let then: JsFunction = started.get_named_property("then")?;
+  let settled = then
+    .call(Some(&started), &[attempt_job])?
+    .coerce_to_object()?;
+
+  let on_result = state.clone();
+  let fulfilled = env.create_function_from_closure("fulfilled", move |ctx| {
+    let result = ctx
+      .get::<JsUnknown>(0)
+      .and_then(|value| ctx.env.from_js_value(value));
+    let outcome = result.map_err(|e| format!("job result is not JSON-serializable: {}", e));
+    if let Some(state) = on_result.upgrade() {
+      state.report(id, outcome);
+    }
+    ctx.env.get_undefined()
+  })?;
+  let on_error = state.clone();
+  let rejected = env.create_function_from_closure("rejected", move |ctx| {
+    let message = ctx
+      .get::<JsUnknown>(0)
+      .and_then(error_message)
+      .unwrap_or_else(|_| "job failed".into());
+    if let Some(state) = on_error.upgrade() {
+      state.report(id, Err(message));
+    }
+    ctx.env.get_undefined()
+  })?;
+  let then: JsFunction = settled.get_named_property("then")?;
+  then.call(Some(&settled), &[fulfilled, rejected])?;
+  Ok(())
+}
+
+fn worker(env: &Env, state: &Arc<QueueState>, handler: JsFunction) -> Result<Worker> {
+  let weak = Arc::downgrade(state);
+  let runner = env.create_function_from_closure("runJob", move |ctx| {
+    if let Err(e) = start_handler(ctx.env, &weak, &ctx) {
+      if let (Some(state), Ok(id)) = (
+        weak.upgrade(),
+        ctx.get::<JsNumber>(1).and_then(|id| id.get_uint32()),
+      ) {
+        state.report(id, Err(e.reason));
+      }
+    }
+    ctx.env.get_undefined()
+  })?;
+  // The handler rides along as the runner's first argument.
+  let runner = runner.coerce_to_object()?;
+  let bind: JsFunction = runner.get_named_property("bind")?;
+  let runner = bind.call(
+    Some(&runner),
+    &[env.get_undefined()?.into_unknown(), handler.into_unknown()],
+  )?;
+  let runner = JsFunction::try_from(runner)?;
+  let mut worker: Worker =
+    runner.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(JobInfo, Value)>| {
+      let (info, payload) = ctx.value;
+      Ok(vec![
+        ctx.env.create_uint32(info.id)?.into_unknown(),
+        ctx.env.create_uint32(info.attempt)?.into_unknown(),
+        ctx.env.to_js_value(&payload)?,
+      ])
+    })?;
+  worker.unref(env)?;
+  Ok(worker)
+}
+
+fn wake(env: &Env, state: &Arc<QueueState>) -> Result<Wake> {
+  let weak = Arc::downgrade(state);
+  let noop = env.create_function_from_closure("wake", |ctx| ctx.env.get_undefined())?;
+  let mut wake: Wake =
+    noop.create_threadsafe_function(0, move |ctx: ThreadSafeCallContext<()>| {
+      if let Some(state) = weak.upgrade() {
+        state.hold_open(&ctx.env)?;
+      }
+      Ok(Vec::<JsUnknown>::new())
+    })?;
+  wake.unref(env)?;
+  Ok(wake)
+}
+
+/// An in-memory job queue. Jobs wait in priority order, delays and retry
+/// backoff are timed on a dedicated runtime thread, and at most
+/// `concurrency` jobs are with the handler at once, so the JS side only
+/// does the work.
+///
+/// Payloads and results travel as JSON values. The queue keeps the process
+/// alive while it has a handler, isn't paused and has jobs left.
+#[napi(custom_finalize)]
+pub struct JobQueue {
+  state: Arc<QueueState>,
+}
+
+#[napi]
+impl JobQueue {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(env: Env) -> Result<Self> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_time()
+      .build()?;
+    let handle = runtime.handle().clone();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let thread = thread::Builder::new().name("jobs".into()).spawn(move || {
+      let _ = runtime.block_on(stop_rx);
+    })?;
+    let state = Arc::new(QueueState {
+      inner: Mutex::new(Inner {
+        runtime: Some(handle),
+        stop: Some(stop_tx),
+        thread: Some(thread),
+        next_id: 0,
+        next_seq: 0,
+        ready: BinaryHeap::new(),
+        waiting: HashMap::new(),
+        running: HashMap::new(),
+        active: 0,
+        concurrency: 1,
+        paused: false,
+        worker: None,
+        wake: None,
+      }),
+      idle: Notify::new(),
+    });
+    lifecycle::register(&env, &state)?;
+    Ok(JobQueue { state })
+  }
+
+  /// Queues a job and returns its id.
+  #[napi(catch_unwind, ts_args_type = "payload: unknown, options?: JobOptions")]
+  pub fn push(&self, env: Env, payload: JsUnknown, options: Option<JobOptions>) -> Result<u32> {
+    let payload: Value = from_js(&env, payload)?;
+    let options = options.unwrap_or_default();
+    let on_complete = match options.on_complete {
+      Some(callback) => Some(callback_fn(&env, callback, |env, result: Value| {
+        env.to_js_value(&result)
+      })?),
+      None => None,
+    };
+    let on_fail = match options.on_fail {
+      Some(callback) => Some(callback_fn(&env, callback, |env, message: String| {
+        Ok(
+          env
+            .create_error(napi::Error::from_reason(message))?
+            .into_unknown(),
+        )
+      })?),
+      None => None,
+    };
+    let mut inner = self.state.lock();
+    if inner.runtime.is_none() {
+      return Err(AppError::Closed("JobQueue is shut down".into()));
+    }
+    inner.next_id = inner.next_id.wrapping_add(1);
+    inner.next_seq += 1;
+    let job = Job {
+      info: JobInfo {
+        id: inner.next_id,
+        attempt: 1,
+      },
+      seq: inner.next_seq,
+      priority: options.priority.unwrap_or(0),
+      payload,
+      retries: options.retries.unwrap_or(0),
+      backoff_ms: options.backoff_ms.unwrap_or(DEFAULT_BACKOFF_MS),
+      on_complete,
+      on_fail,
+    };
+    let id = job.info.id;
+    match options.delay_ms {
+      Some(delay_ms) if delay_ms > 0 => {
+        self
+          .state
+          .delay(&mut inner, job, Duration::from_millis(delay_ms.into()))
+      }
+      _ => inner.ready.push(job),
+    }
+    drop(inner);
+    self.state.hold_open(&env)?;
+    self.state.pump();
+    Ok(id)
+  }
+
+  /// Starts running jobs with `handler(payload, job)`, up to `concurrency`
+  /// at a time. A job succeeds when the handler returns or its promise
+  /// resolves, and fails when it throws or rejects. Only one handler can
+  /// be attached.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "concurrency: number, handler: (payload: unknown, job: JobInfo) => unknown"
+  )]
+  pub fn process(&self, env: Env, concurrency: u32, handler: JsFunction) -> Result<()> {
+    if !(1..=MAX_CONCURRENCY).contains(&concurrency) {
+      return Err(AppError::InvalidInput(format!(
+        "concurrency must be 1 to {}, got {}",
+        MAX_CONCURRENCY, concurrency
+      )));
+    }
+    if self.state.lock().worker.is_some() {
+      return Err(AppError::InvalidInput(
+        "a handler is already attached".into(),
+      ));
+    }
+    let worker = worker(&env, &self.state, handler)?;
+    let wake = wake(&env, &self.state)?;
+    {
+      let mut inner = self.state.lock();
+      if inner.runtime.is_none() {
+        return Err(AppError::Closed("JobQueue is shut down".into()));
+      }
+      inner.worker = Some(worker);
+      inner.wake = Some(wake);
+      inner.concurrency = concurrency;
+    }
+    self.state.hold_open(&env)?;
+    self.state.pump();
+    Ok(())
+  }
+
+  /// Stops handing out jobs. Jobs already with the handler finish, and
+  /// delays keep counting down.
+  #[napi(catch_unwind)]
+  pub fn pause(&self, env: Env) -> Result<()> {
+    self.state.lock().paused = true;
+    self.state.hold_open(&env)
+  }
+
+  #[napi(catch_unwind)]
+  pub fn resume(&self, env: Env) -> Result<()> {
+    self.state.lock().paused = false;
+    self.state.hold_open(&env)?;
+    self.state.pump();
+    Ok(())
+  }
+
+  /// Resolves once no job is ready, delayed or running. Stays pending
+  /// while the queue is paused or has no handler.
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn drain(&self, env: Env) -> Result<JsObject> {
+    let state = self.state.clone();
+    error::spawn(&env, async move {
+      loop {
+        let idle = state.idle.notified();
+        if !state.lock().busy() {
+          return Ok(());
+        }
+        idle.await;
+      }
+    })
+  }
+
+  /// Drops every queued job without calling its callbacks and stops the
+  /// runtime thread. Pushing afterwards throws `ERR_CLOSED`.
+  #[napi(catch_unwind)]
+  pub fn shutdown(&self) {
+    self.state.stop();
+  }
+
+  /// Jobs waiting to run, including delayed ones and those waiting to
+  /// retry.
+  #[napi(catch_unwind, getter)]
+  pub fn pending(&self) -> u32 {
+    let inner = self.state.lock();
+    (inner.ready.len() + inner.waiting.len()) as u32
+  }
+
+  /// Jobs the handler is working on.
+  #[napi(catch_unwind, getter)]
+  pub fn running(&self) -> u32 {
+    self.state.lock().active
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn paused(&self) -> bool {
+    self.state.lock().paused
+  }
+}
+
+/// A job callback, called with the job's outcome and its `JobInfo`. It
+/// doesn't hold the process open itself.
+fn callback_fn<T: Send + 'static>(
+  env: &Env,
+  callback: JsFunction,
+  outcome: impl Fn(&Env, T) -> napi::Result<JsUnknown> + Send + 'static,
+) -> Result<ThreadsafeFunction<(T, JobInfo), ErrorStrategy::Fatal>> {
+  let mut callback =
+    callback.create_threadsafe_function(0, move |ctx: ThreadSafeCallContext<(T, JobInfo)>| {
+      let (value, info) = ctx.value;
+      Ok(vec![outcome(&ctx.env, value)?, to_unknown(&ctx.env, info)?])
+    })?;
+  callback.unref(env)?;
+  Ok(callback)
+}
+
+impl ObjectFinalize for JobQueue {
+  fn finalize(self, _: Env) -> napi::Result<()> {
+    self.state.stop();
+    Ok(())
+  }
+}
@@ -0,0 +1,132 @@
+use napi::bindgen_prelude::FromNapiValue;
+use napi::{Env, JsObject, JsUnknown, NapiRaw, ValueType};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, Result};
+
+/// Guards against stack overflow on cyclic or absurdly deep values: napi's
+/// own conversions recurse without a limit.
+const MAX_DEPTH: usize = 128;
+
+/// Fails once `depth` levels of arrays and objects exceed `MAX_DEPTH`.
+pub(crate) fn check_depth(depth: usize) -> Result<()> {
+  if depth > MAX_DEPTH {
+    return Err(AppError::InvalidInput(format!(
+      "value is nested more than {} levels deep (is it cyclic?)",
+      MAX_DEPTH
+    )));
+  }
+  Ok(())
+}
+
+fn check_nesting(value: &JsUnknown, depth: usize) -> Result<()> {
+  if value.get_type()? != ValueType::Object {
+    return Ok(());
+  }
+  check_depth(depth)?;
+  let object = unsafe { value.cast::<JsObject>() };
+  if value.is_array()? {
+    for i in 0..object.get_array_length()? {
+      check_nesting(&object.get_element::<JsUnknown>(i)?, depth + 1)?;
+    }
+  } else {
+    for key in JsObject::keys(&object)? {
+      check_nesting(&object.get_named_property::<JsUnknown>(&key)?, depth + 1)?;
+    }
+  }
+  Ok(())
+}
+
+/// Converts `value` as a `serde_json::Value` (or `Map`, `Vec` of them, …)
+/// parameter would, but throws `ERR_INVALID_INPUT` for values nested more
+/// than `MAX_DEPTH` levels deep instead of overflowing the stack. Exports
+/// taking arbitrary JS data take a `JsUnknown` and convert it with this.
+pub(crate) fn from_js<T: FromNapiValue>(env: &Env, value: JsUnknown) -> Result<T> {
+  check_nesting(&value, 0)?;
+  Ok(unsafe { T::from_napi_value(env.raw(), value.raw()) }?)
+}
+
+/// Example typed configuration. Maps to a plain JS object in both directions.
+#[napi(object)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+  pub name: String,
+  #[serde(default = "default_port")]
+  pub port: u32,
+  #[serde(default)]
+  pub debug: bool,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  pub database: Option<DatabaseConfig>,
+}
+
+#[napi(object)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseConfig {
+  pub url: String,
+  pub pool_size: Option<u32>,
+}
+
+fn default_port() -> u32 {
+  8080
+}
+
+/// Parses arbitrary JSON into the equivalent JS value.
+#[napi(catch_unwind, ts_return_type = "unknown")]
+pub fn parse_json(json: String) -> Result<Value> {
+  Ok(serde_json::from_str(&json)?)
+}
+
+/// Serializes a JS value to JSON, optionally pretty-printed.
+#[napi(catch_unwind, ts_args_type = "value: unknown, pretty?: boolean")]
+pub fn stringify(env: Env, value: JsUnknown, pretty: Option<bool>) -> Result<String> {
+  to_json(&from_js(&env, value)?, pretty)
+}
+
+fn to_json(value: &Value, pretty: Option<bool>) -> Result<String> {
+  let out = if pretty.unwrap_or(false) {
+    serde_json::to_string_pretty(value)
+  } else {
+    serde_json::to_string(value)
+  };
+  Ok(out?)
+}
+
+/// Parses and validates a JSON config document into a typed `Config`.
+#[napi(catch_unwind)]
+pub fn parse_config(json: String) -> Result<Config> {
+  Ok(serde_json::from_str(&json)?)
+}
+
+/// Serializes a `Config` back to JSON.
+#[napi(catch_unwind)]
+pub fn stringify_config(config: Config, pretty: Option<bool>) -> Result<String> {
+  to_json(&serde_json::to_value(config)?, pretty)
+}
+
+/// Recursively merges `b` into `a`. Nested objects are merged key by key;
+/// any other value in `b` replaces the one in `a`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "a: Record<string, any>, b: Record<string, any>",
+  ts_return_type = "Record<string, any>"
+)]
+pub fn merge_objects(env: Env, a: JsUnknown, b: JsUnknown) -> Result<Map<String, Value>> {
+  let mut out = from_js(&env, a)?;
+  merge_into(&mut out, from_js(&env, b)?);
+  Ok(out)
+}
+
+fn merge_into(target: &mut Map<String, Value>, source: Map<String, Value>) {
+  for (key, value) in source {
+    match (target.get_mut(&key), value) {
+      (Some(Value::Object(existing)), Value::Object(incoming)) => merge_into(existing, incoming),
+      (_, value) => {
+        target.insert(key, value);
+      }
+    }
+  }
+}
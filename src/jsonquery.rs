@@ -0,0 +1,101 @@
+use napi::{Env, JsString, JsUnknown, ValueType};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+fn compile(path: &str) -> Result<JsonPath> {
+  JsonPath::parse(path).map_err(|e| AppError::Parse(format!("invalid JSONPath {:?}: {}", path, e)))
+}
+
+/// A string is parsed as a JSON document in Rust, which for large inputs is
+/// much cheaper than converting an already-parsed JS object.
+fn document(env: &Env, data: JsUnknown) -> Result<Value> {
+  if data.get_type()? == ValueType::String {
+    let text = unsafe { data.cast::<JsString>() }.into_utf8()?;
+    Ok(serde_json::from_str(text.as_str()?)?)
+  } else {
+    from_js(env, data)
+  }
+}
+
+/// Runs the JSONPath query `path` (RFC 9535) against `data`, a JS value or
+/// a JSON string, and returns every match. Array elements come in order;
+/// object members in key order, as RFC 9535 leaves that order open.
+///
+/// Supports child and descendant segments (`.a`, `..a`), wildcards (`*`),
+/// indices and slices (`[0]`, `[-1]`, `[1:10:2]`), unions (`['a','b']`)
+/// and filters (`[?@.price < 10 && @.tags]`) with `length`, `count`,
+/// `match`, `search` and `value`. A bad path throws `ERR_PARSE`. Use
+/// `Query` to run the same path many times.
+#[napi(
+  catch_unwind,
+  ts_args_type = "data: unknown, path: string",
+  ts_return_type = "unknown[]"
+)]
+pub fn json_query(env: Env, data: JsUnknown, path: String) -> Result<Vec<Value>> {
+  let path = compile(&path)?;
+  let data = document(&env, data)?;
+  Ok(path.query(&data).into_iter().cloned().collect())
+}
+
+/// A JSONPath query parsed once, for running against many documents. See
+/// `jsonQuery` for the syntax.
+#[napi]
+pub struct Query {
+  path: JsonPath,
+  source: String,
+}
+
+#[napi]
+impl Query {
+  #[napi(catch_unwind, factory)]
+  pub fn compile(path: String) -> Result<Self> {
+    Ok(Query {
+      path: compile(&path)?,
+      source: path,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn source(&self) -> String {
+    self.source.clone()
+  }
+
+  /// Every match in `data`, ordered as `jsonQuery` orders them.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "data: unknown",
+    ts_return_type = "unknown[]"
+  )]
+  pub fn evaluate(&self, env: Env, data: JsUnknown) -> Result<Vec<Value>> {
+    let data = document(&env, data)?;
+    Ok(self.path.query(&data).into_iter().cloned().collect())
+  }
+
+  /// The first match, or `null` when nothing matches.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "data: unknown",
+    ts_return_type = "unknown"
+  )]
+  pub fn first(&self, env: Env, data: JsUnknown) -> Result<Option<Value>> {
+    let data = document(&env, data)?;
+    Ok(self.path.query(&data).first().cloned())
+  }
+
+  /// Where each match is, as normalized paths such as `$['items'][0]`.
+  #[napi(catch_unwind, ts_args_type = "data: unknown")]
+  pub fn paths(&self, env: Env, data: JsUnknown) -> Result<Vec<String>> {
+    let data = document(&env, data)?;
+    Ok(
+      self
+        .path
+        .query_located(&data)
+        .locations()
+        .map(|location| location.to_string())
+        .collect(),
+    )
+  }
+}
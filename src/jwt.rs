@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use napi::{Env, JsUnknown};
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+/// The largest number of seconds an option takes: the largest integer a JS
+/// number holds exactly.
+const MAX_SECONDS: f64 = 9_007_199_254_740_991.0;
+
+fn jwt_error(e: jsonwebtoken::errors::Error) -> AppError {
+  let message = e.to_string();
+  match e.kind() {
+    ErrorKind::ExpiredSignature => AppError::JwtExpired(message),
+    ErrorKind::ImmatureSignature => AppError::JwtNotYetValid(message),
+    ErrorKind::InvalidSignature => AppError::JwtInvalidSignature(message),
+    ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience | ErrorKind::InvalidSubject => {
+      AppError::JwtClaimMismatch(message)
+    }
+    ErrorKind::InvalidKeyFormat | ErrorKind::InvalidRsaKey(_) | ErrorKind::InvalidEcdsaKey => {
+      AppError::JwtInvalidKey(message)
+    }
+    ErrorKind::InvalidAlgorithm | ErrorKind::InvalidAlgorithmName => {
+      AppError::JwtInvalidAlgorithm(message)
+    }
+    _ => AppError::JwtMalformed(message),
+  }
+}
+
+/// Signing options. `algorithm` is `HS256` (default), `RS256` or `EdDSA`.
+/// For HS256 the key is the shared secret; for RS256/EdDSA it is a PEM
+/// private key when signing and a PEM public key when verifying.
+#[napi(object)]
+pub struct JwtOptions {
+  pub algorithm: Option<String>,
+  /// Sets `exp` to now + this many seconds.
+  pub expires_in: Option<f64>,
+  pub issuer: Option<String>,
+  pub subject: Option<String>,
+  pub audience: Option<String>,
+  /// Sets the `kid` header.
+  pub key_id: Option<String>,
+}
+
+#[napi(object)]
+pub struct VerifyOptions {
+  pub algorithm: Option<String>,
+  pub issuer: Option<String>,
+  pub audience: Option<String>,
+  /// Allowed clock skew in seconds for `exp`/`nbf` (default 0).
+  pub leeway: Option<f64>,
+}
+
+fn algorithm(name: Option<&str>) -> Result<Algorithm> {
+  match name.unwrap_or("HS256") {
+    "HS256" => Ok(Algorithm::HS256),
+    "RS256" => Ok(Algorithm::RS256),
+    "EdDSA" => Ok(Algorithm::EdDSA),
+    other => Err(AppError::JwtInvalidAlgorithm(format!(
+      "unsupported algorithm: {}",
+      other
+    ))),
+  }
+}
+
+/// A whole, non-negative number of seconds. napi would coerce a negative
+/// number to a huge unsigned one, so options read as `f64` and come here.
+fn seconds(name: &str, value: f64) -> Result<u64> {
+  if !(0.0..=MAX_SECONDS).contains(&value) || value.fract() != 0.0 {
+    return Err(AppError::InvalidInput(format!(
+      "{} must be a non-negative whole number of seconds, got {}",
+      name, value
+    )));
+  }
+  Ok(value as u64)
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+/// Signs `claims` and returns the compact JWT.
+#[napi(
+  catch_unwind,
+  ts_args_type = "claims: Record<string, any>, secret: string, opts?: JwtOptions"
+)]
+pub fn sign_jwt(
+  env: Env,
+  claims: JsUnknown,
+  secret: String,
+  opts: Option<JwtOptions>,
+) -> Result<String> {
+  let mut claims: Map<String, Value> = from_js(&env, claims)?;
+  let opts = opts.unwrap_or(JwtOptions {
+    algorithm: None,
+    expires_in: None,
+    issuer: None,
+    subject: None,
+    audience: None,
+    key_id: None,
+  });
+  let alg = algorithm(opts.algorithm.as_deref())?;
+  let key = match alg {
+    Algorithm::RS256 => EncodingKey::from_rsa_pem(secret.as_bytes()),
+    Algorithm::EdDSA => EncodingKey::from_ed_pem(secret.as_bytes()),
+    _ => Ok(EncodingKey::from_secret(secret.as_bytes())),
+  }
+  .map_err(jwt_error)?;
+
+  if let Some(expires_in) = opts.expires_in {
+    let exp = now_secs()
+      .checked_add(seconds("expiresIn", expires_in)?)
+      .ok_or_else(|| AppError::InvalidInput("expiresIn is too large".into()))?;
+    claims.insert("exp".into(), exp.into());
+  }
+  if !claims.contains_key("iat") {
+    claims.insert("iat".into(), now_secs().into());
+  }
+  for (name, value) in [
+    ("iss", opts.issuer),
+    ("sub", opts.subject),
+    ("aud", opts.audience),
+  ] {
+    if let Some(value) = value {
+      claims.insert(name.into(), value.into());
+    }
+  }
+
+  let mut header = Header::new(alg);
+  header.kid = opts.key_id;
+  jsonwebtoken::encode(&header, &claims, &key).map_err(jwt_error)
+}
+
+/// Verifies `token` and returns its claims. `exp` and `nbf` are enforced
+/// when present but not required.
+#[napi(catch_unwind)]
+pub fn verify_jwt(
+  token: String,
+  secret: String,
+  opts: Option<VerifyOptions>,
+) -> Result<Map<String, Value>> {
+  let opts = opts.unwrap_or(VerifyOptions {
+    algorithm: None,
+    issuer: None,
+    audience: None,
+    leeway: None,
+  });
+  let alg = algorithm(opts.algorithm.as_deref())?;
+  let key = match alg {
+    Algorithm::RS256 => DecodingKey::from_rsa_pem(secret.as_bytes()),
+    Algorithm::EdDSA => DecodingKey::from_ed_pem(secret.as_bytes()),
+    _ => Ok(DecodingKey::from_secret(secret.as_bytes())),
+  }
+  .map_err(jwt_error)?;
+
+  let mut validation = Validation::new(alg);
+  validation.required_spec_claims = HashSet::new();
+  validation.validate_nbf = true;
+  validation.leeway = seconds("leeway", opts.leeway.unwrap_or(0.0))?;
+  match opts.audience {
+    Some(aud) => validation.set_audience(&[aud]),
+    None => validation.validate_aud = false,
+  }
+  if let Some(iss) = opts.issuer {
+    validation.set_issuer(&[iss]);
+  }
+
+  jsonwebtoken::decode::<Map<String, Value>>(&token, &key, &validation)
+    .map(|data| data.claims)
+    .map_err(jwt_error)
+}
@@ -0,0 +1,67 @@
+use ::keyring::{Entry, Error as KeyringError};
+
+use crate::error::{AppError, Result};
+
+fn keyring_error(e: KeyringError) -> AppError {
+  match e {
+    KeyringError::NoStorageAccess(_) => AppError::Unsupported(format!("credential store: {}", e)),
+    KeyringError::TooLong(..) | KeyringError::Invalid(..) => {
+      AppError::InvalidInput(format!("credential store: {}", e))
+    }
+    KeyringError::BadEncoding(_) => AppError::Parse(format!("credential store: {}", e)),
+    _ => AppError::Io(format!("credential store: {}", e)),
+  }
+}
+
+fn entry(service: &str, account: &str) -> Result<Entry> {
+  if service.is_empty() || account.is_empty() {
+    return Err(AppError::InvalidInput(
+      "service and account must not be empty".into(),
+    ));
+  }
+  // zbus would otherwise report a missing bus as a generic platform failure.
+  #[cfg(all(unix, not(target_os = "macos")))]
+  if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none()
+    && !std::env::var_os("XDG_RUNTIME_DIR")
+      .is_some_and(|dir| std::path::Path::new(&dir).join("bus").exists())
+  {
+    return Err(AppError::Unsupported(
+      "credential store: no D-Bus session bus to reach the Secret Service".into(),
+    ));
+  }
+  Entry::new(service, account).map_err(keyring_error)
+}
+
+/// Stores `secret` in the OS credential store under `service` and
+/// `account`, replacing any previous one: the login Keychain on macOS,
+/// Credential Manager on Windows, and the Secret Service (GNOME Keyring,
+/// KWallet) over D-Bus on Linux. Throws `ERR_UNSUPPORTED` where no store is
+/// reachable, e.g. a headless Linux box without a session bus.
+#[napi(catch_unwind)]
+pub fn set_secret(service: String, account: String, secret: String) -> Result<()> {
+  entry(&service, &account)?
+    .set_password(&secret)
+    .map_err(keyring_error)
+}
+
+/// The secret stored under `service` and `account`, or `null` if there is
+/// none.
+#[napi(catch_unwind)]
+pub fn get_secret(service: String, account: String) -> Result<Option<String>> {
+  match entry(&service, &account)?.get_password() {
+    Ok(secret) => Ok(Some(secret)),
+    Err(KeyringError::NoEntry) => Ok(None),
+    Err(e) => Err(keyring_error(e)),
+  }
+}
+
+/// Removes the secret stored under `service` and `account`. Returns whether
+/// there was one.
+#[napi(catch_unwind)]
+pub fn delete_secret(service: String, account: String) -> Result<bool> {
+  match entry(&service, &account)?.delete_credential() {
+    Ok(()) => Ok(true),
+    Err(KeyringError::NoEntry) => Ok(false),
+    Err(e) => Err(keyring_error(e)),
+  }
+}
@@ -0,0 +1,407 @@
+use std::sync::{Arc, RwLock};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, NapiRaw, Task, ValueType};
+use redb::{Database, Durability, TableDefinition};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+const TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("kv");
+
+struct Store {
+  db: Database,
+  durability: Durability,
+}
+
+type SharedStore = Arc<RwLock<Option<Store>>>;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct KvStoreOptions {
+  /// Wait for every write to reach the disk before it returns. Defaults to
+  /// `true`. With `false`, writes are much faster but the last ones may be
+  /// lost in a crash unless `flush()` was called after them; the store is
+  /// never left corrupt either way.
+  pub sync: Option<bool>,
+}
+
+/// One write in a `KvStore.batch`.
+#[napi(object)]
+pub struct KvOperation {
+  #[napi(ts_type = "'set' | 'delete'")]
+  pub op: String,
+  #[napi(ts_type = "string | Buffer")]
+  pub key: Either<String, Buffer>,
+  /// Required for `set`.
+  #[napi(ts_type = "string | Buffer")]
+  pub value: Option<Either<String, Buffer>>,
+}
+
+enum Write {
+  Set(Vec<u8>, Vec<u8>),
+  Delete(Vec<u8>),
+}
+
+/// Strings are stored as their UTF-8 bytes, so `'a'` and `Buffer.from('a')`
+/// are the same key.
+fn bytes(value: Either<String, Buffer>) -> Vec<u8> {
+  match value {
+    Either::A(s) => s.into_bytes(),
+    Either::B(b) => b.to_vec(),
+  }
+}
+
+fn writes(operations: Vec<KvOperation>) -> Result<Vec<Write>> {
+  operations
+    .into_iter()
+    .map(|operation| match (operation.op.as_str(), operation.value) {
+      ("set", Some(value)) => Ok(Write::Set(bytes(operation.key), bytes(value))),
+      ("set", None) => Err(AppError::InvalidInput("set operation needs a value".into())),
+      ("delete", _) => Ok(Write::Delete(bytes(operation.key))),
+      (other, _) => Err(AppError::InvalidInput(format!(
+        "unknown batch operation: {}",
+        other
+      ))),
+    })
+    .collect()
+}
+
+/// A stored value, or `null`; becomes a Buffer on the JS thread.
+pub struct Value(Option<Vec<u8>>);
+
+impl TypeName for Value {
+  fn type_name() -> &'static str {
+    "Buffer | null"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ToNapiValue for Value {
+  unsafe fn to_napi_value(env: sys::napi_env, value: Self) -> napi::Result<sys::napi_value> {
+    let env = Env::from_raw(env);
+    Ok(match value.0 {
+      Some(bytes) => env.create_buffer_with_data(bytes)?.into_raw().raw(),
+      None => env.get_null()?.raw(),
+    })
+  }
+}
+
+/// Key/value pairs from a scan, as `[key, value]` Buffers.
+pub struct Entries(Vec<(Vec<u8>, Vec<u8>)>);
+
+impl TypeName for Entries {
+  fn type_name() -> &'static str {
+    "Array<[Buffer, Buffer]>"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ToNapiValue for Entries {
+  unsafe fn to_napi_value(env: sys::napi_env, entries: Self) -> napi::Result<sys::napi_value> {
+    let env = Env::from_raw(env);
+    let mut array = env.create_array_with_length(entries.0.len())?;
+    for (i, (key, value)) in entries.0.into_iter().enumerate() {
+      let mut pair = env.create_array_with_length(2)?;
+      pair.set_element(0, env.create_buffer_with_data(key)?.into_raw())?;
+      pair.set_element(1, env.create_buffer_with_data(value)?.into_raw())?;
+      array.set_element(i as u32, pair)?;
+    }
+    Ok(array.raw())
+  }
+}
+
+fn kv_error(e: impl Into<redb::Error>) -> AppError {
+  let e = e.into();
+  tracing::debug!(error = %e, "key-value store operation failed");
+  AppError::Database(e.to_string())
+}
+
+fn closed() -> AppError {
+  AppError::Closed("key-value store is closed".into())
+}
+
+fn with_store<T>(store: &SharedStore, f: impl FnOnce(&Store) -> Result<T>) -> Result<T> {
+  let guard = store.read().unwrap_or_else(|e| e.into_inner());
+  f(guard.as_ref().ok_or_else(closed)?)
+}
+
+fn get(store: &Store, key: &[u8]) -> Result<Value> {
+  let txn = store.db.begin_read().map_err(kv_error)?;
+  let table = txn.open_table(TABLE).map_err(kv_error)?;
+  let value = table.get(key).map_err(kv_error)?;
+  Ok(Value(value.map(|v| v.value().to_vec())))
+}
+
+/// Applies `writes` in one transaction; returns whether each deleted key
+/// existed.
+fn write(store: &Store, writes: &[Write]) -> Result<Vec<bool>> {
+  let mut txn = store.db.begin_write().map_err(kv_error)?;
+  txn.set_durability(store.durability);
+  let mut existed = Vec::new();
+  {
+    let mut table = txn.open_table(TABLE).map_err(kv_error)?;
+    for write in writes {
+      match write {
+        Write::Set(key, value) => {
+          table
+            .insert(key.as_slice(), value.as_slice())
+            .map_err(kv_error)?;
+        }
+        Write::Delete(key) => {
+          existed.push(table.remove(key.as_slice()).map_err(kv_error)?.is_some());
+        }
+      }
+    }
+  }
+  txn.commit().map_err(kv_error)?;
+  Ok(existed)
+}
+
+fn scan(store: &Store, prefix: &[u8], limit: Option<u32>) -> Result<Entries> {
+  let txn = store.db.begin_read().map_err(kv_error)?;
+  let table = txn.open_table(TABLE).map_err(kv_error)?;
+  let mut entries = Vec::new();
+  for entry in table.range(prefix..).map_err(kv_error)? {
+    let (key, value) = entry.map_err(kv_error)?;
+    if !key.value().starts_with(prefix) || limit.is_some_and(|l| entries.len() >= l as usize) {
+      break;
+    }
+    entries.push((key.value().to_vec(), value.value().to_vec()));
+  }
+  Ok(Entries(entries))
+}
+
+/// A durable empty commit makes every earlier unsynced one durable too.
+fn flush(store: &Store) -> Result<()> {
+  let mut txn = store.db.begin_write().map_err(kv_error)?;
+  txn.set_durability(Durability::Immediate);
+  txn.commit().map_err(kv_error)
+}
+
+type KvOp<T> = Box<dyn FnOnce(&Store) -> Result<T> + Send>;
+
+/// Runs one operation against the store on the libuv thread pool. Reads
+/// run concurrently; writes wait for each other.
+pub struct KvTask<T> {
+  store: SharedStore,
+  op: Option<KvOp<T>>,
+}
+
+impl<T> KvTask<T> {
+  fn new(
+    store: &SharedStore,
+    op: impl FnOnce(&Store) -> Result<T> + Send + 'static,
+  ) -> AsyncTask<Guarded<Self>>
+  where
+    T: ToNapiValue + TypeName + Send + 'static,
+  {
+    panic_guard::task(KvTask {
+      store: store.clone(),
+      op: Some(Box::new(op)),
+    })
+  }
+}
+
+impl<T: ToNapiValue + TypeName + Send + 'static> Task for KvTask<T> {
+  type Output = Result<T>;
+  type JsValue = T;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let op = self.op.take().expect("KvTask computed twice");
+    Ok(with_store(&self.store, op))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// An embedded, persistent, ordered key-value store in a single file,
+/// backed by redb.
+///
+/// Keys and values are bytes; strings are stored as UTF-8. Every write is
+/// an ACID transaction, so a crash never leaves a half-applied `batch`.
+/// Only one process may have a store open at a time.
+#[napi]
+pub struct KvStore {
+  store: SharedStore,
+}
+
+#[napi]
+impl KvStore {
+  /// Opens or creates the store at `path`.
+  #[napi(catch_unwind, factory)]
+  pub fn open(path: String, options: Option<KvStoreOptions>) -> Result<Self> {
+    let db = Database::create(&path).map_err(|e| match e {
+      redb::DatabaseError::Storage(redb::StorageError::Io(e)) => AppError::io(&path, e),
+      e => kv_error(e),
+    })?;
+    let txn = db.begin_write().map_err(kv_error)?;
+    txn.open_table(TABLE).map_err(kv_error)?;
+    txn.commit().map_err(kv_error)?;
+    tracing::debug!(path, "key-value store opened");
+    let durability = match options.unwrap_or_default().sync.unwrap_or(true) {
+      true => Durability::Immediate,
+      false => Durability::Eventual,
+    };
+    Ok(KvStore {
+      store: Arc::new(RwLock::new(Some(Store { db, durability }))),
+    })
+  }
+
+  #[napi(catch_unwind, ts_args_type = "key: string | Buffer")]
+  pub fn get(&self, key: Either<String, Buffer>) -> Result<Value> {
+    let key = bytes(key);
+    with_store(&self.store, |store| get(store, &key))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "key: string | Buffer, value: string | Buffer"
+  )]
+  pub fn set(&self, key: Either<String, Buffer>, value: Either<String, Buffer>) -> Result<()> {
+    let writes = [Write::Set(bytes(key), bytes(value))];
+    with_store(&self.store, |store| write(store, &writes).map(drop))
+  }
+
+  /// Removes `key`; returns whether it was there.
+  #[napi(catch_unwind, ts_args_type = "key: string | Buffer")]
+  pub fn delete(&self, key: Either<String, Buffer>) -> Result<bool> {
+    let writes = [Write::Delete(bytes(key))];
+    with_store(&self.store, |store| Ok(write(store, &writes)?[0]))
+  }
+
+  /// Every entry whose key starts with `prefix`, in key order (bytewise),
+  /// up to `limit` of them. An empty prefix scans the whole store.
+  #[napi(catch_unwind, ts_args_type = "prefix: string | Buffer, limit?: number")]
+  pub fn scan_prefix(&self, prefix: Either<String, Buffer>, limit: Option<u32>) -> Result<Entries> {
+    let prefix = bytes(prefix);
+    with_store(&self.store, |store| scan(store, &prefix, limit))
+  }
+
+  /// Applies all `operations` in one transaction: all of them take effect
+  /// or, if one is invalid, none do. Much faster than separate calls when
+  /// `sync` is on, as there is one disk sync per batch.
+  #[napi(catch_unwind)]
+  pub fn batch(&self, operations: Vec<KvOperation>) -> Result<()> {
+    let writes = writes(operations)?;
+    with_store(&self.store, |store| write(store, &writes).map(drop))
+  }
+
+  /// Makes every earlier write durable. Only needed with `sync: false`.
+  #[napi(catch_unwind)]
+  pub fn flush(&self) -> Result<()> {
+    with_store(&self.store, flush)
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "key: string | Buffer",
+    ts_return_type = "Promise<Buffer | null>"
+  )]
+  pub fn get_async(&self, key: Either<String, Buffer>) -> AsyncTask<Guarded<KvTask<Value>>> {
+    let key = bytes(key);
+    KvTask::new(&self.store, move |store| get(store, &key))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "key: string | Buffer, value: string | Buffer",
+    ts_return_type = "Promise<void>"
+  )]
+  pub fn set_async(
+    &self,
+    key: Either<String, Buffer>,
+    value: Either<String, Buffer>,
+  ) -> AsyncTask<Guarded<KvTask<()>>> {
+    let writes = [Write::Set(bytes(key), bytes(value))];
+    KvTask::new(&self.store, move |store| write(store, &writes).map(drop))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "key: string | Buffer",
+    ts_return_type = "Promise<boolean>"
+  )]
+  pub fn delete_async(&self, key: Either<String, Buffer>) -> AsyncTask<Guarded<KvTask<bool>>> {
+    let writes = [Write::Delete(bytes(key))];
+    KvTask::new(&self.store, move |store| Ok(write(store, &writes)?[0]))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "prefix: string | Buffer, limit?: number",
+    ts_return_type = "Promise<Array<[Buffer, Buffer]>>"
+  )]
+  pub fn scan_prefix_async(
+    &self,
+    prefix: Either<String, Buffer>,
+    limit: Option<u32>,
+  ) -> AsyncTask<Guarded<KvTask<Entries>>> {
+    let prefix = bytes(prefix);
+    KvTask::new(&self.store, move |store| scan(store, &prefix, limit))
+  }
+
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn batch_async(
+    &self,
+    operations: Vec<KvOperation>,
+  ) -> Result<AsyncTask<Guarded<KvTask<()>>>> {
+    let writes = writes(operations)?;
+    Ok(KvTask::new(&self.store, move |store| {
+      write(store, &writes).map(drop)
+    }))
+  }
+
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn flush_async(&self) -> AsyncTask<Guarded<KvTask<()>>> {
+    KvTask::new(&self.store, flush)
+  }
+
+  /// Closes the store, waiting for running operations, and releases the
+  /// file. Later calls throw `ERR_CLOSED`; closing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn close(&self) {
+    let store = self.store.write().unwrap_or_else(|e| e.into_inner()).take();
+    if store.is_some() {
+      tracing::debug!("key-value store closed");
+    }
+  }
+
+  /// Like `close`, but waits for running operations off the main thread.
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn close_async(&self) -> AsyncTask<Guarded<CloseTask>> {
+    panic_guard::task(CloseTask {
+      store: self.store.clone(),
+    })
+  }
+}
+
+/// Takes the store out from under its lock on the libuv thread pool.
+pub struct CloseTask {
+  store: SharedStore,
+}
+
+impl Task for CloseTask {
+  type Output = ();
+  type JsValue = ();
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let store = self.store.write().unwrap_or_else(|e| e.into_inner()).take();
+    if store.is_some() {
+      tracing::debug!("key-value store closed");
+    }
+    Ok(())
+  }
+
+  fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(())
+  }
+}
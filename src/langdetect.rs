@@ -0,0 +1,93 @@
+use whatlang::dev::{self, RawLangInfo};
+use whatlang::{Detector, Lang};
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct LanguagePrediction {
+  /// ISO 639-3 code, e.g. `eng`, `deu`, `cmn`.
+  pub code: String,
+  /// English name of the language.
+  pub name: String,
+  /// Writing system the text was identified as, e.g. `Latin`, `Cyrillic`.
+  pub script: String,
+  /// 0–1; this language's share of the candidates' combined score.
+  pub confidence: f64,
+  /// Set on the top prediction when the detector considers it trustworthy.
+  /// Short inputs rarely are.
+  pub reliable: bool,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct DetectLanguageOptions {
+  /// ISO 639-3 codes to choose between; all supported languages otherwise.
+  pub restrict_to: Option<Vec<String>>,
+  /// Maximum number of predictions. Defaults to 3.
+  pub limit: Option<u32>,
+}
+
+/// Ranks the likely languages of `text`, best first, using the alphabet and
+/// trigram statistics of `whatlang` (69 languages). Confidences are the
+/// detector's scores normalized over the remaining candidates, so they sum
+/// to 1 before `limit` is applied. Returns an empty array when the text has
+/// no letters to go on.
+#[napi(catch_unwind)]
+pub fn detect_language(
+  text: String,
+  options: Option<DetectLanguageOptions>,
+) -> Result<Vec<LanguagePrediction>> {
+  let options = options.unwrap_or_default();
+  let allowed: Option<Vec<Lang>> = options
+    .restrict_to
+    .map(|codes| {
+      codes
+        .iter()
+        .map(|code| {
+          Lang::from_code(code.as_str()).ok_or_else(|| {
+            AppError::InvalidInput(format!("unknown ISO 639-3 language code: {}", code))
+          })
+        })
+        .collect::<Result<_>>()
+    })
+    .transpose()?;
+
+  let raw = dev::raw_detect(&text);
+  let Some(script) = whatlang::detect_script(&text) else {
+    return Ok(Vec::new());
+  };
+  let scores = match raw.lang_info {
+    Some(RawLangInfo::MultiScript(combined)) => combined.scores,
+    Some(RawLangInfo::OneScript(lang)) | Some(RawLangInfo::Mandarin(lang)) => vec![(lang, 1.0)],
+    None => Vec::new(),
+  };
+  let scores: Vec<(Lang, f64)> = scores
+    .into_iter()
+    .filter(|(lang, _)| allowed.as_ref().is_none_or(|list| list.contains(lang)))
+    .collect();
+  let total: f64 = scores.iter().map(|(_, score)| score).sum();
+  if total <= 0.0 {
+    return Ok(Vec::new());
+  }
+  // The detector's own verdict on whether the winner is trustworthy.
+  let reliable = match &allowed {
+    Some(list) => Detector::with_allowlist(list.clone()).detect(&text),
+    None => Detector::new().detect(&text),
+  }
+  .filter(|info| info.is_reliable())
+  .map(|info| info.lang());
+
+  Ok(
+    scores
+      .into_iter()
+      .take(options.limit.unwrap_or(3) as usize)
+      .map(|(lang, score)| LanguagePrediction {
+        code: lang.code().to_string(),
+        name: lang.eng_name().to_string(),
+        script: script.name().to_string(),
+        confidence: score / total,
+        reliable: reliable == Some(lang),
+      })
+      .collect(),
+  )
+}
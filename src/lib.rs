@@ -3,12 +3,128 @@
 #[macro_use]
 extern crate napi_derive;
 
-#[napi]
-pub fn sum(a: i32, b: i32) -> i32 {
-    a + b
+pub mod async_ops;
+pub mod audio;
+pub mod bench;
+pub mod bigint;
+pub mod binfmt;
+pub mod buffer_ops;
+pub mod cache;
+pub mod checksum;
+pub mod chunking;
+pub mod classes;
+pub mod clipboard;
+pub mod codec;
+pub mod collections;
+pub mod color;
+pub mod compress;
+pub mod config_formats;
+pub mod cron;
+pub mod crypto;
+pub mod csv;
+pub mod datetime;
+pub mod decimal;
+pub mod dedup;
+pub mod deep;
+pub mod diff;
+pub mod dns;
+pub mod encoding;
+pub mod envfiles;
+pub mod error;
+pub mod events;
+pub mod exif;
+pub mod expr;
+pub mod file_reader;
+pub mod filetype;
+pub mod flags;
+pub mod framing;
+pub mod fs_walk;
+pub mod fswatch;
+pub mod fuzzy;
+pub mod geo;
+pub mod glob;
+pub mod hash;
+pub mod held;
+pub mod hotkeys;
+pub mod http;
+pub mod httputil;
+pub mod ids;
+pub mod image;
+pub mod introspect;
+pub mod ip;
+pub mod jobs;
+pub mod json;
+pub mod jsonquery;
+pub mod jwt;
+pub mod keyring;
+pub mod kv;
+pub mod langdetect;
+pub mod lifecycle;
+pub mod linalg;
+pub mod logging;
+pub mod markdown;
+pub mod metrics;
+pub mod multipart;
+pub mod ndjson;
+pub mod net;
+pub mod otp;
+pub mod pack;
+pub mod panic_guard;
+pub mod parallel;
+pub mod parquet;
+pub mod password;
+pub mod patch;
+pub mod pdf;
+pub mod phonetic;
+pub mod primes;
+pub mod probabilistic;
+pub mod proc;
+pub mod progress;
+pub mod protobuf;
+pub mod qrcode;
+pub mod random;
+pub mod ratelimit;
+pub mod regex;
+pub mod resilience;
+pub mod sanitize;
+pub mod scheduler;
+pub mod schema;
+pub mod search;
+pub mod semver;
+pub mod shared;
+pub mod shm;
+pub mod signing;
+pub mod snowflake;
+pub mod sort;
+pub mod spell;
+pub mod sqlite;
+pub mod stats;
+pub mod strings;
+pub mod sync;
+pub mod sysinfo;
+pub mod tar;
+pub mod template;
+pub mod ticker;
+pub mod tokenizer;
+pub mod units;
+pub mod url;
+pub mod useragent;
+pub mod validate;
+pub mod wal;
+pub mod ws;
+pub mod x509;
+pub mod xml;
+pub mod zip;
+
+use error::{AppError, Result};
+
+#[napi(catch_unwind)]
+pub fn sum(a: i32, b: i32) -> Result<i32> {
+  a.checked_add(b)
+    .ok_or_else(|| AppError::Overflow(format!("{} + {} overflows i32", a, b)))
 }
 
-#[napi]
+#[napi(catch_unwind)]
 pub fn hello() -> String {
-    "Hello there".to_string()
+  "Hello there".to_string()
 }
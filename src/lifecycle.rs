@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::Result;
+use crate::panic_guard::{self, Guarded};
+
+/// Something that owns a background thread, OS handle or thread-safe
+/// function and can be stopped from any thread.
+pub trait Resource: Send + Sync {
+  /// Short name reported by `activeResources`, e.g. `ticker`.
+  fn kind(&self) -> &'static str;
+  /// Whether the resource still has anything running.
+  fn active(&self) -> bool;
+  /// Stops the resource and releases its JS callbacks. Must be idempotent,
+  /// and may block briefly (e.g. to join a thread).
+  fn shutdown(&self);
+}
+
+struct Entry {
+  /// The `napi_env` the resource was created in, as an address. The main
+  /// thread and each worker have their own.
+  env: usize,
+  resource: Weak<dyn Resource>,
+}
+
+#[derive(Default)]
+struct Registry {
+  entries: Vec<Entry>,
+  /// Environments that already have a cleanup hook installed.
+  hooked: HashSet<usize>,
+}
+
+fn registry() -> MutexGuard<'static, Registry> {
+  static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+  REGISTRY
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+}
+
+fn env_id(env: &Env) -> usize {
+  env.raw() as usize
+}
+
+/// Tracks `resource` until it is dropped, so that `shutdown()` or the
+/// environment's teardown (process exit, `worker.terminate()`) stops it.
+///
+/// The registry only holds a weak reference: registering never keeps a
+/// resource alive, and garbage-collected resources simply disappear.
+pub fn register<R: Resource + 'static>(env: &Env, resource: &Arc<R>) -> Result<()> {
+  let id = env_id(env);
+  let resource: Weak<R> = Arc::downgrade(resource);
+  let mut tracked = registry();
+  tracked.entries.retain(|e| e.resource.strong_count() > 0);
+  tracked.entries.push(Entry { env: id, resource });
+  if tracked.hooked.insert(id) {
+    // Node only runs cleanup hooks during teardown, never inline, so
+    // installing one with the lock held is safe.
+    let mut env = *env;
+    env.add_env_cleanup_hook(id, |id| {
+      registry().hooked.remove(&id);
+      stop_all(id);
+    })?;
+  }
+  Ok(())
+}
+
+/// Live resources of the environment `env`. The registry lock is released
+/// before any of them is touched, since stopping one may drop the last
+/// reference to another.
+fn live(env: usize) -> Vec<Arc<dyn Resource>> {
+  let mut registry = registry();
+  let mut live = Vec::new();
+  registry.entries.retain(|e| match e.resource.upgrade() {
+    Some(resource) => {
+      if e.env == env {
+        live.push(resource);
+      }
+      true
+    }
+    None => false,
+  });
+  live
+}
+
+fn stop_all(env: usize) {
+  for resource in live(env) {
+    tracing::debug!(kind = resource.kind(), "stopping");
+    resource.shutdown();
+  }
+}
+
+pub struct ShutdownTask {
+  env: usize,
+}
+
+impl Task for ShutdownTask {
+  type Output = ();
+  type JsValue = ();
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    stop_all(self.env);
+    Ok(())
+  }
+
+  fn resolve(&mut self, _: Env, _: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(())
+  }
+}
+
+/// Stops every ticker, file watcher, WebSocket client, `NativeServer`,
+/// `Scheduler` and global hotkey created on this thread (the main thread or the calling
+/// worker) and releases their callbacks, so nothing native keeps the event loop alive.
+/// Resources on other threads are left alone. The same happens automatically when the
+/// thread's environment is torn down.
+#[napi(catch_unwind, ts_return_type = "Promise<void>")]
+pub fn shutdown(env: Env) -> AsyncTask<Guarded<ShutdownTask>> {
+  panic_guard::task(ShutdownTask { env: env_id(&env) })
+}
+
+/// Kinds of the resources on this thread that are still running, e.g.
+/// `['ticker', 'watcher']`.
+#[napi(catch_unwind)]
+pub fn active_resources(env: Env) -> Vec<String> {
+  live(env_id(&env))
+    .iter()
+    .filter(|r| r.active())
+    .map(|r| r.kind().to_string())
+    .collect()
+}
@@ -0,0 +1,249 @@
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+
+use crate::error::{AppError, Result};
+
+fn check_shape(name: &str, data: &[f64], rows: u32, cols: u32) -> Result<()> {
+  let expected = (rows as usize)
+    .checked_mul(cols as usize)
+    .ok_or_else(|| AppError::Overflow(format!("{} shape {}x{} is too large", name, rows, cols)))?;
+  if data.len() != expected {
+    return Err(AppError::InvalidInput(format!(
+      "{} has {} elements, expected {}x{} = {}",
+      name,
+      data.len(),
+      rows,
+      cols,
+      expected
+    )));
+  }
+  Ok(())
+}
+
+/// Row-major `m`×`k` times `k`×`n`. Output rows are computed in parallel;
+/// the i-k-j loop order keeps the inner loop contiguous so it vectorizes.
+fn multiply(a: &[f64], b: &[f64], m: usize, k: usize, n: usize) -> Vec<f64> {
+  let mut out = vec![0.0; m * n];
+  if n == 0 {
+    return out;
+  }
+  out.par_chunks_mut(n).enumerate().for_each(|(i, row)| {
+    for p in 0..k {
+      let aip = a[i * k + p];
+      let b_row = &b[p * n..(p + 1) * n];
+      for (o, &bv) in row.iter_mut().zip(b_row) {
+        *o += aip * bv;
+      }
+    }
+  });
+  out
+}
+
+fn transposed(a: &[f64], rows: usize, cols: usize) -> Vec<f64> {
+  let mut out = vec![0.0; rows * cols];
+  if rows == 0 {
+    return out;
+  }
+  out.par_chunks_mut(rows).enumerate().for_each(|(j, col)| {
+    for (i, o) in col.iter_mut().enumerate() {
+      *o = a[i * cols + j];
+    }
+  });
+  out
+}
+
+/// Gaussian elimination with partial pivoting on an `n`×`n` system.
+fn solve(a: &[f64], b: &[f64], n: usize) -> Result<Vec<f64>> {
+  let mut m = a.to_vec();
+  let mut x = b.to_vec();
+  let scale = a.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+  let tolerance = scale * n as f64 * f64::EPSILON;
+  for col in 0..n {
+    let pivot = (col..n)
+      .max_by(|&r, &s| m[r * n + col].abs().total_cmp(&m[s * n + col].abs()))
+      .expect("col < n");
+    if m[pivot * n + col].abs() <= tolerance {
+      return Err(AppError::InvalidInput("matrix is singular".into()));
+    }
+    if pivot != col {
+      for j in 0..n {
+        m.swap(pivot * n + j, col * n + j);
+      }
+      x.swap(pivot, col);
+    }
+    let (upper, lower) = m.split_at_mut((col + 1) * n);
+    let pivot_row = &upper[col * n..];
+    let pivot_x = x[col];
+    let factors: Vec<f64> = lower
+      .par_chunks_mut(n)
+      .map(|row| {
+        let factor = row[col] / pivot_row[col];
+        for (r, &p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+          *r -= factor * p;
+        }
+        factor
+      })
+      .collect();
+    for (offset, factor) in factors.into_iter().enumerate() {
+      x[col + 1 + offset] -= factor * pivot_x;
+    }
+  }
+  for row in (0..n).rev() {
+    let tail: f64 = ((row + 1)..n).map(|j| m[row * n + j] * x[j]).sum();
+    x[row] = (x[row] - tail) / m[row * n + row];
+  }
+  Ok(x)
+}
+
+/// Multiplies the row-major `m`×`k` matrix `a` by the `k`×`n` matrix `b`.
+#[napi(catch_unwind)]
+pub fn mat_mul(a: Float64Array, b: Float64Array, m: u32, k: u32, n: u32) -> Result<Float64Array> {
+  check_shape("a", &a, m, k)?;
+  check_shape("b", &b, k, n)?;
+  Ok(Float64Array::new(multiply(
+    &a, &b, m as usize, k as usize, n as usize,
+  )))
+}
+
+/// Transposes the row-major `rows`×`cols` matrix `a`.
+#[napi(catch_unwind)]
+pub fn transpose(a: Float64Array, rows: u32, cols: u32) -> Result<Float64Array> {
+  check_shape("a", &a, rows, cols)?;
+  Ok(Float64Array::new(transposed(
+    &a,
+    rows as usize,
+    cols as usize,
+  )))
+}
+
+/// Solves `a · x = b` for the row-major `n`×`n` matrix `a`. Throws
+/// `ERR_INVALID_INPUT` if `a` is singular.
+#[napi(catch_unwind)]
+pub fn solve_linear_system(a: Float64Array, b: Float64Array, n: u32) -> Result<Float64Array> {
+  check_shape("a", &a, n, n)?;
+  check_shape("b", &b, n, 1)?;
+  Ok(Float64Array::new(solve(&a, &b, n as usize)?))
+}
+
+/// A row-major dense matrix that carries its shape with its data.
+#[napi]
+pub struct Matrix {
+  rows: u32,
+  cols: u32,
+  data: Vec<f64>,
+}
+
+#[napi]
+impl Matrix {
+  /// A `rows`×`cols` matrix copied from `data`, or filled with zeros.
+  #[napi(catch_unwind, constructor)]
+  pub fn new(rows: u32, cols: u32, data: Option<Float64Array>) -> Result<Self> {
+    let data = match data {
+      Some(data) => {
+        check_shape("data", &data, rows, cols)?;
+        data.to_vec()
+      }
+      None => {
+        let len = (rows as usize)
+          .checked_mul(cols as usize)
+          .ok_or_else(|| AppError::Overflow(format!("shape {}x{} is too large", rows, cols)))?;
+        vec![0.0; len]
+      }
+    };
+    Ok(Matrix { rows, cols, data })
+  }
+
+  #[napi(catch_unwind, factory)]
+  pub fn identity(n: u32) -> Result<Self> {
+    let mut matrix = Matrix::new(n, n, None)?;
+    for i in 0..n as usize {
+      matrix.data[i * n as usize + i] = 1.0;
+    }
+    Ok(matrix)
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn rows(&self) -> u32 {
+    self.rows
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn cols(&self) -> u32 {
+    self.cols
+  }
+
+  /// A copy of the elements in row-major order.
+  #[napi(catch_unwind, getter)]
+  pub fn data(&self) -> Float64Array {
+    Float64Array::new(self.data.clone())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get(&self, row: u32, col: u32) -> Result<f64> {
+    Ok(self.data[self.index(row, col)?])
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set(&mut self, row: u32, col: u32, value: f64) -> Result<()> {
+    let index = self.index(row, col)?;
+    self.data[index] = value;
+    Ok(())
+  }
+
+  #[napi(catch_unwind)]
+  pub fn mul(&self, other: &Matrix) -> Result<Matrix> {
+    if self.cols != other.rows {
+      return Err(AppError::InvalidInput(format!(
+        "cannot multiply {}x{} by {}x{}",
+        self.rows, self.cols, other.rows, other.cols
+      )));
+    }
+    Ok(Matrix {
+      rows: self.rows,
+      cols: other.cols,
+      data: multiply(
+        &self.data,
+        &other.data,
+        self.rows as usize,
+        self.cols as usize,
+        other.cols as usize,
+      ),
+    })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn transpose(&self) -> Matrix {
+    Matrix {
+      rows: self.cols,
+      cols: self.rows,
+      data: transposed(&self.data, self.rows as usize, self.cols as usize),
+    }
+  }
+
+  /// Solves `this · x = b`; the matrix must be square.
+  #[napi(catch_unwind)]
+  pub fn solve(&self, b: Float64Array) -> Result<Float64Array> {
+    if self.rows != self.cols {
+      return Err(AppError::InvalidInput(format!(
+        "cannot solve with a non-square {}x{} matrix",
+        self.rows, self.cols
+      )));
+    }
+    check_shape("b", &b, self.rows, 1)?;
+    Ok(Float64Array::new(solve(
+      &self.data,
+      &b,
+      self.rows as usize,
+    )?))
+  }
+
+  fn index(&self, row: u32, col: u32) -> Result<usize> {
+    if row >= self.rows || col >= self.cols {
+      return Err(AppError::InvalidInput(format!(
+        "index ({}, {}) is outside the {}x{} matrix",
+        row, col, self.rows, self.cols
+      )));
+    }
+    Ok(row as usize * self.cols as usize + col as usize)
+  }
+}
@@ -0,0 +1,196 @@
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+use crate::error::{AppError, Result};
+
+/// One `tracing` event, as passed to the `initLogger` callback.
+#[napi(object)]
+pub struct LogEvent {
+  /// Milliseconds since the Unix epoch.
+  pub time: f64,
+  /// `trace`, `debug`, `info`, `warn` or `error`.
+  pub level: String,
+  /// The emitting module, e.g. `demo_napi::sqlite`.
+  pub target: String,
+  pub message: String,
+  /// The event's structured fields other than the message.
+  #[napi(ts_type = "Record<string, unknown>")]
+  pub fields: Value,
+  pub file: Option<String>,
+  pub line: Option<u32>,
+}
+
+type Sink = ThreadsafeFunction<LogEvent, ErrorStrategy::Fatal>;
+
+struct Logger {
+  sink: Sink,
+  filter: Targets,
+}
+
+/// The current sink. Process-wide, like the `tracing` dispatcher it serves:
+/// `initLogger` on any thread replaces it for all of them.
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
+
+fn logger() -> RwLockReadGuard<'static, Option<Logger>> {
+  LOGGER.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+  message: String,
+  fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+  fn insert(&mut self, field: &Field, value: Value) {
+    if field.name() == "message" {
+      self.message = match value {
+        Value::String(message) => message,
+        other => other.to_string(),
+      };
+    } else {
+      self.fields.insert(field.name().to_string(), value);
+    }
+  }
+}
+
+impl Visit for FieldVisitor {
+  fn record_f64(&mut self, field: &Field, value: f64) {
+    self.insert(field, value.into());
+  }
+
+  fn record_i64(&mut self, field: &Field, value: i64) {
+    self.insert(field, value.into());
+  }
+
+  fn record_u64(&mut self, field: &Field, value: u64) {
+    self.insert(field, value.into());
+  }
+
+  fn record_bool(&mut self, field: &Field, value: bool) {
+    self.insert(field, value.into());
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.insert(field, value.into());
+  }
+
+  fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+    self.insert(field, value.to_string().into());
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    self.insert(field, format!("{:?}", value).into());
+  }
+}
+
+/// Forwards every event the current filter enables to the JS sink.
+struct JsLayer;
+
+impl<S: Subscriber> Layer<S> for JsLayer {
+  fn register_callsite(&self, _: &'static Metadata<'static>) -> Interest {
+    // The filter can change at any time, so ask `enabled` on every event
+    // instead of letting `tracing` cache the answer.
+    Interest::sometimes()
+  }
+
+  fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+    logger()
+      .as_ref()
+      .is_some_and(|l| l.filter.would_enable(metadata.target(), metadata.level()))
+  }
+
+  fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+    let logger = logger();
+    let Some(logger) = logger.as_ref() else {
+      return;
+    };
+    let mut visitor = FieldVisitor::default();
+    event.record(&mut visitor);
+    let metadata = event.metadata();
+    let time = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .unwrap_or_default();
+    logger.sink.call(
+      LogEvent {
+        time: time.as_secs_f64() * 1000.0,
+        level: level_name(metadata.level()).to_string(),
+        target: metadata.target().to_string(),
+        message: visitor.message,
+        fields: Value::Object(visitor.fields),
+        file: metadata.file().map(str::to_string),
+        line: metadata.line(),
+      },
+      ThreadsafeFunctionCallMode::NonBlocking,
+    );
+  }
+}
+
+fn level_name(level: &Level) -> &'static str {
+  match *level {
+    Level::TRACE => "trace",
+    Level::DEBUG => "debug",
+    Level::INFO => "info",
+    Level::WARN => "warn",
+    Level::ERROR => "error",
+  }
+}
+
+/// Sends the addon's log events to `callback`, replacing any earlier one.
+///
+/// `level` is a comma-separated list of `level` and `target=level`
+/// directives, e.g. `warn,demo_napi::http=debug`; the most specific target
+/// prefix wins, and the default is `info`. Events are delivered
+/// asynchronously and in order, and the callback does not keep the process
+/// alive. Map them onto pino or winston with e.g.
+/// `initLogger((e) => log[e.level]({...e.fields, target: e.target}, e.message))`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "callback: (event: LogEvent) => void, level?: string"
+)]
+pub fn init_logger(env: Env, callback: JsFunction, level: Option<String>) -> Result<()> {
+  let level = level.unwrap_or_else(|| "info".to_string());
+  let filter: Targets = level
+    .parse()
+    .map_err(|e| AppError::InvalidInput(format!("invalid log level {:?}: {}", level, e)))?;
+
+  static INSTALLED: OnceLock<bool> = OnceLock::new();
+  let installed = *INSTALLED.get_or_init(|| {
+    tracing::subscriber::set_global_default(Registry::default().with(JsLayer)).is_ok()
+  });
+  if !installed {
+    return Err(AppError::Unsupported(
+      "another tracing subscriber is already installed".into(),
+    ));
+  }
+
+  let mut sink: Sink = callback
+    .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<LogEvent>| {
+      Ok(vec![ctx.value])
+    })?;
+  sink.unref(&env)?;
+  *LOGGER.write().unwrap_or_else(|e| e.into_inner()) = Some(Logger { sink, filter });
+  Ok(())
+}
+
+/// Detaches the callback set by `initLogger`. Returns whether one was set.
+#[napi(catch_unwind)]
+pub fn stop_logger() -> bool {
+  LOGGER
+    .write()
+    .unwrap_or_else(|e| e.into_inner())
+    .take()
+    .is_some()
+}
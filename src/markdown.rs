@@ -0,0 +1,78 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use pulldown_cmark::{html, Options, Parser};
+
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct MarkdownOptions {
+  /// GFM tables. Defaults to `true`.
+  pub tables: Option<bool>,
+  /// `[^1]` footnotes. Defaults to `true`.
+  pub footnotes: Option<bool>,
+  /// `~~strikethrough~~`. Defaults to `true`.
+  pub strikethrough: Option<bool>,
+  /// `- [x]` task lists. Defaults to `true`.
+  pub task_lists: Option<bool>,
+  /// Strip scripts, event handlers and other unsafe markup from the output
+  /// so it can be inserted into a page as-is. Defaults to `false`.
+  pub sanitize: Option<bool>,
+}
+
+fn render(input: &str, opts: &MarkdownOptions) -> String {
+  let mut options = Options::empty();
+  for (enabled, flag) in [
+    (opts.tables, Options::ENABLE_TABLES),
+    (opts.footnotes, Options::ENABLE_FOOTNOTES),
+    (opts.strikethrough, Options::ENABLE_STRIKETHROUGH),
+    (opts.task_lists, Options::ENABLE_TASKLISTS),
+  ] {
+    options.set(flag, enabled.unwrap_or(true));
+  }
+
+  let mut out = String::with_capacity(input.len() * 3 / 2);
+  html::push_html(&mut out, Parser::new_ext(input, options));
+  if opts.sanitize.unwrap_or(false) {
+    ammonia::clean(&out)
+  } else {
+    out
+  }
+}
+
+/// Renders CommonMark (plus the extensions enabled in `opts`) to HTML.
+#[napi(catch_unwind)]
+pub fn render_markdown(input: String, opts: Option<MarkdownOptions>) -> String {
+  render(&input, &opts.unwrap_or_default())
+}
+
+pub struct MarkdownTask {
+  input: String,
+  opts: MarkdownOptions,
+}
+
+impl Task for MarkdownTask {
+  type Output = String;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(render(&self.input, &self.opts))
+  }
+
+  fn resolve(&mut self, _: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// `renderMarkdown` on the libuv thread pool, for documents large enough to
+/// stall the event loop.
+#[napi(catch_unwind, ts_return_type = "Promise<string>")]
+pub fn render_markdown_async(
+  input: String,
+  opts: Option<MarkdownOptions>,
+) -> AsyncTask<Guarded<MarkdownTask>> {
+  panic_guard::task(MarkdownTask {
+    input,
+    opts: opts.unwrap_or_default(),
+  })
+}
@@ -0,0 +1,513 @@
+//! Opt-in call counters and latency histograms for every export.
+//!
+//! The glue `#[napi]` generates offers no hook around calls, so enabling
+//! metrics replaces each exported function and class method with a thin
+//! native wrapper that counts and times the call before forwarding it, and
+//! disabling them puts the originals back: while disabled (the default)
+//! calls cost nothing extra. Counters are process-wide, but each thread
+//! that loads the addon (the main thread, every worker) has its own exports
+//! and enables metrics for itself.
+//!
+//! Methods are swapped on the class prototypes, so every instance sees the
+//! wrappers. Functions are swapped on the addon's exports object, so a
+//! function copied out of it before metrics were enabled, as `index.js` and
+//! ESM named imports do when they load, keeps calling the original
+//! uncounted. Static methods and factories are defined read-only by napi,
+//! so they are not counted either.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::{c_void, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use napi::sys;
+use napi::{Env, JsFunction, JsObject, JsUnknown, NapiRaw, NapiValue, ValueType};
+
+use crate::error::Result;
+
+/// Upper bounds of the histogram buckets; a final bucket counts the rest.
+const BUCKET_BOUNDS_MS: [f64; 7] = [0.01, 0.1, 1.0, 10.0, 100.0, 1000.0, 10000.0];
+const BUCKETS: usize = BUCKET_BOUNDS_MS.len() + 1;
+
+#[derive(Default)]
+struct ExportStats {
+  calls: AtomicU64,
+  errors: AtomicU64,
+  total_ns: AtomicU64,
+  max_ns: AtomicU64,
+  buckets: [AtomicU64; BUCKETS],
+}
+
+impl ExportStats {
+  fn record(&self, elapsed: Duration, failed: bool) {
+    let ns = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+    self.calls.fetch_add(1, Ordering::Relaxed);
+    if failed {
+      self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    self.total_ns.fetch_add(ns, Ordering::Relaxed);
+    self.max_ns.fetch_max(ns, Ordering::Relaxed);
+    let ms = ns as f64 / 1e6;
+    let bucket = BUCKET_BOUNDS_MS
+      .iter()
+      .position(|&bound| ms <= bound)
+      .unwrap_or(BUCKETS - 1);
+    self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+  }
+
+  fn reset(&self) {
+    for counter in [&self.calls, &self.errors, &self.total_ns, &self.max_ns]
+      .into_iter()
+      .chain(&self.buckets)
+    {
+      counter.store(0, Ordering::Relaxed);
+    }
+  }
+}
+
+#[derive(Default)]
+struct Registry {
+  /// Stats by export name. Entries are leaked: there is one per export, and
+  /// workers loading the addon again reuse them.
+  exports: HashMap<String, &'static ExportStats>,
+  errors: HashMap<String, u64>,
+  since: Option<SystemTime>,
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+  static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+  REGISTRY
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+}
+
+fn stats_for(name: &str) -> &'static ExportStats {
+  registry()
+    .exports
+    .entry(name.to_string())
+    .or_insert_with(|| Box::leak(Box::default()))
+}
+
+#[derive(Clone, Copy)]
+struct ActiveCall {
+  stats: &'static ExportStats,
+  started: Instant,
+  deferred: bool,
+}
+
+/// A function property replaced by a wrapper, and what to put back.
+struct Swapped {
+  object: sys::napi_ref,
+  key: CString,
+  original: sys::napi_ref,
+}
+
+/// The addon as loaded on one thread.
+struct Instance {
+  exports: sys::napi_ref,
+  /// Empty while metrics are disabled.
+  swapped: Vec<Swapped>,
+}
+
+thread_local! {
+  /// The innermost export running on this thread while metrics are enabled.
+  static CURRENT: Cell<Option<ActiveCall>> = const { Cell::new(None) };
+  static INSTANCE: RefCell<Option<Instance>> = const { RefCell::new(None) };
+}
+
+fn enabled() -> bool {
+  INSTANCE.with(|instance| {
+    instance
+      .borrow()
+      .as_ref()
+      .is_some_and(|instance| !instance.swapped.is_empty())
+  })
+}
+
+/// A call whose result arrives later, through a Promise.
+pub struct Pending(ActiveCall);
+
+impl Pending {
+  pub fn finish(self, failed: bool) {
+    self.0.stats.record(self.0.started.elapsed(), failed);
+  }
+}
+
+/// Hands the export currently running on this thread over to a Promise, so
+/// its duration and outcome are recorded when `finish` is called instead of
+/// when the export returns. `None` when metrics are disabled.
+pub fn defer() -> Option<Pending> {
+  CURRENT.with(|current| {
+    let mut call = current.get()?;
+    call.deferred = true;
+    current.set(Some(call));
+    Some(Pending(call))
+  })
+}
+
+/// Counts an error thrown to JS with `code`.
+pub fn count_error(code: &str) {
+  if enabled() {
+    *registry().errors.entry(code.to_string()).or_default() += 1;
+  }
+}
+
+struct Wrapped {
+  original: sys::napi_ref,
+  stats: &'static ExportStats,
+}
+
+/// Up to this many arguments are read without allocating.
+const INLINE_ARGS: usize = 8;
+
+unsafe extern "C" fn call_wrapped(
+  env: sys::napi_env,
+  info: sys::napi_callback_info,
+) -> sys::napi_value {
+  let mut argc = INLINE_ARGS;
+  let mut inline = [ptr::null_mut(); INLINE_ARGS];
+  let mut this = ptr::null_mut();
+  let mut data = ptr::null_mut();
+  sys::napi_get_cb_info(
+    env,
+    info,
+    &mut argc,
+    inline.as_mut_ptr(),
+    &mut this,
+    &mut data,
+  );
+  let mut spilled = Vec::new();
+  let argv = if argc > INLINE_ARGS {
+    spilled.resize(argc, ptr::null_mut());
+    sys::napi_get_cb_info(
+      env,
+      info,
+      &mut argc,
+      spilled.as_mut_ptr(),
+      ptr::null_mut(),
+      ptr::null_mut(),
+    );
+    spilled.as_ptr()
+  } else {
+    inline.as_ptr()
+  };
+  let wrapped = &*(data as *const Wrapped);
+  let mut original = ptr::null_mut();
+  sys::napi_get_reference_value(env, wrapped.original, &mut original);
+  let mut result = ptr::null_mut();
+  let outer = CURRENT.with(|current| {
+    current.replace(Some(ActiveCall {
+      stats: wrapped.stats,
+      started: Instant::now(),
+      deferred: false,
+    }))
+  });
+  // A thrown exception stays pending and propagates to the caller.
+  let status = sys::napi_call_function(env, this, original, argc, argv, &mut result);
+  if let Some(call) = CURRENT.with(|current| current.replace(outer)) {
+    if !call.deferred {
+      call
+        .stats
+        .record(call.started.elapsed(), status != sys::Status::napi_ok);
+    }
+  }
+  result
+}
+
+unsafe extern "C" fn finalize_wrapped(env: sys::napi_env, data: *mut c_void, _: *mut c_void) {
+  let wrapped = Box::from_raw(data as *mut Wrapped);
+  sys::napi_delete_reference(env, wrapped.original);
+}
+
+/// Replaces the function `object[key]` with a wrapper recording into the
+/// stats for `name`.
+fn wrap(env: &Env, object: &JsObject, key: &str, name: &str) -> napi::Result<Swapped> {
+  let raw_env = env.raw();
+  let c_key = CString::new(key)?;
+  unsafe {
+    let mut original = ptr::null_mut();
+    napi::check_status!(sys::napi_get_named_property(
+      raw_env,
+      object.raw(),
+      c_key.as_ptr(),
+      &mut original
+    ))?;
+    let mut reference = ptr::null_mut();
+    napi::check_status!(sys::napi_create_reference(
+      raw_env,
+      original,
+      1,
+      &mut reference
+    ))?;
+    let mut kept = ptr::null_mut();
+    napi::check_status!(sys::napi_create_reference(raw_env, original, 1, &mut kept))?;
+    let mut holder = ptr::null_mut();
+    napi::check_status!(sys::napi_create_reference(
+      raw_env,
+      object.raw(),
+      1,
+      &mut holder
+    ))?;
+    let data = Box::into_raw(Box::new(Wrapped {
+      original: reference,
+      stats: stats_for(name),
+    }));
+    let mut wrapper = ptr::null_mut();
+    napi::check_status!(sys::napi_create_function(
+      raw_env,
+      c_key.as_ptr(),
+      key.len(),
+      Some(call_wrapped),
+      data as *mut c_void,
+      &mut wrapper
+    ))?;
+    napi::check_status!(sys::napi_add_finalizer(
+      raw_env,
+      wrapper,
+      data as *mut c_void,
+      Some(finalize_wrapped),
+      ptr::null_mut(),
+      ptr::null_mut()
+    ))?;
+    napi::check_status!(sys::napi_set_named_property(
+      raw_env,
+      object.raw(),
+      c_key.as_ptr(),
+      wrapper
+    ))?;
+    Ok(Swapped {
+      object: holder,
+      key: c_key,
+      original: kept,
+    })
+  }
+}
+
+/// Puts the original function back, leaving the wrapper to be collected.
+fn unwrap(env: &Env, swapped: Swapped) -> napi::Result<()> {
+  let raw_env = env.raw();
+  unsafe {
+    let mut object = ptr::null_mut();
+    napi::check_status!(sys::napi_get_reference_value(
+      raw_env,
+      swapped.object,
+      &mut object
+    ))?;
+    let mut original = ptr::null_mut();
+    napi::check_status!(sys::napi_get_reference_value(
+      raw_env,
+      swapped.original,
+      &mut original
+    ))?;
+    napi::check_status!(sys::napi_set_named_property(
+      raw_env,
+      object,
+      swapped.key.as_ptr(),
+      original
+    ))?;
+    sys::napi_delete_reference(raw_env, swapped.object);
+    sys::napi_delete_reference(raw_env, swapped.original);
+  }
+  Ok(())
+}
+
+/// Own writable properties of `object` whose values (not accessors) are
+/// functions.
+fn function_properties(env: &Env, object: &JsObject) -> napi::Result<Vec<String>> {
+  let global = env.get_global()?;
+  // `Object` and classes are functions, which the checked getters reject.
+  let object_ctor: JsObject = global.get_named_property_unchecked("Object")?;
+  let get_names: JsFunction = object_ctor.get_named_property("getOwnPropertyNames")?;
+  let get_descriptor: JsFunction = object_ctor.get_named_property("getOwnPropertyDescriptor")?;
+  let target = || unsafe { JsUnknown::from_raw_unchecked(env.raw(), object.raw()) };
+  let names: Vec<String> = env.from_js_value(get_names.call(None, &[target()])?)?;
+  let mut functions = Vec::new();
+  for name in names {
+    // Reading the descriptor rather than the property keeps getters from
+    // running against the prototype.
+    let key = env.create_string(&name)?.into_unknown();
+    let descriptor: JsObject = get_descriptor
+      .call(None, &[target(), key])?
+      .coerce_to_object()?;
+    let value: JsUnknown = descriptor.get_named_property("value")?;
+    let writable: Option<bool> = descriptor.get_named_property("writable")?;
+    if writable == Some(true) && value.get_type()? == ValueType::Function {
+      functions.push(name);
+    }
+  }
+  Ok(functions)
+}
+
+/// Wraps every exported function into `swapped`. Exports named in
+/// `PascalCase` are classes: their prototype methods are wrapped as
+/// `Class.method`, but not the constructor itself.
+fn instrument(env: &Env, exports: &JsObject, swapped: &mut Vec<Swapped>) -> napi::Result<()> {
+  for name in function_properties(env, exports)? {
+    if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+      swapped.push(wrap(env, exports, &name, &name)?);
+      continue;
+    }
+    let class: JsObject = exports.get_named_property_unchecked(&name)?;
+    let prototype: JsObject = class.get_named_property("prototype")?;
+    for method in function_properties(env, &prototype)? {
+      if method != "constructor" {
+        swapped.push(wrap(
+          env,
+          &prototype,
+          &method,
+          &format!("{}.{}", name, method),
+        )?);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Keeps hold of this thread's exports until metrics are enabled; nothing
+/// is wrapped yet.
+#[cfg_attr(not(test), module_exports)]
+#[cfg_attr(test, allow(dead_code))]
+fn init(exports: JsObject, env: Env) -> napi::Result<()> {
+  let mut reference = ptr::null_mut();
+  napi::check_status!(unsafe {
+    sys::napi_create_reference(env.raw(), exports.raw(), 1, &mut reference)
+  })?;
+  INSTANCE.with(|instance| {
+    *instance.borrow_mut() = Some(Instance {
+      exports: reference,
+      swapped: Vec::new(),
+    })
+  });
+  Ok(())
+}
+
+#[napi(object)]
+pub struct ExportMetrics {
+  /// `functionName`, or `ClassName.method` for methods.
+  pub name: String,
+  pub calls: i64,
+  /// Calls that threw, or whose Promise rejected.
+  pub errors: i64,
+  pub total_ms: f64,
+  pub max_ms: f64,
+  /// Call counts per latency bucket; see `bucketBoundsMs`.
+  pub histogram: Vec<i64>,
+}
+
+#[napi(object)]
+pub struct MetricsSnapshot {
+  /// Whether this thread is recording.
+  pub enabled: bool,
+  /// When counting started (Unix milliseconds), or null if never enabled.
+  pub since: Option<f64>,
+  /// Upper bounds of the histogram buckets. The last bucket of each
+  /// histogram counts calls slower than all of them.
+  pub bucket_bounds_ms: Vec<f64>,
+  /// Exports called at least once, by name.
+  pub exports: Vec<ExportMetrics>,
+  /// Errors thrown to JS, by `code`.
+  pub errors: HashMap<String, i64>,
+}
+
+/// Starts or stops recording on this thread by installing or removing the
+/// wrappers. Counters keep their values while stopped.
+#[napi(catch_unwind)]
+pub fn enable_metrics(env: Env, enabled: bool) -> Result<()> {
+  INSTANCE.with(|instance| -> Result<()> {
+    let mut instance = instance.borrow_mut();
+    let Some(instance) = instance.as_mut() else {
+      return Ok(());
+    };
+    if enabled && instance.swapped.is_empty() {
+      let exports: JsObject = unsafe {
+        let mut exports = ptr::null_mut();
+        napi::check_status!(sys::napi_get_reference_value(
+          env.raw(),
+          instance.exports,
+          &mut exports
+        ))?;
+        JsObject::from_raw_unchecked(env.raw(), exports)
+      };
+      let mut swapped = Vec::new();
+      let installed = instrument(&env, &exports, &mut swapped);
+      if let Err(e) = installed {
+        for swapped in swapped {
+          unwrap(&env, swapped)?;
+        }
+        return Err(e.into());
+      }
+      instance.swapped = swapped;
+    } else if !enabled {
+      for swapped in std::mem::take(&mut instance.swapped) {
+        unwrap(&env, swapped)?;
+      }
+    }
+    Ok(())
+  })?;
+  let mut registry = registry();
+  if enabled && registry.since.is_none() {
+    registry.since = Some(SystemTime::now());
+  }
+  Ok(())
+}
+
+/// Calls, errors and latencies per export since metrics were first enabled
+/// or last reset. For exports returning a Promise, durations run until the
+/// Promise settles where the work runs on the tokio runtime, and until the
+/// export returns otherwise.
+#[napi(catch_unwind)]
+pub fn get_metrics() -> MetricsSnapshot {
+  let registry = registry();
+  let mut exports: Vec<ExportMetrics> = registry
+    .exports
+    .iter()
+    .filter(|(_, stats)| stats.calls.load(Ordering::Relaxed) > 0)
+    .map(|(name, stats)| ExportMetrics {
+      name: name.clone(),
+      calls: stats.calls.load(Ordering::Relaxed) as i64,
+      errors: stats.errors.load(Ordering::Relaxed) as i64,
+      total_ms: stats.total_ns.load(Ordering::Relaxed) as f64 / 1e6,
+      max_ms: stats.max_ns.load(Ordering::Relaxed) as f64 / 1e6,
+      histogram: stats
+        .buckets
+        .iter()
+        .map(|b| b.load(Ordering::Relaxed) as i64)
+        .collect(),
+    })
+    .collect();
+  exports.sort_by(|a, b| a.name.cmp(&b.name));
+  MetricsSnapshot {
+    enabled: enabled(),
+    since: registry.since.map(|since| {
+      since
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        * 1000.0
+    }),
+    bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+    exports,
+    errors: registry
+      .errors
+      .iter()
+      .map(|(code, &count)| (code.clone(), count as i64))
+      .collect(),
+  }
+}
+
+/// Zeroes every counter and restarts `since`.
+#[napi(catch_unwind)]
+pub fn reset_metrics() {
+  let mut registry = registry();
+  for stats in registry.exports.values() {
+    stats.reset();
+  }
+  registry.errors.clear();
+  if registry.since.is_some() {
+    registry.since = Some(SystemTime::now());
+  }
+}
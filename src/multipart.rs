@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+
+use memchr::memmem::Finder;
+use napi::bindgen_prelude::*;
+use napi::{Env, JsFunction, JsUnknown, NapiValue, Ref};
+
+use crate::error::{AppError, Result};
+
+/// Headers of one part, as passed to `onPart`.
+#[napi(object)]
+#[derive(Default)]
+pub struct PartHeaders {
+  /// The `name` from `Content-Disposition`.
+  pub name: Option<String>,
+  /// The `filename` (or UTF-8 `filename*`) from `Content-Disposition`; set
+  /// for file uploads, absent for plain fields.
+  pub filename: Option<String>,
+  pub content_type: Option<String>,
+  /// Every header, names lowercased.
+  pub headers: HashMap<String, String>,
+}
+
+/// One part collected by `parseMultipart`.
+#[napi(object)]
+pub struct Part {
+  pub name: Option<String>,
+  pub filename: Option<String>,
+  pub content_type: Option<String>,
+  pub headers: HashMap<String, String>,
+  pub data: Buffer,
+}
+
+#[napi(object)]
+pub struct MultipartHandlers {
+  /// A part begins.
+  #[napi(ts_type = "(part: PartHeaders) => void")]
+  pub on_part: Option<JsFunction>,
+  /// The next piece of the current part's body. A part's body may arrive in
+  /// any number of chunks, including none.
+  #[napi(ts_type = "(chunk: Buffer) => void")]
+  pub on_data: Option<JsFunction>,
+  /// The current part is complete.
+  #[napi(ts_type = "() => void")]
+  pub on_part_end: Option<JsFunction>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct MultipartOptions {
+  /// Throw `ERR_INVALID_INPUT` when one part's header block grows past
+  /// this many bytes. Defaults to 16 KiB.
+  pub max_header_bytes: Option<u32>,
+}
+
+const DEFAULT_MAX_HEADER_BYTES: usize = 16 * 1024;
+
+enum Event<'a> {
+  Part(PartHeaders),
+  Data(&'a [u8]),
+  PartEnd,
+}
+
+#[derive(PartialEq)]
+enum State {
+  /// Before the first boundary; the bytes are discarded.
+  Preamble,
+  /// Just past a boundary, waiting for `--` or the end of its line.
+  AfterBoundary,
+  Headers,
+  Body,
+  /// Past the closing boundary; the rest is discarded.
+  Epilogue,
+  /// `end()` was called, or an error or a throwing handler stopped parsing.
+  Finished,
+}
+
+/// Accepts the bare boundary or a whole `multipart/...; boundary=...`
+/// Content-Type header.
+fn boundary_of(value: &str) -> Result<String> {
+  let boundary = if value
+    .trim_start()
+    .to_ascii_lowercase()
+    .starts_with("multipart/")
+  {
+    value
+      .split(';')
+      .skip(1)
+      .filter_map(|param| param.split_once('='))
+      .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+      .map(|(_, v)| v.trim().trim_matches('"').to_string())
+      .ok_or_else(|| AppError::InvalidInput(format!("no boundary in {:?}", value)))?
+  } else {
+    value.to_string()
+  };
+  if boundary.is_empty() || boundary.len() > 70 {
+    return Err(AppError::InvalidInput(
+      "boundary must be 1 to 70 characters".into(),
+    ));
+  }
+  Ok(boundary)
+}
+
+/// Decodes the percent-escapes of an RFC 5987 `filename*` value such as
+/// `UTF-8''na%C3%AFve.txt`.
+fn ext_value(value: &str) -> Option<String> {
+  let (charset, rest) = value.split_once('\'')?;
+  let (_, encoded) = rest.split_once('\'')?;
+  if !charset.eq_ignore_ascii_case("utf-8") {
+    return None;
+  }
+  let bytes = encoded.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    if bytes[i] == b'%' && i + 2 < bytes.len() {
+      out.push(u8::from_str_radix(encoded.get(i + 1..i + 3)?, 16).ok()?);
+      i += 3;
+    } else {
+      out.push(bytes[i]);
+      i += 1;
+    }
+  }
+  String::from_utf8(out).ok()
+}
+
+/// Splits `form-data; name="a"; filename="b.txt"` into its parameters.
+fn disposition_params(value: &str) -> HashMap<String, String> {
+  let mut params = HashMap::new();
+  let mut rest = value.split_once(';').map_or("", |(_, rest)| rest);
+  while let Some((key, after)) = rest.split_once('=') {
+    let key = key.trim().to_ascii_lowercase();
+    let after = after.trim_start();
+    let (value, next) = if let Some(quoted) = after.strip_prefix('"') {
+      // Quoted strings may contain `;` and backslash escapes.
+      let mut value = String::new();
+      let mut chars = quoted.char_indices();
+      let mut end = quoted.len();
+      while let Some((i, c)) = chars.next() {
+        match c {
+          '\\' => {
+            if let Some((_, escaped)) = chars.next() {
+              value.push(escaped);
+            }
+          }
+          '"' => {
+            end = i + 1;
+            break;
+          }
+          c => value.push(c),
+        }
+      }
+      let next = quoted[end..].split_once(';').map_or("", |(_, next)| next);
+      (value, next)
+    } else {
+      match after.split_once(';') {
+        Some((value, next)) => (value.trim().to_string(), next),
+        None => (after.trim().to_string(), ""),
+      }
+    };
+    params.insert(key, value);
+    rest = next;
+  }
+  params
+}
+
+fn parse_headers(block: &[u8]) -> PartHeaders {
+  let mut part = PartHeaders::default();
+  for line in String::from_utf8_lossy(block).split("\r\n") {
+    if let Some((name, value)) = line.split_once(':') {
+      part
+        .headers
+        .insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+  }
+  if let Some(disposition) = part.headers.get("content-disposition") {
+    let mut params = disposition_params(disposition);
+    part.name = params.remove("name");
+    part.filename = params
+      .get("filename*")
+      .and_then(|v| ext_value(v))
+      .or_else(|| params.remove("filename"));
+  }
+  part.content_type = part.headers.get("content-type").cloned();
+  part
+}
+
+/// The byte-level state machine, shared by `MultipartParser` and
+/// `parseMultipart`.
+struct Core {
+  /// `\r\n--boundary`. The body is treated as starting with `\r\n`, so the
+  /// first boundary needs no special case.
+  delimiter: Finder<'static>,
+  delimiter_len: usize,
+  max_header_bytes: usize,
+  pending: Vec<u8>,
+  state: State,
+}
+
+impl Core {
+  fn new(boundary: &str, max_header_bytes: usize) -> Result<Self> {
+    let delimiter = format!("\r\n--{}", boundary_of(boundary)?);
+    Ok(Core {
+      delimiter_len: delimiter.len(),
+      delimiter: Finder::new(delimiter.as_bytes()).into_owned(),
+      max_header_bytes,
+      pending: b"\r\n".to_vec(),
+      state: State::Preamble,
+    })
+  }
+
+  fn feed(&mut self, chunk: &[u8], sink: &mut dyn FnMut(Event) -> Result<()>) -> Result<()> {
+    match self.state {
+      State::Epilogue => return Ok(()),
+      State::Finished => return Err(AppError::Closed("MultipartParser is finished".into())),
+      _ => {}
+    }
+    self.pending.extend_from_slice(chunk);
+    let result = self.process(sink);
+    if result.is_err() {
+      self.state = State::Finished;
+    }
+    result
+  }
+
+  fn process(&mut self, sink: &mut dyn FnMut(Event) -> Result<()>) -> Result<()> {
+    let mut start = 0;
+    loop {
+      let input = &self.pending[start..];
+      match self.state {
+        State::Preamble | State::Body => match self.delimiter.find(input) {
+          Some(at) => {
+            if self.state == State::Body {
+              if at > 0 {
+                sink(Event::Data(&input[..at]))?;
+              }
+              sink(Event::PartEnd)?;
+            }
+            start += at + self.delimiter_len;
+            self.state = State::AfterBoundary;
+          }
+          None => {
+            // Keep back what could be the start of a delimiter.
+            let safe = input.len().saturating_sub(self.delimiter_len - 1);
+            if self.state == State::Body && safe > 0 {
+              sink(Event::Data(&input[..safe]))?;
+            }
+            start += safe;
+            break;
+          }
+        },
+        State::AfterBoundary => {
+          if input.starts_with(b"--") {
+            self.state = State::Epilogue;
+            start = self.pending.len();
+            break;
+          }
+          if input == b"-" {
+            break;
+          }
+          let padding = input
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+          let line = &input[padding..];
+          if line.starts_with(b"\r\n") {
+            start += padding + 2;
+            self.state = State::Headers;
+          } else if line.len() >= 2 || (line.len() == 1 && line[0] != b'\r') {
+            return Err(AppError::Parse(
+              "invalid multipart body: expected a line break after the boundary".into(),
+            ));
+          } else {
+            break;
+          }
+        }
+        State::Headers => {
+          let block_len = if input.starts_with(b"\r\n") {
+            Some(0)
+          } else {
+            memchr::memmem::find(input, b"\r\n\r\n")
+          };
+          match block_len {
+            Some(len) => {
+              if len > self.max_header_bytes {
+                return Err(self.headers_too_large());
+              }
+              let part = parse_headers(&input[..len]);
+              start += if len == 0 { 2 } else { len + 4 };
+              self.state = State::Body;
+              sink(Event::Part(part))?;
+            }
+            None if input.len() > self.max_header_bytes => return Err(self.headers_too_large()),
+            None => break,
+          }
+        }
+        State::Epilogue | State::Finished => {
+          start = self.pending.len();
+          break;
+        }
+      }
+    }
+    self.pending.drain(..start);
+    Ok(())
+  }
+
+  fn headers_too_large(&self) -> AppError {
+    AppError::InvalidInput(format!(
+      "multipart part headers exceed {} bytes",
+      self.max_header_bytes
+    ))
+  }
+
+  fn finish(&mut self) -> Result<()> {
+    let state = std::mem::replace(&mut self.state, State::Finished);
+    self.pending = Vec::new();
+    match state {
+      State::Epilogue | State::Finished => Ok(()),
+      _ => Err(AppError::Parse(
+        "invalid multipart body: ended before the closing boundary".into(),
+      )),
+    }
+  }
+}
+
+/// A handler kept alive between calls.
+struct Handler(Ref<()>);
+
+impl Handler {
+  fn new(env: &Env, callback: Option<JsFunction>) -> Result<Option<Self>> {
+    callback
+      .map(|f| Ok(Handler(env.create_reference(f)?)))
+      .transpose()
+  }
+
+  fn call(&self, env: &Env, args: &[JsUnknown]) -> Result<()> {
+    let callback: JsFunction = env.get_reference_value(&self.0)?;
+    callback.call(None, args)?;
+    Ok(())
+  }
+}
+
+/// An incremental `multipart/form-data` parser for upload handlers.
+///
+/// Feed it the request body as it arrives. Each part is reported to the
+/// handlers as `onPart(headers)`, then its body as any number of
+/// `onData(chunk)` calls, then `onPartEnd()`, so files are streamed instead
+/// of buffered. Handlers run synchronously inside `push`; one that throws
+/// stops the parser and the error propagates from `push`. A malformed body
+/// throws `ERR_PARSE`.
+#[napi(custom_finalize)]
+pub struct MultipartParser {
+  core: Core,
+  on_part: Option<Handler>,
+  on_data: Option<Handler>,
+  on_part_end: Option<Handler>,
+}
+
+#[napi]
+impl MultipartParser {
+  /// `boundary` is the boundary itself or the request's whole
+  /// `Content-Type` header.
+  #[napi(catch_unwind, constructor)]
+  pub fn new(
+    env: Env,
+    boundary: String,
+    handlers: MultipartHandlers,
+    options: Option<MultipartOptions>,
+  ) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let max_header_bytes = options
+      .max_header_bytes
+      .map_or(DEFAULT_MAX_HEADER_BYTES, |n| n as usize);
+    Ok(MultipartParser {
+      core: Core::new(&boundary, max_header_bytes)?,
+      on_part: Handler::new(&env, handlers.on_part)?,
+      on_data: Handler::new(&env, handlers.on_data)?,
+      on_part_end: Handler::new(&env, handlers.on_part_end)?,
+    })
+  }
+
+  /// Parses the next chunk of the body. Anything after the closing boundary
+  /// is ignored; pushing after `end()` throws `ERR_CLOSED`.
+  #[napi(catch_unwind)]
+  pub fn push(&mut self, env: Env, chunk: Buffer) -> Result<()> {
+    let (on_part, on_data, on_part_end) = (&self.on_part, &self.on_data, &self.on_part_end);
+    self.core.feed(&chunk, &mut |event| match event {
+      Event::Part(part) => match on_part {
+        Some(handler) => {
+          let part = unsafe {
+            JsUnknown::from_raw_unchecked(env.raw(), PartHeaders::to_napi_value(env.raw(), part)?)
+          };
+          handler.call(&env, &[part])
+        }
+        None => Ok(()),
+      },
+      Event::Data(bytes) => match on_data {
+        Some(handler) => {
+          let chunk = env.create_buffer_copy(bytes)?.into_raw().into_unknown();
+          handler.call(&env, &[chunk])
+        }
+        None => Ok(()),
+      },
+      Event::PartEnd => match on_part_end {
+        Some(handler) => handler.call(&env, &[]),
+        None => Ok(()),
+      },
+    })
+  }
+
+  /// Marks the end of the body. Throws `ERR_PARSE` if the closing boundary
+  /// has not been seen.
+  #[napi(catch_unwind)]
+  pub fn end(&mut self) -> Result<()> {
+    self.core.finish()
+  }
+
+  /// Whether the closing boundary has been seen.
+  #[napi(catch_unwind, getter)]
+  pub fn done(&self) -> bool {
+    matches!(self.core.state, State::Epilogue)
+  }
+}
+
+impl ObjectFinalize for MultipartParser {
+  fn finalize(self, env: Env) -> napi::Result<()> {
+    for handler in [self.on_part, self.on_data, self.on_part_end]
+      .into_iter()
+      .flatten()
+    {
+      let mut reference = handler.0;
+      reference.unref(env)?;
+    }
+    Ok(())
+  }
+}
+
+/// Parses a whole `multipart/form-data` body at once, collecting each part's
+/// body into a Buffer. `boundary` is as for `MultipartParser`.
+#[napi(catch_unwind)]
+pub fn parse_multipart(body: Buffer, boundary: String) -> Result<Vec<Part>> {
+  let mut core = Core::new(&boundary, DEFAULT_MAX_HEADER_BYTES)?;
+  let mut parts: Vec<(PartHeaders, Vec<u8>)> = Vec::new();
+  core.feed(&body, &mut |event| {
+    match event {
+      Event::Part(headers) => parts.push((headers, Vec::new())),
+      Event::Data(bytes) => {
+        if let Some((_, data)) = parts.last_mut() {
+          data.extend_from_slice(bytes);
+        }
+      }
+      Event::PartEnd => {}
+    }
+    Ok(())
+  })?;
+  core.finish()?;
+  Ok(
+    parts
+      .into_iter()
+      .map(|(headers, data)| Part {
+        name: headers.name,
+        filename: headers.filename,
+        content_type: headers.content_type,
+        headers: headers.headers,
+        data: data.into(),
+      })
+      .collect(),
+  )
+}
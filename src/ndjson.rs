@@ -0,0 +1,226 @@
+use std::ops::Range;
+
+use memchr::memchr;
+use napi::bindgen_prelude::*;
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[napi(object)]
+#[derive(Default)]
+pub struct NdjsonParserOptions {
+  /// Skip lines that are not valid JSON instead of throwing `ERR_PARSE`.
+  pub skip_invalid: Option<bool>,
+  /// Throw `ERR_INVALID_INPUT` once a single line grows past this many
+  /// bytes, instead of buffering it without bound.
+  pub max_line_bytes: Option<u32>,
+}
+
+/// An incremental NDJSON (newline-delimited JSON) parser.
+///
+/// Feed it chunks as they arrive, e.g. from a stream's `data` events; a record
+/// split across chunks is held back until its newline arrives. Blank lines
+/// and `\r\n` line endings are accepted.
+#[napi]
+pub struct NdjsonParser {
+  pending: Vec<u8>,
+  /// Number of lines consumed so far, for error messages.
+  line: u64,
+  skip_invalid: bool,
+  max_line_bytes: Option<usize>,
+  /// Set after an overlong line was rejected: the rest of it is dropped as it
+  /// arrives.
+  discarding: bool,
+}
+
+#[napi]
+impl NdjsonParser {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<NdjsonParserOptions>) -> Self {
+    let options = options.unwrap_or_default();
+    NdjsonParser {
+      pending: Vec::new(),
+      line: 0,
+      skip_invalid: options.skip_invalid.unwrap_or(false),
+      max_line_bytes: options.max_line_bytes.map(|n| n as usize),
+      discarding: false,
+    }
+  }
+
+  /// Parses every record completed by `chunk`. Records are returned, or
+  /// passed one by one to `onRecord` (in which case the result is empty).
+  ///
+  /// An invalid line throws `ERR_PARSE` naming its line number. It is
+  /// dropped, along with the records before it in the same chunk; the lines
+  /// after it stay buffered for the next call.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "chunk: Buffer | string, onRecord?: (record: unknown) => void",
+    ts_return_type = "Array<unknown>"
+  )]
+  pub fn push(
+    &mut self,
+    env: Env,
+    chunk: Either<Buffer, String>,
+    on_record: Option<JsFunction>,
+  ) -> Result<JsObject> {
+    match &chunk {
+      Either::A(bytes) => self.pending.extend_from_slice(bytes),
+      Either::B(text) => self.pending.extend_from_slice(text.as_bytes()),
+    }
+    let mut batch = Batch::default();
+    let mut start = 0;
+    if self.discarding {
+      match memchr(b'\n', &self.pending) {
+        Some(end) => {
+          self.discarding = false;
+          start = end + 1;
+        }
+        None => {
+          self.pending.clear();
+          return batch.deliver(&env, on_record);
+        }
+      }
+    }
+    while let Some(end) = memchr(b'\n', &self.pending[start..]).map(|i| start + i) {
+      let line = start..end;
+      start = end + 1;
+      self.line += 1;
+      match self.check_line(line) {
+        Ok(Some(record)) => batch.add(&self.pending[record]),
+        Ok(None) => {}
+        Err(e) => {
+          self.consume(start);
+          return Err(e);
+        }
+      }
+    }
+    self.consume(start);
+    if let Some(max) = self.max_line_bytes {
+      if self.pending.len() > max {
+        self.pending.clear();
+        self.discarding = true;
+        self.line += 1;
+        return Err(AppError::InvalidInput(format!(
+          "line {} is longer than {} bytes",
+          self.line, max
+        )));
+      }
+    }
+    batch.deliver(&env, on_record)
+  }
+
+  /// Parses the final record if the input did not end with a newline, and
+  /// resets the parser. Returns it (or passes it to `onRecord`) like `push`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "onRecord?: (record: unknown) => void",
+    ts_return_type = "Array<unknown>"
+  )]
+  pub fn end(&mut self, env: Env, on_record: Option<JsFunction>) -> Result<JsObject> {
+    let mut batch = Batch::default();
+    if self.discarding {
+      self.discarding = false;
+      self.pending.clear();
+    }
+    if !self.pending.is_empty() {
+      self.line += 1;
+      let checked = self.check_line(0..self.pending.len());
+      if let Ok(Some(record)) = &checked {
+        batch.add(&self.pending[record.clone()]);
+      }
+      self.pending.clear();
+      checked?;
+    }
+    self.line = 0;
+    batch.deliver(&env, on_record)
+  }
+
+  /// Bytes of an incomplete line waiting for its newline.
+  #[napi(catch_unwind, getter)]
+  pub fn buffered(&self) -> u32 {
+    self.pending.len() as u32
+  }
+}
+
+impl NdjsonParser {
+  /// Validates one line and returns the range of its JSON text, or `None`
+  /// for a blank (or skipped invalid) line.
+  fn check_line(&self, mut range: Range<usize>) -> Result<Option<Range<usize>>> {
+    if self.line == 1 && self.pending[range.clone()].starts_with(BOM) {
+      range.start += BOM.len();
+    }
+    if self.pending[range.clone()].ends_with(b"\r") {
+      range.end -= 1;
+    }
+    let line = &self.pending[range.clone()];
+    if line.iter().all(u8::is_ascii_whitespace) {
+      return Ok(None);
+    }
+    match serde_json::from_slice::<IgnoredAny>(line) {
+      Ok(_) => Ok(Some(range)),
+      Err(_) if self.skip_invalid => Ok(None),
+      Err(e) => Err(AppError::Parse(format!(
+        "invalid JSON on line {}: {}",
+        self.line, e
+      ))),
+    }
+  }
+
+  fn consume(&mut self, upto: usize) {
+    self.pending.drain(..upto);
+  }
+}
+
+/// Validated records joined into the text of one JSON array. Building the
+/// objects with a single `JSON.parse` call is much faster than creating them
+/// one property at a time through napi.
+#[derive(Default)]
+struct Batch {
+  text: Vec<u8>,
+  len: u32,
+}
+
+impl Batch {
+  fn add(&mut self, record: &[u8]) {
+    self.text.push(if self.len == 0 { b'[' } else { b',' });
+    self.text.extend_from_slice(record);
+    self.len += 1;
+  }
+
+  fn deliver(mut self, env: &Env, on_record: Option<JsFunction>) -> Result<JsObject> {
+    if self.len == 0 {
+      return Ok(env.create_array_with_length(0)?);
+    }
+    self.text.push(b']');
+    let json: JsObject = env.get_global()?.get_named_property("JSON")?;
+    let parse: JsFunction = json.get_named_property("parse")?;
+    let text = env.create_string(&String::from_utf8_lossy(&self.text))?;
+    let records = parse.call(Some(&json), &[text])?.coerce_to_object()?;
+    let Some(callback) = on_record else {
+      return Ok(records);
+    };
+    for i in 0..self.len {
+      let record: JsUnknown = records.get_element(i)?;
+      callback.call(None, &[record])?;
+    }
+    Ok(env.create_array_with_length(0)?)
+  }
+}
+
+/// Serializes each value as one line of JSON, each followed by `\n`.
+#[napi(catch_unwind, ts_args_type = "records: Array<unknown>")]
+pub fn ndjson_stringify(env: Env, records: JsUnknown) -> Result<Buffer> {
+  let records: Vec<Value> = from_js(&env, records)?;
+  let mut out = Vec::new();
+  for record in &records {
+    serde_json::to_writer(&mut out, record)?;
+    out.push(b'\n');
+  }
+  Ok(out.into())
+}
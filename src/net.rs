@@ -0,0 +1,532 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+
+use crate::error::{self, AppError, Result};
+use crate::lifecycle::{self, Resource};
+
+const READ_BUFFER_BYTES: usize = 64 * 1024;
+/// Pause after a failed `accept` (e.g. out of file descriptors) before
+/// trying again, so the loop does not spin.
+const ACCEPT_RETRY: Duration = Duration::from_millis(50);
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ListenOptions {
+  /// TCP port; 0 (the default) picks a free one.
+  pub port: Option<u32>,
+  /// Address to bind for TCP. Defaults to `127.0.0.1`; use `0.0.0.0` or
+  /// `::` to accept connections from other hosts.
+  pub host: Option<String>,
+  /// Listen on this Unix domain socket instead of TCP. The socket file is
+  /// removed when the server closes.
+  pub path: Option<String>,
+}
+
+#[napi(object)]
+pub struct ServerAddress {
+  pub host: Option<String>,
+  pub port: Option<u32>,
+  pub path: Option<String>,
+}
+
+enum Command {
+  Write(Vec<u8>),
+  End,
+}
+
+type ConnectionListener = ThreadsafeFunction<(u32, String), ErrorStrategy::Fatal>;
+type DataListener = ThreadsafeFunction<(u32, Vec<u8>), ErrorStrategy::Fatal>;
+type CloseListener = ThreadsafeFunction<(u32, Option<String>), ErrorStrategy::Fatal>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+  Idle,
+  Binding,
+  Listening,
+  Closed,
+}
+
+struct Shared {
+  state: State,
+  /// Dropping it stops the accept loop.
+  stop: Option<oneshot::Sender<()>>,
+  next_id: u32,
+  /// Write queues of the open connections; dropping one ends its
+  /// connection.
+  connections: HashMap<u32, UnboundedSender<Command>>,
+  /// Connection tasks still running, including ones already removed from
+  /// `connections` that have yet to report `close`.
+  running: usize,
+  on_connection: Vec<ConnectionListener>,
+  on_data: Vec<DataListener>,
+  on_close: Vec<CloseListener>,
+}
+
+type SharedState = Arc<Mutex<Shared>>;
+
+fn lock(shared: &SharedState) -> MutexGuard<'_, Shared> {
+  shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Shared {
+  /// Stops accepting and ends every connection.
+  fn close(&mut self) {
+    self.state = State::Closed;
+    self.stop = None;
+    self.connections.clear();
+    self.release_if_done();
+  }
+
+  /// Drops the listeners once the server is closed and the last connection
+  /// has reported `close`, so they no longer keep the process alive.
+  fn release_if_done(&mut self) {
+    if self.state == State::Closed && self.running == 0 {
+      self.on_connection.clear();
+      self.on_data.clear();
+      self.on_close.clear();
+    }
+  }
+}
+
+impl Resource for Mutex<Shared> {
+  fn kind(&self) -> &'static str {
+    "server"
+  }
+
+  fn active(&self) -> bool {
+    let shared = self.lock().unwrap_or_else(|e| e.into_inner());
+    matches!(shared.state, State::Binding | State::Listening) || shared.running > 0
+  }
+
+  /// Closes the server and drops the listeners at once instead of waiting
+  /// for connections to report `close`.
+  fn shutdown(&self) {
+    let mut shared = self.lock().unwrap_or_else(|e| e.into_inner());
+    shared.close();
+    shared.on_connection.clear();
+    shared.on_data.clear();
+    shared.on_close.clear();
+  }
+}
+
+/// Calls every listener with `value()` and waits until they have all
+/// returned, so events reach JS in order and a slow handler throttles
+/// reading instead of chunks piling up.
+async fn deliver<T: 'static>(
+  listeners: Vec<ThreadsafeFunction<T, ErrorStrategy::Fatal>>,
+  value: impl Fn() -> T,
+) {
+  let mut done = Vec::with_capacity(listeners.len());
+  for listener in &listeners {
+    let (done_tx, done_rx) = oneshot::channel();
+    listener.call_with_return_value(
+      value(),
+      ThreadsafeFunctionCallMode::NonBlocking,
+      move |_: JsUnknown| {
+        let _ = done_tx.send(());
+        Ok(())
+      },
+    );
+    done.push(done_rx);
+  }
+  for done_rx in done {
+    // An error means the callback threw or the listener went away.
+    let _ = done_rx.await;
+  }
+}
+
+trait Stream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> Stream for S {}
+
+enum Listener {
+  Tcp(tokio::net::TcpListener),
+  #[cfg(unix)]
+  Unix(tokio::net::UnixListener, String),
+}
+
+impl Listener {
+  async fn bind(options: ListenOptions) -> Result<(Listener, ServerAddress)> {
+    if let Some(path) = options.path {
+      if options.port.is_some() || options.host.is_some() {
+        return Err(AppError::InvalidInput(
+          "listen takes either a path or a port and host, not both".into(),
+        ));
+      }
+      #[cfg(unix)]
+      {
+        let listener = tokio::net::UnixListener::bind(&path).map_err(|e| AppError::io(&path, e))?;
+        let address = ServerAddress {
+          host: None,
+          port: None,
+          path: Some(path.clone()),
+        };
+        return Ok((Listener::Unix(listener, path), address));
+      }
+      #[cfg(not(unix))]
+      return Err(AppError::Unsupported(format!(
+        "Unix domain sockets are not supported on this platform: {}",
+        path
+      )));
+    }
+    let host = options.host.unwrap_or_else(|| "127.0.0.1".to_string());
+    let port = u16::try_from(options.port.unwrap_or(0)).map_err(|_| {
+      AppError::InvalidInput(format!("invalid port: {}", options.port.unwrap_or(0)))
+    })?;
+    let listener = tokio::net::TcpListener::bind((host.as_str(), port))
+      .await
+      .map_err(|e| AppError::Network(format!("cannot listen on {}:{}: {}", host, port, e)))?;
+    let local = listener.local_addr()?;
+    let address = ServerAddress {
+      host: Some(local.ip().to_string()),
+      port: Some(local.port() as u32),
+      path: None,
+    };
+    Ok((Listener::Tcp(listener), address))
+  }
+
+  /// Accepts the next connection, with the peer's address as text.
+  async fn accept(&self) -> std::io::Result<(Box<dyn Stream>, String)> {
+    match self {
+      Listener::Tcp(listener) => {
+        let (stream, peer) = listener.accept().await?;
+        stream.set_nodelay(true)?;
+        Ok((Box::new(stream), peer.to_string()))
+      }
+      #[cfg(unix)]
+      Listener::Unix(listener, path) => {
+        let (stream, _) = listener.accept().await?;
+        Ok((Box::new(stream), path.clone()))
+      }
+    }
+  }
+}
+
+impl Drop for Listener {
+  fn drop(&mut self) {
+    #[cfg(unix)]
+    if let Listener::Unix(_, path) = self {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+async fn accept_loop(listener: Listener, mut stop: oneshot::Receiver<()>, shared: SharedState) {
+  loop {
+    let accepted = tokio::select! {
+      // Resolves with an error once the sender is dropped.
+      _ = &mut stop => break,
+      accepted = listener.accept() => accepted,
+    };
+    let (stream, peer) = match accepted {
+      Ok(accepted) => accepted,
+      Err(e) => {
+        tracing::warn!(error = %e, "accept failed");
+        tokio::time::sleep(ACCEPT_RETRY).await;
+        continue;
+      }
+    };
+    let (tx, rx) = unbounded_channel();
+    let id = {
+      let mut guard = lock(&shared);
+      if guard.state != State::Listening {
+        break;
+      }
+      guard.next_id = guard.next_id.wrapping_add(1);
+      let id = guard.next_id;
+      guard.connections.insert(id, tx);
+      guard.running += 1;
+      id
+    };
+    tracing::debug!(id, peer, "connection accepted");
+    let shared = shared.clone();
+    tokio::spawn(async move {
+      let listeners = lock(&shared).on_connection.clone();
+      deliver(listeners, || (id, peer.clone())).await;
+      serve(stream, id, rx, shared).await;
+    });
+  }
+}
+
+enum Step {
+  Command(Option<Command>),
+  Read(std::io::Result<usize>),
+}
+
+/// Pumps one connection until either side ends it, then reports `close`.
+async fn serve(
+  stream: Box<dyn Stream>,
+  id: u32,
+  mut commands: UnboundedReceiver<Command>,
+  shared: SharedState,
+) {
+  let (mut reader, mut writer) = tokio::io::split(stream);
+  let mut buf = vec![0; READ_BUFFER_BYTES];
+  let error = loop {
+    let step = tokio::select! {
+      command = commands.recv() => Step::Command(command),
+      read = reader.read(&mut buf) => Step::Read(read),
+    };
+    match step {
+      Step::Command(Some(Command::Write(data))) => {
+        if let Err(e) = writer.write_all(&data).await {
+          break Some(e.to_string());
+        }
+      }
+      // `end`, `close`, or the server was garbage-collected.
+      Step::Command(Some(Command::End) | None) => {
+        let _ = writer.shutdown().await;
+        break None;
+      }
+      // The peer finished sending; like Node's default (`allowHalfOpen:
+      // false`), end our side too.
+      Step::Read(Ok(0)) => {
+        let _ = writer.shutdown().await;
+        break None;
+      }
+      Step::Read(Ok(n)) => {
+        let listeners = lock(&shared).on_data.clone();
+        deliver(listeners, || (id, buf[..n].to_vec())).await;
+      }
+      Step::Read(Err(e)) => break Some(e.to_string()),
+    }
+  };
+  tracing::debug!(id, error, "connection closed");
+  let on_close = {
+    let mut guard = lock(&shared);
+    guard.connections.remove(&id);
+    guard.on_close.clone()
+  };
+  for listener in &on_close {
+    listener.call((id, error.clone()), ThreadsafeFunctionCallMode::NonBlocking);
+  }
+  let mut guard = lock(&shared);
+  guard.running -= 1;
+  guard.release_if_done();
+}
+
+/// A TCP or Unix domain socket server whose accept loop and sockets run on
+/// the tokio runtime. Connections are identified by a numeric id, and their
+/// events are delivered to listeners in order: `connection`, any number of
+/// `data`, then `close`.
+///
+/// A server listens once; create a new one to listen again. While
+/// listening, registered listeners keep the process alive.
+#[napi(custom_finalize)]
+pub struct NativeServer {
+  shared: SharedState,
+}
+
+#[napi]
+impl NativeServer {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    NativeServer {
+      shared: Arc::new(Mutex::new(Shared {
+        state: State::Idle,
+        stop: None,
+        next_id: 0,
+        connections: HashMap::new(),
+        running: 0,
+        on_connection: Vec::new(),
+        on_data: Vec::new(),
+        on_close: Vec::new(),
+      })),
+    }
+  }
+
+  /// Starts accepting connections on a TCP port or a Unix domain socket.
+  /// Resolves with the bound address, e.g. the port picked for `port: 0`.
+  #[napi(catch_unwind, ts_return_type = "Promise<ServerAddress>")]
+  pub fn listen(&self, env: Env, options: Option<ListenOptions>) -> Result<JsObject> {
+    {
+      let mut shared = lock(&self.shared);
+      if shared.state != State::Idle {
+        return Err(AppError::InvalidInput(
+          "NativeServer has already listened".into(),
+        ));
+      }
+      shared.state = State::Binding;
+    }
+    lifecycle::register(&env, &self.shared)?;
+    let shared = self.shared.clone();
+    let options = options.unwrap_or_default();
+    error::spawn(&env, async move {
+      let bound = Listener::bind(options).await;
+      let mut guard = lock(&shared);
+      let (listener, address) = match bound {
+        Ok(bound) if guard.state == State::Binding => bound,
+        // Closed while binding.
+        Ok(_) => return Err(AppError::Closed("NativeServer is closed".into())),
+        Err(e) => {
+          guard.close();
+          return Err(e);
+        }
+      };
+      let (stop_tx, stop_rx) = oneshot::channel();
+      guard.state = State::Listening;
+      guard.stop = Some(stop_tx);
+      drop(guard);
+      tracing::debug!(
+        host = address.host.as_deref(),
+        port = address.port,
+        path = address.path.as_deref(),
+        "listening"
+      );
+      tokio::spawn(accept_loop(listener, stop_rx, shared));
+      Ok(address)
+    })
+  }
+
+  /// Registers `callback(connId, remoteAddress)`, called for each accepted
+  /// connection before any of its data.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (connId: number, remoteAddress: string) => void"
+  )]
+  pub fn on_connection(&self, callback: JsFunction) -> Result<()> {
+    let listener: ConnectionListener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(u32, String)>| {
+        let (id, peer) = ctx.value;
+        Ok(vec![
+          ctx.env.create_uint32(id)?.into_unknown(),
+          ctx.env.create_string_from_std(peer)?.into_unknown(),
+        ])
+      })?;
+    self.listeners()?.on_connection.push(listener);
+    Ok(())
+  }
+
+  /// Registers `callback(connId, data)` for bytes received on any
+  /// connection. The socket is not read again until every data listener has
+  /// returned.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (connId: number, data: Buffer) => void"
+  )]
+  pub fn on_data(&self, callback: JsFunction) -> Result<()> {
+    let listener: DataListener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(u32, Vec<u8>)>| {
+        let (id, data) = ctx.value;
+        Ok(vec![
+          ctx.env.create_uint32(id)?.into_unknown(),
+          ctx
+            .env
+            .create_buffer_with_data(data)?
+            .into_raw()
+            .into_unknown(),
+        ])
+      })?;
+    self.listeners()?.on_data.push(listener);
+    Ok(())
+  }
+
+  /// Registers `callback(connId, error)`, called once per connection when
+  /// it ends. `error` is the socket error that ended it, or `null`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (connId: number, error: string | null) => void"
+  )]
+  pub fn on_close(&self, callback: JsFunction) -> Result<()> {
+    let listener: CloseListener = callback.create_threadsafe_function(
+      0,
+      |ctx: ThreadSafeCallContext<(u32, Option<String>)>| {
+        let (id, error) = ctx.value;
+        Ok(vec![
+          ctx.env.create_uint32(id)?.into_unknown(),
+          match error {
+            Some(error) => ctx.env.create_string_from_std(error)?.into_unknown(),
+            None => ctx.env.get_null()?.into_unknown(),
+          },
+        ])
+      },
+    )?;
+    self.listeners()?.on_close.push(listener);
+    Ok(())
+  }
+
+  /// Queues `data` for the connection `connId`. An unknown or already ended
+  /// connection throws `ERR_NOT_FOUND`.
+  #[napi(catch_unwind)]
+  pub fn send(&self, conn_id: u32, data: Either<Buffer, String>) -> Result<()> {
+    let data = match data {
+      Either::A(data) => data.to_vec(),
+      Either::B(text) => text.into_bytes(),
+    };
+    let shared = lock(&self.shared);
+    shared
+      .connections
+      .get(&conn_id)
+      .and_then(|queue| queue.send(Command::Write(data)).ok())
+      .ok_or_else(|| AppError::NotFound(format!("no open connection {}", conn_id)))
+  }
+
+  /// Ends the connection `connId` once the data queued before it is
+  /// written. Returns false if it was not open.
+  #[napi(catch_unwind)]
+  pub fn end(&self, conn_id: u32) -> bool {
+    lock(&self.shared)
+      .connections
+      .remove(&conn_id)
+      .is_some_and(|queue| queue.send(Command::End).is_ok())
+  }
+
+  /// Stops accepting connections and ends the open ones. Close listeners
+  /// still fire for each of them.
+  #[napi(catch_unwind)]
+  pub fn close(&self) {
+    lock(&self.shared).close();
+  }
+
+  /// Number of open connections.
+  #[napi(catch_unwind, getter)]
+  pub fn connections(&self) -> u32 {
+    lock(&self.shared).connections.len() as u32
+  }
+
+  /// `idle`, `listening` or `closed`. A server that is still binding reports
+  /// `idle`.
+  #[napi(
+    catch_unwind,
+    getter,
+    ts_return_type = "'idle' | 'listening' | 'closed'"
+  )]
+  pub fn state(&self) -> &'static str {
+    match lock(&self.shared).state {
+      State::Idle | State::Binding => "idle",
+      State::Listening => "listening",
+      State::Closed => "closed",
+    }
+  }
+
+  fn listeners(&self) -> Result<MutexGuard<'_, Shared>> {
+    let shared = lock(&self.shared);
+    if shared.state == State::Closed {
+      return Err(AppError::Closed("NativeServer is closed".into()));
+    }
+    Ok(shared)
+  }
+}
+
+impl Default for NativeServer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ObjectFinalize for NativeServer {
+  fn finalize(self, _: Env) -> napi::Result<()> {
+    lock(&self.shared).close();
+    Ok(())
+  }
+}
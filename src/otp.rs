@@ -0,0 +1,290 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use napi::bindgen_prelude::{BigInt, Either};
+use napi::JsDate;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::datetime::date_to_utc;
+use crate::error::{AppError, Result};
+
+/// RFC 3986 unreserved characters, which `otpauth://` labels keep as is.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'-')
+  .remove(b'_')
+  .remove(b'.')
+  .remove(b'~');
+/// Counters given as a number must be exact integers.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct OtpOptions {
+  /// The HMAC hash. Defaults to `sha1`, the only one every authenticator
+  /// app supports.
+  #[napi(ts_type = "'sha1' | 'sha256' | 'sha512'")]
+  pub algorithm: Option<String>,
+  /// Code length, 6 to 8. Defaults to 6.
+  pub digits: Option<u32>,
+  /// TOTP only: seconds each code is valid for. Defaults to 30.
+  pub period: Option<u32>,
+  /// TOTP only: the time to generate or verify for instead of now.
+  pub at: Option<JsDate>,
+}
+
+enum Algorithm {
+  Sha1,
+  Sha256,
+  Sha512,
+}
+
+impl Algorithm {
+  fn name(&self) -> &'static str {
+    match self {
+      Algorithm::Sha1 => "SHA1",
+      Algorithm::Sha256 => "SHA256",
+      Algorithm::Sha512 => "SHA512",
+    }
+  }
+}
+
+struct Params {
+  algorithm: Algorithm,
+  digits: u32,
+  period: u32,
+}
+
+impl Params {
+  fn from_options(options: &OtpOptions) -> Result<Self> {
+    let algorithm = match options.algorithm.as_deref() {
+      None | Some("sha1") => Algorithm::Sha1,
+      Some("sha256") => Algorithm::Sha256,
+      Some("sha512") => Algorithm::Sha512,
+      Some(other) => {
+        return Err(AppError::InvalidInput(format!(
+          "unknown algorithm: {}",
+          other
+        )))
+      }
+    };
+    let digits = options.digits.unwrap_or(6);
+    if !(6..=8).contains(&digits) {
+      return Err(AppError::InvalidInput(format!(
+        "digits must be 6 to 8, got {}",
+        digits
+      )));
+    }
+    let period = options.period.unwrap_or(30);
+    if period == 0 {
+      return Err(AppError::InvalidInput("period must be positive".into()));
+    }
+    Ok(Params {
+      algorithm,
+      digits,
+      period,
+    })
+  }
+
+  /// RFC 4226 section 5.3: HMAC the big-endian counter, then take 31 bits at
+  /// the offset named by the last nibble.
+  fn hotp(&self, key: &[u8], counter: u64) -> String {
+    fn mac<M: Mac + KeyInit>(key: &[u8], counter: u64) -> Vec<u8> {
+      let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC takes any key length");
+      mac.update(&counter.to_be_bytes());
+      mac.finalize().into_bytes().to_vec()
+    }
+    let digest = match self.algorithm {
+      Algorithm::Sha1 => mac::<Hmac<Sha1>>(key, counter),
+      Algorithm::Sha256 => mac::<Hmac<Sha256>>(key, counter),
+      Algorithm::Sha512 => mac::<Hmac<Sha512>>(key, counter),
+    };
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+      digest[offset],
+      digest[offset + 1],
+      digest[offset + 2],
+      digest[offset + 3],
+    ]) & 0x7fff_ffff;
+    format!(
+      "{:0width$}",
+      value % 10u32.pow(self.digits),
+      width = self.digits as usize
+    )
+  }
+
+  /// The TOTP time step for `at`, or now.
+  fn time_step(&self, at: Option<&JsDate>) -> Result<u64> {
+    let ms = match at {
+      Some(at) => date_to_utc(at)?.timestamp_millis(),
+      None => chrono::Utc::now().timestamp_millis(),
+    };
+    if ms < 0 {
+      return Err(AppError::InvalidInput(
+        "time is before the Unix epoch".into(),
+      ));
+    }
+    Ok(ms as u64 / 1000 / self.period as u64)
+  }
+}
+
+/// Decodes a base32 secret the way authenticator apps accept it: in either
+/// case, with or without padding, and with spaces between groups.
+fn decode_secret(secret: &str) -> Result<Vec<u8>> {
+  let normalized: String = secret
+    .chars()
+    .filter(|c| !c.is_whitespace() && *c != '=')
+    .map(|c| c.to_ascii_uppercase())
+    .collect();
+  let key = BASE32_NOPAD
+    .decode(normalized.as_bytes())
+    .map_err(|e| AppError::Parse(format!("invalid base32 secret: {}", e)))?;
+  if key.is_empty() {
+    return Err(AppError::InvalidInput("secret must not be empty".into()));
+  }
+  Ok(key)
+}
+
+/// A random secret of `bytes` bytes (16 to 64, default 20 as RFC 4226
+/// recommends), base32-encoded without padding.
+#[napi(catch_unwind)]
+pub fn generate_secret(bytes: Option<u32>) -> Result<String> {
+  let bytes = bytes.unwrap_or(20);
+  if !(16..=64).contains(&bytes) {
+    return Err(AppError::InvalidInput(format!(
+      "secret length must be 16 to 64 bytes, got {}",
+      bytes
+    )));
+  }
+  let mut key = vec![0u8; bytes as usize];
+  OsRng.fill_bytes(&mut key);
+  Ok(BASE32_NOPAD.encode(&key))
+}
+
+/// An HOTP counter given as a safe integer or, for the full 64-bit range,
+/// a BigInt.
+fn to_counter(counter: Either<f64, BigInt>) -> Result<u64> {
+  let invalid = |what: &str| AppError::InvalidInput(format!("counter must be {}", what));
+  match counter {
+    Either::A(number) => {
+      if number.fract() != 0.0 || number.abs() > MAX_SAFE_INTEGER {
+        return Err(invalid("a safe integer or a BigInt"));
+      }
+      if number < 0.0 {
+        return Err(invalid("non-negative"));
+      }
+      Ok(number as u64)
+    }
+    Either::B(bigint) => match bigint.get_u64() {
+      (false, v, true) => Ok(v),
+      (true, _, _) => Err(invalid("non-negative")),
+      _ => Err(invalid("less than 2^64")),
+    },
+  }
+}
+
+/// The RFC 4226 HMAC-based one-time password for `counter`. `period` and
+/// `at` are ignored.
+#[napi(catch_unwind)]
+pub fn generate_hotp(
+  secret: String,
+  counter: Either<f64, BigInt>,
+  options: Option<OtpOptions>,
+) -> Result<String> {
+  let params = Params::from_options(&options.unwrap_or_default())?;
+  let counter = to_counter(counter)?;
+  Ok(params.hotp(&decode_secret(&secret)?, counter))
+}
+
+/// The RFC 6238 time-based one-time password for the current period, as
+/// shown by Google Authenticator and similar apps.
+#[napi(catch_unwind)]
+pub fn generate_totp(secret: String, options: Option<OtpOptions>) -> Result<String> {
+  let options = options.unwrap_or_default();
+  let params = Params::from_options(&options)?;
+  let step = params.time_step(options.at.as_ref())?;
+  Ok(params.hotp(&decode_secret(&secret)?, step))
+}
+
+/// Whether `code` is the TOTP for the current period or up to `window`
+/// periods (default 1) either side of it, allowing for clock drift and
+/// slow typing. Codes are compared in constant time.
+#[napi(catch_unwind)]
+pub fn verify_totp(
+  code: String,
+  secret: String,
+  window: Option<u32>,
+  options: Option<OtpOptions>,
+) -> Result<bool> {
+  let options = options.unwrap_or_default();
+  let params = Params::from_options(&options)?;
+  let window = window.unwrap_or(1);
+  if window > 10 {
+    return Err(AppError::InvalidInput(format!(
+      "window must be at most 10, got {}",
+      window
+    )));
+  }
+  let key = decode_secret(&secret)?;
+  let step = params.time_step(options.at.as_ref())?;
+  let code = code.trim();
+  // Length isn't secret, and a mismatch can never verify.
+  if code.len() != params.digits as usize {
+    return Ok(false);
+  }
+  let mut matched = subtle::Choice::from(0);
+  for candidate in step.saturating_sub(window as u64)..=step.saturating_add(window as u64) {
+    matched |= params
+      .hotp(&key, candidate)
+      .as_bytes()
+      .ct_eq(code.as_bytes());
+  }
+  Ok(matched.into())
+}
+
+/// An `otpauth://totp/` URI in the Key Uri Format that authenticator apps
+/// read from a QR code, e.g. rendered with `generateQrSvg`. Only non-default
+/// options are written, since some apps reject parameters they don't support.
+#[napi(catch_unwind)]
+pub fn otpauth_url(
+  issuer: String,
+  account: String,
+  secret: String,
+  options: Option<OtpOptions>,
+) -> Result<String> {
+  if issuer.contains(':') || account.contains(':') {
+    return Err(AppError::InvalidInput(
+      "issuer and account must not contain ':'".into(),
+    ));
+  }
+  if account.is_empty() {
+    return Err(AppError::InvalidInput("account must not be empty".into()));
+  }
+  let options = options.unwrap_or_default();
+  let params = Params::from_options(&options)?;
+  let secret = BASE32_NOPAD.encode(&decode_secret(&secret)?);
+  let account = utf8_percent_encode(&account, UNRESERVED);
+  let mut url = if issuer.is_empty() {
+    format!("otpauth://totp/{}?secret={}", account, secret)
+  } else {
+    let issuer = utf8_percent_encode(&issuer, UNRESERVED);
+    format!(
+      "otpauth://totp/{}:{}?secret={}&issuer={}",
+      issuer, account, secret, issuer
+    )
+  };
+  if options.algorithm.is_some() {
+    url.push_str(&format!("&algorithm={}", params.algorithm.name()));
+  }
+  if options.digits.is_some() {
+    url.push_str(&format!("&digits={}", params.digits));
+  }
+  if options.period.is_some() {
+    url.push_str(&format!("&period={}", params.period));
+  }
+  Ok(url)
+}
@@ -0,0 +1,378 @@
+use napi::bindgen_prelude::*;
+use napi::{
+  Env, JsBoolean, JsDate, JsFunction, JsNumber, JsObject, JsString, JsUnknown, NapiRaw, NapiValue,
+  ValueType,
+};
+
+use crate::buffer_ops::buffer_bytes;
+use crate::error::{AppError, Result};
+use crate::json::check_depth;
+
+/// The first byte of every packed buffer. Bump it whenever the layout
+/// changes, so old readers reject new data instead of misreading it.
+const VERSION: u8 = 1;
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+const UNDEFINED: u8 = 0x00;
+const NULL: u8 = 0x01;
+const FALSE: u8 = 0x02;
+const TRUE: u8 = 0x03;
+/// A safe integer, zigzag varint encoded.
+const INT: u8 = 0x04;
+/// Any other number, as a little-endian f64.
+const FLOAT: u8 = 0x05;
+/// Varint byte length, then UTF-8.
+const STRING: u8 = 0x06;
+/// Sign byte, varint word count, then little-endian u64 words.
+const BIGINT: u8 = 0x07;
+/// Epoch milliseconds as a little-endian f64.
+const DATE: u8 = 0x08;
+/// Varint length, then the bytes.
+const BYTES: u8 = 0x09;
+/// Varint length, then each element.
+const ARRAY: u8 = 0x0a;
+/// Varint count, then string key / value pairs.
+const OBJECT: u8 = 0x0b;
+/// Varint count, then key / value pairs.
+const MAP: u8 = 0x0c;
+/// Varint count, then each value.
+const SET: u8 = 0x0d;
+
+/// The globals needed to recognise and rebuild Maps and Sets, looked up once
+/// per call.
+struct Globals {
+  map: JsFunction,
+  set: JsFunction,
+  array_from: JsFunction,
+}
+
+impl Globals {
+  fn new(env: &Env) -> Result<Self> {
+    let global = env.get_global()?;
+    let array = global
+      .get_named_property::<JsFunction>("Array")?
+      .coerce_to_object()?;
+    Ok(Globals {
+      map: global.get_named_property("Map")?,
+      set: global.get_named_property("Set")?,
+      array_from: array.get_named_property("from")?,
+    })
+  }
+
+  /// The entries of a Map as `[key, value]` arrays, or the values of a Set.
+  fn items(&self, collection: &JsObject) -> Result<JsObject> {
+    Ok(unsafe {
+      self
+        .array_from
+        .call(None, &[collection])?
+        .cast::<JsObject>()
+    })
+  }
+}
+
+struct Encoder<'a> {
+  env: &'a Env,
+  globals: Globals,
+  out: Vec<u8>,
+}
+
+impl Encoder<'_> {
+  fn varint(&mut self, mut n: u64) {
+    while n >= 0x80 {
+      self.out.push(n as u8 | 0x80);
+      n >>= 7;
+    }
+    self.out.push(n as u8);
+  }
+
+  fn bytes(&mut self, tag: u8, bytes: &[u8]) {
+    self.out.push(tag);
+    self.varint(bytes.len() as u64);
+    self.out.extend_from_slice(bytes);
+  }
+
+  fn string(&mut self, value: JsString) -> Result<()> {
+    let utf8 = value.into_utf8()?;
+    self.bytes(STRING, utf8.as_str()?.as_bytes());
+    Ok(())
+  }
+
+  fn value(&mut self, value: JsUnknown, depth: usize) -> Result<()> {
+    check_depth(depth)?;
+    match value.get_type()? {
+      ValueType::Undefined => self.out.push(UNDEFINED),
+      ValueType::Null => self.out.push(NULL),
+      ValueType::Boolean => {
+        let b = unsafe { value.cast::<JsBoolean>() }.get_value()?;
+        self.out.push(if b { TRUE } else { FALSE });
+      }
+      ValueType::Number => {
+        let n = unsafe { value.cast::<JsNumber>() }.get_double()?;
+        // -0 is an integer by value but would lose its sign as one.
+        if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER && !(n == 0.0 && n.is_sign_negative()) {
+          let n = n as i64;
+          self.out.push(INT);
+          self.varint(((n << 1) ^ (n >> 63)) as u64);
+        } else {
+          self.out.push(FLOAT);
+          self.out.extend_from_slice(&n.to_le_bytes());
+        }
+      }
+      ValueType::String => self.string(unsafe { value.cast::<JsString>() })?,
+      ValueType::BigInt => {
+        let bigint = unsafe { BigInt::from_napi_value(self.env.raw(), value.raw())? };
+        self.out.push(BIGINT);
+        self.out.push(bigint.sign_bit as u8);
+        self.varint(bigint.words.len() as u64);
+        for word in &bigint.words {
+          self.out.extend_from_slice(&word.to_le_bytes());
+        }
+      }
+      ValueType::Object if value.is_date()? => {
+        let ms = unsafe { value.cast::<JsDate>() }.value_of()?;
+        self.out.push(DATE);
+        self.out.extend_from_slice(&ms.to_le_bytes());
+      }
+      ValueType::Object if value.is_buffer()? => {
+        self.bytes(BYTES, &buffer_bytes(&value)?);
+      }
+      ValueType::Object if value.is_typedarray()? => {
+        let bytes =
+          unsafe { Uint8Array::from_napi_value(self.env.raw(), value.raw()) }.map_err(|_| {
+            AppError::Unsupported("only Uint8Array typed arrays can be packed".into())
+          })?;
+        self.bytes(BYTES, &bytes);
+      }
+      ValueType::Object if value.is_array()? => {
+        let array = unsafe { value.cast::<JsObject>() };
+        let len = array.get_array_length()?;
+        self.out.push(ARRAY);
+        self.varint(len as u64);
+        for i in 0..len {
+          self.value(array.get_element::<JsUnknown>(i)?, depth + 1)?;
+        }
+      }
+      ValueType::Object if value.instanceof(&self.globals.map)? => {
+        let entries = self.globals.items(&unsafe { value.cast::<JsObject>() })?;
+        let len = entries.get_array_length()?;
+        self.out.push(MAP);
+        self.varint(len as u64);
+        for i in 0..len {
+          let entry = entries.get_element::<JsObject>(i)?;
+          self.value(entry.get_element::<JsUnknown>(0)?, depth + 1)?;
+          self.value(entry.get_element::<JsUnknown>(1)?, depth + 1)?;
+        }
+      }
+      ValueType::Object if value.instanceof(&self.globals.set)? => {
+        let values = self.globals.items(&unsafe { value.cast::<JsObject>() })?;
+        let len = values.get_array_length()?;
+        self.out.push(SET);
+        self.varint(len as u64);
+        for i in 0..len {
+          self.value(values.get_element::<JsUnknown>(i)?, depth + 1)?;
+        }
+      }
+      ValueType::Object => {
+        let object = unsafe { value.cast::<JsObject>() };
+        let keys = object.get_property_names()?;
+        let len = keys.get_array_length()?;
+        self.out.push(OBJECT);
+        self.varint(len as u64);
+        for i in 0..len {
+          let key = keys.get_element::<JsString>(i)?;
+          let value = object.get_property::<_, JsUnknown>(key)?;
+          self.string(key)?;
+          self.value(value, depth + 1)?;
+        }
+      }
+      other => {
+        return Err(AppError::Unsupported(format!("cannot pack a {:?}", other)));
+      }
+    }
+    Ok(())
+  }
+}
+
+fn invalid(reason: &str) -> AppError {
+  AppError::Parse(format!("invalid packed data: {}", reason))
+}
+
+struct Decoder<'a> {
+  env: &'a Env,
+  globals: Globals,
+  input: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+  fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+    if n > self.input.len() {
+      return Err(invalid("unexpected end of data"));
+    }
+    let (taken, rest) = self.input.split_at(n);
+    self.input = rest;
+    Ok(taken)
+  }
+
+  fn byte(&mut self) -> Result<u8> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn varint(&mut self) -> Result<u64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+      let byte = self.byte()?;
+      n |= ((byte & 0x7f) as u64) << shift;
+      if byte & 0x80 == 0 {
+        return Ok(n);
+      }
+    }
+    Err(invalid("varint is too long"))
+  }
+
+  /// A length or count of items that each take at least `min_bytes`
+  /// bytes, checked against what is left so corrupt data cannot make
+  /// us allocate gigabytes.
+  fn len(&mut self, min_bytes: usize) -> Result<usize> {
+    let len = self.varint()?;
+    if len > (self.input.len() / min_bytes) as u64 {
+      return Err(invalid("length is past the end of data"));
+    }
+    Ok(len as usize)
+  }
+
+  fn f64(&mut self) -> Result<f64> {
+    Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+  }
+
+  fn str(&mut self) -> Result<&'a str> {
+    let len = self.len(1)?;
+    std::str::from_utf8(self.take(len)?).map_err(|_| invalid("string is not UTF-8"))
+  }
+
+  fn value(&mut self, depth: usize) -> Result<JsUnknown> {
+    check_depth(depth)?;
+    let env = self.env;
+    Ok(match self.byte()? {
+      UNDEFINED => env.get_undefined()?.into_unknown(),
+      NULL => env.get_null()?.into_unknown(),
+      FALSE => env.get_boolean(false)?.into_unknown(),
+      TRUE => env.get_boolean(true)?.into_unknown(),
+      INT => {
+        let n = self.varint()?;
+        env
+          .create_int64((n >> 1) as i64 ^ -((n & 1) as i64))?
+          .into_unknown()
+      }
+      FLOAT => env.create_double(self.f64()?)?.into_unknown(),
+      STRING => env.create_string(self.str()?)?.into_unknown(),
+      BIGINT => {
+        let sign_bit = self.byte()? != 0;
+        let len = self.len(8)?;
+        let words = self
+          .take(len * 8)?
+          .chunks_exact(8)
+          .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+          .collect();
+        unsafe {
+          JsUnknown::from_raw_unchecked(
+            env.raw(),
+            BigInt::to_napi_value(env.raw(), BigInt { sign_bit, words })?,
+          )
+        }
+      }
+      DATE => env.create_date(self.f64()?)?.into_unknown(),
+      BYTES => {
+        let len = self.len(1)?;
+        let bytes = self.take(len)?.to_vec();
+        env
+          .create_buffer_with_data(bytes)?
+          .into_raw()
+          .into_unknown()
+      }
+      ARRAY => {
+        let len = self.len(1)?;
+        let mut array = env.create_array_with_length(len)?;
+        for i in 0..len {
+          array.set_element(i as u32, self.value(depth + 1)?)?;
+        }
+        array.into_unknown()
+      }
+      OBJECT => {
+        let len = self.len(2)?;
+        let mut object = env.create_object()?;
+        for _ in 0..len {
+          if self.byte()? != STRING {
+            return Err(invalid("object key is not a string"));
+          }
+          let key = env.create_string(self.str()?)?;
+          object.set_property(key, self.value(depth + 1)?)?;
+        }
+        object.into_unknown()
+      }
+      MAP => {
+        let len = self.len(2)?;
+        let mut entries = env.create_array_with_length(len)?;
+        for i in 0..len {
+          let mut entry = env.create_array_with_length(2)?;
+          entry.set_element(0, self.value(depth + 1)?)?;
+          entry.set_element(1, self.value(depth + 1)?)?;
+          entries.set_element(i as u32, entry)?;
+        }
+        self.globals.map.new_instance(&[entries])?.into_unknown()
+      }
+      SET => {
+        let len = self.len(1)?;
+        let mut values = env.create_array_with_length(len)?;
+        for i in 0..len {
+          values.set_element(i as u32, self.value(depth + 1)?)?;
+        }
+        self.globals.set.new_instance(&[values])?.into_unknown()
+      }
+      tag => return Err(invalid(&format!("unknown tag 0x{:02x}", tag))),
+    })
+  }
+}
+
+/// Serializes a JS value to a compact binary buffer, for passing structured
+/// data between processes without the losses of JSON.
+///
+/// Handles `undefined`, `null`, booleans, numbers (including `-0`, `NaN` and
+/// `Infinity`), strings, BigInts of any size, Dates, Buffers (Uint8Arrays
+/// come back as Buffers), arrays, Maps and Sets with keys of any type, and
+/// plain objects, whose enumerable string-keyed properties are kept even
+/// when `undefined`. A value reachable twice is written twice; cycles
+/// throw `ERR_INVALID_INPUT`, and functions and symbols
+/// `ERR_UNSUPPORTED`.
+#[napi(catch_unwind, ts_args_type = "value: unknown")]
+pub fn pack(env: Env, value: JsUnknown) -> Result<Buffer> {
+  let mut encoder = Encoder {
+    env: &env,
+    globals: Globals::new(&env)?,
+    out: vec![VERSION],
+  };
+  encoder.value(value, 0)?;
+  Ok(encoder.out.into())
+}
+
+/// Rebuilds a value written by `pack`. Malformed data throws `ERR_PARSE`,
+/// and data from a newer format version `ERR_UNSUPPORTED`.
+#[napi(catch_unwind, ts_return_type = "unknown")]
+pub fn unpack(env: Env, data: Buffer) -> Result<JsUnknown> {
+  let (&version, input) = data.split_first().ok_or_else(|| invalid("empty buffer"))?;
+  if version != VERSION {
+    return Err(AppError::Unsupported(format!(
+      "packed data has format version {}, expected {}",
+      version, VERSION
+    )));
+  }
+  let mut decoder = Decoder {
+    env: &env,
+    globals: Globals::new(&env)?,
+    input,
+  };
+  let value = decoder.value(0)?;
+  if !decoder.input.is_empty() {
+    return Err(invalid("trailing bytes after value"));
+  }
+  Ok(value)
+}
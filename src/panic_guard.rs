@@ -0,0 +1,241 @@
+//! Turns Rust panics into catchable JS errors instead of aborting Node.
+//!
+//! A panic unwinding out of a `#[napi]` function would cross the `extern "C"`
+//! boundary into Node and abort the process. Every exported function and
+//! method is therefore declared with `#[napi(catch_unwind)]`, which stops the
+//! unwind at the boundary — `build.rs` fails the build for any that is not,
+//! so new exports get this by default. The panic itself is reported by the
+//! hook installed here: on a thread running JS it throws an `Error` with code
+//! `RUST_PANIC`, which the generated glue then propagates in place of its own
+//! generic failure.
+//!
+//! Code that runs off the JS thread is not covered by the glue. Futures passed
+//! to `error::spawn` already run under [`guard_future`], and `AsyncTask`s are
+//! created with [`task`], which runs `Task::compute` under [`guard`] so the
+//! Promise rejects with `RUST_PANIC` instead of the libuv worker aborting the
+//! process; `build.rs` rejects `AsyncTask::new` anywhere else. Run other
+//! background work under [`guard`] too.
+
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::ptr;
+use std::sync::Once;
+use std::task::Poll;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsObject, Task};
+
+use crate::error::{AppError, Result};
+
+thread_local! {
+  /// The environment whose JS runs on this thread (the main thread's or a
+  /// worker's). Null on libuv, rayon and tokio threads.
+  static JS_ENV: Cell<napi::sys::napi_env> = const { Cell::new(ptr::null_mut()) };
+  /// How many `guard` calls are active on this thread.
+  static GUARDED: Cell<u32> = const { Cell::new(0) };
+  /// The description of the last panic on this thread, for `guard`.
+  static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Like the glue `#[napi]` generates, the registration is left out of test
+// builds, which are not linked against Node.
+#[cfg_attr(not(test), module_exports)]
+#[cfg_attr(test, allow(dead_code))]
+fn init(_exports: JsObject, env: Env) -> napi::Result<()> {
+  install(env)
+}
+
+/// Records `env` as this thread's JS environment and installs the panic hook
+/// the first time the addon is loaded.
+fn install(mut env: Env) -> napi::Result<()> {
+  static HOOK: Once = Once::new();
+  HOOK.call_once(|| {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+      if !report(info) {
+        previous(info);
+      }
+    }));
+  });
+  JS_ENV.with(|js_env| js_env.set(env.raw()));
+  // A panic during teardown must not throw into an environment that is
+  // going away.
+  env.add_env_cleanup_hook((), |_| {
+    let _ = JS_ENV.try_with(|js_env| js_env.set(ptr::null_mut()));
+  })?;
+  Ok(())
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+  if let Some(message) = payload.downcast_ref::<&str>() {
+    message
+  } else if let Some(message) = payload.downcast_ref::<String>() {
+    message
+  } else {
+    "Box<dyn Any>"
+  }
+}
+
+/// `panicked at src/file.rs:1:2: message`, followed by a backtrace when
+/// `RUST_BACKTRACE` enables one.
+fn describe(info: &PanicHookInfo) -> String {
+  let mut description = match info.location() {
+    Some(location) => format!(
+      "panicked at {}: {}",
+      location,
+      payload_message(info.payload())
+    ),
+    None => format!("panicked: {}", payload_message(info.payload())),
+  };
+  let backtrace = Backtrace::capture();
+  if backtrace.status() == BacktraceStatus::Captured {
+    description.push_str(&format!("\n{}", backtrace));
+  }
+  description
+}
+
+/// Handles a panic if it will be caught by `guard` or by the glue of an
+/// exported function. Returns false to fall back to the default hook, which
+/// prints to stderr.
+fn report(info: &PanicHookInfo) -> bool {
+  let guarded = GUARDED.try_with(Cell::get).unwrap_or(0) > 0;
+  let env = JS_ENV.try_with(Cell::get).unwrap_or(ptr::null_mut());
+  if !guarded && env.is_null() {
+    return false;
+  }
+  let description = describe(info);
+  if guarded {
+    let _ = LAST_PANIC.try_with(|last| *last.borrow_mut() = Some(description));
+  } else {
+    // The glue rethrows an already pending exception instead of its own, so
+    // this is what JS sees.
+    unsafe {
+      let error = JsError::from(AppError::Panic(description)).into_value(env);
+      napi::sys::napi_throw(env, error);
+    }
+  }
+  true
+}
+
+/// Runs `f`, converting a panic into `AppError::Panic`.
+pub fn guard<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+  GUARDED.with(|guarded| guarded.set(guarded.get() + 1));
+  let result = panic::catch_unwind(AssertUnwindSafe(f));
+  GUARDED.with(|guarded| guarded.set(guarded.get() - 1));
+  result.unwrap_or_else(|payload| {
+    let description = LAST_PANIC
+      .with(|last| last.borrow_mut().take())
+      .unwrap_or_else(|| format!("panicked: {}", payload_message(&*payload)));
+    Err(AppError::Panic(description))
+  })
+}
+
+/// Drives `fut`, converting a panic in any poll into `AppError::Panic`. The
+/// future is dropped without being polled again.
+pub async fn guard_future<T>(fut: impl Future<Output = Result<T>>) -> Result<T> {
+  let mut fut = std::pin::pin!(fut);
+  std::future::poll_fn(|cx| match guard(|| Ok(fut.as_mut().poll(cx))) {
+    Ok(poll) => poll,
+    Err(e) => Poll::Ready(Err(e)),
+  })
+  .await
+}
+
+/// A `Task` whose `compute` runs under [`guard`].
+pub struct Guarded<T>(T);
+
+impl<T: Task> Task for Guarded<T> {
+  type Output = Result<T::Output>;
+  type JsValue = T::JsValue;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(guard(|| self.0.compute().map_err(AppError::from)))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(output) => self.0.resolve(env, output),
+      Err(e) => Err(e.into_napi(env)),
+    }
+  }
+
+  fn reject(&mut self, env: Env, err: napi::Error) -> napi::Result<Self::JsValue> {
+    self.0.reject(env, err)
+  }
+
+  fn finally(&mut self, env: Env) -> napi::Result<()> {
+    self.0.finally(env)
+  }
+}
+
+/// Queues `task` on the libuv thread pool; see [`Guarded`].
+pub fn task<T: Task>(task: T) -> AsyncTask<Guarded<T>> {
+  AsyncTask::new(Guarded(task))
+}
+
+/// Like [`task`], cancelled by aborting `signal`.
+pub fn task_with_signal<T: Task>(task: T, signal: Option<AbortSignal>) -> AsyncTask<Guarded<T>> {
+  AsyncTask::with_optional_signal(Guarded(task), signal)
+}
+
+/// Exports that panic on purpose, for the spec's `RUST_PANIC` tests. They
+/// are only compiled into debug builds, so release addons never ship them.
+#[cfg(debug_assertions)]
+pub mod triggers {
+  use napi::bindgen_prelude::*;
+  use napi::{Env, JsObject, Task};
+
+  use super::{task, Guarded};
+  use crate::error::Result;
+
+  fn panic_with(message: String) -> Result<()> {
+    panic!("{}", message)
+  }
+
+  /// Panics with `message`, so tests can see how a `RUST_PANIC` error
+  /// arrives.
+  ///
+  /// @internal
+  #[napi(catch_unwind)]
+  pub fn raise_panic(message: String) -> Result<()> {
+    panic_with(message)
+  }
+
+  /// Like `raisePanic`, but from a Promise future on a tokio thread: rejects
+  /// with `RUST_PANIC`.
+  ///
+  /// @internal
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn raise_panic_async(env: Env, message: String) -> Result<JsObject> {
+    crate::error::spawn(&env, async move { panic_with(message) })
+  }
+
+  pub struct PanicTask {
+    message: String,
+  }
+
+  impl Task for PanicTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+      panic_with(std::mem::take(&mut self.message)).map_err(napi::Error::from)
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> napi::Result<Self::JsValue> {
+      Ok(())
+    }
+  }
+
+  /// Like `raisePanic`, but from an `AsyncTask` on a libuv worker thread:
+  /// rejects with `RUST_PANIC`.
+  ///
+  /// @internal
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn raise_panic_task(message: String) -> AsyncTask<Guarded<PanicTask>> {
+    task(PanicTask { message })
+  }
+}
@@ -0,0 +1,51 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use rayon::prelude::*;
+
+use crate::panic_guard::{self, Guarded};
+
+pub struct SquaresTask {
+  input: Vec<f64>,
+}
+
+impl Task for SquaresTask {
+  type Output = Vec<f64>;
+  type JsValue = Vec<f64>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(self.input.par_iter().map(|x| x * x).collect())
+  }
+
+  fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SumTask {
+  input: Float64Array,
+}
+
+impl Task for SumTask {
+  type Output = f64;
+  type JsValue = f64;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(self.input.par_iter().sum())
+  }
+
+  fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// Squares every element, spread across all cores by rayon.
+#[napi(catch_unwind, ts_return_type = "Promise<number[]>")]
+pub fn parallel_map_squares(input: Vec<f64>) -> AsyncTask<Guarded<SquaresTask>> {
+  panic_guard::task(SquaresTask { input })
+}
+
+/// Sums a `Float64Array` in parallel. The array is read in place, not copied.
+#[napi(catch_unwind, ts_return_type = "Promise<number>")]
+pub fn parallel_sum(input: Float64Array) -> AsyncTask<Guarded<SumTask>> {
+  panic_guard::task(SumTask { input })
+}
@@ -0,0 +1,706 @@
+use std::fs::File;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+  Float32Type, Float64Type, Int32Type, Int64Type, TimestampMillisecondType, UInt32Type, UInt64Type,
+};
+use arrow_array::{
+  ArrayRef, BinaryArray, BooleanArray, Float32Array as ArrowFloat32, Float64Array as ArrowFloat64,
+  Int32Array as ArrowInt32, Int64Array as ArrowInt64, RecordBatch, StringArray,
+  TimestampMillisecondArray, UInt32Array as ArrowUInt32, UInt64Array as ArrowUInt64,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use napi::bindgen_prelude::*;
+use napi::{
+  Env, JsBigInt, JsBoolean, JsDate, JsNumber, JsObject, JsString, JsUnknown, NapiValue, Task,
+  ValueType,
+};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+
+use crate::buffer_ops::buffer_bytes;
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+const BATCH_SIZE: usize = 8192;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ReadParquetOptions {
+  /// Only read these columns, in this order. Defaults to all of them.
+  pub columns: Option<Vec<String>>,
+  /// Stop after this many rows.
+  pub limit: Option<u32>,
+}
+
+#[napi(object)]
+pub struct ParquetField {
+  pub name: String,
+  #[napi(
+    js_name = "type",
+    ts_type = "'int32' | 'uint32' | 'int64' | 'uint64' | 'float' | 'double' | 'boolean' | 'string' | 'binary' | 'timestamp'"
+  )]
+  pub kind: String,
+  /// Whether values may be null. Defaults to `true` when writing.
+  pub nullable: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct WriteParquetOptions {
+  /// Defaults to `snappy`.
+  #[napi(ts_type = "'snappy' | 'zstd' | 'gzip' | 'none'")]
+  pub compression: Option<String>,
+  /// Rows per row group. Defaults to 1048576.
+  pub row_group_size: Option<u32>,
+}
+
+fn parquet_error(e: impl std::fmt::Display) -> AppError {
+  AppError::Parse(format!("parquet: {}", e))
+}
+
+/// A column's values, with arrow's many physical types folded into the
+/// ones JS has an array type for.
+enum Values {
+  Int32(Vec<i32>),
+  UInt32(Vec<u32>),
+  Int64(Vec<i64>),
+  UInt64(Vec<u64>),
+  Float32(Vec<f32>),
+  Float64(Vec<f64>),
+  /// Milliseconds since the Unix epoch.
+  Timestamp(Vec<f64>),
+  Boolean(Vec<Option<bool>>),
+  Utf8(Vec<Option<String>>),
+  Binary(Vec<Option<Vec<u8>>>),
+}
+
+impl Values {
+  fn kind(&self) -> &'static str {
+    match self {
+      Values::Int32(_) => "int32",
+      Values::UInt32(_) => "uint32",
+      Values::Int64(_) => "int64",
+      Values::UInt64(_) => "uint64",
+      Values::Float32(_) => "float",
+      Values::Float64(_) => "double",
+      Values::Timestamp(_) => "timestamp",
+      Values::Boolean(_) => "boolean",
+      Values::Utf8(_) => "string",
+      Values::Binary(_) => "binary",
+    }
+  }
+
+  /// Empty values of the type `data_type` is read as.
+  fn for_type(name: &str, data_type: &DataType) -> Result<(Self, DataType)> {
+    use DataType::*;
+    Ok(match data_type {
+      Int8 | Int16 | Int32 => (Values::Int32(Vec::new()), Int32),
+      UInt8 | UInt16 | UInt32 => (Values::UInt32(Vec::new()), UInt32),
+      Int64 => (Values::Int64(Vec::new()), Int64),
+      UInt64 => (Values::UInt64(Vec::new()), UInt64),
+      Float16 | Float32 => (Values::Float32(Vec::new()), Float32),
+      Float64 => (Values::Float64(Vec::new()), Float64),
+      Date32 | Date64 | Timestamp(_, _) => (
+        Values::Timestamp(Vec::new()),
+        Timestamp(TimeUnit::Millisecond, None),
+      ),
+      Boolean => (Values::Boolean(Vec::new()), Boolean),
+      // Decimals as strings, which keeps every digit.
+      Utf8
+      | LargeUtf8
+      | Utf8View
+      | Decimal32(_, _)
+      | Decimal64(_, _)
+      | Decimal128(_, _)
+      | Decimal256(_, _) => (Values::Utf8(Vec::new()), Utf8),
+      Binary | LargeBinary | BinaryView | FixedSizeBinary(_) => {
+        (Values::Binary(Vec::new()), Binary)
+      }
+      Dictionary(_, values) => Self::for_type(name, values)?,
+      other => {
+        return Err(AppError::Unsupported(format!(
+          "column {} has unsupported type {}",
+          name, other
+        )))
+      }
+    })
+  }
+
+  /// Appends `array`, already cast to the type from `for_type`. Nulls in
+  /// numeric columns become 0, or NaN for floats and timestamps; `valid`
+  /// records where they were.
+  fn extend(&mut self, array: &ArrayRef, valid: &mut Vec<bool>) {
+    valid.extend((0..array.len()).map(|i| array.is_valid(i)));
+    match self {
+      Values::Int32(values) => values.extend(
+        array
+          .as_primitive::<Int32Type>()
+          .iter()
+          .map(Option::unwrap_or_default),
+      ),
+      Values::UInt32(values) => values.extend(
+        array
+          .as_primitive::<UInt32Type>()
+          .iter()
+          .map(Option::unwrap_or_default),
+      ),
+      Values::Int64(values) => values.extend(
+        array
+          .as_primitive::<Int64Type>()
+          .iter()
+          .map(Option::unwrap_or_default),
+      ),
+      Values::UInt64(values) => values.extend(
+        array
+          .as_primitive::<UInt64Type>()
+          .iter()
+          .map(Option::unwrap_or_default),
+      ),
+      Values::Float32(values) => values.extend(
+        array
+          .as_primitive::<Float32Type>()
+          .iter()
+          .map(|v| v.unwrap_or(f32::NAN)),
+      ),
+      Values::Float64(values) => values.extend(
+        array
+          .as_primitive::<Float64Type>()
+          .iter()
+          .map(|v| v.unwrap_or(f64::NAN)),
+      ),
+      Values::Timestamp(values) => values.extend(
+        array
+          .as_primitive::<TimestampMillisecondType>()
+          .iter()
+          .map(|v| v.map_or(f64::NAN, |ms| ms as f64)),
+      ),
+      Values::Boolean(values) => values.extend(array.as_boolean().iter()),
+      Values::Utf8(values) => {
+        values.extend(array.as_string::<i32>().iter().map(|v| v.map(String::from)))
+      }
+      Values::Binary(values) => values.extend(
+        array
+          .as_binary::<i32>()
+          .iter()
+          .map(|v| v.map(<[u8]>::to_vec)),
+      ),
+    }
+  }
+}
+
+pub struct Column {
+  name: String,
+  nullable: bool,
+  values: Values,
+  valid: Vec<bool>,
+}
+
+fn to_unknown<T: ToNapiValue>(env: &Env, value: T) -> Result<JsUnknown> {
+  unsafe {
+    Ok(JsUnknown::from_raw_unchecked(
+      env.raw(),
+      T::to_napi_value(env.raw(), value)?,
+    ))
+  }
+}
+
+impl Column {
+  fn cell(&self, env: &Env, row: usize) -> Result<JsUnknown> {
+    if !self.valid[row] {
+      return Ok(env.get_null()?.into_unknown());
+    }
+    Ok(match &self.values {
+      Values::Int32(values) => env.create_int32(values[row])?.into_unknown(),
+      Values::UInt32(values) => env.create_uint32(values[row])?.into_unknown(),
+      Values::Int64(values) => env.create_bigint_from_i64(values[row])?.into_unknown()?,
+      Values::UInt64(values) => env.create_bigint_from_u64(values[row])?.into_unknown()?,
+      Values::Float32(values) => env.create_double(values[row].into())?.into_unknown(),
+      Values::Float64(values) => env.create_double(values[row])?.into_unknown(),
+      Values::Timestamp(values) => env.create_date(values[row])?.into_unknown(),
+      Values::Boolean(values) => env
+        .get_boolean(values[row].unwrap_or_default())?
+        .into_unknown(),
+      Values::Utf8(values) => env
+        .create_string(values[row].as_deref().unwrap_or_default())?
+        .into_unknown(),
+      // `create_buffer_copy` aborts on an empty slice in debug builds.
+      Values::Binary(values) => env
+        .create_buffer_with_data(values[row].clone().unwrap_or_default())?
+        .into_unknown(),
+    })
+  }
+}
+
+fn read(source: Either<String, Buffer>, options: ReadParquetOptions) -> Result<Vec<Column>> {
+  match source {
+    Either::A(path) => read_from(
+      File::open(&path).map_err(|e| AppError::io(&path, e))?,
+      options,
+    ),
+    Either::B(data) => read_from(bytes::Bytes::from(data.to_vec()), options),
+  }
+}
+
+fn read_from<R: parquet::file::reader::ChunkReader + 'static>(
+  reader: R,
+  options: ReadParquetOptions,
+) -> Result<Vec<Column>> {
+  let mut builder = ParquetRecordBatchReaderBuilder::try_new(reader)
+    .map_err(parquet_error)?
+    .with_batch_size(BATCH_SIZE);
+  let schema = builder.schema().clone();
+  let indices = match &options.columns {
+    Some(names) => names
+      .iter()
+      .map(|name| {
+        schema
+          .index_of(name)
+          .map_err(|_| AppError::NotFound(format!("no column named {}", name)))
+      })
+      .collect::<Result<Vec<_>>>()?,
+    None => (0..schema.fields().len()).collect(),
+  };
+  let mask = ProjectionMask::roots(builder.parquet_schema(), indices.iter().copied());
+  builder = builder.with_projection(mask);
+  if let Some(limit) = options.limit {
+    builder = builder.with_limit(limit as usize);
+  }
+  // The projection keeps file order, so match batch columns by name.
+  let mut columns = Vec::with_capacity(indices.len());
+  let mut targets = Vec::with_capacity(indices.len());
+  for &index in &indices {
+    let field = schema.field(index);
+    let (values, target) = Values::for_type(field.name(), field.data_type())?;
+    columns.push(Column {
+      name: field.name().clone(),
+      nullable: field.is_nullable(),
+      values,
+      valid: Vec::new(),
+    });
+    targets.push(target);
+  }
+  for batch in builder.build().map_err(parquet_error)? {
+    let batch: RecordBatch = batch.map_err(parquet_error)?;
+    for (column, target) in columns.iter_mut().zip(&targets) {
+      let array = batch
+        .column_by_name(&column.name)
+        .expect("projected column is in the batch");
+      let array = arrow_cast::cast(array, target).map_err(parquet_error)?;
+      column.values.extend(&array, &mut column.valid);
+    }
+  }
+  Ok(columns)
+}
+
+pub struct ReadParquetTask {
+  source: Option<Either<String, Buffer>>,
+  options: Option<ReadParquetOptions>,
+}
+
+impl Task for ReadParquetTask {
+  type Output = Result<Vec<Column>>;
+  type JsValue = Table;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let source = self.source.take().expect("compute runs once");
+    Ok(read(source, self.options.take().unwrap_or_default()))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    let columns = output.map_err(|e| e.into_napi(env))?;
+    Ok(Table {
+      rows: columns.first().map_or(0, |column| column.valid.len()),
+      columns,
+    })
+  }
+}
+
+/// Reads a Parquet file, from a path or from its bytes, into a columnar
+/// `Table` on the libuv thread pool. Nested columns (lists, structs and
+/// maps) throw `ERR_UNSUPPORTED`; leave them out with `columns`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "source: string | Buffer, options?: ReadParquetOptions",
+  ts_return_type = "Promise<Table>"
+)]
+pub fn read_parquet(
+  source: Either<String, Buffer>,
+  options: Option<ReadParquetOptions>,
+) -> AsyncTask<Guarded<ReadParquetTask>> {
+  panic_guard::task(ReadParquetTask {
+    source: Some(source),
+    options,
+  })
+}
+
+/// Columns read by `readParquet`.
+#[napi]
+pub struct Table {
+  columns: Vec<Column>,
+  rows: usize,
+}
+
+#[napi]
+impl Table {
+  #[napi(catch_unwind, getter)]
+  pub fn num_rows(&self) -> u32 {
+    self.rows as u32
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn column_names(&self) -> Vec<String> {
+    self
+      .columns
+      .iter()
+      .map(|column| column.name.clone())
+      .collect()
+  }
+
+  /// Each column's name and the type it reads as: decimals as `string`,
+  /// dates as `timestamp`, and 8- and 16-bit integers widened to 32 bits.
+  #[napi(catch_unwind, getter)]
+  pub fn schema(&self) -> Vec<ParquetField> {
+    self
+      .columns
+      .iter()
+      .map(|column| ParquetField {
+        name: column.name.clone(),
+        kind: column.values.kind().into(),
+        nullable: Some(column.nullable),
+      })
+      .collect()
+  }
+
+  /// A column's values: a typed array for numbers (`BigInt64Array` for
+  /// 64-bit integers, `Float64Array` of epoch milliseconds for
+  /// timestamps), otherwise an array of booleans, strings or Buffers.
+  /// Typed arrays can't hold null, so null numbers read as 0, or NaN for
+  /// floats and timestamps; arrays and `toObjects` use null.
+  #[napi(
+    catch_unwind,
+    ts_return_type = "Int32Array | Uint32Array | BigInt64Array | BigUint64Array | Float32Array | Float64Array | Array<boolean | null> | Array<string | null> | Array<Buffer | null>"
+  )]
+  pub fn column(&self, env: Env, name: String) -> Result<JsUnknown> {
+    let column = self
+      .columns
+      .iter()
+      .find(|column| column.name == name)
+      .ok_or_else(|| AppError::NotFound(format!("no column named {}", name)))?;
+    match &column.values {
+      Values::Int32(values) => to_unknown(&env, Int32Array::new(values.clone())),
+      Values::UInt32(values) => to_unknown(&env, Uint32Array::new(values.clone())),
+      Values::Int64(values) => to_unknown(&env, BigInt64Array::new(values.clone())),
+      Values::UInt64(values) => to_unknown(&env, BigUint64Array::new(values.clone())),
+      Values::Float32(values) => to_unknown(&env, Float32Array::new(values.clone())),
+      Values::Float64(values) | Values::Timestamp(values) => {
+        to_unknown(&env, Float64Array::new(values.clone()))
+      }
+      Values::Boolean(values) => to_unknown(&env, values.clone()),
+      Values::Utf8(values) => to_unknown(&env, values.clone()),
+      Values::Binary(values) => to_unknown(
+        &env,
+        values
+          .iter()
+          .map(|value| value.clone().map(Buffer::from))
+          .collect::<Vec<_>>(),
+      ),
+    }
+  }
+
+  /// One object per row, keyed by column name, with nulls as null,
+  /// 64-bit integers as BigInt and timestamps as Dates.
+  #[napi(catch_unwind, ts_return_type = "Array<Record<string, unknown>>")]
+  pub fn to_objects(&self, env: Env) -> Result<JsObject> {
+    let mut rows = env.create_array_with_length(self.rows)?;
+    for row in 0..self.rows {
+      let mut object = env.create_object()?;
+      for column in &self.columns {
+        object.set_named_property(&column.name, column.cell(&env, row)?)?;
+      }
+      rows.set_element(row as u32, object)?;
+    }
+    Ok(rows)
+  }
+}
+
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+fn put<T>(values: &mut Vec<Option<T>>, value: Option<T>) -> bool {
+  let accepted = value.is_some();
+  if accepted {
+    values.push(value);
+  }
+  accepted
+}
+
+/// A column being collected from JS rows for `writeParquet`.
+enum Builder {
+  Int32(Vec<Option<i32>>),
+  UInt32(Vec<Option<u32>>),
+  Int64(Vec<Option<i64>>),
+  UInt64(Vec<Option<u64>>),
+  Float32(Vec<Option<f32>>),
+  Float64(Vec<Option<f64>>),
+  Timestamp(Vec<Option<i64>>),
+  Boolean(Vec<Option<bool>>),
+  Utf8(Vec<Option<String>>),
+  Binary(Vec<Option<Vec<u8>>>),
+}
+
+impl Builder {
+  fn new(field: &ParquetField) -> Result<(Self, DataType)> {
+    Ok(match field.kind.as_str() {
+      "int32" => (Builder::Int32(Vec::new()), DataType::Int32),
+      "uint32" => (Builder::UInt32(Vec::new()), DataType::UInt32),
+      "int64" => (Builder::Int64(Vec::new()), DataType::Int64),
+      "uint64" => (Builder::UInt64(Vec::new()), DataType::UInt64),
+      "float" => (Builder::Float32(Vec::new()), DataType::Float32),
+      "double" => (Builder::Float64(Vec::new()), DataType::Float64),
+      "timestamp" => (
+        Builder::Timestamp(Vec::new()),
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+      ),
+      "boolean" => (Builder::Boolean(Vec::new()), DataType::Boolean),
+      "string" => (Builder::Utf8(Vec::new()), DataType::Utf8),
+      "binary" => (Builder::Binary(Vec::new()), DataType::Binary),
+      other => {
+        return Err(AppError::InvalidInput(format!(
+          "column {} has unknown type: {}",
+          field.name, other
+        )))
+      }
+    })
+  }
+
+  /// Appends one value, or returns false if it isn't of the column's type.
+  /// `None` is a missing or null value; the caller has checked the column
+  /// is nullable.
+  fn push(&mut self, value: Option<JsUnknown>) -> Result<bool> {
+    let Some(value) = value else {
+      match self {
+        Builder::Int32(v) => v.push(None),
+        Builder::UInt32(v) => v.push(None),
+        Builder::Int64(v) => v.push(None),
+        Builder::UInt64(v) => v.push(None),
+        Builder::Float32(v) => v.push(None),
+        Builder::Float64(v) => v.push(None),
+        Builder::Timestamp(v) => v.push(None),
+        Builder::Boolean(v) => v.push(None),
+        Builder::Utf8(v) => v.push(None),
+        Builder::Binary(v) => v.push(None),
+      }
+      return Ok(true);
+    };
+    let kind = value.get_type()?;
+    let value = &value;
+    let number = || -> Result<Option<f64>> {
+      Ok(
+        (kind == ValueType::Number)
+          .then(|| unsafe { value.cast::<JsNumber>() }.get_double())
+          .transpose()?,
+      )
+    };
+    let integer = |min: f64, max: f64| -> Result<Option<f64>> {
+      Ok(number()?.filter(|n| n.fract() == 0.0 && (min..=max).contains(n)))
+    };
+    let bigint = || (kind == ValueType::BigInt).then(|| unsafe { value.cast::<JsBigInt>() });
+    Ok(match self {
+      Builder::Int32(v) => put(
+        v,
+        integer(i32::MIN.into(), i32::MAX.into())?.map(|n| n as i32),
+      ),
+      Builder::UInt32(v) => put(v, integer(0.0, u32::MAX.into())?.map(|n| n as u32)),
+      Builder::Int64(v) => match bigint() {
+        Some(big) => put(
+          v,
+          Some(big.get_i64()?)
+            .filter(|&(_, lossless)| lossless)
+            .map(|(n, _)| n),
+        ),
+        None => put(
+          v,
+          integer(-MAX_SAFE_INTEGER, MAX_SAFE_INTEGER)?.map(|n| n as i64),
+        ),
+      },
+      Builder::UInt64(v) => match bigint() {
+        Some(big) => put(
+          v,
+          Some(big.get_u64()?)
+            .filter(|&(_, lossless)| lossless)
+            .map(|(n, _)| n),
+        ),
+        None => put(v, integer(0.0, MAX_SAFE_INTEGER)?.map(|n| n as u64)),
+      },
+      Builder::Float32(v) => put(v, number()?.map(|n| n as f32)),
+      Builder::Float64(v) => put(v, number()?),
+      Builder::Timestamp(v) => {
+        let ms = if kind == ValueType::Object && value.is_date()? {
+          Some(unsafe { value.cast::<JsDate>() }.value_of()?)
+        } else {
+          number()?
+        };
+        put(v, ms.filter(|ms| ms.is_finite()).map(|ms| ms as i64))
+      }
+      Builder::Boolean(v) => put(
+        v,
+        (kind == ValueType::Boolean)
+          .then(|| unsafe { value.cast::<JsBoolean>() }.get_value())
+          .transpose()?,
+      ),
+      Builder::Utf8(v) => put(
+        v,
+        (kind == ValueType::String)
+          .then(|| {
+            unsafe { value.cast::<JsString>() }
+              .into_utf8()?
+              .into_owned()
+          })
+          .transpose()?,
+      ),
+      Builder::Binary(v) => put(
+        v,
+        (kind == ValueType::Object && value.is_buffer()?)
+          .then(|| buffer_bytes(value))
+          .transpose()?,
+      ),
+    })
+  }
+
+  fn finish(self, data_type: &DataType) -> ArrayRef {
+    match self {
+      Builder::Int32(v) => Arc::new(ArrowInt32::from(v)),
+      Builder::UInt32(v) => Arc::new(ArrowUInt32::from(v)),
+      Builder::Int64(v) => Arc::new(ArrowInt64::from(v)),
+      Builder::UInt64(v) => Arc::new(ArrowUInt64::from(v)),
+      Builder::Float32(v) => Arc::new(ArrowFloat32::from(v)),
+      Builder::Float64(v) => Arc::new(ArrowFloat64::from(v)),
+      Builder::Timestamp(v) => {
+        let array = TimestampMillisecondArray::from(v);
+        match data_type {
+          DataType::Timestamp(_, Some(tz)) => Arc::new(array.with_timezone(tz.clone())),
+          _ => Arc::new(array),
+        }
+      }
+      Builder::Boolean(v) => Arc::new(BooleanArray::from(v)),
+      Builder::Utf8(v) => Arc::new(StringArray::from(v)),
+      Builder::Binary(v) => Arc::new(BinaryArray::from_iter(v)),
+    }
+  }
+}
+
+pub struct WriteParquetTask {
+  batch: Option<RecordBatch>,
+  properties: Option<WriterProperties>,
+}
+
+impl Task for WriteParquetTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let batch = self.batch.take().expect("compute runs once");
+    let mut write = || -> std::result::Result<Vec<u8>, parquet::errors::ParquetError> {
+      let mut out = Vec::new();
+      let mut writer = ArrowWriter::try_new(&mut out, batch.schema(), self.properties.take())?;
+      writer.write(&batch)?;
+      writer.close()?;
+      Ok(out)
+    };
+    Ok(write().map_err(|e| AppError::Io(format!("parquet: {}", e))))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Encodes `rows` as a Parquet file with the columns in `schema`. Values
+/// are checked against their column's type here, so a mismatch throws
+/// `ERR_INVALID_INPUT` naming the row; the encoding runs on the libuv
+/// thread pool. Timestamps take Dates or epoch milliseconds, 64-bit
+/// integers BigInts or safe integers, and binary columns Buffers.
+#[napi(
+  catch_unwind,
+  ts_args_type = "rows: Array<Record<string, unknown>>, schema: ParquetField[], options?: WriteParquetOptions",
+  ts_return_type = "Promise<Buffer>"
+)]
+pub fn write_parquet(
+  rows: Vec<JsObject>,
+  schema: Vec<ParquetField>,
+  options: Option<WriteParquetOptions>,
+) -> Result<AsyncTask<Guarded<WriteParquetTask>>> {
+  let options = options.unwrap_or_default();
+  if schema.is_empty() {
+    return Err(AppError::InvalidInput(
+      "schema needs at least one column".into(),
+    ));
+  }
+  let mut fields = Vec::with_capacity(schema.len());
+  let mut builders = Vec::with_capacity(schema.len());
+  for field in &schema {
+    let (builder, data_type) = Builder::new(field)?;
+    fields.push(Field::new(
+      &field.name,
+      data_type,
+      field.nullable.unwrap_or(true),
+    ));
+    builders.push(builder);
+  }
+  for (index, row) in rows.iter().enumerate() {
+    for ((field, spec), builder) in fields.iter().zip(&schema).zip(&mut builders) {
+      let value: JsUnknown = row.get_named_property(&spec.name)?;
+      let value = match value.get_type()? {
+        ValueType::Undefined | ValueType::Null if field.is_nullable() => None,
+        ValueType::Undefined | ValueType::Null => {
+          return Err(AppError::InvalidInput(format!(
+            "row {}: {} is required",
+            index, spec.name
+          )))
+        }
+        _ => Some(value),
+      };
+      if !builder.push(value)? {
+        return Err(AppError::InvalidInput(format!(
+          "row {}: {} must be of type {}",
+          index, spec.name, spec.kind
+        )));
+      }
+    }
+  }
+  let arrays = builders
+    .into_iter()
+    .zip(&fields)
+    .map(|(builder, field)| builder.finish(field.data_type()))
+    .collect();
+  let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+    .map_err(|e| AppError::InvalidInput(format!("parquet: {}", e)))?;
+  let compression = match options.compression.as_deref() {
+    None | Some("snappy") => Compression::SNAPPY,
+    Some("zstd") => Compression::ZSTD(ZstdLevel::default()),
+    Some("gzip") => Compression::GZIP(GzipLevel::default()),
+    Some("none") => Compression::UNCOMPRESSED,
+    Some(other) => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown compression: {}",
+        other
+      )))
+    }
+  };
+  let mut properties = WriterProperties::builder().set_compression(compression);
+  if let Some(size) = options.row_group_size {
+    if size == 0 {
+      return Err(AppError::InvalidInput(
+        "rowGroupSize must be positive".into(),
+      ));
+    }
+    properties = properties.set_max_row_group_row_count(Some(size as usize));
+  }
+  Ok(panic_guard::task(WriteParquetTask {
+    batch: Some(batch),
+    properties: Some(properties.build()),
+  }))
+}
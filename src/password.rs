@@ -0,0 +1,106 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// Argon2id cost parameters. Anything left out uses the argon2 crate defaults
+/// (19 MiB memory, 2 iterations, 1 lane).
+#[napi(object)]
+pub struct HashOptions {
+  /// Memory cost in KiB.
+  pub memory_cost: Option<u32>,
+  /// Number of iterations.
+  pub time_cost: Option<u32>,
+  /// Degree of parallelism (lanes).
+  pub parallelism: Option<u32>,
+}
+
+fn hash_error(e: impl std::fmt::Display) -> AppError {
+  AppError::Crypto(format!("password hashing failed: {}", e))
+}
+
+pub struct HashPasswordTask {
+  password: String,
+  params: Params,
+}
+
+impl Task for HashPasswordTask {
+  type Output = Result<String>;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(
+      Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+        .hash_password(self.password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(hash_error),
+    )
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+pub struct VerifyPasswordTask {
+  password: String,
+  hash: String,
+}
+
+impl Task for VerifyPasswordTask {
+  type Output = Result<bool>;
+  type JsValue = bool;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    // bcrypt hashes are accepted so callers can migrate existing users.
+    if self.hash.starts_with("$2") {
+      return Ok(bcrypt::verify(&self.password, &self.hash).map_err(hash_error));
+    }
+    Ok(
+      PasswordHash::new(&self.hash)
+        .map_err(hash_error)
+        .map(|parsed| {
+          Argon2::default()
+            .verify_password(self.password.as_bytes(), &parsed)
+            .is_ok()
+        }),
+    )
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Hashes `password` with argon2id on the thread pool and resolves with the
+/// PHC string (`$argon2id$v=19$...`).
+#[napi(catch_unwind, ts_return_type = "Promise<string>")]
+pub fn hash_password(
+  password: String,
+  opts: Option<HashOptions>,
+) -> Result<AsyncTask<Guarded<HashPasswordTask>>> {
+  let opts = opts.unwrap_or(HashOptions {
+    memory_cost: None,
+    time_cost: None,
+    parallelism: None,
+  });
+  let params = Params::new(
+    opts.memory_cost.unwrap_or(Params::DEFAULT_M_COST),
+    opts.time_cost.unwrap_or(Params::DEFAULT_T_COST),
+    opts.parallelism.unwrap_or(Params::DEFAULT_P_COST),
+    None,
+  )
+  .map_err(|e| AppError::InvalidInput(format!("invalid hash options: {}", e)))?;
+  Ok(panic_guard::task(HashPasswordTask { password, params }))
+}
+
+/// Checks `password` against an argon2 or bcrypt `hash`.
+#[napi(catch_unwind, ts_return_type = "Promise<boolean>")]
+pub fn verify_password(password: String, hash: String) -> AsyncTask<Guarded<VerifyPasswordTask>> {
+  panic_guard::task(VerifyPasswordTask { password, hash })
+}
@@ -0,0 +1,115 @@
+use std::io::Read;
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// Format marker and version of the patches written here. They are not
+/// compatible with the classic `bsdiff` tool's `BSDIFF40` files.
+const MAGIC: &[u8; 8] = b"NRSDIFF1";
+/// Magic, old and new lengths, then BLAKE3 hashes of old and new.
+const HEADER_LEN: usize = 8 + 8 + 8 + 32 + 32;
+/// Patches compress well, as unchanged regions turn into runs of zeroes.
+const ZSTD_LEVEL: i32 = 19;
+
+fn create(old: &[u8], new: &[u8]) -> Result<Vec<u8>> {
+  let mut diff = Vec::new();
+  bsdiff::diff(old, new, &mut diff).map_err(|e| AppError::Io(format!("diff: {}", e)))?;
+  let mut patch = Vec::with_capacity(HEADER_LEN + diff.len() / 4);
+  patch.extend_from_slice(MAGIC);
+  patch.extend_from_slice(&(old.len() as u64).to_le_bytes());
+  patch.extend_from_slice(&(new.len() as u64).to_le_bytes());
+  patch.extend_from_slice(blake3::hash(old).as_bytes());
+  patch.extend_from_slice(blake3::hash(new).as_bytes());
+  zstd::stream::copy_encode(&diff[..], &mut patch, ZSTD_LEVEL)
+    .map_err(|e| AppError::Io(format!("compressing patch: {}", e)))?;
+  Ok(patch)
+}
+
+fn apply(old: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+  if patch.len() < HEADER_LEN || &patch[..8] != MAGIC {
+    return Err(AppError::Parse(
+      "not a patch made by createBinaryPatch".into(),
+    ));
+  }
+  let old_len = u64::from_le_bytes(patch[8..16].try_into().expect("8 bytes"));
+  let new_len = u64::from_le_bytes(patch[16..24].try_into().expect("8 bytes"));
+  if old_len != old.len() as u64 || blake3::hash(old).as_bytes() != &patch[24..56] {
+    return Err(AppError::InvalidInput(
+      "patch was made against different contents".into(),
+    ));
+  }
+  let corrupt = |e: std::io::Error| AppError::Parse(format!("corrupt patch: {}", e));
+  // Each control entry adds its lengths' worth of bytes to the output
+  // after 24 bytes of header, so a diff that decompresses to much more than
+  // the promised output is corrupt and isn't worth inflating.
+  let limit = new_len.saturating_mul(25).saturating_add(24);
+  let mut diff = zstd::stream::Decoder::new(&patch[HEADER_LEN..])
+    .map_err(corrupt)?
+    .take(limit);
+  let mut new = Vec::new();
+  bsdiff::patch(old, &mut diff, &mut new).map_err(corrupt)?;
+  if new.len() as u64 != new_len || blake3::hash(&new).as_bytes() != &patch[56..88] {
+    return Err(AppError::Parse(
+      "corrupt patch: output does not match".into(),
+    ));
+  }
+  Ok(new)
+}
+
+/// Diffs on the libuv thread pool; suffix-sorting `old` takes a while for
+/// large inputs.
+pub struct CreatePatchTask {
+  old: Buffer,
+  new: Buffer,
+}
+
+impl Task for CreatePatchTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(create(&self.old, &self.new))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+pub struct ApplyPatchTask {
+  old: Buffer,
+  patch: Buffer,
+}
+
+impl Task for ApplyPatchTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(apply(&self.old, &self.patch))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+/// A bsdiff delta that turns `old` into `new`, zstd-compressed. For a
+/// new build of a binary it is typically a small fraction of the full
+/// file. Needs about 8 bytes of memory per byte of `old`.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn create_binary_patch(old: Buffer, new: Buffer) -> AsyncTask<Guarded<CreatePatchTask>> {
+  panic_guard::task(CreatePatchTask { old, new })
+}
+
+/// Rebuilds `new` from `old` and a patch from `createBinaryPatch`. The patch
+/// records hashes of both, so applying it to the wrong `old` rejects with
+/// `ERR_INVALID_INPUT` and a damaged patch with `ERR_PARSE`, rather than
+/// producing a broken file.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn apply_binary_patch(old: Buffer, patch: Buffer) -> AsyncTask<Guarded<ApplyPatchTask>> {
+  panic_guard::task(ApplyPatchTask { old, patch })
+}
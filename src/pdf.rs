@@ -0,0 +1,148 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use pdf_extract::{Document, Object, OutputError, PlainTextOutput};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+pub struct PageText {
+  /// 1-based page number.
+  pub page: u32,
+  pub text: String,
+}
+
+#[napi(object)]
+pub struct PdfInfo {
+  /// PDF version from the file header, e.g. `1.7`.
+  pub version: String,
+  pub pages: u32,
+  pub encrypted: bool,
+  pub title: Option<String>,
+  pub author: Option<String>,
+  pub subject: Option<String>,
+  pub keywords: Option<String>,
+  pub creator: Option<String>,
+  pub producer: Option<String>,
+  /// Raw PDF date string, e.g. `D:20240101120000Z`.
+  pub creation_date: Option<String>,
+  pub modification_date: Option<String>,
+}
+
+fn pdf_error(e: pdf_extract::Error) -> AppError {
+  AppError::Parse(format!("invalid PDF: {}", e))
+}
+
+fn output_error(e: OutputError) -> AppError {
+  match e {
+    OutputError::PdfError(e) => pdf_error(e),
+    e => AppError::Parse(format!("cannot extract PDF text: {}", e)),
+  }
+}
+
+fn load(input: &[u8], password: Option<&str>) -> Result<Document> {
+  let mut doc = Document::load_mem(input).map_err(pdf_error)?;
+  if doc.is_encrypted() {
+    // Most encrypted PDFs only restrict editing and open with an empty user
+    // password.
+    doc
+      .decrypt(password.unwrap_or(""))
+      .map_err(|_| AppError::Crypto("PDF is encrypted and the password is wrong".into()))?;
+  }
+  Ok(doc)
+}
+
+/// The text-extraction crate panics on some malformed content streams;
+/// report those as parse errors instead of aborting the process.
+fn guarded<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+  catch_unwind(AssertUnwindSafe(f))
+    .unwrap_or_else(|_| Err(AppError::Parse("invalid PDF: malformed content".into())))
+}
+
+fn extract(input: &[u8], password: Option<&str>) -> Result<Vec<PageText>> {
+  guarded(|| {
+    let doc = load(input, password)?;
+    doc
+      .get_pages()
+      .into_keys()
+      .map(|page| {
+        let mut text = String::new();
+        pdf_extract::output_doc_page(&doc, &mut PlainTextOutput::new(&mut text), page)
+          .map_err(output_error)?;
+        Ok(PageText { page, text })
+      })
+      .collect()
+  })
+}
+
+pub struct ExtractPdfTextTask {
+  input: Buffer,
+  password: Option<String>,
+}
+
+impl Task for ExtractPdfTextTask {
+  type Output = Result<Vec<PageText>>;
+  type JsValue = Vec<PageText>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(extract(&self.input, self.password.as_deref()))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Extracts the text of every page on the libuv thread pool. Encrypted
+/// documents are opened with `password`, or the empty password when
+/// omitted; a wrong password rejects with `ERR_CRYPTO`.
+#[napi(catch_unwind, ts_return_type = "Promise<PageText[]>")]
+pub fn extract_pdf_text(
+  input: Buffer,
+  password: Option<String>,
+) -> AsyncTask<Guarded<ExtractPdfTextTask>> {
+  panic_guard::task(ExtractPdfTextTask { input, password })
+}
+
+/// Reads the page count and the document information dictionary without
+/// decoding any page content.
+#[napi(catch_unwind)]
+pub fn pdf_metadata(input: Buffer) -> Result<PdfInfo> {
+  guarded(|| {
+    let mut doc = Document::load_mem(&input).map_err(pdf_error)?;
+    let encrypted = doc.is_encrypted();
+    if encrypted {
+      // Info strings are encrypted too; decrypt when no password is needed.
+      let _ = doc.decrypt("");
+    }
+    let info = doc
+      .trailer
+      .get(b"Info")
+      .and_then(|info| doc.dereference(info))
+      .and_then(|(_, info)| info.as_dict())
+      .ok();
+    let field = |key: &[u8]| -> Option<String> {
+      let value = info?.get(key).ok()?;
+      let (_, value) = doc.dereference(value).ok()?;
+      match value {
+        Object::String(..) => pdf_extract::decode_text_string(value).ok(),
+        _ => None,
+      }
+    };
+    Ok(PdfInfo {
+      version: doc.version.clone(),
+      pages: doc.get_pages().len() as u32,
+      encrypted,
+      title: field(b"Title"),
+      author: field(b"Author"),
+      subject: field(b"Subject"),
+      keywords: field(b"Keywords"),
+      creator: field(b"Creator"),
+      producer: field(b"Producer"),
+      creation_date: field(b"CreationDate"),
+      modification_date: field(b"ModDate"),
+    })
+  })
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use rphonetic::{DoubleMetaphone, Encoder, Metaphone, Soundex};
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct MetaphoneOptions {
+  /// Longest code to produce. Defaults to 4, the classic length; 0 means no
+  /// limit.
+  pub max_length: Option<u32>,
+}
+
+#[napi(object)]
+pub struct DoubleMetaphoneCodes {
+  pub primary: String,
+  /// The code for the other plausible pronunciation; the same as `primary`
+  /// when there is only one.
+  pub alternate: String,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct NgramOptions {
+  /// Split into words and take runs of `n` words ("shingles") instead of
+  /// `n` characters. Defaults to `false`.
+  pub words: Option<bool>,
+}
+
+fn max_length(options: Option<MetaphoneOptions>) -> Option<usize> {
+  match options.unwrap_or_default().max_length {
+    None => Some(4),
+    Some(0) => None,
+    Some(n) => Some(n as usize),
+  }
+}
+
+/// American Soundex: the first letter and three digits, e.g. `R163` for
+/// both "Robert" and "Rupert". Characters other than ASCII letters are
+/// ignored, so a string without any gives `""`.
+#[napi(catch_unwind)]
+pub fn soundex(text: String) -> String {
+  Soundex::default().encode(&text)
+}
+
+/// The original Metaphone code of `text`, e.g. `NT` for "Knight".
+#[napi(catch_unwind)]
+pub fn metaphone(text: String, options: Option<MetaphoneOptions>) -> String {
+  Metaphone::new(max_length(options)).encode(&text)
+}
+
+/// Double Metaphone codes of `text`. Unlike `metaphone` it knows many
+/// non-English spellings, so "Schmidt" (primary `XMT`) and "Smith"
+/// (alternate `XMT`) can be matched by comparing both codes.
+#[napi(catch_unwind)]
+pub fn double_metaphone(text: String, options: Option<MetaphoneOptions>) -> DoubleMetaphoneCodes {
+  let codes = DoubleMetaphone::new(max_length(options)).double_metaphone(&text);
+  DoubleMetaphoneCodes {
+    primary: codes.primary(),
+    alternate: codes.alternate(),
+  }
+}
+
+fn split(text: &str, n: usize, words: bool) -> Vec<String> {
+  if words {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.windows(n).map(|w| w.join(" ")).collect()
+  } else {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(n).map(|w| w.iter().collect()).collect()
+  }
+}
+
+fn check_n(n: u32) -> Result<usize> {
+  if n == 0 {
+    return Err(AppError::InvalidInput("n must be at least 1".into()));
+  }
+  Ok(n as usize)
+}
+
+/// Every run of `n` consecutive characters (or words) in `text`, in order
+/// and with repeats. Text shorter than `n` has none.
+#[napi(catch_unwind)]
+pub fn ngrams(text: String, n: u32, options: Option<NgramOptions>) -> Result<Vec<String>> {
+  let words = options.unwrap_or_default().words.unwrap_or(false);
+  Ok(split(&text, check_n(n)?, words))
+}
+
+/// Lowercases and collapses whitespace so case and spacing don't count as
+/// differences.
+fn normalize(text: &str) -> String {
+  text
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase()
+}
+
+/// How alike `a` and `b` are, from 0 to 1: the Jaccard similarity of their
+/// sets of `n`-character shingles (default 3), ignoring case and extra
+/// whitespace. Tolerant of reordered words, which edit distances are not.
+/// Strings too short to have a shingle are compared for equality.
+#[napi(catch_unwind)]
+pub fn shingle_similarity(
+  a: String,
+  b: String,
+  n: Option<u32>,
+  options: Option<NgramOptions>,
+) -> Result<f64> {
+  let n = check_n(n.unwrap_or(3))?;
+  let words = options.unwrap_or_default().words.unwrap_or(false);
+  let (a, b) = (normalize(&a), normalize(&b));
+  let set_a: HashSet<String> = split(&a, n, words).into_iter().collect();
+  let set_b: HashSet<String> = split(&b, n, words).into_iter().collect();
+  if set_a.is_empty() || set_b.is_empty() {
+    return Ok(if a == b { 1.0 } else { 0.0 });
+  }
+  let shared = set_a.intersection(&set_b).count();
+  Ok(shared as f64 / (set_a.len() + set_b.len() - shared) as f64)
+}
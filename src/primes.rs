@@ -0,0 +1,52 @@
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::panic_guard::{self, Guarded};
+
+/// Sieve of Eratosthenes run on the libuv thread pool.
+pub struct PrimeTask {
+  limit: u32,
+}
+
+impl Task for PrimeTask {
+  type Output = Vec<u32>;
+  type JsValue = Vec<u32>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(sieve(self.limit))
+  }
+
+  fn resolve(&mut self, _: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+fn sieve(limit: u32) -> Vec<u32> {
+  let limit = limit as usize;
+  if limit < 2 {
+    return Vec::new();
+  }
+  let mut composite = vec![false; limit + 1];
+  let mut primes = Vec::new();
+  for n in 2..=limit {
+    if composite[n] {
+      continue;
+    }
+    primes.push(n as u32);
+    let mut multiple = n * n;
+    while multiple <= limit {
+      composite[multiple] = true;
+      multiple += n;
+    }
+  }
+  primes
+}
+
+/// Resolves with every prime `<= limit`.
+///
+/// Aborting `signal` rejects the Promise with an `AbortError` right away; if
+/// the work hasn't been picked up by a pool thread yet it is cancelled too.
+#[napi(catch_unwind, ts_return_type = "Promise<number[]>")]
+pub fn compute_primes(limit: u32, signal: Option<AbortSignal>) -> AsyncTask<Guarded<PrimeTask>> {
+  panic_guard::task_with_signal(PrimeTask { limit }, signal)
+}
@@ -0,0 +1,245 @@
+use napi::bindgen_prelude::*;
+use xxhash_rust::xxh64::xxh64;
+
+use crate::error::{AppError, Result};
+use crate::hash::input_bytes;
+
+const BLOOM_MAGIC: &[u8; 4] = b"BLM1";
+const HLL_MAGIC: &[u8; 4] = b"HLL1";
+
+fn invalid(what: &str) -> AppError {
+  AppError::Parse(format!("invalid serialized {}", what))
+}
+
+/// Reads a little-endian integer of `N` bytes at `*pos`, advancing it.
+fn take<const N: usize>(data: &[u8], pos: &mut usize, what: &str) -> Result<[u8; N]> {
+  let bytes = data
+    .get(*pos..*pos + N)
+    .ok_or_else(|| invalid(what))?
+    .try_into()
+    .expect("slice has length N");
+  *pos += N;
+  Ok(bytes)
+}
+
+/// Bit positions for `item`, derived from two hashes (Kirsch–Mitzenmacher)
+/// instead of `hashes` independent ones.
+fn positions(item: &[u8], bit_count: u64, hashes: u32) -> impl Iterator<Item = u64> {
+  let (h1, h2) = (xxh64(item, 0), xxh64(item, 0x9e37_79b9_7f4a_7c15) | 1);
+  (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+}
+
+/// Fixed-size set membership filter: `mightContain` never gives a false
+/// negative, and false positives stay near the rate chosen at construction
+/// as long as no more than `expectedItems` are added.
+#[napi]
+pub struct BloomFilter {
+  bits: Vec<u64>,
+  bit_count: u64,
+  hashes: u32,
+  items: u64,
+}
+
+#[napi]
+impl BloomFilter {
+  /// Sizes the filter for `expectedItems` at `falsePositiveRate` (default
+  /// 0.01).
+  #[napi(catch_unwind, constructor)]
+  pub fn new(expected_items: u32, false_positive_rate: Option<f64>) -> Result<Self> {
+    let rate = false_positive_rate.unwrap_or(0.01);
+    if expected_items == 0 || !(rate > 0.0 && rate < 1.0) {
+      return Err(AppError::InvalidInput(
+        "expectedItems must be positive and falsePositiveRate between 0 and 1".into(),
+      ));
+    }
+    let n = expected_items as f64;
+    let ln2 = std::f64::consts::LN_2;
+    let bit_count = (-n * rate.ln() / (ln2 * ln2)).ceil().max(64.0) as u64;
+    let hashes = ((bit_count as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+    Ok(BloomFilter {
+      bits: vec![0; bit_count.div_ceil(64) as usize],
+      bit_count,
+      hashes,
+      items: 0,
+    })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn add(&mut self, item: Either<String, Buffer>) {
+    for bit in positions(input_bytes(&item), self.bit_count, self.hashes) {
+      self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+    }
+    self.items += 1;
+  }
+
+  #[napi(catch_unwind)]
+  pub fn might_contain(&self, item: Either<String, Buffer>) -> bool {
+    positions(input_bytes(&item), self.bit_count, self.hashes)
+      .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+  }
+
+  /// Number of `add` calls so far, duplicates included.
+  #[napi(catch_unwind, getter)]
+  pub fn count(&self) -> f64 {
+    self.items as f64
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn bit_count(&self) -> f64 {
+    self.bit_count as f64
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn hash_count(&self) -> u32 {
+    self.hashes
+  }
+
+  #[napi(catch_unwind)]
+  pub fn serialize(&self) -> Buffer {
+    let mut out = Vec::with_capacity(24 + self.bits.len() * 8);
+    out.extend_from_slice(BLOOM_MAGIC);
+    out.extend_from_slice(&self.hashes.to_le_bytes());
+    out.extend_from_slice(&self.bit_count.to_le_bytes());
+    out.extend_from_slice(&self.items.to_le_bytes());
+    for word in &self.bits {
+      out.extend_from_slice(&word.to_le_bytes());
+    }
+    out.into()
+  }
+
+  #[napi(catch_unwind, factory)]
+  pub fn deserialize(data: Buffer) -> Result<Self> {
+    let what = "BloomFilter";
+    let mut pos = 0;
+    if &take::<4>(&data, &mut pos, what)? != BLOOM_MAGIC {
+      return Err(invalid(what));
+    }
+    let hashes = u32::from_le_bytes(take(&data, &mut pos, what)?);
+    let bit_count = u64::from_le_bytes(take(&data, &mut pos, what)?);
+    let items = u64::from_le_bytes(take(&data, &mut pos, what)?);
+    let words = &data[pos..];
+    if hashes == 0 || bit_count == 0 || words.len() as u64 != bit_count.div_ceil(64) * 8 {
+      return Err(invalid(what));
+    }
+    Ok(BloomFilter {
+      bits: words
+        .chunks_exact(8)
+        .map(|w| u64::from_le_bytes(w.try_into().unwrap()))
+        .collect(),
+      bit_count,
+      hashes,
+      items,
+    })
+  }
+}
+
+/// Estimates the number of distinct items seen using 2^`precision` bytes,
+/// with a standard error of about 1.04 / sqrt(2^precision) (0.8% at the
+/// default precision of 14).
+#[napi]
+pub struct HyperLogLog {
+  precision: u8,
+  registers: Vec<u8>,
+}
+
+#[napi]
+impl HyperLogLog {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(precision: Option<u8>) -> Result<Self> {
+    let precision = precision.unwrap_or(14);
+    if !(4..=18).contains(&precision) {
+      return Err(AppError::InvalidInput(format!(
+        "precision must be between 4 and 18, got {}",
+        precision
+      )));
+    }
+    Ok(HyperLogLog {
+      precision,
+      registers: vec![0; 1 << precision],
+    })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn add(&mut self, item: Either<String, Buffer>) {
+    let hash = xxh64(input_bytes(&item), 0);
+    let index = (hash >> (64 - self.precision)) as usize;
+    // Rank of the first set bit in the remaining bits; the sentinel bit
+    // caps it when they are all zero.
+    let rest = (hash << self.precision) | (1 << (self.precision - 1));
+    let rank = rest.leading_zeros() as u8 + 1;
+    let register = &mut self.registers[index];
+    *register = (*register).max(rank);
+  }
+
+  /// Estimated number of distinct items added.
+  #[napi(catch_unwind)]
+  pub fn count(&self) -> f64 {
+    let m = self.registers.len() as f64;
+    let alpha = match self.registers.len() {
+      16 => 0.673,
+      32 => 0.697,
+      64 => 0.709,
+      _ => 0.7213 / (1.0 + 1.079 / m),
+    };
+    let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+    let estimate = alpha * m * m / sum;
+    let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+    // Linear counting is far more accurate while many registers are empty.
+    if estimate <= 2.5 * m && zeros > 0 {
+      (m * (m / zeros as f64).ln()).round()
+    } else {
+      estimate.round()
+    }
+  }
+
+  /// Folds `other` into this sketch, as if its items had been added here.
+  /// Both must have the same precision.
+  #[napi(catch_unwind)]
+  pub fn merge(&mut self, other: &HyperLogLog) -> Result<()> {
+    if other.precision != self.precision {
+      return Err(AppError::InvalidInput(format!(
+        "cannot merge precision {} into precision {}",
+        other.precision, self.precision
+      )));
+    }
+    for (mine, theirs) in self.registers.iter_mut().zip(&other.registers) {
+      *mine = (*mine).max(*theirs);
+    }
+    Ok(())
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn precision(&self) -> u8 {
+    self.precision
+  }
+
+  #[napi(catch_unwind)]
+  pub fn serialize(&self) -> Buffer {
+    let mut out = Vec::with_capacity(5 + self.registers.len());
+    out.extend_from_slice(HLL_MAGIC);
+    out.push(self.precision);
+    out.extend_from_slice(&self.registers);
+    out.into()
+  }
+
+  #[napi(catch_unwind, factory)]
+  pub fn deserialize(data: Buffer) -> Result<Self> {
+    let what = "HyperLogLog";
+    let mut pos = 0;
+    if &take::<4>(&data, &mut pos, what)? != HLL_MAGIC {
+      return Err(invalid(what));
+    }
+    let [precision] = take::<1>(&data, &mut pos, what)?;
+    let registers = &data[pos..];
+    if !(4..=18).contains(&precision)
+      || registers.len() != 1 << precision
+      || registers.iter().any(|&r| r > 65)
+    {
+      return Err(invalid(what));
+    }
+    Ok(HyperLogLog {
+      precision,
+      registers: registers.to_vec(),
+    })
+  }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+use crate::error::{self, AppError, Result};
+
+type ChunkListener = ThreadsafeFunction<Vec<u8>, ErrorStrategy::Fatal>;
+
+#[napi(object, object_to_js = false)]
+#[derive(Default)]
+pub struct RunCommandOptions {
+  /// Working directory; the current one by default.
+  pub cwd: Option<String>,
+  /// Variables added to (or overriding) the inherited environment.
+  pub env: Option<HashMap<String, String>>,
+  /// Start from an empty environment instead of inheriting this process's.
+  pub clear_env: Option<bool>,
+  /// Written to the child's stdin, which is then closed. Without it stdin is
+  /// `/dev/null`.
+  pub input: Option<Either<String, Buffer>>,
+  /// Kill the child and reject with `ERR_TIMEOUT` after this long.
+  pub timeout_ms: Option<u32>,
+  /// Kill the child and reject with an `AbortError` when aborted.
+  #[napi(ts_type = "AbortSignal")]
+  pub signal: Option<JsObject>,
+  /// Receives stdout as it arrives instead of it being collected into the
+  /// result.
+  #[napi(ts_type = "(chunk: Buffer) => void")]
+  pub on_stdout: Option<JsFunction>,
+  /// Receives stderr as it arrives instead of it being collected into the
+  /// result.
+  #[napi(ts_type = "(chunk: Buffer) => void")]
+  pub on_stderr: Option<JsFunction>,
+}
+
+#[napi(object)]
+pub struct CommandResult {
+  /// Exit status; absent when the child was killed by a signal.
+  pub exit_code: Option<i32>,
+  /// Number of the signal that killed the child (Unix only).
+  pub signal: Option<i32>,
+  /// Collected output; empty when streamed through `onStdout`.
+  pub stdout: Buffer,
+  /// Collected output; empty when streamed through `onStderr`.
+  pub stderr: Buffer,
+  pub duration_ms: f64,
+}
+
+/// What `run` hands back before it is turned into JS values on the main
+/// thread (`Buffer` is not `Send`).
+struct Finished {
+  exit_code: Option<i32>,
+  signal: Option<i32>,
+  stdout: Vec<u8>,
+  stderr: Vec<u8>,
+  duration_ms: f64,
+}
+
+impl ToNapiValue for Finished {
+  unsafe fn to_napi_value(
+    env: napi::sys::napi_env,
+    val: Self,
+  ) -> napi::Result<napi::sys::napi_value> {
+    CommandResult::to_napi_value(
+      env,
+      CommandResult {
+        exit_code: val.exit_code,
+        signal: val.signal,
+        stdout: val.stdout.into(),
+        stderr: val.stderr.into(),
+        duration_ms: val.duration_ms,
+      },
+    )
+  }
+}
+
+fn abort_error() -> AppError {
+  AppError::Napi(napi::Error::new(Status::Cancelled, "AbortError".to_owned()))
+}
+
+fn listener(callback: Option<JsFunction>) -> Result<Option<ChunkListener>> {
+  callback
+    .map(|callback| {
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Vec<u8>>| {
+        Ok(vec![ctx.env.create_buffer_with_data(ctx.value)?.into_raw()])
+      })
+    })
+    .transpose()
+    .map_err(AppError::from)
+}
+
+/// Reads `stream` to the end, either forwarding each chunk to `listener` or
+/// collecting it.
+async fn drain(
+  mut stream: impl AsyncRead + Unpin,
+  listener: Option<ChunkListener>,
+) -> std::io::Result<Vec<u8>> {
+  let mut collected = Vec::new();
+  let mut chunk = vec![0u8; 64 * 1024];
+  loop {
+    let n = stream.read(&mut chunk).await?;
+    if n == 0 {
+      return Ok(collected);
+    }
+    match &listener {
+      Some(listener) => {
+        listener.call(chunk[..n].to_vec(), ThreadsafeFunctionCallMode::NonBlocking);
+      }
+      None => collected.extend_from_slice(&chunk[..n]),
+    }
+  }
+}
+
+/// Registers a one-shot `abort` listener on `signal`. Returns `None` when it
+/// is already aborted.
+fn on_abort(env: &Env, signal: &JsObject) -> Result<Option<oneshot::Receiver<()>>> {
+  if signal.get_named_property::<bool>("aborted")? {
+    return Ok(None);
+  }
+  let (tx, rx) = oneshot::channel();
+  let tx = Mutex::new(Some(tx));
+  let handler = env.create_function_from_closure("onabort", move |ctx| {
+    if let Some(tx) = tx.lock().unwrap_or_else(|e| e.into_inner()).take() {
+      let _ = tx.send(());
+    }
+    ctx.env.get_undefined()
+  })?;
+  let mut once = env.create_object()?;
+  once.set_named_property("once", true)?;
+  let add: JsFunction = signal.get_named_property("addEventListener")?;
+  add.call(
+    Some(signal),
+    &[
+      env.create_string("abort")?.into_unknown(),
+      handler.into_unknown(),
+      once.into_unknown(),
+    ],
+  )?;
+  Ok(Some(rx))
+}
+
+/// Runs `command` directly — no shell, so arguments are passed verbatim and
+/// never re-split or globbed — and resolves once it exits and its output is
+/// drained. A non-zero exit still resolves; check `exitCode`.
+///
+/// A missing executable rejects with `ERR_NOT_FOUND`. On timeout or abort the
+/// child is killed (SIGKILL on Unix) before the Promise rejects.
+#[napi(catch_unwind, ts_return_type = "Promise<CommandResult>")]
+pub fn run_command(
+  env: Env,
+  command: String,
+  args: Option<Vec<String>>,
+  options: Option<RunCommandOptions>,
+) -> Result<JsObject> {
+  let options = options.unwrap_or_default();
+  let mut cmd = Command::new(&command);
+  cmd
+    .args(args.unwrap_or_default())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  if let Some(cwd) = &options.cwd {
+    cmd.current_dir(cwd);
+  }
+  if options.clear_env.unwrap_or(false) {
+    cmd.env_clear();
+  }
+  if let Some(vars) = &options.env {
+    cmd.envs(vars);
+  }
+  let input: Option<Vec<u8>> = options.input.map(|input| match input {
+    Either::A(text) => text.into_bytes(),
+    Either::B(bytes) => bytes.to_vec(),
+  });
+  cmd.stdin(if input.is_some() {
+    Stdio::piped()
+  } else {
+    Stdio::null()
+  });
+  let timeout = options
+    .timeout_ms
+    .map(|ms| Duration::from_millis(ms as u64));
+  let aborted = match &options.signal {
+    Some(signal) => match on_abort(&env, signal)? {
+      Some(rx) => Some(rx),
+      None => return error::spawn(&env, async { Err::<Finished, _>(abort_error()) }),
+    },
+    None => None,
+  };
+  let on_stdout = listener(options.on_stdout)?;
+  let on_stderr = listener(options.on_stderr)?;
+
+  error::spawn(&env, async move {
+    let started = Instant::now();
+    let mut child = cmd.spawn().map_err(|e| AppError::io(&command, e))?;
+    tracing::debug!(command, pid = child.id(), "spawned");
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+      // Fed concurrently with draining the output, so a child that writes
+      // before reading everything cannot deadlock against us. A child that
+      // exits without reading its input is not an error.
+      tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+      });
+    }
+    let stdout = drain(child.stdout.take().expect("stdout is piped"), on_stdout);
+    let stderr = drain(child.stderr.take().expect("stderr is piped"), on_stderr);
+    let finished = async {
+      let (stdout, stderr) = tokio::try_join!(stdout, stderr)?;
+      let status = child.wait().await?;
+      std::io::Result::Ok((status, stdout, stderr))
+    };
+    let deadline = async {
+      match timeout {
+        Some(timeout) => tokio::time::sleep(timeout).await,
+        None => std::future::pending().await,
+      }
+    };
+    let abort = async {
+      // A dropped sender means the signal was collected without firing.
+      if let Some(rx) = aborted {
+        if rx.await.is_ok() {
+          return;
+        }
+      }
+      std::future::pending().await
+    };
+    // The child is killed by `kill_on_drop` when the other branches win.
+    let (status, stdout, stderr) = tokio::select! {
+      result = finished => result.map_err(|e| AppError::Io(format!("{}: {}", command, e)))?,
+      _ = deadline => {
+        tracing::debug!(command, "timed out; killing");
+        return Err(AppError::Timeout(format!(
+          "{} timed out after {} ms",
+          command,
+          timeout.expect("deadline only fires with a timeout").as_millis()
+        )));
+      }
+      _ = abort => {
+        tracing::debug!(command, "aborted; killing");
+        return Err(abort_error());
+      }
+    };
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+    #[cfg(not(unix))]
+    let signal = None;
+    let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    tracing::debug!(
+      command,
+      exit_code = status.code(),
+      signal,
+      duration_ms,
+      "exited"
+    );
+    Ok(Finished {
+      exit_code: status.code(),
+      signal,
+      stdout,
+      stderr,
+      duration_ms,
+    })
+  })
+}
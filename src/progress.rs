@@ -0,0 +1,89 @@
+use std::sync::mpsc;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown, Task};
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+pub struct ProcessResult {
+  pub processed: u32,
+  /// Hex SHA-256 of each input item, in order.
+  pub digests: Vec<String>,
+}
+
+type ProgressFn = ThreadsafeFunction<(f64, String), ErrorStrategy::Fatal>;
+
+pub struct ProcessItemsTask {
+  items: Vec<String>,
+  on_progress: ProgressFn,
+}
+
+impl Task for ProcessItemsTask {
+  type Output = ProcessResult;
+  type JsValue = ProcessResult;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let total = self.items.len();
+    let mut digests = Vec::with_capacity(total);
+    for (i, item) in self.items.iter().enumerate() {
+      digests.push(hex::encode(Sha256::digest(item.as_bytes())));
+      let percent = (i + 1) as f64 / total as f64 * 100.0;
+      let message = format!("processed {}/{}", i + 1, total);
+      if i + 1 < total {
+        self
+          .on_progress
+          .call((percent, message), ThreadsafeFunctionCallMode::Blocking);
+      } else {
+        // Wait until the last report has actually run in JS, so every
+        // progress callback fires before the Promise resolves.
+        let (done_tx, done_rx) = mpsc::channel();
+        self.on_progress.call_with_return_value(
+          (percent, message),
+          ThreadsafeFunctionCallMode::Blocking,
+          move |_: JsUnknown| {
+            let _ = done_tx.send(());
+            Ok(())
+          },
+        );
+        let _ = done_rx.recv();
+      }
+    }
+    Ok(ProcessResult {
+      processed: total as u32,
+      digests,
+    })
+  }
+
+  fn resolve(&mut self, _: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// Hashes every item on the thread pool, calling `onProgress(percent,
+/// message)` after each one, and resolves with the digests once all progress
+/// callbacks have run.
+#[napi(
+  catch_unwind,
+  ts_args_type = "items: string[], onProgress: (percent: number, message: string) => void",
+  ts_return_type = "Promise<ProcessResult>"
+)]
+pub fn process_items(
+  items: Vec<String>,
+  on_progress: JsFunction,
+) -> Result<AsyncTask<Guarded<ProcessItemsTask>>> {
+  let on_progress: ProgressFn =
+    on_progress.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(f64, String)>| {
+      let (percent, message) = ctx.value;
+      Ok(vec![
+        ctx.env.create_double(percent)?.into_unknown(),
+        ctx.env.create_string(&message)?.into_unknown(),
+      ])
+    })?;
+  Ok(panic_guard::task(ProcessItemsTask { items, on_progress }))
+}
@@ -0,0 +1,189 @@
+use napi::bindgen_prelude::*;
+use napi::JsUnknown;
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::FileDescriptorSet;
+use prost_reflect::{
+  DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, SerializeOptions,
+};
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ProtoDecodeOptions {
+  /// Use the field names from the `.proto` file (`user_id`) instead of
+  /// their JSON names (`userId`). Defaults to `false`.
+  pub proto_field_names: Option<bool>,
+  /// Return enum values as numbers instead of their names. Defaults to
+  /// `false`.
+  pub enums_as_numbers: Option<bool>,
+  /// Include fields set to their default value (`0`, `""`, `[]`, …), which
+  /// are otherwise left out. Defaults to `false`.
+  pub emit_defaults: Option<bool>,
+}
+
+/// Serves the source passed to `loadSchema`, files loaded earlier, and the
+/// well-known `google/protobuf/*.proto` types to the compiler.
+struct SchemaResolver {
+  name: String,
+  source: String,
+  loaded: DescriptorPool,
+}
+
+impl FileResolver for SchemaResolver {
+  fn open_file(&self, name: &str) -> std::result::Result<File, protox::Error> {
+    if name == self.name {
+      return File::from_source(name, &self.source);
+    }
+    match self.loaded.get_file_by_name(name) {
+      Some(file) => Ok(File::from_file_descriptor_proto(
+        file.file_descriptor_proto().clone(),
+      )),
+      None => Err(protox::Error::file_not_found(name)),
+    }
+  }
+}
+
+/// Protobuf message types loaded at runtime, for encoding and decoding
+/// without generated code.
+///
+/// Messages are converted using the standard protobuf JSON mapping: 64-bit
+/// integers and `bytes` are strings (decimal and base64), enums are names and
+/// well-known types such as `Timestamp` use their JSON forms.
+#[napi]
+pub struct ProtoRegistry {
+  pool: DescriptorPool,
+}
+
+impl Default for ProtoRegistry {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[napi]
+impl ProtoRegistry {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    ProtoRegistry {
+      pool: DescriptorPool::new(),
+    }
+  }
+
+  /// Adds the message types from `schema`: either `.proto` source text, or
+  /// an encoded `FileDescriptorSet` (as written by `protoc
+  /// --descriptor_set_out --include_imports`). Source may import files loaded
+  /// earlier by their `name` and the `google/protobuf/*.proto` well-known
+  /// types. Returns the full names of the message types it defined.
+  ///
+  /// Syntax and type errors throw `ERR_PARSE`.
+  #[napi(catch_unwind, ts_args_type = "schema: string | Buffer, name?: string")]
+  pub fn load_schema(
+    &mut self,
+    schema: Either<String, Buffer>,
+    name: Option<String>,
+  ) -> Result<Vec<String>> {
+    let files: Vec<String> = match schema {
+      Either::A(source) => {
+        let name = name.unwrap_or_else(|| "schema.proto".to_string());
+        let mut resolver = ChainFileResolver::new();
+        resolver.add(SchemaResolver {
+          name: name.clone(),
+          source,
+          loaded: self.pool.clone(),
+        });
+        resolver.add(GoogleFileResolver::new());
+        let mut compiler = protox::Compiler::with_file_resolver(resolver);
+        compiler.include_imports(true);
+        compiler
+          .open_file(&name)
+          .map_err(|e| AppError::Parse(format!("invalid schema {:?}: {}", name, e)))?;
+        self
+          .pool
+          .add_file_descriptor_set(compiler.file_descriptor_set())
+          .map_err(|e| AppError::Parse(format!("invalid schema {:?}: {}", name, e)))?;
+        vec![name]
+      }
+      Either::B(bytes) => {
+        let set = FileDescriptorSet::decode(bytes.as_ref())
+          .map_err(|e| AppError::Parse(format!("invalid FileDescriptorSet: {}", e)))?;
+        let files = set.file.iter().map(|f| f.name().to_string()).collect();
+        self
+          .pool
+          .add_file_descriptor_set(set)
+          .map_err(|e| AppError::Parse(format!("invalid FileDescriptorSet: {}", e)))?;
+        files
+      }
+    };
+    Ok(
+      self
+        .pool
+        .all_messages()
+        .filter(|m| !m.is_map_entry() && files.iter().any(|f| f == m.parent_file().name()))
+        .map(|m| m.full_name().to_string())
+        .collect(),
+    )
+  }
+
+  /// Full names of every loaded message type, e.g. `acme.v1.User`.
+  #[napi(catch_unwind, getter)]
+  pub fn message_types(&self) -> Vec<String> {
+    self
+      .pool
+      .all_messages()
+      .filter(|m| !m.is_map_entry())
+      .map(|m| m.full_name().to_string())
+      .collect()
+  }
+
+  /// Encodes `value` as the binary form of `messageType`. Unknown fields and
+  /// values of the wrong type throw `ERR_INVALID_INPUT`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "messageType: string, value: Record<string, unknown>"
+  )]
+  pub fn encode(&self, env: Env, message_type: String, value: JsUnknown) -> Result<Buffer> {
+    let value: Value = from_js(&env, value)?;
+    let descriptor = self.message(&message_type)?;
+    let message =
+      DynamicMessage::deserialize_with_options(descriptor, value, &DeserializeOptions::new())
+        .map_err(|e| AppError::InvalidInput(format!("invalid {}: {}", message_type, e)))?;
+    Ok(message.encode_to_vec().into())
+  }
+
+  /// Decodes the binary form of `messageType`. Malformed input throws
+  /// `ERR_PARSE`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "messageType: string, buffer: Buffer, options?: ProtoDecodeOptions",
+    ts_return_type = "Record<string, unknown>"
+  )]
+  pub fn decode(
+    &self,
+    message_type: String,
+    buffer: Buffer,
+    options: Option<ProtoDecodeOptions>,
+  ) -> Result<Value> {
+    let options = options.unwrap_or_default();
+    let descriptor = self.message(&message_type)?;
+    let message = DynamicMessage::decode(descriptor, buffer.as_ref())
+      .map_err(|e| AppError::Parse(format!("invalid {}: {}", message_type, e)))?;
+    let options = SerializeOptions::new()
+      .use_proto_field_name(options.proto_field_names.unwrap_or(false))
+      .use_enum_numbers(options.enums_as_numbers.unwrap_or(false))
+      .skip_default_fields(!options.emit_defaults.unwrap_or(false));
+    Ok(message.serialize_with_options(serde_json::value::Serializer, &options)?)
+  }
+}
+
+impl ProtoRegistry {
+  fn message(&self, name: &str) -> Result<MessageDescriptor> {
+    self
+      .pool
+      .get_message_by_name(name)
+      .ok_or_else(|| AppError::NotFound(format!("no message type named {:?}", name)))
+  }
+}
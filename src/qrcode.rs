@@ -0,0 +1,173 @@
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+use ::image::{ImageFormat, Rgba, RgbaImage};
+use ::qrcode::types::QrError;
+use ::qrcode::{Color, EcLevel, QrCode};
+use napi::bindgen_prelude::Buffer;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct QrOptions {
+  /// `L` (7%), `M` (15%, default), `Q` (25%) or `H` (30%) of the code can
+  /// be damaged and still scan.
+  #[napi(ts_type = "'L' | 'M' | 'Q' | 'H'")]
+  pub error_correction: Option<String>,
+  /// Quiet zone around the code, in modules. Defaults to 4, the minimum the
+  /// spec asks for.
+  pub margin: Option<u32>,
+  /// Width and height of the output in pixels. Defaults to 256. PNG output
+  /// is padded to exactly this size when it cannot be divided evenly.
+  pub size: Option<u32>,
+  /// `#rgb`, `#rrggbb` or `#rrggbbaa`. Defaults to `#000000`.
+  pub dark: Option<String>,
+  /// Defaults to `#ffffff`.
+  pub light: Option<String>,
+}
+
+struct Qr {
+  code: QrCode,
+  margin: usize,
+  size: u32,
+  dark: [u8; 4],
+  light: [u8; 4],
+}
+
+fn parse_color(color: Option<&str>, default: [u8; 4]) -> Result<[u8; 4]> {
+  let Some(color) = color else {
+    return Ok(default);
+  };
+  let invalid = || AppError::InvalidInput(format!("invalid color: {:?}", color));
+  let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+  let digits: Vec<u8> = hex
+    .chars()
+    .map(|c| c.to_digit(16).map(|d| d as u8))
+    .collect::<Option<_>>()
+    .ok_or_else(invalid)?;
+  match *digits.as_slice() {
+    [r, g, b] => Ok([r * 17, g * 17, b * 17, 255]),
+    [r1, r2, g1, g2, b1, b2] => Ok([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2, 255]),
+    [r1, r2, g1, g2, b1, b2, a1, a2] => {
+      Ok([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2, a1 << 4 | a2])
+    }
+    _ => Err(invalid()),
+  }
+}
+
+impl Qr {
+  fn new(data: &str, options: Option<QrOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let level = match options.error_correction.as_deref().unwrap_or("M") {
+      "L" => EcLevel::L,
+      "M" => EcLevel::M,
+      "Q" => EcLevel::Q,
+      "H" => EcLevel::H,
+      other => {
+        return Err(AppError::InvalidInput(format!(
+          "unknown error correction level: {}",
+          other
+        )))
+      }
+    };
+    let dark = parse_color(options.dark.as_deref(), [0, 0, 0, 255])?;
+    let light = parse_color(options.light.as_deref(), [255, 255, 255, 255])?;
+    let code = QrCode::with_error_correction_level(data, level).map_err(|e| match e {
+      QrError::DataTooLong => AppError::InvalidInput(format!(
+        "{} bytes is too long for a QR code at level {:?}",
+        data.len(),
+        level
+      )),
+      e => AppError::InvalidInput(e.to_string()),
+    })?;
+    let size = options.size.unwrap_or(256);
+    if size == 0 {
+      return Err(AppError::InvalidInput("size must be non-zero".into()));
+    }
+    Ok(Qr {
+      code,
+      margin: options.margin.unwrap_or(4) as usize,
+      size,
+      dark,
+      light,
+    })
+  }
+
+  /// Width of the code including the quiet zone, in modules.
+  fn modules(&self) -> usize {
+    self.code.width() + 2 * self.margin
+  }
+
+  fn is_dark(&self, x: usize, y: usize) -> bool {
+    let width = self.code.width();
+    let (Some(x), Some(y)) = (x.checked_sub(self.margin), y.checked_sub(self.margin)) else {
+      return false;
+    };
+    x < width && y < width && self.code[(x, y)] == Color::Dark
+  }
+
+  fn png(&self) -> Result<Vec<u8>> {
+    let modules = self.modules() as u32;
+    if self.size < modules {
+      return Err(AppError::InvalidInput(format!(
+        "size {} is smaller than the {} modules of this code",
+        self.size, modules
+      )));
+    }
+    let scale = self.size / modules;
+    let offset = (self.size - scale * modules) / 2;
+    let image = RgbaImage::from_fn(self.size, self.size, |px, py| {
+      let module = |p: u32| p.checked_sub(offset).map(|p| (p / scale) as usize);
+      match (module(px), module(py)) {
+        (Some(x), Some(y)) if self.is_dark(x, y) => Rgba(self.dark),
+        _ => Rgba(self.light),
+      }
+    });
+    let mut out = Cursor::new(Vec::new());
+    image
+      .write_to(&mut out, ImageFormat::Png)
+      .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok(out.into_inner())
+  }
+
+  fn svg(&self) -> String {
+    let css = |[r, g, b, a]: [u8; 4]| match a {
+      255 => format!("#{:02x}{:02x}{:02x}", r, g, b),
+      a => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+    };
+    let modules = self.modules();
+    let mut path = String::new();
+    for y in 0..modules {
+      for x in 0..modules {
+        if self.is_dark(x, y) {
+          let _ = write!(path, "M{},{}h1v1h-1z", x, y);
+        }
+      }
+    }
+    format!(
+      concat!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {n} {n}" shape-rendering="crispEdges">"#,
+        r#"<rect width="{n}" height="{n}" fill="{light}"/><path fill="{dark}" d="{path}"/></svg>"#
+      ),
+      size = self.size,
+      n = modules,
+      light = css(self.light),
+      dark = css(self.dark),
+      path = path
+    )
+  }
+}
+
+/// Encodes `data` as a QR code and renders it as a PNG.
+#[napi(catch_unwind)]
+pub fn generate_qr(data: String, options: Option<QrOptions>) -> Result<Buffer> {
+  Ok(Qr::new(&data, options)?.png()?.into())
+}
+
+/// Encodes `data` as a QR code and renders it as a standalone SVG document
+/// with one `<path>` for the dark modules.
+#[napi(catch_unwind)]
+pub fn generate_qr_svg(data: String, options: Option<QrOptions>) -> Result<String> {
+  Ok(Qr::new(&data, options)?.svg())
+}
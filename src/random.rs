@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use napi::bindgen_prelude::*;
+use napi::{JsObject, JsUnknown};
+use rand::distributions::{Distribution, Uniform};
+use rand::rngs::OsRng;
+use rand::{Rng as _, RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::error::{AppError, Result};
+
+const ALPHANUMERIC: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// Largest integer a JS number holds exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+fn int_range(min: i64, max: i64) -> Result<Uniform<i64>> {
+  if min >= max || min < -MAX_SAFE_INTEGER || max > MAX_SAFE_INTEGER {
+    return Err(AppError::InvalidInput(format!(
+      "need min < max within ±Number.MAX_SAFE_INTEGER, got [{}, {})",
+      min, max
+    )));
+  }
+  Ok(Uniform::new(min, max))
+}
+
+/// `n` bytes from the operating system's CSPRNG.
+#[napi(catch_unwind)]
+pub fn random_bytes(n: u32) -> Buffer {
+  let mut bytes = vec![0u8; n as usize];
+  OsRng.fill_bytes(&mut bytes);
+  bytes.into()
+}
+
+/// A uniformly distributed integer in `[min, max)` from the OS CSPRNG,
+/// without modulo bias (like `crypto.randomInt`).
+#[napi(catch_unwind)]
+pub fn random_int(min: i64, max: i64) -> Result<i64> {
+  Ok(int_range(min, max)?.sample(&mut OsRng))
+}
+
+/// A `length`-character string drawn uniformly from `alphabet` (default
+/// `0-9A-Za-z`) with the OS CSPRNG. The alphabet needs at least two
+/// distinct characters and no repeats.
+#[napi(catch_unwind)]
+pub fn random_token(length: u32, alphabet: Option<String>) -> Result<String> {
+  let alphabet: Vec<char> = alphabet
+    .as_deref()
+    .unwrap_or(ALPHANUMERIC)
+    .chars()
+    .collect();
+  if alphabet.len() < 2 || alphabet.iter().collect::<HashSet<_>>().len() != alphabet.len() {
+    return Err(AppError::InvalidInput(
+      "alphabet must have at least two characters and no repeats".into(),
+    ));
+  }
+  let pick = Uniform::new(0, alphabet.len());
+  Ok(
+    (0..length)
+      .map(|_| alphabet[pick.sample(&mut OsRng)])
+      .collect(),
+  )
+}
+
+/// A seeded xoshiro256++ generator. The same seed always produces the same
+/// sequence, on every platform, which makes simulations and tests
+/// reproducible. Not suitable for secrets; use `randomBytes` for those.
+#[napi]
+pub struct Rng {
+  inner: Xoshiro256PlusPlus,
+}
+
+#[napi]
+impl Rng {
+  /// Seeds from `seed` (an integer or BigInt below 2^64), or from the OS
+  /// CSPRNG when omitted.
+  #[napi(catch_unwind, constructor, ts_args_type = "seed?: number | bigint")]
+  pub fn new(seed: Option<Either<f64, BigInt>>) -> Result<Self> {
+    let seed = match seed {
+      None => OsRng.next_u64(),
+      Some(Either::A(n)) if n >= 0.0 && n.fract() == 0.0 && n <= MAX_SAFE_INTEGER as f64 => {
+        n as u64
+      }
+      Some(Either::B(big)) => match big.get_u64() {
+        (false, value, true) => value,
+        _ => {
+          return Err(AppError::InvalidInput(
+            "seed must fit in 64 unsigned bits".into(),
+          ))
+        }
+      },
+      Some(Either::A(n)) => {
+        return Err(AppError::InvalidInput(format!(
+          "seed must be a non-negative safe integer, got {}",
+          n
+        )))
+      }
+    };
+    Ok(Rng {
+      inner: Xoshiro256PlusPlus::seed_from_u64(seed),
+    })
+  }
+
+  /// A float in `[0, 1)` with 53 random bits.
+  #[napi(catch_unwind)]
+  pub fn next_float(&mut self) -> f64 {
+    self.inner.gen::<f64>()
+  }
+
+  /// An integer in `[min, max)`.
+  #[napi(catch_unwind)]
+  pub fn next_int(&mut self, min: i64, max: i64) -> Result<i64> {
+    Ok(int_range(min, max)?.sample(&mut self.inner))
+  }
+
+  /// Shuffles `array` in place (Fisher–Yates) and returns it.
+  #[napi(catch_unwind, ts_args_type = "array: T[]", ts_return_type = "T[]")]
+  pub fn shuffle(&mut self, array: JsObject) -> Result<JsObject> {
+    if !array.is_array()? {
+      return Err(AppError::InvalidInput("shuffle expects an array".into()));
+    }
+    let mut array = array;
+    for i in (1..array.get_array_length()?).rev() {
+      let j = self.inner.gen_range(0..=i);
+      if i != j {
+        let a: JsUnknown = array.get_element(i)?;
+        let b: JsUnknown = array.get_element(j)?;
+        array.set_element(i, b)?;
+        array.set_element(j, a)?;
+      }
+    }
+    Ok(array)
+  }
+}
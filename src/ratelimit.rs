@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::error::{AppError, Result};
+
+/// How often (in `tryAcquire` calls) idle keys are swept from memory.
+const SWEEP_EVERY: u32 = 1024;
+
+#[napi(object)]
+pub struct RateLimiterOptions {
+  /// `token-bucket` (default) or `sliding-window`.
+  pub algorithm: Option<String>,
+  /// Requests (or cost units) allowed per window. For a token bucket this is
+  /// also the burst size.
+  pub limit: u32,
+  /// Window length in milliseconds. A token bucket refills `limit` tokens
+  /// over this period.
+  pub window_ms: u32,
+}
+
+#[napi(object)]
+pub struct RateDecision {
+  pub allowed: bool,
+  /// Units still available after this call.
+  pub remaining: u32,
+  /// Milliseconds until the key is back to its full allowance.
+  pub reset_ms: f64,
+  /// Milliseconds until a request of the same cost would be allowed; 0 when
+  /// `allowed` is true.
+  pub retry_after_ms: f64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Algorithm {
+  TokenBucket,
+  SlidingWindow,
+}
+
+enum State {
+  Bucket {
+    tokens: f64,
+    updated: Instant,
+  },
+  /// Approximates a sliding window from the counts of the current and the
+  /// previous fixed window, weighting the previous one by how much of it
+  /// still overlaps the sliding window.
+  Window {
+    started: Instant,
+    current: f64,
+    previous: f64,
+  },
+}
+
+/// Per-key rate limiter. All state lives in Rust, so tracking many keys
+/// costs no JS heap; keys that have fully recovered are dropped
+/// periodically.
+#[napi]
+pub struct RateLimiter {
+  algorithm: Algorithm,
+  limit: f64,
+  window_ms: f64,
+  keys: HashMap<String, State>,
+  calls: u32,
+}
+
+#[napi]
+impl RateLimiter {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(opts: RateLimiterOptions) -> Result<Self> {
+    let algorithm = match opts.algorithm.as_deref().unwrap_or("token-bucket") {
+      "token-bucket" => Algorithm::TokenBucket,
+      "sliding-window" => Algorithm::SlidingWindow,
+      other => {
+        return Err(AppError::InvalidInput(format!(
+          "unknown rate limit algorithm: {}",
+          other
+        )))
+      }
+    };
+    if opts.limit == 0 || opts.window_ms == 0 {
+      return Err(AppError::InvalidInput(
+        "limit and windowMs must be non-zero".into(),
+      ));
+    }
+    Ok(RateLimiter {
+      algorithm,
+      limit: opts.limit as f64,
+      window_ms: opts.window_ms as f64,
+      keys: HashMap::new(),
+      calls: 0,
+    })
+  }
+
+  /// Takes `cost` (default 1) units from `key` if they are available.
+  #[napi(catch_unwind)]
+  pub fn try_acquire(&mut self, key: String, cost: Option<u32>) -> Result<RateDecision> {
+    let cost = cost.unwrap_or(1) as f64;
+    if cost > self.limit {
+      return Err(AppError::InvalidInput(format!(
+        "cost {} exceeds the limit of {}",
+        cost, self.limit
+      )));
+    }
+    let now = Instant::now();
+    self.calls = self.calls.wrapping_add(1);
+    if self.calls.is_multiple_of(SWEEP_EVERY) {
+      self.prune_at(now);
+    }
+
+    let (limit, window_ms, algorithm) = (self.limit, self.window_ms, self.algorithm);
+    let state = self.keys.entry(key).or_insert_with(|| match algorithm {
+      Algorithm::TokenBucket => State::Bucket {
+        tokens: limit,
+        updated: now,
+      },
+      Algorithm::SlidingWindow => State::Window {
+        started: now,
+        current: 0.0,
+        previous: 0.0,
+      },
+    });
+    Ok(match state {
+      State::Bucket { tokens, updated } => {
+        let rate = limit / window_ms;
+        *tokens = (*tokens + millis(now - *updated) * rate).min(limit);
+        *updated = now;
+        let allowed = *tokens >= cost;
+        if allowed {
+          *tokens -= cost;
+        }
+        RateDecision {
+          allowed,
+          remaining: tokens.floor() as u32,
+          reset_ms: ((limit - *tokens) / rate).ceil(),
+          retry_after_ms: if allowed {
+            0.0
+          } else {
+            ((cost - *tokens) / rate).ceil()
+          },
+        }
+      }
+      State::Window {
+        started,
+        current,
+        previous,
+      } => {
+        roll_window(now, window_ms, started, current, previous);
+        let elapsed = millis(now - *started);
+        let weight = 1.0 - elapsed / window_ms;
+        let used = *previous * weight + *current;
+        let allowed = used + cost <= limit;
+        if allowed {
+          *current += cost;
+        }
+        let used = *previous * weight + *current;
+        let until_rollover = window_ms - elapsed;
+        let reset_ms = if *current > 0.0 {
+          until_rollover + window_ms
+        } else if *previous > 0.0 {
+          until_rollover
+        } else {
+          0.0
+        };
+        RateDecision {
+          allowed,
+          remaining: (limit - used).max(0.0).floor() as u32,
+          reset_ms: reset_ms.ceil(),
+          retry_after_ms: if allowed {
+            0.0
+          } else {
+            window_retry(limit, cost, window_ms, elapsed, *current, *previous).ceil()
+          },
+        }
+      }
+    })
+  }
+
+  /// Forgets all state for `key`, restoring its full allowance.
+  #[napi(catch_unwind)]
+  pub fn reset(&mut self, key: String) -> bool {
+    self.keys.remove(&key).is_some()
+  }
+
+  /// Drops keys that are back to their full allowance. Runs automatically
+  /// every 1024 calls; returns how many keys were removed.
+  #[napi(catch_unwind)]
+  pub fn prune(&mut self) -> u32 {
+    self.prune_at(Instant::now())
+  }
+
+  /// Number of keys currently tracked.
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.keys.len() as u32
+  }
+
+  fn prune_at(&mut self, now: Instant) -> u32 {
+    let (limit, window_ms) = (self.limit, self.window_ms);
+    let before = self.keys.len();
+    self.keys.retain(|_, state| match state {
+      State::Bucket { tokens, updated } => {
+        *tokens + millis(now - *updated) * limit / window_ms < limit
+      }
+      State::Window { started, .. } => millis(now - *started) < 2.0 * window_ms,
+    });
+    (before - self.keys.len()) as u32
+  }
+}
+
+fn millis(d: std::time::Duration) -> f64 {
+  d.as_secs_f64() * 1000.0
+}
+
+/// Advances the fixed windows so `started` is the start of the window that
+/// contains `now`.
+fn roll_window(
+  now: Instant,
+  window_ms: f64,
+  started: &mut Instant,
+  current: &mut f64,
+  previous: &mut f64,
+) {
+  let windows = (millis(now - *started) / window_ms).floor();
+  if windows >= 1.0 {
+    *previous = if windows == 1.0 { *current } else { 0.0 };
+    *current = 0.0;
+    *started += std::time::Duration::from_secs_f64(windows * window_ms / 1000.0);
+  }
+}
+
+/// Time until `previous * weight + current + cost <= limit` holds again.
+fn window_retry(
+  limit: f64,
+  cost: f64,
+  window_ms: f64,
+  elapsed: f64,
+  current: f64,
+  previous: f64,
+) -> f64 {
+  if current + cost <= limit {
+    // Only the decaying previous window is in the way.
+    window_ms * (1.0 - (limit - current - cost) / previous) - elapsed
+  } else {
+    // Wait for this window to become the previous one and decay enough.
+    (window_ms - elapsed) + window_ms * (1.0 - (limit - cost) / current)
+  }
+}
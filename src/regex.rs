@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use ::regex::{Regex, RegexBuilder};
+
+use crate::error::{AppError, Result};
+
+/// One match from `Pattern.captures`.
+#[napi(object)]
+pub struct Captures {
+  /// The whole matched text.
+  pub matched: String,
+  /// Offset of the match in UTF-16 code units, like `RegExp` `match.index`.
+  pub index: u32,
+  /// Positional groups, starting at group 1; `null` for groups that didn't
+  /// participate in the match.
+  pub groups: Vec<Option<String>>,
+  /// Named groups that participated in the match.
+  pub named: HashMap<String, String>,
+}
+
+/// A regular expression compiled once in Rust.
+///
+/// Uses the `regex` crate's linear-time engine, so there is no catastrophic
+/// backtracking, but also no look-around or backreferences. `flags` may
+/// contain `i` (case-insensitive), `m` (multi-line), `s` (`.` matches `\n`),
+/// `x` (ignore whitespace) and `U` (swap greediness).
+#[napi]
+pub struct Pattern {
+  regex: Regex,
+  flags: String,
+}
+
+#[napi]
+impl Pattern {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(pattern: String, flags: Option<String>) -> Result<Self> {
+    let flags = flags.unwrap_or_default();
+    let mut builder = RegexBuilder::new(&pattern);
+    for flag in flags.chars() {
+      match flag {
+        'i' => builder.case_insensitive(true),
+        'm' => builder.multi_line(true),
+        's' => builder.dot_matches_new_line(true),
+        'x' => builder.ignore_whitespace(true),
+        'U' => builder.swap_greed(true),
+        other => {
+          return Err(AppError::InvalidInput(format!(
+            "unknown regex flag: {:?}",
+            other
+          )))
+        }
+      };
+    }
+    let regex = builder.build().map_err(|e| match e {
+      ::regex::Error::Syntax(msg) => AppError::Parse(format!("invalid regex: {}", msg)),
+      other => AppError::InvalidInput(other.to_string()),
+    })?;
+    Ok(Pattern { regex, flags })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn source(&self) -> String {
+    self.regex.as_str().to_string()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn flags(&self) -> String {
+    self.flags.clone()
+  }
+
+  #[napi(catch_unwind)]
+  pub fn is_match(&self, text: String) -> bool {
+    self.regex.is_match(&text)
+  }
+
+  /// All non-overlapping matches, in order.
+  #[napi(catch_unwind)]
+  pub fn find_all(&self, text: String) -> Vec<String> {
+    self
+      .regex
+      .find_iter(&text)
+      .map(|m| m.as_str().to_string())
+      .collect()
+  }
+
+  /// Replaces every match. `replacement` may refer to groups as `$1` or
+  /// `$name`; use `$$` for a literal `$`.
+  #[napi(catch_unwind)]
+  pub fn replace_all(&self, text: String, replacement: String) -> String {
+    self
+      .regex
+      .replace_all(&text, replacement.as_str())
+      .into_owned()
+  }
+
+  /// Capture groups of the first match, or `null` if there is none.
+  #[napi(catch_unwind)]
+  pub fn captures(&self, text: String) -> Option<Captures> {
+    let caps = self.regex.captures(&text)?;
+    let whole = caps.get(0)?;
+    let named = self
+      .regex
+      .capture_names()
+      .flatten()
+      .filter_map(|name| Some((name.to_string(), caps.name(name)?.as_str().to_string())))
+      .collect();
+    Some(Captures {
+      matched: whole.as_str().to_string(),
+      index: text[..whole.start()].encode_utf16().count() as u32,
+      groups: caps
+        .iter()
+        .skip(1)
+        .map(|g| g.map(|g| g.as_str().to_string()))
+        .collect(),
+      named,
+    })
+  }
+}
@@ -0,0 +1,462 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject, JsUnknown, NapiRaw, NapiValue};
+use rand::Rng;
+
+use crate::error::{self, AppError, Result};
+
+const MAX_RETRIES: u32 = 100;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct CircuitBreakerOptions {
+  /// Consecutive failures that open the circuit. Defaults to 5.
+  pub failure_threshold: Option<u32>,
+  /// Milliseconds the circuit stays open before letting probe calls
+  /// through. Defaults to 30000.
+  pub reset_timeout_ms: Option<u32>,
+  /// Probe calls allowed at once while half-open. Defaults to 1.
+  pub half_open_max_calls: Option<u32>,
+  /// Successful probes that close the circuit again. Defaults to 1.
+  pub success_threshold: Option<u32>,
+  #[napi(
+    ts_type = "(state: 'closed' | 'open' | 'half-open', previous: 'closed' | 'open' | 'half-open') => void"
+  )]
+  pub on_state_change: Option<JsFunction>,
+}
+
+type StateChange = ThreadsafeFunction<(&'static str, &'static str), ErrorStrategy::Fatal>;
+
+enum State {
+  Closed { failures: u32 },
+  Open { until: Instant },
+  HalfOpen { in_flight: u32, successes: u32 },
+}
+
+impl State {
+  fn name(&self) -> &'static str {
+    match self {
+      State::Closed { .. } => "closed",
+      State::Open { .. } => "open",
+      State::HalfOpen { .. } => "half-open",
+    }
+  }
+}
+
+struct Breaker {
+  state: State,
+  /// Bumped on every transition, so calls that outlive the state they
+  /// started in don't count towards the next one.
+  generation: u64,
+  failure_threshold: u32,
+  reset_timeout: Duration,
+  half_open_max_calls: u32,
+  success_threshold: u32,
+  on_state_change: Option<StateChange>,
+}
+
+impl Breaker {
+  fn transition(&mut self, to: State) {
+    let previous = self.state.name();
+    self.state = to;
+    self.generation += 1;
+    if previous == self.state.name() {
+      return;
+    }
+    if let Some(on_state_change) = &self.on_state_change {
+      on_state_change.call(
+        (self.state.name(), previous),
+        ThreadsafeFunctionCallMode::NonBlocking,
+      );
+    }
+  }
+
+  fn open(&mut self) {
+    self.transition(State::Open {
+      until: Instant::now() + self.reset_timeout,
+    });
+  }
+
+  /// Moves an open circuit whose timeout has passed to half-open.
+  fn refresh(&mut self) {
+    if let State::Open { until } = self.state {
+      if Instant::now() >= until {
+        self.transition(State::HalfOpen {
+          in_flight: 0,
+          successes: 0,
+        });
+      }
+    }
+  }
+
+  /// Lets a call through, returning the generation to report its outcome
+  /// against.
+  fn admit(&mut self) -> Result<u64> {
+    self.refresh();
+    match &mut self.state {
+      State::Closed { .. } => Ok(self.generation),
+      State::Open { until } => Err(AppError::CircuitOpen(format!(
+        "circuit is open for another {} ms",
+        until.saturating_duration_since(Instant::now()).as_millis()
+      ))),
+      State::HalfOpen { in_flight, .. } if *in_flight < self.half_open_max_calls => {
+        *in_flight += 1;
+        Ok(self.generation)
+      }
+      State::HalfOpen { .. } => Err(AppError::CircuitOpen(
+        "circuit is half-open and its probe calls are taken".into(),
+      )),
+    }
+  }
+
+  fn record(&mut self, generation: u64, succeeded: bool) {
+    if generation != self.generation {
+      return;
+    }
+    match &mut self.state {
+      State::Closed { failures } if succeeded => *failures = 0,
+      State::Closed { failures } => {
+        *failures += 1;
+        if *failures >= self.failure_threshold {
+          self.open();
+        }
+      }
+      State::HalfOpen {
+        in_flight,
+        successes,
+      } if succeeded => {
+        *in_flight -= 1;
+        *successes += 1;
+        if *successes >= self.success_threshold {
+          self.transition(State::Closed { failures: 0 });
+        }
+      }
+      State::HalfOpen { .. } => self.open(),
+      State::Open { .. } => {}
+    }
+  }
+}
+
+/// `Promise[method](...args)`.
+fn promise(env: &Env, method: &str, args: &[JsUnknown]) -> napi::Result<JsObject> {
+  let promise = env
+    .get_global()?
+    .get_named_property::<JsFunction>("Promise")?
+    .coerce_to_object()?;
+  let method: JsFunction = promise.get_named_property(method)?;
+  method.call(Some(&promise), args)?.coerce_to_object()
+}
+
+/// `promise.then(on_fulfilled, on_rejected)`.
+fn then(promise: &JsObject, callbacks: &[JsUnknown]) -> napi::Result<JsObject> {
+  let then: JsFunction = promise.get_named_property("then")?;
+  then.call(Some(promise), callbacks)?.coerce_to_object()
+}
+
+/// A promise rejected with `e`, code included.
+fn rejected(env: &Env, e: AppError) -> napi::Result<JsObject> {
+  let error =
+    unsafe { JsUnknown::from_raw_unchecked(env.raw(), JsError::from(e).into_value(env.raw())) };
+  promise(env, "reject", &[error])
+}
+
+/// Fails fast while a dependency is down: after `failureThreshold`
+/// consecutive failures the circuit opens and `execute` rejects with
+/// `ERR_CIRCUIT_OPEN` without calling anything. After `resetTimeoutMs` it
+/// goes half-open and lets probe calls through, closing again once enough
+/// of them succeed and reopening as soon as one fails.
+#[napi]
+pub struct CircuitBreaker {
+  breaker: Arc<Mutex<Breaker>>,
+}
+
+#[napi]
+impl CircuitBreaker {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(env: Env, options: Option<CircuitBreakerOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let at_least_one = |name: &str, value: Option<u32>, default: u32| match value.unwrap_or(default)
+    {
+      0 => Err(AppError::InvalidInput(format!(
+        "{} must be at least 1",
+        name
+      ))),
+      value => Ok(value),
+    };
+    let on_state_change = match options.on_state_change {
+      Some(callback) => {
+        let mut callback: StateChange = callback.create_threadsafe_function(
+          0,
+          |ctx: ThreadSafeCallContext<(&'static str, &'static str)>| {
+            let (state, previous) = ctx.value;
+            Ok(vec![
+              ctx.env.create_string(state)?,
+              ctx.env.create_string(previous)?,
+            ])
+          },
+        )?;
+        callback.unref(&env)?;
+        Some(callback)
+      }
+      None => None,
+    };
+    Ok(CircuitBreaker {
+      breaker: Arc::new(Mutex::new(Breaker {
+        state: State::Closed { failures: 0 },
+        generation: 0,
+        failure_threshold: at_least_one("failureThreshold", options.failure_threshold, 5)?,
+        reset_timeout: Duration::from_millis(options.reset_timeout_ms.unwrap_or(30_000).into()),
+        half_open_max_calls: at_least_one("halfOpenMaxCalls", options.half_open_max_calls, 1)?,
+        success_threshold: at_least_one("successThreshold", options.success_threshold, 1)?,
+        on_state_change,
+      })),
+    })
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Breaker> {
+    self.breaker.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Calls `fn` if the circuit allows it and settles like its result. A
+  /// throw or rejection counts as a failure.
+  #[napi(
+    catch_unwind,
+    ts_generic_types = "T",
+    ts_args_type = "fn: () => T | Promise<T>",
+    ts_return_type = "Promise<T>"
+  )]
+  pub fn execute(&self, env: Env, callback: JsFunction) -> Result<JsObject> {
+    let generation = match self.lock().admit() {
+      Ok(generation) => generation,
+      Err(e) => return Ok(rejected(&env, e)?),
+    };
+    // Calling from a `then` callback turns a throw into a rejection.
+    let started = then(&promise(&env, "resolve", &[])?, &[callback.into_unknown()])?;
+    let outcome = |succeeded: bool| {
+      let breaker = self.breaker.clone();
+      env.create_function_from_closure("settled", move |ctx| {
+        breaker
+          .lock()
+          .unwrap_or_else(|e| e.into_inner())
+          .record(generation, succeeded);
+        ctx.env.get_undefined()
+      })
+    };
+    // Attached before the caller can attach anything, so the outcome is
+    // recorded by the time the caller sees it.
+    then(
+      &started,
+      &[
+        outcome(true)?.into_unknown(),
+        outcome(false)?.into_unknown(),
+      ],
+    )?;
+    Ok(started)
+  }
+
+  #[napi(
+    catch_unwind,
+    getter,
+    ts_return_type = "'closed' | 'open' | 'half-open'"
+  )]
+  pub fn state(&self) -> &'static str {
+    let mut breaker = self.lock();
+    breaker.refresh();
+    breaker.state.name()
+  }
+
+  /// Consecutive failures while closed.
+  #[napi(catch_unwind, getter)]
+  pub fn failures(&self) -> u32 {
+    match self.lock().state {
+      State::Closed { failures } => failures,
+      _ => 0,
+    }
+  }
+
+  /// Opens the circuit now, e.g. when a health check fails.
+  #[napi(catch_unwind)]
+  pub fn trip(&self) {
+    self.lock().open();
+  }
+
+  /// Closes the circuit and forgets past failures.
+  #[napi(catch_unwind)]
+  pub fn reset(&self) {
+    self.lock().transition(State::Closed { failures: 0 });
+  }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct RetryPolicyOptions {
+  /// Attempts after the first, 0 to 100. Defaults to 3.
+  pub retries: Option<u32>,
+  /// Delay before the first retry. Defaults to 100.
+  pub initial_delay_ms: Option<u32>,
+  /// Cap on any one delay. Defaults to 30000.
+  pub max_delay_ms: Option<u32>,
+  /// What each delay is multiplied by, at least 1. Defaults to 2.
+  pub factor: Option<f64>,
+  /// `full` waits a random time up to the delay, `equal` at least half
+  /// of it, `none` exactly it. Defaults to `full`, which spreads out
+  /// clients retrying after a shared outage best.
+  #[napi(ts_type = "'full' | 'equal' | 'none'")]
+  pub jitter: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Jitter {
+  Full,
+  Equal,
+  None,
+}
+
+#[derive(Clone, Copy)]
+struct Policy {
+  retries: u32,
+  initial_delay_ms: f64,
+  max_delay_ms: f64,
+  factor: f64,
+  jitter: Jitter,
+}
+
+impl Policy {
+  fn delay(&self, retry: u32) -> Duration {
+    let exponent = retry.saturating_sub(1).min(1000) as i32;
+    let delay = (self.initial_delay_ms * self.factor.powi(exponent)).min(self.max_delay_ms);
+    let delay = match self.jitter {
+      Jitter::Full => rand::thread_rng().gen_range(0.0..=delay),
+      Jitter::Equal => delay / 2.0 + rand::thread_rng().gen_range(0.0..=delay / 2.0),
+      Jitter::None => delay,
+    };
+    Duration::from_millis(delay.round() as u64)
+  }
+}
+
+/// Runs attempt `attempt` of `callback`, then schedules the next one if it
+/// fails. The returned promise settles with the first success or the last
+/// failure.
+fn attempt(
+  env: &Env,
+  policy: Policy,
+  callback: JsFunction,
+  attempt: u32,
+) -> napi::Result<JsObject> {
+  let run = unsafe { JsUnknown::from_raw_unchecked(env.raw(), callback.raw()) };
+  let started = then(
+    &promise(
+      env,
+      "resolve",
+      &[env.create_uint32(attempt)?.into_unknown()],
+    )?,
+    &[run],
+  )?;
+  let retry = env.create_function_from_closure("retry", move |ctx| {
+    let callback = ctx.get::<JsFunction>(0)?;
+    let error = ctx.get::<JsUnknown>(1)?;
+    if attempt > policy.retries {
+      return promise(ctx.env, "reject", &[error]);
+    }
+    let wait = policy.delay(attempt);
+    let slept = error::spawn(ctx.env, async move {
+      tokio::time::sleep(wait).await;
+      Ok(())
+    })?;
+    let next = ctx.env.create_function_from_closure("next", move |ctx| {
+      self::attempt(ctx.env, policy, ctx.get::<JsFunction>(0)?, attempt + 1)
+    })?;
+    then(&slept, &[bind(ctx.env, next, callback)?])
+  })?;
+  then(
+    &started,
+    &[
+      env.get_undefined()?.into_unknown(),
+      bind(env, retry, callback)?,
+    ],
+  )
+}
+
+/// `function.bind(undefined, argument)`. Passing the user's callback along
+/// as a bound argument lets the engine keep it alive between attempts.
+fn bind(env: &Env, function: JsFunction, argument: JsFunction) -> napi::Result<JsUnknown> {
+  let function = function.coerce_to_object()?;
+  let bind: JsFunction = function.get_named_property("bind")?;
+  bind.call(
+    Some(&function),
+    &[env.get_undefined()?.into_unknown(), argument.into_unknown()],
+  )
+}
+
+/// Retries a failing async operation with exponential backoff and jitter.
+/// The delays are timed on the native runtime rather than with
+/// `setTimeout`.
+#[napi]
+pub struct RetryPolicy {
+  policy: Policy,
+}
+
+#[napi]
+impl RetryPolicy {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<RetryPolicyOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let retries = options.retries.unwrap_or(3);
+    if retries > MAX_RETRIES {
+      return Err(AppError::InvalidInput(format!(
+        "retries must be at most {}, got {}",
+        MAX_RETRIES, retries
+      )));
+    }
+    let factor = options.factor.unwrap_or(2.0);
+    if !(factor >= 1.0 && factor.is_finite()) {
+      return Err(AppError::InvalidInput(format!(
+        "factor must be at least 1, got {}",
+        factor
+      )));
+    }
+    let jitter = match options.jitter.as_deref() {
+      None | Some("full") => Jitter::Full,
+      Some("equal") => Jitter::Equal,
+      Some("none") => Jitter::None,
+      Some(other) => return Err(AppError::InvalidInput(format!("unknown jitter: {}", other))),
+    };
+    Ok(RetryPolicy {
+      policy: Policy {
+        retries,
+        initial_delay_ms: options.initial_delay_ms.unwrap_or(100).into(),
+        max_delay_ms: options.max_delay_ms.unwrap_or(30_000).into(),
+        factor,
+        jitter,
+      },
+    })
+  }
+
+  /// Calls `fn(attempt)` until it succeeds or runs out of retries, and
+  /// settles like the last call. `attempt` counts from 1.
+  #[napi(
+    catch_unwind,
+    ts_generic_types = "T",
+    ts_args_type = "fn: (attempt: number) => T | Promise<T>",
+    ts_return_type = "Promise<T>"
+  )]
+  pub fn execute(&self, env: Env, callback: JsFunction) -> Result<JsObject> {
+    Ok(attempt(&env, self.policy, callback, 1)?)
+  }
+
+  /// Milliseconds to wait before retry `retry` (from 1), jitter included.
+  #[napi(catch_unwind)]
+  pub fn delay(&self, retry: u32) -> u32 {
+    self.policy.delay(retry).as_millis().min(u32::MAX as u128) as u32
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn retries(&self) -> u32 {
+    self.policy.retries
+  }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::Utc;
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown};
+use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::cron::{CronOptions, CronSchedule};
+use crate::error::{AppError, Result};
+use crate::lifecycle::{self, Resource};
+
+/// Called with the run number and the time the run was scheduled for, in
+/// milliseconds since the Unix epoch.
+type JobCallback = ThreadsafeFunction<(u32, f64), ErrorStrategy::Fatal>;
+
+struct Inner {
+  runtime: Option<tokio::runtime::Handle>,
+  /// Dropping it ends the runtime thread.
+  stop: Option<oneshot::Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+  next_id: u32,
+  jobs: HashMap<u32, AbortHandle>,
+}
+
+/// Shared with the jobs and the lifecycle registry.
+struct SchedulerState(Mutex<Inner>);
+
+impl SchedulerState {
+  fn lock(&self) -> MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn stop(&self) {
+    let thread = {
+      let mut inner = self.lock();
+      for (_, job) in inner.jobs.drain() {
+        job.abort();
+      }
+      inner.runtime = None;
+      inner.stop = None;
+      inner.thread.take()
+    };
+    if let Some(thread) = thread {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Resource for SchedulerState {
+  fn kind(&self) -> &'static str {
+    "scheduler"
+  }
+
+  fn active(&self) -> bool {
+    !self.lock().jobs.is_empty()
+  }
+
+  fn shutdown(&self) {
+    self.stop();
+  }
+}
+
+fn now_ms() -> f64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_secs_f64()
+    * 1000.0
+}
+
+/// Runs the callback on the JS thread and waits for it to return, so runs of
+/// one job never overlap or queue up behind a busy event loop.
+async fn fire(callback: &JobCallback, run: u32, scheduled_ms: f64) {
+  let (done_tx, done_rx) = oneshot::channel();
+  callback.call_with_return_value(
+    (run, scheduled_ms),
+    ThreadsafeFunctionCallMode::NonBlocking,
+    move |_: JsUnknown| {
+      let _ = done_tx.send(());
+      Ok(())
+    },
+  );
+  // An error means the callback threw or the environment is going away.
+  let _ = done_rx.await;
+}
+
+async fn run_interval(period: Duration, callback: JobCallback) {
+  let start = Instant::now();
+  let start_ms = now_ms();
+  // Ticks stay on the `start + n * period` grid however long callbacks
+  // take; ticks missed while one ran are skipped rather than bunched up.
+  let mut interval = tokio::time::interval_at(start + period, period);
+  interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+  let mut run = 0u32;
+  loop {
+    let tick = interval.tick().await;
+    run = run.wrapping_add(1);
+    let scheduled_ms = start_ms + (tick - start).as_secs_f64() * 1000.0;
+    fire(&callback, run, scheduled_ms).await;
+  }
+}
+
+async fn run_cron(schedule: CronSchedule, callback: JobCallback) {
+  let mut after = Utc::now();
+  let mut run = 0u32;
+  while let Some(next) = schedule.next_after(&after) {
+    // Each wait is measured from the wall clock, so the sleep never
+    // accumulates error from earlier runs.
+    let wait = (next - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(wait).await;
+    run = run.wrapping_add(1);
+    fire(&callback, run, next.timestamp_millis() as f64).await;
+    // Runs that fell due while the callback ran are skipped.
+    after = next.max(Utc::now());
+  }
+}
+
+/// Interval and cron jobs timed on a dedicated runtime thread, so timing
+/// does not depend on the event loop being free. Each run calls the job's
+/// callback as `callback(run, scheduledAt)` on the JS thread, where `run`
+/// counts from 1 and `scheduledAt` is the `Date` the run was due.
+///
+/// While it has jobs, a scheduler keeps the process alive; `shutdown()`, the
+/// global `shutdown()` or garbage collection of the scheduler stops them all.
+#[napi(custom_finalize)]
+pub struct Scheduler {
+  state: Arc<SchedulerState>,
+}
+
+#[napi]
+impl Scheduler {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(env: Env) -> Result<Self> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_time()
+      .build()?;
+    let handle = runtime.handle().clone();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let thread = thread::Builder::new()
+      .name("scheduler".into())
+      .spawn(move || {
+        // Returns once the sender is dropped; dropping the runtime then
+        // drops every remaining job.
+        let _ = runtime.block_on(stop_rx);
+      })?;
+    let state = Arc::new(SchedulerState(Mutex::new(Inner {
+      runtime: Some(handle),
+      stop: Some(stop_tx),
+      thread: Some(thread),
+      next_id: 0,
+      jobs: HashMap::new(),
+    })));
+    lifecycle::register(&env, &state)?;
+    Ok(Scheduler { state })
+  }
+
+  /// Calls `callback` every `intervalMs` milliseconds, first after one
+  /// interval. Returns the job's id.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "intervalMs: number, callback: (run: number, scheduledAt: Date) => void"
+  )]
+  pub fn schedule_interval(&self, interval_ms: u32, callback: JsFunction) -> Result<u32> {
+    if interval_ms == 0 {
+      return Err(AppError::InvalidInput(
+        "intervalMs must be at least 1".into(),
+      ));
+    }
+    let callback = job_callback(callback)?;
+    let period = Duration::from_millis(interval_ms as u64);
+    self.spawn(run_interval(period, callback))
+  }
+
+  /// Calls `callback` at each time matching the cron expression `expr`
+  /// (the syntax of `CronSchedule.parse`). Returns the job's id. A
+  /// schedule with no more runs removes itself.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "expr: string, callback: (run: number, scheduledAt: Date) => void, options?: CronOptions"
+  )]
+  pub fn schedule_cron(
+    &self,
+    expr: String,
+    callback: JsFunction,
+    options: Option<CronOptions>,
+  ) -> Result<u32> {
+    let schedule = CronSchedule::parse(expr, options)?;
+    let callback = job_callback(callback)?;
+    self.spawn(run_cron(schedule, callback))
+  }
+
+  /// Stops the job `jobId`. Returns false if there was no such job.
+  #[napi(catch_unwind)]
+  pub fn cancel(&self, job_id: u32) -> bool {
+    match self.state.lock().jobs.remove(&job_id) {
+      Some(job) => {
+        job.abort();
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Cancels every job and stops the runtime thread. Scheduling afterwards
+  /// throws `ERR_CLOSED`.
+  #[napi(catch_unwind)]
+  pub fn shutdown(&self) {
+    self.state.stop();
+  }
+
+  /// Ids of the scheduled jobs, in ascending order.
+  #[napi(catch_unwind, getter)]
+  pub fn jobs(&self) -> Vec<u32> {
+    let mut jobs: Vec<u32> = self.state.lock().jobs.keys().copied().collect();
+    jobs.sort_unstable();
+    jobs
+  }
+
+  fn spawn(&self, job: impl Future<Output = ()> + Send + 'static) -> Result<u32> {
+    let mut inner = self.state.lock();
+    let runtime = inner
+      .runtime
+      .clone()
+      .ok_or_else(|| AppError::Closed("Scheduler is shut down".into()))?;
+    inner.next_id = inner.next_id.wrapping_add(1);
+    let id = inner.next_id;
+    let state: Weak<SchedulerState> = Arc::downgrade(&self.state);
+    // The lock is held until the job is recorded, so a job that ends at
+    // once still finds itself to remove.
+    let task = runtime.spawn(async move {
+      job.await;
+      if let Some(state) = state.upgrade() {
+        state.lock().jobs.remove(&id);
+      }
+    });
+    inner.jobs.insert(id, task.abort_handle());
+    Ok(id)
+  }
+}
+
+fn job_callback(callback: JsFunction) -> Result<JobCallback> {
+  Ok(
+    callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(u32, f64)>| {
+      let (run, scheduled_ms) = ctx.value;
+      Ok(vec![
+        ctx.env.create_uint32(run)?.into_unknown(),
+        ctx.env.create_date(scheduled_ms)?.into_unknown(),
+      ])
+    })?,
+  )
+}
+
+impl ObjectFinalize for Scheduler {
+  fn finalize(self, _: Env) -> napi::Result<()> {
+    self.state.stop();
+    Ok(())
+  }
+}
@@ -0,0 +1,116 @@
+use jsonschema::Draft;
+use napi::{Env, JsUnknown};
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ValidatorOptions {
+  /// The draft to validate against. Defaults to the schema's `$schema`, or
+  /// 2020-12 when it has none.
+  #[napi(ts_type = "'4' | '6' | '7' | '2019-09' | '2020-12'")]
+  pub draft: Option<String>,
+  /// Check `format` keywords (`email`, `date-time`, `uri`, …) instead of
+  /// treating them as annotations. Defaults to `false`, as the spec says.
+  pub validate_formats: Option<bool>,
+}
+
+/// One way the data failed the schema.
+#[napi(object)]
+pub struct SchemaError {
+  /// JSON Pointer to the failing value, `""` for the root.
+  pub instance_path: String,
+  /// JSON Pointer to the keyword that failed. A keyword reached through a
+  /// `$ref` is given where it is defined, e.g. `/$defs/tag/enum`.
+  pub schema_path: String,
+  /// The failing keyword, such as `required` or `minimum`.
+  pub keyword: String,
+  pub message: String,
+}
+
+#[napi(object)]
+pub struct ValidationReport {
+  pub valid: bool,
+  /// Every error, in schema order; empty when `valid`.
+  pub errors: Vec<SchemaError>,
+}
+
+fn draft(name: &str) -> Result<Draft> {
+  Ok(match name {
+    "4" => Draft::Draft4,
+    "6" => Draft::Draft6,
+    "7" => Draft::Draft7,
+    "2019-09" => Draft::Draft201909,
+    "2020-12" => Draft::Draft202012,
+    other => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown JSON Schema draft: {}",
+        other
+      )))
+    }
+  })
+}
+
+/// A JSON Schema compiled once and reused for every document, so each
+/// validation only walks the data.
+///
+/// Supports drafts 4, 6, 7, 2019-09 and 2020-12, including `$ref`s to
+/// `$defs` and `$id`s inside the schema. External references are not
+/// fetched: a `$ref` to another document fails to compile.
+#[napi]
+pub struct Validator {
+  validator: jsonschema::Validator,
+}
+
+#[napi]
+impl Validator {
+  /// Compiles `schema`. A schema that breaks its draft's rules, or has a
+  /// `$ref` that cannot be resolved, throws `ERR_INVALID_INPUT`.
+  #[napi(
+    catch_unwind,
+    factory,
+    ts_args_type = "schema: object | boolean, options?: ValidatorOptions"
+  )]
+  pub fn compile(env: Env, schema: JsUnknown, options: Option<ValidatorOptions>) -> Result<Self> {
+    let schema: Value = from_js(&env, schema)?;
+    let options = options.unwrap_or_default();
+    let mut builder = jsonschema::options();
+    if let Some(name) = options.draft.as_deref() {
+      builder = builder.with_draft(draft(name)?);
+    }
+    let validator = builder
+      .should_validate_formats(options.validate_formats.unwrap_or(false))
+      .build(&schema)
+      .map_err(|e| AppError::InvalidInput(format!("invalid schema: {}", e)))?;
+    Ok(Validator { validator })
+  }
+
+  /// Validates `data`, collecting every error.
+  #[napi(catch_unwind, ts_args_type = "data: unknown")]
+  pub fn validate(&self, env: Env, data: JsUnknown) -> Result<ValidationReport> {
+    let data: Value = from_js(&env, data)?;
+    let errors: Vec<SchemaError> = self
+      .validator
+      .iter_errors(&data)
+      .map(|error| SchemaError {
+        instance_path: error.instance_path().as_str().to_string(),
+        schema_path: error.schema_path().as_str().to_string(),
+        keyword: error.kind().keyword().to_string(),
+        message: error.to_string(),
+      })
+      .collect();
+    Ok(ValidationReport {
+      valid: errors.is_empty(),
+      errors,
+    })
+  }
+
+  /// Whether `data` is valid. Faster than `validate` when the errors
+  /// themselves are not needed, as it stops at the first one.
+  #[napi(catch_unwind, ts_args_type = "data: unknown")]
+  pub fn is_valid(&self, env: Env, data: JsUnknown) -> Result<bool> {
+    Ok(self.validator.is_valid(&from_js(&env, data)?))
+  }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, TantivyError, Term};
+
+use crate::error::{AppError, Result};
+
+/// The reserved field every document is keyed by.
+const ID_FIELD: &str = "id";
+
+#[napi(object)]
+pub struct SearchField {
+  pub name: String,
+  /// `text` (tokenized, the default) or `string` (indexed as one exact
+  /// token, e.g. tags or categories).
+  #[napi(ts_type = "'text' | 'string'")]
+  pub kind: Option<String>,
+  /// Whether the value is returned with search hits. Defaults to `true`.
+  pub stored: Option<bool>,
+}
+
+#[napi(object)]
+pub struct SearchIndexOptions {
+  pub fields: Vec<SearchField>,
+  /// Directory to persist the index in; created if missing. Without it the
+  /// index lives in memory.
+  pub path: Option<String>,
+  /// Indexing memory budget in MiB, shared by the writer threads.
+  /// Defaults to 50.
+  pub memory_budget_mb: Option<u32>,
+}
+
+#[napi(object)]
+pub struct SearchHit {
+  pub id: String,
+  pub score: f64,
+  /// Stored fields of the document.
+  pub fields: HashMap<String, String>,
+}
+
+fn search_error(e: TantivyError) -> AppError {
+  AppError::Search(e.to_string())
+}
+
+fn closed() -> AppError {
+  AppError::Closed("search index is closed".into())
+}
+
+struct Inner {
+  writer: IndexWriter,
+  reader: IndexReader,
+}
+
+/// An embedded full-text index (tantivy) with BM25 ranking.
+///
+/// Documents are keyed by a string `id`; adding a document with an existing
+/// id replaces it. Changes become searchable after `commit()`.
+#[napi]
+pub struct SearchIndex {
+  id_field: Field,
+  /// Configured fields by name, in declaration order.
+  fields: Vec<(String, Field)>,
+  parser: QueryParser,
+  inner: Option<Inner>,
+}
+
+#[napi]
+impl SearchIndex {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: SearchIndexOptions) -> Result<Self> {
+    if options.fields.is_empty() {
+      return Err(AppError::InvalidInput(
+        "search index needs at least one field".into(),
+      ));
+    }
+    let mut builder = Schema::builder();
+    let id_field = builder.add_text_field(ID_FIELD, STRING | STORED);
+    let mut fields: Vec<(String, Field)> = Vec::new();
+    let mut text_fields = Vec::new();
+    for spec in &options.fields {
+      if spec.name == ID_FIELD || fields.iter().any(|(name, _)| *name == spec.name) {
+        return Err(AppError::InvalidInput(format!(
+          "duplicate or reserved field name: {}",
+          spec.name
+        )));
+      }
+      let stored = spec.stored.unwrap_or(true);
+      let field = match spec.kind.as_deref().unwrap_or("text") {
+        "text" if stored => builder.add_text_field(&spec.name, TEXT | STORED),
+        "text" => builder.add_text_field(&spec.name, TEXT),
+        "string" if stored => builder.add_text_field(&spec.name, STRING | STORED),
+        "string" => builder.add_text_field(&spec.name, STRING),
+        other => {
+          return Err(AppError::InvalidInput(format!(
+            "unknown field kind: {}",
+            other
+          )))
+        }
+      };
+      if spec.kind.as_deref().unwrap_or("text") == "text" {
+        text_fields.push(field);
+      }
+      fields.push((spec.name.clone(), field));
+    }
+    let schema = builder.build();
+
+    let index = match &options.path {
+      Some(path) => {
+        std::fs::create_dir_all(path).map_err(|e| AppError::io(path, e))?;
+        let dir =
+          MmapDirectory::open(Path::new(path)).map_err(|e| AppError::Search(e.to_string()))?;
+        Index::open_or_create(dir, schema).map_err(search_error)?
+      }
+      None => Index::create_in_ram(schema),
+    };
+    let budget = options.memory_budget_mb.unwrap_or(50) as usize * 1024 * 1024;
+    let writer = index.writer(budget).map_err(search_error)?;
+    let reader = index
+      .reader_builder()
+      .reload_policy(ReloadPolicy::Manual)
+      .try_into()
+      .map_err(search_error)?;
+    // Free-text terms search every tokenized field; `string` fields are
+    // still reachable with `field:value`.
+    let parser = QueryParser::for_index(&index, text_fields);
+    Ok(SearchIndex {
+      id_field,
+      fields,
+      parser,
+      inner: Some(Inner { writer, reader }),
+    })
+  }
+
+  /// Adds or replaces the document `id`. `fields` maps configured field
+  /// names to strings; unknown names are rejected.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "id: string, fields: Record<string, string>"
+  )]
+  pub fn add_document(&mut self, id: String, fields: HashMap<String, String>) -> Result<()> {
+    let mut doc = TantivyDocument::default();
+    doc.add_text(self.id_field, &id);
+    for (name, value) in &fields {
+      let field = self.field(name)?;
+      doc.add_text(field, value);
+    }
+    let id_field = self.id_field;
+    let writer = &mut self.inner()?.writer;
+    writer.delete_term(Term::from_field_text(id_field, &id));
+    writer.add_document(doc).map_err(search_error)?;
+    Ok(())
+  }
+
+  /// Removes the document `id` (takes effect on the next commit).
+  #[napi(catch_unwind)]
+  pub fn delete_document(&mut self, id: String) -> Result<()> {
+    let term = Term::from_field_text(self.id_field, &id);
+    self.inner()?.writer.delete_term(term);
+    Ok(())
+  }
+
+  /// Persists pending changes and makes them visible to `search`.
+  #[napi(catch_unwind)]
+  pub fn commit(&mut self) -> Result<()> {
+    let inner = self.inner()?;
+    inner.writer.commit().map_err(search_error)?;
+    inner.reader.reload().map_err(search_error)
+  }
+
+  /// Runs `query` (tantivy query syntax: terms, `"phrases"`, `field:term`,
+  /// `AND`/`OR`/`-term`) and returns the best `limit` hits (default 10).
+  #[napi(catch_unwind)]
+  pub fn search(&mut self, query: String, limit: Option<u32>) -> Result<Vec<SearchHit>> {
+    let limit = limit.unwrap_or(10);
+    if limit == 0 {
+      return Err(AppError::InvalidInput("limit must be at least 1".into()));
+    }
+    let query = self
+      .parser
+      .parse_query(&query)
+      .map_err(|e| AppError::Parse(format!("invalid search query: {}", e)))?;
+    let searcher = self.inner()?.reader.searcher();
+    let top = searcher
+      .search(&query, &TopDocs::with_limit(limit as usize))
+      .map_err(search_error)?;
+    top
+      .into_iter()
+      .map(|(score, address)| {
+        let doc: TantivyDocument = searcher.doc(address).map_err(search_error)?;
+        let text = |field: Field| {
+          doc
+            .get_first(field)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+        };
+        Ok(SearchHit {
+          id: text(self.id_field).unwrap_or_default(),
+          score: score as f64,
+          fields: self
+            .fields
+            .iter()
+            .filter_map(|(name, field)| text(*field).map(|v| (name.clone(), v)))
+            .collect(),
+        })
+      })
+      .collect()
+  }
+
+  /// Number of committed documents.
+  #[napi(catch_unwind, getter)]
+  pub fn num_docs(&mut self) -> Result<u32> {
+    Ok(self.inner()?.reader.searcher().num_docs() as u32)
+  }
+
+  /// Discards uncommitted changes and releases the writer (and its lock on
+  /// an on-disk index). Later calls throw `ERR_CLOSED`.
+  #[napi(catch_unwind)]
+  pub fn close(&mut self) {
+    self.inner.take();
+  }
+
+  fn field(&self, name: &str) -> Result<Field> {
+    self
+      .fields
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, field)| *field)
+      .ok_or_else(|| AppError::InvalidInput(format!("unknown field: {}", name)))
+  }
+
+  fn inner(&mut self) -> Result<&mut Inner> {
+    self.inner.as_mut().ok_or_else(closed)
+  }
+}
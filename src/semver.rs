@@ -0,0 +1,80 @@
+use node_semver::{Range, SemverError, Version};
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct SemverParts {
+  /// The version in canonical form, without a `v` prefix.
+  pub version: String,
+  pub major: i64,
+  pub minor: i64,
+  pub patch: i64,
+  /// Dot-separated prerelease identifiers, e.g. `["beta", "2"]`.
+  pub prerelease: Vec<String>,
+  pub build: Vec<String>,
+}
+
+fn parse_version(input: &str) -> Result<Version> {
+  Version::parse(input).map_err(|e| semver_error("version", input, e))
+}
+
+fn parse_range(input: &str) -> Result<Range> {
+  Range::parse(input).map_err(|e| semver_error("range", input, e))
+}
+
+fn semver_error(what: &str, input: &str, e: SemverError) -> AppError {
+  AppError::Parse(format!("invalid semver {} {:?}: {}", what, input, e.kind()))
+}
+
+/// Parses a version such as `v1.2.3-beta.2+build.5`. A leading `v` or `=`
+/// is accepted, as in node-semver.
+#[napi(catch_unwind)]
+pub fn parse_semver(input: String) -> Result<SemverParts> {
+  let v = parse_version(&input)?;
+  let ids = |ids: &[node_semver::Identifier]| ids.iter().map(ToString::to_string).collect();
+  Ok(SemverParts {
+    version: v.to_string(),
+    major: v.major as i64,
+    minor: v.minor as i64,
+    patch: v.patch as i64,
+    prerelease: ids(&v.pre_release),
+    build: ids(&v.build),
+  })
+}
+
+/// Whether `version` matches `range`, using node-semver's range grammar
+/// (`^1.2`, `~1.2.3`, `>=1 <2`, `1.x`, `1 - 2`, `||`).
+#[napi(catch_unwind)]
+pub fn satisfies(version: String, range: String) -> Result<bool> {
+  Ok(parse_range(&range)?.satisfies(&parse_version(&version)?))
+}
+
+/// The highest version in `versions` matching `range`, or `null`. Entries
+/// that are not valid versions are skipped.
+#[napi(catch_unwind)]
+pub fn max_satisfying(versions: Vec<String>, range: String) -> Result<Option<String>> {
+  let range = parse_range(&range)?;
+  Ok(
+    versions
+      .into_iter()
+      .filter_map(|s| Version::parse(&s).ok().map(|v| (v, s)))
+      .filter(|(v, _)| range.satisfies(v))
+      .max_by(|a, b| a.0.cmp(&b.0))
+      .map(|(_, s)| s),
+  )
+}
+
+/// Sorts versions in ascending semver precedence (descending if
+/// `descending` is set), returning the original strings.
+#[napi(catch_unwind)]
+pub fn sort_versions(versions: Vec<String>, descending: Option<bool>) -> Result<Vec<String>> {
+  let mut parsed = versions
+    .into_iter()
+    .map(|s| Ok((parse_version(&s)?, s)))
+    .collect::<Result<Vec<_>>>()?;
+  parsed.sort_by(|a, b| a.0.cmp(&b.0));
+  if descending.unwrap_or(false) {
+    parsed.reverse();
+  }
+  Ok(parsed.into_iter().map(|(_, s)| s).collect())
+}
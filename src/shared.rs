@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+use dashmap::DashMap;
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+#[derive(Clone)]
+enum Value {
+  Text(String),
+  Bytes(Vec<u8>),
+}
+
+type Map = DashMap<String, Value>;
+
+/// Every live map in the process by name. The addon is loaded once per
+/// process, so this static is the same for the main thread and every worker
+/// even though each gets its own JS classes. Entries are weak: a map is
+/// freed once no thread holds a `SharedMap` for it.
+fn attach(name: &str) -> Arc<Map> {
+  static MAPS: OnceLock<Mutex<HashMap<String, Weak<Map>>>> = OnceLock::new();
+  let mut maps = MAPS
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+  if let Some(map) = maps.get(name).and_then(Weak::upgrade) {
+    return map;
+  }
+  maps.retain(|_, map| map.strong_count() > 0);
+  let map = Arc::new(Map::new());
+  maps.insert(name.to_string(), Arc::downgrade(&map));
+  map
+}
+
+/// A string-keyed map of strings and Buffers shared by every thread in the
+/// process.
+///
+/// JS objects, including `External` handles, cannot cross `worker_threads`
+/// boundaries, so maps are found by name instead: `new SharedMap('jobs')` in
+/// a worker attaches to the same storage as `new SharedMap('jobs')` on the
+/// main thread. Pass `map.name` through `workerData` to share an unnamed
+/// map. Reads and writes are lock-striped, so threads rarely contend, and
+/// values are copied in and out, so a Buffer set by one thread is never
+/// aliased by another.
+#[napi]
+pub struct SharedMap {
+  name: String,
+  map: Arc<Map>,
+}
+
+#[napi]
+impl SharedMap {
+  /// Attaches to the map called `name`, creating it if no thread holds it.
+  /// Without a name a new map with a unique one is created.
+  #[napi(catch_unwind, constructor)]
+  pub fn new(name: Option<String>) -> Result<Self> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let name = match name {
+      Some(name) if name.is_empty() => {
+        return Err(AppError::InvalidInput(
+          "SharedMap name must not be empty".into(),
+        ));
+      }
+      Some(name) => name,
+      None => format!(
+        "shared-map-{}-{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+      ),
+    };
+    Ok(SharedMap {
+      map: attach(&name),
+      name,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  #[napi(catch_unwind)]
+  pub fn get(&self, key: String) -> Option<Either<String, Buffer>> {
+    self.map.get(&key).map(|value| match value.clone() {
+      Value::Text(text) => Either::A(text),
+      Value::Bytes(bytes) => Either::B(bytes.into()),
+    })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn set(&self, key: String, value: Either<String, Buffer>) {
+    let value = match value {
+      Either::A(text) => Value::Text(text),
+      Either::B(bytes) => Value::Bytes(bytes.to_vec()),
+    };
+    self.map.insert(key, value);
+  }
+
+  #[napi(catch_unwind)]
+  pub fn has(&self, key: String) -> bool {
+    self.map.contains_key(&key)
+  }
+
+  /// Returns whether the key was present.
+  #[napi(catch_unwind)]
+  pub fn delete(&self, key: String) -> bool {
+    self.map.remove(&key).is_some()
+  }
+
+  #[napi(catch_unwind)]
+  pub fn len(&self) -> u32 {
+    self.map.len() as u32
+  }
+
+  #[napi(catch_unwind)]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// A snapshot of the keys, in no particular order.
+  #[napi(catch_unwind)]
+  pub fn keys(&self) -> Vec<String> {
+    self.map.iter().map(|entry| entry.key().clone()).collect()
+  }
+
+  #[napi(catch_unwind)]
+  pub fn clear(&self) {
+    self.map.clear();
+  }
+}
@@ -0,0 +1,381 @@
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use memmap2::MmapMut;
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+/// Written last when a segment is initialized, so a process opening a
+/// segment another one is still creating knows to wait.
+const MAGIC: u64 = u64::from_le_bytes(*b"NAPIRNG1");
+// Header fields, each on its own cache line so producer and consumer don't
+// contend on the same one.
+const MAGIC_AT: usize = 0;
+const CAPACITY_AT: usize = 8;
+const HEAD_AT: usize = 64;
+const TAIL_AT: usize = 128;
+const DROPPED_AT: usize = 192;
+const DATA_AT: usize = 256;
+/// Each message is stored after its length as a little-endian u32.
+const LEN_BYTES: u64 = 4;
+const DEFAULT_CAPACITY: u64 = 1 << 20;
+const MAX_CAPACITY: u64 = 1 << 30;
+
+#[derive(Clone, Copy)]
+enum Overflow {
+  Reject,
+  Error,
+  DropOldest,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SharedRingOptions {
+  /// Bytes of message storage when the segment is created, 64 B–1 GiB;
+  /// each message also takes 4 bytes of framing. Defaults to 1 MiB. Ignored
+  /// when the segment already exists.
+  pub capacity: Option<u32>,
+  /// What `push` does when the message doesn't fit: return `false`
+  /// (`reject`, the default), throw `ERR_OVERFLOW` (`error`), or discard
+  /// the oldest messages until it fits (`drop-oldest`).
+  #[napi(ts_type = "'reject' | 'error' | 'drop-oldest'")]
+  pub overflow: Option<String>,
+}
+
+/// Where the named object `name` of `kind` lives: a file in `/dev/shm`,
+/// which is memory-backed, where it exists, and in the temp directory
+/// elsewhere.
+pub(crate) fn named_path(kind: &str, name: &str) -> Result<PathBuf> {
+  let valid = !name.is_empty()
+    && name.len() <= 200
+    && name
+      .bytes()
+      .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+    && !name.starts_with('.');
+  if !valid {
+    return Err(AppError::InvalidInput(format!(
+      "invalid {} name {:?}: use letters, digits, '.', '_' and '-'",
+      kind, name
+    )));
+  }
+  let file = format!("napi-{}-{}", kind, name);
+  let shm = PathBuf::from("/dev/shm");
+  Ok(if shm.is_dir() {
+    shm.join(file)
+  } else {
+    std::env::temp_dir().join(file)
+  })
+}
+
+struct Ring {
+  path: PathBuf,
+  // Keeps the mapping alive; all access goes through `base`.
+  _map: MmapMut,
+  base: *mut u8,
+  capacity: u64,
+}
+
+impl Ring {
+  fn atomic(&self, at: usize) -> &AtomicU64 {
+    // The mapping is page-aligned and the offsets are multiples of 8.
+    unsafe { &*(self.base.add(at) as *const AtomicU64) }
+  }
+
+  /// Copies `src` into the ring at stream position `pos`, wrapping around.
+  fn write_at(&self, pos: u64, src: &[u8]) {
+    let start = (pos % self.capacity) as usize;
+    let first = src.len().min(self.capacity as usize - start);
+    unsafe {
+      let data = self.base.add(DATA_AT);
+      std::ptr::copy_nonoverlapping(src.as_ptr(), data.add(start), first);
+      std::ptr::copy_nonoverlapping(src.as_ptr().add(first), data, src.len() - first);
+    }
+  }
+
+  fn read_at(&self, pos: u64, dst: &mut [u8]) {
+    let start = (pos % self.capacity) as usize;
+    let first = dst.len().min(self.capacity as usize - start);
+    unsafe {
+      let data = self.base.add(DATA_AT);
+      std::ptr::copy_nonoverlapping(data.add(start), dst.as_mut_ptr(), first);
+      std::ptr::copy_nonoverlapping(data, dst.as_mut_ptr().add(first), dst.len() - first);
+    }
+  }
+
+  fn len_at(&self, pos: u64) -> u64 {
+    let mut len = [0; LEN_BYTES as usize];
+    self.read_at(pos, &mut len);
+    u32::from_le_bytes(len) as u64
+  }
+
+  fn push(&self, message: &[u8], overflow: Overflow) -> Result<bool> {
+    let need = LEN_BYTES + message.len() as u64;
+    if need > self.capacity {
+      return Err(AppError::InvalidInput(format!(
+        "message of {} bytes can never fit in a ring of {}",
+        message.len(),
+        self.capacity
+      )));
+    }
+    let (head, tail) = (self.atomic(HEAD_AT), self.atomic(TAIL_AT));
+    // Only the producer moves `tail`.
+    let end = tail.load(Ordering::Relaxed);
+    loop {
+      let start = head.load(Ordering::Acquire);
+      if self.capacity - (end - start) >= need {
+        break;
+      }
+      match overflow {
+        Overflow::Reject => return Ok(false),
+        Overflow::Error => {
+          return Err(AppError::Overflow(format!(
+            "ring is full: {} of {} bytes free, {} needed",
+            self.capacity - (end - start),
+            self.capacity,
+            need
+          )))
+        }
+        Overflow::DropOldest => {
+          // The consumer may pop the same message first; either way it is
+          // gone and the loop re-checks the space.
+          let oldest = LEN_BYTES + self.len_at(start);
+          if head
+            .compare_exchange(start, start + oldest, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+          {
+            self.atomic(DROPPED_AT).fetch_add(1, Ordering::Relaxed);
+          }
+        }
+      }
+    }
+    self.write_at(end, &(message.len() as u32).to_le_bytes());
+    self.write_at(end + LEN_BYTES, message);
+    tail.store(end + need, Ordering::Release);
+    Ok(true)
+  }
+
+  fn pop(&self) -> Result<Option<Vec<u8>>> {
+    let (head, tail) = (self.atomic(HEAD_AT), self.atomic(TAIL_AT));
+    loop {
+      let start = head.load(Ordering::Acquire);
+      let end = tail.load(Ordering::Acquire);
+      if start == end {
+        return Ok(None);
+      }
+      let len = self.len_at(start);
+      let mut message = Vec::new();
+      // A producer dropping this message could be overwriting it as it's
+      // read; the length is then garbage and the exchange below fails.
+      if LEN_BYTES + len <= end - start {
+        message.resize(len as usize, 0);
+        self.read_at(start + LEN_BYTES, &mut message);
+      }
+      if head
+        .compare_exchange(
+          start,
+          start + LEN_BYTES + len,
+          Ordering::AcqRel,
+          Ordering::Acquire,
+        )
+        .is_ok()
+      {
+        if LEN_BYTES + len > end - start {
+          return Err(AppError::Parse("shared ring is corrupt".into()));
+        }
+        return Ok(Some(message));
+      }
+    }
+  }
+}
+
+fn map(file: &File) -> Result<(MmapMut, *mut u8)> {
+  let mut map = unsafe { MmapMut::map_mut(file) }?;
+  let base = map.as_mut_ptr();
+  Ok((map, base))
+}
+
+/// Creates the segment, or returns `None` if another process got there
+/// first.
+fn create(path: &PathBuf, capacity: u64) -> Result<Option<Ring>> {
+  let file = match OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create_new(true)
+    .open(path)
+  {
+    Ok(file) => file,
+    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(None),
+    Err(e) => return Err(AppError::io(&path.display().to_string(), e)),
+  };
+  file.set_len(DATA_AT as u64 + capacity)?;
+  let (map, base) = map(&file)?;
+  let ring = Ring {
+    path: path.clone(),
+    _map: map,
+    base,
+    capacity,
+  };
+  unsafe { (base.add(CAPACITY_AT) as *mut u64).write(capacity) };
+  ring.atomic(MAGIC_AT).store(MAGIC, Ordering::Release);
+  Ok(Some(ring))
+}
+
+fn open(path: &PathBuf) -> Result<Ring> {
+  let file = OpenOptions::new()
+    .read(true)
+    .write(true)
+    .open(path)
+    .map_err(|e| AppError::io(&path.display().to_string(), e))?;
+  let deadline = Instant::now() + Duration::from_secs(1);
+  loop {
+    let len = file.metadata()?.len();
+    if len >= DATA_AT as u64 {
+      let (map, base) = map(&file)?;
+      let ring = Ring {
+        path: path.clone(),
+        _map: map,
+        base,
+        capacity: 0,
+      };
+      if ring.atomic(MAGIC_AT).load(Ordering::Acquire) == MAGIC {
+        let capacity = unsafe { (base.add(CAPACITY_AT) as *const u64).read() };
+        if capacity == 0 || DATA_AT as u64 + capacity > len {
+          return Err(AppError::Parse(format!(
+            "{}: not a shared ring segment",
+            path.display()
+          )));
+        }
+        return Ok(Ring { capacity, ..ring });
+      }
+    }
+    if Instant::now() > deadline {
+      return Err(AppError::Parse(format!(
+        "{}: not a shared ring segment",
+        path.display()
+      )));
+    }
+    std::thread::sleep(Duration::from_millis(1));
+  }
+}
+
+/// A ring buffer of binary messages in named shared memory, for passing
+/// messages between Node processes on one machine without sockets.
+///
+/// `new SharedRing('jobs')` in any process attaches to the same segment,
+/// creating it if needed. The ring is lock-free for one producer and one
+/// consumer: at any time, at most one process (or thread) may push and at
+/// most one may pop. Messages come out whole and in order. The segment
+/// outlives the processes using it until `unlink()` is called.
+#[napi]
+pub struct SharedRing {
+  name: String,
+  ring: Option<Ring>,
+  overflow: Overflow,
+}
+
+impl SharedRing {
+  fn ring(&self) -> Result<&Ring> {
+    self
+      .ring
+      .as_ref()
+      .ok_or_else(|| AppError::Closed("shared ring is closed".into()))
+  }
+}
+
+#[napi]
+impl SharedRing {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(name: String, options: Option<SharedRingOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let overflow = match options.overflow.as_deref() {
+      None | Some("reject") => Overflow::Reject,
+      Some("error") => Overflow::Error,
+      Some("drop-oldest") => Overflow::DropOldest,
+      Some(other) => {
+        return Err(AppError::InvalidInput(format!(
+          "unknown overflow policy: {}",
+          other
+        )))
+      }
+    };
+    let capacity = options.capacity.map_or(DEFAULT_CAPACITY, u64::from);
+    if !(64..=MAX_CAPACITY).contains(&capacity) {
+      return Err(AppError::InvalidInput(format!(
+        "capacity must be 64 B–1 GiB, got {}",
+        capacity
+      )));
+    }
+    let path = named_path("ring", &name)?;
+    let ring = match create(&path, capacity)? {
+      Some(ring) => ring,
+      None => open(&path)?,
+    };
+    Ok(SharedRing {
+      name,
+      ring: Some(ring),
+      overflow,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  /// Bytes of message storage, as set by whoever created the segment.
+  #[napi(catch_unwind, getter)]
+  pub fn capacity(&self) -> Result<u32> {
+    Ok(self.ring()?.capacity as u32)
+  }
+
+  /// Bytes currently taken by queued messages and their framing.
+  #[napi(catch_unwind, getter)]
+  pub fn used(&self) -> Result<u32> {
+    let ring = self.ring()?;
+    let head = ring.atomic(HEAD_AT).load(Ordering::Acquire);
+    let tail = ring.atomic(TAIL_AT).load(Ordering::Acquire);
+    Ok(tail.saturating_sub(head) as u32)
+  }
+
+  /// Messages discarded by `drop-oldest` producers over the segment's life.
+  #[napi(catch_unwind, getter)]
+  pub fn dropped(&self) -> Result<f64> {
+    Ok(self.ring()?.atomic(DROPPED_AT).load(Ordering::Relaxed) as f64)
+  }
+
+  /// Queues a copy of `message`. Returns `false` if it doesn't fit and the
+  /// overflow policy is `reject`. A message larger than the whole ring throws
+  /// `ERR_INVALID_INPUT` under any policy.
+  #[napi(catch_unwind)]
+  pub fn push(&self, message: Buffer) -> Result<bool> {
+    self.ring()?.push(&message, self.overflow)
+  }
+
+  /// The oldest message, or `null` when the ring is empty.
+  #[napi(catch_unwind)]
+  pub fn pop(&self) -> Result<Option<Buffer>> {
+    Ok(self.ring()?.pop()?.map(Buffer::from))
+  }
+
+  /// Detaches this process. The segment and its messages stay for others.
+  /// Later calls throw `ERR_CLOSED`; closing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn close(&mut self) {
+    self.ring = None;
+  }
+
+  /// Removes the segment's name, so the next `new SharedRing` with it
+  /// starts an empty ring. Processes already attached keep working on the
+  /// old one until they close it.
+  #[napi(catch_unwind)]
+  pub fn unlink(&self) -> Result<()> {
+    let path = match &self.ring {
+      Some(ring) => ring.path.clone(),
+      None => named_path("ring", &self.name)?,
+    };
+    std::fs::remove_file(&path).map_err(|e| AppError::io(&path.display().to_string(), e))
+  }
+}
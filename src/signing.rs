@@ -0,0 +1,226 @@
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{
+  DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, KeypairBytes,
+};
+use ed25519_dalek::{Signer, Verifier};
+use napi::bindgen_prelude::*;
+use rand::rngs::OsRng;
+
+use crate::error::{AppError, Result};
+
+/// PEM-encoded keys: PKCS#8 for the private key and SPKI for the public
+/// one, the formats `node:crypto`, OpenSSL and `signJwt` (EdDSA) take.
+#[napi(object)]
+pub struct KeyPair {
+  #[napi(ts_type = "'ed25519' | 'ecdsa-p256'")]
+  pub algorithm: String,
+  pub public_key: String,
+  pub private_key: String,
+}
+
+enum PrivateKey {
+  Ed25519(ed25519_dalek::SigningKey),
+  P256(p256::ecdsa::SigningKey),
+}
+
+enum PublicKey {
+  Ed25519(ed25519_dalek::VerifyingKey),
+  P256(p256::ecdsa::VerifyingKey),
+}
+
+fn encode_error(e: impl std::fmt::Display) -> AppError {
+  AppError::Crypto(format!("encoding key: {}", e))
+}
+
+impl PrivateKey {
+  fn generate(algorithm: &str) -> Result<Self> {
+    match algorithm {
+      "ed25519" => Ok(PrivateKey::Ed25519(ed25519_dalek::SigningKey::generate(
+        &mut OsRng,
+      ))),
+      "ecdsa-p256" => Ok(PrivateKey::P256(p256::ecdsa::SigningKey::random(
+        &mut OsRng,
+      ))),
+      other => Err(AppError::InvalidInput(format!(
+        "unknown algorithm: {}",
+        other
+      ))),
+    }
+  }
+
+  /// Accepts PKCS#8 (`BEGIN PRIVATE KEY`) for either algorithm, and SEC1
+  /// (`BEGIN EC PRIVATE KEY`) as `openssl ecparam -genkey` writes it.
+  fn from_pem(pem: &str) -> Result<Self> {
+    if let Ok(key) = ed25519_dalek::SigningKey::from_pkcs8_pem(pem) {
+      return Ok(PrivateKey::Ed25519(key));
+    }
+    if let Ok(key) = p256::ecdsa::SigningKey::from_pkcs8_pem(pem) {
+      return Ok(PrivateKey::P256(key));
+    }
+    if let Ok(key) = p256::SecretKey::from_sec1_pem(pem) {
+      return Ok(PrivateKey::P256(key.into()));
+    }
+    Err(AppError::InvalidInput(
+      "expected a PEM Ed25519 or P-256 private key".into(),
+    ))
+  }
+
+  fn algorithm(&self) -> &'static str {
+    match self {
+      PrivateKey::Ed25519(_) => "ed25519",
+      PrivateKey::P256(_) => "ecdsa-p256",
+    }
+  }
+
+  fn public_key(&self) -> PublicKey {
+    match self {
+      PrivateKey::Ed25519(key) => PublicKey::Ed25519(key.verifying_key()),
+      PrivateKey::P256(key) => PublicKey::P256(*key.verifying_key()),
+    }
+  }
+
+  fn to_pem(&self) -> Result<String> {
+    let pem = match self {
+      // Without the public key, i.e. PKCS#8 v1: OpenSSL 3 rejects the v2
+      // encoding `SigningKey` writes.
+      PrivateKey::Ed25519(key) => KeypairBytes {
+        secret_key: key.to_bytes(),
+        public_key: None,
+      }
+      .to_pkcs8_pem(LineEnding::LF),
+      PrivateKey::P256(key) => key.to_pkcs8_pem(LineEnding::LF),
+    }
+    .map_err(encode_error)?;
+    Ok(pem.to_string())
+  }
+}
+
+impl PublicKey {
+  fn from_pem(pem: &str) -> Result<Self> {
+    if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_pem(pem) {
+      return Ok(PublicKey::Ed25519(key));
+    }
+    if let Ok(key) = p256::ecdsa::VerifyingKey::from_public_key_pem(pem) {
+      return Ok(PublicKey::P256(key));
+    }
+    Err(AppError::InvalidInput(
+      "expected a PEM Ed25519 or P-256 public key".into(),
+    ))
+  }
+
+  fn to_pem(&self) -> Result<String> {
+    match self {
+      PublicKey::Ed25519(key) => key.to_public_key_pem(LineEnding::LF),
+      PublicKey::P256(key) => key.to_public_key_pem(LineEnding::LF),
+    }
+    .map_err(encode_error)
+  }
+}
+
+/// A new random key pair. `algorithm` is `ed25519` (default) or
+/// `ecdsa-p256`.
+#[napi(catch_unwind, ts_args_type = "algorithm?: 'ed25519' | 'ecdsa-p256'")]
+pub fn generate_keypair(algorithm: Option<String>) -> Result<KeyPair> {
+  let private_key = PrivateKey::generate(algorithm.as_deref().unwrap_or("ed25519"))?;
+  Ok(KeyPair {
+    algorithm: private_key.algorithm().into(),
+    public_key: private_key.public_key().to_pem()?,
+    private_key: private_key.to_pem()?,
+  })
+}
+
+/// Signs `message` with a PEM private key, detecting the algorithm from
+/// the key. P-256 signs the SHA-256 digest and returns the 64-byte `r || s`
+/// form used by WebCrypto and JWS, i.e. `dsaEncoding: 'ieee-p1363'` in
+/// `node:crypto`; Ed25519 signatures are always 64 bytes.
+#[napi(catch_unwind)]
+pub fn sign(message: Buffer, private_key: String) -> Result<Buffer> {
+  let signature = match PrivateKey::from_pem(&private_key)? {
+    PrivateKey::Ed25519(key) => key.sign(&message).to_bytes().to_vec(),
+    PrivateKey::P256(key) => {
+      let signature: p256::ecdsa::Signature = key.sign(&message);
+      signature.to_bytes().to_vec()
+    }
+  };
+  Ok(signature.into())
+}
+
+/// Whether `signature` is a valid signature of `message` by the PEM
+/// public key. A malformed signature is simply invalid; a malformed key
+/// throws.
+#[napi(catch_unwind)]
+pub fn verify(message: Buffer, signature: Buffer, public_key: String) -> Result<bool> {
+  Ok(match PublicKey::from_pem(&public_key)? {
+    PublicKey::Ed25519(key) => ed25519_dalek::Signature::from_slice(&signature)
+      .is_ok_and(|signature| key.verify_strict(&message, &signature).is_ok()),
+    PublicKey::P256(key) => p256::ecdsa::Signature::from_slice(&signature)
+      .is_ok_and(|signature| key.verify(&message, &signature).is_ok()),
+  })
+}
+
+/// The PEM public key belonging to a PEM private key.
+#[napi(catch_unwind)]
+pub fn derive_public_key(private_key: String) -> Result<String> {
+  PrivateKey::from_pem(&private_key)?.public_key().to_pem()
+}
+
+/// The raw bytes of a PEM key: the 32-byte seed or public key for Ed25519,
+/// and the 32-byte scalar or 65-byte uncompressed point for P-256.
+#[napi(catch_unwind)]
+pub fn export_raw_key(pem: String) -> Result<Buffer> {
+  let raw = if pem.contains("PUBLIC KEY-----") {
+    match PublicKey::from_pem(&pem)? {
+      PublicKey::Ed25519(key) => key.to_bytes().to_vec(),
+      PublicKey::P256(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+    }
+  } else {
+    match PrivateKey::from_pem(&pem)? {
+      PrivateKey::Ed25519(key) => key.to_bytes().to_vec(),
+      PrivateKey::P256(key) => key.to_bytes().to_vec(),
+    }
+  };
+  Ok(raw.into())
+}
+
+/// The PEM form of raw key bytes as `exportRawKey` returns them. P-256
+/// public keys may also be 33-byte compressed points.
+#[napi(
+  catch_unwind,
+  ts_args_type = "raw: Buffer, algorithm: 'ed25519' | 'ecdsa-p256', kind: 'public' | 'private'"
+)]
+pub fn import_raw_key(raw: BufferSlice, algorithm: String, kind: String) -> Result<String> {
+  // A borrowed `BufferSlice` rather than `Buffer`, whose conversion can
+  // fail on an empty Buffer before this check runs.
+  if raw.is_empty() {
+    return Err(AppError::InvalidInput("raw key must not be empty".into()));
+  }
+  let invalid =
+    |what: &str| AppError::InvalidInput(format!("invalid raw {} {} key", algorithm, what));
+  match (algorithm.as_str(), kind.as_str()) {
+    ("ed25519", "private") => {
+      let seed = <[u8; 32]>::try_from(&raw[..]).map_err(|_| invalid("private"))?;
+      PrivateKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&seed)).to_pem()
+    }
+    ("ed25519", "public") => {
+      let bytes = <[u8; 32]>::try_from(&raw[..]).map_err(|_| invalid("public"))?;
+      let key = ed25519_dalek::VerifyingKey::from_bytes(&bytes).map_err(|_| invalid("public"))?;
+      PublicKey::Ed25519(key).to_pem()
+    }
+    ("ecdsa-p256", "private") => {
+      let key = p256::ecdsa::SigningKey::from_slice(&raw).map_err(|_| invalid("private"))?;
+      PrivateKey::P256(key).to_pem()
+    }
+    ("ecdsa-p256", "public") => {
+      let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&raw).map_err(|_| invalid("public"))?;
+      PublicKey::P256(key).to_pem()
+    }
+    ("ed25519" | "ecdsa-p256", other) => Err(AppError::InvalidInput(format!(
+      "unknown key kind: {}",
+      other
+    ))),
+    (other, _) => Err(AppError::InvalidInput(format!(
+      "unknown algorithm: {}",
+      other
+    ))),
+  }
+}
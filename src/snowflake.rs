@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+/// Twitter's epoch, 2010-11-04T01:42:54.657Z.
+const DEFAULT_EPOCH_MS: u64 = 1_288_834_974_657;
+const TIMESTAMP_BITS: u32 = 41;
+const MACHINE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const MAX_MACHINE_ID: u32 = (1 << MACHINE_BITS) - 1;
+const SEQUENCE_MASK: u64 = (1 << SEQUENCE_BITS) - 1;
+
+#[napi(object)]
+pub struct SnowflakeOptions {
+  /// 0–1023; must be unique among processes generating IDs concurrently.
+  pub machine_id: u32,
+  /// Custom epoch in Unix milliseconds. Defaults to Twitter's
+  /// (1288834974657).
+  pub epoch: Option<f64>,
+}
+
+#[napi(object)]
+pub struct SnowflakeParts {
+  /// Unix milliseconds at which the ID was generated.
+  pub timestamp: f64,
+  pub machine_id: u32,
+  pub sequence: u32,
+}
+
+type States = Mutex<HashMap<(u32, u64), Arc<AtomicU64>>>;
+
+/// Last issued `(timestamp << SEQUENCE_BITS) | sequence`, shared by every
+/// generator in the process with the same machine id and epoch. Workers
+/// each get their own JS object, so this is what keeps their IDs unique.
+fn shared_state(machine_id: u32, epoch: u64) -> Arc<AtomicU64> {
+  static STATES: OnceLock<States> = OnceLock::new();
+  let mut states = STATES
+    .get_or_init(Default::default)
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+  states.entry((machine_id, epoch)).or_default().clone()
+}
+
+fn now_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Generates 64-bit Snowflake IDs: 41 bits of milliseconds since the
+/// epoch, 10 bits of machine id and a 12-bit per-millisecond sequence.
+///
+/// IDs are strictly increasing, even across worker threads. When 4096 IDs
+/// are used up within one millisecond, or the clock steps backwards,
+/// generation carries on from the last timestamp instead of waiting.
+#[napi]
+pub struct SnowflakeGenerator {
+  machine_id: u32,
+  epoch: u64,
+  state: Arc<AtomicU64>,
+}
+
+#[napi]
+impl SnowflakeGenerator {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: SnowflakeOptions) -> Result<Self> {
+    if options.machine_id > MAX_MACHINE_ID {
+      return Err(AppError::InvalidInput(format!(
+        "machineId must be between 0 and {}",
+        MAX_MACHINE_ID
+      )));
+    }
+    let epoch = match options.epoch {
+      None => DEFAULT_EPOCH_MS,
+      Some(ms) if ms.is_finite() && ms >= 0.0 && ms.fract() == 0.0 && ms as u64 <= now_ms() => {
+        ms as u64
+      }
+      Some(ms) => {
+        return Err(AppError::InvalidInput(format!(
+          "epoch must be a past Unix time in whole milliseconds, got {}",
+          ms
+        )))
+      }
+    };
+    Ok(SnowflakeGenerator {
+      machine_id: options.machine_id,
+      epoch,
+      state: shared_state(options.machine_id, epoch),
+    })
+  }
+
+  /// The next ID as a BigInt.
+  #[napi(catch_unwind)]
+  pub fn next_id(&self) -> Result<BigInt> {
+    Ok(BigInt::from(self.generate()?))
+  }
+
+  /// The next ID as a decimal string, for JSON and databases without
+  /// 64-bit integers.
+  #[napi(catch_unwind)]
+  pub fn next_id_string(&self) -> Result<String> {
+    Ok(self.generate()?.to_string())
+  }
+
+  /// Splits an ID from this generator's scheme into its parts.
+  #[napi(catch_unwind, ts_args_type = "id: bigint | string")]
+  pub fn decompose(&self, id: Either<BigInt, String>) -> Result<SnowflakeParts> {
+    let id = match id {
+      Either::A(big) => match big.get_u64() {
+        (false, value, true) => value,
+        _ => {
+          return Err(AppError::InvalidInput(
+            "id must fit in 64 unsigned bits".into(),
+          ))
+        }
+      },
+      Either::B(text) => text
+        .parse::<u64>()
+        .map_err(|_| AppError::Parse(format!("invalid snowflake id: {:?}", text)))?,
+    };
+    Ok(SnowflakeParts {
+      timestamp: ((id >> (MACHINE_BITS + SEQUENCE_BITS)) + self.epoch) as f64,
+      machine_id: ((id >> SEQUENCE_BITS) as u32) & MAX_MACHINE_ID,
+      sequence: (id & SEQUENCE_MASK) as u32,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn machine_id(&self) -> u32 {
+    self.machine_id
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn epoch(&self) -> f64 {
+    self.epoch as f64
+  }
+
+  fn generate(&self) -> Result<u64> {
+    let now = now_ms().saturating_sub(self.epoch);
+    let mut prev = self.state.load(Ordering::Relaxed);
+    let next = loop {
+      let next = if now > prev >> SEQUENCE_BITS {
+        now << SEQUENCE_BITS
+      } else {
+        // Same millisecond, or the clock went backwards: bump the
+        // sequence, spilling into the next millisecond when it wraps.
+        prev + 1
+      };
+      match self
+        .state
+        .compare_exchange_weak(prev, next, Ordering::Relaxed, Ordering::Relaxed)
+      {
+        Ok(_) => break next,
+        Err(actual) => prev = actual,
+      }
+    };
+    let timestamp = next >> SEQUENCE_BITS;
+    if timestamp >= 1 << TIMESTAMP_BITS {
+      return Err(AppError::Overflow(
+        "snowflake timestamp exceeds 41 bits; choose a later epoch".into(),
+      ));
+    }
+    Ok(
+      timestamp << (MACHINE_BITS + SEQUENCE_BITS)
+        | (self.machine_id as u64) << SEQUENCE_BITS
+        | next & SEQUENCE_MASK,
+    )
+  }
+}
@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SortOptions {
+  /// Largest first. Defaults to `false`.
+  pub descending: Option<bool>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct TopKOptions {
+  /// Take the `k` smallest values, smallest first, instead of the largest.
+  pub smallest: Option<bool>,
+}
+
+/// The order `Float64Array.prototype.sort` uses (`-0` before `0`), reversed
+/// when `descending`, with NaN always last.
+fn order(a: f64, b: f64, descending: bool) -> Ordering {
+  match (a.is_nan(), b.is_nan()) {
+    (true, true) => Ordering::Equal,
+    (true, false) => Ordering::Greater,
+    (false, true) => Ordering::Less,
+    _ if descending => b.total_cmp(&a),
+    _ => a.total_cmp(&b),
+  }
+}
+
+/// Sorts `data` in place on the rayon pool. The array's own memory is
+/// sorted, so nothing is copied in either direction; NaN values go last.
+#[napi(catch_unwind)]
+pub fn sort_f64(mut data: Float64Array, options: Option<SortOptions>) {
+  let descending = options.unwrap_or_default().descending.unwrap_or(false);
+  data.par_sort_unstable_by(|a, b| order(*a, *b, descending));
+}
+
+/// The indices that would sort `data`, as `sortF64` orders it. The sort is
+/// stable: equal values keep their original order. `data` is not changed.
+#[napi(catch_unwind)]
+pub fn argsort(data: Float64Array, options: Option<SortOptions>) -> Result<Uint32Array> {
+  if data.len() > u32::MAX as usize {
+    return Err(AppError::InvalidInput(
+      "argsort supports at most 2^32 - 1 elements".into(),
+    ));
+  }
+  let descending = options.unwrap_or_default().descending.unwrap_or(false);
+  let values: &[f64] = &data;
+  let mut indices: Vec<u32> = (0..values.len() as u32).collect();
+  indices.par_sort_by(|&a, &b| order(values[a as usize], values[b as usize], descending));
+  Ok(Uint32Array::new(indices))
+}
+
+/// The distinct strings of `strings`, each where it first occurs.
+#[napi(catch_unwind)]
+pub fn dedup_strings(strings: Vec<String>) -> Vec<String> {
+  let keep: Vec<bool> = {
+    let mut seen = HashSet::with_capacity(strings.len());
+    strings.iter().map(|s| seen.insert(s.as_str())).collect()
+  };
+  strings
+    .into_iter()
+    .zip(keep)
+    .filter_map(|(s, keep)| keep.then_some(s))
+    .collect()
+}
+
+/// The `k` largest values of `data`, largest first, or with `smallest` the
+/// `k` smallest, smallest first. NaN values are skipped. Selecting is
+/// linear in the length of `data`; only the `k` results are sorted.
+#[napi(catch_unwind)]
+pub fn top_k(data: Float64Array, k: u32, options: Option<TopKOptions>) -> Float64Array {
+  let descending = !options.unwrap_or_default().smallest.unwrap_or(false);
+  let mut values: Vec<f64> = data.par_iter().copied().filter(|v| !v.is_nan()).collect();
+  let k = (k as usize).min(values.len());
+  if k == 0 {
+    return Float64Array::new(Vec::new());
+  }
+  values.select_nth_unstable_by(k - 1, |a, b| order(*a, *b, descending));
+  values.truncate(k);
+  values.sort_unstable_by(|a, b| order(*a, *b, descending));
+  Float64Array::new(values)
+}
@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+
+use napi::bindgen_prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SpellCheckerOptions {
+  /// Largest edit distance a suggestion may be from the word, 1–3. Defaults
+  /// to 2. Each step multiplies the index size.
+  pub max_edit_distance: Option<u32>,
+  /// How many leading characters of each word are indexed. Defaults to 7;
+  /// smaller is leaner but slower to search.
+  pub prefix_length: Option<u32>,
+}
+
+/// A dictionary indexed SymSpell-style: every deletion of up to
+/// `maxEditDistance` characters from each word's prefix is precomputed,
+/// so a lookup only generates deletions of the misspelling and checks a
+/// handful of candidates instead of scanning the dictionary.
+///
+/// Words are compared lowercased. Distances are optimal string alignment
+/// (Levenshtein plus adjacent transpositions).
+#[napi]
+pub struct SpellChecker {
+  max_distance: usize,
+  prefix_length: usize,
+  words: Vec<String>,
+  counts: Vec<u64>,
+  ids: FxHashMap<String, u32>,
+  deletes: FxHashMap<String, Vec<u32>>,
+}
+
+/// `word` with one character removed at each position.
+fn single_deletes(word: &str) -> impl Iterator<Item = String> + '_ {
+  let chars: Vec<char> = word.chars().collect();
+  (0..chars.len()).map(move |skip| {
+    chars
+      .iter()
+      .enumerate()
+      .filter(|&(i, _)| i != skip)
+      .map(|(_, c)| c)
+      .collect()
+  })
+}
+
+fn prefix(word: &str, length: usize) -> &str {
+  match word.char_indices().nth(length) {
+    Some((end, _)) => &word[..end],
+    None => word,
+  }
+}
+
+impl SpellChecker {
+  fn add(&mut self, word: &str, count: u64) {
+    let word = word.trim().to_lowercase();
+    if word.is_empty() {
+      return;
+    }
+    if let Some(&id) = self.ids.get(&word) {
+      self.counts[id as usize] += count;
+      return;
+    }
+    let id = self.words.len() as u32;
+    let key = prefix(&word, self.prefix_length).to_string();
+    let mut variants = HashSet::from([key.clone()]);
+    let mut frontier = vec![key];
+    for _ in 0..self.max_distance {
+      frontier = frontier
+        .iter()
+        .flat_map(|variant| single_deletes(variant))
+        .filter(|variant| variants.insert(variant.clone()))
+        .collect();
+    }
+    for variant in variants {
+      self.deletes.entry(variant).or_default().push(id);
+    }
+    self.ids.insert(word.clone(), id);
+    self.words.push(word);
+    self.counts.push(count);
+  }
+
+  fn load_file(&mut self, path: &str) -> Result<u32> {
+    let text = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    let before = self.words.len();
+    for (number, line) in text.lines().enumerate() {
+      let mut fields = line.split_whitespace();
+      let Some(word) = fields.next() else {
+        continue;
+      };
+      let count = match fields.next() {
+        None => 1,
+        Some(count) => count.parse::<u64>().map_err(|_| {
+          AppError::Parse(format!(
+            "{}:{}: invalid word count {:?}",
+            path,
+            number + 1,
+            count
+          ))
+        })?,
+      };
+      self.add(word, count);
+    }
+    Ok((self.words.len() - before) as u32)
+  }
+}
+
+#[napi]
+impl SpellChecker {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<SpellCheckerOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let max_distance = options.max_edit_distance.unwrap_or(2);
+    if !(1..=3).contains(&max_distance) {
+      return Err(AppError::InvalidInput(format!(
+        "maxEditDistance must be 1–3, got {}",
+        max_distance
+      )));
+    }
+    let prefix_length = options.prefix_length.unwrap_or(7);
+    if prefix_length <= max_distance {
+      return Err(AppError::InvalidInput(format!(
+        "prefixLength must exceed maxEditDistance, got {}",
+        prefix_length
+      )));
+    }
+    Ok(SpellChecker {
+      max_distance: max_distance as usize,
+      prefix_length: prefix_length as usize,
+      words: Vec::new(),
+      counts: Vec::new(),
+      ids: FxHashMap::default(),
+      deletes: FxHashMap::default(),
+    })
+  }
+
+  /// Adds words and returns how many were new. An array adds each entry
+  /// once (repeats raise its frequency); a string is the path of a file
+  /// with one `word` or `word count` per line, as frequency lists come.
+  /// Frequencies break ties between equally close suggestions.
+  #[napi(catch_unwind, ts_args_type = "source: string[] | string")]
+  pub fn load_dictionary(&mut self, source: Either<Vec<String>, String>) -> Result<u32> {
+    match source {
+      Either::A(words) => {
+        let before = self.words.len();
+        for word in &words {
+          self.add(word, 1);
+        }
+        Ok((self.words.len() - before) as u32)
+      }
+      Either::B(path) => self.load_file(&path),
+    }
+  }
+
+  /// Whether `word` is in the dictionary, ignoring case.
+  #[napi(catch_unwind)]
+  pub fn check(&self, word: String) -> bool {
+    self.ids.contains_key(&word.trim().to_lowercase())
+  }
+
+  /// Up to `max` (default 5) dictionary words within `maxEditDistance` of
+  /// `word`: closest first, then most frequent, then alphabetical. A
+  /// correctly spelled word comes back first, at distance 0.
+  #[napi(catch_unwind)]
+  pub fn suggest(&self, word: String, max: Option<u32>) -> Vec<String> {
+    let input = word.trim().to_lowercase();
+    let input_len = input.chars().count();
+    let start = prefix(&input, self.prefix_length).to_string();
+    let start_len = start.chars().count();
+
+    let mut found: FxHashMap<u32, usize> = FxHashMap::default();
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = vec![start];
+    while let Some(candidate) = queue.pop() {
+      let removed = start_len - candidate.chars().count();
+      if let Some(ids) = self.deletes.get(&candidate) {
+        for &id in ids {
+          if found.contains_key(&id) {
+            continue;
+          }
+          let suggestion = &self.words[id as usize];
+          if suggestion.chars().count().abs_diff(input_len) > self.max_distance {
+            continue;
+          }
+          let distance = strsim::osa_distance(&input, suggestion);
+          if distance <= self.max_distance {
+            found.insert(id, distance);
+          }
+        }
+      }
+      if removed < self.max_distance {
+        for variant in single_deletes(&candidate) {
+          if seen.insert(variant.clone()) {
+            queue.push(variant);
+          }
+        }
+      }
+    }
+
+    let mut ranked: Vec<(usize, u32)> = found.into_iter().map(|(id, d)| (d, id)).collect();
+    ranked.sort_by(|a, b| {
+      a.0
+        .cmp(&b.0)
+        .then(self.counts[b.1 as usize].cmp(&self.counts[a.1 as usize]))
+        .then(self.words[a.1 as usize].cmp(&self.words[b.1 as usize]))
+    });
+    ranked
+      .into_iter()
+      .take(max.unwrap_or(5) as usize)
+      .map(|(_, id)| self.words[id as usize].clone())
+      .collect()
+  }
+
+  /// The number of distinct words loaded.
+  #[napi(catch_unwind, getter)]
+  pub fn size(&self) -> u32 {
+    self.words.len() as u32
+  }
+}
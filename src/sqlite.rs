@@ -0,0 +1,357 @@
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsBigInt, JsUnknown, NapiRaw, Task, ValueType};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+
+use crate::buffer_ops::buffer_bytes;
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+type SharedConnection = Arc<Mutex<Option<Connection>>>;
+
+/// Integers beyond this come back as BigInt so they round-trip losslessly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[napi(object)]
+pub struct RunResult {
+  /// Rows inserted, updated or deleted by the statement.
+  pub changes: u32,
+  pub last_insert_rowid: i64,
+}
+
+/// Query results as plain Rust values; they only become JS objects on the JS
+/// thread, which lets the same type come out of sync calls and async tasks.
+pub struct Rows {
+  columns: Vec<String>,
+  values: Vec<Vec<SqlValue>>,
+}
+
+impl TypeName for Rows {
+  fn type_name() -> &'static str {
+    "Array<Record<string, unknown>>"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ToNapiValue for Rows {
+  unsafe fn to_napi_value(env: sys::napi_env, rows: Self) -> napi::Result<sys::napi_value> {
+    let env = Env::from_raw(env);
+    let mut array = env.create_array_with_length(rows.values.len())?;
+    for (i, row) in rows.values.into_iter().enumerate() {
+      let mut object = env.create_object()?;
+      for (column, value) in rows.columns.iter().zip(row) {
+        object.set_named_property(column, to_js(&env, value)?)?;
+      }
+      array.set_element(i as u32, object)?;
+    }
+    Ok(array.raw())
+  }
+}
+
+fn to_js(env: &Env, value: SqlValue) -> napi::Result<JsUnknown> {
+  Ok(match value {
+    SqlValue::Null => env.get_null()?.into_unknown(),
+    SqlValue::Integer(i) if i.unsigned_abs() <= MAX_SAFE_INTEGER => {
+      env.create_int64(i)?.into_unknown()
+    }
+    SqlValue::Integer(i) => env.create_bigint_from_i64(i)?.into_unknown()?,
+    SqlValue::Real(f) => env.create_double(f)?.into_unknown(),
+    SqlValue::Text(s) => env.create_string_from_std(s)?.into_unknown(),
+    SqlValue::Blob(b) => env.create_buffer_with_data(b)?.into_raw().into_unknown(),
+  })
+}
+
+/// Converts a bound parameter. Booleans become 0/1, integral numbers become
+/// INTEGER, Buffers become BLOB.
+fn to_sql(value: JsUnknown) -> Result<SqlValue> {
+  Ok(match value.get_type()? {
+    ValueType::Null | ValueType::Undefined => SqlValue::Null,
+    ValueType::Boolean => SqlValue::Integer(value.coerce_to_bool()?.get_value()? as i64),
+    ValueType::Number => {
+      let n = value.coerce_to_number()?.get_double()?;
+      if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER as f64 {
+        SqlValue::Integer(n as i64)
+      } else {
+        SqlValue::Real(n)
+      }
+    }
+    ValueType::String => SqlValue::Text(value.coerce_to_string()?.into_utf8()?.into_owned()?),
+    ValueType::BigInt => match unsafe { value.cast::<JsBigInt>() }.get_i64()? {
+      (i, true) => SqlValue::Integer(i),
+      _ => {
+        return Err(AppError::Overflow(
+          "BigInt parameter does not fit in i64".into(),
+        ))
+      }
+    },
+    ValueType::Object if value.is_buffer()? => SqlValue::Blob(buffer_bytes(&value)?),
+    other => {
+      return Err(AppError::InvalidInput(format!(
+        "cannot bind a {:?} as an SQL parameter",
+        other
+      )))
+    }
+  })
+}
+
+fn bind(params: Option<Vec<JsUnknown>>) -> Result<Vec<SqlValue>> {
+  params.unwrap_or_default().into_iter().map(to_sql).collect()
+}
+
+fn db_error(e: rusqlite::Error) -> AppError {
+  tracing::debug!(error = %e, "statement failed");
+  AppError::Database(e.to_string())
+}
+
+fn closed() -> AppError {
+  AppError::Closed("database is closed".into())
+}
+
+fn with_conn<T>(db: &SharedConnection, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+  let guard = db.lock().unwrap_or_else(|e| e.into_inner());
+  f(guard.as_ref().ok_or_else(closed)?)
+}
+
+fn exec(conn: &Connection, sql: &str) -> Result<()> {
+  tracing::trace!(sql, "exec");
+  conn.execute_batch(sql).map_err(db_error)
+}
+
+fn query(conn: &Connection, sql: &str, params: &[SqlValue]) -> Result<Rows> {
+  tracing::trace!(sql, params = params.len(), "query");
+  let mut stmt = conn.prepare_cached(sql).map_err(db_error)?;
+  let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+  let width = columns.len();
+  let mut rows = stmt.query(params_from_iter(params)).map_err(db_error)?;
+  let mut values = Vec::new();
+  while let Some(row) = rows.next().map_err(db_error)? {
+    let row = (0..width)
+      .map(|i| row.get::<_, SqlValue>(i))
+      .collect::<rusqlite::Result<_>>()
+      .map_err(db_error)?;
+    values.push(row);
+  }
+  Ok(Rows { columns, values })
+}
+
+fn run(conn: &Connection, sql: &str, params: &[SqlValue]) -> Result<RunResult> {
+  tracing::trace!(sql, params = params.len(), "run");
+  let changes = conn
+    .prepare_cached(sql)
+    .and_then(|mut stmt| stmt.execute(params_from_iter(params)))
+    .map_err(db_error)?;
+  Ok(RunResult {
+    changes: changes as u32,
+    last_insert_rowid: conn.last_insert_rowid(),
+  })
+}
+
+type DbOp<T> = Box<dyn FnOnce(&Connection) -> Result<T> + Send>;
+
+/// Runs one operation against the connection on the libuv thread pool.
+/// Other calls on the same database wait for it to finish.
+pub struct DbTask<T> {
+  db: SharedConnection,
+  op: Option<DbOp<T>>,
+}
+
+impl<T> DbTask<T> {
+  fn new(
+    db: &SharedConnection,
+    op: impl FnOnce(&Connection) -> Result<T> + Send + 'static,
+  ) -> AsyncTask<Guarded<Self>>
+  where
+    T: ToNapiValue + TypeName + Send + 'static,
+  {
+    panic_guard::task(DbTask {
+      db: db.clone(),
+      op: Some(Box::new(op)),
+    })
+  }
+}
+
+impl<T: ToNapiValue + TypeName + Send + 'static> Task for DbTask<T> {
+  type Output = Result<T>;
+  type JsValue = T;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let op = self.op.take().expect("DbTask computed twice");
+    Ok(with_conn(&self.db, op))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// An embedded SQLite database.
+///
+/// Parameters are positional (`?`) and bound from an array. Statements are
+/// cached per connection, so repeating the same SQL skips re-parsing.
+#[napi]
+pub struct Database {
+  db: SharedConnection,
+}
+
+#[napi]
+impl Database {
+  /// Opens or creates the database at `path`; `:memory:` opens a private
+  /// in-memory database.
+  #[napi(catch_unwind, factory)]
+  pub fn open(path: String) -> Result<Self> {
+    let conn = Connection::open(&path).map_err(db_error)?;
+    tracing::debug!(path, "database opened");
+    Ok(Database {
+      db: Arc::new(Mutex::new(Some(conn))),
+    })
+  }
+
+  /// Executes one or more `;`-separated statements that take no parameters.
+  #[napi(catch_unwind)]
+  pub fn exec(&self, sql: String) -> Result<()> {
+    with_conn(&self.db, |conn| exec(conn, &sql))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "sql: string, params?: Array<unknown>",
+    ts_return_type = "Array<Record<string, unknown>>"
+  )]
+  pub fn query(&self, sql: String, params: Option<Vec<JsUnknown>>) -> Result<Rows> {
+    let params = bind(params)?;
+    with_conn(&self.db, |conn| query(conn, &sql, &params))
+  }
+
+  /// Executes a statement that returns no rows.
+  #[napi(catch_unwind, ts_args_type = "sql: string, params?: Array<unknown>")]
+  pub fn run(&self, sql: String, params: Option<Vec<JsUnknown>>) -> Result<RunResult> {
+    let params = bind(params)?;
+    with_conn(&self.db, |conn| run(conn, &sql, &params))
+  }
+
+  /// Compiles `sql` now, so syntax errors surface here rather than on first
+  /// use.
+  #[napi(catch_unwind)]
+  pub fn prepare(&self, sql: String) -> Result<Statement> {
+    with_conn(&self.db, |conn| {
+      conn.prepare_cached(&sql).map(drop).map_err(db_error)
+    })?;
+    Ok(Statement {
+      db: self.db.clone(),
+      sql,
+    })
+  }
+
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn exec_async(&self, sql: String) -> AsyncTask<Guarded<DbTask<()>>> {
+    DbTask::new(&self.db, move |conn| exec(conn, &sql))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "sql: string, params?: Array<unknown>",
+    ts_return_type = "Promise<Array<Record<string, unknown>>>"
+  )]
+  pub fn query_async(
+    &self,
+    sql: String,
+    params: Option<Vec<JsUnknown>>,
+  ) -> Result<AsyncTask<Guarded<DbTask<Rows>>>> {
+    let params = bind(params)?;
+    Ok(DbTask::new(&self.db, move |conn| {
+      query(conn, &sql, &params)
+    }))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "sql: string, params?: Array<unknown>",
+    ts_return_type = "Promise<RunResult>"
+  )]
+  pub fn run_async(
+    &self,
+    sql: String,
+    params: Option<Vec<JsUnknown>>,
+  ) -> Result<AsyncTask<Guarded<DbTask<RunResult>>>> {
+    let params = bind(params)?;
+    Ok(DbTask::new(&self.db, move |conn| run(conn, &sql, &params)))
+  }
+
+  /// Closes the connection. Later calls on this database or its statements
+  /// throw `ERR_CLOSED`; closing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn close(&self) -> Result<()> {
+    let conn = self.db.lock().unwrap_or_else(|e| e.into_inner()).take();
+    match conn {
+      Some(conn) => {
+        tracing::debug!("database closed");
+        conn.close().map_err(|(_, e)| db_error(e))
+      }
+      None => Ok(()),
+    }
+  }
+}
+
+/// A statement prepared by `Database.prepare`.
+#[napi]
+pub struct Statement {
+  db: SharedConnection,
+  sql: String,
+}
+
+#[napi]
+impl Statement {
+  #[napi(catch_unwind, getter)]
+  pub fn sql(&self) -> String {
+    self.sql.clone()
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "params?: Array<unknown>",
+    ts_return_type = "Array<Record<string, unknown>>"
+  )]
+  pub fn query(&self, params: Option<Vec<JsUnknown>>) -> Result<Rows> {
+    let params = bind(params)?;
+    with_conn(&self.db, |conn| query(conn, &self.sql, &params))
+  }
+
+  #[napi(catch_unwind, ts_args_type = "params?: Array<unknown>")]
+  pub fn run(&self, params: Option<Vec<JsUnknown>>) -> Result<RunResult> {
+    let params = bind(params)?;
+    with_conn(&self.db, |conn| run(conn, &self.sql, &params))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "params?: Array<unknown>",
+    ts_return_type = "Promise<Array<Record<string, unknown>>>"
+  )]
+  pub fn query_async(
+    &self,
+    params: Option<Vec<JsUnknown>>,
+  ) -> Result<AsyncTask<Guarded<DbTask<Rows>>>> {
+    let (params, sql) = (bind(params)?, self.sql.clone());
+    Ok(DbTask::new(&self.db, move |conn| {
+      query(conn, &sql, &params)
+    }))
+  }
+
+  #[napi(
+    catch_unwind,
+    ts_args_type = "params?: Array<unknown>",
+    ts_return_type = "Promise<RunResult>"
+  )]
+  pub fn run_async(
+    &self,
+    params: Option<Vec<JsUnknown>>,
+  ) -> Result<AsyncTask<Guarded<DbTask<RunResult>>>> {
+    let (params, sql) = (bind(params)?, self.sql.clone());
+    Ok(DbTask::new(&self.db, move |conn| run(conn, &sql, &params)))
+  }
+}
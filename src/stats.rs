@@ -0,0 +1,193 @@
+use napi::bindgen_prelude::*;
+
+use crate::error::{AppError, Result};
+
+type Numbers = Either<Float64Array, Int32Array>;
+
+/// Five-number summary plus mean and standard deviation.
+#[napi(object)]
+pub struct SummaryStats {
+  pub count: u32,
+  pub mean: f64,
+  /// Population standard deviation.
+  pub stddev: f64,
+  pub min: f64,
+  pub p25: f64,
+  pub median: f64,
+  pub p75: f64,
+  pub max: f64,
+}
+
+#[napi(object)]
+pub struct Histogram {
+  /// `bins + 1` bin boundaries in ascending order.
+  pub edges: Vec<f64>,
+  /// Counts per bin. Every bin is half-open except the last, which also
+  /// includes its upper edge.
+  pub counts: Vec<u32>,
+}
+
+/// A borrowed view of the TypedArray's backing store.
+enum Samples<'a> {
+  F64(&'a [f64]),
+  I32(&'a [i32]),
+}
+
+impl<'a> Samples<'a> {
+  fn new(input: &'a Numbers) -> Result<Self> {
+    let samples = match input {
+      Either::A(values) => Samples::F64(values),
+      Either::B(values) => Samples::I32(values),
+    };
+    if samples.len() == 0 {
+      return Err(AppError::InvalidInput("input must not be empty".into()));
+    }
+    if let Samples::F64(values) = samples {
+      if values.iter().any(|v| v.is_nan()) {
+        return Err(AppError::InvalidInput("input must not contain NaN".into()));
+      }
+    }
+    Ok(samples)
+  }
+
+  fn len(&self) -> usize {
+    match self {
+      Samples::F64(values) => values.len(),
+      Samples::I32(values) => values.len(),
+    }
+  }
+
+  fn iter(&self) -> Box<dyn Iterator<Item = f64> + 'a> {
+    match *self {
+      Samples::F64(values) => Box::new(values.iter().copied()),
+      Samples::I32(values) => Box::new(values.iter().map(|&v| v as f64)),
+    }
+  }
+
+  /// Order statistics need a sorted copy; everything else reads in place.
+  fn sorted(&self) -> Vec<f64> {
+    let mut values: Vec<f64> = self.iter().collect();
+    values.sort_unstable_by(f64::total_cmp);
+    values
+  }
+
+  /// Mean and population variance in one pass (Welford's algorithm).
+  fn moments(&self) -> (f64, f64) {
+    let (mut count, mut mean, mut m2) = (0.0, 0.0, 0.0);
+    for v in self.iter() {
+      count += 1.0;
+      let delta = v - mean;
+      mean += delta / count;
+      m2 += delta * (v - mean);
+    }
+    (mean, m2 / count)
+  }
+}
+
+/// Linear interpolation between closest ranks, as in NumPy's default.
+pub(crate) fn quantile(sorted: &[f64], q: f64) -> f64 {
+  let rank = q * (sorted.len() - 1) as f64;
+  let lo = rank.floor() as usize;
+  let hi = rank.ceil() as usize;
+  sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+#[napi(catch_unwind, ts_args_type = "input: Float64Array | Int32Array")]
+pub fn mean(input: Numbers) -> Result<f64> {
+  Ok(Samples::new(&input)?.moments().0)
+}
+
+#[napi(catch_unwind, ts_args_type = "input: Float64Array | Int32Array")]
+pub fn median(input: Numbers) -> Result<f64> {
+  Ok(quantile(&Samples::new(&input)?.sorted(), 0.5))
+}
+
+/// Population standard deviation, or the sample (n - 1) estimate when
+/// `sample` is `true`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "input: Float64Array | Int32Array, sample?: boolean"
+)]
+pub fn stddev(input: Numbers, sample: Option<bool>) -> Result<f64> {
+  let samples = Samples::new(&input)?;
+  let n = samples.len() as f64;
+  let variance = samples.moments().1;
+  if !sample.unwrap_or(false) {
+    return Ok(variance.sqrt());
+  }
+  if n < 2.0 {
+    return Err(AppError::InvalidInput(
+      "sample standard deviation needs at least two values".into(),
+    ));
+  }
+  Ok((variance * n / (n - 1.0)).sqrt())
+}
+
+/// The `p`th percentile, `p` in `[0, 100]`, interpolating between ranks.
+#[napi(
+  catch_unwind,
+  ts_args_type = "input: Float64Array | Int32Array, p: number"
+)]
+pub fn percentile(input: Numbers, p: f64) -> Result<f64> {
+  if !(0.0..=100.0).contains(&p) {
+    return Err(AppError::InvalidInput(format!(
+      "percentile must be between 0 and 100, got {}",
+      p
+    )));
+  }
+  Ok(quantile(&Samples::new(&input)?.sorted(), p / 100.0))
+}
+
+/// Counts values into `bins` equal-width bins spanning the input's range.
+/// If every value is the same, the range is widened by 0.5 on each side.
+#[napi(
+  catch_unwind,
+  ts_args_type = "input: Float64Array | Int32Array, bins: number"
+)]
+pub fn histogram(input: Numbers, bins: u32) -> Result<Histogram> {
+  if bins == 0 {
+    return Err(AppError::InvalidInput("bins must be at least 1".into()));
+  }
+  let samples = Samples::new(&input)?;
+  let (mut lo, mut hi) = samples
+    .iter()
+    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+      (lo.min(v), hi.max(v))
+    });
+  if !(hi - lo).is_finite() {
+    return Err(AppError::InvalidInput("input range must be finite".into()));
+  }
+  if lo == hi {
+    lo -= 0.5;
+    hi += 0.5;
+  }
+  let width = (hi - lo) / bins as f64;
+  let mut counts = vec![0u32; bins as usize];
+  for v in samples.iter() {
+    let bin = (((v - lo) / width) as usize).min(bins as usize - 1);
+    counts[bin] += 1;
+  }
+  let edges = (0..=bins)
+    .map(|i| if i == bins { hi } else { lo + width * i as f64 })
+    .collect();
+  Ok(Histogram { edges, counts })
+}
+
+/// Count, mean, standard deviation, min, quartiles and max in one call,
+/// sorting the input only once.
+#[napi(catch_unwind, ts_args_type = "input: Float64Array | Int32Array")]
+pub fn describe(input: Numbers) -> Result<SummaryStats> {
+  let samples = Samples::new(&input)?;
+  let (mean, variance) = samples.moments();
+  let sorted = samples.sorted();
+  Ok(SummaryStats {
+    count: sorted.len() as u32,
+    mean,
+    stddev: variance.sqrt(),
+    min: sorted[0],
+    p25: quantile(&sorted, 0.25),
+    median: quantile(&sorted, 0.5),
+    p75: quantile(&sorted, 0.75),
+    max: sorted[sorted.len() - 1],
+  })
+}
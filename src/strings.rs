@@ -0,0 +1,63 @@
+use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[napi(catch_unwind)]
+pub fn normalize_nfc(s: String) -> String {
+  s.nfc().collect()
+}
+
+#[napi(catch_unwind)]
+pub fn normalize_nfkd(s: String) -> String {
+  s.nfkd().collect()
+}
+
+/// Lower-case ASCII slug: accents are stripped (`é` → `e`) and every run of
+/// other characters becomes a single `-`.
+#[napi(catch_unwind)]
+pub fn slugify(s: String) -> String {
+  let mut slug = String::with_capacity(s.len());
+  for c in s.nfkd().filter(|c| !is_combining_mark(*c)) {
+    if c.is_ascii_alphanumeric() {
+      slug.push(c.to_ascii_lowercase());
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  if slug.ends_with('-') {
+    slug.pop();
+  }
+  slug
+}
+
+#[napi(catch_unwind)]
+pub fn camel_case(s: String) -> String {
+  s.to_lower_camel_case()
+}
+
+#[napi(catch_unwind)]
+pub fn snake_case(s: String) -> String {
+  s.to_snake_case()
+}
+
+#[napi(catch_unwind)]
+pub fn kebab_case(s: String) -> String {
+  s.to_kebab_case()
+}
+
+/// Number of user-perceived characters (extended grapheme clusters), so
+/// `'👍🏽'` and `'é'` written as `e` + accent both count as 1.
+#[napi(catch_unwind)]
+pub fn grapheme_length(s: String) -> u32 {
+  s.graphemes(true).count() as u32
+}
+
+/// Keeps the first `n` grapheme clusters without ever splitting one.
+#[napi(catch_unwind)]
+pub fn truncate_graphemes(s: String, n: u32) -> String {
+  match s.grapheme_indices(true).nth(n as usize) {
+    Some((end, _)) => s[..end].to_string(),
+    None => s,
+  }
+}
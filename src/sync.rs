@@ -0,0 +1,197 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use napi::{Env, JsObject};
+use tokio::time::Instant;
+
+use crate::error::{self, AppError, Result};
+use crate::shm::named_path;
+
+const MAX_PERMITS: u32 = 1024;
+/// Longest pause between attempts while waiting for a contended lock.
+const MAX_BACKOFF: Duration = Duration::from_millis(20);
+
+#[napi(object)]
+#[derive(Default)]
+pub struct AcquireOptions {
+  /// Give up and reject with `ERR_TIMEOUT` after this long. Defaults to
+  /// waiting forever.
+  pub timeout_ms: Option<u32>,
+}
+
+/// One advisory file lock per permit. The OS releases a lock when its file
+/// is closed, including when the holding process dies, so a crash can
+/// never leave a permit taken. Each attempt opens its own file handle,
+/// which is what makes two holders in one process exclude each other too.
+#[derive(Clone)]
+struct Permits {
+  kind: &'static str,
+  name: String,
+  paths: Vec<PathBuf>,
+}
+
+impl Permits {
+  fn new(kind: &'static str, name: String, permits: u32) -> Result<Self> {
+    let base = named_path(kind, &name)?;
+    let paths = (0..permits)
+      .map(|i| {
+        let mut path = base.clone().into_os_string();
+        path.push(format!(".{}", i));
+        PathBuf::from(path)
+      })
+      .collect();
+    Ok(Permits { kind, name, paths })
+  }
+
+  fn try_acquire(&self) -> Result<Option<LockGuard>> {
+    for path in &self.paths {
+      let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| AppError::io(&path.display().to_string(), e))?;
+      match file.try_lock() {
+        Ok(()) => return Ok(Some(LockGuard { file: Some(file) })),
+        Err(TryLockError::WouldBlock) => continue,
+        Err(TryLockError::Error(e)) => return Err(AppError::io(&path.display().to_string(), e)),
+      }
+    }
+    Ok(None)
+  }
+
+  /// Polls with exponential backoff: the OS has no way to wait on any one
+  /// of several locks, and a blocking wait would pin a thread per waiter.
+  async fn acquire(self, timeout_ms: Option<u32>) -> Result<LockGuard> {
+    let deadline = timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms as u64));
+    let mut backoff = Duration::from_millis(1);
+    loop {
+      if let Some(guard) = self.try_acquire()? {
+        return Ok(guard);
+      }
+      let mut wait = backoff;
+      if let Some(deadline) = deadline {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+          return Err(AppError::Timeout(format!(
+            "{} {:?} not acquired within {} ms",
+            self.kind,
+            self.name,
+            timeout_ms.unwrap_or_default()
+          )));
+        }
+        wait = wait.min(left);
+      }
+      tokio::time::sleep(wait).await;
+      backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+  }
+}
+
+/// A held mutex or semaphore permit. Call `release()` when done; a guard
+/// that is garbage-collected unreleased gives its permit back then, which
+/// may be much later.
+#[napi]
+pub struct LockGuard {
+  file: Option<File>,
+}
+
+#[napi]
+impl LockGuard {
+  /// Gives the permit back. Releasing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn release(&mut self) {
+    self.file = None;
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn released(&self) -> bool {
+    self.file.is_none()
+  }
+}
+
+/// A mutex known by name to every worker thread and process on the
+/// machine: at most one `LockGuard` for a name exists at a time, wherever
+/// it was acquired. Not reentrant; a second `acquire` from the holder
+/// waits like any other.
+#[napi]
+pub struct NamedMutex {
+  permits: Permits,
+}
+
+#[napi]
+impl NamedMutex {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(name: String) -> Result<Self> {
+    Ok(NamedMutex {
+      permits: Permits::new("mutex", name, 1)?,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn name(&self) -> String {
+    self.permits.name.clone()
+  }
+
+  /// Resolves with a guard once the mutex is free.
+  #[napi(catch_unwind, ts_return_type = "Promise<LockGuard>")]
+  pub fn acquire(&self, env: Env, options: Option<AcquireOptions>) -> Result<JsObject> {
+    let timeout_ms = options.unwrap_or_default().timeout_ms;
+    error::spawn(&env, self.permits.clone().acquire(timeout_ms))
+  }
+
+  /// A guard if the mutex is free right now, otherwise `null`.
+  #[napi(catch_unwind)]
+  pub fn try_acquire(&self) -> Result<Option<LockGuard>> {
+    self.permits.try_acquire()
+  }
+}
+
+/// A counting semaphore known by name across worker threads and processes:
+/// at most `permits` guards for a name exist at a time. Every user of a
+/// name must pass the same `permits`.
+#[napi]
+pub struct Semaphore {
+  permits: Permits,
+}
+
+#[napi]
+impl Semaphore {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(name: String, permits: u32) -> Result<Self> {
+    if !(1..=MAX_PERMITS).contains(&permits) {
+      return Err(AppError::InvalidInput(format!(
+        "permits must be 1–{}, got {}",
+        MAX_PERMITS, permits
+      )));
+    }
+    Ok(Semaphore {
+      permits: Permits::new("semaphore", name, permits)?,
+    })
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn name(&self) -> String {
+    self.permits.name.clone()
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn permits(&self) -> u32 {
+    self.permits.paths.len() as u32
+  }
+
+  /// Resolves with a guard once a permit is free.
+  #[napi(catch_unwind, ts_return_type = "Promise<LockGuard>")]
+  pub fn acquire(&self, env: Env, options: Option<AcquireOptions>) -> Result<JsObject> {
+    let timeout_ms = options.unwrap_or_default().timeout_ms;
+    error::spawn(&env, self.permits.clone().acquire(timeout_ms))
+  }
+
+  /// A guard if a permit is free right now, otherwise `null`.
+  #[napi(catch_unwind)]
+  pub fn try_acquire(&self) -> Result<Option<LockGuard>> {
+    self.permits.try_acquire()
+  }
+}
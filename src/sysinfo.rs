@@ -0,0 +1,281 @@
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use ::sysinfo::{Disks, Pid, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+#[napi(object)]
+pub struct CpuCore {
+  pub name: String,
+  /// Marketing name, e.g. `AMD Ryzen 9 7950X 16-Core Processor`.
+  pub brand: String,
+  pub vendor: String,
+  /// MHz.
+  pub frequency: u32,
+  /// Percent busy (0–100) since the previous reading.
+  pub usage: f64,
+}
+
+/// Byte counts.
+#[napi(object)]
+pub struct MemInfo {
+  pub total: i64,
+  /// What can be allocated without swapping, including reclaimable caches.
+  pub available: i64,
+  pub used: i64,
+  pub free: i64,
+  pub swap_total: i64,
+  pub swap_used: i64,
+}
+
+#[napi(object)]
+pub struct DiskUsage {
+  /// Where the filesystem holding the path is mounted.
+  pub mount_point: String,
+  /// Filesystem type, e.g. `ext4`, `apfs`, `NTFS`.
+  pub file_system: String,
+  /// Bytes.
+  pub total: i64,
+  /// Bytes available to unprivileged users.
+  pub available: i64,
+  /// Bytes.
+  pub used: i64,
+  pub removable: bool,
+}
+
+#[napi(object)]
+pub struct ProcessInfo {
+  pub pid: u32,
+  pub parent_pid: Option<u32>,
+  pub name: String,
+  pub exe: Option<String>,
+  /// Command line, including the program itself. Empty when the process
+  /// belongs to another user and the OS hides it.
+  pub cmd: Vec<String>,
+  /// Resident memory in bytes.
+  pub memory: i64,
+  /// Percent of one core (so above 100 for multi-threaded work) since the
+  /// previous listing.
+  pub cpu_usage: f64,
+  /// Unix time in seconds.
+  pub start_time: i64,
+  /// Scheduler state as the OS names it, e.g. `runnable` or `sleeping`.
+  pub status: String,
+}
+
+/// One `System` for the process, so that CPU usage is measured between
+/// consecutive calls rather than over a fresh interval each time.
+struct Monitor {
+  system: System,
+  cpus_primed: bool,
+  processes_primed: bool,
+}
+
+fn monitor() -> MutexGuard<'static, Monitor> {
+  static MONITOR: OnceLock<Mutex<Monitor>> = OnceLock::new();
+  MONITOR
+    .get_or_init(|| {
+      Mutex::new(Monitor {
+        system: System::new(),
+        cpus_primed: false,
+        processes_primed: false,
+      })
+    })
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn cpus() -> Vec<CpuCore> {
+  let mut monitor = monitor();
+  // Usage is a difference between two samples, so the very first reading
+  // waits out the minimum interval.
+  if !monitor.cpus_primed {
+    monitor.system.refresh_cpu_all();
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    monitor.cpus_primed = true;
+  }
+  monitor.system.refresh_cpu_all();
+  monitor
+    .system
+    .cpus()
+    .iter()
+    .map(|cpu| CpuCore {
+      name: cpu.name().to_string(),
+      brand: cpu.brand().trim().to_string(),
+      vendor: cpu.vendor_id().to_string(),
+      frequency: cpu.frequency() as u32,
+      usage: cpu.cpu_usage() as f64,
+    })
+    .collect()
+}
+
+fn processes() -> Vec<ProcessInfo> {
+  let mut monitor = monitor();
+  if !monitor.processes_primed {
+    monitor
+      .system
+      .refresh_processes(ProcessesToUpdate::All, true);
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    monitor.processes_primed = true;
+  }
+  monitor
+    .system
+    .refresh_processes(ProcessesToUpdate::All, true);
+  let mut list: Vec<ProcessInfo> = monitor
+    .system
+    .processes()
+    .values()
+    // Linux reports threads as tasks of their process; skip them.
+    .filter(|p| p.thread_kind().is_none())
+    .map(|p| ProcessInfo {
+      pid: p.pid().as_u32(),
+      parent_pid: p.parent().map(Pid::as_u32),
+      name: p.name().to_string_lossy().into_owned(),
+      exe: p.exe().map(|e| e.to_string_lossy().into_owned()),
+      cmd: p
+        .cmd()
+        .iter()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect(),
+      memory: p.memory() as i64,
+      cpu_usage: p.cpu_usage() as f64,
+      start_time: p.start_time() as i64,
+      status: p.status().to_string().to_lowercase(),
+    })
+    .collect();
+  list.sort_unstable_by_key(|p| p.pid);
+  list
+}
+
+fn disk(path: &str) -> Result<DiskUsage> {
+  let target = std::fs::canonicalize(path).map_err(|e| AppError::io(path, e))?;
+  let disks = Disks::new_with_refreshed_list();
+  // The innermost mount wins, e.g. `/home` over `/`.
+  let disk = disks
+    .iter()
+    .filter(|d| target.starts_with(d.mount_point()))
+    .max_by_key(|d| d.mount_point().components().count())
+    .ok_or_else(|| AppError::NotFound(format!("no mounted filesystem contains {}", path)))?;
+  Ok(DiskUsage {
+    mount_point: disk.mount_point().to_string_lossy().into_owned(),
+    file_system: disk.file_system().to_string_lossy().into_owned(),
+    total: disk.total_space() as i64,
+    available: disk.available_space() as i64,
+    used: disk.total_space().saturating_sub(disk.available_space()) as i64,
+    removable: disk.is_removable(),
+  })
+}
+
+/// Per-core model, clock and utilisation. Utilisation covers the time since
+/// the previous `cpuInfo` call, so poll it at your sampling rate; the first
+/// call blocks for about 200 ms to take a baseline.
+#[napi(catch_unwind)]
+pub fn cpu_info() -> Vec<CpuCore> {
+  cpus()
+}
+
+#[napi(catch_unwind)]
+pub fn memory_info() -> MemInfo {
+  let mut monitor = monitor();
+  monitor.system.refresh_memory();
+  let system = &monitor.system;
+  MemInfo {
+    total: system.total_memory() as i64,
+    available: system.available_memory() as i64,
+    used: system.used_memory() as i64,
+    free: system.free_memory() as i64,
+    swap_total: system.total_swap() as i64,
+    swap_used: system.used_swap() as i64,
+  }
+}
+
+/// Space on the filesystem that holds `path`.
+#[napi(catch_unwind)]
+pub fn disk_usage(path: String) -> Result<DiskUsage> {
+  disk(&path)
+}
+
+/// Every process visible to this user, ordered by pid. CPU usage is measured
+/// since the previous listing, as with `cpuInfo`.
+#[napi(catch_unwind)]
+pub fn list_processes() -> Vec<ProcessInfo> {
+  processes()
+}
+
+/// Whether a process with this pid is running (zombies included).
+#[napi(catch_unwind)]
+pub fn process_exists(pid: u32) -> bool {
+  let mut system = System::new();
+  let pid = Pid::from_u32(pid);
+  system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+  system.process(pid).is_some()
+}
+
+pub struct CpuInfoTask;
+
+impl Task for CpuInfoTask {
+  type Output = Vec<CpuCore>;
+  type JsValue = Vec<CpuCore>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(cpus())
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct DiskUsageTask {
+  path: String,
+}
+
+impl Task for DiskUsageTask {
+  type Output = Result<DiskUsage>;
+  type JsValue = DiskUsage;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(disk(&self.path))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+pub struct ListProcessesTask;
+
+impl Task for ListProcessesTask {
+  type Output = Vec<ProcessInfo>;
+  type JsValue = Vec<ProcessInfo>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(processes())
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// `cpuInfo` on the libuv thread pool, which keeps the first call's 200 ms
+/// baseline off the main thread.
+#[napi(catch_unwind, ts_return_type = "Promise<CpuCore[]>")]
+pub fn cpu_info_async() -> AsyncTask<Guarded<CpuInfoTask>> {
+  panic_guard::task(CpuInfoTask)
+}
+
+#[napi(catch_unwind, ts_return_type = "Promise<DiskUsage>")]
+pub fn disk_usage_async(path: String) -> AsyncTask<Guarded<DiskUsageTask>> {
+  panic_guard::task(DiskUsageTask { path })
+}
+
+/// `listProcesses` on the libuv thread pool; reading every process's stats
+/// can take tens of milliseconds on a busy host.
+#[napi(catch_unwind, ts_return_type = "Promise<ProcessInfo[]>")]
+pub fn list_processes_async() -> AsyncTask<Guarded<ListProcessesTask>> {
+  panic_guard::task(ListProcessesTask)
+}
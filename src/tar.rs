@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// One file (or directory tree) to put in a tarball.
+#[napi(object)]
+pub struct TarEntry {
+  /// Path inside the archive.
+  pub name: String,
+  /// File contents. Exactly one of `data` and `path` must be set.
+  pub data: Option<Either<String, Buffer>>,
+  /// File or directory on disk to add; directories are added recursively.
+  /// Permissions and mtimes are taken from disk unless overridden.
+  pub path: Option<String>,
+  /// Unix permission bits, e.g. `0o755`. Defaults to `0o644` for `data`.
+  pub mode: Option<u32>,
+  /// Modification time in milliseconds since the epoch. Defaults to now for
+  /// `data`.
+  pub mtime_ms: Option<f64>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct TarOptions {
+  /// Gzip the archive. Defaults to `true`.
+  pub gzip: Option<bool>,
+  /// Gzip level 0–9 (default 6).
+  pub level: Option<u32>,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn now_secs() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn append(builder: &mut ::tar::Builder<Vec<u8>>, entry: &TarEntry) -> Result<()> {
+  let mtime = entry.mtime_ms.map(|ms| (ms / 1000.0).max(0.0) as u64);
+  match (&entry.data, &entry.path) {
+    (Some(data), None) => {
+      let bytes = crate::hash::input_bytes(data);
+      let mut header = ::tar::Header::new_gnu();
+      header.set_size(bytes.len() as u64);
+      header.set_mode(entry.mode.unwrap_or(0o644));
+      header.set_mtime(mtime.unwrap_or_else(now_secs));
+      header.set_entry_type(::tar::EntryType::Regular);
+      builder.append_data(&mut header, &entry.name, bytes)?;
+    }
+    (None, Some(path)) => {
+      let metadata = std::fs::metadata(path).map_err(|e| AppError::io(path, e))?;
+      if metadata.is_dir() {
+        builder.append_dir_all(&entry.name, path)?;
+      } else {
+        let mut header = ::tar::Header::new_gnu();
+        header.set_metadata(&metadata);
+        if let Some(mode) = entry.mode {
+          header.set_mode(mode);
+        }
+        if let Some(mtime) = mtime {
+          header.set_mtime(mtime);
+        }
+        let file = File::open(path).map_err(|e| AppError::io(path, e))?;
+        builder.append_data(&mut header, &entry.name, BufReader::new(file))?;
+      }
+    }
+    _ => {
+      return Err(AppError::InvalidInput(format!(
+        "tar entry {:?} needs exactly one of `data` or `path`",
+        entry.name
+      )))
+    }
+  }
+  Ok(())
+}
+
+pub struct CreateTarTask {
+  files: Vec<TarEntry>,
+  opts: TarOptions,
+}
+
+impl CreateTarTask {
+  fn run(&self) -> Result<Vec<u8>> {
+    let mut builder = ::tar::Builder::new(Vec::new());
+    builder.mode(::tar::HeaderMode::Complete);
+    for entry in &self.files {
+      append(&mut builder, entry)?;
+    }
+    let tar = builder.into_inner()?;
+    if !self.opts.gzip.unwrap_or(true) {
+      return Ok(tar);
+    }
+    let level = Compression::new(self.opts.level.unwrap_or(6).min(9));
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    std::io::Write::write_all(&mut encoder, &tar)?;
+    Ok(encoder.finish()?)
+  }
+}
+
+impl Task for CreateTarTask {
+  type Output = Result<Vec<u8>>;
+  type JsValue = Buffer;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map(Buffer::from).map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Builds a tar archive (gzipped by default) on the thread pool.
+#[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+pub fn create_tarball(
+  files: Vec<TarEntry>,
+  opts: Option<TarOptions>,
+) -> AsyncTask<Guarded<CreateTarTask>> {
+  panic_guard::task(CreateTarTask {
+    files,
+    opts: opts.unwrap_or_default(),
+  })
+}
+
+pub struct ExtractTarTask {
+  input: Either<String, Buffer>,
+  dest: String,
+}
+
+impl ExtractTarTask {
+  fn run(&self) -> Result<Vec<String>> {
+    let reader: Box<dyn Read> = match &self.input {
+      Either::A(path) => {
+        let file = File::open(path).map_err(|e| AppError::io(path, e))?;
+        Box::new(BufReader::new(file))
+      }
+      Either::B(buffer) => Box::new(Cursor::new(buffer.to_vec())),
+    };
+    // Sniff the gzip magic so callers don't have to say which they have.
+    let mut reader = BufReader::new(reader);
+    let gzipped = std::io::BufRead::fill_buf(&mut reader)?.starts_with(&GZIP_MAGIC);
+    let reader: Box<dyn Read> = if gzipped {
+      Box::new(GzDecoder::new(reader))
+    } else {
+      Box::new(reader)
+    };
+
+    let mut archive = ::tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_mtime(true);
+    std::fs::create_dir_all(&self.dest).map_err(|e| AppError::io(&self.dest, e))?;
+
+    let mut extracted = Vec::new();
+    for entry in archive.entries().map_err(tar_error)? {
+      let mut entry = entry.map_err(tar_error)?;
+      let name = entry
+        .path()
+        .map_err(tar_error)?
+        .to_string_lossy()
+        .into_owned();
+      // `unpack_in` refuses paths that would escape `dest`.
+      if entry.unpack_in(Path::new(&self.dest)).map_err(tar_error)? {
+        extracted.push(name);
+      }
+    }
+    Ok(extracted)
+  }
+}
+
+/// Reading a truncated or corrupt archive surfaces as an I/O error from the
+/// tar crate; report those as parse errors.
+fn tar_error(e: std::io::Error) -> AppError {
+  match e.kind() {
+    std::io::ErrorKind::UnexpectedEof
+    | std::io::ErrorKind::InvalidData
+    | std::io::ErrorKind::Other => AppError::Parse(format!("invalid tar archive: {}", e)),
+    _ => AppError::from(e),
+  }
+}
+
+impl Task for ExtractTarTask {
+  type Output = Result<Vec<String>>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// Extracts a tar or tar.gz (from a path or Buffer) into `dest`, keeping
+/// permissions and mtimes. Resolves with the archive paths written.
+#[napi(catch_unwind, ts_return_type = "Promise<string[]>")]
+pub fn extract_tarball(
+  input: Either<String, Buffer>,
+  dest: String,
+) -> AsyncTask<Guarded<ExtractTarTask>> {
+  panic_guard::task(ExtractTarTask { input, dest })
+}
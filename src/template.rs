@@ -0,0 +1,114 @@
+use handlebars::{Handlebars, RenderError, RenderErrorReason, TemplateError};
+use napi::{Env, JsUnknown};
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::json::from_js;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct TemplateOptions {
+  /// Fail the render when a template references a missing field, instead
+  /// of printing nothing. Defaults to `false`.
+  pub strict: Option<bool>,
+  /// Skip HTML-escaping of `{{value}}` output, e.g. for plain-text email.
+  /// `{{{value}}}` is never escaped. Defaults to `false`.
+  pub no_escape: Option<bool>,
+}
+
+/// Reports where parsing stopped, with the offending line and a caret under
+/// the column.
+fn template_error(name: &str, source: &str, e: TemplateError) -> AppError {
+  match e.pos() {
+    Some((line, column)) => {
+      let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+      AppError::Parse(format!(
+        "invalid template {:?} at line {}, column {}: {}\n  {}\n  {}^",
+        name,
+        line,
+        column,
+        e.reason(),
+        text,
+        " ".repeat(column.saturating_sub(1))
+      ))
+    }
+    None => AppError::Parse(format!("invalid template {:?}: {}", name, e.reason())),
+  }
+}
+
+fn render_error(e: RenderError) -> AppError {
+  match e.reason() {
+    RenderErrorReason::TemplateNotFound(name) => {
+      AppError::NotFound(format!("no template named {:?}", name))
+    }
+    RenderErrorReason::PartialNotFound(name) => {
+      AppError::NotFound(format!("no partial named {:?}", name))
+    }
+    _ => AppError::InvalidInput(e.to_string()),
+  }
+}
+
+/// Handlebars templates compiled once and rendered many times.
+///
+/// Supports the usual Handlebars syntax: `{{field.path}}`, `{{{raw}}}`,
+/// `{{#if}}`/`{{#unless}}`/`{{#each}}`/`{{#with}}` blocks, the built-in
+/// comparison helpers (`eq`, `gt`, `and`, …) and `{{> partial}}`.
+#[napi]
+pub struct TemplateEngine {
+  registry: Handlebars<'static>,
+}
+
+#[napi]
+impl TemplateEngine {
+  #[napi(catch_unwind, constructor)]
+  pub fn new(options: Option<TemplateOptions>) -> Self {
+    let options = options.unwrap_or_default();
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(options.strict.unwrap_or(false));
+    if options.no_escape.unwrap_or(false) {
+      registry.register_escape_fn(handlebars::no_escape);
+    }
+    TemplateEngine { registry }
+  }
+
+  /// Compiles `source` and stores it as `name`, replacing any template
+  /// already registered under that name. Syntax errors throw `ERR_PARSE`
+  /// with the line, column and offending source line.
+  #[napi(catch_unwind)]
+  pub fn register_template(&mut self, name: String, source: String) -> Result<()> {
+    self
+      .registry
+      .register_template_string(&name, &source)
+      .map_err(|e| template_error(&name, &source, e))
+  }
+
+  /// Compiles `source` as a partial, usable from other templates as
+  /// `{{> name}}`.
+  #[napi(catch_unwind)]
+  pub fn register_partial(&mut self, name: String, source: String) -> Result<()> {
+    self
+      .registry
+      .register_partial(&name, &source)
+      .map_err(|e| template_error(&name, &source, e))
+  }
+
+  #[napi(catch_unwind)]
+  pub fn has_template(&self, name: String) -> bool {
+    self.registry.has_template(&name)
+  }
+
+  /// Renders the template registered as `name` with `data` as its context.
+  /// An unknown template or partial throws `ERR_NOT_FOUND`; a missing field
+  /// in strict mode throws `ERR_INVALID_INPUT`.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "name: string, data?: Record<string, unknown>"
+  )]
+  pub fn render(&self, env: Env, name: String, data: Option<JsUnknown>) -> Result<String> {
+    let data = match data {
+      Some(data) => from_js(&env, data)?,
+      None => Value::Null,
+    };
+    self.registry.render(&name, &data).map_err(render_error)
+  }
+}
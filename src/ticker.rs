@@ -0,0 +1,97 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsFunction};
+
+use crate::error::Result;
+use crate::lifecycle::{self, Resource};
+
+struct Ticker {
+  stop_tx: Option<Sender<()>>,
+  thread: Option<JoinHandle<()>>,
+}
+
+/// Shared with the lifecycle registry so `shutdown()` can stop the ticker.
+struct TickerState(Mutex<Ticker>);
+
+impl TickerState {
+  fn lock(&self) -> MutexGuard<'_, Ticker> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn stop(&self) {
+    let thread = {
+      let mut ticker = self.lock();
+      // Dropping the sender wakes the thread out of `recv_timeout`.
+      ticker.stop_tx.take();
+      ticker.thread.take()
+    };
+    if let Some(thread) = thread {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Resource for TickerState {
+  fn kind(&self) -> &'static str {
+    "ticker"
+  }
+
+  fn active(&self) -> bool {
+    self.lock().thread.is_some()
+  }
+
+  fn shutdown(&self) {
+    self.stop();
+  }
+}
+
+/// Handle returned by `startTicker`. Dropping it (or calling `stop`) ends the
+/// background thread.
+#[napi]
+pub struct TickerHandle {
+  state: Arc<TickerState>,
+}
+
+#[napi]
+impl TickerHandle {
+  /// Stops the ticker and waits for the background thread to exit. No more
+  /// callbacks are queued once this returns.
+  #[napi(catch_unwind)]
+  pub fn stop(&mut self) {
+    self.state.stop();
+  }
+
+  #[napi(catch_unwind, getter)]
+  pub fn running(&self) -> bool {
+    self.state.active()
+  }
+}
+
+/// Calls `callback(tick)` every `interval_ms` milliseconds from a background
+/// Rust thread, where `tick` counts up from 1.
+#[napi(catch_unwind)]
+pub fn start_ticker(env: Env, interval_ms: u32, callback: JsFunction) -> Result<TickerHandle> {
+  let tsfn: ThreadsafeFunction<u32, ErrorStrategy::Fatal> =
+    callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+  let interval = Duration::from_millis(interval_ms.max(1) as u64);
+  let (stop_tx, stop_rx) = mpsc::channel::<()>();
+
+  let thread = thread::spawn(move || {
+    let mut tick = 0u32;
+    while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+      tick = tick.wrapping_add(1);
+      tsfn.call(tick, ThreadsafeFunctionCallMode::NonBlocking);
+    }
+  });
+
+  let state = Arc::new(TickerState(Mutex::new(Ticker {
+    stop_tx: Some(stop_tx),
+    thread: Some(thread),
+  })));
+  lifecycle::register(&env, &state)?;
+  Ok(TickerHandle { state })
+}
@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use rustc_hash::FxHashMap;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer as Encoding};
+use tiktoken_rs::CoreBPE;
+
+use crate::error::{AppError, Result};
+
+/// The `cl100k_base` pre-tokenization pattern, used by `fromFile` when no
+/// pattern is given.
+const DEFAULT_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+#[napi(object)]
+#[derive(Default)]
+pub struct TokenizerFileOptions {
+  /// Regex that splits text into pieces before BPE merging. Defaults to the
+  /// `cl100k_base` pattern.
+  pub pattern: Option<String>,
+  /// Special tokens such as `<|endoftext|>` and their ids.
+  pub special_tokens: Option<HashMap<String, u32>>,
+}
+
+enum Bpe {
+  /// The built-in encodings are loaded once per process and shared.
+  Shared(&'static CoreBPE),
+  Owned(Box<CoreBPE>),
+}
+
+/// A byte-pair-encoding tokenizer compatible with OpenAI's `tiktoken`.
+#[napi]
+pub struct Tokenizer {
+  bpe: Bpe,
+  name: String,
+}
+
+fn encoding_name(encoding: Encoding) -> &'static str {
+  match encoding {
+    Encoding::O200kBase => "o200k_base",
+    Encoding::Cl100kBase => "cl100k_base",
+    Encoding::P50kBase => "p50k_base",
+    Encoding::P50kEdit => "p50k_edit",
+    Encoding::R50kBase | Encoding::Gpt2 => "r50k_base",
+  }
+}
+
+#[napi]
+impl Tokenizer {
+  /// Loads a built-in encoding — `o200k_base`, `cl100k_base`, `p50k_base`,
+  /// `p50k_edit` or `r50k_base` — or the encoding used by a model name such
+  /// as `gpt-4o` or `gpt-3.5-turbo`. The vocabularies ship inside the addon,
+  /// so no network access is needed.
+  #[napi(catch_unwind, factory)]
+  pub fn from_pretrained(name: String) -> Result<Self> {
+    let encoding = match name.as_str() {
+      "o200k_base" => Encoding::O200kBase,
+      "cl100k_base" => Encoding::Cl100kBase,
+      "p50k_base" => Encoding::P50kBase,
+      "p50k_edit" => Encoding::P50kEdit,
+      "r50k_base" => Encoding::R50kBase,
+      model => get_tokenizer(model)
+        .ok_or_else(|| AppError::NotFound(format!("unknown encoding or model: {}", model)))?,
+    };
+    let bpe = match encoding {
+      Encoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+      Encoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+      Encoding::P50kBase => tiktoken_rs::p50k_base_singleton(),
+      Encoding::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+      Encoding::R50kBase | Encoding::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    };
+    Ok(Tokenizer {
+      bpe: Bpe::Shared(bpe),
+      name: encoding_name(encoding).to_string(),
+    })
+  }
+
+  /// Loads a vocabulary in `tiktoken` format: one base64-encoded token and
+  /// its rank per line, covering at least every single byte.
+  #[napi(catch_unwind, factory)]
+  pub fn from_file(path: String, options: Option<TokenizerFileOptions>) -> Result<Self> {
+    let options = options.unwrap_or_default();
+    let text = std::fs::read_to_string(&path).map_err(|e| AppError::io(&path, e))?;
+    let mut encoder = FxHashMap::default();
+    let mut ranks = HashSet::new();
+    for (n, line) in text
+      .lines()
+      .enumerate()
+      .filter(|(_, l)| !l.trim().is_empty())
+    {
+      let invalid = || {
+        AppError::Parse(format!(
+          "{}:{}: expected '<base64 token> <rank>'",
+          path,
+          n + 1
+        ))
+      };
+      let (token, rank) = line.trim().split_once(' ').ok_or_else(invalid)?;
+      let token = base64_simd::STANDARD
+        .decode_to_vec(token)
+        .map_err(|_| invalid())?;
+      let rank: u32 = rank.trim().parse().map_err(|_| invalid())?;
+      if !ranks.insert(rank) || encoder.insert(token, rank).is_some() {
+        return Err(AppError::Parse(format!(
+          "{}:{}: duplicate token or rank {}",
+          path,
+          n + 1,
+          rank
+        )));
+      }
+    }
+    // BPE starts from single bytes, so text containing a byte with no token
+    // could not be encoded.
+    if let Some(byte) = (0..=255u8).find(|b| !encoder.contains_key(&[*b][..])) {
+      return Err(AppError::Parse(format!(
+        "{}: vocabulary has no token for byte 0x{:02x}",
+        path, byte
+      )));
+    }
+    let mut special = FxHashMap::default();
+    for (token, rank) in options.special_tokens.unwrap_or_default() {
+      if ranks.contains(&rank) {
+        return Err(AppError::InvalidInput(format!(
+          "special token {:?} reuses rank {}",
+          token, rank
+        )));
+      }
+      special.insert(token, rank);
+    }
+    let pattern = options.pattern.as_deref().unwrap_or(DEFAULT_PATTERN);
+    let bpe = CoreBPE::new(encoder, special, pattern)
+      .map_err(|e| AppError::InvalidInput(format!("invalid tokenizer pattern: {}", e)))?;
+    Ok(Tokenizer {
+      bpe: Bpe::Owned(Box::new(bpe)),
+      name: path,
+    })
+  }
+
+  /// The encoding name, or the file path for `fromFile` tokenizers.
+  #[napi(catch_unwind, getter)]
+  pub fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  /// Encodes `text` as token ids. Special tokens such as `<|endoftext|>` are
+  /// treated as plain text unless `allowSpecial` is set.
+  #[napi(catch_unwind)]
+  pub fn encode(&self, text: String, allow_special: Option<bool>) -> Vec<u32> {
+    if allow_special.unwrap_or(false) {
+      self.bpe().encode_with_special_tokens(&text)
+    } else {
+      self.bpe().encode_ordinary(&text)
+    }
+  }
+
+  /// Decodes token ids back to text. Unknown ids, and slices that split a
+  /// multi-byte character, throw `ERR_INVALID_INPUT`.
+  #[napi(catch_unwind)]
+  pub fn decode(&self, tokens: Vec<u32>) -> Result<String> {
+    self
+      .bpe()
+      .decode(tokens)
+      .map_err(|e| AppError::InvalidInput(e.to_string()))
+  }
+
+  /// Same as `encode(text).length`, without copying the ids into a JS array.
+  #[napi(catch_unwind)]
+  pub fn count_tokens(&self, text: String) -> u32 {
+    self.bpe().encode_ordinary(&text).len() as u32
+  }
+
+  fn bpe(&self) -> &CoreBPE {
+    match &self.bpe {
+      Bpe::Shared(bpe) => bpe,
+      Bpe::Owned(bpe) => bpe,
+    }
+  }
+}
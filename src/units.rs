@@ -0,0 +1,420 @@
+use std::str::FromStr;
+
+use napi::bindgen_prelude::*;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct FormatBytesOptions {
+  /// Use SI units (`kB`, `MB`, powers of 1000) instead of IEC units
+  /// (`KiB`, `MiB`, powers of 1024). Defaults to `false`.
+  pub si: Option<bool>,
+  /// Most digits after the point; trailing zeros are dropped. Defaults
+  /// to 1.
+  pub decimals: Option<u32>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct FormatDurationOptions {
+  /// Spell units out (`2 hours 30 minutes`) instead of `2h 30m`.
+  pub long: Option<bool>,
+  /// Show at most this many units, largest first; the rest are truncated.
+  pub max_units: Option<u32>,
+}
+
+type ByteCount = Either<f64, BigInt>;
+
+const IEC_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const SI_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+
+fn bigint_to_f64(value: &BigInt) -> f64 {
+  let magnitude = value
+    .words
+    .iter()
+    .rev()
+    .fold(0.0, |acc, &word| acc * 18446744073709551616.0 + word as f64);
+  if value.sign_bit {
+    -magnitude
+  } else {
+    magnitude
+  }
+}
+
+/// `value` with at most `decimals` digits after the point and no trailing
+/// zeros.
+fn trimmed(value: f64, decimals: usize) -> String {
+  let text = format!("{:.*}", decimals, value);
+  if text.contains('.') {
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+  } else {
+    text
+  }
+}
+
+fn format_byte_count(bytes: f64, si: bool, decimals: usize) -> String {
+  let (base, units) = if si {
+    (1000.0, SI_UNITS)
+  } else {
+    (1024.0, IEC_UNITS)
+  };
+  let sign = if bytes < 0.0 { "-" } else { "" };
+  let mut value = bytes.abs();
+  let mut unit = 0;
+  while value >= base && unit < units.len() - 1 {
+    value /= base;
+    unit += 1;
+  }
+  // Rounding can carry into the next unit: 1023.95 KiB is 1 MiB.
+  let mut text = trimmed(value, if unit == 0 { 0 } else { decimals });
+  if text.parse::<f64>().unwrap_or(0.0) >= base && unit < units.len() - 1 {
+    unit += 1;
+    text = trimmed(value / base, decimals);
+  }
+  format!("{}{} {}", sign, text, units[unit])
+}
+
+/// Formats a byte count for display, e.g. `1.5 KiB` or, with `si`, `1.5 kB`.
+/// Pass an array to format a whole column in one call.
+#[napi(
+  catch_unwind,
+  ts_args_type = "bytes: number | bigint | Array<number | bigint>, options?: FormatBytesOptions",
+  ts_return_type = "string | string[]"
+)]
+pub fn format_bytes(
+  bytes: Either3<f64, BigInt, Vec<ByteCount>>,
+  options: Option<FormatBytesOptions>,
+) -> Either<String, Vec<String>> {
+  let options = options.unwrap_or_default();
+  let si = options.si.unwrap_or(false);
+  let decimals = options.decimals.unwrap_or(1).min(20) as usize;
+  let format = |bytes: f64| format_byte_count(bytes, si, decimals);
+  match bytes {
+    Either3::A(bytes) => Either::A(format(bytes)),
+    Either3::B(bytes) => Either::A(format(bigint_to_f64(&bytes))),
+    Either3::C(column) => Either::B(
+      column
+        .iter()
+        .map(|bytes| match bytes {
+          Either::A(bytes) => format(*bytes),
+          Either::B(bytes) => format(bigint_to_f64(bytes)),
+        })
+        .collect(),
+    ),
+  }
+}
+
+fn byte_multiplier(unit: &str) -> Option<u64> {
+  let power = |base: u64, exp: u32| base.pow(exp);
+  Some(match unit.to_ascii_lowercase().as_str() {
+    "" | "b" | "byte" | "bytes" => 1,
+    "k" | "kib" => power(1024, 1),
+    "m" | "mib" => power(1024, 2),
+    "g" | "gib" => power(1024, 3),
+    "t" | "tib" => power(1024, 4),
+    "p" | "pib" => power(1024, 5),
+    "e" | "eib" => power(1024, 6),
+    "kb" => power(1000, 1),
+    "mb" => power(1000, 2),
+    "gb" => power(1000, 3),
+    "tb" => power(1000, 4),
+    "pb" => power(1000, 5),
+    "eb" => power(1000, 6),
+    _ => return None,
+  })
+}
+
+/// Parses a size such as `1.5 GiB`, `10MB` or `512`. Units are
+/// case-insensitive: `KiB`…`EiB` and bare `K`…`E` are powers of 1024, and
+/// `kB`…`EB` powers of 1000. Fractional bytes round to the nearest byte.
+/// Anything else throws `ERR_PARSE`.
+#[napi(catch_unwind)]
+pub fn parse_bytes(text: String) -> Result<BigInt> {
+  let invalid = || AppError::Parse(format!("invalid byte size {:?}", text));
+  let trimmed = text.trim();
+  let split = trimmed
+    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+    .unwrap_or(trimmed.len());
+  let (number, unit) = trimmed.split_at(split);
+  let number = Decimal::from_str(number).map_err(|_| invalid())?;
+  let multiplier = byte_multiplier(unit.trim()).ok_or_else(invalid)?;
+  let bytes = number
+    .checked_mul(Decimal::from(multiplier))
+    .ok_or_else(|| AppError::Overflow(format!("{:?} is too large", text)))?
+    .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+  Ok(BigInt::from(bytes.to_u128().ok_or_else(invalid)?))
+}
+
+/// Duration units, largest first: (short, singular, plural, milliseconds).
+const DURATION_UNITS: [(&str, &str, &str, f64); 5] = [
+  ("d", "day", "days", 86_400_000.0),
+  ("h", "hour", "hours", 3_600_000.0),
+  ("m", "minute", "minutes", 60_000.0),
+  ("s", "second", "seconds", 1_000.0),
+  ("ms", "millisecond", "milliseconds", 1.0),
+];
+
+fn format_ms(ms: f64, long: bool, max_units: usize) -> String {
+  if !ms.is_finite() {
+    return ms.to_string();
+  }
+  let sign = if ms < 0.0 { "-" } else { "" };
+  let name = |&(short, singular, plural, _): &(&str, &str, &str, f64), n: &str| {
+    if !long {
+      format!("{}{}", n, short)
+    } else if n == "1" {
+      format!("{} {}", n, singular)
+    } else {
+      format!("{} {}", n, plural)
+    }
+  };
+  let magnitude = ms.abs();
+  // Below a millisecond there is nothing to split; keep the fraction.
+  if magnitude < 1.0 {
+    return format!(
+      "{}{}",
+      sign,
+      name(&DURATION_UNITS[4], &trimmed(magnitude, 3))
+    );
+  }
+  let mut left = magnitude.round() as u64;
+  let mut parts = Vec::new();
+  for unit in &DURATION_UNITS {
+    let size = unit.3 as u64;
+    let count = left / size;
+    left %= size;
+    if count > 0 && parts.len() < max_units {
+      parts.push(name(unit, &count.to_string()));
+    }
+  }
+  format!("{}{}", sign, parts.join(" "))
+}
+
+/// Formats milliseconds as `1d 2h 3m 4s 5ms`, leaving out zero units, or
+/// with `long` as `1 day 2 hours …`. Pass an array to format a whole column
+/// in one call.
+#[napi(
+  catch_unwind,
+  ts_args_type = "ms: number | number[], options?: FormatDurationOptions",
+  ts_return_type = "string | string[]"
+)]
+pub fn format_duration(
+  ms: Either<f64, Vec<f64>>,
+  options: Option<FormatDurationOptions>,
+) -> Either<String, Vec<String>> {
+  let options = options.unwrap_or_default();
+  let long = options.long.unwrap_or(false);
+  let max_units = options.max_units.map_or(usize::MAX, |n| n.max(1) as usize);
+  match ms {
+    Either::A(ms) => Either::A(format_ms(ms, long, max_units)),
+    Either::B(column) => Either::B(
+      column
+        .into_iter()
+        .map(|ms| format_ms(ms, long, max_units))
+        .collect(),
+    ),
+  }
+}
+
+fn duration_unit_ms(unit: &str) -> Option<f64> {
+  Some(match unit.to_ascii_lowercase().as_str() {
+    "w" | "wk" | "wks" | "week" | "weeks" => 604_800_000.0,
+    "d" | "day" | "days" => 86_400_000.0,
+    "h" | "hr" | "hrs" | "hour" | "hours" => 3_600_000.0,
+    "m" | "min" | "mins" | "minute" | "minutes" => 60_000.0,
+    "s" | "sec" | "secs" | "second" | "seconds" => 1_000.0,
+    "ms" | "msec" | "millisecond" | "milliseconds" => 1.0,
+    "us" | "µs" | "microsecond" | "microseconds" => 1e-3,
+    "ns" | "nanosecond" | "nanoseconds" => 1e-6,
+    _ => return None,
+  })
+}
+
+/// Parses a duration such as `2h30m`, `1.5 hours`, `1d 4h` or `-90s` into
+/// milliseconds. Units run from `w` (weeks) down to `ns`; a bare number is
+/// taken as milliseconds. Anything else throws `ERR_PARSE`.
+#[napi(catch_unwind)]
+pub fn parse_duration(text: String) -> Result<f64> {
+  let invalid = |reason: &str| AppError::Parse(format!("invalid duration {:?}: {}", text, reason));
+  let mut rest = text.trim();
+  let negative = rest.starts_with('-');
+  rest = rest
+    .strip_prefix('-')
+    .or_else(|| rest.strip_prefix('+'))
+    .unwrap_or(rest);
+  if rest.is_empty() {
+    return Err(invalid("empty"));
+  }
+  if let Ok(ms) = rest.parse::<f64>() {
+    return Ok(if negative { -ms } else { ms });
+  }
+  let mut total = 0.0;
+  while !rest.is_empty() {
+    let number_len = rest
+      .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+      .unwrap_or(rest.len());
+    let number: f64 = rest[..number_len]
+      .parse()
+      .map_err(|_| invalid("expected a number"))?;
+    rest = rest[number_len..].trim_start();
+    let unit_len = rest
+      .find(|c: char| !c.is_alphabetic())
+      .unwrap_or(rest.len());
+    let unit = &rest[..unit_len];
+    let size = duration_unit_ms(unit).ok_or_else(|| {
+      if unit.is_empty() {
+        invalid("expected a unit after each number")
+      } else {
+        invalid(&format!("unknown unit {:?}", unit))
+      }
+    })?;
+    total += number * size;
+    rest = rest[unit_len..].trim_start_matches([' ', ',']);
+  }
+  Ok(if negative { -total } else { total })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dimension {
+  Length,
+  Mass,
+  Time,
+  Volume,
+  Area,
+  Speed,
+  Temperature,
+  Data,
+}
+
+impl Dimension {
+  fn name(self) -> &'static str {
+    match self {
+      Dimension::Length => "length",
+      Dimension::Mass => "mass",
+      Dimension::Time => "time",
+      Dimension::Volume => "volume",
+      Dimension::Area => "area",
+      Dimension::Speed => "speed",
+      Dimension::Temperature => "temperature",
+      Dimension::Data => "data",
+    }
+  }
+}
+
+/// (symbol, dimension, factor, offset): a value `v` in the unit is
+/// `v * factor + offset` in the dimension's base unit (m, kg, s, m³, m²,
+/// m/s, K, bytes).
+const UNITS: &[(&str, Dimension, f64, f64)] = &[
+  ("m", Dimension::Length, 1.0, 0.0),
+  ("km", Dimension::Length, 1e3, 0.0),
+  ("cm", Dimension::Length, 1e-2, 0.0),
+  ("mm", Dimension::Length, 1e-3, 0.0),
+  ("um", Dimension::Length, 1e-6, 0.0),
+  ("nm", Dimension::Length, 1e-9, 0.0),
+  ("mi", Dimension::Length, 1609.344, 0.0),
+  ("yd", Dimension::Length, 0.9144, 0.0),
+  ("ft", Dimension::Length, 0.3048, 0.0),
+  ("in", Dimension::Length, 0.0254, 0.0),
+  ("nmi", Dimension::Length, 1852.0, 0.0),
+  ("kg", Dimension::Mass, 1.0, 0.0),
+  ("g", Dimension::Mass, 1e-3, 0.0),
+  ("mg", Dimension::Mass, 1e-6, 0.0),
+  ("t", Dimension::Mass, 1e3, 0.0),
+  ("lb", Dimension::Mass, 0.45359237, 0.0),
+  ("oz", Dimension::Mass, 0.028349523125, 0.0),
+  ("st", Dimension::Mass, 6.35029318, 0.0),
+  ("ns", Dimension::Time, 1e-9, 0.0),
+  ("us", Dimension::Time, 1e-6, 0.0),
+  ("ms", Dimension::Time, 1e-3, 0.0),
+  ("s", Dimension::Time, 1.0, 0.0),
+  ("min", Dimension::Time, 60.0, 0.0),
+  ("h", Dimension::Time, 3600.0, 0.0),
+  ("d", Dimension::Time, 86400.0, 0.0),
+  ("wk", Dimension::Time, 604800.0, 0.0),
+  ("m3", Dimension::Volume, 1.0, 0.0),
+  ("l", Dimension::Volume, 1e-3, 0.0),
+  ("ml", Dimension::Volume, 1e-6, 0.0),
+  ("gal", Dimension::Volume, 3.785411784e-3, 0.0),
+  ("qt", Dimension::Volume, 9.46352946e-4, 0.0),
+  ("pt", Dimension::Volume, 4.73176473e-4, 0.0),
+  ("cup", Dimension::Volume, 2.365882365e-4, 0.0),
+  ("floz", Dimension::Volume, 2.95735295625e-5, 0.0),
+  ("m2", Dimension::Area, 1.0, 0.0),
+  ("km2", Dimension::Area, 1e6, 0.0),
+  ("cm2", Dimension::Area, 1e-4, 0.0),
+  ("ha", Dimension::Area, 1e4, 0.0),
+  ("acre", Dimension::Area, 4046.8564224, 0.0),
+  ("ft2", Dimension::Area, 0.09290304, 0.0),
+  ("mi2", Dimension::Area, 2589988.110336, 0.0),
+  ("m/s", Dimension::Speed, 1.0, 0.0),
+  ("km/h", Dimension::Speed, 1.0 / 3.6, 0.0),
+  ("mph", Dimension::Speed, 0.44704, 0.0),
+  ("kn", Dimension::Speed, 1852.0 / 3600.0, 0.0),
+  ("ft/s", Dimension::Speed, 0.3048, 0.0),
+  ("K", Dimension::Temperature, 1.0, 0.0),
+  ("C", Dimension::Temperature, 1.0, 273.15),
+  ("F", Dimension::Temperature, 5.0 / 9.0, 459.67 * 5.0 / 9.0),
+  ("b", Dimension::Data, 0.125, 0.0),
+  ("B", Dimension::Data, 1.0, 0.0),
+  ("kB", Dimension::Data, 1e3, 0.0),
+  ("MB", Dimension::Data, 1e6, 0.0),
+  ("GB", Dimension::Data, 1e9, 0.0),
+  ("TB", Dimension::Data, 1e12, 0.0),
+  ("PB", Dimension::Data, 1e15, 0.0),
+  ("KiB", Dimension::Data, 1024.0, 0.0),
+  ("MiB", Dimension::Data, 1048576.0, 0.0),
+  ("GiB", Dimension::Data, 1073741824.0, 0.0),
+  ("TiB", Dimension::Data, 1099511627776.0, 0.0),
+  ("PiB", Dimension::Data, 1125899906842624.0, 0.0),
+];
+
+fn unit(symbol: &str) -> Result<(Dimension, f64, f64)> {
+  UNITS
+    .iter()
+    .find(|(name, ..)| *name == symbol)
+    .map(|&(_, dimension, factor, offset)| (dimension, factor, offset))
+    .ok_or_else(|| AppError::InvalidInput(format!("unknown unit {:?}", symbol)))
+}
+
+/// Converts `value` from one unit to another of the same kind, e.g.
+/// `convert(26.2, 'mi', 'km')` or `convert(temps, 'F', 'C')` with a
+/// `Float64Array` of values.
+///
+/// Units are case-sensitive: length `m km cm mm um nm mi yd ft in nmi`,
+/// mass `kg g mg t lb oz st`, time `ns us ms s min h d wk`, volume
+/// `m3 l ml gal qt pt cup floz` (US customary), area
+/// `m2 km2 cm2 ha acre ft2 mi2`, speed `m/s km/h mph kn ft/s`, temperature
+/// `K C F` and data `b B kB MB GB TB PB KiB MiB GiB TiB PiB`. An unknown
+/// unit or a conversion between kinds throws `ERR_INVALID_INPUT`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "value: number | Float64Array, from: string, to: string",
+  ts_return_type = "number | Float64Array"
+)]
+pub fn convert(
+  value: Either<f64, Float64Array>,
+  from: String,
+  to: String,
+) -> Result<Either<f64, Float64Array>> {
+  let (from_dim, from_factor, from_offset) = unit(&from)?;
+  let (to_dim, to_factor, to_offset) = unit(&to)?;
+  if from_dim != to_dim {
+    return Err(AppError::InvalidInput(format!(
+      "cannot convert {} ({}) to {} ({})",
+      from_dim.name(),
+      from,
+      to_dim.name(),
+      to
+    )));
+  }
+  let convert = |v: f64| ((v * from_factor + from_offset) - to_offset) / to_factor;
+  Ok(match value {
+    Either::A(v) => Either::A(convert(v)),
+    Either::B(values) => Either::B(Float64Array::new(
+      values.iter().map(|&v| convert(v)).collect(),
+    )),
+  })
+}
@@ -0,0 +1,128 @@
+use ::url::{quirks, Host, Url};
+
+use crate::error::{AppError, Result};
+
+/// The components of a URL, named as in the WHATWG `URL` class.
+#[napi(object)]
+pub struct UrlParts {
+  pub href: String,
+  pub origin: String,
+  /// Scheme with its trailing colon, e.g. `https:`.
+  pub protocol: String,
+  pub username: String,
+  pub password: String,
+  /// Hostname plus port, if the port is not the scheme's default.
+  pub host: String,
+  /// In ASCII (punycode) form.
+  pub hostname: String,
+  /// Absent when the URL has no port or uses the scheme's default.
+  pub port: Option<u16>,
+  pub pathname: String,
+  /// Query with its leading `?`, or empty.
+  pub search: String,
+  /// Fragment with its leading `#`, or empty.
+  pub hash: String,
+}
+
+#[napi(object)]
+pub struct DomainParts {
+  /// Registrable domain, e.g. `example.co.uk`.
+  pub domain: String,
+  /// Labels left of the registrable domain, e.g. `www.shop`; empty if none.
+  pub subdomain: String,
+  /// Public suffix, e.g. `co.uk`.
+  pub suffix: String,
+}
+
+fn parse(input: &str, base: Option<&str>) -> Result<Url> {
+  let parsed = match base {
+    Some(base) => Url::parse(base).and_then(|base| base.join(input)),
+    None => Url::parse(input),
+  };
+  parsed.map_err(|e| AppError::Parse(format!("invalid URL {:?}: {}", input, e)))
+}
+
+/// Parses `input`, resolved against `base` if given, following the WHATWG
+/// URL standard (so results match `new URL()`).
+#[napi(catch_unwind)]
+pub fn parse_url(input: String, base: Option<String>) -> Result<UrlParts> {
+  let url = parse(&input, base.as_deref())?;
+  Ok(UrlParts {
+    href: quirks::href(&url).to_string(),
+    origin: quirks::origin(&url),
+    protocol: quirks::protocol(&url).to_string(),
+    username: quirks::username(&url).to_string(),
+    password: quirks::password(&url).to_string(),
+    host: quirks::host(&url).to_string(),
+    hostname: quirks::hostname(&url).to_string(),
+    port: url.port(),
+    pathname: quirks::pathname(&url).to_string(),
+    search: quirks::search(&url).to_string(),
+    hash: quirks::hash(&url).to_string(),
+  })
+}
+
+/// Normalizes a URL for use as a cache or dedup key. On top of WHATWG
+/// serialization (lower-cased host, default port and dot segments
+/// removed), this drops the fragment, sorts query parameters by name
+/// (stable for repeated names) and drops an empty query.
+#[napi(catch_unwind)]
+pub fn canonicalize_url(input: String) -> Result<String> {
+  let mut url = parse(&input, None)?;
+  url.set_fragment(None);
+  let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+  if pairs.is_empty() {
+    url.set_query(None);
+  } else {
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+  }
+  Ok(url.into())
+}
+
+/// Converts a punycode (`xn--`) domain to Unicode. Labels that are not
+/// valid punycode are rejected.
+#[napi(catch_unwind)]
+pub fn punycode_to_unicode(domain: String) -> Result<String> {
+  let (unicode, result) = idna::domain_to_unicode(&domain);
+  result.map_err(|_| AppError::Parse(format!("invalid punycode domain: {}", domain)))?;
+  Ok(unicode)
+}
+
+/// Converts a Unicode domain to its punycode (ASCII) form.
+#[napi(catch_unwind)]
+pub fn unicode_to_punycode(domain: String) -> Result<String> {
+  idna::domain_to_ascii(&domain).map_err(|_| AppError::Parse(format!("invalid domain: {}", domain)))
+}
+
+/// Splits the host of a URL (or a bare host name) into subdomain,
+/// registrable domain and public suffix, using the compiled-in Public
+/// Suffix List. Returns `null` for IP addresses and for hosts that are
+/// themselves a public suffix.
+#[napi(catch_unwind)]
+pub fn extract_domain(input: String) -> Result<Option<DomainParts>> {
+  let host = if input.contains("://") {
+    match parse(&input, None)?.host() {
+      Some(Host::Domain(domain)) => domain.to_string(),
+      _ => return Ok(None),
+    }
+  } else {
+    match Host::parse(input.trim_end_matches('.')) {
+      Ok(Host::Domain(domain)) => domain,
+      Ok(_) => return Ok(None),
+      Err(e) => return Err(AppError::Parse(format!("invalid host {:?}: {}", input, e))),
+    }
+  };
+  let Some(domain) = psl::domain(host.as_bytes()) else {
+    return Ok(None);
+  };
+  // psl only returns slices of `host`, which is ASCII after parsing.
+  let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+  let domain_str = text(domain.as_bytes());
+  let subdomain = host[..host.len() - domain_str.len()].trim_end_matches('.');
+  Ok(Some(DomainParts {
+    subdomain: subdomain.to_string(),
+    suffix: text(domain.suffix().as_bytes()),
+    domain: domain_str,
+  }))
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use ::regex::{Regex, RegexSet};
+use napi::bindgen_prelude::*;
+use rayon::prelude::*;
+
+use crate::panic_guard::{self, Guarded};
+
+/// Browsers, most specific first: Edge, Opera and most others also claim to
+/// be Chrome and Safari, so those two come last.
+const BROWSERS: &[(&str, &str)] = &[
+  (r"Edg(?:e|A|iOS)?/([\d.]+)", "Edge"),
+  (r"(?:OPR|OPiOS)/([\d.]+)", "Opera"),
+  (r"Opera[/ ](?:.*Version/)?([\d.]+)", "Opera"),
+  (r"SamsungBrowser/([\d.]+)", "Samsung Internet"),
+  (r"YaBrowser/([\d.]+)", "Yandex Browser"),
+  (r"Vivaldi/([\d.]+)", "Vivaldi"),
+  (r"UC?Browser/([\d.]+)", "UC Browser"),
+  (r"FxiOS/([\d.]+)", "Firefox"),
+  (r"Firefox/([\d.]+)", "Firefox"),
+  (r"CriOS/([\d.]+)", "Chrome"),
+  (r"Chromium/([\d.]+)", "Chromium"),
+  (r"MSIE ([\d.]+)", "Internet Explorer"),
+  (r"Trident/.*rv:([\d.]+)", "Internet Explorer"),
+  (r"Chrome/([\d.]+)", "Chrome"),
+  (r"Version/([\d.]+).*Safari/", "Safari"),
+  (r"(?:iPhone|iPad|iPod).*AppleWebKit/([\d.]+)", "Safari"),
+];
+
+/// Operating systems. Android and ChromeOS say `Linux` too, and Windows
+/// Phone says `Android`, so they are tried first.
+const SYSTEMS: &[(&str, &str)] = &[
+  (r"Windows Phone(?: OS)? ([\d.]+)", "Windows Phone"),
+  (r"(?:iPhone|iPad|iPod).*? OS ([\d_]+)", "iOS"),
+  (r"Android ([\d.]+)", "Android"),
+  (r"Android", "Android"),
+  (r"CrOS \S+ ([\d.]+)", "ChromeOS"),
+  (r"Windows NT ([\d.]+)", "Windows"),
+  (r"Windows", "Windows"),
+  (r"Mac OS X ([\d_.]+)", "macOS"),
+  (r"Macintosh", "macOS"),
+  (r"Linux", "Linux"),
+];
+
+/// Crawlers, monitors, headless browsers and HTTP libraries.
+const BOTS: &[&str] = &[
+  r"(?i)[a-z]*bot\b",
+  r"(?i)crawl|spider|slurp|scrape",
+  r"(?i)facebookexternalhit|mediapartners-google|google-inspectiontool|feedfetcher",
+  r"(?i)headlesschrome|phantomjs|lighthouse|pingdom|uptimerobot",
+  r"(?i)^(?:curl|wget|python-requests|python-urllib|aiohttp|go-http-client|java|okhttp|axios|node-fetch|undici|libwww-perl|apache-httpclient)\b",
+];
+
+/// Real devices whose names happen to match `BOTS`.
+const NOT_BOTS: &str = r"(?i)\bcubot\b";
+
+const TV: &str = r"(?i)smart-?tv|googletv|appletv|hbbtv|roku|crkey|\btv\b";
+const CONSOLE: &str = r"PlayStation|Xbox|Nintendo";
+const TABLET: &str = r"iPad|Tablet|Kindle|Silk/|PlayBook";
+const MOBILE: &str = r"Mobi|iPhone|iPod|Android|Windows Phone|BlackBerry|Opera Mini";
+
+struct Database {
+  browsers: Vec<(Regex, &'static str)>,
+  systems: Vec<(Regex, &'static str)>,
+  bots: RegexSet,
+  not_bots: Regex,
+  tv: Regex,
+  console: Regex,
+  tablet: Regex,
+  mobile: Regex,
+}
+
+/// The patterns are fixed, so they are compiled on first use and shared by
+/// every call and thread.
+fn database() -> &'static Database {
+  static DATABASE: OnceLock<Database> = OnceLock::new();
+  DATABASE.get_or_init(|| {
+    let compile = |pattern: &str| Regex::new(pattern).expect("built-in pattern");
+    let table = |rules: &[(&str, &'static str)]| {
+      rules
+        .iter()
+        .map(|&(pattern, name)| (compile(pattern), name))
+        .collect()
+    };
+    Database {
+      browsers: table(BROWSERS),
+      systems: table(SYSTEMS),
+      bots: RegexSet::new(BOTS).expect("built-in pattern"),
+      not_bots: compile(NOT_BOTS),
+      tv: compile(TV),
+      console: compile(CONSOLE),
+      tablet: compile(TABLET),
+      mobile: compile(MOBILE),
+    }
+  })
+}
+
+/// What a `User-Agent` header says about the client.
+#[napi(object)]
+#[derive(Clone)]
+pub struct UaInfo {
+  /// Browser name such as `Chrome`, `Firefox` or `Safari`; absent when not
+  /// recognized.
+  pub browser: Option<String>,
+  pub browser_version: Option<String>,
+  /// `Windows`, `macOS`, `iOS`, `Android`, `Linux`, `ChromeOS` or
+  /// `Windows Phone`.
+  pub os: Option<String>,
+  /// Dotted, e.g. `17.4` for iOS. Windows reports its marketing name
+  /// (`10`, `8.1`, `7`, `XP`); Windows 11 still reads as `10`.
+  pub os_version: Option<String>,
+  #[napi(ts_type = "'desktop' | 'mobile' | 'tablet' | 'tv' | 'console' | 'bot' | 'unknown'")]
+  pub device: String,
+  pub is_bot: bool,
+}
+
+fn find(rules: &[(Regex, &'static str)], ua: &str) -> Option<(String, Option<String>)> {
+  rules.iter().find_map(|(regex, name)| {
+    let captures = regex.captures(ua)?;
+    let version = captures
+      .get(1)
+      .map(|m| m.as_str().trim_end_matches(['.', '_']).replace('_', "."));
+    Some((name.to_string(), version))
+  })
+}
+
+fn windows_version(nt: &str) -> String {
+  match nt {
+    "10.0" => "10",
+    "6.3" => "8.1",
+    "6.2" => "8",
+    "6.1" => "7",
+    "6.0" => "Vista",
+    "5.1" | "5.2" => "XP",
+    other => other,
+  }
+  .to_string()
+}
+
+fn detect_bot(db: &Database, ua: &str) -> bool {
+  db.bots.is_match(ua) && !db.not_bots.is_match(ua)
+}
+
+fn parse(ua: &str) -> UaInfo {
+  let db = database();
+  let is_bot = detect_bot(db, ua);
+  let (browser, browser_version) = find(&db.browsers, ua).unzip();
+  let (os, os_version) = find(&db.systems, ua).unzip();
+  let os_version = match os.as_deref() {
+    Some("Windows") => os_version.flatten().map(|v| windows_version(&v)),
+    _ => os_version.flatten(),
+  };
+  let device = if is_bot {
+    "bot"
+  } else if db.tv.is_match(ua) {
+    "tv"
+  } else if db.console.is_match(ua) {
+    "console"
+  } else if db.tablet.is_match(ua) || (ua.contains("Android") && !ua.contains("Mobi")) {
+    "tablet"
+  } else if db.mobile.is_match(ua) {
+    "mobile"
+  } else if browser.is_some() || os.is_some() {
+    "desktop"
+  } else {
+    "unknown"
+  };
+  UaInfo {
+    browser,
+    browser_version: browser_version.flatten(),
+    os,
+    os_version,
+    device: device.to_string(),
+    is_bot,
+  }
+}
+
+/// Picks the browser, operating system and device type out of a
+/// `User-Agent` header using a built-in pattern table. Anything not
+/// recognized is left absent; a tablet is an iPad, a tablet-class device or
+/// an Android device that makes no claim to be mobile.
+#[napi(catch_unwind)]
+pub fn parse_user_agent(ua: String) -> UaInfo {
+  parse(&ua)
+}
+
+/// Whether `ua` belongs to a crawler, monitor, headless browser or HTTP
+/// library (`curl`, `python-requests`, …) rather than a person's browser.
+/// An empty string is not counted as a bot.
+#[napi(catch_unwind)]
+pub fn is_bot(ua: String) -> bool {
+  detect_bot(database(), &ua)
+}
+
+/// Parses a batch of user agents on the libuv thread pool.
+pub struct ParseUserAgentsTask {
+  user_agents: Vec<String>,
+}
+
+impl Task for ParseUserAgentsTask {
+  type Output = Vec<UaInfo>;
+  type JsValue = Vec<UaInfo>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    // Logs repeat a small set of user agents, so each is parsed once.
+    let mut slots: HashMap<&str, usize> = HashMap::new();
+    let mut unique = Vec::new();
+    let indices: Vec<usize> = self
+      .user_agents
+      .iter()
+      .map(|ua| {
+        *slots.entry(ua.as_str()).or_insert_with(|| {
+          unique.push(ua.as_str());
+          unique.len() - 1
+        })
+      })
+      .collect();
+    let parsed: Vec<UaInfo> = unique.par_iter().map(|ua| parse(ua)).collect();
+    Ok(indices.into_iter().map(|i| parsed[i].clone()).collect())
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// `parseUserAgent` for every entry of `userAgents`, in order, computed off
+/// the main thread with distinct strings parsed once and in parallel.
+#[napi(catch_unwind, ts_return_type = "Promise<UaInfo[]>")]
+pub fn parse_user_agents(user_agents: Vec<String>) -> AsyncTask<Guarded<ParseUserAgentsTask>> {
+  panic_guard::task(ParseUserAgentsTask { user_agents })
+}
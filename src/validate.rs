@@ -0,0 +1,133 @@
+use email_address::{EmailAddress, Options};
+use iban::{Iban, IbanLike, ParseIbanError};
+use phonenumber::country::Id;
+use phonenumber::{Mode, Type as PhoneNumberType};
+
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct ValidationResult {
+  pub valid: bool,
+  /// Why the input was rejected; set only when `valid` is `false`.
+  pub reason: Option<String>,
+  /// Canonical form of the input; set only when `valid` is `true`.
+  pub normalized: Option<String>,
+}
+
+impl ValidationResult {
+  fn ok(normalized: String) -> Self {
+    ValidationResult {
+      valid: true,
+      reason: None,
+      normalized: Some(normalized),
+    }
+  }
+
+  fn fail(reason: impl ToString) -> Self {
+    ValidationResult {
+      valid: false,
+      reason: Some(reason.to_string()),
+      normalized: None,
+    }
+  }
+}
+
+#[napi(object)]
+pub struct PhoneInfo {
+  /// Whether the number matches a known numbering plan.
+  pub valid: bool,
+  pub e164: String,
+  pub international: String,
+  pub national: String,
+  pub country_code: u16,
+  /// ISO 3166 region, e.g. `US`; absent for non-geographic numbers.
+  pub region: Option<String>,
+  /// libphonenumber type name, e.g. `MOBILE`, `FIXED_LINE`, `TOLL_FREE` or
+  /// `UNKNOWN`.
+  #[napi(js_name = "type")]
+  pub number_type: String,
+}
+
+/// Checks `input` against the RFC 5322 address grammar. Display names
+/// (`Name <a@b>`) are rejected, and the domain must have at least two
+/// labels unless `requireTld` is `false`. The normalized form has a
+/// lower-cased domain.
+#[napi(catch_unwind)]
+pub fn validate_email(input: String, require_tld: Option<bool>) -> ValidationResult {
+  let mut options = Options::default().without_display_text();
+  if require_tld.unwrap_or(true) {
+    options = options.with_required_tld();
+  }
+  match EmailAddress::parse_with_options(&input, options) {
+    Ok(email) => ValidationResult::ok(format!(
+      "{}@{}",
+      email.local_part(),
+      email.domain().to_lowercase()
+    )),
+    Err(e) => ValidationResult::fail(e),
+  }
+}
+
+fn type_name(number_type: PhoneNumberType) -> &'static str {
+  match number_type {
+    PhoneNumberType::FixedLine => "FIXED_LINE",
+    PhoneNumberType::Mobile => "MOBILE",
+    PhoneNumberType::FixedLineOrMobile => "FIXED_LINE_OR_MOBILE",
+    PhoneNumberType::TollFree => "TOLL_FREE",
+    PhoneNumberType::PremiumRate => "PREMIUM_RATE",
+    PhoneNumberType::SharedCost => "SHARED_COST",
+    PhoneNumberType::PersonalNumber => "PERSONAL_NUMBER",
+    PhoneNumberType::Voip => "VOIP",
+    PhoneNumberType::Pager => "PAGER",
+    PhoneNumberType::Uan => "UAN",
+    PhoneNumberType::Emergency => "EMERGENCY",
+    PhoneNumberType::Voicemail => "VOICEMAIL",
+    PhoneNumberType::ShortCode => "SHORT_CODE",
+    PhoneNumberType::StandardRate => "STANDARD_RATE",
+    PhoneNumberType::Carrier => "CARRIER",
+    PhoneNumberType::NoInternational => "NO_INTERNATIONAL",
+    PhoneNumberType::Unknown => "UNKNOWN",
+  }
+}
+
+/// Parses a phone number with libphonenumber's metadata. `region` (ISO
+/// 3166, e.g. `GB`) is needed for numbers written without a `+` country
+/// code. Throws `ERR_PARSE` if the input is not a phone number at all; a
+/// well-formed number outside any numbering plan comes back with
+/// `valid: false`.
+#[napi(catch_unwind)]
+pub fn parse_phone(input: String, region: Option<String>) -> Result<PhoneInfo> {
+  let region = region
+    .map(|r| {
+      r.to_ascii_uppercase()
+        .parse::<Id>()
+        .map_err(|_| AppError::InvalidInput(format!("unknown region: {}", r)))
+    })
+    .transpose()?;
+  let number = phonenumber::parse(region, &input)
+    .map_err(|e| AppError::Parse(format!("invalid phone number {:?}: {}", input, e)))?;
+  let format = |mode| number.format().mode(mode).to_string();
+  Ok(PhoneInfo {
+    valid: number.is_valid(),
+    e164: format(Mode::E164),
+    international: format(Mode::International),
+    national: format(Mode::National),
+    country_code: number.country().code(),
+    region: number.country().id().map(|id| id.as_ref().to_string()),
+    number_type: type_name(number.number_type(&phonenumber::metadata::DATABASE)).to_string(),
+  })
+}
+
+/// Validates an IBAN's structure, country-specific BBAN format and
+/// mod-97 checksum. Spaces are allowed; the normalized form has none.
+#[napi(catch_unwind)]
+pub fn validate_iban(input: String) -> ValidationResult {
+  match input.parse::<Iban>() {
+    Ok(iban) => ValidationResult::ok(iban.electronic_str().to_string()),
+    Err(ParseIbanError::InvalidBaseIban { source }) => ValidationResult::fail(source),
+    Err(ParseIbanError::InvalidBban(_)) => {
+      ValidationResult::fail("account number does not match the country's format")
+    }
+    Err(ParseIbanError::UnknownCountry(_)) => ValidationResult::fail("unknown country code"),
+  }
+}
@@ -0,0 +1,429 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use napi::bindgen_prelude::*;
+use napi::{Env, NapiRaw, Task, ValueType};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// File header: magic and the logical offset of the first byte after it.
+const MAGIC: &[u8; 8] = b"NAPIWAL1";
+const HEADER_LEN: u64 = 16;
+/// Record header: payload length and CRC-32C, both little-endian.
+const FRAME_LEN: u64 = 8;
+
+type SharedLog = Arc<Mutex<Option<Log>>>;
+
+#[napi(object)]
+#[derive(Default)]
+pub struct AppendLogOptions {
+  /// Sync each append to disk before its promise resolves. Defaults to
+  /// `true`; with `false`, records written since the last `flush()` may be
+  /// lost in a crash (recovery still drops any torn one).
+  pub sync: Option<bool>,
+}
+
+struct Log {
+  path: PathBuf,
+  file: File,
+  /// Logical offset of the first record.
+  base: u64,
+  /// Logical offset the next record will get.
+  end: u64,
+  sync: bool,
+}
+
+/// The checksum covers the length too, so reading from an offset that is
+/// not a record boundary is caught rather than returning garbage.
+fn checksum(len: u32, payload: &[u8]) -> u32 {
+  crc32c::crc32c_append(crc32c::crc32c(&len.to_le_bytes()), payload)
+}
+
+fn io(path: &Path, e: std::io::Error) -> AppError {
+  AppError::io(&path.display().to_string(), e)
+}
+
+/// Reads the record at file position `pos`, or `None` if there is no
+/// complete, intact record there.
+fn read_record(file: &mut File, pos: u64, file_len: u64) -> std::io::Result<Option<Vec<u8>>> {
+  if pos + FRAME_LEN > file_len {
+    return Ok(None);
+  }
+  let mut frame = [0; FRAME_LEN as usize];
+  file.seek(SeekFrom::Start(pos))?;
+  file.read_exact(&mut frame)?;
+  let len = u32::from_le_bytes(frame[..4].try_into().expect("4 bytes"));
+  let crc = u32::from_le_bytes(frame[4..].try_into().expect("4 bytes"));
+  if pos + FRAME_LEN + len as u64 > file_len {
+    return Ok(None);
+  }
+  let mut payload = vec![0; len as usize];
+  file.read_exact(&mut payload)?;
+  Ok((checksum(len, &payload) == crc).then_some(payload))
+}
+
+fn write_header(file: &mut File, base: u64) -> std::io::Result<()> {
+  let mut header = MAGIC.to_vec();
+  header.extend_from_slice(&base.to_le_bytes());
+  file.seek(SeekFrom::Start(0))?;
+  file.write_all(&header)
+}
+
+impl Log {
+  /// Opens or creates the log and cuts off anything after the last intact
+  /// record: a write torn by a crash, or garbage after it.
+  fn open(path: PathBuf, sync: bool) -> Result<Log> {
+    let mut file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(&path)
+      .map_err(|e| io(&path, e))?;
+    let file_len = file.metadata().map_err(|e| io(&path, e))?.len();
+    let base = if file_len == 0 {
+      write_header(&mut file, 0).map_err(|e| io(&path, e))?;
+      file.sync_all().map_err(|e| io(&path, e))?;
+      0
+    } else {
+      let mut header = [0; HEADER_LEN as usize];
+      file
+        .read_exact(&mut header)
+        .ok()
+        .filter(|_| &header[..8] == MAGIC)
+        .ok_or_else(|| AppError::Parse(format!("{}: not an append log", path.display())))?;
+      u64::from_le_bytes(header[8..].try_into().expect("8 bytes"))
+    };
+
+    let file_len = file_len.max(HEADER_LEN);
+    let mut pos = HEADER_LEN;
+    while let Some(payload) = read_record(&mut file, pos, file_len).map_err(|e| io(&path, e))? {
+      pos += FRAME_LEN + payload.len() as u64;
+    }
+    if pos < file_len {
+      tracing::warn!(
+        path = %path.display(),
+        dropped = file_len - pos,
+        "append log ends in a torn record; truncating"
+      );
+      file.set_len(pos).map_err(|e| io(&path, e))?;
+      file.sync_all().map_err(|e| io(&path, e))?;
+    }
+    Ok(Log {
+      path,
+      file,
+      base,
+      end: base + pos - HEADER_LEN,
+      sync,
+    })
+  }
+
+  fn position(&self, offset: u64) -> Result<u64> {
+    if offset < self.base || offset > self.end {
+      return Err(AppError::InvalidInput(format!(
+        "offset {} is outside the log, which spans {}..{}",
+        offset, self.base, self.end
+      )));
+    }
+    Ok(offset - self.base + HEADER_LEN)
+  }
+
+  fn append(&mut self, payload: &[u8]) -> Result<u64> {
+    let len = u32::try_from(payload.len())
+      .map_err(|_| AppError::InvalidInput("record is larger than 4 GiB".into()))?;
+    let mut frame = Vec::with_capacity(FRAME_LEN as usize + payload.len());
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(&checksum(len, payload).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let pos = self.position(self.end)?;
+    let written = self
+      .file
+      .seek(SeekFrom::Start(pos))
+      .and_then(|_| self.file.write_all(&frame))
+      .and_then(|_| {
+        if self.sync {
+          self.file.sync_data()
+        } else {
+          Ok(())
+        }
+      });
+    if let Err(e) = written {
+      // Don't leave a partial record for the next append to follow.
+      let _ = self.file.set_len(pos);
+      return Err(io(&self.path, e));
+    }
+    let offset = self.end;
+    self.end += frame.len() as u64;
+    Ok(offset)
+  }
+
+  fn read_from(&mut self, offset: u64, max: usize) -> Result<Records> {
+    let mut pos = self.position(offset)?;
+    let file_len = self.position(self.end)?;
+    let mut records = Vec::new();
+    while records.len() < max && pos < file_len {
+      let payload = read_record(&mut self.file, pos, file_len)
+        .map_err(|e| io(&self.path, e))?
+        .ok_or_else(|| {
+          AppError::InvalidInput(format!(
+            "offset {} is not the start of a record",
+            pos - HEADER_LEN + self.base
+          ))
+        })?;
+      let offset = pos - HEADER_LEN + self.base;
+      pos += FRAME_LEN + payload.len() as u64;
+      records.push(LogRecord {
+        offset,
+        data: payload,
+        next: pos - HEADER_LEN + self.base,
+      });
+    }
+    Ok(Records(records))
+  }
+
+  /// Copies the records from `offset` on into a new file and swaps it in,
+  /// so the space before `offset` is given back.
+  fn truncate_before(&mut self, offset: u64) -> Result<()> {
+    let start = self.position(offset)?;
+    let file_len = self.position(self.end)?;
+    if start < file_len
+      && read_record(&mut self.file, start, file_len)
+        .map_err(|e| io(&self.path, e))?
+        .is_none()
+    {
+      return Err(AppError::InvalidInput(format!(
+        "offset {} is not the start of a record",
+        offset
+      )));
+    }
+    let mut tmp_path = self.path.clone().into_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let copied = (|| {
+      let mut tmp = File::create(&tmp_path)?;
+      write_header(&mut tmp, offset)?;
+      self.file.seek(SeekFrom::Start(start))?;
+      std::io::copy(&mut (&mut self.file).take(file_len - start), &mut tmp)?;
+      tmp.sync_all()?;
+      std::fs::rename(&tmp_path, &self.path)?;
+      // Make the rename itself durable. Directories can't be opened on
+      // Windows, where this is skipped.
+      let dir = self.path.parent().filter(|dir| !dir.as_os_str().is_empty());
+      let _ = File::open(dir.unwrap_or(Path::new("."))).and_then(|dir| dir.sync_all());
+      OpenOptions::new().read(true).write(true).open(&self.path)
+    })();
+    match copied {
+      Ok(file) => {
+        self.file = file;
+        self.base = offset;
+        Ok(())
+      }
+      Err(e) => {
+        let _ = std::fs::remove_file(&tmp_path);
+        Err(io(&self.path, e))
+      }
+    }
+  }
+}
+
+struct LogRecord {
+  offset: u64,
+  data: Vec<u8>,
+  next: u64,
+}
+
+/// Records read by `readFrom`; they become JS objects on the JS thread.
+pub struct Records(Vec<LogRecord>);
+
+impl TypeName for Records {
+  fn type_name() -> &'static str {
+    "Array<{ offset: bigint; data: Buffer; next: bigint }>"
+  }
+
+  fn value_type() -> ValueType {
+    ValueType::Object
+  }
+}
+
+impl ToNapiValue for Records {
+  unsafe fn to_napi_value(env: sys::napi_env, records: Self) -> napi::Result<sys::napi_value> {
+    let env = Env::from_raw(env);
+    let mut array = env.create_array_with_length(records.0.len())?;
+    for (i, record) in records.0.into_iter().enumerate() {
+      let mut object = env.create_object()?;
+      object.set_named_property(
+        "offset",
+        env.create_bigint_from_u64(record.offset)?.into_unknown()?,
+      )?;
+      object.set_named_property("data", env.create_buffer_with_data(record.data)?.into_raw())?;
+      object.set_named_property(
+        "next",
+        env.create_bigint_from_u64(record.next)?.into_unknown()?,
+      )?;
+      array.set_element(i as u32, object)?;
+    }
+    Ok(array.raw())
+  }
+}
+
+fn closed() -> AppError {
+  AppError::Closed("append log is closed".into())
+}
+
+fn with_log<T>(log: &SharedLog, f: impl FnOnce(&mut Log) -> Result<T>) -> Result<T> {
+  let mut guard = log.lock().unwrap_or_else(|e| e.into_inner());
+  f(guard.as_mut().ok_or_else(closed)?)
+}
+
+fn offset_arg(offset: Either<BigInt, f64>) -> Result<u64> {
+  match offset {
+    Either::A(offset) => match offset.get_u64() {
+      (false, offset, true) => Ok(offset),
+      _ => Err(AppError::InvalidInput(
+        "offset must be a non-negative 64-bit integer".into(),
+      )),
+    },
+    Either::B(offset) if offset >= 0.0 && offset.fract() == 0.0 => Ok(offset as u64),
+    Either::B(offset) => Err(AppError::InvalidInput(format!(
+      "offset must be a non-negative integer, got {}",
+      offset
+    ))),
+  }
+}
+
+type LogOp<T> = Box<dyn FnOnce(&mut Log) -> Result<T> + Send>;
+
+/// Runs one operation against the log on the libuv thread pool, one at a
+/// time per log.
+pub struct LogTask<T> {
+  log: SharedLog,
+  op: Option<LogOp<T>>,
+}
+
+impl<T> LogTask<T> {
+  fn new(
+    log: &SharedLog,
+    op: impl FnOnce(&mut Log) -> Result<T> + Send + 'static,
+  ) -> AsyncTask<Guarded<Self>>
+  where
+    T: ToNapiValue + TypeName + Send + 'static,
+  {
+    panic_guard::task(LogTask {
+      log: log.clone(),
+      op: Some(Box::new(op)),
+    })
+  }
+}
+
+impl<T: ToNapiValue + TypeName + Send + 'static> Task for LogTask<T> {
+  type Output = Result<T>;
+  type JsValue = T;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    let op = self.op.take().expect("LogTask computed twice");
+    Ok(with_log(&self.log, op))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// An append-only log of binary records in a single file, the building
+/// block for durable queues and event sourcing.
+///
+/// Each record is framed with its length and a CRC-32C. Records are
+/// addressed by byte offset, which stays valid across `truncateBefore`.
+/// Opening the log verifies every record and cuts the file at the first
+/// one that is incomplete or fails its checksum, as a crash mid-append
+/// leaves it.
+#[napi]
+pub struct AppendLog {
+  log: SharedLog,
+}
+
+#[napi]
+impl AppendLog {
+  #[napi(catch_unwind, factory)]
+  pub fn open(path: String, options: Option<AppendLogOptions>) -> Result<Self> {
+    let sync = options.unwrap_or_default().sync.unwrap_or(true);
+    let log = Log::open(PathBuf::from(path), sync)?;
+    Ok(AppendLog {
+      log: Arc::new(Mutex::new(Some(log))),
+    })
+  }
+
+  /// Appends `data` as one record and resolves with its offset.
+  #[napi(catch_unwind, ts_return_type = "Promise<bigint>")]
+  pub fn append(&self, data: Buffer) -> AsyncTask<Guarded<LogTask<BigInt>>> {
+    let data = data.to_vec();
+    LogTask::new(&self.log, move |log| log.append(&data).map(BigInt::from))
+  }
+
+  /// Up to `max` records (default 1000) starting at `offset`, which must be
+  /// a record's offset or `end`. Each record's `next` is where to continue.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "offset: bigint | number, max?: number",
+    ts_return_type = "Promise<Array<{ offset: bigint; data: Buffer; next: bigint }>>"
+  )]
+  pub fn read_from(
+    &self,
+    offset: Either<BigInt, f64>,
+    max: Option<u32>,
+  ) -> Result<AsyncTask<Guarded<LogTask<Records>>>> {
+    let offset = offset_arg(offset)?;
+    let max = max.unwrap_or(1000) as usize;
+    Ok(LogTask::new(&self.log, move |log| {
+      log.read_from(offset, max)
+    }))
+  }
+
+  /// Drops every record before `offset`, a record's offset or `end`, and
+  /// frees their space. Rewrites the rest of the file, so it costs as much
+  /// as the records kept.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "offset: bigint | number",
+    ts_return_type = "Promise<void>"
+  )]
+  pub fn truncate_before(
+    &self,
+    offset: Either<BigInt, f64>,
+  ) -> Result<AsyncTask<Guarded<LogTask<()>>>> {
+    let offset = offset_arg(offset)?;
+    Ok(LogTask::new(&self.log, move |log| {
+      log.truncate_before(offset)
+    }))
+  }
+
+  /// Syncs appended records to disk. Only needed with `sync: false`.
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn flush(&self) -> AsyncTask<Guarded<LogTask<()>>> {
+    LogTask::new(&self.log, |log| {
+      log.file.sync_data().map_err(|e| io(&log.path, e))
+    })
+  }
+
+  /// Offset of the first record still in the log.
+  #[napi(catch_unwind, getter)]
+  pub fn start(&self) -> Result<u64> {
+    with_log(&self.log, |log| Ok(log.base))
+  }
+
+  /// Offset the next appended record will get.
+  #[napi(catch_unwind, getter)]
+  pub fn end(&self) -> Result<u64> {
+    with_log(&self.log, |log| Ok(log.end))
+  }
+
+  /// Closes the file, waiting for a running operation. Later calls throw
+  /// `ERR_CLOSED`; closing twice is a no-op.
+  #[napi(catch_unwind)]
+  pub fn close(&self) {
+    self.log.lock().unwrap_or_else(|e| e.into_inner()).take();
+  }
+}
@@ -0,0 +1,363 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsObject};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+use crate::error::{self, AppError, Result};
+use crate::lifecycle::{self, Resource};
+
+/// How long to wait for the server to answer our close frame before
+/// dropping the connection.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Close code reported when the connection ends without a close frame.
+const ABNORMAL_CLOSURE: u16 = 1006;
+
+enum Payload {
+  Text(String),
+  Binary(Vec<u8>),
+}
+
+type MessageListener = ThreadsafeFunction<Payload, ErrorStrategy::Fatal>;
+type CloseListener = ThreadsafeFunction<(u16, String), ErrorStrategy::Fatal>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+  Idle,
+  Connecting,
+  Open,
+  Closing,
+  Closed,
+}
+
+struct Shared {
+  state: State,
+  /// Frames for the connection task to write; dropping it starts a close.
+  outgoing: Option<UnboundedSender<Message>>,
+  on_message: Vec<MessageListener>,
+  on_close: Vec<CloseListener>,
+}
+
+type SharedState = Arc<Mutex<Shared>>;
+
+fn lock(shared: &SharedState) -> MutexGuard<'_, Shared> {
+  shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn ws_error(e: WsError) -> AppError {
+  match e {
+    WsError::Url(e) => AppError::InvalidInput(format!("invalid WebSocket URL: {}", e)),
+    WsError::Http(response) => AppError::Network(format!(
+      "WebSocket handshake rejected with status {}",
+      response.status()
+    )),
+    e => AppError::Network(e.to_string()),
+  }
+}
+
+fn not_open() -> AppError {
+  AppError::Closed("WebSocket is not open".into())
+}
+
+impl Resource for Mutex<Shared> {
+  fn kind(&self) -> &'static str {
+    "websocket"
+  }
+
+  fn active(&self) -> bool {
+    let state = self.lock().unwrap_or_else(|e| e.into_inner()).state;
+    matches!(state, State::Connecting | State::Open | State::Closing)
+  }
+
+  /// Sends a 1001 (going away) close frame and drops the listeners at once
+  /// instead of waiting for the server's answer.
+  fn shutdown(&self) {
+    let mut shared = self.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(outgoing) = shared.outgoing.take() {
+      let frame = CloseFrame {
+        code: CloseCode::Away,
+        reason: "shutting down".into(),
+      };
+      let _ = outgoing.send(Message::Close(Some(frame)));
+    }
+    shared.state = match shared.state {
+      // The connection task reports `closed` once the socket winds down.
+      State::Open => State::Closing,
+      // Resolving `connect` sees the state change and rejects.
+      State::Idle | State::Connecting => State::Closed,
+      state => state,
+    };
+    shared.on_message.clear();
+    shared.on_close.clear();
+  }
+}
+
+/// Marks the client closed, then notifies close listeners and releases
+/// every listener so they no longer keep the process alive.
+fn finish(shared: &SharedState, code: u16, reason: String) {
+  let (on_close, _on_message) = {
+    let mut shared = lock(shared);
+    shared.state = State::Closed;
+    shared.outgoing = None;
+    (
+      std::mem::take(&mut shared.on_close),
+      std::mem::take(&mut shared.on_message),
+    )
+  };
+  for listener in &on_close {
+    listener.call(
+      (code, reason.clone()),
+      ThreadsafeFunctionCallMode::NonBlocking,
+    );
+  }
+}
+
+fn dispatch(shared: &SharedState, payload: impl Fn() -> Payload) {
+  for listener in &lock(shared).on_message {
+    listener.call(payload(), ThreadsafeFunctionCallMode::NonBlocking);
+  }
+}
+
+/// Pumps frames both ways until either side closes.
+async fn run<S>(stream: S, mut outgoing: UnboundedReceiver<Message>, shared: SharedState)
+where
+  S: futures_util::Stream<Item = std::result::Result<Message, WsError>>
+    + futures_util::Sink<Message, Error = WsError>
+    + Unpin,
+{
+  let (mut sink, mut stream) = stream.split();
+  let mut closing = false;
+  let (code, reason) = loop {
+    tokio::select! {
+      frame = outgoing.recv(), if !closing => {
+        // The sender is gone when the client was closed or finalized.
+        let frame = frame.unwrap_or(Message::Close(None));
+        closing = matches!(frame, Message::Close(_));
+        if let Err(e) = sink.send(frame).await {
+          break (ABNORMAL_CLOSURE, e.to_string());
+        }
+      }
+      incoming = stream.next() => match incoming {
+        Some(Ok(Message::Text(text))) => dispatch(&shared, || Payload::Text(text.clone())),
+        Some(Ok(Message::Binary(data))) => dispatch(&shared, || Payload::Binary(data.clone())),
+        Some(Ok(Message::Close(frame))) => {
+          break match frame {
+            Some(frame) => (frame.code.into(), frame.reason.into_owned()),
+            None => (CloseCode::Status.into(), String::new()),
+          };
+        }
+        // Pings are answered by tungstenite itself.
+        Some(Ok(_)) => {}
+        Some(Err(e)) => break (ABNORMAL_CLOSURE, e.to_string()),
+        None => break (ABNORMAL_CLOSURE, String::new()),
+      },
+      _ = tokio::time::sleep(CLOSE_TIMEOUT), if closing => {
+        break (ABNORMAL_CLOSURE, "server did not answer the close frame".into());
+      }
+    }
+  };
+  tracing::debug!(code, reason, "connection closed");
+  finish(&shared, code, reason);
+}
+
+/// A WebSocket client. Incoming messages and the close event are delivered
+/// to listeners through thread-safe functions, so the socket is serviced on
+/// the tokio runtime without ever blocking the JS thread.
+///
+/// A client connects once; create a new one to reconnect. While connected,
+/// registered listeners keep the process alive.
+#[napi(custom_finalize)]
+pub struct WsClient {
+  shared: SharedState,
+}
+
+#[napi]
+impl WsClient {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    WsClient {
+      shared: Arc::new(Mutex::new(Shared {
+        state: State::Idle,
+        outgoing: None,
+        on_message: Vec::new(),
+        on_close: Vec::new(),
+      })),
+    }
+  }
+
+  /// Opens a `ws://` or `wss://` connection. Resolves once the handshake
+  /// completes.
+  #[napi(catch_unwind, ts_return_type = "Promise<void>")]
+  pub fn connect(&self, env: Env, url: String) -> Result<JsObject> {
+    let request = url
+      .as_str()
+      .into_client_request()
+      .map_err(|e| AppError::InvalidInput(format!("invalid WebSocket URL {}: {}", url, e)))?;
+    {
+      let mut shared = lock(&self.shared);
+      if shared.state != State::Idle {
+        return Err(AppError::InvalidInput(
+          "WebSocket client has already connected".into(),
+        ));
+      }
+      shared.state = State::Connecting;
+    }
+    lifecycle::register(&env, &self.shared)?;
+    let shared = self.shared.clone();
+    error::spawn(&env, async move {
+      let stream = match tokio_tungstenite::connect_async(request).await {
+        Ok((stream, _)) => stream,
+        Err(e) => {
+          let mut shared = lock(&shared);
+          shared.state = State::Closed;
+          shared.on_message.clear();
+          shared.on_close.clear();
+          return Err(ws_error(e));
+        }
+      };
+      let (tx, rx) = unbounded_channel();
+      {
+        let mut guard = lock(&shared);
+        if guard.state != State::Connecting {
+          // Closed while the handshake was in flight.
+          return Err(not_open());
+        }
+        guard.state = State::Open;
+        guard.outgoing = Some(tx);
+      }
+      tracing::debug!(url, "connected");
+      tokio::spawn(run(stream, rx, shared));
+      Ok(())
+    })
+  }
+
+  /// Queues a text (string) or binary (Buffer) message.
+  #[napi(catch_unwind)]
+  pub fn send(&self, data: Either<String, Buffer>) -> Result<()> {
+    let message = match data {
+      Either::A(text) => Message::Text(text),
+      Either::B(data) => Message::Binary(data.to_vec()),
+    };
+    let shared = lock(&self.shared);
+    let outgoing = shared.outgoing.as_ref().ok_or_else(not_open)?;
+    outgoing.send(message).map_err(|_| not_open())
+  }
+
+  /// Registers `callback(data)`; text frames arrive as strings and binary
+  /// frames as Buffers.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (data: string | Buffer) => void"
+  )]
+  pub fn on_message(&self, callback: JsFunction) -> Result<()> {
+    let listener: MessageListener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Payload>| {
+        Ok(vec![match ctx.value {
+          Payload::Text(text) => ctx.env.create_string_from_std(text)?.into_unknown(),
+          Payload::Binary(data) => ctx
+            .env
+            .create_buffer_with_data(data)?
+            .into_raw()
+            .into_unknown(),
+        }])
+      })?;
+    self.listeners()?.on_message.push(listener);
+    Ok(())
+  }
+
+  /// Registers `callback(code, reason)`, called once when the connection
+  /// ends. Abnormal terminations report code 1006.
+  #[napi(
+    catch_unwind,
+    ts_args_type = "callback: (code: number, reason: string) => void"
+  )]
+  pub fn on_close(&self, callback: JsFunction) -> Result<()> {
+    let listener: CloseListener =
+      callback.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(u16, String)>| {
+        let (code, reason) = ctx.value;
+        Ok(vec![
+          ctx.env.create_uint32(code as u32)?.into_unknown(),
+          ctx.env.create_string_from_std(reason)?.into_unknown(),
+        ])
+      })?;
+    self.listeners()?.on_close.push(listener);
+    Ok(())
+  }
+
+  /// Starts the closing handshake with `code` (default 1000). Close
+  /// listeners fire once the server answers. A no-op unless open.
+  #[napi(catch_unwind)]
+  pub fn close(&self, code: Option<u16>, reason: Option<String>) {
+    let mut shared = lock(&self.shared);
+    match shared.state {
+      State::Open => {
+        if let Some(outgoing) = shared.outgoing.take() {
+          let frame = CloseFrame {
+            code: code.unwrap_or(1000).into(),
+            reason: reason.unwrap_or_default().into(),
+          };
+          let _ = outgoing.send(Message::Close(Some(frame)));
+        }
+        shared.state = State::Closing;
+      }
+      // Resolving `connect` sees the state change and rejects.
+      State::Connecting | State::Idle => {
+        shared.state = State::Closed;
+        shared.on_message.clear();
+        shared.on_close.clear();
+      }
+      State::Closing | State::Closed => {}
+    }
+  }
+
+  /// `idle`, `connecting`, `open`, `closing` or `closed`.
+  #[napi(
+    catch_unwind,
+    getter,
+    ts_return_type = "'idle' | 'connecting' | 'open' | 'closing' | 'closed'"
+  )]
+  pub fn state(&self) -> &'static str {
+    match lock(&self.shared).state {
+      State::Idle => "idle",
+      State::Connecting => "connecting",
+      State::Open => "open",
+      State::Closing => "closing",
+      State::Closed => "closed",
+    }
+  }
+
+  fn listeners(&self) -> Result<MutexGuard<'_, Shared>> {
+    let shared = lock(&self.shared);
+    if shared.state == State::Closed {
+      return Err(AppError::Closed("WebSocket is closed".into()));
+    }
+    Ok(shared)
+  }
+}
+
+impl Default for WsClient {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ObjectFinalize for WsClient {
+  fn finalize(self, _: Env) -> napi::Result<()> {
+    // Dropping the sender makes the connection task send a close frame and
+    // wind down; the listeners go with the client.
+    let mut shared = lock(&self.shared);
+    shared.outgoing = None;
+    shared.on_message.clear();
+    shared.on_close.clear();
+    Ok(())
+  }
+}
@@ -0,0 +1,392 @@
+use std::net::IpAddr;
+
+use napi::bindgen_prelude::*;
+use napi::JsDate;
+use rcgen::{CertificateParams, DnType, IsCa, KeyPair};
+use rustls_pki_types::{CertificateDer, ServerName, TrustAnchor, UnixTime};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use webpki::{EndEntityCert, KeyUsage};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::oid_registry::{
+  OID_KEY_TYPE_EC_PUBLIC_KEY, OID_PKCS1_RSAENCRYPTION, OID_SIG_ED25519,
+};
+use x509_parser::pem::Pem;
+use x509_parser::prelude::{FromDer, X509Name};
+
+use crate::datetime::date_to_utc;
+use crate::error::{AppError, Result};
+
+#[napi(object)]
+pub struct CertInfo {
+  /// The subject's distinguished name, e.g. `CN=example.com, O=Example`.
+  pub subject: String,
+  pub issuer: String,
+  /// The subject's first common name.
+  pub common_name: Option<String>,
+  /// Alternative names prefixed with their type as Node formats them:
+  /// `DNS:`, `IP Address:`, `email:` or `URI:`.
+  pub subject_alt_names: Vec<String>,
+  /// Uppercase hex.
+  pub serial_number: String,
+  /// Start of the validity period, in milliseconds since the Unix epoch.
+  pub valid_from_ms: f64,
+  pub valid_to_ms: f64,
+  pub is_ca: bool,
+  /// Whether subject and issuer are the same name. The signature isn't
+  /// checked; `verifyChain` does that.
+  pub self_issued: bool,
+  /// `RSA`, `EC`, `Ed25519`, or the algorithm's OID.
+  pub public_key_algorithm: String,
+  /// E.g. `sha256WithRSAEncryption` or `ecdsa-with-SHA256`.
+  pub signature_algorithm: String,
+  /// SHA-1 of the DER encoding as colon-separated uppercase hex, like
+  /// `X509Certificate.fingerprint` in Node.
+  pub fingerprint: String,
+  pub fingerprint256: String,
+  pub version: u32,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct VerifyChainOptions {
+  /// Also require the leaf to be valid for this DNS name or IP address.
+  pub hostname: Option<String>,
+  /// The extended key usage to require. Defaults to `server`.
+  #[napi(ts_type = "'server' | 'client'")]
+  pub usage: Option<String>,
+  /// Check validity periods at this time instead of now.
+  pub at: Option<JsDate>,
+}
+
+#[napi(object)]
+pub struct ChainReport {
+  pub valid: bool,
+  /// Why verification failed, e.g. `UnknownIssuer`, `CertExpired` or
+  /// `CertNotValidForName`. Absent when `valid`.
+  pub error: Option<String>,
+  /// Subjects along the verified path, from the leaf to the trust anchor.
+  /// Empty when no path was found.
+  pub chain: Vec<String>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SelfSignedOptions {
+  /// Defaults to `localhost`.
+  pub common_name: Option<String>,
+  /// DNS names and IP addresses. Defaults to `localhost`, `127.0.0.1` and
+  /// `::1`.
+  pub subject_alt_names: Option<Vec<String>>,
+  /// Validity period starting now. Defaults to 365.
+  pub days: Option<u32>,
+  /// The key type. Defaults to `ecdsa-p256`.
+  #[napi(ts_type = "'ecdsa-p256' | 'ecdsa-p384' | 'ed25519'")]
+  pub algorithm: Option<String>,
+  /// Mark the certificate as a CA, so it can sign others. Defaults to
+  /// `false`.
+  pub is_ca: Option<bool>,
+}
+
+#[napi(object)]
+pub struct SelfSignedCertificate {
+  pub cert_pem: String,
+  /// PKCS#8.
+  pub key_pem: String,
+}
+
+/// The DER certificates in `input`: every `CERTIFICATE` block of PEM text,
+/// or `input` itself when it is DER.
+fn certificates(input: Either<String, Buffer>) -> Result<Vec<Vec<u8>>> {
+  let bytes: Vec<u8> = match input {
+    Either::A(text) => text.into_bytes(),
+    Either::B(buffer) => buffer.to_vec(),
+  };
+  if !bytes.trim_ascii_start().starts_with(b"-----BEGIN") {
+    return Ok(vec![bytes]);
+  }
+  let mut ders = Vec::new();
+  for pem in Pem::iter_from_buffer(&bytes) {
+    let pem = pem.map_err(|e| AppError::Parse(format!("invalid PEM: {}", e)))?;
+    if pem.label == "CERTIFICATE" {
+      ders.push(pem.contents);
+    }
+  }
+  if ders.is_empty() {
+    return Err(AppError::Parse("no CERTIFICATE block in PEM".into()));
+  }
+  Ok(ders)
+}
+
+fn parse(der: &[u8]) -> Result<X509Certificate<'_>> {
+  let (_, cert) = X509Certificate::from_der(der)
+    .map_err(|e| AppError::Parse(format!("invalid certificate: {}", e)))?;
+  Ok(cert)
+}
+
+fn fingerprint<D: Digest>(der: &[u8]) -> String {
+  D::digest(der)
+    .iter()
+    .map(|b| format!("{:02X}", b))
+    .collect::<Vec<_>>()
+    .join(":")
+}
+
+fn alt_names(cert: &X509Certificate) -> Result<Vec<String>> {
+  let Some(san) = cert
+    .subject_alternative_name()
+    .map_err(|e| AppError::Parse(format!("invalid subjectAltName: {}", e)))?
+  else {
+    return Ok(Vec::new());
+  };
+  Ok(
+    san
+      .value
+      .general_names
+      .iter()
+      .filter_map(|name| match name {
+        GeneralName::DNSName(dns) => Some(format!("DNS:{}", dns)),
+        GeneralName::RFC822Name(email) => Some(format!("email:{}", email)),
+        GeneralName::URI(uri) => Some(format!("URI:{}", uri)),
+        GeneralName::IPAddress(bytes) => {
+          let ip = match bytes.len() {
+            4 => IpAddr::from(<[u8; 4]>::try_from(*bytes).ok()?),
+            16 => IpAddr::from(<[u8; 16]>::try_from(*bytes).ok()?),
+            _ => return None,
+          };
+          Some(format!("IP Address:{}", ip))
+        }
+        _ => None,
+      })
+      .collect(),
+  )
+}
+
+fn info(der: &[u8]) -> Result<CertInfo> {
+  let cert = parse(der)?;
+  let key_oid = &cert.public_key().algorithm.algorithm;
+  let public_key_algorithm = if *key_oid == OID_PKCS1_RSAENCRYPTION {
+    "RSA".to_string()
+  } else if *key_oid == OID_KEY_TYPE_EC_PUBLIC_KEY {
+    "EC".to_string()
+  } else if *key_oid == OID_SIG_ED25519 {
+    "Ed25519".to_string()
+  } else {
+    key_oid.to_id_string()
+  };
+  let signature_oid = &cert.signature_algorithm.algorithm;
+  let is_ca = cert
+    .basic_constraints()
+    .map_err(|e| AppError::Parse(format!("invalid basicConstraints: {}", e)))?
+    .is_some_and(|constraints| constraints.value.ca);
+  let common_name = cert
+    .subject()
+    .iter_common_name()
+    .next()
+    .and_then(|cn| cn.as_str().ok())
+    .map(str::to_string);
+  Ok(CertInfo {
+    subject: cert.subject().to_string(),
+    issuer: cert.issuer().to_string(),
+    common_name,
+    subject_alt_names: alt_names(&cert)?,
+    serial_number: hex::encode_upper(cert.raw_serial()),
+    valid_from_ms: cert.validity().not_before.timestamp() as f64 * 1000.0,
+    valid_to_ms: cert.validity().not_after.timestamp() as f64 * 1000.0,
+    is_ca,
+    self_issued: cert.subject().as_raw() == cert.issuer().as_raw(),
+    public_key_algorithm,
+    signature_algorithm: oid2sn(signature_oid, oid_registry())
+      .map(str::to_string)
+      .unwrap_or_else(|_| signature_oid.to_id_string()),
+    fingerprint: fingerprint::<Sha1>(der),
+    fingerprint256: fingerprint::<Sha256>(der),
+    version: cert.version().0 + 1,
+  })
+}
+
+/// Reads a certificate from PEM text (the first `CERTIFICATE` block) or
+/// DER bytes.
+#[napi(catch_unwind, ts_args_type = "certificate: string | Buffer")]
+pub fn parse_certificate(certificate: Either<String, Buffer>) -> Result<CertInfo> {
+  info(&certificates(certificate)?[0])
+}
+
+fn subject(der: &[u8]) -> Result<String> {
+  Ok(parse(der)?.subject().to_string())
+}
+
+/// Formats a trust anchor's subject, which webpki keeps without the
+/// enclosing SEQUENCE header.
+fn anchor_subject(anchor: &TrustAnchor) -> String {
+  let body: &[u8] = &anchor.subject;
+  let mut der = vec![0x30];
+  match body.len() {
+    len @ 0..=0x7f => der.push(len as u8),
+    len @ 0x80..=0xff => der.extend([0x81, len as u8]),
+    len => der.extend([0x82, (len >> 8) as u8, len as u8]),
+  }
+  der.extend_from_slice(body);
+  X509Name::from_der(&der)
+    .map(|(_, name)| name.to_string())
+    .unwrap_or_default()
+}
+
+/// The name of a webpki error without its fields, e.g. `CertExpired`.
+fn error_name(e: &webpki::Error) -> String {
+  let debug = format!("{:?}", e);
+  debug
+    .split(|c: char| !c.is_alphanumeric())
+    .next()
+    .unwrap_or_default()
+    .to_string()
+}
+
+fn failed(error: &webpki::Error) -> ChainReport {
+  ChainReport {
+    valid: false,
+    error: Some(error_name(error)),
+    chain: Vec::new(),
+  }
+}
+
+/// Checks that `leaf` chains up to a trusted root through `intermediates`,
+/// with valid signatures, validity periods, CA constraints and key usage.
+/// `roots` defaults to the Mozilla root store. Each argument takes PEM
+/// (bundles too) or DER. Revocation is not checked.
+///
+/// Untrusted or expired chains don't throw; the report says why they
+/// failed. Only unreadable input throws `ERR_PARSE`.
+#[napi(
+  catch_unwind,
+  ts_args_type = "leaf: string | Buffer, intermediates?: Array<string | Buffer>, roots?: Array<string | Buffer>, options?: VerifyChainOptions"
+)]
+pub fn verify_chain(
+  leaf: Either<String, Buffer>,
+  intermediates: Option<Vec<Either<String, Buffer>>>,
+  roots: Option<Vec<Either<String, Buffer>>>,
+  options: Option<VerifyChainOptions>,
+) -> Result<ChainReport> {
+  let options = options.unwrap_or_default();
+  let usage = match options.usage.as_deref() {
+    None | Some("server") => KeyUsage::server_auth(),
+    Some("client") => KeyUsage::client_auth(),
+    Some(other) => return Err(AppError::InvalidInput(format!("unknown usage: {}", other))),
+  };
+  let time = match &options.at {
+    Some(at) => UnixTime::since_unix_epoch(std::time::Duration::from_secs(
+      date_to_utc(at)?.timestamp().max(0) as u64,
+    )),
+    None => UnixTime::now(),
+  };
+  let server_name = options
+    .hostname
+    .map(|hostname| {
+      ServerName::try_from(hostname.clone())
+        .map_err(|_| AppError::InvalidInput(format!("invalid hostname: {}", hostname)))
+    })
+    .transpose()?;
+
+  let leaf_der = CertificateDer::from(certificates(leaf)?.swap_remove(0));
+  let mut intermediate_ders = Vec::new();
+  for input in intermediates.unwrap_or_default() {
+    intermediate_ders.extend(certificates(input)?.into_iter().map(CertificateDer::from));
+  }
+  let root_ders = match roots {
+    Some(roots) => {
+      let mut ders = Vec::new();
+      for input in roots {
+        ders.extend(certificates(input)?.into_iter().map(CertificateDer::from));
+      }
+      Some(ders)
+    }
+    None => None,
+  };
+  let anchors: Vec<TrustAnchor> = match &root_ders {
+    Some(ders) => ders
+      .iter()
+      .map(|der| {
+        webpki::anchor_from_trusted_cert(der)
+          .map(|anchor| anchor.to_owned())
+          .map_err(|e| AppError::Parse(format!("invalid root certificate: {:?}", e)))
+      })
+      .collect::<Result<_>>()?,
+    None => webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+  };
+
+  let end_entity = EndEntityCert::try_from(&leaf_der)
+    .map_err(|e| AppError::Parse(format!("invalid leaf certificate: {:?}", e)))?;
+  let path = match end_entity.verify_for_usage(
+    webpki::ALL_VERIFICATION_ALGS,
+    &anchors,
+    &intermediate_ders,
+    time,
+    usage,
+    None,
+    None,
+  ) {
+    Ok(path) => path,
+    Err(e) => return Ok(failed(&e)),
+  };
+  if let Some(name) = &server_name {
+    if let Err(e) = end_entity.verify_is_valid_for_subject_name(name) {
+      return Ok(failed(&e));
+    }
+  }
+  let mut chain = vec![subject(&leaf_der)?];
+  for intermediate in path.intermediate_certificates() {
+    chain.push(subject(&intermediate.der())?);
+  }
+  chain.push(anchor_subject(path.anchor()));
+  Ok(ChainReport {
+    valid: true,
+    error: None,
+    chain,
+  })
+}
+
+/// Makes a self-signed certificate and its private key, for local HTTPS
+/// and tests. Not for production: nothing trusts it unless told to.
+#[napi(catch_unwind)]
+pub fn generate_self_signed(options: Option<SelfSignedOptions>) -> Result<SelfSignedCertificate> {
+  let options = options.unwrap_or_default();
+  let algorithm = match options.algorithm.as_deref() {
+    None | Some("ecdsa-p256") => &rcgen::PKCS_ECDSA_P256_SHA256,
+    Some("ecdsa-p384") => &rcgen::PKCS_ECDSA_P384_SHA384,
+    Some("ed25519") => &rcgen::PKCS_ED25519,
+    Some(other) => {
+      return Err(AppError::InvalidInput(format!(
+        "unknown algorithm: {}",
+        other
+      )))
+    }
+  };
+  let days = options.days.unwrap_or(365);
+  if days == 0 {
+    return Err(AppError::InvalidInput("days must be at least 1".into()));
+  }
+  let names = options
+    .subject_alt_names
+    .unwrap_or_else(|| vec!["localhost".into(), "127.0.0.1".into(), "::1".into()]);
+  let crypto = |e: rcgen::Error| AppError::Crypto(format!("certificate generation failed: {}", e));
+  let mut params = CertificateParams::new(names)
+    .map_err(|e| AppError::InvalidInput(format!("invalid subjectAltNames: {}", e)))?;
+  params.distinguished_name.push(
+    DnType::CommonName,
+    options.common_name.unwrap_or_else(|| "localhost".into()),
+  );
+  let now = time::OffsetDateTime::now_utc();
+  params.not_before = now;
+  params.not_after = now + time::Duration::days(days as i64);
+  if options.is_ca.unwrap_or(false) {
+    params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+  }
+  let key = KeyPair::generate_for(algorithm).map_err(crypto)?;
+  let cert = params.self_signed(&key).map_err(crypto)?;
+  Ok(SelfSignedCertificate {
+    cert_pem: cert.pem(),
+    key_pem: key.serialize_pem(),
+  })
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{
+  ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, JsUnknown, Task};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+/// Elements nested deeper than this are rejected: converting the tree to JS
+/// is recursive, so a hostile document could otherwise overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// An element of a parsed document.
+#[napi(object)]
+pub struct XmlNode {
+  /// Tag name, including any namespace prefix.
+  pub name: String,
+  pub attributes: HashMap<String, String>,
+  /// Child elements in document order.
+  pub children: Vec<XmlNode>,
+  /// The element's own text and CDATA, concatenated and trimmed. Text of
+  /// child elements is not included.
+  pub text: String,
+}
+
+/// An element reported by `parseXmlStream`, without its children.
+#[napi(object)]
+pub struct XmlElement {
+  pub name: String,
+  pub attributes: HashMap<String, String>,
+  pub text: String,
+  /// Nesting level; the root element has depth 0.
+  pub depth: u32,
+}
+
+fn xml_error<R>(reader: &Reader<R>, e: impl std::fmt::Display) -> AppError {
+  AppError::Parse(format!(
+    "invalid XML at byte {}: {}",
+    reader.error_position(),
+    e
+  ))
+}
+
+fn start_node<R>(reader: &Reader<R>, start: &BytesStart) -> Result<XmlNode> {
+  let mut attributes = HashMap::new();
+  for attr in start.attributes() {
+    let attr = attr.map_err(|e| xml_error(reader, e))?;
+    let value = attr.unescape_value().map_err(|e| xml_error(reader, e))?;
+    attributes.insert(
+      String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+      value.into_owned(),
+    );
+  }
+  Ok(XmlNode {
+    name: String::from_utf8_lossy(start.name().as_ref()).into_owned(),
+    attributes,
+    children: Vec::new(),
+    text: String::new(),
+  })
+}
+
+fn check_depth<R>(reader: &Reader<R>, stack: &[XmlNode]) -> Result<()> {
+  if stack.len() == MAX_DEPTH {
+    return Err(xml_error(
+      reader,
+      format!("elements nested more than {} levels deep", MAX_DEPTH),
+    ));
+  }
+  Ok(())
+}
+
+/// Walks the document, handing each element to `on_close` once its end tag
+/// has been read. Returning the node from `on_close` attaches it to its
+/// parent (building the full tree); returning `None` discards it so memory
+/// stays flat. Returns the root if it was kept.
+fn walk<R: BufRead>(
+  input: R,
+  mut on_close: impl FnMut(XmlNode, u32) -> Option<XmlNode>,
+) -> Result<Option<XmlNode>> {
+  let mut reader = Reader::from_reader(input);
+  reader.config_mut().trim_text(true);
+  let mut buf = Vec::new();
+  let mut stack: Vec<XmlNode> = Vec::new();
+  let mut root = None;
+  let mut seen_root = false;
+
+  loop {
+    let closed = match reader.read_event_into(&mut buf) {
+      Ok(Event::Start(start)) => {
+        if stack.is_empty() && seen_root {
+          return Err(xml_error(&reader, "more than one root element"));
+        }
+        check_depth(&reader, &stack)?;
+        seen_root = true;
+        stack.push(start_node(&reader, &start)?);
+        None
+      }
+      Ok(Event::Empty(start)) => {
+        if stack.is_empty() && seen_root {
+          return Err(xml_error(&reader, "more than one root element"));
+        }
+        check_depth(&reader, &stack)?;
+        seen_root = true;
+        Some(start_node(&reader, &start)?)
+      }
+      Ok(Event::End(_)) => stack.pop(),
+      Ok(Event::Text(text)) => {
+        let text = text.unescape().map_err(|e| xml_error(&reader, e))?;
+        if let Some(node) = stack.last_mut() {
+          node.text.push_str(&text);
+        }
+        None
+      }
+      Ok(Event::CData(data)) => {
+        if let Some(node) = stack.last_mut() {
+          node.text.push_str(&String::from_utf8_lossy(&data));
+        }
+        None
+      }
+      Ok(Event::Eof) => break,
+      Ok(_) => None,
+      Err(e) => return Err(xml_error(&reader, e)),
+    };
+    if let Some(node) = closed {
+      if let Some(node) = on_close(node, stack.len() as u32) {
+        match stack.last_mut() {
+          Some(parent) => parent.children.push(node),
+          None => root = Some(node),
+        }
+      }
+    }
+    buf.clear();
+  }
+
+  if !stack.is_empty() {
+    return Err(xml_error(&reader, "unexpected end of document"));
+  }
+  if !seen_root {
+    return Err(AppError::Parse("XML document has no root element".into()));
+  }
+  Ok(root)
+}
+
+/// Parses a whole document into a tree of `XmlNode`s. Elements nested more
+/// than 128 levels deep throw `ERR_PARSE`, here and in the other parsers.
+#[napi(catch_unwind)]
+pub fn parse_xml(input: String) -> Result<XmlNode> {
+  let root = walk(input.as_bytes(), |node, _| Some(node))?;
+  Ok(root.expect("walk keeps the root when every node is kept"))
+}
+
+/// Converts a node using the common "BadgerFish-lite" convention:
+/// attributes become `@name` keys, text becomes `#text`, repeated child tags
+/// become arrays, and an element with nothing but text becomes a string.
+fn node_to_json(node: XmlNode) -> Value {
+  if node.attributes.is_empty() && node.children.is_empty() {
+    return Value::String(node.text);
+  }
+  let mut object = Map::new();
+  for (name, value) in node.attributes {
+    object.insert(format!("@{}", name), Value::String(value));
+  }
+  if !node.text.is_empty() {
+    object.insert("#text".into(), Value::String(node.text));
+  }
+  for child in node.children {
+    let name = child.name.clone();
+    let value = node_to_json(child);
+    match object.get_mut(&name) {
+      Some(Value::Array(items)) => items.push(value),
+      Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+      None => {
+        object.insert(name, value);
+      }
+    }
+  }
+  Value::Object(object)
+}
+
+/// Parses a document straight into plain JS objects, keyed by the root tag.
+#[napi(catch_unwind, ts_return_type = "Record<string, unknown>")]
+pub fn xml_to_json(input: String) -> Result<Map<String, Value>> {
+  let root = parse_xml(input)?;
+  let mut out = Map::new();
+  out.insert(root.name.clone(), node_to_json(root));
+  Ok(out)
+}
+
+type ElementFn = ThreadsafeFunction<XmlElement, ErrorStrategy::Fatal>;
+
+/// Elements queued for JS before the parser blocks and waits for it.
+const STREAM_QUEUE: usize = 1024;
+
+pub struct XmlStreamTask {
+  path: String,
+  on_element: ElementFn,
+}
+
+impl XmlStreamTask {
+  fn run(&self) -> Result<u32> {
+    let file = File::open(&self.path).map_err(|e| AppError::io(&self.path, e))?;
+    let mut count = 0;
+    // One element is held back so the last call can wait for JS to run it;
+    // that way every callback fires before the Promise resolves.
+    let mut pending: Option<XmlElement> = None;
+    walk(BufReader::new(file), |node, depth| {
+      let element = XmlElement {
+        name: node.name,
+        attributes: node.attributes,
+        text: node.text,
+        depth,
+      };
+      if let Some(previous) = pending.replace(element) {
+        self
+          .on_element
+          .call(previous, ThreadsafeFunctionCallMode::Blocking);
+      }
+      count += 1;
+      None
+    })?;
+    if let Some(last) = pending {
+      let (done_tx, done_rx) = mpsc::channel();
+      self.on_element.call_with_return_value(
+        last,
+        ThreadsafeFunctionCallMode::Blocking,
+        move |_: JsUnknown| {
+          let _ = done_tx.send(());
+          Ok(())
+        },
+      );
+      let _ = done_rx.recv();
+    }
+    Ok(count)
+  }
+}
+
+impl Task for XmlStreamTask {
+  type Output = Result<u32>;
+  type JsValue = u32;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    output.map_err(|e| e.into_napi(env))
+  }
+}
+
+/// SAX-style parse of the XML file at `path` on the thread pool. Calls
+/// `onElement` as each element closes (children before parents) and
+/// resolves with the number of elements. Only one element is in memory at a
+/// time, so documents of any size can be processed.
+#[napi(
+  catch_unwind,
+  ts_args_type = "path: string, onElement: (element: XmlElement) => void",
+  ts_return_type = "Promise<number>"
+)]
+pub fn parse_xml_stream(
+  path: String,
+  on_element: JsFunction,
+) -> Result<AsyncTask<Guarded<XmlStreamTask>>> {
+  let on_element: ElementFn = on_element
+    .create_threadsafe_function(STREAM_QUEUE, |ctx: ThreadSafeCallContext<XmlElement>| {
+      Ok(vec![ctx.value])
+    })?;
+  Ok(panic_guard::task(XmlStreamTask { path, on_element }))
+}
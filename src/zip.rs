@@ -0,0 +1,215 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, Write};
+use std::sync::{Arc, Mutex};
+
+use ::zip::result::ZipError;
+use ::zip::write::SimpleFileOptions;
+use ::zip::CompressionMethod;
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+
+use crate::error::{AppError, Result};
+use crate::panic_guard::{self, Guarded};
+
+trait Source: Read + Seek + Send {}
+impl<T: Read + Seek + Send> Source for T {}
+
+type SharedArchive = Arc<Mutex<::zip::ZipArchive<Box<dyn Source>>>>;
+
+#[napi(object)]
+pub struct EntryInfo {
+  pub name: String,
+  /// Uncompressed size in bytes.
+  pub size: i64,
+  pub compressed_size: i64,
+  pub is_dir: bool,
+  pub crc32: u32,
+}
+
+fn zip_error(e: ZipError) -> AppError {
+  match e {
+    ZipError::Io(e) => AppError::from(e),
+    ZipError::FileNotFound => AppError::NotFound("no such entry in the archive".into()),
+    ZipError::InvalidArchive(msg) => AppError::Parse(format!("invalid zip archive: {}", msg)),
+    ZipError::UnsupportedArchive(msg) => AppError::Unsupported(msg.to_string()),
+    e => AppError::Parse(e.to_string()),
+  }
+}
+
+fn lock(archive: &SharedArchive) -> std::sync::MutexGuard<'_, ::zip::ZipArchive<Box<dyn Source>>> {
+  archive.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+enum ZipOp {
+  Read(String),
+  ExtractAll(String),
+}
+
+/// Decompresses on the libuv thread pool. Other calls on the same archive
+/// wait for it to finish.
+pub struct ZipTask {
+  archive: SharedArchive,
+  op: ZipOp,
+}
+
+impl ZipTask {
+  fn run(&self) -> Result<Either<Vec<u8>, u32>> {
+    let mut archive = lock(&self.archive);
+    match &self.op {
+      ZipOp::Read(name) => {
+        let mut entry = archive.by_name(name).map_err(|e| match e {
+          ZipError::FileNotFound => AppError::NotFound(format!("no entry named {:?}", name)),
+          e => zip_error(e),
+        })?;
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        Ok(Either::A(data))
+      }
+      ZipOp::ExtractAll(dest) => {
+        // zip rejects the whole extraction with "Invalid file path" on the
+        // first entry whose path would escape `dest`; entries before it are
+        // already written by then.
+        archive.extract(dest).map_err(zip_error)?;
+        Ok(Either::B(archive.len() as u32))
+      }
+    }
+  }
+}
+
+impl Task for ZipTask {
+  type Output = Result<Either<Vec<u8>, u32>>;
+  type JsValue = Either<Buffer, u32>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(self.run())
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(Either::A(data)) => Ok(Either::A(data.into())),
+      Ok(Either::B(count)) => Ok(Either::B(count)),
+      Err(e) => Err(e.into_napi(env)),
+    }
+  }
+}
+
+/// Read access to a zip archive on disk or in memory.
+#[napi]
+pub struct ZipArchive {
+  archive: SharedArchive,
+}
+
+#[napi]
+impl ZipArchive {
+  /// Opens the archive at `source` if it's a path, or reads it from memory if
+  /// it's a Buffer. Only the central directory is read up front.
+  #[napi(catch_unwind, factory)]
+  pub fn open(source: Either<String, Buffer>) -> Result<Self> {
+    let reader: Box<dyn Source> = match source {
+      Either::A(path) => Box::new(File::open(&path).map_err(|e| AppError::io(&path, e))?),
+      Either::B(buffer) => Box::new(Cursor::new(buffer.to_vec())),
+    };
+    let archive = ::zip::ZipArchive::new(reader).map_err(zip_error)?;
+    Ok(ZipArchive {
+      archive: Arc::new(Mutex::new(archive)),
+    })
+  }
+
+  #[napi(catch_unwind)]
+  pub fn entries(&self) -> Result<Vec<EntryInfo>> {
+    let mut archive = lock(&self.archive);
+    (0..archive.len())
+      .map(|i| {
+        let entry = archive.by_index_raw(i).map_err(zip_error)?;
+        Ok(EntryInfo {
+          name: entry.name().to_string(),
+          size: entry.size() as i64,
+          compressed_size: entry.compressed_size() as i64,
+          is_dir: entry.is_dir(),
+          crc32: entry.crc32(),
+        })
+      })
+      .collect()
+  }
+
+  #[napi(catch_unwind, ts_return_type = "Promise<Buffer>")]
+  pub fn read_entry(&self, name: String) -> AsyncTask<Guarded<ZipTask>> {
+    panic_guard::task(ZipTask {
+      archive: self.archive.clone(),
+      op: ZipOp::Read(name),
+    })
+  }
+
+  /// Extracts every entry under `dest`, creating directories as needed.
+  /// Resolves with the number of entries. Rejects with `ERR_PARSE` if an
+  /// entry's path would escape `dest`, such as `../x` or `/etc/x`.
+  #[napi(catch_unwind, ts_return_type = "Promise<number>")]
+  pub fn extract_all(&self, dest: String) -> AsyncTask<Guarded<ZipTask>> {
+    panic_guard::task(ZipTask {
+      archive: self.archive.clone(),
+      op: ZipOp::ExtractAll(dest),
+    })
+  }
+}
+
+/// Builds a zip archive in memory.
+#[napi]
+pub struct ZipWriter {
+  writer: Option<::zip::ZipWriter<Cursor<Vec<u8>>>>,
+}
+
+#[napi]
+impl ZipWriter {
+  #[napi(catch_unwind, constructor)]
+  pub fn new() -> Self {
+    ZipWriter {
+      writer: Some(::zip::ZipWriter::new(Cursor::new(Vec::new()))),
+    }
+  }
+
+  /// Adds a file. Entries are deflated unless `compress` is `false`.
+  #[napi(catch_unwind)]
+  pub fn add_file(
+    &mut self,
+    name: String,
+    data: Either<String, Buffer>,
+    compress: Option<bool>,
+  ) -> Result<()> {
+    let method = if compress.unwrap_or(true) {
+      CompressionMethod::Deflated
+    } else {
+      CompressionMethod::Stored
+    };
+    let writer = self.writer()?;
+    writer
+      .start_file(
+        name,
+        SimpleFileOptions::default().compression_method(method),
+      )
+      .map_err(zip_error)?;
+    writer.write_all(crate::hash::input_bytes(&data))?;
+    Ok(())
+  }
+
+  /// Writes the central directory and returns the archive. The writer can't
+  /// be used afterwards.
+  #[napi(catch_unwind)]
+  pub fn finish(&mut self) -> Result<Buffer> {
+    let writer = self.writer.take().ok_or_else(finished)?;
+    Ok(writer.finish().map_err(zip_error)?.into_inner().into())
+  }
+
+  fn writer(&mut self) -> Result<&mut ::zip::ZipWriter<Cursor<Vec<u8>>>> {
+    self.writer.as_mut().ok_or_else(finished)
+  }
+}
+
+impl Default for ZipWriter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn finished() -> AppError {
+  AppError::Closed("zip writer has already been finished".into())
+}